@@ -0,0 +1,86 @@
+mod common;
+
+use common::rng::{XorShift, SEED};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use shar_search::{auto::SearchTuning, SharBinarySearch};
+
+/// Returns `count` reproducible queries below `max`.
+fn queries(count: usize, max: u64) -> Vec<u64> {
+    let mut rng = XorShift::new(SEED);
+    (0..count).map(|_| rng.below(max)).collect()
+}
+
+/// Forces each strategy, to compare them at each size.
+const STRATEGIES: [(&str, SearchTuning); 3] = [
+    (
+        "linear",
+        SearchTuning {
+            linear_below: usize::MAX,
+            early_exit_above_bytes: usize::MAX,
+        },
+    ),
+    (
+        "branchless",
+        SearchTuning {
+            linear_below: 0,
+            early_exit_above_bytes: usize::MAX,
+        },
+    ),
+    (
+        "early_exit",
+        SearchTuning {
+            linear_below: 0,
+            early_exit_above_bytes: 0,
+        },
+    ),
+];
+
+fn bench_sizes<T>(c: &mut Criterion, name: &str, sizes: &[usize], to: impl Fn(u64) -> T)
+where
+    T: Ord,
+{
+    let mut group = c.benchmark_group(format!("auto_{name}"));
+    const QUERIES: usize = 1000;
+
+    for &len in sizes {
+        let slice: Vec<T> = (0..len as u64).map(|i| to(i * 2)).collect();
+        let queries: Vec<T> = queries(QUERIES, len as u64 * 2)
+            .into_iter()
+            .map(&to)
+            .collect();
+
+        for (strategy, tuning) in &STRATEGIES {
+            // Scanning millions of elements would take far too long, and obviously loses.
+            if *strategy == "linear" && len > 256 {
+                continue;
+            }
+            group.bench_function(format!("{strategy}_{len}"), |b| {
+                b.iter(|| {
+                    for q in black_box(&queries) {
+                        black_box(slice.bl_search_auto_with(q, tuning)).ok();
+                    }
+                })
+            });
+        }
+        group.bench_function(format!("auto_{len}"), |b| {
+            b.iter(|| {
+                for q in black_box(&queries) {
+                    black_box(slice.bl_search_auto(q)).ok();
+                }
+            })
+        });
+    }
+}
+
+pub fn auto(c: &mut Criterion) {
+    let small = [2, 4, 8, 12, 16, 24, 32, 64];
+    let large = [1 << 12, 1 << 16, 1 << 18, 1 << 20, 1 << 22, 1 << 24];
+
+    bench_sizes(c, "u32", &small, |x| x as u32);
+    bench_sizes(c, "u32", &large, |x| x as u32);
+    bench_sizes(c, "u64", &small, |x| x);
+    bench_sizes(c, "u64", &large, |x| x);
+}
+
+criterion_group!(benches, auto);
+criterion_main!(benches);
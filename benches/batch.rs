@@ -0,0 +1,102 @@
+mod common;
+
+use common::rng::{XorShift, SEED};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use shar_search::{multi::search_in_each, SharBatchSearch, SharBinarySearch};
+
+/// Returns `count` reproducible queries below `bound`.
+fn queries(count: usize, bound: u32) -> Vec<u32> {
+    let mut rng = XorShift::new(SEED);
+    (0..count).map(|_| rng.below(bound.into()) as u32).collect()
+}
+
+pub fn batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("batch_search");
+    group.sample_size(20);
+
+    // 2^26 `u32`s is 256 MiB, far larger than any cache.
+    const LEN: u32 = 1 << 26;
+    let slice: Vec<u32> = (0..LEN).map(|i| i * 2).collect();
+    let keys = queries(4096, LEN * 2);
+
+    group.throughput(Throughput::Elements(keys.len() as u64));
+
+    group.bench_function("scalar", |b| {
+        b.iter(|| {
+            black_box(&keys)
+                .iter()
+                .map(|k| slice.bl_binary_search(k))
+                .collect::<Vec<_>>()
+        })
+    });
+    group.bench_function("interleave_1", |b| {
+        b.iter(|| slice.bl_binary_search_batch_by::<1, _, _>(black_box(&keys), u32::cmp))
+    });
+    group.bench_function("interleave_4", |b| {
+        b.iter(|| slice.bl_binary_search_batch_by::<4, _, _>(black_box(&keys), u32::cmp))
+    });
+    group.bench_function("interleave_8", |b| {
+        b.iter(|| slice.bl_binary_search_batch_by::<8, _, _>(black_box(&keys), u32::cmp))
+    });
+    group.bench_function("interleave_16", |b| {
+        b.iter(|| slice.bl_binary_search_batch_by::<16, _, _>(black_box(&keys), u32::cmp))
+    });
+}
+
+pub fn sorted_keys(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sorted_keys");
+    group.sample_size(20);
+
+    const LEN: u32 = 1 << 26;
+    let slice: Vec<u32> = (0..LEN).map(|i| i * 2).collect();
+
+    for (name, count) in [("sparse", 4096), ("dense", 1 << 22)] {
+        let mut keys = queries(count, LEN * 2);
+        keys.sort_unstable();
+
+        group.throughput(Throughput::Elements(keys.len() as u64));
+        group.bench_function(format!("interleaved_{name}"), |b| {
+            b.iter(|| slice.bl_binary_search_batch(black_box(&keys)))
+        });
+        group.bench_function(format!("sorted_keys_{name}"), |b| {
+            b.iter(|| slice.bl_binary_search_sorted_keys(black_box(&keys)))
+        });
+    }
+}
+
+pub fn multi(c: &mut Criterion) {
+    let mut group = c.benchmark_group("search_in_each");
+    group.sample_size(20);
+
+    // 32 slices of 2^22 `u32`s, 512 MiB in total.
+    const LEN: u32 = 1 << 22;
+    let slices: Vec<Vec<u32>> = (0..32)
+        .map(|s| (0..LEN).map(|i| i * 2 + s % 2).collect())
+        .collect();
+    let refs: Vec<&[u32]> = slices.iter().map(Vec::as_slice).collect();
+    let keys = queries(256, LEN * 2);
+
+    group.throughput(Throughput::Elements((keys.len() * refs.len()) as u64));
+
+    group.bench_function("sequential", |b| {
+        b.iter(|| {
+            for key in black_box(&keys) {
+                black_box(
+                    refs.iter()
+                        .map(|s| s.bl_binary_search(key))
+                        .collect::<Vec<_>>(),
+                );
+            }
+        })
+    });
+    group.bench_function("interleaved", |b| {
+        b.iter(|| {
+            for key in black_box(&keys) {
+                black_box(search_in_each(&refs, key));
+            }
+        })
+    });
+}
+
+criterion_group!(benches, batch, sorted_keys, multi);
+criterion_main!(benches);
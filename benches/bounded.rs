@@ -0,0 +1,60 @@
+mod common;
+
+use common::rng::{XorShift, SEED};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use shar_search::SharBinarySearch;
+
+/// Returns `count` reproducible queries, of which `out_of_range` percent fall outside of
+/// `low..high`, split evenly below and above it.
+fn queries(count: usize, low: u32, high: u32, out_of_range: u64) -> Vec<u32> {
+    let mut rng = XorShift::new(SEED);
+
+    (0..count)
+        .map(|_| {
+            let r = rng.next_u64();
+            if r % 100 < out_of_range {
+                if r.is_multiple_of(2) {
+                    rng.below(low.into()) as u32
+                } else {
+                    high + rng.below(low.into()) as u32
+                }
+            } else {
+                low + rng.below((high - low).into()) as u32
+            }
+        })
+        .collect()
+}
+
+pub fn bounded(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bounded");
+    group.sample_size(20);
+
+    // The slice covers `LEN..3 * LEN`, one shard's share of the key space.
+    const LEN: u32 = 1 << 20;
+    let slice: Vec<u32> = (0..LEN).map(|i| LEN + i * 2).collect();
+
+    for out_of_range in [90, 50, 0] {
+        let keys = queries(4096, LEN, LEN * 3, out_of_range);
+        group.throughput(Throughput::Elements(keys.len() as u64));
+
+        group.bench_function(format!("unbounded_{out_of_range}%_out"), |b| {
+            b.iter(|| {
+                black_box(&keys)
+                    .iter()
+                    .map(|k| slice.bl_binary_search(k))
+                    .collect::<Vec<_>>()
+            })
+        });
+        group.bench_function(format!("bounded_{out_of_range}%_out"), |b| {
+            b.iter(|| {
+                black_box(&keys)
+                    .iter()
+                    .map(|k| slice.bl_binary_search_bounded(k))
+                    .collect::<Vec<_>>()
+            })
+        });
+    }
+}
+
+criterion_group!(benches, bounded);
+criterion_main!(benches);
@@ -0,0 +1,151 @@
+//! A matrix of searches over element types, slice sizes and hit rates.
+//!
+//! [`bench_matrix`] runs each search over every combination of sizes and hit rates for one
+//! element type, as one criterion group per type. Within a group, each benchmark is named
+//! `{search}/{hit rate}` and parameterized by the slice length, so the HTML report plots each
+//! search and hit rate as a line over the lengths. Other benchmarks can pass their own
+//! searches, such as variants of the auto-selection heuristic, to compare on the same inputs.
+
+use criterion::{black_box, BenchmarkId, Criterion, Throughput};
+
+use super::rng::XorShift;
+
+/// The number of queries searched in each iteration.
+pub const QUERIES: usize = 1024;
+
+/// A search to benchmark, returning the same as `bl_binary_search`.
+pub type Search<T> = fn(&[T], &T) -> Result<usize, usize>;
+
+/// An element type in the matrix.
+pub trait Element: Clone + Ord {
+    /// The name of the criterion group for this type.
+    const NAME: &'static str;
+
+    /// The number of distinct elements `from_key` can make.
+    const DISTINCT: u64 = u64::MAX;
+
+    /// Returns the element for `key`. Larger keys must make larger elements.
+    fn from_key(key: u64) -> Self;
+}
+
+macro_rules! impl_element {
+    ($($ty:ty),*) => {
+        $(
+            impl Element for $ty {
+                const NAME: &'static str = stringify!($ty);
+                const DISTINCT: u64 = <$ty>::MAX as u64 / 2 + 1;
+
+                fn from_key(key: u64) -> Self {
+                    key as $ty
+                }
+            }
+        )*
+    };
+}
+
+impl_element!(u8, u16, u32, u64);
+
+impl Element for u128 {
+    const NAME: &'static str = "u128";
+
+    fn from_key(key: u64) -> Self {
+        // Keep the high half busy, so comparisons can't stop at it.
+        (u128::from(key) << 64) | u128::from(key)
+    }
+}
+
+impl Element for (u64, u64) {
+    const NAME: &'static str = "(u64, u64)";
+
+    fn from_key(key: u64) -> Self {
+        // Equal first fields, so every comparison looks at both.
+        (0, key)
+    }
+}
+
+impl Element for String {
+    const NAME: &'static str = "String";
+
+    fn from_key(key: u64) -> Self {
+        // Zero-padded, so that the strings sort like the keys, after a common prefix.
+        format!("key-{key:020}")
+    }
+}
+
+/// A 64-byte record, searched by its key, so that each probe touches a whole cache line.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Record {
+    pub key: u64,
+    pub payload: [u64; 7],
+}
+
+impl Element for Record {
+    const NAME: &'static str = "Record (64 bytes, by key)";
+
+    fn from_key(key: u64) -> Self {
+        Record {
+            key,
+            payload: [key; 7],
+        }
+    }
+}
+
+/// The inputs of one benchmark: a sorted slice and the queries to search it for.
+pub struct Inputs<T> {
+    pub slice: Vec<T>,
+    pub queries: Vec<T>,
+}
+
+impl<T: Element> Inputs<T> {
+    /// Returns a slice of `len` distinct elements, and queries of which `hit_percent` percent
+    /// are in the slice. Elements are made from even keys, and misses from odd keys, so that
+    /// misses fall between elements rather than off the ends.
+    pub fn new(len: usize, hit_percent: u64, seed: u64) -> Self {
+        let mut rng = XorShift::new(seed);
+        let slice = (0..len as u64).map(|i| T::from_key(i * 2)).collect();
+        let queries = (0..QUERIES)
+            .map(|_| {
+                let i = rng.below(len as u64);
+                let hit = rng.below(100) < hit_percent;
+                T::from_key(i * 2 + u64::from(!hit))
+            })
+            .collect();
+        Self { slice, queries }
+    }
+}
+
+/// Benchmarks each of `searches` on slices of `T` of each of `lens`, with queries of each of
+/// `hit_percents`. Lengths that `T` has too few distinct elements for are skipped.
+///
+/// All inputs are generated before anything is timed.
+pub fn bench_matrix<T: Element>(
+    c: &mut Criterion,
+    searches: &[(&str, Search<T>)],
+    lens: &[usize],
+    hit_percents: &[u64],
+) {
+    let mut group = c.benchmark_group(format!("matrix/{}", T::NAME));
+    group.throughput(Throughput::Elements(QUERIES as u64));
+
+    let inputs: Vec<(usize, u64, Inputs<T>)> = lens
+        .iter()
+        .filter(|len| (**len as u64) < T::DISTINCT)
+        .flat_map(|len| hit_percents.iter().map(move |hits| (*len, *hits)))
+        .map(|(len, hits)| (len, hits, Inputs::new(len, hits, len as u64 ^ hits)))
+        .collect();
+
+    for (len, hits, inputs) in &inputs {
+        for (name, search) in searches {
+            let id = BenchmarkId::new(format!("{name}/{hits}% hits"), len);
+            group.bench_with_input(id, inputs, |b, inputs| {
+                b.iter(|| {
+                    for query in black_box(&inputs.queries) {
+                        black_box(search(&inputs.slice, query)).ok();
+                    }
+                })
+            });
+        }
+    }
+
+    group.finish();
+}
@@ -0,0 +1,7 @@
+//! Harnesses shared between benchmarks. Include them from a benchmark with `mod common;`.
+
+// Each benchmark uses only some of these.
+#![allow(dead_code)]
+
+pub mod matrix;
+pub mod rng;
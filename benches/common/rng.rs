@@ -0,0 +1,33 @@
+//! A small xorshift generator, so the inputs of the benchmarks are reproducible.
+
+/// The seed that benchmarks needing only one stream of numbers start from.
+pub const SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// A xorshift64 generator.
+pub struct XorShift(u64);
+
+impl XorShift {
+    /// Returns a generator starting from `seed`. A seed of zero is replaced with one, since
+    /// xorshift would only ever return zero from it.
+    pub fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    /// Returns the next number.
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Returns a number below `n`.
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+
+    /// Returns a number in `[0, 1)`, from the top 53 bits of the next number.
+    pub fn unit_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::rng::{XorShift, SEED};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use shar_search::{compacting::CompactingSharMap, SharMap};
+
+/// Returns `count` reproducible operations on keys below `len`. Each operation is a key to
+/// remove and a key to look up.
+fn operations(count: usize, len: u64) -> Vec<(u64, u64)> {
+    let mut rng = XorShift::new(SEED);
+
+    (0..count)
+        .map(|_| (rng.below(len), rng.below(len)))
+        .collect()
+}
+
+pub fn compacting(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compacting");
+    group.sample_size(10);
+
+    const LEN: u64 = 1 << 17;
+    let pairs: Vec<(u64, u64)> = (0..LEN).map(|k| (k, k)).collect();
+
+    // Removing about a fifth, then most, of the keys.
+    for count in [LEN as usize / 4, LEN as usize * 2] {
+        let operations = operations(count, LEN);
+
+        group.bench_function(format!("plain_{count}"), |b| {
+            b.iter_batched_ref(
+                || {
+                    let mut map = SharMap::new();
+                    map.extend(pairs.iter().copied());
+                    map
+                },
+                |map| {
+                    for (remove, get) in black_box(&operations) {
+                        map.remove(remove);
+                        black_box(map.get(get));
+                    }
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(format!("compacting_{count}"), |b| {
+            b.iter_batched_ref(
+                || {
+                    let mut map = SharMap::new();
+                    map.extend(pairs.iter().copied());
+                    CompactingSharMap::from(map)
+                },
+                |map| {
+                    for (remove, get) in black_box(&operations) {
+                        map.remove(remove);
+                        black_box(map.get(get));
+                    }
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+}
+
+criterion_group!(benches, compacting);
+criterion_main!(benches);
@@ -0,0 +1,41 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use shar_search::{SearchCursor, SharBinarySearch};
+
+pub fn sequential_scan(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sequential_scan");
+    group.sample_size(20);
+
+    const LEN: u32 = 1 << 24;
+    let slice: Vec<u32> = (0..LEN).map(|i| i * 2).collect();
+
+    // A scan that moves forward a few elements per key, as when replaying a log against a
+    // time series.
+    for step in [1, 16, 1024] {
+        let keys: Vec<u32> = (0..LEN / step)
+            .take(1 << 16)
+            .map(|i| i * step * 2 + 1)
+            .collect();
+        group.throughput(Throughput::Elements(keys.len() as u64));
+
+        group.bench_function(format!("stateless_step_{step}"), |b| {
+            b.iter(|| {
+                black_box(&keys)
+                    .iter()
+                    .map(|k| slice.bl_binary_search(k))
+                    .collect::<Vec<_>>()
+            })
+        });
+        group.bench_function(format!("cursor_step_{step}"), |b| {
+            b.iter(|| {
+                let mut cursor = SearchCursor::new(&slice);
+                black_box(&keys)
+                    .iter()
+                    .map(|k| cursor.seek(k))
+                    .collect::<Vec<_>>()
+            })
+        });
+    }
+}
+
+criterion_group!(benches, sequential_scan);
+criterion_main!(benches);
@@ -0,0 +1,77 @@
+mod common;
+
+use common::rng::{XorShift, SEED};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use shar_search::diff::diff_sorted;
+
+/// A plain two-pointer diff, comparing every element, as a baseline. This counts both sides in
+/// one pass, so it is compared with a single iterator of the diff, which also takes one pass.
+fn linear_diff(old: &[u64], new: &[u64]) -> (usize, usize) {
+    let (mut i, mut j) = (0, 0);
+    let (mut removed, mut added) = (0, 0);
+    while i < old.len() && j < new.len() {
+        match old[i].cmp(&new[j]) {
+            std::cmp::Ordering::Less => {
+                removed += 1;
+                i += 1;
+            }
+            std::cmp::Ordering::Greater => {
+                added += 1;
+                j += 1;
+            }
+            std::cmp::Ordering::Equal => {
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    (removed + old.len() - i, added + new.len() - j)
+}
+
+pub fn diff(c: &mut Criterion) {
+    let mut group = c.benchmark_group("diff");
+    group.sample_size(20);
+
+    // Even numbers, so odd numbers can be added.
+    const LEN: u64 = 10_000_000;
+    let old: Vec<u64> = (0..LEN).map(|i| i * 2).collect();
+
+    // 50 removals and 50 additions, scattered across the base.
+    let mut rng = XorShift::new(SEED);
+    let mut new = old.clone();
+    for _ in 0..50 {
+        new.remove(rng.below(new.len() as u64) as usize);
+    }
+    new.extend((0..50).map(|_| rng.below(LEN) * 2 + 1));
+    new.sort_unstable();
+
+    group.bench_function("linear", |b| {
+        b.iter(|| linear_diff(black_box(&old), black_box(&new)))
+    });
+    group.bench_function("diff_sorted", |b| {
+        b.iter(|| {
+            diff_sorted(black_box(&old), black_box(&new))
+                .added()
+                .count()
+        })
+    });
+
+    // The same size of delta, but as one block of insertions.
+    let mut clustered = old.clone();
+    clustered.extend((0..100).map(|i| LEN + i * 2 + 1));
+    clustered.sort_unstable();
+
+    group.bench_function("linear_clustered", |b| {
+        b.iter(|| linear_diff(black_box(&old), black_box(&clustered)))
+    });
+    group.bench_function("diff_sorted_clustered", |b| {
+        b.iter(|| {
+            diff_sorted(black_box(&old), black_box(&clustered))
+                .added()
+                .count()
+        })
+    });
+}
+
+criterion_group!(benches, diff);
+criterion_main!(benches);
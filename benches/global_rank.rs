@@ -0,0 +1,59 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use shar_search::{
+    multi::{global_rank, global_select},
+    SharBinarySearch,
+};
+
+const SHARDS: u32 = 64;
+/// 64 shards of 10^7 `u32`s take 2.5 GB; `u64`s would take twice that.
+const SHARD_LEN: u32 = 10_000_000;
+
+/// Returns shards that interleave: shard `i` holds every value congruent to `i` modulo
+/// [`SHARDS`], so every key lands in the middle of every shard.
+fn shards() -> Vec<Vec<u32>> {
+    (0..SHARDS)
+        .map(|i| (0..SHARD_LEN).map(|j| j * SHARDS + i).collect())
+        .collect()
+}
+
+/// Sums the ranks of `key` in the shards one shard at a time.
+fn sequential_rank(shards: &[&[u32]], key: u32) -> u64 {
+    shards
+        .iter()
+        .map(|shard| shard.bl_partition_point(|&x| x < key) as u64)
+        .sum()
+}
+
+pub fn rank(c: &mut Criterion) {
+    let mut group = c.benchmark_group("global_rank");
+    group.sample_size(20);
+
+    let shards = shards();
+    let refs: Vec<&[u32]> = shards.iter().map(Vec::as_slice).collect();
+    let total = SHARDS * SHARD_LEN;
+
+    let mut key = 0_u32;
+    group.bench_function("sequential", |b| {
+        b.iter(|| {
+            key = key.wrapping_add(0x9E37_79B9) % total;
+            sequential_rank(black_box(&refs), key)
+        })
+    });
+    group.bench_function("interleaved", |b| {
+        b.iter(|| {
+            key = key.wrapping_add(0x9E37_79B9) % total;
+            global_rank(black_box(&refs), &key)
+        })
+    });
+
+    let mut k = 0_u64;
+    group.bench_function("select", |b| {
+        b.iter(|| {
+            k = (k + 0x9E37_79B9) % u64::from(total);
+            global_select(black_box(&refs), k)
+        })
+    });
+}
+
+criterion_group!(benches, rank);
+criterion_main!(benches);
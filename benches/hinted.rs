@@ -0,0 +1,78 @@
+mod common;
+
+use std::thread;
+
+use common::rng::{XorShift, SEED};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use shar_search::{hinted::HintedSlice, SharBinarySearch};
+
+const THREADS: u64 = 8;
+const QUERIES: usize = 4096;
+
+/// Returns reproducible queries, a different stream for each `seed`. With `spread` of zero the
+/// queries are uniform over `0..len`; otherwise each is within `spread` of a center drifting
+/// through the slice, as when threads poll the latest window of a time series.
+fn queries(seed: u64, len: u64, spread: u64) -> Vec<u64> {
+    let mut rng = XorShift::new(SEED ^ seed);
+
+    let mut center = len / 2;
+    (0..QUERIES)
+        .map(|_| {
+            if spread == 0 {
+                rng.below(len)
+            } else {
+                center = (center + rng.below(8)).min(len - spread);
+                center + rng.below(spread)
+            }
+        })
+        .collect()
+}
+
+pub fn hinted(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hinted");
+    group.sample_size(20);
+
+    const LEN: u64 = 1 << 22;
+    let slice: Vec<u64> = (0..LEN).map(|i| i * 3).collect();
+
+    for (name, spread) in [("clustered", 64), ("uniform", 0)] {
+        // Each thread queries its own stream, all sharing the one hint.
+        let keys: Vec<Vec<u64>> = (0..THREADS)
+            .map(|t| queries(t, LEN, spread).into_iter().map(|k| k * 3).collect())
+            .collect();
+        group.throughput(Throughput::Elements(THREADS * QUERIES as u64));
+
+        group.bench_function(format!("plain_{name}"), |b| {
+            b.iter(|| {
+                thread::scope(|s| {
+                    for keys in &keys {
+                        let slice = &slice;
+                        s.spawn(move || {
+                            for k in black_box(keys) {
+                                black_box(slice.bl_binary_search(k)).ok();
+                            }
+                        });
+                    }
+                })
+            })
+        });
+        group.bench_function(format!("hinted_{name}"), |b| {
+            let hinted = HintedSlice::new(&slice);
+            b.iter(|| {
+                thread::scope(|s| {
+                    for keys in &keys {
+                        let hinted = &hinted;
+                        s.spawn(move || {
+                            for k in black_box(keys) {
+                                black_box(hinted.search(k)).ok();
+                            }
+                        });
+                    }
+                })
+            })
+        });
+    }
+}
+
+criterion_group!(benches, hinted);
+criterion_main!(benches);
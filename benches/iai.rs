@@ -0,0 +1,98 @@
+//! Counts instructions, L1 and last-level cache accesses, and estimated cycles with Callgrind,
+//! for `bl_binary_search` and `slice::binary_search` as a baseline. Unlike wall-clock times,
+//! these counts are the same from run to run, so a small regression shows up even on a noisy
+//! machine.
+//!
+//! Each search is of a slice of `u64`s of length 1, 7, 64, 1000 or 2^20, for its first element,
+//! its last element, or a key missing from the middle. Building the slice is not counted.
+//!
+//! This needs Linux, valgrind, and `iai-callgrind-runner` at the same version as the
+//! `iai-callgrind` dev-dependency:
+//!
+//! ```text
+//! cargo install iai-callgrind-runner --version 0.16.1
+//! cargo bench --features iai --bench iai
+//! ```
+//!
+//! Redirect the output of a run to `benches/iai_baselines.txt` and commit it with any change to
+//! the search, so that a change to the counts shows up in the diff.
+
+// Everything but `main` is only used on Linux.
+#![cfg_attr(not(target_os = "linux"), allow(dead_code, unused_imports))]
+
+use std::hint::black_box;
+
+#[cfg(target_os = "linux")]
+use iai_callgrind::{
+    library_benchmark, library_benchmark_group, main, Callgrind, CallgrindMetrics,
+    LibraryBenchmarkConfig,
+};
+use shar_search::SharBinarySearch;
+
+/// Which key to search for.
+#[derive(Clone, Copy)]
+enum Query {
+    HitFirst,
+    HitLast,
+    MissMiddle,
+}
+
+/// Returns a slice of `len` even keys, and the key to search it for.
+fn setup(len: usize, query: Query) -> (Vec<u64>, u64) {
+    let slice: Vec<u64> = (0..len as u64).map(|i| i * 2).collect();
+    let key = match query {
+        Query::HitFirst => 0,
+        Query::HitLast => (len as u64 - 1) * 2,
+        Query::MissMiddle => len as u64 / 2 * 2 + 1,
+    };
+    (slice, key)
+}
+
+#[cfg(target_os = "linux")]
+#[library_benchmark]
+#[benches::cases(
+    args = [
+        (1, Query::HitFirst), (1, Query::HitLast), (1, Query::MissMiddle),
+        (7, Query::HitFirst), (7, Query::HitLast), (7, Query::MissMiddle),
+        (64, Query::HitFirst), (64, Query::HitLast), (64, Query::MissMiddle),
+        (1000, Query::HitFirst), (1000, Query::HitLast), (1000, Query::MissMiddle),
+        (1 << 20, Query::HitFirst), (1 << 20, Query::HitLast), (1 << 20, Query::MissMiddle),
+    ],
+    setup = setup
+)]
+fn bl_binary_search((slice, key): (Vec<u64>, u64)) -> Result<usize, usize> {
+    black_box(slice.bl_binary_search(&key))
+}
+
+#[cfg(target_os = "linux")]
+#[library_benchmark]
+#[benches::cases(
+    args = [
+        (1, Query::HitFirst), (1, Query::HitLast), (1, Query::MissMiddle),
+        (7, Query::HitFirst), (7, Query::HitLast), (7, Query::MissMiddle),
+        (64, Query::HitFirst), (64, Query::HitLast), (64, Query::MissMiddle),
+        (1000, Query::HitFirst), (1000, Query::HitLast), (1000, Query::MissMiddle),
+        (1 << 20, Query::HitFirst), (1 << 20, Query::HitLast), (1 << 20, Query::MissMiddle),
+    ],
+    setup = setup
+)]
+fn binary_search((slice, key): (Vec<u64>, u64)) -> Result<usize, usize> {
+    black_box(slice.binary_search(&key))
+}
+
+#[cfg(target_os = "linux")]
+library_benchmark_group!(name = search; benchmarks = bl_binary_search, binary_search);
+
+#[cfg(target_os = "linux")]
+main!(
+    // Simulating the caches is what gives the L1, last-level and estimated cycle counts.
+    config = LibraryBenchmarkConfig::default().tool(
+        Callgrind::with_args(["--cache-sim=yes"]).format([CallgrindMetrics::All])
+    );
+    library_benchmark_groups = search
+);
+
+#[cfg(not(target_os = "linux"))]
+fn main() {
+    println!("skipping: Callgrind is only run on Linux");
+}
@@ -0,0 +1,55 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use shar_search::SortedVec;
+
+/// Generates `count` pseudo-random values with a simple LCG so runs are reproducible.
+fn values(count: usize, seed: u64) -> Vec<u64> {
+    let mut state = seed;
+    (0..count)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            state >> 16
+        })
+        .collect()
+}
+
+fn base(count: usize) -> SortedVec<u64> {
+    let mut vec = SortedVec::new();
+    vec.insert_many(values(count, 1));
+    vec
+}
+
+pub fn insert_many(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert_many");
+
+    for (n, k) in [(10_000, 100), (10_000, 10_000), (100_000, 1_000)] {
+        let batch = values(k, 2);
+
+        group.bench_function(format!("single_{n}_{k}"), |b| {
+            b.iter_batched(
+                || base(n),
+                |mut vec| {
+                    for &value in &batch {
+                        vec.insert(value);
+                    }
+                    black_box(vec)
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(format!("many_{n}_{k}"), |b| {
+            b.iter_batched(
+                || base(n),
+                |mut vec| {
+                    vec.insert_many(batch.iter().copied());
+                    black_box(vec)
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+}
+
+criterion_group!(benches, insert_many);
+criterion_main!(benches);
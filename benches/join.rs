@@ -0,0 +1,122 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use shar_search::{
+    join::{anti_join, join_indices, semi_join},
+    multi::intersect_k,
+};
+
+fn linear_join(a: &[u64], b: &[u64], mut emit: impl FnMut((usize, usize))) {
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] < b[j] {
+            i += 1;
+        } else if b[j] < a[i] {
+            j += 1;
+        } else {
+            let a_end = i + a[i..].iter().take_while(|x| **x == a[i]).count();
+            let b_end = j + b[j..].iter().take_while(|y| **y == b[j]).count();
+            for x in i..a_end {
+                for y in j..b_end {
+                    emit((x, y));
+                }
+            }
+            i = a_end;
+            j = b_end;
+        }
+    }
+}
+
+/// Returns `count` sorted values spread evenly over `0..range`, starting at `offset`.
+fn spread(count: u64, range: u64, offset: u64) -> Vec<u64> {
+    (0..count).map(|i| i * (range / count) + offset).collect()
+}
+
+pub fn join(c: &mut Criterion) {
+    let mut group = c.benchmark_group("join");
+
+    const BIG: u64 = 1_000_000;
+    let big = spread(BIG, BIG * 2, 0);
+
+    // The big side holds the even numbers. At 1:1, the small side holds multiples of 3, so the
+    // sides interleave and every third element matches.
+    for (name, small) in [
+        ("1:1", spread(BIG, BIG * 3, 0)),
+        ("1:10000", spread(BIG / 10_000, BIG * 2, 0)),
+    ] {
+        group.bench_function(format!("linear_{name}"), |b| {
+            b.iter(|| {
+                linear_join(black_box(&small), black_box(&big), |pair| {
+                    black_box(pair);
+                })
+            })
+        });
+        group.bench_function(format!("gallop_{name}"), |b| {
+            b.iter(|| {
+                join_indices(black_box(&small), black_box(&big)).for_each(|pair| {
+                    black_box(pair);
+                })
+            })
+        });
+    }
+}
+
+pub fn filter(c: &mut Criterion) {
+    let mut group = c.benchmark_group("semi_anti_join");
+
+    const BIG: u64 = 1_000_000;
+    let big = spread(BIG, BIG * 2, 0);
+    let small = spread(BIG / 10_000, BIG * 2, 1);
+
+    for (name, a, b) in [("small_a", &small, &big), ("small_b", &big, &small)] {
+        group.bench_function(format!("semi_{name}"), |bench| {
+            bench.iter(|| semi_join(black_box(a), black_box(b)).count())
+        });
+        group.bench_function(format!("anti_{name}"), |bench| {
+            bench.iter(|| anti_join(black_box(a), black_box(b)).count())
+        });
+    }
+}
+
+fn pairwise_intersection(lists: &[&[u64]]) -> Vec<u64> {
+    let mut result = lists[0].to_vec();
+    for list in &lists[1..] {
+        let mut next = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < result.len() && j < list.len() {
+            if result[i] < list[j] {
+                i += 1;
+            } else if list[j] < result[i] {
+                j += 1;
+            } else {
+                next.push(result[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+        result = next;
+    }
+    result
+}
+
+pub fn intersect(c: &mut Criterion) {
+    let mut group = c.benchmark_group("intersect_k");
+
+    // Posting lists of multiples of 2, 3, 5, and 7 up to 10^6, plus a rare term.
+    let mut lists: Vec<Vec<u64>> = [2, 3, 5, 7]
+        .into_iter()
+        .map(|step| (0..1_000_000 / step).map(|i| i * step).collect())
+        .collect();
+    lists.push(spread(100, 1_000_000, 0));
+    let refs: Vec<&[u64]> = lists.iter().map(Vec::as_slice).collect();
+
+    for (name, lists) in [("common_terms", &refs[..4]), ("rare_term", &refs[..])] {
+        group.bench_function(format!("pairwise_{name}"), |b| {
+            b.iter(|| pairwise_intersection(black_box(lists)))
+        });
+        group.bench_function(format!("gallop_{name}"), |b| {
+            b.iter(|| intersect_k(black_box(lists)).copied().collect::<Vec<_>>())
+        });
+    }
+}
+
+criterion_group!(benches, join, filter, intersect);
+criterion_main!(benches);
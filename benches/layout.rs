@@ -0,0 +1,36 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rayon::ThreadPoolBuilder;
+use shar_search::layout::{Eytzinger, STree};
+
+/// 10^8 `u32`s take 400 MB, and the layout as much again.
+const LEN: u32 = 100_000_000;
+
+pub fn build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("layout_build");
+    group.sample_size(10);
+
+    let sorted: Vec<u32> = (0..LEN).collect();
+
+    group.bench_function("eytzinger", |b| {
+        b.iter(|| Eytzinger::from_sorted(black_box(&sorted)))
+    });
+    group.bench_function("stree", |b| {
+        b.iter(|| STree::<_, 16>::from_sorted(black_box(&sorted)))
+    });
+
+    for threads in [1, 4, 16] {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        group.bench_function(format!("eytzinger_par_{threads}"), |b| {
+            b.iter(|| pool.install(|| Eytzinger::par_from_sorted(black_box(&sorted))))
+        });
+        group.bench_function(format!("stree_par_{threads}"), |b| {
+            b.iter(|| pool.install(|| STree::<_, 16>::par_from_sorted(black_box(&sorted))))
+        });
+    }
+}
+
+criterion_group!(benches, build);
+criterion_main!(benches);
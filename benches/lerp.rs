@@ -0,0 +1,42 @@
+mod common;
+
+use common::rng::{XorShift, SEED};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use shar_search::lerp::{LerpTable, OutOfRange};
+
+/// Returns `count` reproducible queries in `[0, max)`.
+fn queries(seed: u64, count: usize, max: f64) -> Vec<f64> {
+    let mut rng = XorShift::new(SEED ^ seed);
+    (0..count).map(|_| rng.unit_f64() * max).collect()
+}
+
+pub fn lerp(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lerp");
+
+    const BREAKPOINTS: usize = 1 << 16;
+    let x: Vec<f64> = (0..BREAKPOINTS).map(|i| (i as f64).powf(1.5)).collect();
+    let y: Vec<f64> = (0..BREAKPOINTS).map(|i| (i as f64).sin()).collect();
+    let table = LerpTable::new(&x, &y, OutOfRange::Clamp).unwrap();
+    let max = x[BREAKPOINTS - 1];
+
+    for count in [64, 4096, 1 << 18] {
+        let mut sorted = queries(count as u64, count, max);
+        sorted.sort_by(f64::total_cmp);
+        group.throughput(Throughput::Elements(count as u64));
+
+        group.bench_with_input(BenchmarkId::new("eval", count), &sorted, |b, xs| {
+            b.iter(|| {
+                black_box(xs)
+                    .iter()
+                    .map(|&x| table.eval(x))
+                    .collect::<Vec<_>>()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("eval_sorted", count), &sorted, |b, xs| {
+            b.iter(|| table.eval_sorted(black_box(xs)))
+        });
+    }
+}
+
+criterion_group!(benches, lerp);
+criterion_main!(benches);
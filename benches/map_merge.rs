@@ -0,0 +1,55 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use shar_search::SharMap;
+
+/// Returns a map of `count` keys spread evenly over `0..range`, offset so that the two maps
+/// merged below share about a tenth of their keys.
+fn spread(count: u64, range: u64, offset: u64) -> SharMap<u64, u64> {
+    let mut map = SharMap::new();
+    map.extend((0..count).map(|i| (i * (range / count) + offset * (i % 10 != 0) as u64, i)));
+    map
+}
+
+pub fn map_merge(c: &mut Criterion) {
+    let mut group = c.benchmark_group("map_merge");
+    group.sample_size(10);
+
+    const BIG: u64 = 1_000_000;
+    let big = spread(BIG, BIG * 4, 0);
+
+    // Inserting one at a time moves on average half the map per insert, so the insert loop is
+    // only measured with a thousandth of the pairs; at 1M + 1M it would take hours.
+    for (name, small_len, with_insert) in [("1M+1K", BIG / 1000, true), ("1M+1M", BIG, false)] {
+        let small = spread(small_len, BIG * 4, 1);
+
+        if with_insert {
+            group.bench_function(format!("insert_loop_{name}"), |b| {
+                b.iter_batched(
+                    || (big.clone(), small.clone()),
+                    |(mut big, small)| {
+                        for (k, v) in small.iter() {
+                            big.insert(*k, *v);
+                        }
+                        big
+                    },
+                    BatchSize::LargeInput,
+                )
+            });
+        }
+        group.bench_function(format!("merge_with_{name}"), |b| {
+            b.iter_batched(
+                || (big.clone(), small.clone()),
+                |(mut big, small)| {
+                    big.merge_with(small, |_, ours, theirs| ours + theirs);
+                    big
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(format!("merged_{name}"), |b| {
+            b.iter(|| SharMap::merged(black_box(&big), black_box(&small), |_, a, b| a + b))
+        });
+    }
+}
+
+criterion_group!(benches, map_merge);
+criterion_main!(benches);
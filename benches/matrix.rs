@@ -0,0 +1,39 @@
+mod common;
+
+use common::matrix::{bench_matrix, Element, Record, Search};
+use criterion::{criterion_group, criterion_main, Criterion};
+use shar_search::SharBinarySearch;
+
+/// From a few cache lines to well past L2.
+const LENS: [usize; 5] = [16, 256, 4096, 65_536, 1 << 20];
+const HIT_PERCENTS: [u64; 3] = [0, 50, 100];
+
+fn searches<T: Element>() -> [(&'static str, Search<T>); 2] {
+    [
+        ("bl", |slice, x| slice.bl_binary_search(x)),
+        ("std", |slice, x| slice.binary_search(x)),
+    ]
+}
+
+pub fn matrix(c: &mut Criterion) {
+    bench_matrix::<u8>(c, &searches(), &LENS, &HIT_PERCENTS);
+    bench_matrix::<u16>(c, &searches(), &LENS, &HIT_PERCENTS);
+    bench_matrix::<u32>(c, &searches(), &LENS, &HIT_PERCENTS);
+    bench_matrix::<u64>(c, &searches(), &LENS, &HIT_PERCENTS);
+    bench_matrix::<u128>(c, &searches(), &LENS, &HIT_PERCENTS);
+    bench_matrix::<(u64, u64)>(c, &searches(), &LENS, &HIT_PERCENTS);
+    bench_matrix::<String>(c, &searches(), &LENS, &HIT_PERCENTS);
+
+    let by_key: [(&str, Search<Record>); 2] = [
+        ("bl", |slice, x| {
+            slice.bl_binary_search_by_key(&x.key, |r| r.key)
+        }),
+        ("std", |slice, x| {
+            slice.binary_search_by_key(&x.key, |r| r.key)
+        }),
+    ];
+    bench_matrix(c, &by_key, &LENS, &HIT_PERCENTS);
+}
+
+criterion_group!(benches, matrix);
+criterion_main!(benches);
@@ -0,0 +1,52 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use shar_search::merge::merge_sorted;
+
+fn naive_merge(a: &[u64], b: &[u64], out: &mut Vec<u64>) {
+    out.reserve(a.len() + b.len());
+
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if b[j] < a[i] {
+            out.push(b[j]);
+            j += 1;
+        } else {
+            out.push(a[i]);
+            i += 1;
+        }
+    }
+    out.extend_from_slice(&a[i..]);
+    out.extend_from_slice(&b[j..]);
+}
+
+/// Returns `count` sorted values spread evenly over `0..range`, offset to avoid exact ties.
+fn spread(count: u64, range: u64, offset: u64) -> Vec<u64> {
+    (0..count).map(|i| i * (range / count) + offset).collect()
+}
+
+pub fn merge(c: &mut Criterion) {
+    let mut group = c.benchmark_group("merge");
+
+    const BIG: u64 = 1_000_000;
+    let big = spread(BIG, BIG * 4, 0);
+
+    for (name, small_len) in [("1:1", BIG), ("1:1000", BIG / 1000), ("1:1000000", 1)] {
+        let small = spread(small_len, BIG * 4, 1);
+        let mut out = Vec::with_capacity(big.len() + small.len());
+
+        group.bench_function(format!("naive_{name}"), |b| {
+            b.iter(|| {
+                out.clear();
+                naive_merge(black_box(&small), black_box(&big), &mut out);
+            })
+        });
+        group.bench_function(format!("gallop_{name}"), |b| {
+            b.iter(|| {
+                out.clear();
+                merge_sorted(black_box(&small), black_box(&big), &mut out);
+            })
+        });
+    }
+}
+
+criterion_group!(benches, merge);
+criterion_main!(benches);
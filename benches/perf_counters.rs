@@ -0,0 +1,358 @@
+//! Counts branches, branch misses, cache references and cache misses per lookup with the
+//! hardware performance counters, for `bl_binary_search` and `slice::binary_search`.
+//!
+//! This needs Linux and the `perf` feature. Every flag is optional, and `--len` and `--hit`
+//! take comma-separated lists to measure each combination:
+//!
+//! ```text
+//! cargo bench --features perf --bench perf_counters -- \
+//!     --len 4096,1048576 --hit 0,50,100 --queries uniform --lookups 1000000 --csv counts.csv
+//! ```
+//!
+//! `--queries` is `uniform` for random queries, `sorted` for the same queries in ascending
+//! order, which makes the branches predictable, or `hot` for random queries among only 16
+//! keys, which stay in cache. The counters are opened for this process in user space only, which
+//! a `perf_event_paranoid` of 2 or less allows. When they can't be opened, as in many VMs and
+//! containers, this says why and exits without measuring.
+
+// Everything but `main` is only used on Linux.
+#![cfg_attr(not(target_os = "linux"), allow(dead_code, unused_imports))]
+
+mod common;
+
+use std::{fmt::Write as _, hint::black_box, process::ExitCode};
+
+use common::rng::XorShift;
+use shar_search::SharBinarySearch;
+
+/// A search to measure, returning the same as `bl_binary_search`.
+type Search = fn(&[u64], &u64) -> Result<usize, usize>;
+
+const SEARCHES: [(&str, Search); 2] = [
+    ("bl", |slice, x| slice.bl_binary_search(x)),
+    ("std", |slice, x| slice.binary_search(x)),
+];
+
+/// The order of the queries.
+#[derive(Clone, Copy)]
+enum Queries {
+    Uniform,
+    Sorted,
+    Hot,
+}
+
+impl Queries {
+    fn name(self) -> &'static str {
+        match self {
+            Queries::Uniform => "uniform",
+            Queries::Sorted => "sorted",
+            Queries::Hot => "hot",
+        }
+    }
+}
+
+struct Options {
+    lens: Vec<usize>,
+    hit_percents: Vec<u64>,
+    queries: Queries,
+    lookups: usize,
+    csv: Option<String>,
+}
+
+fn parse_list<T: std::str::FromStr>(flag: &str, value: &str) -> Result<Vec<T>, String> {
+    value
+        .split(',')
+        .map(|item| {
+            item.trim()
+                .parse()
+                .map_err(|_| format!("invalid value for {flag}: {item:?}"))
+        })
+        .collect()
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        lens: vec![4096, 1 << 20, 1 << 24],
+        hit_percents: vec![50],
+        queries: Queries::Uniform,
+        lookups: 1 << 20,
+        csv: None,
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        // Cargo passes `--bench` to every benchmark binary.
+        if flag == "--bench" {
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {flag}"))?;
+        match flag.as_str() {
+            "--len" => options.lens = parse_list(&flag, &value)?,
+            "--hit" => options.hit_percents = parse_list(&flag, &value)?,
+            "--lookups" => {
+                options.lookups = value
+                    .parse()
+                    .map_err(|_| format!("invalid value for --lookups: {value:?}"))?
+            }
+            "--queries" => {
+                options.queries = match value.as_str() {
+                    "uniform" => Queries::Uniform,
+                    "sorted" => Queries::Sorted,
+                    "hot" => Queries::Hot,
+                    _ => return Err(format!("unknown query distribution {value:?}")),
+                }
+            }
+            "--csv" => options.csv = Some(value),
+            _ => return Err(format!("unknown flag {flag}")),
+        }
+    }
+
+    if options.lens.contains(&0) || options.lookups == 0 {
+        return Err("lengths and the number of lookups must be positive".into());
+    }
+    Ok(options)
+}
+
+/// Returns `lookups` queries into a slice of even keys below `2 * len`, of which `hit_percent`
+/// percent are in the slice.
+fn make_queries(len: usize, hit_percent: u64, queries: Queries, lookups: usize) -> Vec<u64> {
+    let mut rng = XorShift::new(len as u64 ^ hit_percent ^ 0x9e37_79b9);
+    let mut query = || {
+        let hit = rng.below(100) < hit_percent;
+        rng.below(len as u64) * 2 + u64::from(!hit)
+    };
+
+    match queries {
+        Queries::Uniform => (0..lookups).map(|_| query()).collect(),
+        Queries::Sorted => {
+            let mut all: Vec<u64> = (0..lookups).map(|_| query()).collect();
+            all.sort_unstable();
+            all
+        }
+        Queries::Hot => {
+            let hot: Vec<u64> = (0..16).map(|_| query()).collect();
+            hot.iter().copied().cycle().take(lookups).collect()
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod counters {
+    //! Just enough of `perf_event_open(2)` to count hardware events for this process.
+
+    use std::{io, mem::size_of};
+
+    use libc::c_int;
+
+    /// The events counted, with their `PERF_COUNT_HW_*` configs.
+    pub const EVENTS: [(&str, u64); 4] = [
+        ("branches", 4),
+        ("branch-misses", 5),
+        ("cache-refs", 2),
+        ("cache-misses", 3),
+    ];
+
+    const PERF_TYPE_HARDWARE: u32 = 0;
+    const FLAG_DISABLED: u64 = 1 << 0;
+    const FLAG_EXCLUDE_KERNEL: u64 = 1 << 5;
+    const FLAG_EXCLUDE_HV: u64 = 1 << 6;
+    const FORMAT_TOTAL_TIME_ENABLED: u64 = 1 << 0;
+    const FORMAT_TOTAL_TIME_RUNNING: u64 = 1 << 1;
+    const IOC_ENABLE: u64 = 0x2400;
+    const IOC_DISABLE: u64 = 0x2401;
+    const IOC_RESET: u64 = 0x2403;
+    const FD_CLOEXEC: u64 = 1 << 3;
+
+    /// `struct perf_event_attr`, up to `PERF_ATTR_SIZE_VER8`, with the fields after `flags`
+    /// left zeroed.
+    #[repr(C)]
+    #[derive(Default)]
+    struct Attr {
+        kind: u32,
+        size: u32,
+        config: u64,
+        sample_period: u64,
+        sample_type: u64,
+        read_format: u64,
+        flags: u64,
+        rest: [u64; 10],
+    }
+
+    /// One hardware counter, counting this process in user space on any CPU.
+    pub struct Counter(c_int);
+
+    impl Counter {
+        pub fn open(config: u64) -> io::Result<Self> {
+            let attr = Attr {
+                kind: PERF_TYPE_HARDWARE,
+                size: size_of::<Attr>() as u32,
+                config,
+                read_format: FORMAT_TOTAL_TIME_ENABLED | FORMAT_TOTAL_TIME_RUNNING,
+                flags: FLAG_DISABLED | FLAG_EXCLUDE_KERNEL | FLAG_EXCLUDE_HV,
+                ..Attr::default()
+            };
+            let (this_process, any_cpu, no_group) = (0, -1, -1);
+            // SAFETY: `attr` is a valid `perf_event_attr` of the size it declares, and lives
+            // for the call.
+            let fd = unsafe {
+                libc::syscall(
+                    libc::SYS_perf_event_open,
+                    &attr as *const Attr,
+                    this_process,
+                    any_cpu,
+                    no_group,
+                    FD_CLOEXEC,
+                )
+            };
+            if fd < 0 {
+                Err(io::Error::last_os_error())
+            } else {
+                Ok(Counter(fd as c_int))
+            }
+        }
+
+        fn ioctl(&self, request: u64) {
+            // SAFETY: these requests take no argument.
+            unsafe { libc::ioctl(self.0, request as _, 0) };
+        }
+
+        pub fn reset_and_enable(&self) {
+            self.ioctl(IOC_RESET);
+            self.ioctl(IOC_ENABLE);
+        }
+
+        pub fn disable(&self) {
+            self.ioctl(IOC_DISABLE);
+        }
+
+        /// Returns the count, scaled up if the kernel only ran the counter for part of the time
+        /// it was enabled, or `None` if it never ran it.
+        pub fn read(&self) -> io::Result<Option<f64>> {
+            let mut values = [0_u64; 3];
+            let size = size_of::<[u64; 3]>();
+            // SAFETY: `values` is valid for writes of `size` bytes.
+            let read = unsafe { libc::read(self.0, values.as_mut_ptr().cast(), size) };
+            if read != size as isize {
+                return Err(io::Error::last_os_error());
+            }
+            let [count, enabled, running] = values;
+            Ok((running > 0).then(|| count as f64 * enabled as f64 / running as f64))
+        }
+    }
+
+    impl Drop for Counter {
+        fn drop(&mut self) {
+            // SAFETY: the descriptor is owned by this counter.
+            unsafe { libc::close(self.0) };
+        }
+    }
+
+    /// Opens a counter for each of [`EVENTS`], or explains why they can't be opened.
+    pub fn open_all() -> Result<Vec<Counter>, String> {
+        EVENTS
+            .iter()
+            .map(|(name, config)| {
+                Counter::open(*config).map_err(|error| {
+                    let paranoid = std::fs::read_to_string("/proc/sys/kernel/perf_event_paranoid")
+                        .map_or_else(|_| "unknown".into(), |s| s.trim().to_string());
+                    format!(
+                        "can't count {name}: {error} (perf_event_paranoid is {paranoid}; \
+                         counting needs it at most 2, and a CPU whose counters the kernel exposes)"
+                    )
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn main() -> ExitCode {
+    use counters::{open_all, EVENTS};
+
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let counters = match open_all() {
+        Ok(counters) => counters,
+        Err(message) => {
+            println!("skipping: {message}");
+            return ExitCode::SUCCESS;
+        }
+    };
+
+    let mut csv = String::from("len,hit_percent,queries,search");
+    for (name, _) in EVENTS {
+        write!(csv, ",{}", name.replace('-', "_")).unwrap();
+    }
+    csv.push('\n');
+
+    print!(
+        "{:>10} {:>5} {:>8} {:>6}",
+        "len", "hits", "queries", "search"
+    );
+    for (name, _) in EVENTS {
+        print!(" {name:>13}");
+    }
+    println!("  (per lookup)");
+
+    for &len in &options.lens {
+        let slice: Vec<u64> = (0..len as u64).map(|i| i * 2).collect();
+
+        for &hit_percent in &options.hit_percents {
+            let queries = make_queries(len, hit_percent, options.queries, options.lookups);
+
+            for (name, search) in SEARCHES {
+                // Once untimed, to fault in the pages and warm the caches.
+                for query in &queries {
+                    black_box(search(black_box(&slice), query)).ok();
+                }
+
+                counters.iter().for_each(|c| c.reset_and_enable());
+                for query in &queries {
+                    black_box(search(black_box(&slice), query)).ok();
+                }
+                counters.iter().for_each(|c| c.disable());
+
+                let queries_name = options.queries.name();
+                print!("{len:>10} {hit_percent:>4}% {queries_name:>8} {name:>6}");
+                write!(csv, "{len},{hit_percent},{queries_name},{name}").unwrap();
+                for counter in &counters {
+                    match counter.read() {
+                        Ok(Some(count)) => {
+                            let per_lookup = count / queries.len() as f64;
+                            print!(" {per_lookup:>13.3}");
+                            write!(csv, ",{per_lookup}").unwrap();
+                        }
+                        Ok(None) | Err(_) => {
+                            print!(" {:>13}", "-");
+                            csv.push(',');
+                        }
+                    }
+                }
+                println!();
+                csv.push('\n');
+            }
+        }
+    }
+
+    if let Some(path) = options.csv {
+        if let Err(error) = std::fs::write(&path, csv) {
+            eprintln!("can't write {path}: {error}");
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+#[cfg(not(target_os = "linux"))]
+fn main() -> ExitCode {
+    println!("skipping: hardware counters are only read on Linux");
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use common::rng::{XorShift, SEED};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use shar_search::SharBinarySearch;
+
+/// Returns `count` reproducible queries below `max`.
+fn queries(count: usize, max: u64) -> Vec<u32> {
+    let mut rng = XorShift::new(SEED);
+    (0..count).map(|_| rng.below(max) as u32).collect()
+}
+
+pub fn quaternary(c: &mut Criterion) {
+    let mut group = c.benchmark_group("quaternary");
+    group.sample_size(20);
+
+    // From in cache to well past a typical L3: 16 KiB, 4 MiB, 64 MiB and 512 MiB.
+    for len in [1 << 12, 1 << 20, 1 << 24, 1 << 27] {
+        let slice: Vec<u32> = (0..len as u32).map(|i| i.wrapping_mul(2)).collect();
+        let keys = queries(4096, len as u64 * 2);
+        group.throughput(Throughput::Elements(keys.len() as u64));
+
+        group.bench_function(format!("binary_{len}"), |b| {
+            b.iter(|| {
+                for k in black_box(&keys) {
+                    black_box(slice.bl_binary_search(k)).ok();
+                }
+            })
+        });
+        group.bench_function(format!("4ary_{len}"), |b| {
+            b.iter(|| {
+                for k in black_box(&keys) {
+                    black_box(slice.bl_binary_search_4ary(k)).ok();
+                }
+            })
+        });
+        group.bench_function(format!("std_{len}"), |b| {
+            b.iter(|| {
+                for k in black_box(&keys) {
+                    black_box(slice.binary_search(k)).ok();
+                }
+            })
+        });
+    }
+}
+
+criterion_group!(benches, quaternary);
+criterion_main!(benches);
@@ -0,0 +1,48 @@
+mod common;
+
+use common::rng::{XorShift, SEED};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use shar_search::{ranked::RankedSortedVec, SharBinarySearch, SortedVec};
+
+/// Returns `count` reproducible values.
+fn values(count: usize) -> Vec<u32> {
+    let mut rng = XorShift::new(SEED);
+    (0..count).map(|_| rng.next_u64() as u32).collect()
+}
+
+pub fn insert_rank(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert_rank");
+    group.sample_size(10);
+
+    // Each value is inserted, then the rank of the next one is queried.
+    for count in [10_000, 200_000] {
+        let values = values(count + 1);
+        group.throughput(Throughput::Elements(count as u64));
+
+        group.bench_function(format!("sorted_vec_{count}"), |b| {
+            b.iter(|| {
+                let mut vec = SortedVec::new();
+                let mut ranks = 0;
+                for w in black_box(&values).windows(2) {
+                    vec.insert(w[0]);
+                    ranks += vec.bl_lower_bound(&w[1]);
+                }
+                ranks
+            })
+        });
+        group.bench_function(format!("ranked_sorted_vec_{count}"), |b| {
+            b.iter(|| {
+                let mut vec = RankedSortedVec::new();
+                let mut ranks = 0;
+                for w in black_box(&values).windows(2) {
+                    vec.insert(w[0]);
+                    ranks += vec.rank(&w[1]);
+                }
+                ranks
+            })
+        });
+    }
+}
+
+criterion_group!(benches, insert_rank);
+criterion_main!(benches);
@@ -0,0 +1,41 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use shar_search::{SharBinarySearch, VecSortedExt};
+
+pub fn remove_keys(c: &mut Criterion) {
+    let mut group = c.benchmark_group("remove_keys");
+    group.sample_size(20);
+
+    const LEN: u64 = 1 << 16;
+    let vec: Vec<u64> = (0..LEN).collect();
+
+    // From a few keys, where the naive loop's shifts are cheap in total, up to a quarter of the
+    // vector.
+    for count in [16, 256, 4096, 16384] {
+        let step = LEN / count;
+        let keys: Vec<u64> = (0..count).map(|i| i * step + step / 2).collect();
+
+        group.bench_function(format!("naive_{count}"), |b| {
+            b.iter_batched_ref(
+                || vec.clone(),
+                |v| {
+                    for key in black_box(&keys) {
+                        if let Ok(index) = v.bl_binary_search(key) {
+                            v.remove(index);
+                        }
+                    }
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(format!("remove_sorted_keys_{count}"), |b| {
+            b.iter_batched_ref(
+                || vec.clone(),
+                |v| v.remove_sorted_keys(black_box(&keys)),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+}
+
+criterion_group!(benches, remove_keys);
+criterion_main!(benches);
@@ -0,0 +1,36 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use shar_search::{runs::count_runs, SharBinarySearch};
+
+pub fn runs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("runs");
+    group.sample_size(20);
+
+    const LEN: u32 = 1 << 24;
+
+    // A handful of gigantic runs, where galloping skips almost everything, and runs of two,
+    // where it cannot.
+    for (name, run_len) in [("gigantic", LEN / 8), ("short", 2)] {
+        let slice: Vec<u32> = (0..LEN).map(|i| i / run_len).collect();
+
+        group.bench_function(format!("chunk_by_{name}"), |b| {
+            b.iter(|| black_box(&slice).chunk_by(|a, b| a == b).count())
+        });
+        group.bench_function(format!("bl_runs_{name}"), |b| {
+            b.iter(|| black_box(&slice).bl_runs().count())
+        });
+        group.bench_function(format!("chunk_by_counts_{name}"), |b| {
+            b.iter(|| {
+                black_box(&slice)
+                    .chunk_by(|a, b| a == b)
+                    .map(|run| (&run[0], run.len() as u64))
+                    .collect::<Vec<_>>()
+            })
+        });
+        group.bench_function(format!("count_runs_{name}"), |b| {
+            b.iter(|| count_runs(black_box(&slice)))
+        });
+    }
+}
+
+criterion_group!(benches, runs);
+criterion_main!(benches);
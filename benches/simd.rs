@@ -0,0 +1,35 @@
+mod common;
+
+use common::rng::{XorShift, SEED};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use shar_search::{simd::bl_batch_lower_bound_simd, SharBatchSearch};
+
+/// Returns `count` reproducible queries below `bound`.
+fn queries(count: usize, bound: u32) -> Vec<u32> {
+    let mut rng = XorShift::new(SEED);
+    (0..count).map(|_| rng.below(bound.into()) as u32).collect()
+}
+
+pub fn simd(c: &mut Criterion) {
+    let mut group = c.benchmark_group("simd_lower_bound");
+    group.sample_size(20);
+
+    // 2^20 `u32`s is 4 MiB, around the size of L2; 2^26 is 256 MiB, far larger than any cache.
+    for len in [1_u32 << 20, 1 << 26] {
+        let haystack: Vec<u32> = (0..len).map(|i| i * 2).collect();
+        let keys = queries(4096, len * 2);
+        let mut out = vec![0_u32; keys.len()];
+        let mut results = vec![Ok(0); keys.len()];
+
+        group.throughput(Throughput::Elements(keys.len() as u64));
+        group.bench_with_input(BenchmarkId::new("scalar_batch", len), &keys, |b, keys| {
+            b.iter(|| haystack.bl_binary_search_batch_into(black_box(keys), &mut results))
+        });
+        group.bench_with_input(BenchmarkId::new("avx2", len), &keys, |b, keys| {
+            b.iter(|| bl_batch_lower_bound_simd(&haystack, black_box(keys), &mut out))
+        });
+    }
+}
+
+criterion_group!(benches, simd);
+criterion_main!(benches);
@@ -0,0 +1,50 @@
+use std::collections::BTreeMap;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use shar_search::{small::SmallSharMap, SharMap};
+
+/// Returns `len` distinct keys in a scrambled order, so the maps are built with real inserts.
+fn keys(len: u32) -> Vec<u32> {
+    (0..len).map(|i| (i * 7 + 3) % len).collect()
+}
+
+/// Builds a map of each size and looks up every key in it, as tiny maps are typically built,
+/// used briefly and dropped, so the allocation matters as much as the searches.
+pub fn small(c: &mut Criterion) {
+    let mut group = c.benchmark_group("small");
+
+    for len in [2, 4, 8, 16] {
+        let keys = keys(len);
+
+        group.bench_function(format!("small_shar_map_8_{len}"), |b| {
+            b.iter(|| {
+                let mut map: SmallSharMap<u32, u32, 8> = SmallSharMap::new();
+                for &k in black_box(&keys) {
+                    map.insert(k, k);
+                }
+                keys.iter().map(|k| map.get(k).unwrap()).sum::<u32>()
+            })
+        });
+        group.bench_function(format!("shar_map_{len}"), |b| {
+            b.iter(|| {
+                let mut map = SharMap::new();
+                for &k in black_box(&keys) {
+                    map.insert(k, k);
+                }
+                keys.iter().map(|k| map.get(k).unwrap()).sum::<u32>()
+            })
+        });
+        group.bench_function(format!("btree_map_{len}"), |b| {
+            b.iter(|| {
+                let mut map = BTreeMap::new();
+                for &k in black_box(&keys) {
+                    map.insert(k, k);
+                }
+                keys.iter().map(|k| map.get(k).unwrap()).sum::<u32>()
+            })
+        });
+    }
+}
+
+criterion_group!(benches, small);
+criterion_main!(benches);
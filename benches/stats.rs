@@ -0,0 +1,43 @@
+mod common;
+
+use common::rng::{XorShift, SEED};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use shar_search::stats::ranks_f64;
+
+/// Returns `count` reproducible numbers in `[0, 1)`.
+fn uniform(count: usize, seed: u64) -> Vec<f64> {
+    let mut rng = XorShift::new(seed);
+    (0..count).map(|_| rng.unit_f64()).collect()
+}
+
+pub fn ranks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ranks");
+    group.sample_size(20);
+
+    // 10^7 `f64`s is 80 MB, far larger than any cache.
+    let mut sample = uniform(10_000_000, SEED);
+    sample.sort_by(f64::total_cmp);
+    let queries = uniform(100_000, 0xD1B5_4A32_D192_ED03);
+    let mut sorted_queries = queries.clone();
+    sorted_queries.sort_by(f64::total_cmp);
+
+    group.throughput(Throughput::Elements(queries.len() as u64));
+
+    group.bench_function("scalar", |b| {
+        b.iter(|| {
+            black_box(&queries)
+                .iter()
+                .map(|q| sample.partition_point(|p| p.total_cmp(q).is_le()))
+                .collect::<Vec<_>>()
+        })
+    });
+    group.bench_function("unsorted_queries", |b| {
+        b.iter(|| ranks_f64(&sample, black_box(&queries), true))
+    });
+    group.bench_function("sorted_queries", |b| {
+        b.iter(|| ranks_f64(&sample, black_box(&sorted_queries), true))
+    });
+}
+
+criterion_group!(benches, ranks);
+criterion_main!(benches);
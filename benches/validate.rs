@@ -0,0 +1,30 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use shar_search::validate::{is_sorted_f64, is_sorted_fast};
+
+pub fn validate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("validate");
+    group.sample_size(10);
+
+    // Sorted, so every check has to scan the whole slice.
+    const LEN: u64 = 100_000_000;
+    let ints: Vec<u64> = (0..LEN).collect();
+    group.throughput(Throughput::Elements(LEN));
+
+    group.bench_function("std_u64", |b| b.iter(|| black_box(&ints).is_sorted()));
+    group.bench_function("fast_u64", |b| b.iter(|| is_sorted_fast(black_box(&ints))));
+    drop(ints);
+
+    let ints: Vec<u32> = (0..LEN as u32).collect();
+    group.bench_function("std_u32", |b| b.iter(|| black_box(&ints).is_sorted()));
+    group.bench_function("fast_u32", |b| b.iter(|| is_sorted_fast(black_box(&ints))));
+    drop(ints);
+
+    let floats: Vec<f64> = (0..LEN).map(|i| i as f64 - 5e7).collect();
+    group.bench_function("std_f64_total_cmp", |b| {
+        b.iter(|| black_box(&floats).is_sorted_by(|a, b| a.total_cmp(b).is_le()))
+    });
+    group.bench_function("fast_f64", |b| b.iter(|| is_sorted_f64(black_box(&floats))));
+}
+
+criterion_group!(benches, validate);
+criterion_main!(benches);
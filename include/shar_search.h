@@ -0,0 +1,51 @@
+/* Generated from src/ffi.rs: run `SHAR_BLESS=1 cargo test --features ffi` after
+ * changing the exported functions to update it. */
+
+#ifndef SHAR_SEARCH_H
+#define SHAR_SEARCH_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+size_t shar_lower_bound_u8(const uint8_t *ptr, size_t len, uint8_t key);
+size_t shar_upper_bound_u8(const uint8_t *ptr, size_t len, uint8_t key);
+bool shar_search_u8(const uint8_t *ptr, size_t len, uint8_t key, size_t *out_index);
+
+size_t shar_lower_bound_u16(const uint16_t *ptr, size_t len, uint16_t key);
+size_t shar_upper_bound_u16(const uint16_t *ptr, size_t len, uint16_t key);
+bool shar_search_u16(const uint16_t *ptr, size_t len, uint16_t key, size_t *out_index);
+
+size_t shar_lower_bound_u32(const uint32_t *ptr, size_t len, uint32_t key);
+size_t shar_upper_bound_u32(const uint32_t *ptr, size_t len, uint32_t key);
+bool shar_search_u32(const uint32_t *ptr, size_t len, uint32_t key, size_t *out_index);
+
+size_t shar_lower_bound_u64(const uint64_t *ptr, size_t len, uint64_t key);
+size_t shar_upper_bound_u64(const uint64_t *ptr, size_t len, uint64_t key);
+bool shar_search_u64(const uint64_t *ptr, size_t len, uint64_t key, size_t *out_index);
+
+size_t shar_lower_bound_i8(const int8_t *ptr, size_t len, int8_t key);
+size_t shar_upper_bound_i8(const int8_t *ptr, size_t len, int8_t key);
+bool shar_search_i8(const int8_t *ptr, size_t len, int8_t key, size_t *out_index);
+
+size_t shar_lower_bound_i16(const int16_t *ptr, size_t len, int16_t key);
+size_t shar_upper_bound_i16(const int16_t *ptr, size_t len, int16_t key);
+bool shar_search_i16(const int16_t *ptr, size_t len, int16_t key, size_t *out_index);
+
+size_t shar_lower_bound_i32(const int32_t *ptr, size_t len, int32_t key);
+size_t shar_upper_bound_i32(const int32_t *ptr, size_t len, int32_t key);
+bool shar_search_i32(const int32_t *ptr, size_t len, int32_t key, size_t *out_index);
+
+size_t shar_lower_bound_i64(const int64_t *ptr, size_t len, int64_t key);
+size_t shar_upper_bound_i64(const int64_t *ptr, size_t len, int64_t key);
+bool shar_search_i64(const int64_t *ptr, size_t len, int64_t key, size_t *out_index);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SHAR_SEARCH_H */
@@ -0,0 +1,281 @@
+//! Aligning sorted timestamp series by matching each query to its nearest reference.
+//!
+//! Both series must be sorted. Since the queries are sorted, each one's search starts where
+//! the previous one ended and gallops forward, so aligning `q` queries against `n` references
+//! costs `O(q log(n / q))` comparisons rather than `q` independent searches.
+//!
+//! When two references are equally close to a query, the earlier one (with the smaller
+//! timestamp) is chosen. Among references with equal timestamps, the one with the lowest index
+//! is chosen.
+
+use alloc::vec::Vec;
+
+use crate::{gallop::gallop, SharBinarySearch};
+
+/// Matches each of the sorted `queries` to the index of the closest of the sorted `reference`
+/// timestamps that is within `tolerance` of it, or `None` if there is no such reference.
+///
+/// If `unique_matches` is set, each reference is matched at most once, for a one-to-one
+/// alignment. The matching is then greedy: queries are matched in order, each to the closest
+/// reference not already matched to an earlier query, even if a later query is closer to it.
+///
+/// See the [module documentation](self) for how ties are broken. In debug builds, this panics
+/// if `queries` is not sorted.
+///
+/// ```
+/// use shar_search::align::nearest_within;
+///
+/// let reference = [100, 200, 300];
+/// let queries = [95, 150, 240, 260, 400];
+/// assert_eq!(
+///     nearest_within(&reference, &queries, 50, false),
+///     [Some(0), Some(0), Some(1), Some(2), None]
+/// );
+/// assert_eq!(
+///     nearest_within(&reference, &queries, 50, true),
+///     [Some(0), Some(1), None, Some(2), None]
+/// );
+/// ```
+pub fn nearest_within(
+    reference: &[u64],
+    queries: &[u64],
+    tolerance: u64,
+    unique_matches: bool,
+) -> Vec<Option<usize>> {
+    nearest_within_by_key(
+        reference,
+        queries,
+        tolerance,
+        unique_matches,
+        |&r| r,
+        |&q| q,
+    )
+}
+
+/// Matches each of the sorted `queries` to the closest of the sorted `reference` elements,
+/// where the timestamps of each are given by `reference_key` and `query_key`. See
+/// [`nearest_within`].
+pub fn nearest_within_by_key<R, Q, F, G>(
+    reference: &[R],
+    queries: &[Q],
+    tolerance: u64,
+    unique_matches: bool,
+    mut reference_key: F,
+    mut query_key: G,
+) -> Vec<Option<usize>>
+where
+    F: FnMut(&R) -> u64,
+    G: FnMut(&Q) -> u64,
+{
+    debug_assert!(
+        queries
+            .windows(2)
+            .all(|w| query_key(&w[0]) <= query_key(&w[1])),
+        "the queries must be sorted"
+    );
+
+    let mut free = unique_matches.then(|| FreeList::new(reference.len()));
+    let mut start = 0;
+
+    queries
+        .iter()
+        .map(|query| {
+            let target = query_key(query);
+
+            // Everything before `lower` is less than the target, and everything from it on is
+            // at least the target.
+            let lower = start + gallop(&reference[start..], |r| reference_key(r) < target);
+            start = lower;
+
+            let (before, after) = match &mut free {
+                Some(free) => (free.last_before(lower), free.first_from(lower)),
+                None => (lower.checked_sub(1), Some(lower)),
+            };
+
+            let before = before
+                .map(|index| (index, target - reference_key(&reference[index])))
+                .filter(|&(_, distance)| distance <= tolerance);
+            let after = after
+                .filter(|&index| index < reference.len())
+                .map(|index| (index, reference_key(&reference[index]) - target))
+                .filter(|&(_, distance)| distance <= tolerance);
+
+            let chosen = match (before, after) {
+                (Some(before), Some(after)) if after.1 < before.1 => after.0,
+                (Some((index, _)), _) => {
+                    // Prefer the first (unmatched) reference with the same timestamp.
+                    let timestamp = reference_key(&reference[index]);
+                    let first =
+                        reference[..index].bl_partition_point(|r| reference_key(r) < timestamp);
+                    match &mut free {
+                        Some(free) => free.first_from(first).unwrap_or(index),
+                        None => first,
+                    }
+                }
+                (None, Some((index, _))) => index,
+                (None, None) => return None,
+            };
+
+            if let Some(free) = &mut free {
+                free.take(chosen);
+            }
+
+            Some(chosen)
+        })
+        .collect()
+}
+
+/// Tracks which references are still unmatched, finding the nearest unmatched one on either
+/// side of an index in near-constant amortized time with path-compressed "next free" links.
+struct FreeList {
+    /// `forward[i]` leads towards the first unmatched index at or after `i`; `len` means none.
+    forward: Vec<usize>,
+    /// `backward[i]` leads towards one plus the last unmatched index before `i`; 0 means none.
+    backward: Vec<usize>,
+}
+
+impl FreeList {
+    fn new(len: usize) -> Self {
+        Self {
+            forward: (0..=len).collect(),
+            backward: (0..=len).collect(),
+        }
+    }
+
+    fn find(links: &mut [usize], index: usize) -> usize {
+        let mut root = index;
+        while links[root] != root {
+            root = links[root];
+        }
+
+        let mut current = index;
+        while links[current] != root {
+            let next = links[current];
+            links[current] = root;
+            current = next;
+        }
+
+        root
+    }
+
+    /// Returns the first unmatched index at or after `index`.
+    fn first_from(&mut self, index: usize) -> Option<usize> {
+        let found = Self::find(&mut self.forward, index);
+        (found < self.forward.len() - 1).then_some(found)
+    }
+
+    /// Returns the last unmatched index before `index`.
+    fn last_before(&mut self, index: usize) -> Option<usize> {
+        Self::find(&mut self.backward, index).checked_sub(1)
+    }
+
+    fn take(&mut self, index: usize) {
+        self.forward[index] = index + 1;
+        self.backward[index + 1] = index;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{nearest_within, nearest_within_by_key};
+    use crate::reference::{self, assert_matches_reference, inputs};
+
+    #[test]
+    fn test_against_reference() {
+        // Sorted timestamps with duplicates, where a tolerance of 0 matches only exact hits and
+        // one of 14 spans several neighbours.
+        let cases = inputs::slice_pairs(47).flat_map(|(times, queries)| {
+            let times: Vec<u64> = times.into_iter().map(u64::from).collect();
+            let queries: Vec<u64> = queries.into_iter().map(u64::from).collect();
+            [0, 2, 7, 14].into_iter().flat_map(move |tolerance| {
+                [false, true].map(|unique_matches| {
+                    (times.clone(), queries.clone(), tolerance, unique_matches)
+                })
+            })
+        });
+
+        assert_matches_reference!(
+            for (times, queries, tolerance, unique_matches) in cases =>
+            nearest_within(&times, &queries, tolerance, unique_matches),
+            reference::nearest_within(&times, &queries, tolerance, unique_matches),
+        );
+    }
+
+    #[test]
+    fn test_clustered_and_ties() {
+        // Equidistant neighbours resolve to the earlier one, and duplicate timestamps to the
+        // lowest index.
+        let reference = [10, 20, 20, 20, 30];
+        assert_eq!(
+            nearest_within(&reference, &[15, 20, 25, 26], 5, false),
+            [Some(0), Some(1), Some(1), Some(4)]
+        );
+
+        // In unique mode, a cluster of queries takes a cluster of references in turn.
+        assert_eq!(
+            nearest_within(&reference, &[20, 20, 20, 20, 20], 10, true),
+            [Some(1), Some(2), Some(3), Some(0), Some(4)]
+        );
+    }
+
+    #[test]
+    fn test_tolerance_and_range() {
+        let reference = [100, 200, 300];
+
+        assert_eq!(
+            nearest_within(&reference, &[99, 100, 101, 300], 0, false),
+            [None, Some(0), None, Some(2)]
+        );
+        assert_eq!(
+            nearest_within(&reference, &[0, 50, 350, u64::MAX], 50, false),
+            [None, Some(0), Some(2), None]
+        );
+        assert_eq!(nearest_within(&[], &[1, 2], 10, true), [None, None]);
+        assert!(nearest_within(&reference, &[], 10, false).is_empty());
+    }
+
+    #[test]
+    fn test_greedy_unique() {
+        // The first query takes the reference that the second one is closer to.
+        let reference = [10, 14];
+        assert_eq!(
+            nearest_within(&reference, &[13, 14], 5, true),
+            [Some(1), Some(0)]
+        );
+        assert_eq!(
+            nearest_within(&reference, &[13, 14], 3, true),
+            [Some(1), None]
+        );
+    }
+
+    #[test]
+    fn test_by_key() {
+        struct Sample {
+            timestamp: u64,
+            value: f32,
+        }
+
+        let reference = [
+            Sample {
+                timestamp: 1_000,
+                value: 1.0,
+            },
+            Sample {
+                timestamp: 2_000,
+                value: 2.0,
+            },
+        ];
+        let queries = [(1_100, 'a'), (1_600, 'b'), (5_000, 'c')];
+
+        let matches = nearest_within_by_key(
+            &reference,
+            &queries,
+            500,
+            false,
+            |s| s.timestamp,
+            |&(t, _)| t,
+        );
+        assert_eq!(matches, [Some(0), Some(1), None]);
+        assert_eq!(reference[matches[1].unwrap()].value, 2.0);
+    }
+}
@@ -0,0 +1,239 @@
+//! Approximate searches of sorted float slices, for values that come out of numerical
+//! computations where exact equality is meaningless.
+
+use crate::SharBinarySearch;
+
+/// How close two floats must be to be considered equal by
+/// [`bl_binary_search_approx`](SharApproxSearch::bl_binary_search_approx).
+///
+/// Whatever the tolerance, equal values (including infinities of the same sign, and `0.0` and
+/// `-0.0`) are always within it, an infinity is never within it of any other value, and NaN is
+/// never within it of anything.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tolerance {
+    /// Values are within the tolerance if their difference is at most this.
+    Absolute(f64),
+    /// Values are within the tolerance if their difference is at most this fraction of the
+    /// larger of their magnitudes.
+    Relative(f64),
+    /// Values are within the tolerance if there are at most this many representable values
+    /// from one to the other, counting `0.0` and `-0.0` as one value.
+    Ulps(u64),
+}
+
+/// Trait for approximately searching sorted slices of floats.
+pub trait SharApproxSearch<F> {
+    /// Binary searches this slice for the element closest to `x` that is within `tol` of it.
+    /// Note it is assumed that the slice is sorted and holds no NaNs.
+    ///
+    /// If there is such an element, returns `Ok` with its index. When several elements are
+    /// equally close, the one with the lowest index is returned. Otherwise, returns `Err` with
+    /// the index where `x` could be inserted to keep the slice sorted.
+    ///
+    /// ```
+    /// use shar_search::approx::{SharApproxSearch, Tolerance};
+    ///
+    /// // Nominal sample points every 0.1s, and timestamps measured with some noise.
+    /// let nominal: Vec<f64> = (0..10).map(|i| i as f64 / 10.0).collect();
+    /// let measured = [0.1 + 0.2, 0.5000001, 0.55, 2.0];
+    ///
+    /// let matched: Vec<_> = measured
+    ///     .iter()
+    ///     .map(|&t| nominal.bl_binary_search_approx(t, Tolerance::Absolute(1e-6)))
+    ///     .collect();
+    /// assert_eq!(matched, [Ok(3), Ok(5), Err(6), Err(10)]);
+    ///
+    /// // 0.1 + 0.2 and 0.3 differ in the last place, so an exact search would miss.
+    /// assert_ne!(0.1 + 0.2, nominal[3]);
+    /// ```
+    fn bl_binary_search_approx(&self, x: F, tol: Tolerance) -> Result<usize, usize>;
+}
+
+macro_rules! impl_approx {
+    ($float:ty, $bits:ty) => {
+        impl SharApproxSearch<$float> for [$float] {
+            fn bl_binary_search_approx(&self, x: $float, tol: Tolerance) -> Result<usize, usize> {
+                /// Maps a float to an integer with the same ordering, with `-0.0` and `0.0`
+                /// mapping to the same integer, so ULP distances are differences.
+                fn ordered(v: $float) -> $bits {
+                    const SIGN: $bits = 1 << (<$bits>::BITS - 1);
+                    let bits = v.to_bits();
+                    if bits & SIGN != 0 {
+                        // Negative values count down from `SIGN`, with `-0.0` at `SIGN`.
+                        SIGN - (bits & !SIGN)
+                    } else {
+                        SIGN + bits
+                    }
+                }
+
+                fn within(a: $float, x: $float, tol: Tolerance) -> bool {
+                    if a == x {
+                        return true;
+                    }
+                    if !a.is_finite() || !x.is_finite() {
+                        return false;
+                    }
+
+                    let diff = f64::from((a - x).abs());
+                    match tol {
+                        Tolerance::Absolute(eps) => diff <= eps,
+                        Tolerance::Relative(fraction) => {
+                            diff <= fraction * f64::from(a.abs().max(x.abs()))
+                        }
+                        Tolerance::Ulps(ulps) => u64::from(ordered(a).abs_diff(ordered(x))) <= ulps,
+                    }
+                }
+
+                let lower = self.bl_partition_point(|a| *a < x);
+
+                let after = self.get(lower).copied().filter(|&a| within(a, x, tol));
+                if after == Some(x) {
+                    return Ok(lower);
+                }
+
+                let before = lower
+                    .checked_sub(1)
+                    .map(|i| self[i])
+                    .filter(|&a| within(a, x, tol));
+
+                match (before, after) {
+                    // Prefer the element before on ties, as it has the lower index.
+                    (Some(b), Some(a)) if a - x < x - b => Ok(lower),
+                    // The first element equal to `b`.
+                    (Some(b), _) => Ok(self[..lower].bl_partition_point(|a| *a < b)),
+                    (None, Some(_)) => Ok(lower),
+                    (None, None) => Err(lower),
+                }
+            }
+        }
+    };
+}
+
+impl_approx!(f64, u64);
+impl_approx!(f32, u32);
+
+#[cfg(test)]
+mod test {
+    use super::{SharApproxSearch, Tolerance};
+
+    #[test]
+    fn test_last_ulp() {
+        let one = 1.0_f64;
+        let slice = [0.5, one.next_down(), one.next_up(), 2.0];
+
+        assert_eq!(
+            slice.bl_binary_search_approx(one, Tolerance::Ulps(0)),
+            Err(2)
+        );
+        // Both neighbours are one ULP away in ordering, but the one below is closer in value.
+        assert_eq!(
+            slice.bl_binary_search_approx(one, Tolerance::Ulps(1)),
+            Ok(1)
+        );
+        assert_eq!(
+            slice.bl_binary_search_approx(one, Tolerance::Absolute(1e-15)),
+            Ok(1)
+        );
+        assert_eq!(
+            slice.bl_binary_search_approx(one, Tolerance::Absolute(1e-17)),
+            Err(2)
+        );
+        assert_eq!(
+            slice.bl_binary_search_approx(one, Tolerance::Relative(1e-15)),
+            Ok(1)
+        );
+
+        let up = [1.0, one.next_up(), one.next_up().next_up()];
+        let x = one.next_up().next_up().next_up();
+        assert_eq!(up.bl_binary_search_approx(x, Tolerance::Ulps(1)), Ok(2));
+        assert_eq!(up.bl_binary_search_approx(x, Tolerance::Ulps(3)), Ok(2));
+        assert_eq!(
+            up.bl_binary_search_approx(x.next_up(), Tolerance::Ulps(1)),
+            Err(3)
+        );
+    }
+
+    #[test]
+    fn test_closest_then_leftmost() {
+        let slice = [1.0, 1.9, 2.0, 2.0, 2.0, 2.2];
+        let tol = Tolerance::Absolute(0.5);
+
+        assert_eq!(slice.bl_binary_search_approx(2.0, tol), Ok(2));
+        assert_eq!(slice.bl_binary_search_approx(2.01, tol), Ok(2));
+        assert_eq!(slice.bl_binary_search_approx(2.15, tol), Ok(5));
+        assert_eq!(slice.bl_binary_search_approx(1.45, tol), Ok(0));
+        assert_eq!(slice.bl_binary_search_approx(3.0, tol), Err(6));
+        assert_eq!(slice.bl_binary_search_approx(0.0, tol), Err(0));
+
+        // Exactly between two elements, the lower index wins.
+        let halves = [1.0, 2.0];
+        assert_eq!(halves.bl_binary_search_approx(1.5, tol), Ok(0));
+    }
+
+    #[test]
+    fn test_special_values() {
+        let slice = [f64::NEG_INFINITY, -1.0, -0.0, 1.0, f64::MAX, f64::INFINITY];
+        let ulps = Tolerance::Ulps(u64::MAX);
+        let relative = Tolerance::Relative(1.0);
+
+        assert_eq!(
+            slice.bl_binary_search_approx(0.0, Tolerance::Ulps(0)),
+            Ok(2)
+        );
+        assert_eq!(
+            slice.bl_binary_search_approx(-0.0, Tolerance::Absolute(0.0)),
+            Ok(2)
+        );
+        assert_eq!(slice.bl_binary_search_approx(f64::INFINITY, ulps), Ok(5));
+        assert_eq!(
+            slice.bl_binary_search_approx(f64::NEG_INFINITY, ulps),
+            Ok(0)
+        );
+
+        // Infinities match only themselves, however loose the tolerance.
+        let finite = [-1.0, 1.0, f64::MAX];
+        assert_eq!(
+            finite.bl_binary_search_approx(f64::INFINITY, relative),
+            Err(3)
+        );
+        assert_eq!(
+            finite.bl_binary_search_approx(f64::NEG_INFINITY, ulps),
+            Err(0)
+        );
+        assert_eq!(slice.bl_binary_search_approx(f64::NAN, ulps).ok(), None);
+
+        // The smallest subnormals either side of zero are two ULPs apart.
+        let tiny = f64::from_bits(1);
+        assert_eq!(
+            [-tiny].bl_binary_search_approx(tiny, Tolerance::Ulps(2)),
+            Ok(0)
+        );
+        assert_eq!(
+            [-tiny].bl_binary_search_approx(tiny, Tolerance::Ulps(1)),
+            Err(1)
+        );
+    }
+
+    #[test]
+    fn test_f32() {
+        let one = 1.0_f32;
+        let slice = [0.0, one.next_up(), 3.0];
+
+        assert_eq!(
+            slice.bl_binary_search_approx(one, Tolerance::Ulps(1)),
+            Ok(1)
+        );
+        assert_eq!(
+            slice.bl_binary_search_approx(one, Tolerance::Ulps(0)),
+            Err(1)
+        );
+        assert_eq!(
+            slice.bl_binary_search_approx(2.9, Tolerance::Absolute(0.2)),
+            Ok(2)
+        );
+        assert_eq!(
+            slice.bl_binary_search_approx(2.9, Tolerance::Relative(0.01)),
+            Err(2)
+        );
+    }
+}
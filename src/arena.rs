@@ -0,0 +1,283 @@
+//! Searches of sorted strings stored in one contiguous byte buffer, addressed by an offset
+//! table, without materializing a slice per string.
+//!
+//! A [`StrArena`] borrows the two buffers of a dictionary: `bytes`, holding every string back
+//! to back, and `offsets`, holding `n + 1` offsets into it for `n` strings. String `i` is
+//! `bytes[offsets[i]..offsets[i + 1]]`, so the last string ends at the last offset, which needn't
+//! be the end of `bytes`. Each probe of a search slices only the string it compares.
+//!
+//! Strings are compared as bytes, which orders UTF-8 the same as [`str`]'s [`Ord`].
+//!
+//! ```
+//! use shar_search::arena::StrArena;
+//!
+//! let bytes = b"applebananabandcherry";
+//! let offsets = [0, 5, 11, 15, 21];
+//! let arena = StrArena::new(bytes, &offsets).unwrap();
+//!
+//! assert_eq!(arena.len(), 4);
+//! assert_eq!(arena.search(b"band"), Ok(2));
+//! assert_eq!(arena.search(b"blueberry"), Err(3));
+//! assert_eq!(arena.prefix_range(b"ban"), 1..3);
+//! assert_eq!(arena.as_str(3), Ok("cherry"));
+//! ```
+
+use core::{error::Error, fmt, ops::Range, str::Utf8Error};
+
+use crate::columns::partition_point;
+
+/// The error returned when an offset table is invalid for its bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArenaError {
+    /// The offset at this index is less than the one before it.
+    Decreasing(usize),
+    /// The last offset is past the end of the bytes.
+    OutOfBounds {
+        /// The last offset.
+        offset: usize,
+        /// The length of the bytes.
+        len: usize,
+    },
+}
+
+impl fmt::Display for ArenaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArenaError::Decreasing(index) => {
+                write!(f, "offset at index {index} is less than its predecessor")
+            }
+            ArenaError::OutOfBounds { offset, len } => {
+                write!(f, "offset {offset} is past the end of {len} bytes")
+            }
+        }
+    }
+}
+
+impl Error for ArenaError {}
+
+/// A read-only, sorted table of byte strings stored in one buffer. See the
+/// [module documentation](self).
+#[derive(Clone, Copy, Debug)]
+pub struct StrArena<'a> {
+    bytes: &'a [u8],
+    offsets: &'a [u32],
+}
+
+impl<'a> StrArena<'a> {
+    /// Creates an arena of the strings of `bytes` delimited by `offsets`, checking that the
+    /// offsets never decrease and that the last is within `bytes`. Note it is assumed that the
+    /// strings are sorted.
+    ///
+    /// An empty `offsets` is accepted as an arena with no strings, like one with a single
+    /// offset.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an offset is less than the one before it, or if the last offset is
+    /// past the end of `bytes`.
+    pub fn new(bytes: &'a [u8], offsets: &'a [u32]) -> Result<Self, ArenaError> {
+        if let Some(index) = offsets.windows(2).position(|w| w[0] > w[1]) {
+            return Err(ArenaError::Decreasing(index + 1));
+        }
+
+        if let Some(&last) = offsets.last() {
+            let offset = last as usize;
+            if offset > bytes.len() {
+                return Err(ArenaError::OutOfBounds {
+                    offset,
+                    len: bytes.len(),
+                });
+            }
+        }
+
+        Ok(Self { bytes, offsets })
+    }
+
+    /// Returns the number of strings.
+    pub fn len(&self) -> usize {
+        self.offsets.len().saturating_sub(1)
+    }
+
+    /// Returns whether there are no strings.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns string `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn get(&self, index: usize) -> &'a [u8] {
+        assert!(
+            index < self.len(),
+            "index {index} is out of bounds for an arena of {} strings",
+            self.len()
+        );
+        &self.bytes[self.offsets[index] as usize..self.offsets[index + 1] as usize]
+    }
+
+    /// Returns string `index` as a `str`, validating it as UTF-8.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the string isn't valid UTF-8.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn as_str(&self, index: usize) -> Result<&'a str, Utf8Error> {
+        core::str::from_utf8(self.get(index))
+    }
+
+    /// Returns an iterator over the strings, in order.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &'a [u8]> + DoubleEndedIterator + '_ {
+        (0..self.len()).map(|index| self.get(index))
+    }
+
+    /// Binary searches for `key`. If there are multiple matches, the first is returned.
+    pub fn search(&self, key: &[u8]) -> Result<usize, usize> {
+        let index = self.lower_bound(key);
+
+        if index < self.len() && self.get(index) == key {
+            Ok(index)
+        } else {
+            Err(index)
+        }
+    }
+
+    /// Returns the index of the first string not less than `key`.
+    pub fn lower_bound(&self, key: &[u8]) -> usize {
+        partition_point(self.len(), |index| self.get(index) < key)
+    }
+
+    /// Returns the range of indices of the strings that start with `prefix`. The range is empty
+    /// (and starts at the insertion point) if there are none.
+    pub fn prefix_range(&self, prefix: &[u8]) -> Range<usize> {
+        let start = self.lower_bound(prefix);
+        let end = partition_point(self.len(), |index| {
+            let s = self.get(index);
+            s < prefix || s.starts_with(prefix)
+        });
+
+        start..end
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::XorShift;
+
+    #[test]
+    fn test_empty_strings() {
+        let offsets = [0, 0, 0, 3, 3, 6];
+        let arena = StrArena::new(b"abcxyz", &offsets).unwrap();
+        assert_eq!(arena.len(), 5);
+
+        assert_eq!(arena.get(0), b"");
+        assert_eq!(arena.get(1), b"");
+        assert_eq!(arena.get(3), b"");
+        assert_eq!(arena.search(b""), Ok(0));
+        assert_eq!(arena.prefix_range(b""), 0..5);
+        assert_eq!(arena.search(b"abc"), Ok(2));
+
+        for empty in [StrArena::new(b"", &[]), StrArena::new(b"abc", &[2])] {
+            let empty = empty.unwrap();
+            assert!(empty.is_empty());
+            assert_eq!(empty.search(b""), Err(0));
+            assert_eq!(empty.prefix_range(b"a"), 0..0);
+            assert_eq!(empty.iter().count(), 0);
+        }
+    }
+
+    #[test]
+    fn test_last_entry() {
+        // The last string ends at the last offset, not at the end of the bytes.
+        let bytes = b"antbeecattrailing";
+        let offsets = [0, 3, 6, 9];
+        let arena = StrArena::new(bytes, &offsets).unwrap();
+
+        assert_eq!(arena.len(), 3);
+        assert_eq!(arena.get(2), b"cat");
+        assert_eq!(arena.search(b"cat"), Ok(2));
+        assert_eq!(arena.search(b"cattrailing"), Err(3));
+        assert_eq!(arena.prefix_range(b"ca"), 2..3);
+        assert_eq!(arena.iter().next_back(), Some(&b"cat"[..]));
+
+        // Offsets needn't start at zero either.
+        let arena = StrArena::new(bytes, &offsets[1..]).unwrap();
+        assert_eq!(arena.get(0), b"bee");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_get_out_of_bounds() {
+        StrArena::new(b"ab", &[0, 1, 2]).unwrap().get(2);
+    }
+
+    #[test]
+    fn test_invalid_offsets() {
+        assert_eq!(
+            StrArena::new(b"abc", &[0, 2, 1, 3]).unwrap_err(),
+            ArenaError::Decreasing(2)
+        );
+        let err = StrArena::new(b"abc", &[0, 4]).unwrap_err();
+        assert_eq!(err, ArenaError::OutOfBounds { offset: 4, len: 3 });
+        assert_eq!(err.to_string(), "offset 4 is past the end of 3 bytes");
+    }
+
+    #[test]
+    fn test_as_str() {
+        // "café", then a byte that is never valid UTF-8.
+        let bytes = b"caf\xc3\xa9\xff";
+        let arena = StrArena::new(bytes, &[0, 5, 6]).unwrap();
+
+        assert_eq!(arena.as_str(0), Ok("caf\u{e9}"));
+        assert!(arena.as_str(1).is_err());
+        assert_eq!(arena.search(b"\xff"), Ok(1));
+    }
+
+    #[test]
+    fn test_fuzz_against_vec() {
+        let mut rng = XorShift::new(147);
+
+        for _ in 0..200 {
+            let mut strings: Vec<Vec<u8>> = (0..rng.below(25))
+                .map(|_| {
+                    (0..rng.below(4))
+                        .map(|_| b'a' + rng.below(3) as u8)
+                        .collect()
+                })
+                .collect();
+            strings.sort_unstable();
+
+            let bytes: Vec<u8> = strings.concat();
+            let mut offsets = vec![0_u32];
+            for s in &strings {
+                offsets.push(offsets.last().unwrap() + s.len() as u32);
+            }
+
+            let arena = StrArena::new(&bytes, &offsets).unwrap();
+            let reference: Vec<&[u8]> = strings.iter().map(Vec::as_slice).collect();
+            assert!(arena.iter().eq(reference.iter().copied()));
+
+            for _ in 0..30 {
+                let key: Vec<u8> = (0..rng.below(5))
+                    .map(|_| b'a' + rng.below(4) as u8)
+                    .collect();
+
+                let lower = reference.partition_point(|s| *s < &key[..]);
+                let expected = match reference.get(lower) {
+                    Some(s) if *s == &key[..] => Ok(lower),
+                    _ => Err(lower),
+                };
+                assert_eq!(arena.search(&key), expected);
+
+                let end = reference.partition_point(|s| *s < &key[..] || s.starts_with(&key));
+                assert_eq!(arena.prefix_range(&key), lower..end);
+                assert!(reference[lower..end].iter().all(|s| s.starts_with(&key)));
+            }
+        }
+    }
+}
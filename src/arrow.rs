@@ -0,0 +1,424 @@
+//! Searches of sorted [Apache Arrow](https://arrow.apache.org/) arrays, enabled by the `arrow`
+//! feature.
+//!
+//! [`SharArrowSearch`] is implemented for [`PrimitiveArray`]s, which are searched through their
+//! values buffer, and for string and binary arrays ([`GenericByteArray`]), which are searched
+//! through their offsets and value bytes without building a `&str` per probe. Results are
+//! logical row indices, relative to the start of the array even when it's a slice of a larger
+//! one, in the same `Result<usize, usize>` form as
+//! [`bl_binary_search`](crate::SharBinarySearch::bl_binary_search).
+//!
+//! # Nulls
+//!
+//! A sorted array with nulls keeps them together at one end, as given by a [`NullOrder`]. The
+//! searches only look at the rows in between, so no null is ever matched, and an insertion point
+//! is never among the nulls. If the nulls aren't all at the given end, the results are
+//! unspecified. Arrays without a null buffer are searched the same way under either order.
+//!
+//! Primitive floats are compared with the same total order as
+//! [`ArrowNativeTypeOp::compare`], which matches Arrow's sort kernels, and strings by their
+//! bytes.
+//!
+//! # Dictionaries
+//!
+//! Dictionary-encoded string arrays are searched by their decoded values with
+//! [`bl_binary_search_dictionary`]. Dictionaries of other value types are rejected with
+//! [`UnsupportedDictionary`].
+//!
+//! ```
+//! use arrow_array::{Int64Array, StringArray};
+//! use shar_search::arrow::{NullOrder, SharArrowSearch};
+//!
+//! let ids = Int64Array::from(vec![None, Some(3), Some(8), Some(8), Some(21)]);
+//! assert_eq!(ids.bl_binary_search_arrow(&8, NullOrder::First), Ok(2));
+//! assert_eq!(ids.bl_binary_search_arrow(&1, NullOrder::First), Err(1));
+//!
+//! let names = StringArray::from(vec!["ash", "birch", "elm", "oak"]).slice(1, 3);
+//! assert_eq!(names.bl_binary_search_arrow("elm", NullOrder::Last), Ok(1));
+//! ```
+
+use core::{cmp::Ordering, error::Error, fmt, ops::Range};
+
+use arrow_array::{
+    cast::AsArray,
+    types::{ArrowDictionaryKeyType, ByteArrayType},
+    Array, ArrowNativeTypeOp, ArrowPrimitiveType, DictionaryArray, GenericByteArray,
+    GenericStringArray, OffsetSizeTrait, PrimitiveArray,
+};
+use arrow_buffer::ArrowNativeType;
+use arrow_schema::DataType;
+
+use crate::{columns::partition_point, SharBinarySearch};
+
+/// Where the nulls of a sorted Arrow array are.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NullOrder {
+    /// Nulls come before every value, which is Arrow's default sort order.
+    #[default]
+    First,
+    /// Nulls come after every value.
+    Last,
+}
+
+impl NullOrder {
+    /// Returns the rows holding values, out of `len` rows of which `null_count` are null.
+    fn value_rows(self, len: usize, null_count: usize) -> Range<usize> {
+        match self {
+            NullOrder::First => null_count..len,
+            NullOrder::Last => 0..len - null_count,
+        }
+    }
+}
+
+/// Trait for binary searching sorted Arrow arrays. See the [module documentation](self).
+pub trait SharArrowSearch {
+    /// The type of the values, such as `i64` for an `Int64Array` or `str` for a
+    /// `StringArray`.
+    type Key: ?Sized;
+
+    /// Binary searches this array for `key`, with its nulls (if any) at the end given by
+    /// `nulls`. Note it is assumed that the array is sorted.
+    ///
+    /// If there are multiple matches, the *first* is returned. If there are none, returns `Err`
+    /// with the row where `key` could be inserted to keep the array sorted, which is always
+    /// outside the nulls.
+    fn bl_binary_search_arrow(&self, key: &Self::Key, nulls: NullOrder) -> Result<usize, usize>;
+}
+
+impl<T: ArrowPrimitiveType> SharArrowSearch for PrimitiveArray<T> {
+    type Key = T::Native;
+
+    fn bl_binary_search_arrow(&self, key: &T::Native, nulls: NullOrder) -> Result<usize, usize> {
+        let rows = nulls.value_rows(self.len(), self.null_count());
+        let offset = |index: usize| rows.start + index;
+
+        self.values()[rows.clone()]
+            .bl_binary_search_by(|p| p.compare(*key))
+            .map(offset)
+            .map_err(offset)
+    }
+}
+
+/// Returns the bytes of row `row` of `array`.
+#[inline]
+fn value_bytes<T: ByteArrayType>(array: &GenericByteArray<T>, row: usize) -> &[u8] {
+    let offsets = array.value_offsets();
+    let (start, end) = (offsets[row].as_usize(), offsets[row + 1].as_usize());
+    &array.value_data()[start..end]
+}
+
+/// Binary searches `rows` by `compare`, which returns the ordering of a row relative to the key.
+fn search_rows<F>(rows: Range<usize>, mut compare: F) -> Result<usize, usize>
+where
+    F: FnMut(usize) -> Ordering,
+{
+    let index = rows.start + partition_point(rows.len(), |i| compare(rows.start + i).is_lt());
+
+    if index < rows.end && compare(index).is_eq() {
+        Ok(index)
+    } else {
+        Err(index)
+    }
+}
+
+impl<T: ByteArrayType> SharArrowSearch for GenericByteArray<T> {
+    type Key = T::Native;
+
+    fn bl_binary_search_arrow(&self, key: &T::Native, nulls: NullOrder) -> Result<usize, usize> {
+        let key: &[u8] = key.as_ref();
+        let rows = nulls.value_rows(self.len(), self.null_count());
+
+        search_rows(rows, |row| value_bytes(self, row).cmp(key))
+    }
+}
+
+/// The error returned when searching a dictionary array whose values aren't strings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnsupportedDictionary {
+    value_type: DataType,
+}
+
+impl UnsupportedDictionary {
+    /// Returns the type of the dictionary's values.
+    pub fn value_type(&self) -> &DataType {
+        &self.value_type
+    }
+}
+
+impl fmt::Display for UnsupportedDictionary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot search a dictionary of {} values, only Utf8 and LargeUtf8 ones",
+            self.value_type
+        )
+    }
+}
+
+impl Error for UnsupportedDictionary {}
+
+/// Searches the rows of `array` whose dictionary values are `strings`.
+fn search_dictionary_strings<K, O>(
+    array: &DictionaryArray<K>,
+    strings: &GenericStringArray<O>,
+    key: &str,
+    nulls: NullOrder,
+) -> Result<usize, usize>
+where
+    K: ArrowDictionaryKeyType,
+    O: OffsetSizeTrait,
+{
+    let keys = array.keys().values();
+    let rows = nulls.value_rows(array.len(), array.logical_null_count());
+
+    search_rows(rows, |row| {
+        value_bytes(strings, keys[row].as_usize()).cmp(key.as_bytes())
+    })
+}
+
+/// Binary searches a dictionary-encoded string array for `key` by its decoded values, with its
+/// nulls (if any) at the end given by `nulls`. Note it is assumed that the decoded values are
+/// sorted; the dictionary itself needn't be.
+///
+/// Nulls are counted from both the keys and the dictionary's values, which takes a pass over
+/// the keys if the values have nulls.
+///
+/// # Errors
+///
+/// Returns [`UnsupportedDictionary`] if the dictionary's values aren't `Utf8` or `LargeUtf8`.
+///
+/// ```
+/// use arrow_array::{types::Int8Type, DictionaryArray};
+/// use shar_search::arrow::{bl_binary_search_dictionary, NullOrder};
+///
+/// let levels: DictionaryArray<Int8Type> = vec!["debug", "debug", "info", "warn", "warn"]
+///     .into_iter()
+///     .collect();
+///
+/// let search = |key| bl_binary_search_dictionary(&levels, key, NullOrder::Last).unwrap();
+/// assert_eq!(search("warn"), Ok(3));
+/// assert_eq!(search("error"), Err(2));
+/// ```
+pub fn bl_binary_search_dictionary<K: ArrowDictionaryKeyType>(
+    array: &DictionaryArray<K>,
+    key: &str,
+    nulls: NullOrder,
+) -> Result<Result<usize, usize>, UnsupportedDictionary> {
+    let values = array.values();
+
+    match values.data_type() {
+        DataType::Utf8 => Ok(search_dictionary_strings(
+            array,
+            values.as_string::<i32>(),
+            key,
+            nulls,
+        )),
+        DataType::LargeUtf8 => Ok(search_dictionary_strings(
+            array,
+            values.as_string::<i64>(),
+            key,
+            nulls,
+        )),
+        other => Err(UnsupportedDictionary {
+            value_type: other.clone(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use arrow_array::{
+        types::{Int16Type, Int32Type},
+        BinaryArray, Float64Array, Int32Array, LargeStringArray, StringArray, UInt64Array,
+    };
+
+    use super::*;
+    use crate::test_util::XorShift;
+
+    /// Searches `values`, given as the non-null rows of an array with `nulls` nulls at the end
+    /// given by `order`, the slow way.
+    fn expected<T: Ord + ?Sized>(
+        values: &[&T],
+        key: &T,
+        nulls: usize,
+        order: NullOrder,
+    ) -> Result<usize, usize> {
+        let start = if order == NullOrder::First { nulls } else { 0 };
+        match values.iter().position(|v| *v >= key) {
+            Some(i) if values[i] == key => Ok(start + i),
+            Some(i) => Err(start + i),
+            None => Err(start + values.len()),
+        }
+    }
+
+    /// Returns `values` with `nulls` nulls added at the end given by `order`.
+    fn with_nulls<T: Copy>(values: &[T], nulls: usize, order: NullOrder) -> Vec<Option<T>> {
+        let values = values.iter().copied().map(Some);
+        let nulls = std::iter::repeat_n(None, nulls);
+        match order {
+            NullOrder::First => nulls.chain(values).collect(),
+            NullOrder::Last => values.chain(nulls).collect(),
+        }
+    }
+
+    #[test]
+    fn test_primitive_nulls() {
+        let mut rng = XorShift::new(145);
+
+        for _ in 0..100 {
+            let mut values: Vec<i32> = (0..rng.below(30)).map(|_| rng.below(40) as i32).collect();
+            values.sort_unstable();
+            let nulls = rng.below(4) as usize;
+
+            for order in [NullOrder::First, NullOrder::Last] {
+                let array = Int32Array::from(with_nulls(&values, nulls, order));
+                let refs: Vec<&i32> = values.iter().collect();
+
+                for key in -1..42 {
+                    assert_eq!(
+                        array.bl_binary_search_arrow(&key, order),
+                        expected(&refs, &key, nulls, order)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_strings() {
+        let words = ["", "a", "ab", "ab", "b", "ba", "\u{e9}t\u{e9}"];
+        let keys = [
+            "",
+            "a",
+            "aa",
+            "ab",
+            "abc",
+            "b",
+            "c",
+            "\u{e9}t\u{e9}",
+            "\u{ff}",
+        ];
+
+        for nulls in 0..3 {
+            for order in [NullOrder::First, NullOrder::Last] {
+                let rows = with_nulls(&words, nulls, order);
+                let utf8 = StringArray::from(rows.clone());
+                let large = LargeStringArray::from(rows.clone());
+                let binary: BinaryArray = rows.iter().map(|r| r.map(str::as_bytes)).collect();
+                let refs: Vec<&str> = words.to_vec();
+
+                for key in keys {
+                    let expected = expected(&refs, key, nulls, order);
+                    assert_eq!(utf8.bl_binary_search_arrow(key, order), expected);
+                    assert_eq!(large.bl_binary_search_arrow(key, order), expected);
+                    assert_eq!(
+                        binary.bl_binary_search_arrow(key.as_bytes(), order),
+                        expected
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sliced() {
+        let ints = UInt64Array::from(vec![Some(1), Some(4), Some(4), Some(9), Some(12), None]);
+        let tail = ints.slice(2, 4);
+        assert_eq!(tail.bl_binary_search_arrow(&4, NullOrder::Last), Ok(0));
+        assert_eq!(tail.bl_binary_search_arrow(&10, NullOrder::Last), Err(2));
+        assert_eq!(tail.bl_binary_search_arrow(&13, NullOrder::Last), Err(3));
+        let head = ints.slice(0, 3);
+        assert_eq!(head.bl_binary_search_arrow(&9, NullOrder::First), Err(3));
+
+        let strings = StringArray::from(vec![None, Some("fig"), Some("kiwi"), Some("lime")]);
+        let sliced = strings.slice(1, 3);
+        assert_eq!(
+            sliced.bl_binary_search_arrow("kiwi", NullOrder::First),
+            Ok(1)
+        );
+        assert_eq!(
+            sliced.bl_binary_search_arrow("apple", NullOrder::First),
+            Err(0)
+        );
+        assert_eq!(
+            strings.bl_binary_search_arrow("apple", NullOrder::First),
+            Err(1)
+        );
+    }
+
+    #[test]
+    fn test_float_total_order() {
+        let floats = Float64Array::from(vec![
+            f64::NEG_INFINITY,
+            -0.0,
+            0.0,
+            2.5,
+            f64::INFINITY,
+            f64::NAN,
+        ]);
+        assert_eq!(floats.bl_binary_search_arrow(&-0.0, NullOrder::Last), Ok(1));
+        assert_eq!(floats.bl_binary_search_arrow(&0.0, NullOrder::Last), Ok(2));
+        assert_eq!(
+            floats.bl_binary_search_arrow(&f64::NAN, NullOrder::Last),
+            Ok(5)
+        );
+        assert_eq!(floats.bl_binary_search_arrow(&1.0, NullOrder::Last), Err(3));
+    }
+
+    #[test]
+    fn test_dictionaries() {
+        let rows = [None, Some("b"), Some("b"), Some("d"), Some("f"), Some("f")];
+        let array: DictionaryArray<Int16Type> = rows.into_iter().collect();
+
+        for (key, result) in [
+            ("a", Err(1)),
+            ("b", Ok(1)),
+            ("c", Err(3)),
+            ("f", Ok(4)),
+            ("g", Err(6)),
+        ] {
+            assert_eq!(
+                bl_binary_search_dictionary(&array, key, NullOrder::First),
+                Ok(result)
+            );
+        }
+
+        let sliced = array.slice(2, 3);
+        assert_eq!(
+            bl_binary_search_dictionary(&sliced, "d", NullOrder::First),
+            Ok(Ok(1))
+        );
+        assert_eq!(
+            bl_binary_search_dictionary(&sliced, "e", NullOrder::First),
+            Ok(Err(2))
+        );
+
+        // A null in the dictionary's values, rather than its keys, is a null row too.
+        let values = Arc::new(LargeStringArray::from(vec![Some("z"), Some("m"), None]));
+        let keys = Int32Array::from(vec![1, 0, 2, 2]);
+        let array = DictionaryArray::<Int32Type>::try_new(keys, values).unwrap();
+        assert_eq!(
+            bl_binary_search_dictionary(&array, "z", NullOrder::Last),
+            Ok(Ok(1))
+        );
+        assert_eq!(
+            bl_binary_search_dictionary(&array, "zz", NullOrder::Last),
+            Ok(Err(2))
+        );
+    }
+
+    #[test]
+    fn test_unsupported_dictionary() {
+        let values = Arc::new(Int32Array::from(vec![10, 20]));
+        let array = DictionaryArray::<Int16Type>::try_new(vec![0_i16, 1].into(), values).unwrap();
+
+        let err = bl_binary_search_dictionary(&array, "10", NullOrder::First).unwrap_err();
+        assert_eq!(err.value_type(), &DataType::Int32);
+        assert_eq!(
+            err.to_string(),
+            "cannot search a dictionary of Int32 values, only Utf8 and LargeUtf8 ones"
+        );
+    }
+}
@@ -0,0 +1,484 @@
+//! Searches of sorted fixed-size records read asynchronously with tokio, for records on slow
+//! storage that shouldn't block a runtime thread for each probe.
+//!
+//! [`AsyncFileSearcher`] mirrors [`FileSearcher`](crate::io::FileSearcher), with the same
+//! record layout, [`KeyCodec`]s, and errors, over a reader implementing tokio's [`AsyncRead`]
+//! and [`AsyncSeek`]. [`search_many`](AsyncFileSearcher::search_many) searches for several
+//! keys at once, reading each record that more than one of their descents passes through
+//! only once.
+//!
+//! # Cancellation
+//!
+//! Every method is cancellation safe in the sense that dropping its future, such as when a
+//! [`timeout`](https://docs.rs/tokio/latest/tokio/time/fn.timeout.html) elapses, leaves the
+//! searcher usable. A dropped probe may leave a seek or read in flight, so each probe first
+//! waits for any such operation with [`poll_complete`](AsyncSeek::poll_complete), discarding
+//! its outcome, even an error, and then seeks to its record from the start of the reader, so
+//! nothing depends on where the reader was left. The dropped call's result is lost, of course.
+//!
+//! ```
+//! use std::io::Cursor;
+//!
+//! use shar_search::{async_io::AsyncFileSearcher, io::KeyCodec};
+//!
+//! # tokio::runtime::Builder::new_current_thread().build()?.block_on(async {
+//! // Records of a little-endian `u64` key followed by a one-byte tag.
+//! let mut bytes = Vec::new();
+//! for (key, tag) in [(10_u64, b'a'), (20, b'b'), (30, b'c')] {
+//!     bytes.extend_from_slice(&key.to_le_bytes());
+//!     bytes.push(tag);
+//! }
+//! let mut searcher = AsyncFileSearcher::new(Cursor::new(bytes), 9, KeyCodec::u64_le(0)).await?;
+//!
+//! assert_eq!(searcher.search(&20).await?, Ok(1));
+//! assert_eq!(searcher.search_many(&[35, 10, 15]).await?, [Err(3), Ok(0), Err(1)]);
+//! assert_eq!(searcher.read_record(2).await?[8], b'c');
+//! # Ok::<(), std::io::Error>(())
+//! # })?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::{
+    cmp::Ordering,
+    future::poll_fn,
+    io::{self, SeekFrom},
+    pin::Pin,
+    vec,
+    vec::Vec,
+};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+use crate::{
+    io::{check_index, check_layout, record_count, KeyCodec},
+    SharBinarySearch,
+};
+
+/// Searches an asynchronous reader of fixed-size records sorted by key. See the
+/// [module documentation](self).
+#[derive(Debug)]
+pub struct AsyncFileSearcher<R, K> {
+    reader: R,
+    record_size: usize,
+    len: u64,
+    codec: KeyCodec<K>,
+    buffer: Vec<u8>,
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin, K> AsyncFileSearcher<R, K> {
+    /// Creates a searcher over the records of `record_size` bytes in `reader`, from its start
+    /// to its end, with keys decoded by `codec`. Note it is assumed that the records are
+    /// sorted by key.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`FileSearcher::new`](crate::io::FileSearcher::new).
+    pub async fn new(mut reader: R, record_size: usize, codec: KeyCodec<K>) -> io::Result<Self> {
+        check_layout(record_size, &codec)?;
+        let len = record_count(reader.seek(SeekFrom::End(0)).await?, record_size)?;
+
+        Ok(Self {
+            reader,
+            record_size,
+            len,
+            codec,
+            buffer: vec![0; record_size],
+        })
+    }
+
+    /// Returns the number of records.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns whether there are no records.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the size of each record, in bytes.
+    pub fn record_size(&self) -> usize {
+        self.record_size
+    }
+
+    /// Returns the reader, positioned wherever the last read left it.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads `len` bytes from `offset` in record `index` into the start of the buffer.
+    async fn read_at(&mut self, index: u64, offset: usize, len: usize) -> io::Result<&[u8]> {
+        // A dropped probe may have left an operation in flight. `seek` would wait for it too,
+        // but fail if it failed, when what it did doesn't matter, as the seek is from the start.
+        poll_fn(|cx| Pin::new(&mut self.reader).poll_complete(cx))
+            .await
+            .ok();
+
+        let position = index * self.record_size as u64 + offset as u64;
+        self.reader.seek(SeekFrom::Start(position)).await?;
+        let bytes = &mut self.buffer[..len];
+        self.reader.read_exact(bytes).await?;
+        Ok(bytes)
+    }
+
+    /// Reads record `index`, into a buffer that the next read reuses.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as
+    /// [`FileSearcher::read_record`](crate::io::FileSearcher::read_record).
+    pub async fn read_record(&mut self, index: u64) -> io::Result<&[u8]> {
+        check_index(index, self.len)?;
+        self.read_at(index, 0, self.record_size).await
+    }
+
+    /// Reads the key of record `index`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`read_record`](Self::read_record).
+    pub async fn read_key(&mut self, index: u64) -> io::Result<K> {
+        check_index(index, self.len)?;
+        self.key_at(index).await
+    }
+
+    async fn key_at(&mut self, index: u64) -> io::Result<K> {
+        let (offset, width) = (self.codec.offset(), self.codec.width());
+        let decode = self.codec.decoder();
+        self.read_at(index, offset, width).await.map(decode)
+    }
+
+    /// Binary searches the records for `key`, as
+    /// [`FileSearcher::search`](crate::io::FileSearcher::search) does.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from seeking or reading, in which case the search is abandoned and the
+    /// searcher can still be used.
+    pub async fn search(&mut self, key: &K) -> io::Result<Result<u64, u64>>
+    where
+        K: Ord,
+    {
+        // The first index whose key isn't less than `key` is in `low..=high`, and `found` is
+        // whether the key at `high` equals it.
+        let (mut low, mut high, mut found) = (0, self.len, false);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match self.key_at(mid).await?.cmp(key) {
+                Ordering::Less => low = mid + 1,
+                ordering => (high, found) = (mid, ordering.is_eq()),
+            }
+        }
+
+        Ok(if found { Ok(low) } else { Err(low) })
+    }
+
+    /// Binary searches the records for each of `keys`, returning the results in the same
+    /// order as [`search`](Self::search) would.
+    ///
+    /// The keys descend together: each record probed is compared to every key whose search
+    /// would probe it, and the keys split into those going left and right of it. Keys that are
+    /// close together share most of their probes, so `k` keys read far fewer than
+    /// `k * log₂ n` records when they are clustered, and never more.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from seeking or reading, in which case the results for every key are
+    /// lost and the searcher can still be used.
+    pub async fn search_many(&mut self, keys: &[K]) -> io::Result<Vec<Result<u64, u64>>>
+    where
+        K: Ord,
+    {
+        let mut order: Vec<usize> = (0..keys.len()).collect();
+        order.sort_by(|&a, &b| keys[a].cmp(&keys[b]));
+        let mut found = vec![false; keys.len()];
+        let mut results = vec![Err(0); keys.len()];
+
+        // Each window of the descent, with the sorted keys in it, as `order[start..end]`.
+        let mut windows = vec![(0, self.len, 0, keys.len())];
+        while let Some((low, high, start, end)) = windows.pop() {
+            if start == end {
+                continue;
+            }
+            if low == high {
+                for &i in &order[start..end] {
+                    results[i] = if found[i] { Ok(low) } else { Err(low) };
+                }
+                continue;
+            }
+
+            let mid = low + (high - low) / 2;
+            let probe = self.key_at(mid).await?;
+            // Keys up to the probe continue to its left, and the rest to its right.
+            let split = start + order[start..end].bl_partition_point(|&i| keys[i] <= probe);
+            for &i in &order[start..split] {
+                found[i] = keys[i] == probe;
+            }
+            windows.push((mid + 1, high, split, end));
+            windows.push((low, mid, start, split));
+        }
+
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        future::Future,
+        io::{self, Cursor, ErrorKind, SeekFrom},
+        pin::{pin, Pin},
+        task::{ready, Context, Poll, Waker},
+        time::Duration,
+        vec::Vec,
+    };
+
+    use tokio::{
+        fs::File,
+        io::{AsyncRead, AsyncSeek, ReadBuf},
+        runtime::Runtime,
+        time::timeout,
+    };
+
+    use super::AsyncFileSearcher;
+    use crate::{
+        io::{FileSearcher, KeyCodec},
+        test_util::TempFile,
+        SharBinarySearch,
+    };
+
+    fn runtime() -> Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+    }
+
+    /// Polls `future` up to `polls` times and then drops it, as a timeout that elapses part
+    /// way through would, returning whether it was still pending.
+    fn dropped_while_pending(future: impl Future, polls: usize) -> bool {
+        let mut context = Context::from_waker(Waker::noop());
+        let mut future = pin!(future);
+        (0..polls).all(|_| future.as_mut().poll(&mut context).is_pending())
+    }
+
+    fn records(keys: &[u64]) -> Vec<u8> {
+        keys.iter().flat_map(|key| key.to_be_bytes()).collect()
+    }
+
+    #[test]
+    fn test_matches_sync_searcher() {
+        let keys: Vec<u64> = (0..3000).map(|i| i / 4 * 5).collect();
+        let bytes = records(&keys);
+        let file = TempFile::new("async_matches_sync", &bytes);
+        let mut sync = FileSearcher::new(Cursor::new(&bytes), 8, KeyCodec::u64_be(0)).unwrap();
+        let queries: Vec<u64> = (0..keys[2999] + 3).collect();
+
+        runtime().block_on(async {
+            let file = File::open(file.path()).await.unwrap();
+            let mut searcher = AsyncFileSearcher::new(file, 8, KeyCodec::u64_be(0))
+                .await
+                .unwrap();
+            assert_eq!(searcher.len(), 3000);
+
+            let many = searcher.search_many(&queries).await.unwrap();
+            for (x, many) in queries.iter().zip(many) {
+                let expected = sync.search(x).unwrap();
+                assert_eq!(searcher.search(x).await.unwrap(), expected);
+                assert_eq!(many, expected, "key {x}");
+            }
+
+            assert_eq!(searcher.read_record(7).await.unwrap(), &bytes[56..64]);
+            assert_eq!(searcher.read_key(2999).await.unwrap(), keys[2999]);
+            let error = searcher.read_key(3000).await.unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        });
+    }
+
+    /// A cursor that counts its seeks, which is one for each record read.
+    struct Counting(Cursor<Vec<u8>>, usize);
+
+    impl AsyncRead for Counting {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.0).poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncSeek for Counting {
+        fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+            self.1 += 1;
+            Pin::new(&mut self.0).start_seek(position)
+        }
+
+        fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+            Pin::new(&mut self.0).poll_complete(cx)
+        }
+    }
+
+    #[test]
+    fn test_search_many_shares_probes() {
+        let keys: Vec<u64> = (0..1 << 12).collect();
+
+        runtime().block_on(async {
+            let reader = Counting(Cursor::new(records(&keys)), 0);
+            let mut searcher = AsyncFileSearcher::new(reader, 8, KeyCodec::u64_be(0))
+                .await
+                .unwrap();
+
+            // Sixteen neighbouring keys share all but the last few of their twelve probes.
+            let queries: Vec<u64> = (0..16).map(|i| 2000 + i).rev().collect();
+            let results = searcher.search_many(&queries).await.unwrap();
+            assert!(results.iter().zip(&queries).all(|(r, x)| *r == Ok(*x)));
+            // One seek measured the length.
+            let reads = searcher.into_inner().1 - 1;
+            assert!(reads < 16 * 3, "{reads} reads");
+        });
+    }
+
+    #[test]
+    fn test_empty_and_duplicates() {
+        runtime().block_on(async {
+            let mut empty = AsyncFileSearcher::new(Cursor::new([]), 8, KeyCodec::u64_be(0))
+                .await
+                .unwrap();
+            assert!(empty.is_empty());
+            assert_eq!(empty.search(&1).await.unwrap(), Err(0));
+            assert_eq!(empty.search_many(&[1, 0]).await.unwrap(), [Err(0), Err(0)]);
+
+            let bytes = records(&[2, 2, 2, 4, 4]);
+            let mut searcher = AsyncFileSearcher::new(Cursor::new(bytes), 8, KeyCodec::u64_be(0))
+                .await
+                .unwrap();
+            let results = searcher.search_many(&[4, 2, 3, 4, 5, 1]).await.unwrap();
+            assert_eq!(results, [Ok(3), Ok(0), Err(3), Ok(3), Err(5), Err(0)]);
+            assert!(searcher.search_many(&[]).await.unwrap().is_empty());
+
+            let partial = AsyncFileSearcher::new(Cursor::new([0; 12]), 8, KeyCodec::u64_be(0));
+            assert_eq!(partial.await.unwrap_err().kind(), ErrorKind::InvalidData);
+        });
+    }
+
+    /// A cursor whose every seek and read is pending once before it completes, and which,
+    /// like tokio's `File`, refuses to start a seek while another is in flight.
+    struct Stalling {
+        inner: Cursor<Vec<u8>>,
+        seeking: bool,
+        stalled: bool,
+    }
+
+    impl Stalling {
+        /// Returns pending the first time it is called for an operation, and ready after.
+        fn stall(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+            self.stalled = !self.stalled;
+            if self.stalled {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            } else {
+                Poll::Ready(())
+            }
+        }
+    }
+
+    impl AsyncRead for Stalling {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            ready!(self.stall(cx));
+            Pin::new(&mut self.inner).poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncSeek for Stalling {
+        fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+            if self.seeking {
+                return Err(io::Error::other("a seek is in flight"));
+            }
+            self.seeking = true;
+            Pin::new(&mut self.inner).start_seek(position)
+        }
+
+        fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+            if self.seeking {
+                ready!(self.stall(cx));
+                self.seeking = false;
+            }
+            Pin::new(&mut self.inner).poll_complete(cx)
+        }
+    }
+
+    #[test]
+    fn test_cancellation() {
+        let keys: Vec<u64> = (0..1000).map(|i| i * 3).collect();
+        let reader = Stalling {
+            inner: Cursor::new(records(&keys)),
+            seeking: false,
+            stalled: false,
+        };
+
+        runtime().block_on(async {
+            let mut searcher = AsyncFileSearcher::new(reader, 8, KeyCodec::u64_be(0))
+                .await
+                .unwrap();
+
+            // Drop each call at every await in turn, with a seek or a read in flight. Every
+            // call after it must still succeed.
+            for x in [0_u64, 1, 1500, 2997, 4000] {
+                let expected = keys.bl_binary_search(&x);
+                let expected = expected.map(|i| i as u64).map_err(|i| i as u64);
+                for polls in 1.. {
+                    if !dropped_while_pending(searcher.search(&x), polls) {
+                        break;
+                    }
+                    assert_eq!(searcher.search(&x).await.unwrap(), expected, "key {x}");
+                }
+                for polls in 1.. {
+                    if !dropped_while_pending(searcher.search_many(&[x, x / 2]), polls) {
+                        break;
+                    }
+                    assert_eq!(searcher.read_key(4).await.unwrap(), 12);
+                }
+                for polls in 1.. {
+                    if !dropped_while_pending(searcher.read_record(999), polls) {
+                        break;
+                    }
+                    assert_eq!(searcher.search(&x).await.unwrap(), expected, "key {x}");
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn test_cancellation_with_file() {
+        let keys: Vec<u64> = (0..5000).map(|i| i * 3).collect();
+        let file = TempFile::new("async_cancellation", &records(&keys));
+
+        runtime().block_on(async {
+            let file = File::open(file.path()).await.unwrap();
+            let mut searcher = AsyncFileSearcher::new(file, 8, KeyCodec::u64_be(0))
+                .await
+                .unwrap();
+
+            // Whether each seek or read on the blocking thread has finished by then is a race,
+            // so these drop calls part way through without checking where.
+            for x in [0, 2999, 3000, 14_997, 20_000] {
+                dropped_while_pending(searcher.search(&x), 1);
+                let result = searcher.search(&x).await.unwrap();
+                assert_eq!(result.is_ok(), x % 3 == 0 && x < 15_000, "key {x}");
+            }
+            for micros in [0, 50, 200, 1000] {
+                let limit = Duration::from_micros(micros);
+                timeout(limit, searcher.search_many(&[1, 2, 3, 4500, 9000]))
+                    .await
+                    .ok();
+                let results = searcher.search_many(&[3, 4500]).await.unwrap();
+                assert_eq!(results, [Ok(1), Ok(1500)]);
+            }
+        });
+    }
+}
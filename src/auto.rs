@@ -0,0 +1,247 @@
+//! Choosing a search algorithm by the size of the slice, for
+//! [`bl_search_auto`](crate::SharBinarySearch::bl_search_auto).
+//!
+//! The branchless search is not the fastest at every size. On a handful of elements, a linear
+//! scan does less work than setting up a binary search. Once the slice no longer fits in the
+//! cache, each probe is a cache miss, and a search with branches wins: the CPU speculates past
+//! each comparison and starts loading a likely next probe before the current one arrives, while
+//! the branchless search must wait for each load to finish. [`SearchTuning`] holds the two
+//! thresholds between these [`SearchStrategy`]s.
+//!
+//! Whichever strategy is used, the result is the same as
+//! [`bl_binary_search`](crate::SharBinarySearch::bl_binary_search), including returning the
+//! *first* of several matches, so the thresholds only affect performance.
+//!
+//! ```
+//! use shar_search::{
+//!     auto::{SearchStrategy, SearchTuning},
+//!     SharBinarySearch,
+//! };
+//!
+//! let slice: Vec<u32> = (0..1000).map(|i| i * 2).collect();
+//! assert_eq!(slice.bl_search_auto(&500), Ok(250));
+//!
+//! let tuning = SearchTuning::default();
+//! assert_eq!(tuning.strategy::<u32>(4), SearchStrategy::Linear);
+//! assert_eq!(tuning.strategy::<u32>(slice.len()), SearchStrategy::Branchless);
+//!
+//! // Use the early-exit search for slices over 256 MiB.
+//! let tuning = SearchTuning {
+//!     early_exit_above_bytes: 256 << 20,
+//!     ..SearchTuning::DEFAULT
+//! };
+//! assert_eq!(tuning.strategy::<u32>(1 << 28), SearchStrategy::EarlyExit);
+//! assert_eq!(slice.bl_search_auto_with(&500, &tuning), Ok(250));
+//! ```
+
+use core::{cmp::Ordering, mem};
+
+use crate::SharBinarySearch;
+
+/// An algorithm that [`bl_search_auto`](crate::SharBinarySearch::bl_search_auto) may use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SearchStrategy {
+    /// Scanning from the start for the first element not less than the key.
+    Linear,
+    /// Shar's branchless binary search, as in
+    /// [`bl_binary_search`](crate::SharBinarySearch::bl_binary_search).
+    Branchless,
+    /// A binary search with branches that stops as soon as it finds a match, as
+    /// [`slice::binary_search`] was before Rust 1.83, followed by a search for the first match if
+    /// the one found has an equal predecessor.
+    EarlyExit,
+}
+
+/// The thresholds that decide which [`SearchStrategy`] to use for a slice.
+///
+/// The defaults come from the `auto` benchmark, run on an x86-64 virtual machine with 48 KiB of
+/// L1, 2 MiB of L2 and 105 MiB of L3 cache, searching for 1000 random keys. The time per search:
+///
+/// | Elements | Linear | Branchless | Early exit |
+/// |---|---|---|---|
+/// | 4 `u32` | 2.8 ns | 4.9 ns | 4.8 ns |
+/// | 8 `u32` | 3.8 ns | 5.9 ns | 5.8 ns |
+/// | 16 `u32` | 5.8 ns | 18 ns[^noise] | 7.0 ns |
+/// | 24 `u32` | 7.6 ns | 8.5 ns | 9.1 ns |
+/// | 32 `u32` | 11 ns | 9.0 ns | 8.6 ns |
+/// | 64 `u32` | 33 ns | 14 ns | 13 ns |
+/// | 8 `u64` | 7.4 ns | 8.8 ns | 7.5 ns |
+/// | 12 `u64` | 9.3 ns | 10 ns | 8.3 ns |
+/// | 64 Ki `u32` (256 KiB) | | 108 ns | 91 ns |
+/// | 1 Mi `u32` (4 MiB) | | 228 ns | 210 ns |
+/// | 4 Mi `u32` (16 MiB) | | 370 ns | 418 ns |
+/// | 16 Mi `u32` (64 MiB) | | 501 ns | 636 ns |
+/// | 16 Mi `u64` (128 MiB) | | 586 ns | 676 ns |
+///
+/// [^noise]: An earlier run measured 12 ns. The machine is shared, so single results are noisy.
+///
+/// A linear scan wins up to about 16 elements, hence [`linear_below`](Self::linear_below). Above
+/// that, the two binary searches are within noise of each other until the slice outgrows L2,
+/// and from there the branchless search is ahead, even at 128 MiB, past this machine's L3. So
+/// the early-exit search is off by default, with
+/// [`early_exit_above_bytes`](Self::early_exit_above_bytes) at `usize::MAX`. It is worth trying
+/// on machines that speculate further ahead, or whose memory is slower relative to the CPU.
+///
+/// Other machines will differ, particularly in cache sizes, so override the defaults if a
+/// benchmark of your own data says so.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SearchTuning {
+    /// Slices with fewer elements than this are scanned linearly.
+    pub linear_below: usize,
+    /// Slices taking more bytes than this use the early-exit search; other slices use the
+    /// branchless search.
+    pub early_exit_above_bytes: usize,
+}
+
+impl SearchTuning {
+    /// The default thresholds. See the [type documentation](SearchTuning).
+    pub const DEFAULT: Self = Self {
+        linear_below: 16,
+        early_exit_above_bytes: usize::MAX,
+    };
+
+    /// Returns the strategy these thresholds choose for a slice of `len` elements of type `T`.
+    pub const fn strategy<T>(&self, len: usize) -> SearchStrategy {
+        if len < self.linear_below {
+            SearchStrategy::Linear
+        } else if len.saturating_mul(mem::size_of::<T>()) > self.early_exit_above_bytes {
+            SearchStrategy::EarlyExit
+        } else {
+            SearchStrategy::Branchless
+        }
+    }
+}
+
+impl Default for SearchTuning {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Searches `slice` for `x` with the strategy `tuning` chooses.
+#[inline]
+pub(crate) fn search<T: Ord>(slice: &[T], x: &T, tuning: &SearchTuning) -> Result<usize, usize> {
+    match tuning.strategy::<T>(slice.len()) {
+        SearchStrategy::Linear => {
+            let index = slice.iter().position(|p| p >= x).unwrap_or(slice.len());
+            match slice.get(index) {
+                Some(p) if p == x => Ok(index),
+                _ => Err(index),
+            }
+        }
+        SearchStrategy::Branchless => slice.bl_binary_search(x),
+        SearchStrategy::EarlyExit => early_exit_search(slice, x),
+    }
+}
+
+/// A binary search with branches, returning as soon as it finds a match.
+///
+/// `slice::binary_search` is no longer this: since Rust 1.83 it is branchless as well.
+#[inline]
+fn early_exit_search<T: Ord>(slice: &[T], x: &T) -> Result<usize, usize> {
+    let (mut low, mut high) = (0, slice.len());
+    while low < high {
+        let mid = low + (high - low) / 2;
+        match slice[mid].cmp(x) {
+            Ordering::Less => low = mid + 1,
+            Ordering::Greater => high = mid,
+            // Most sorted slices searched by key have no duplicates, so this is rare.
+            Ordering::Equal if mid > low && slice[mid - 1] == *x => {
+                return Ok(low + slice[low..mid].partition_point(|p| p < x));
+            }
+            Ordering::Equal => return Ok(mid),
+        }
+    }
+    Err(low)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SearchStrategy, SearchTuning};
+    use crate::{
+        reference::{self, assert_matches_reference, inputs},
+        test_util::XorShift,
+        SharBinarySearch,
+    };
+
+    #[test]
+    fn test_strategies_agree() {
+        let tunings = [
+            SearchTuning::DEFAULT,
+            SearchTuning {
+                linear_below: usize::MAX,
+                early_exit_above_bytes: 0,
+            },
+            SearchTuning {
+                linear_below: 0,
+                early_exit_above_bytes: usize::MAX,
+            },
+            SearchTuning {
+                linear_below: 0,
+                early_exit_above_bytes: 0,
+            },
+        ];
+
+        // Every length around the default thresholds, with duplicates.
+        let mut rng = XorShift::new(171);
+        let slices = (0..40).flat_map(|len| {
+            let mut slice: Vec<u16> = (0..len).map(|_| rng.below(30) as u16).collect();
+            slice.sort_unstable();
+            (0..32).map(move |x| (slice.clone(), x))
+        });
+        assert_matches_reference!(
+            for (slice, x) in slices =>
+            tunings.map(|tuning| slice.bl_search_auto_with(&x, &tuning)),
+            [reference::binary_search(&slice, &x); 4],
+        );
+        assert_matches_reference!(
+            for (slice, x) in inputs::slices_with_keys(171) =>
+            slice.bl_search_auto(&x),
+            reference::binary_search(&slice, &x),
+        );
+    }
+
+    #[test]
+    fn test_early_exit_finds_first_match() {
+        let slice = [1, 2, 2, 2, 2, 2, 2, 3];
+        let tuning = SearchTuning {
+            linear_below: 0,
+            early_exit_above_bytes: 0,
+        };
+        assert_eq!(
+            tuning.strategy::<i32>(slice.len()),
+            SearchStrategy::EarlyExit
+        );
+        assert_eq!(slice.bl_search_auto_with(&2, &tuning), Ok(1));
+        assert_eq!(slice.bl_search_auto_with(&0, &tuning), Err(0));
+        assert_eq!(slice.bl_search_auto_with(&4, &tuning), Err(8));
+    }
+
+    #[test]
+    fn test_thresholds() {
+        let tuning = SearchTuning::DEFAULT;
+        assert_eq!(tuning.strategy::<u64>(0), SearchStrategy::Linear);
+        assert_eq!(tuning.strategy::<u64>(15), SearchStrategy::Linear);
+        assert_eq!(tuning.strategy::<u64>(16), SearchStrategy::Branchless);
+        assert_eq!(
+            tuning.strategy::<u8>(usize::MAX),
+            SearchStrategy::Branchless
+        );
+
+        let tuning = SearchTuning {
+            early_exit_above_bytes: 8 << 20,
+            ..SearchTuning::DEFAULT
+        };
+        assert_eq!(tuning.strategy::<u64>(1 << 20), SearchStrategy::Branchless);
+        assert_eq!(
+            tuning.strategy::<u64>((1 << 20) + 1),
+            SearchStrategy::EarlyExit
+        );
+        assert_eq!(tuning.strategy::<u8>(usize::MAX), SearchStrategy::EarlyExit);
+        // Zero-sized elements never take any bytes.
+        assert_eq!(
+            tuning.strategy::<()>(usize::MAX),
+            SearchStrategy::Branchless
+        );
+    }
+}
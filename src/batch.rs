@@ -0,0 +1,628 @@
+//! Searching for many keys at once, with the searches interleaved so their memory accesses
+//! overlap.
+//!
+//! A single binary search over a large slice spends most of its time waiting on cache misses,
+//! since each probe depends on the previous one. Independent searches have no such dependency,
+//! so the batch searches here run a group of them side by side, advancing every search in the
+//! group by one level per round. The loads of a round can then be in flight at the same time.
+//!
+//! When the keys are themselves sorted, each answer is at least the previous one, so the
+//! `sorted_keys` searches instead gallop forward from the previous answer.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use core::borrow::Borrow;
+use core::{cmp::Ordering, error::Error, fmt, mem::MaybeUninit};
+
+#[cfg(feature = "alloc")]
+use crate::{gallop::gallop, stream::SearchStream};
+
+/// The number of searches interleaved by [`SharBatchSearch::bl_binary_search_batch`] and
+/// [`SharBatchSearch::bl_binary_search_batch_by_key`].
+pub const DEFAULT_INTERLEAVE: usize = 8;
+
+/// The error returned when a batch search's output buffer does not match the number of keys.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BatchSizeMismatch {
+    pub(crate) keys: usize,
+    pub(crate) out: usize,
+}
+
+impl BatchSizeMismatch {
+    /// Returns the number of searches: the number of keys, or of slices for
+    /// [`multi::search_in_each_into`](crate::multi::search_in_each_into).
+    pub fn keys(&self) -> usize {
+        self.keys
+    }
+
+    /// Returns the length of the output buffer.
+    pub fn out(&self) -> usize {
+        self.out
+    }
+}
+
+impl fmt::Display for BatchSizeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "output buffer of length {} cannot hold results for {} keys",
+            self.out, self.keys
+        )
+    }
+}
+
+impl Error for BatchSizeMismatch {}
+
+/// Trait for searching a sorted slice for many keys at once.
+///
+/// The results are identical to calling [`bl_binary_search_by`](crate::SharBinarySearch) once
+/// per key: if there are multiple matches, the *first* is returned.
+///
+/// Besides the allocating methods, which need the `alloc` feature, there are `_into` methods
+/// that write into an initialized buffer of exactly one result per key, and `_uninit` methods
+/// that write into the start of a possibly longer uninitialized buffer and return the
+/// initialized prefix. Neither allocates.
+pub trait SharBatchSearch<T> {
+    /// Binary searches this slice for each of `keys` with a comparator function, interleaving
+    /// `W` searches at a time, and writes the results to the start of `out`. `compare(element,
+    /// key)` returns the ordering of an element relative to a key. Note it is assumed that the
+    /// slice is sorted.
+    ///
+    /// Returns the prefix of `out` holding the results, in the same order as `keys`. The rest
+    /// of `out` is left untouched.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BatchSizeMismatch`] without writing anything if `out` is shorter than `keys`.
+    ///
+    /// If `compare` panics, some elements of `out` may have been written, but no initialized
+    /// prefix is returned.
+    fn bl_binary_search_batch_by_uninit<'o, const W: usize, Q, F>(
+        &self,
+        keys: &[Q],
+        out: &'o mut [MaybeUninit<Result<usize, usize>>],
+        compare: F,
+    ) -> Result<&'o mut [Result<usize, usize>], BatchSizeMismatch>
+    where
+        F: FnMut(&T, &Q) -> Ordering;
+
+    /// Binary searches this slice for each of `keys` with a comparator function, interleaving
+    /// `W` searches at a time. `compare(element, key)` returns the ordering of an element
+    /// relative to a key. Note it is assumed that the slice is sorted.
+    ///
+    /// The results are returned in the same order as `keys`.
+    ///
+    /// ```
+    /// use shar_search::SharBatchSearch;
+    ///
+    /// let slice = [1, 3, 3, 5, 8];
+    /// let results = slice.bl_binary_search_batch_by::<4, _, _>(&[3, 4, 8], |p, k| p.cmp(k));
+    /// assert_eq!(results, [Ok(1), Err(3), Ok(4)]);
+    /// ```
+    #[cfg(feature = "alloc")]
+    fn bl_binary_search_batch_by<const W: usize, Q, F>(
+        &self,
+        keys: &[Q],
+        compare: F,
+    ) -> Vec<Result<usize, usize>>
+    where
+        F: FnMut(&T, &Q) -> Ordering,
+    {
+        let mut results = Vec::with_capacity(keys.len());
+        let written = match self.bl_binary_search_batch_by_uninit::<W, _, _>(
+            keys,
+            results.spare_capacity_mut(),
+            compare,
+        ) {
+            Ok(written) => written.len(),
+            Err(_) => unreachable!("the capacity is at least the number of keys"),
+        };
+
+        // The first `written` elements of the spare capacity were just initialized.
+        unsafe { results.set_len(written) };
+        results
+    }
+
+    /// Binary searches this slice for each of `keys` with a comparator function, interleaving
+    /// `W` searches at a time, and writes the results to `out` in the same order as `keys`.
+    /// Note it is assumed that the slice is sorted.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BatchSizeMismatch`] without writing anything if `out` and `keys` have
+    /// different lengths.
+    fn bl_binary_search_batch_by_into<const W: usize, Q, F>(
+        &self,
+        keys: &[Q],
+        out: &mut [Result<usize, usize>],
+        compare: F,
+    ) -> Result<(), BatchSizeMismatch>
+    where
+        F: FnMut(&T, &Q) -> Ordering,
+    {
+        if out.len() != keys.len() {
+            return Err(BatchSizeMismatch {
+                keys: keys.len(),
+                out: out.len(),
+            });
+        }
+
+        // An initialized slice can be viewed as a `MaybeUninit` one, as long as only
+        // initialized values are written through it, which the search guarantees.
+        let out = unsafe {
+            &mut *(out as *mut [Result<usize, usize>] as *mut [MaybeUninit<Result<usize, usize>>])
+        };
+        self.bl_binary_search_batch_by_uninit::<W, _, _>(keys, out, compare)
+            .map(|_| ())
+    }
+
+    /// Binary searches this slice for each of `keys`, interleaving [`DEFAULT_INTERLEAVE`]
+    /// searches at a time. Note it is assumed that the slice is sorted.
+    ///
+    /// The results are returned in the same order as `keys`.
+    #[cfg(feature = "alloc")]
+    #[inline]
+    fn bl_binary_search_batch(&self, keys: &[T]) -> Vec<Result<usize, usize>>
+    where
+        T: Ord,
+    {
+        self.bl_binary_search_batch_by::<DEFAULT_INTERLEAVE, _, _>(keys, T::cmp)
+    }
+
+    /// Binary searches this slice for each of `keys` like
+    /// [`bl_binary_search_batch`](SharBatchSearch::bl_binary_search_batch), writing the results
+    /// to `out` instead of allocating. Note it is assumed that the slice is sorted.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BatchSizeMismatch`] without writing anything if `out` and `keys` have
+    /// different lengths.
+    ///
+    /// ```
+    /// use shar_search::SharBatchSearch;
+    ///
+    /// let slice = [10, 20, 30];
+    /// let mut out = [Ok(0); 2];
+    /// slice.bl_binary_search_batch_into(&[20, 25], &mut out).unwrap();
+    /// assert_eq!(out, [Ok(1), Err(2)]);
+    ///
+    /// assert!(slice.bl_binary_search_batch_into(&[20], &mut out).is_err());
+    /// ```
+    #[inline]
+    fn bl_binary_search_batch_into(
+        &self,
+        keys: &[T],
+        out: &mut [Result<usize, usize>],
+    ) -> Result<(), BatchSizeMismatch>
+    where
+        T: Ord,
+    {
+        self.bl_binary_search_batch_by_into::<DEFAULT_INTERLEAVE, _, _>(keys, out, T::cmp)
+    }
+
+    /// Binary searches this slice for each of `keys` like
+    /// [`bl_binary_search_batch`](SharBatchSearch::bl_binary_search_batch), writing the results
+    /// to the start of `out` and returning that initialized prefix. Note it is assumed that the
+    /// slice is sorted.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BatchSizeMismatch`] without writing anything if `out` is shorter than `keys`.
+    ///
+    /// ```
+    /// use std::mem::MaybeUninit;
+    ///
+    /// use shar_search::SharBatchSearch;
+    ///
+    /// let slice = [10, 20, 30];
+    /// let mut arena = [MaybeUninit::uninit(); 16];
+    /// let results = slice.bl_binary_search_batch_uninit(&[30, 5], &mut arena).unwrap();
+    /// assert_eq!(results, [Ok(2), Err(0)]);
+    /// ```
+    #[inline]
+    fn bl_binary_search_batch_uninit<'o>(
+        &self,
+        keys: &[T],
+        out: &'o mut [MaybeUninit<Result<usize, usize>>],
+    ) -> Result<&'o mut [Result<usize, usize>], BatchSizeMismatch>
+    where
+        T: Ord,
+    {
+        self.bl_binary_search_batch_by_uninit::<DEFAULT_INTERLEAVE, _, _>(keys, out, T::cmp)
+    }
+
+    /// Binary searches this slice for each of `keys` with a key extraction function,
+    /// interleaving [`DEFAULT_INTERLEAVE`] searches at a time. Note it is assumed that the
+    /// slice is sorted by the extracted key.
+    ///
+    /// The results are returned in the same order as `keys`.
+    #[cfg(feature = "alloc")]
+    #[inline]
+    fn bl_binary_search_batch_by_key<B, F>(&self, keys: &[B], mut f: F) -> Vec<Result<usize, usize>>
+    where
+        F: FnMut(&T) -> B,
+        B: Ord,
+    {
+        self.bl_binary_search_batch_by::<DEFAULT_INTERLEAVE, _, _>(keys, |p, b| f(p).cmp(b))
+    }
+
+    /// Returns an iterator that searches this slice for each key of `keys`, yielding the key
+    /// together with its result. Keys are pulled and searched for a window of
+    /// [`DEFAULT_INTERLEAVE`] at a time, with the searches of a window interleaved. Note it is
+    /// assumed that the slice is sorted.
+    ///
+    /// ```
+    /// use shar_search::SharBatchSearch;
+    ///
+    /// let slice = [10, 20, 30];
+    /// let results: Vec<_> = slice.bl_search_stream([30, 15]).collect();
+    /// assert_eq!(results, [(30, Ok(2)), (15, Err(1))]);
+    /// ```
+    #[cfg(feature = "alloc")]
+    fn bl_search_stream<I>(
+        &self,
+        keys: I,
+    ) -> SearchStream<'_, T, I::IntoIter, impl FnMut(&T, &I::Item) -> Ordering, DEFAULT_INTERLEAVE>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<T>,
+        T: Ord;
+
+    /// Returns an iterator that searches this slice for each key of `keys` with a comparator
+    /// function, pulling and searching for a window of `W` keys at a time. `compare(element,
+    /// key)` returns the ordering of an element relative to a key. See [`SearchStream`].
+    #[cfg(feature = "alloc")]
+    fn bl_search_stream_by<const W: usize, I, F>(
+        &self,
+        keys: I,
+        compare: F,
+    ) -> SearchStream<'_, T, I::IntoIter, F, W>
+    where
+        I: IntoIterator,
+        F: FnMut(&T, &I::Item) -> Ordering;
+
+    /// Binary searches this slice for each of `sorted_keys` with a comparator function, where
+    /// the keys are sorted in the same order as the slice. `compare(element, key)` returns the
+    /// ordering of an element relative to a key. Note it is assumed that both the slice and the
+    /// keys are sorted; unsorted keys give unspecified (but memory-safe) results.
+    ///
+    /// Each search starts where the previous one ended and gallops forward, so searching for
+    /// `q` keys in a slice of length `n` costs `O(q log(n / q))` comparisons, and approaches a
+    /// linear merge as `q` approaches `n`. The results are returned in the same order as
+    /// `sorted_keys`.
+    ///
+    /// The galloping probes still depend on each other, so for a few keys spread over a slice
+    /// much larger than the cache, the interleaved
+    /// [`bl_binary_search_batch_by`](SharBatchSearch::bl_binary_search_batch_by) can be faster.
+    #[cfg(feature = "alloc")]
+    fn bl_binary_search_sorted_keys_by<Q, F>(
+        &self,
+        sorted_keys: &[Q],
+        compare: F,
+    ) -> Vec<Result<usize, usize>>
+    where
+        F: FnMut(&T, &Q) -> Ordering;
+
+    /// Binary searches this slice for each of `sorted_keys`, which must be sorted. See
+    /// [`bl_binary_search_sorted_keys_by`](SharBatchSearch::bl_binary_search_sorted_keys_by).
+    ///
+    /// In debug builds, this panics if `sorted_keys` is not sorted.
+    ///
+    /// ```
+    /// use shar_search::SharBatchSearch;
+    ///
+    /// let slice = [1, 3, 3, 5, 8];
+    /// let results = slice.bl_binary_search_sorted_keys(&[0, 3, 3, 6, 8, 9]);
+    /// assert_eq!(results, [Err(0), Ok(1), Ok(1), Err(4), Ok(4), Err(5)]);
+    /// ```
+    #[cfg(feature = "alloc")]
+    #[inline]
+    fn bl_binary_search_sorted_keys(&self, sorted_keys: &[T]) -> Vec<Result<usize, usize>>
+    where
+        T: Ord,
+    {
+        debug_assert!(sorted_keys.is_sorted(), "the keys must be sorted");
+        self.bl_binary_search_sorted_keys_by(sorted_keys, T::cmp)
+    }
+
+    /// Binary searches this slice for each of `sorted_keys` with a key extraction function.
+    /// The slice must be sorted by the extracted key, and the keys must be sorted. See
+    /// [`bl_binary_search_sorted_keys_by`](SharBatchSearch::bl_binary_search_sorted_keys_by).
+    ///
+    /// In debug builds, this panics if `sorted_keys` is not sorted.
+    #[cfg(feature = "alloc")]
+    #[inline]
+    fn bl_binary_search_sorted_keys_by_key<B, F>(
+        &self,
+        sorted_keys: &[B],
+        mut f: F,
+    ) -> Vec<Result<usize, usize>>
+    where
+        F: FnMut(&T) -> B,
+        B: Ord,
+    {
+        debug_assert!(sorted_keys.is_sorted(), "the keys must be sorted");
+        self.bl_binary_search_sorted_keys_by(sorted_keys, |p, b| f(p).cmp(b))
+    }
+}
+
+impl<T> SharBatchSearch<T> for [T] {
+    #[cfg(feature = "alloc")]
+    fn bl_search_stream<I>(
+        &self,
+        keys: I,
+    ) -> SearchStream<'_, T, I::IntoIter, impl FnMut(&T, &I::Item) -> Ordering, DEFAULT_INTERLEAVE>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<T>,
+        T: Ord,
+    {
+        self.bl_search_stream_by::<DEFAULT_INTERLEAVE, _, _>(keys, |p, k| p.cmp(k.borrow()))
+    }
+
+    #[cfg(feature = "alloc")]
+    fn bl_search_stream_by<const W: usize, I, F>(
+        &self,
+        keys: I,
+        compare: F,
+    ) -> SearchStream<'_, T, I::IntoIter, F, W>
+    where
+        I: IntoIterator,
+        F: FnMut(&T, &I::Item) -> Ordering,
+    {
+        SearchStream::new(self, keys.into_iter(), compare)
+    }
+
+    #[cfg(feature = "alloc")]
+    fn bl_binary_search_sorted_keys_by<Q, F>(
+        &self,
+        sorted_keys: &[Q],
+        mut compare: F,
+    ) -> Vec<Result<usize, usize>>
+    where
+        F: FnMut(&T, &Q) -> Ordering,
+    {
+        let mut results = Vec::with_capacity(sorted_keys.len());
+
+        // Everything before `start` is less than the previous key, and so less than this one.
+        let mut start = 0;
+        for key in sorted_keys {
+            let index = start + gallop(&self[start..], |p| compare(p, key).is_lt());
+            results.push(match self.get(index) {
+                Some(p) if compare(p, key).is_eq() => Ok(index),
+                _ => Err(index),
+            });
+            start = index;
+        }
+
+        results
+    }
+
+    fn bl_binary_search_batch_by_uninit<'o, const W: usize, Q, F>(
+        &self,
+        keys: &[Q],
+        out: &'o mut [MaybeUninit<Result<usize, usize>>],
+        mut compare: F,
+    ) -> Result<&'o mut [Result<usize, usize>], BatchSizeMismatch>
+    where
+        F: FnMut(&T, &Q) -> Ordering,
+    {
+        assert!(W > 0, "the interleave width must be non-zero");
+
+        if out.len() < keys.len() {
+            return Err(BatchSizeMismatch {
+                keys: keys.len(),
+                out: out.len(),
+            });
+        }
+
+        let out = &mut out[..keys.len()];
+
+        if self.is_empty() {
+            out.fill(MaybeUninit::new(Err(0)));
+        } else {
+            for (group, group_out) in keys.chunks(W).zip(out.chunks_mut(W)) {
+                // Every search in the group shares the same remaining length, so they all
+                // advance one level per round and the loads of a round are independent of each
+                // other.
+                let mut bases = [0_usize; W];
+                let mut size = self.len();
+
+                while size > 1 {
+                    let half = size / 2;
+                    for (base, key) in bases.iter_mut().zip(group) {
+                        let mid = *base + half;
+                        if compare(unsafe { self.get_unchecked(mid) }, key).is_lt() {
+                            *base = mid;
+                        }
+                    }
+                    size -= half;
+                }
+
+                for ((&base, key), slot) in bases.iter().zip(group).zip(group_out) {
+                    let result = match compare(unsafe { self.get_unchecked(base) }, key) {
+                        Ordering::Less => match self.get(base + 1) {
+                            Some(next) if compare(next, key).is_eq() => Ok(base + 1),
+                            _ => Err(base + 1),
+                        },
+                        Ordering::Equal => Ok(base),
+                        Ordering::Greater => Err(base),
+                    };
+                    slot.write(result);
+                }
+            }
+        }
+
+        // Every element of `out` was written above.
+        Ok(unsafe {
+            &mut *(out as *mut [MaybeUninit<Result<usize, usize>>] as *mut [Result<usize, usize>])
+        })
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod test {
+    use std::mem::MaybeUninit;
+
+    use super::{BatchSizeMismatch, SharBatchSearch};
+    use crate::{
+        test_util::{count_allocations, XorShift},
+        SharBinarySearch,
+    };
+
+    fn check<const W: usize>(slice: &[u32], keys: &[u32]) {
+        let expected: Vec<_> = keys.iter().map(|k| slice.bl_binary_search(k)).collect();
+        assert_eq!(
+            slice.bl_binary_search_batch_by::<W, _, _>(keys, |p, k| p.cmp(k)),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_batch_matches_scalar() {
+        let mut rng = XorShift::new(17);
+
+        for len in [0, 1, 2, 3, 10, 64, 100, 1000] {
+            // Values drawn from a small range so that the slice has runs of duplicates.
+            let mut slice: Vec<u32> = (0..len).map(|_| rng.below(len + 1) as u32).collect();
+            slice.sort();
+
+            for batch_size in [0, 1, 7, 64] {
+                let mut keys: Vec<u32> =
+                    (0..batch_size).map(|_| rng.below(len + 3) as u32).collect();
+                // Repeat some queries within the batch.
+                if batch_size > 1 {
+                    keys[batch_size as usize - 1] = keys[0];
+                }
+
+                check::<1>(&slice, &keys);
+                check::<4>(&slice, &keys);
+                check::<8>(&slice, &keys);
+                check::<16>(&slice, &keys);
+                assert_eq!(slice.bl_binary_search_batch(&keys).len(), keys.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_batch_by_key() {
+        let pairs: Vec<(u32, char)> = vec![(1, 'a'), (2, 'b'), (2, 'c'), (5, 'd'), (9, 'e')];
+        let results = pairs.bl_binary_search_batch_by_key(&[2, 9, 0, 6, 2], |&(k, _)| k);
+        assert_eq!(results, [Ok(1), Ok(4), Err(0), Err(4), Ok(1)]);
+
+        let empty: [u32; 0] = [];
+        assert_eq!(empty.bl_binary_search_batch(&[1, 2]), [Err(0), Err(0)]);
+    }
+
+    #[test]
+    fn test_batch_into() {
+        let slice: Vec<u32> = (0..1000).map(|i| i * 3).collect();
+        let keys: Vec<u32> = (0..64).map(|i| i * 47 % 3000).collect();
+        // The allocating version makes exactly one allocation, which the counter sees.
+        let (expected, allocations) = count_allocations(|| slice.bl_binary_search_batch(&keys));
+        assert_eq!(allocations, 1);
+
+        let mut out = vec![Ok(usize::MAX); keys.len()];
+        let (result, allocations) =
+            count_allocations(|| slice.bl_binary_search_batch_into(&keys, &mut out));
+        assert_eq!(result, Ok(()));
+        assert_eq!(allocations, 0);
+        assert_eq!(out, expected);
+
+        // A mismatched buffer is rejected without being written to.
+        let mut short = vec![Ok(usize::MAX); keys.len() - 1];
+        assert_eq!(
+            slice.bl_binary_search_batch_into(&keys, &mut short),
+            Err(BatchSizeMismatch { keys: 64, out: 63 })
+        );
+        assert!(short.iter().all(|r| *r == Ok(usize::MAX)));
+
+        let mut long = vec![Ok(usize::MAX); keys.len() + 1];
+        let err = slice
+            .bl_binary_search_batch_into(&keys, &mut long)
+            .unwrap_err();
+        assert_eq!((err.keys(), err.out()), (64, 65));
+        assert_eq!(
+            err.to_string(),
+            "output buffer of length 65 cannot hold results for 64 keys"
+        );
+    }
+
+    #[test]
+    fn test_batch_uninit() {
+        let slice: Vec<u32> = (0..1000).map(|i| i * 3).collect();
+        let keys: Vec<u32> = (0..7).map(|i| i * 401 % 3000).collect();
+        let expected = slice.bl_binary_search_batch(&keys);
+
+        // Fill the arena with a sentinel, so that any element past the returned prefix would
+        // show up as such if it were read.
+        let mut arena = [MaybeUninit::new(Ok(usize::MAX)); 16];
+        let (results, allocations) =
+            count_allocations(|| slice.bl_binary_search_batch_uninit(&keys, &mut arena));
+        let results = results.unwrap();
+        assert_eq!(allocations, 0);
+        assert_eq!(results, expected.as_slice());
+
+        let rest = &arena[keys.len()..];
+        assert!(rest
+            .iter()
+            .all(|slot| unsafe { slot.assume_init() } == Ok(usize::MAX)));
+
+        let mut small = [MaybeUninit::uninit(); 4];
+        assert!(slice
+            .bl_binary_search_batch_uninit(&keys, &mut small)
+            .is_err());
+
+        let empty: [u32; 0] = [];
+        let results = empty
+            .bl_binary_search_batch_uninit(&keys, &mut arena)
+            .unwrap();
+        assert_eq!(results, [Err(0); 7]);
+    }
+
+    #[test]
+    fn test_sorted_keys() {
+        let mut rng = XorShift::new(23);
+
+        for len in [0, 1, 5, 100, 1000] {
+            let mut slice: Vec<u32> = (0..len).map(|_| rng.below(len * 2 + 1) as u32).collect();
+            slice.sort();
+
+            // Sparse keys with duplicates, keys denser than the data, and keys that all miss.
+            let mut sparse: Vec<u32> = (0..len / 10 + 3)
+                .map(|_| rng.below(len * 2 + 3) as u32)
+                .collect();
+            sparse.extend_from_slice(&sparse.clone()[..2]);
+            let dense: Vec<u32> = (0..len as u32 * 2 + 3).collect();
+            let misses: Vec<u32> = (0..20).map(|i| len as u32 * 2 + 1 + i).collect();
+
+            for mut keys in [sparse, dense, misses] {
+                keys.sort();
+                let expected: Vec<_> = keys.iter().map(|k| slice.bl_binary_search(k)).collect();
+                assert_eq!(slice.bl_binary_search_sorted_keys(&keys), expected);
+            }
+        }
+
+        let evens: Vec<u32> = (0..50).map(|i| i * 2).collect();
+        let odds: Vec<u32> = (0..50).map(|i| i * 2 + 1).collect();
+        assert!(evens
+            .bl_binary_search_sorted_keys(&odds)
+            .iter()
+            .all(Result::is_err));
+    }
+
+    #[test]
+    fn test_sorted_keys_by_key() {
+        let pairs = [(1, 'a'), (2, 'b'), (2, 'c'), (5, 'd'), (9, 'e')];
+        let results = pairs.bl_binary_search_sorted_keys_by_key(&[0, 2, 2, 6, 9, 10], |&(k, _)| k);
+        assert_eq!(results, [Err(0), Ok(1), Ok(1), Err(4), Ok(4), Err(5)]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "the keys must be sorted")]
+    fn test_sorted_keys_unsorted() {
+        [1, 2, 3].bl_binary_search_sorted_keys(&[3, 1]);
+    }
+}
@@ -0,0 +1,319 @@
+//! Binning values against sorted bin edges, as NumPy's `digitize` does, and histograms of the
+//! bins.
+//!
+//! `n` sorted edges make `n + 1` bins: bin 0 holds the values below the first edge, bin `i`
+//! those between edges `i - 1` and `i`, and bin `n` those above the last edge. A value equal to
+//! an edge falls in the bin to the edge's right, unless the bins are `right_closed`, when it
+//! falls in the bin to its left.
+//!
+//! This is the inverse of [splitting a sorted slice at cuts](crate::partition): rather than
+//! finding where each edge falls among the values, it finds where each value falls among the
+//! edges. Each value is found with the branchless search, except that if the values are sorted,
+//! each search gallops forward from the previous one instead.
+//!
+//! ```
+//! use shar_search::bins::{digitize, histogram};
+//!
+//! let edges = [0.0, 1.0, 2.5, 4.0, 10.0];
+//! let values = [0.2, 6.4, 3.0, 1.6, 2.5];
+//! assert_eq!(digitize(&edges, &values, false), [1, 4, 3, 2, 3]);
+//! assert_eq!(digitize(&edges, &values, true), [1, 4, 3, 2, 2]);
+//! assert_eq!(histogram(&edges, &values, false), [0, 1, 1, 2, 1, 0]);
+//! ```
+
+use alloc::{vec, vec::Vec};
+use core::cmp::Ordering;
+
+use crate::{gallop::gallop, SharBinarySearch};
+
+/// Calls `emit` with the bin of each of `values` in turn, where `compare` orders an edge
+/// relative to a value and `sorted` says whether the values are in order.
+fn bin_each_by<E, T, F>(
+    edges: &[E],
+    values: &[T],
+    right_closed: bool,
+    sorted: bool,
+    mut compare: F,
+    mut emit: impl FnMut(usize),
+) where
+    F: FnMut(&E, &T) -> Ordering,
+{
+    // The bin of a value is the number of edges to its left.
+    let mut left_of = |edge: &E, value: &T| match compare(edge, value) {
+        Ordering::Less => true,
+        Ordering::Equal => !right_closed,
+        Ordering::Greater => false,
+    };
+
+    if sorted {
+        // Every edge left of the previous value is left of this one too.
+        let mut bin = 0;
+        for value in values {
+            bin += gallop(&edges[bin..], |edge| left_of(edge, value));
+            emit(bin);
+        }
+    } else {
+        for value in values {
+            emit(edges.bl_partition_point(|edge| left_of(edge, value)));
+        }
+    }
+}
+
+/// Collects the bins of `bin_each_by`.
+fn digitize_by<E, T, F>(
+    edges: &[E],
+    values: &[T],
+    right_closed: bool,
+    sorted: bool,
+    compare: F,
+) -> Vec<usize>
+where
+    F: FnMut(&E, &T) -> Ordering,
+{
+    let mut bins = Vec::with_capacity(values.len());
+    bin_each_by(edges, values, right_closed, sorted, compare, |bin| {
+        bins.push(bin)
+    });
+    bins
+}
+
+/// Counts the bins of `bin_each_by`.
+fn histogram_by<E, T, F>(
+    edges: &[E],
+    values: &[T],
+    right_closed: bool,
+    sorted: bool,
+    compare: F,
+) -> Vec<u64>
+where
+    F: FnMut(&E, &T) -> Ordering,
+{
+    let mut counts = vec![0; edges.len() + 1];
+    bin_each_by(edges, values, right_closed, sorted, compare, |bin| {
+        counts[bin] += 1
+    });
+    counts
+}
+
+/// Orders floats as NumPy sorts them: by value, so that `-0.0` equals `0.0`, with NaNs after
+/// every number.
+fn nan_last(a: &f64, b: &f64) -> Ordering {
+    a.partial_cmp(b)
+        .unwrap_or_else(|| a.is_nan().cmp(&b.is_nan()))
+}
+
+/// Returns the bin of each of `values` among the sorted `edges`, in the same order, matching
+/// NumPy's `digitize` for increasing bins. Note it is assumed that `edges` is sorted, with any
+/// NaNs last.
+///
+/// A value falls in bin `i` if `edges[i - 1] <= value < edges[i]`, or if
+/// `edges[i - 1] < value <= edges[i]` when `right_closed`, where the missing edges at either
+/// end are infinite. With no edges, every value falls in bin 0.
+///
+/// A NaN value falls in the last bin, `edges.len()`, as NaNs sort after every number in NumPy.
+///
+/// In debug builds, this panics if `edges` is not sorted.
+///
+/// ```
+/// use shar_search::bins::digitize;
+///
+/// let edges = [0.0, 5.0, 10.0, 15.0, 20.0];
+/// let values = [1.2, 10.0, 12.4, 15.5, 20.0, f64::NAN];
+/// assert_eq!(digitize(&edges, &values, false), [1, 3, 3, 4, 5, 5]);
+/// assert_eq!(digitize(&edges, &values, true), [1, 2, 3, 4, 4, 5]);
+/// ```
+pub fn digitize(edges: &[f64], values: &[f64], right_closed: bool) -> Vec<usize> {
+    debug_assert!(
+        edges.is_sorted_by(|a, b| nan_last(a, b).is_le()),
+        "the edges must be sorted"
+    );
+    let sorted = values.is_sorted_by(|a, b| nan_last(a, b).is_le());
+    digitize_by(edges, values, right_closed, sorted, nan_last)
+}
+
+/// Returns the bin of each of `values` among the sorted `edges` of an [`Ord`] type, such as
+/// the integers. Note it is assumed that `edges` is sorted. See [`digitize`].
+///
+/// In debug builds, this panics if `edges` is not sorted.
+///
+/// ```
+/// use shar_search::bins::digitize_ord;
+///
+/// let ages = [17, 18, 30, 64, 65, 90];
+/// assert_eq!(digitize_ord(&[18, 65], &ages, false), [0, 1, 1, 1, 2, 2]);
+/// ```
+pub fn digitize_ord<T: Ord>(edges: &[T], values: &[T], right_closed: bool) -> Vec<usize> {
+    debug_assert!(edges.is_sorted(), "the edges must be sorted");
+    digitize_by(edges, values, right_closed, values.is_sorted(), T::cmp)
+}
+
+/// Returns the bin of each of `values` among the sorted `edges`, comparing the key extracted
+/// from each value by `f` to the edges. Note it is assumed that `edges` is sorted. See
+/// [`digitize`].
+///
+/// In debug builds, this panics if `edges` is not sorted.
+///
+/// ```
+/// use shar_search::bins::digitize_by_key;
+///
+/// let requests = [("/a", 120), ("/b", 3), ("/c", 40)];
+/// let latency_edges = [10, 100];
+/// assert_eq!(digitize_by_key(&latency_edges, &requests, false, |r| r.1), [2, 0, 1]);
+/// ```
+pub fn digitize_by_key<T, K, F>(
+    edges: &[K],
+    values: &[T],
+    right_closed: bool,
+    mut f: F,
+) -> Vec<usize>
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    debug_assert!(edges.is_sorted(), "the edges must be sorted");
+    let sorted = values.is_sorted_by_key(&mut f);
+    digitize_by(edges, values, right_closed, sorted, |edge, value| {
+        edge.cmp(&f(value))
+    })
+}
+
+/// Returns the number of `values` in each of the `edges.len() + 1` bins among the sorted
+/// `edges`, binning each value as [`digitize`] does, with NaNs in the last bin. Note it is
+/// assumed that `edges` is sorted, with any NaNs last.
+///
+/// The values are binned in one pass, without collecting their bins.
+///
+/// In debug builds, this panics if `edges` is not sorted.
+pub fn histogram(edges: &[f64], values: &[f64], right_closed: bool) -> Vec<u64> {
+    debug_assert!(
+        edges.is_sorted_by(|a, b| nan_last(a, b).is_le()),
+        "the edges must be sorted"
+    );
+    let sorted = values.is_sorted_by(|a, b| nan_last(a, b).is_le());
+    histogram_by(edges, values, right_closed, sorted, nan_last)
+}
+
+/// Returns the number of `values` in each bin among the sorted `edges` of an [`Ord`] type.
+/// Note it is assumed that `edges` is sorted. See [`histogram`] and [`digitize_ord`].
+///
+/// In debug builds, this panics if `edges` is not sorted.
+pub fn histogram_ord<T: Ord>(edges: &[T], values: &[T], right_closed: bool) -> Vec<u64> {
+    debug_assert!(edges.is_sorted(), "the edges must be sorted");
+    histogram_by(edges, values, right_closed, values.is_sorted(), T::cmp)
+}
+
+/// Returns the number of `values` in each bin among the sorted `edges`, comparing the key
+/// extracted from each value by `f` to the edges. Note it is assumed that `edges` is sorted.
+/// See [`histogram`] and [`digitize_by_key`].
+///
+/// In debug builds, this panics if `edges` is not sorted.
+pub fn histogram_by_key<T, K, F>(
+    edges: &[K],
+    values: &[T],
+    right_closed: bool,
+    mut f: F,
+) -> Vec<u64>
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    debug_assert!(edges.is_sorted(), "the edges must be sorted");
+    let sorted = values.is_sorted_by_key(&mut f);
+    histogram_by(edges, values, right_closed, sorted, |edge, value| {
+        edge.cmp(&f(value))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reference::{self, assert_matches_reference, inputs};
+
+    #[test]
+    fn test_numpy_examples() {
+        // The examples in NumPy's documentation of `digitize`.
+        let edges = [0.0, 1.0, 2.5, 4.0, 10.0];
+        assert_eq!(digitize(&edges, &[0.2, 6.4, 3.0, 1.6], false), [1, 4, 3, 2]);
+
+        let edges = [0.0, 5.0, 10.0, 15.0, 20.0];
+        let values = [1.2, 10.0, 12.4, 15.5, 20.0];
+        assert_eq!(digitize(&edges, &values, true), [1, 2, 3, 4, 4]);
+        assert_eq!(digitize(&edges, &values, false), [1, 3, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_values_on_edges() {
+        let edges = [1.0, 2.0, 2.0, 3.0];
+        let values = [0.0, 1.0, 2.0, 3.0, 4.0];
+        assert_eq!(digitize(&edges, &values, false), [0, 1, 3, 4, 4]);
+        assert_eq!(digitize(&edges, &values, true), [0, 0, 1, 3, 4]);
+
+        // Zeros of either sign are equal, as in NumPy.
+        assert_eq!(digitize(&[0.0], &[-0.0, 0.0], false), [1, 1]);
+        assert_eq!(digitize(&[-0.0], &[-0.0, 0.0], true), [0, 0]);
+    }
+
+    #[test]
+    fn test_nan_and_infinities() {
+        let edges = [0.0, 1.0];
+        let values = [f64::NAN, f64::NEG_INFINITY, -f64::NAN, 0.5, f64::INFINITY];
+        for right_closed in [false, true] {
+            assert_eq!(digitize(&edges, &values, right_closed), [2, 0, 2, 1, 2]);
+            assert_eq!(histogram(&edges, &values, right_closed), [1, 1, 3]);
+        }
+
+        // NaN edges come last, and a NaN value is equal to them.
+        let edges = [0.0, f64::NAN];
+        assert_eq!(digitize(&edges, &[f64::NAN, 1.0], false), [2, 1]);
+        assert_eq!(digitize(&edges, &[f64::NAN, 1.0], true), [1, 1]);
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(digitize(&[], &[-1.0, 0.0, f64::NAN], false), [0, 0, 0]);
+        assert_eq!(digitize_ord(&[], &[1, 2], true), [0, 0]);
+        assert_eq!(histogram(&[], &[-1.0, 0.0, f64::NAN], false), [3]);
+        assert!(digitize(&[1.0], &[], false).is_empty());
+        assert_eq!(histogram(&[1.0, 2.0], &[], true), [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_against_reference() {
+        // Few distinct values, so many values land on edges, and repeated edges. The values
+        // come sorted, which takes the galloping path, and reversed, which does not.
+        let cases = inputs::slice_pairs(61).flat_map(|(edges, values)| {
+            let reversed: Vec<u32> = values.iter().rev().copied().collect();
+            [false, true].into_iter().flat_map(move |right_closed| {
+                [
+                    (edges.clone(), values.clone(), right_closed),
+                    (edges.clone(), reversed.clone(), right_closed),
+                ]
+            })
+        });
+
+        assert_matches_reference!(
+            for (edges, values, right_closed) in cases =>
+            {
+                let floats: Vec<f64> = values.iter().map(|&v| f64::from(v)).collect();
+                let float_edges: Vec<f64> = edges.iter().map(|&e| f64::from(e)).collect();
+                let pairs: Vec<(u32, ())> = values.iter().map(|&v| (v, ())).collect();
+                (
+                    [
+                        digitize_ord(&edges, &values, right_closed),
+                        digitize(&float_edges, &floats, right_closed),
+                        digitize_by_key(&edges, &pairs, right_closed, |p| p.0),
+                    ],
+                    [
+                        histogram_ord(&edges, &values, right_closed),
+                        histogram(&float_edges, &floats, right_closed),
+                        histogram_by_key(&edges, &pairs, right_closed, |p| p.0),
+                    ],
+                )
+            },
+            (
+                [(); 3].map(|()| reference::digitize(&edges, &values, right_closed)),
+                [(); 3].map(|()| reference::histogram(&edges, &values, right_closed)),
+            ),
+        );
+    }
+}
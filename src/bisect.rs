@@ -0,0 +1,368 @@
+//! Bisection over an `f64` interval: the partition point of a monotone predicate over the reals.
+//!
+//! [`partition_point_f64`] finds the smallest `x` in `[lo, hi]` where a predicate flips from
+//! `false` to `true`, to a tolerance, by bisecting on the midpoint of the interval.
+//! [`partition_point_f64_bits`] instead bisects on the ordered bit patterns of the floats in
+//! the interval, and so finds the flip exactly, in at most 64 evaluations.
+//!
+//! ```
+//! use shar_search::bisect::{partition_point_f64, partition_point_f64_bits, BisectOptions};
+//!
+//! // The dose at which the response first reaches 0.9.
+//! let response = |dose: f64| 1.0 - (-dose / 4.0).exp();
+//! let options = BisectOptions {
+//!     abs_tolerance: 1e-9,
+//!     ..BisectOptions::DEFAULT
+//! };
+//! let dose = partition_point_f64(0.0, 100.0, |dose| response(dose) >= 0.9, options)?;
+//! assert!((dose - 4.0 * 10_f64.ln()).abs() < 1e-8);
+//!
+//! // The smallest float whose square is at least 2.
+//! let root = partition_point_f64_bits(0.0, 2.0, |x| x * x >= 2.0)?;
+//! assert!(root * root >= 2.0);
+//! assert!(root.next_down() * root.next_down() < 2.0);
+//! # Ok::<(), shar_search::bisect::BisectError>(())
+//! ```
+
+use core::{error::Error, fmt};
+
+/// When [`partition_point_f64`] stops bisecting.
+///
+/// Bisection stops once the interval `[lo, hi]` known to hold the flip satisfies
+/// `hi - lo <= abs_tolerance + rel_tolerance * max(|lo|, |hi|)`, once `lo` and `hi` are
+/// adjacent floats, or after `max_iterations` evaluations of the predicate, whichever comes
+/// first.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BisectOptions {
+    /// The absolute width below which the interval is narrow enough.
+    pub abs_tolerance: f64,
+    /// The width, relative to the larger magnitude of the interval's ends, below which the
+    /// interval is narrow enough.
+    pub rel_tolerance: f64,
+    /// The most times to bisect.
+    pub max_iterations: u32,
+}
+
+impl BisectOptions {
+    /// No tolerance, and enough iterations to bisect any finite interval down to adjacent
+    /// floats, so the result is as exact as bisecting on the midpoint allows.
+    pub const DEFAULT: Self = Self {
+        abs_tolerance: 0.0,
+        rel_tolerance: 0.0,
+        max_iterations: 2100,
+    };
+}
+
+impl Default for BisectOptions {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// The error returned when the bounds of a bisection are invalid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BisectError {
+    /// A bound is NaN.
+    NaN,
+    /// A bound is infinite, which [`partition_point_f64`] cannot take the midpoint of.
+    Infinite,
+    /// The lower bound is greater than the upper bound.
+    Reversed,
+}
+
+impl fmt::Display for BisectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BisectError::NaN => write!(f, "a bound is NaN"),
+            BisectError::Infinite => write!(f, "a bound is infinite"),
+            BisectError::Reversed => write!(f, "the lower bound is greater than the upper bound"),
+        }
+    }
+}
+
+impl Error for BisectError {}
+
+/// Checks that `lo` and `hi` are ordered and not NaN.
+fn check_bounds(lo: f64, hi: f64) -> Result<(), BisectError> {
+    if lo.is_nan() || hi.is_nan() {
+        Err(BisectError::NaN)
+    } else if lo > hi {
+        Err(BisectError::Reversed)
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns the smallest `x` in `[lo, hi]` for which `pred` is `true`, to the tolerance of
+/// `options`. Note it is assumed that `pred` is monotone on `[lo, hi]`: `false` up to some
+/// point, and `true` from there on.
+///
+/// If `pred(lo)` is `true`, this returns `lo`. Otherwise, the result is the end of the final
+/// interval where `pred` is `true`, or `hi` if `pred` was `false` everywhere it was evaluated,
+/// which it is if `pred` is `false` on the whole interval. `pred` is never evaluated at `hi`.
+///
+/// Midpoints are taken with [`f64::midpoint`], which neither overflows for bounds of mixed
+/// sign and large magnitude, nor loses precision in the subnormal range.
+///
+/// # Errors
+///
+/// Returns an error if either bound is NaN or infinite, or if `lo > hi`.
+pub fn partition_point_f64<P>(
+    lo: f64,
+    hi: f64,
+    mut pred: P,
+    options: BisectOptions,
+) -> Result<f64, BisectError>
+where
+    P: FnMut(f64) -> bool,
+{
+    check_bounds(lo, hi)?;
+    if lo.is_infinite() || hi.is_infinite() {
+        return Err(BisectError::Infinite);
+    }
+    if pred(lo) {
+        return Ok(lo);
+    }
+
+    // `pred` is false at `lo`, and true at `hi` unless it is the original upper bound.
+    let (mut lo, mut hi) = (lo, hi);
+    for _ in 0..options.max_iterations {
+        let tolerance = options.abs_tolerance + options.rel_tolerance * lo.abs().max(hi.abs());
+        // The width overflows to infinity for wide intervals, which are never narrow enough.
+        if hi - lo <= tolerance {
+            break;
+        }
+
+        let mid = lo.midpoint(hi);
+        if mid <= lo || mid >= hi {
+            break;
+        }
+        if pred(mid) {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+    }
+    Ok(hi)
+}
+
+/// Maps a float to an integer with the same order as [`f64::total_cmp`].
+fn to_ordered(x: f64) -> u64 {
+    let bits = x.to_bits();
+    if bits >> 63 == 1 {
+        !bits
+    } else {
+        bits | 1 << 63
+    }
+}
+
+/// The inverse of [`to_ordered`].
+fn from_ordered(key: u64) -> f64 {
+    f64::from_bits(if key >> 63 == 1 {
+        key & !(1 << 63)
+    } else {
+        !key
+    })
+}
+
+/// Returns exactly the smallest `x` in `[lo, hi]` for which `pred` is `true`, or `hi` if
+/// there is none. Note it is assumed that `pred` is monotone on `[lo, hi]`: `false` up to some
+/// point, and `true` from there on.
+///
+/// Rather than bisecting on the midpoint of the interval, this bisects on the number of floats
+/// in it, by mapping each float to an integer in the order of [`f64::total_cmp`]. Each step
+/// then halves the number of candidates, so this takes at most 64 evaluations of `pred`, and
+/// works for infinite bounds too. In that order `-0.0` is just below `0.0`, so both are
+/// candidates if the interval contains zero.
+///
+/// # Errors
+///
+/// Returns an error if either bound is NaN, or if `lo > hi`.
+pub fn partition_point_f64_bits<P>(lo: f64, hi: f64, mut pred: P) -> Result<f64, BisectError>
+where
+    P: FnMut(f64) -> bool,
+{
+    check_bounds(lo, hi)?;
+
+    // `pred` is false below `lo`, and true at `hi` unless it is the original upper bound.
+    let (mut lo, mut hi) = (to_ordered(lo), to_ordered(hi));
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if pred(from_ordered(mid)) {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    Ok(from_ordered(lo))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        from_ordered, partition_point_f64, partition_point_f64_bits, to_ordered, BisectError,
+        BisectOptions,
+    };
+    use crate::test_util::XorShift;
+
+    const EXACT: BisectOptions = BisectOptions::DEFAULT;
+
+    /// Checks that both bisections find `threshold` exactly when it is where `pred` flips.
+    fn check_threshold(lo: f64, hi: f64, threshold: f64) {
+        let pred = |x: f64| x >= threshold;
+        assert_eq!(partition_point_f64_bits(lo, hi, pred), Ok(threshold));
+        assert_eq!(partition_point_f64(lo, hi, pred, EXACT), Ok(threshold));
+    }
+
+    #[test]
+    fn test_ordered_bits() {
+        let values = [
+            f64::NEG_INFINITY,
+            f64::MIN,
+            -1.0,
+            -f64::MIN_POSITIVE,
+            -5e-324,
+            -0.0,
+            0.0,
+            5e-324,
+            f64::MIN_POSITIVE,
+            1.0,
+            f64::MAX,
+            f64::INFINITY,
+        ];
+        for pair in values.windows(2) {
+            assert!(to_ordered(pair[0]) < to_ordered(pair[1]), "{pair:?}");
+        }
+        for x in values {
+            assert_eq!(from_ordered(to_ordered(x)).to_bits(), x.to_bits());
+        }
+    }
+
+    #[test]
+    fn test_known_thresholds() {
+        check_threshold(0.0, 10.0, 3.25);
+        check_threshold(-1e300, 1e300, -3.5);
+        check_threshold(-f64::MAX, f64::MAX, 1e-300);
+        // Subnormals.
+        check_threshold(0.0, 1e-300, 5e-324);
+        check_threshold(-1e-310, 1e-310, -2.5e-320);
+
+        let root = partition_point_f64_bits(0.0, 2.0, |x| x * x >= 2.0).unwrap();
+        assert!(root * root >= 2.0 && root.next_down() * root.next_down() < 2.0);
+        assert_eq!(
+            partition_point_f64(0.0, 2.0, |x| x * x >= 2.0, EXACT),
+            Ok(root)
+        );
+
+        let ln = partition_point_f64(0.0, 5.0, |x| x.exp() >= 10.0, EXACT).unwrap();
+        assert!((ln - 10_f64.ln()).abs() <= 4.0 * f64::EPSILON);
+
+        // Infinite bounds are fine when bisecting on the bits.
+        assert_eq!(
+            partition_point_f64_bits(f64::NEG_INFINITY, f64::INFINITY, |x| x >= 7.0),
+            Ok(7.0)
+        );
+        assert_eq!(
+            partition_point_f64_bits(f64::NEG_INFINITY, f64::INFINITY, |x| x > f64::MAX),
+            Ok(f64::INFINITY)
+        );
+    }
+
+    #[test]
+    fn test_constant_predicates() {
+        for (lo, hi) in [(0.0, 1.0), (-3.0, 8.0), (2.5, 2.5)] {
+            assert_eq!(partition_point_f64(lo, hi, |_| true, EXACT), Ok(lo));
+            assert_eq!(partition_point_f64(lo, hi, |_| false, EXACT), Ok(hi));
+            assert_eq!(partition_point_f64_bits(lo, hi, |_| true), Ok(lo));
+            assert_eq!(partition_point_f64_bits(lo, hi, |_| false), Ok(hi));
+        }
+    }
+
+    #[test]
+    fn test_invalid_bounds() {
+        let options = EXACT;
+        assert_eq!(
+            partition_point_f64(2.0, 1.0, |_| true, options),
+            Err(BisectError::Reversed)
+        );
+        assert_eq!(
+            partition_point_f64_bits(2.0, -1.0, |_| true),
+            Err(BisectError::Reversed)
+        );
+        assert_eq!(
+            partition_point_f64(f64::NAN, 1.0, |_| true, options),
+            Err(BisectError::NaN)
+        );
+        assert_eq!(
+            partition_point_f64_bits(0.0, f64::NAN, |_| true),
+            Err(BisectError::NaN)
+        );
+        assert_eq!(
+            partition_point_f64(0.0, f64::INFINITY, |_| true, options),
+            Err(BisectError::Infinite)
+        );
+    }
+
+    #[test]
+    fn test_tolerances_and_cap() {
+        let threshold = 0.123_456_789;
+        let pred = |x: f64| x >= threshold;
+
+        let options = BisectOptions {
+            abs_tolerance: 1e-3,
+            ..EXACT
+        };
+        let x = partition_point_f64(0.0, 1.0, pred, options).unwrap();
+        assert!(x >= threshold && x - threshold <= 1e-3, "{x}");
+
+        let options = BisectOptions {
+            rel_tolerance: 1e-6,
+            ..EXACT
+        };
+        let x = partition_point_f64(0.0, 1e6, pred, options).unwrap();
+        assert!(x >= threshold && x - threshold <= 1.0, "{x}");
+
+        let mut calls = 0;
+        let options = BisectOptions {
+            max_iterations: 10,
+            ..EXACT
+        };
+        let x = partition_point_f64(
+            0.0,
+            1.0,
+            |x| {
+                calls += 1;
+                pred(x)
+            },
+            options,
+        )
+        .unwrap();
+        // One call at `lo`, then one per iteration.
+        assert_eq!(calls, 11);
+        assert!(x >= threshold && x - threshold <= 1.0 / 1024.0, "{x}");
+
+        let mut calls = 0;
+        partition_point_f64_bits(-f64::MAX, f64::MAX, |x| {
+            calls += 1;
+            pred(x)
+        })
+        .unwrap();
+        assert!(calls <= 64);
+    }
+
+    #[test]
+    fn test_random_thresholds() {
+        let mut rng = XorShift::new(192);
+        for _ in 0..500 {
+            let float = |rng: &mut XorShift| {
+                let exponent = rng.below(40) as i32 - 20;
+                let sign = if rng.below(2) == 0 { -1.0 } else { 1.0 };
+                sign * (rng.below(1 << 20) as f64) * 2_f64.powi(exponent)
+            };
+            let (a, b) = (float(&mut rng), float(&mut rng));
+            let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+            let t = lo + (hi - lo) * (rng.below(1001) as f64 / 1000.0);
+            check_threshold(lo, hi, t);
+        }
+    }
+}
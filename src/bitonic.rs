@@ -0,0 +1,150 @@
+//! Searching bitonic slices, which increase to a single peak and then decrease.
+
+use core::cmp::Ordering;
+
+use crate::SharBinarySearch;
+
+/// Returns the index of a local maximum of `slice`: an element greater than the one before it
+/// (if any) and not less than the one after it (if any). For a strictly bitonic slice, this is
+/// its maximum. Returns 0 for an empty slice.
+pub(crate) fn peak_by<T, F>(slice: &[T], mut compare: F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // The local maximum is kept in `base..base + size`: everything before `base` rises to it,
+    // and the element at `base + size - 1` is not less than the one after it.
+    let mut base = 0;
+    let mut size = slice.len();
+
+    while size > 1 {
+        let half = size / 2;
+        let mid = base + half;
+        // `1 <= mid < slice.len()`, since `half >= 1` and `mid <= base + size - 1`.
+        let rising = compare(unsafe { slice.get_unchecked(mid - 1) }, unsafe {
+            slice.get_unchecked(mid)
+        })
+        .is_lt();
+        base = if rising { mid } else { base };
+        size = if rising { size - half } else { half };
+    }
+
+    base
+}
+
+/// Searches `slice`, whose peak is at `peak`, with a comparator function, which returns the
+/// ordering of an element relative to the target. The part up to and including the peak is
+/// searched first.
+pub(crate) fn search_bitonic_by<T, F>(slice: &[T], peak: usize, mut f: F) -> Option<usize>
+where
+    F: FnMut(&T) -> Ordering,
+{
+    let (rising, falling) = slice.split_at((peak + 1).min(slice.len()));
+
+    if let Ok(index) = rising.bl_binary_search_by(&mut f) {
+        return Some(index);
+    }
+    falling
+        .bl_binary_search_by(|p| f(p).reverse())
+        .ok()
+        .map(|index| rising.len() + index)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{reference, test_util::XorShift, SharBinarySearch};
+
+    /// Returns a random strictly bitonic slice, whose sides may share values.
+    fn bitonic(rng: &mut XorShift) -> Vec<u64> {
+        let side = |rng: &mut XorShift| {
+            let len = rng.below(20);
+            let mut values: Vec<u64> = (0..len).map(|_| rng.below(40)).collect();
+            values.sort_unstable();
+            values.dedup();
+            values
+        };
+
+        let rising = side(rng);
+        let mut falling = side(rng);
+        falling.reverse();
+
+        let mut slice = rising;
+        if rng.below(4) != 0 {
+            slice.push(40);
+        }
+        slice.extend(falling);
+        slice.dedup();
+        slice
+    }
+
+    #[test]
+    fn test_shapes() {
+        let empty: [u32; 0] = [];
+        assert_eq!(empty.bl_peak(), 0);
+        assert_eq!(empty.bl_binary_search_bitonic(&1), None);
+
+        assert_eq!([4].bl_peak(), 0);
+        assert_eq!([4].bl_binary_search_bitonic(&4), Some(0));
+
+        // Fully ascending and fully descending.
+        let ascending = [1, 3, 5, 7, 9];
+        assert_eq!(ascending.bl_peak(), 4);
+        assert_eq!(ascending.bl_binary_search_bitonic(&3), Some(1));
+        assert_eq!(ascending.bl_binary_search_bitonic(&4), None);
+        let descending = [9, 7, 5, 3, 1];
+        assert_eq!(descending.bl_peak(), 0);
+        assert_eq!(descending.bl_binary_search_bitonic(&3), Some(3));
+        assert_eq!(descending.bl_binary_search_bitonic(&10), None);
+
+        // The ascending side wins when a value is on both sides of the peak.
+        let both = [1, 4, 6, 9, 6, 2];
+        assert_eq!(both.bl_peak(), 3);
+        assert_eq!(both.bl_binary_search_bitonic(&6), Some(2));
+        assert_eq!(both.bl_binary_search_bitonic(&2), Some(5));
+    }
+
+    #[test]
+    fn test_plateaus() {
+        // All equal: every element is a maximum, and the first is returned.
+        assert_eq!([5, 5, 5, 5].bl_peak(), 0);
+        assert_eq!([5, 5, 5, 5].bl_binary_search_bitonic(&5), Some(0));
+
+        // A plateau at the peak is found somewhere along it.
+        let plateau = [1, 2, 7, 7, 7, 3];
+        assert_eq!(plateau[plateau.bl_peak()], 7);
+
+        // Elsewhere, the result is only a local maximum.
+        let slice = [1, 3, 3, 3, 3, 3, 8, 2];
+        let peak = slice.bl_peak();
+        assert!(peak == 0 || slice[peak - 1] < slice[peak]);
+        assert!(peak + 1 == slice.len() || slice[peak] >= slice[peak + 1]);
+    }
+
+    #[test]
+    fn test_by_and_by_key() {
+        let sweep = [(1, 'a'), (4, 'b'), (8, 'c'), (5, 'd'), (2, 'e')];
+        assert_eq!(sweep.bl_peak_by_key(|x| x.0), 2);
+        assert_eq!(sweep.bl_peak_by(|a, b| a.0.cmp(&b.0)), 2);
+        assert_eq!(sweep.bl_binary_search_bitonic_by_key(&5, |x| x.0), Some(3));
+        assert_eq!(
+            sweep.bl_binary_search_bitonic_by(&(4, 'z'), |a, b| a.0.cmp(&b.0)),
+            Some(1)
+        );
+        assert_eq!(sweep.bl_binary_search_bitonic_by_key(&3, |x| x.0), None);
+    }
+
+    #[test]
+    fn test_against_linear_scan() {
+        let mut rng = XorShift::new(190);
+        for _ in 0..2000 {
+            let slice = bitonic(&mut rng);
+            assert_eq!(slice.bl_peak(), reference::peak(&slice), "{slice:?}");
+            for x in 0..=41 {
+                assert_eq!(
+                    slice.bl_binary_search_bitonic(&x),
+                    slice.iter().position(|&e| e == x),
+                    "{slice:?} {x}"
+                );
+            }
+        }
+    }
+}
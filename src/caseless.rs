@@ -0,0 +1,254 @@
+//! Case-insensitive searches of ASCII string tables, such as HTTP header names or configuration
+//! keys.
+//!
+//! Everything here orders strings by the same comparison as
+//! [`caseless_ascii`](crate::comparators::caseless_ascii): byte by byte, with ASCII letters
+//! folded to lowercase and all other bytes, including those of non-ASCII characters, compared
+//! unchanged. Nothing is allocated. Sorting a table with [`sort_key_ignore_ascii_case`] (or
+//! `caseless_ascii`) and searching it with
+//! [`bl_binary_search_ignore_ascii_case`](SharCaselessSearch::bl_binary_search_ignore_ascii_case)
+//! therefore always agree, and [`CaselessSorted`] checks that a table really is sorted that way
+//! before searching it.
+//!
+//! ```
+//! use shar_search::caseless::{sort_key_ignore_ascii_case, CaselessSorted};
+//!
+//! let mut headers = vec!["Content-Type", "accept", "X-Request-Id", "Host", "ACCEPT"];
+//! headers.sort_by_key(|h| sort_key_ignore_ascii_case(h));
+//! assert_eq!(headers, ["accept", "ACCEPT", "Content-Type", "Host", "X-Request-Id"]);
+//!
+//! let headers = CaselessSorted::new(&headers).unwrap();
+//! assert_eq!(headers.search("host"), Ok(3));
+//! assert_eq!(headers.equal_range("Accept"), 0..2);
+//! ```
+
+use core::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    ops::Range,
+};
+
+use crate::{comparators::caseless_ascii, SharBinarySearch, UnsortedError};
+
+/// Compares `a` and `b` ignoring the case of ASCII letters.
+#[inline]
+fn compare(a: &str, b: &str) -> Ordering {
+    caseless_ascii()(a, b)
+}
+
+/// A string ordered, compared and hashed ignoring the case of ASCII letters, returned by
+/// [`sort_key_ignore_ascii_case`].
+#[derive(Clone, Copy, Debug)]
+pub struct CaselessKey<'a>(&'a str);
+
+impl<'a> CaselessKey<'a> {
+    /// Returns the original string.
+    pub fn as_str(&self) -> &'a str {
+        self.0
+    }
+}
+
+impl PartialEq for CaselessKey<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_ignore_ascii_case(other.0)
+    }
+}
+
+impl Eq for CaselessKey<'_> {}
+
+impl PartialOrd for CaselessKey<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CaselessKey<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare(self.0, other.0)
+    }
+}
+
+impl Hash for CaselessKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for b in self.0.bytes() {
+            state.write_u8(b.to_ascii_lowercase());
+        }
+        // Like `str`, so that keys hashed in sequence can't run into each other.
+        state.write_u8(0xff);
+    }
+}
+
+/// Returns `s` as a key that orders it ignoring the case of ASCII letters, for sorting with
+/// [`slice::sort_by_key`], or as the key of an ordered or hashed map.
+pub fn sort_key_ignore_ascii_case(s: &str) -> CaselessKey<'_> {
+    CaselessKey(s)
+}
+
+/// Trait for searching sorted string tables ignoring the case of ASCII letters.
+pub trait SharCaselessSearch {
+    /// Binary searches this slice for `key`, ignoring the case of ASCII letters. Note it is
+    /// assumed that the slice is sorted the same way, e.g. with [`sort_key_ignore_ascii_case`].
+    ///
+    /// If there are multiple matches, such as the same name in different cases, the *first* is
+    /// returned.
+    fn bl_binary_search_ignore_ascii_case(&self, key: &str) -> Result<usize, usize>;
+}
+
+impl<S: AsRef<str>> SharCaselessSearch for [S] {
+    #[inline]
+    fn bl_binary_search_ignore_ascii_case(&self, key: &str) -> Result<usize, usize> {
+        self.bl_binary_search_by(|p| compare(p.as_ref(), key))
+    }
+}
+
+/// A string table checked to be sorted ignoring the case of ASCII letters, so it can be searched
+/// that way without the sort order and the search drifting apart.
+#[derive(Debug)]
+pub struct CaselessSorted<'a, S = &'a str> {
+    slice: &'a [S],
+}
+
+impl<S> Clone for CaselessSorted<'_, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S> Copy for CaselessSorted<'_, S> {}
+
+impl<'a, S: AsRef<str>> CaselessSorted<'a, S> {
+    /// Wraps `slice`, checking that it is sorted ignoring the case of ASCII letters.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`UnsortedError`] with the index of the first string that is less than its
+    /// predecessor, ignoring case, if `slice` is not sorted that way.
+    pub fn new(slice: &'a [S]) -> Result<Self, UnsortedError> {
+        match slice
+            .windows(2)
+            .position(|w| compare(w[0].as_ref(), w[1].as_ref()).is_gt())
+        {
+            Some(index) => Err(UnsortedError { index: index + 1 }),
+            None => Ok(Self { slice }),
+        }
+    }
+
+    /// Returns the wrapped slice.
+    pub fn as_slice(&self) -> &'a [S] {
+        self.slice
+    }
+
+    /// Binary searches for `key`, ignoring the case of ASCII letters. If there are multiple
+    /// matches, the first is returned.
+    pub fn search(&self, key: &str) -> Result<usize, usize> {
+        self.slice.bl_binary_search_ignore_ascii_case(key)
+    }
+
+    /// Returns the first string equal to `key` ignoring the case of ASCII letters, if any.
+    pub fn get(&self, key: &str) -> Option<&'a S> {
+        self.search(key).ok().map(|index| &self.slice[index])
+    }
+
+    /// Returns the range of indices of the strings equal to `key` ignoring the case of ASCII
+    /// letters. The range is empty (and starts at the insertion point) if there are none.
+    pub fn equal_range(&self, key: &str) -> Range<usize> {
+        self.slice.bl_equal_range_by(|p| compare(p.as_ref(), key))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    const TABLE: [&str; 8] = [
+        "Accept",
+        "accept",
+        "ACCEPT-Encoding",
+        "content-length",
+        "Content-Type",
+        "host",
+        "X-Forwarded-For",
+        "x-request-id",
+    ];
+
+    #[test]
+    fn test_keys_differing_in_case() {
+        let table = CaselessSorted::new(&TABLE).unwrap();
+
+        for (i, name) in TABLE.iter().enumerate().skip(2) {
+            for key in [
+                name.to_string(),
+                name.to_ascii_lowercase(),
+                name.to_ascii_uppercase(),
+            ] {
+                assert_eq!(table.search(&key), Ok(i));
+                assert_eq!(table.get(&key), Some(name));
+            }
+        }
+
+        assert_eq!(table.search("Connection"), Err(3));
+        assert_eq!(table.search("ZZZ"), Err(8));
+        assert_eq!(table.search(""), Err(0));
+    }
+
+    #[test]
+    fn test_duplicates_leftmost() {
+        let table = CaselessSorted::new(&TABLE).unwrap();
+
+        for key in ["accept", "ACCEPT", "aCcEpT"] {
+            assert_eq!(table.search(key), Ok(0));
+            assert_eq!(table.get(key), Some(&"Accept"));
+            assert_eq!(table.equal_range(key), 0..2);
+        }
+        assert_eq!(table.equal_range("accept-charset"), 2..2);
+
+        let owned: Vec<String> = ["a", "B", "b", "B", "c"].map(String::from).to_vec();
+        assert_eq!(owned.bl_binary_search_ignore_ascii_case("b"), Ok(1));
+        assert_eq!(CaselessSorted::new(&owned).unwrap().equal_range("B"), 1..4);
+    }
+
+    #[test]
+    fn test_non_ascii_unchanged() {
+        // 'É' (C3 89) and 'é' (C3 A9) are different bytes that ASCII folding leaves alone, and
+        // both sort after every ASCII letter.
+        let table = ["caf\u{e9}", "CAF\u{e9}s", "caf\u{c9}"];
+        assert!(CaselessSorted::new(&table).is_err());
+
+        let mut sorted = table;
+        sorted.sort_by_key(|s| sort_key_ignore_ascii_case(s));
+        assert_eq!(sorted, ["caf\u{c9}", "caf\u{e9}", "CAF\u{e9}s"]);
+
+        let sorted = CaselessSorted::new(&sorted).unwrap();
+        assert_eq!(sorted.search("CAF\u{c9}"), Ok(0));
+        assert_eq!(sorted.search("caf\u{e9}"), Ok(1));
+        assert_eq!(sorted.search("CAF\u{e9}"), Ok(1));
+        assert_eq!(sorted.search("caf\u{e9}S"), Ok(2));
+        assert_eq!(sorted.search("cafe"), Err(0));
+    }
+
+    #[test]
+    fn test_rejects_case_sensitive_order() {
+        // Sorted by byte value, but not ignoring case.
+        let table = ["Zebra", "apple", "mango"];
+        let err = CaselessSorted::new(&table).unwrap_err();
+        assert_eq!(err.index(), 1);
+
+        assert!(CaselessSorted::<&str>::new(&[]).is_ok());
+        assert!(CaselessSorted::new(&["same", "SAME", "Same"]).is_ok());
+    }
+
+    #[test]
+    fn test_key_traits() {
+        let keys: HashSet<_> = ["Host", "HOST", "host", "Accept"]
+            .into_iter()
+            .map(sort_key_ignore_ascii_case)
+            .collect();
+        assert_eq!(keys.len(), 2);
+        assert!(keys.contains(&sort_key_ignore_ascii_case("hOsT")));
+
+        assert!(sort_key_ignore_ascii_case("a") < sort_key_ignore_ascii_case("B"));
+        assert_eq!(sort_key_ignore_ascii_case("Host").as_str(), "Host");
+    }
+}
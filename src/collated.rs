@@ -0,0 +1,338 @@
+//! Searches of string tables sorted by an ICU collator, such as localized lists in a UI.
+//!
+//! A table sorted for display with [`icu_collator`] is generally not sorted by `str::cmp`:
+//! German sorts `"Äpfel"` next to `"Apfel"`, Swedish sorts `'å'`, `'ä'` and `'ö'` after `'z'`,
+//! and neither cares about byte values. Searching such a table needs the same collator, with the
+//! same options. [`CollatedSorted`] keeps the table and its collator together, checks the order
+//! once when it is built, and can cache the collation sort keys of the table for hot paths.
+//!
+//! ```
+//! use icu_collator::{options::CollatorOptions, Collator};
+//! use icu_locale_core::locale;
+//! use shar_search::collated::CollatedSorted;
+//!
+//! let swedish = Collator::try_new(locale!("sv").into(), CollatorOptions::default()).unwrap();
+//!
+//! let words = ["arg", "zon", "ål", "äng", "öl"];
+//! let words = CollatedSorted::new(&words, &swedish).unwrap();
+//! assert_eq!(words.search("äng"), Ok(3));
+//! assert_eq!(words.search("örn"), Err(5));
+//! ```
+
+use alloc::{boxed::Box, vec::Vec};
+use core::{cmp::Ordering, ops::Range};
+
+use icu_collator::CollatorBorrowed;
+
+use crate::{SharBinarySearch, UnsortedError};
+
+/// Returns the sort key of `s` under `collator`, which compares bytewise like the collator
+/// compares the strings.
+fn sort_key(collator: &CollatorBorrowed<'_>, s: &str) -> Vec<u8> {
+    let mut key = Vec::new();
+    let Ok(()) = collator.write_sort_key_to(s, &mut key);
+    key
+}
+
+/// Returns the index of the first element of `slice` that is less than its predecessor, if any.
+fn first_unsorted<T, F>(slice: &[T], mut compare: F) -> Option<usize>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    slice
+        .windows(2)
+        .position(|w| compare(&w[0], &w[1]).is_gt())
+        .map(|index| index + 1)
+}
+
+/// Trait for searching string tables sorted by an ICU collator.
+pub trait SharCollatedSearch {
+    /// Binary searches this slice for `key`, comparing with `collator`. Note it is assumed that
+    /// the slice is sorted by the same collator. An owned [`Collator`](icu_collator::Collator)
+    /// lends one with [`as_borrowed`](icu_collator::Collator::as_borrowed).
+    ///
+    /// If there are multiple matches, such as strings that only differ at a level the collator
+    /// ignores, the *first* is returned.
+    fn bl_binary_search_collated(
+        &self,
+        key: &str,
+        collator: &CollatorBorrowed<'_>,
+    ) -> Result<usize, usize>;
+}
+
+impl<S: AsRef<str>> SharCollatedSearch for [S] {
+    #[inline]
+    fn bl_binary_search_collated(
+        &self,
+        key: &str,
+        collator: &CollatorBorrowed<'_>,
+    ) -> Result<usize, usize> {
+        self.bl_binary_search_by(|p| collator.compare(p.as_ref(), key))
+    }
+}
+
+/// A string table checked to be sorted by an ICU collator, which it keeps, so that the table is
+/// always searched with the collation it was checked against.
+#[derive(Debug)]
+pub struct CollatedSorted<'a, S = &'a str> {
+    slice: &'a [S],
+    collator: &'a CollatorBorrowed<'a>,
+    /// The sort keys of `slice`, if they are cached.
+    sort_keys: Option<Vec<Box<[u8]>>>,
+}
+
+impl<'a, S: AsRef<str>> CollatedSorted<'a, S> {
+    /// Wraps `slice`, checking that it is sorted by `collator`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`UnsortedError`] with the index of the first string that collates before its
+    /// predecessor, if `slice` is not sorted by `collator`.
+    pub fn new(slice: &'a [S], collator: &'a CollatorBorrowed<'a>) -> Result<Self, UnsortedError> {
+        match first_unsorted(slice, |a, b| collator.compare(a.as_ref(), b.as_ref())) {
+            Some(index) => Err(UnsortedError { index }),
+            None => Ok(Self {
+                slice,
+                collator,
+                sort_keys: None,
+            }),
+        }
+    }
+
+    /// Wraps `slice` like [`new`](Self::new), and computes and keeps the collation sort key of
+    /// every string. Searches then compute the sort key of the search key once and compare bytes,
+    /// which is faster for long tables that are searched often, at the cost of memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`UnsortedError`] with the index of the first string that collates before its
+    /// predecessor, if `slice` is not sorted by `collator`.
+    pub fn with_sort_keys(
+        slice: &'a [S],
+        collator: &'a CollatorBorrowed<'a>,
+    ) -> Result<Self, UnsortedError> {
+        let sort_keys: Vec<Box<[u8]>> = slice
+            .iter()
+            .map(|s| sort_key(collator, s.as_ref()).into_boxed_slice())
+            .collect();
+
+        match first_unsorted(&sort_keys, |a, b| a.cmp(b)) {
+            Some(index) => Err(UnsortedError { index }),
+            None => Ok(Self {
+                slice,
+                collator,
+                sort_keys: Some(sort_keys),
+            }),
+        }
+    }
+
+    /// Returns the wrapped slice.
+    pub fn as_slice(&self) -> &'a [S] {
+        self.slice
+    }
+
+    /// Returns the collator the slice is sorted by.
+    pub fn collator(&self) -> &'a CollatorBorrowed<'a> {
+        self.collator
+    }
+
+    /// Returns whether the sort keys of the slice are cached.
+    pub fn has_sort_keys(&self) -> bool {
+        self.sort_keys.is_some()
+    }
+
+    /// Binary searches for `key` by the collation. If there are multiple matches, the first is
+    /// returned.
+    pub fn search(&self, key: &str) -> Result<usize, usize> {
+        match &self.sort_keys {
+            Some(sort_keys) => {
+                let key = sort_key(self.collator, key);
+                sort_keys.bl_binary_search_by(|p| p[..].cmp(&key))
+            }
+            None => self.slice.bl_binary_search_collated(key, self.collator),
+        }
+    }
+
+    /// Returns the first string that collates equal to `key`, if any.
+    pub fn get(&self, key: &str) -> Option<&'a S> {
+        self.search(key).ok().map(|index| &self.slice[index])
+    }
+
+    /// Returns the range of indices of the strings that collate equal to `key`. The range is
+    /// empty (and starts at the insertion point) if there are none.
+    pub fn equal_range(&self, key: &str) -> Range<usize> {
+        match &self.sort_keys {
+            Some(sort_keys) => {
+                let key = sort_key(self.collator, key);
+                sort_keys.bl_equal_range_by(|p| p[..].cmp(&key))
+            }
+            None => self
+                .slice
+                .bl_equal_range_by(|p| self.collator.compare(p.as_ref(), key)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use icu_collator::{
+        options::{CollatorOptions, Strength},
+        Collator,
+    };
+    use icu_locale_core::{locale, Locale};
+
+    use super::*;
+
+    fn collator(locale: Locale, strength: Option<Strength>) -> CollatorBorrowed<'static> {
+        let mut options = CollatorOptions::default();
+        options.strength = strength;
+        Collator::try_new(locale.into(), options).unwrap()
+    }
+
+    /// Checks that both kinds of table find every string of `sorted` at its first equal index,
+    /// and agree with each other on `absent`.
+    fn check(sorted: &[&str], collator: &CollatorBorrowed<'_>, absent: &[&str]) {
+        let plain = CollatedSorted::new(sorted, collator).unwrap();
+        let keyed = CollatedSorted::with_sort_keys(sorted, collator).unwrap();
+        assert!(!plain.has_sort_keys() && keyed.has_sort_keys());
+
+        for (i, s) in sorted.iter().enumerate() {
+            let first = sorted[..i]
+                .iter()
+                .rposition(|p| collator.compare(p, s).is_lt())
+                .map_or(0, |p| p + 1);
+            assert_eq!(plain.search(s), Ok(first), "{s}");
+            assert_eq!(keyed.search(s), Ok(first), "{s}");
+            assert_eq!(sorted.bl_binary_search_collated(s, collator), Ok(first));
+            assert_eq!(plain.equal_range(s), keyed.equal_range(s), "{s}");
+        }
+        for s in absent {
+            assert!(plain.search(s).is_err(), "{s}");
+            assert_eq!(plain.search(s), keyed.search(s), "{s}");
+            assert_eq!(plain.equal_range(s), keyed.equal_range(s), "{s}");
+        }
+    }
+
+    #[test]
+    fn test_german() {
+        let german = collator(locale!("de"), None);
+
+        // Byte order puts every capital before every lowercase letter, and umlauts last.
+        let words = [
+            "Apfel", "Äpfel", "Birne", "Ofen", "Öl", "ölig", "Zucker", "zählen",
+        ];
+        assert!(!words.is_sorted());
+        let mut sorted = words;
+        sorted.sort_by(|a, b| german.compare(a, b));
+        assert_eq!(
+            sorted,
+            ["Apfel", "Äpfel", "Birne", "Ofen", "Öl", "ölig", "zählen", "Zucker"]
+        );
+
+        let table = CollatedSorted::new(&sorted, &german).unwrap();
+        assert_eq!(table.search("Öl"), Ok(4));
+        assert_eq!(table.search("Banane"), Err(2));
+        assert_eq!(table.get("zählen"), Some(&"zählen"));
+        // Byte order would put this past the end.
+        assert_eq!(table.search("Übel"), Err(6));
+        check(&sorted, &german, &["", "Apfelsine", "Ä", "Oel", "zz", "ü"]);
+    }
+
+    #[test]
+    fn test_swedish() {
+        let swedish = collator(locale!("sv"), None);
+        let german = collator(locale!("de"), None);
+
+        // Swedish sorts 'å', 'ä' and 'ö' after 'z', in that order, though 'ä' < 'å' in bytes.
+        let words = ["apa", "ärm", "åka", "öga", "zebra", "ost"];
+        let mut sorted = words;
+        sorted.sort_by(|a, b| swedish.compare(a, b));
+        assert_eq!(sorted, ["apa", "ost", "zebra", "åka", "ärm", "öga"]);
+        let mut bytewise = words;
+        bytewise.sort_unstable();
+        assert_eq!(bytewise, ["apa", "ost", "zebra", "ärm", "åka", "öga"]);
+
+        let table = CollatedSorted::with_sort_keys(&sorted, &swedish).unwrap();
+        assert_eq!(table.search("ärm"), Ok(4));
+        assert_eq!(table.search("äpple"), Err(4));
+        assert_eq!(table.search("ödla"), Err(5));
+        check(&sorted, &swedish, &["aa", "zz", "å", "öz", "ø"]);
+
+        // Byte order and German order are both rejected, at the first misplaced string.
+        assert_eq!(
+            CollatedSorted::new(&bytewise, &swedish)
+                .unwrap_err()
+                .index(),
+            4
+        );
+        let err = CollatedSorted::with_sort_keys(&bytewise, &swedish).unwrap_err();
+        assert_eq!(err.index(), 4);
+        let err = CollatedSorted::new(&sorted, &german).unwrap_err();
+        assert_eq!(err.index(), 3);
+
+        assert!(CollatedSorted::<&str>::new(&[], &swedish).is_ok());
+    }
+
+    #[test]
+    fn test_strength_duplicates() {
+        // At primary strength, case and accents are ignored.
+        let primary = collator(locale!("de"), Some(Strength::Primary));
+        let names = ["Muller", "muller", "Müller", "MÜLLER", "Schmidt"];
+        let plain = CollatedSorted::new(&names, &primary).unwrap();
+        let keyed = CollatedSorted::with_sort_keys(&names, &primary).unwrap();
+        for key in ["muller", "MULLER", "müller"] {
+            assert_eq!(plain.search(key), Ok(0));
+            assert_eq!(keyed.search(key), Ok(0));
+            assert_eq!(plain.equal_range(key), 0..4);
+            assert_eq!(keyed.equal_range(key), 0..4);
+        }
+        assert_eq!(plain.equal_range("Schmid"), 4..4);
+
+        // The default tertiary strength tells them apart.
+        let tertiary = collator(locale!("de"), None);
+        assert!(CollatedSorted::new(&names, &tertiary).is_err());
+    }
+
+    #[test]
+    fn test_mixed_scripts() {
+        let root = collator(locale!("und"), None);
+
+        // Latin, then Greek, then Cyrillic, then Han; digits before all letters.
+        let mut words = [
+            "日本",
+            "zebra",
+            "Ωμέγα",
+            "жук",
+            "42",
+            "apple",
+            "αλφα",
+            "Яблоко",
+            "中文",
+            "Émile",
+        ];
+        words.sort_by(|a, b| root.compare(a, b));
+        assert_eq!(
+            words[..8],
+            [
+                "42",
+                "apple",
+                "Émile",
+                "zebra",
+                "αλφα",
+                "Ωμέγα",
+                "жук",
+                "Яблоко"
+            ]
+        );
+        check(&words, &root, &["", "7", "ε", "Ж", "東京", "zz"]);
+
+        let table = CollatedSorted::new(&words, &root).unwrap();
+        assert_eq!(table.search("beta"), Err(2));
+        assert_eq!(table.search("βήτα"), Err(5));
+
+        let owned: Vec<String> = words.iter().map(|s| s.to_string()).collect();
+        let table = CollatedSorted::with_sort_keys(&owned, &root).unwrap();
+        assert_eq!(table.get("жук").map(String::as_str), Some("жук"));
+        assert_eq!(table.as_slice().len(), 10);
+    }
+}
@@ -0,0 +1,242 @@
+//! Lexicographic searches over composite keys stored as parallel columns.
+//!
+//! A columnar table stores each field of a composite key in its own slice, such as
+//! `(years, months, ids)`, with the rows sorted lexicographically as a whole. The searches here
+//! compare a row to a key one column at a time, stopping at the first column that differs,
+//! without materializing the rows.
+//!
+//! Keys may fix all of the columns, or only the leading ones: with a one-column key `(year,)`,
+//! [`lex_prefix_range`] returns the rows of that year.
+
+use core::{cmp::Ordering, ops::Range};
+
+/// A tuple of column slices of equal length, whose rows are sorted lexicographically.
+/// Implemented for tuples of two and three slices.
+pub trait ColumnSet: Copy {
+    /// Returns the number of rows, or `None` if the columns have different lengths.
+    fn row_count(&self) -> Option<usize>;
+}
+
+/// A key to search a [`ColumnSet`] for, fixing its leading columns.
+///
+/// For columns `(&[A], &[B], &[C])`, this is implemented for `(A, B, C)`, `(A, B)` and `(A,)`.
+pub trait ColumnKey<C: ColumnSet> {
+    /// Returns the ordering of row `row` of `columns` relative to this key, comparing only the
+    /// columns this key fixes.
+    fn compare_row(&self, columns: C, row: usize) -> Ordering;
+}
+
+macro_rules! impl_columns {
+    ($first:ident $($rest:ident)*; $($index:tt)*) => {
+        impl<'a, $first, $($rest),*> ColumnSet for (&'a [$first], $(&'a [$rest]),*) {
+            fn row_count(&self) -> Option<usize> {
+                let rows = self.0.len();
+                [$(self.$index.len()),*].iter().all(|&len| len == rows).then_some(rows)
+            }
+        }
+    };
+}
+
+impl_columns!(A B; 1);
+impl_columns!(A B C; 1 2);
+
+macro_rules! impl_column_key {
+    (($($column:ident),*); $($key:ident $index:tt),*) => {
+        impl<'a, $($column),*> ColumnKey<($(&'a [$column],)*)> for ($($key,)*)
+        where
+            $($key: Ord,)*
+        {
+            fn compare_row(&self, columns: ($(&'a [$column],)*), row: usize) -> Ordering {
+                Ordering::Equal $(.then_with(|| columns.$index[row].cmp(&self.$index)))*
+            }
+        }
+    };
+}
+
+impl_column_key!((A, B); A 0);
+impl_column_key!((A, B); A 0, B 1);
+impl_column_key!((A, B, C); A 0);
+impl_column_key!((A, B, C); A 0, B 1);
+impl_column_key!((A, B, C); A 0, B 1, C 2);
+
+/// Returns the row count of `columns`, panicking if the columns have different lengths.
+fn row_count<C: ColumnSet>(columns: C) -> usize {
+    columns
+        .row_count()
+        .expect("the columns must have the same length")
+}
+
+/// Returns the first row for which `pred` is false, using the branchless search over row
+/// indices. Note it is assumed that the rows are partitioned by `pred`.
+pub(crate) fn partition_point<P>(rows: usize, mut pred: P) -> usize
+where
+    P: FnMut(usize) -> bool,
+{
+    if rows == 0 {
+        return 0;
+    }
+
+    let mut base = 0;
+    let mut size = rows;
+    while size > 1 {
+        let half = size / 2;
+        if pred(base + half) {
+            base += half;
+        }
+        size -= half;
+    }
+
+    base + usize::from(pred(base))
+}
+
+/// Binary searches the rows of `columns` for `key`, returning the row index. Note it is
+/// assumed that the rows are sorted lexicographically.
+///
+/// As with [`bl_binary_search`](crate::SharBinarySearch::bl_binary_search), if there are
+/// multiple matches, the *first* is returned. A key that fixes only the leading columns matches
+/// every row that starts with it.
+///
+/// # Panics
+///
+/// Panics if the columns have different lengths.
+///
+/// ```
+/// use shar_search::columns::{lex_prefix_range, lex_search};
+///
+/// let years: &[u16] = &[2023, 2023, 2024, 2024, 2024];
+/// let months: &[u8] = &[11, 12, 1, 1, 3];
+/// let ids: &[u64] = &[7, 2, 5, 9, 1];
+/// let table = (years, months, ids);
+///
+/// assert_eq!(lex_search(table, &(2024, 1, 9)), Ok(3));
+/// assert_eq!(lex_search(table, &(2024, 2, 0)), Err(4));
+/// assert_eq!(lex_prefix_range(table, &(2024,)), 2..5);
+/// assert_eq!(lex_prefix_range(table, &(2024, 1)), 2..4);
+/// ```
+pub fn lex_search<C, K>(columns: C, key: &K) -> Result<usize, usize>
+where
+    C: ColumnSet,
+    K: ColumnKey<C>,
+{
+    let rows = row_count(columns);
+    let index = partition_point(rows, |row| key.compare_row(columns, row).is_lt());
+
+    if index < rows && key.compare_row(columns, index).is_eq() {
+        Ok(index)
+    } else {
+        Err(index)
+    }
+}
+
+/// Returns the first row of `columns` that is not less than `key`. Note it is assumed that the
+/// rows are sorted lexicographically.
+///
+/// # Panics
+///
+/// Panics if the columns have different lengths.
+pub fn lex_lower_bound<C, K>(columns: C, key: &K) -> usize
+where
+    C: ColumnSet,
+    K: ColumnKey<C>,
+{
+    partition_point(row_count(columns), |row| {
+        key.compare_row(columns, row).is_lt()
+    })
+}
+
+/// Returns the first row of `columns` that is greater than `key`. Note it is assumed that the
+/// rows are sorted lexicographically.
+///
+/// # Panics
+///
+/// Panics if the columns have different lengths.
+pub fn lex_upper_bound<C, K>(columns: C, key: &K) -> usize
+where
+    C: ColumnSet,
+    K: ColumnKey<C>,
+{
+    partition_point(row_count(columns), |row| {
+        key.compare_row(columns, row).is_le()
+    })
+}
+
+/// Returns the range of rows of `columns` that start with `prefix`, a key fixing only the
+/// leading columns (or all of them). The range is empty (and starts at the insertion point) if
+/// there are none. Note it is assumed that the rows are sorted lexicographically.
+///
+/// # Panics
+///
+/// Panics if the columns have different lengths.
+pub fn lex_prefix_range<C, K>(columns: C, prefix: &K) -> Range<usize>
+where
+    C: ColumnSet,
+    K: ColumnKey<C>,
+{
+    lex_lower_bound(columns, prefix)..lex_upper_bound(columns, prefix)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{lex_lower_bound, lex_prefix_range, lex_search, lex_upper_bound};
+    use crate::{test_util::XorShift, SharBinarySearch};
+
+    #[test]
+    fn test_against_rows() {
+        let mut rng = XorShift::new(71);
+
+        for _ in 0..100 {
+            // Few distinct leading values, so they repeat.
+            let mut rows: Vec<(u8, u16, u32)> = (0..rng.below(80))
+                .map(|_| {
+                    (
+                        rng.below(4) as u8,
+                        rng.below(6) as u16,
+                        rng.below(10) as u32,
+                    )
+                })
+                .collect();
+            rows.sort();
+            let a: Vec<_> = rows.iter().map(|r| r.0).collect();
+            let b: Vec<_> = rows.iter().map(|r| r.1).collect();
+            let c: Vec<_> = rows.iter().map(|r| r.2).collect();
+            let table = (&a[..], &b[..], &c[..]);
+            let pairs = (&a[..], &b[..]);
+
+            for x in 0..5 {
+                let range = rows.bl_equal_range_by(|r| r.0.cmp(&x));
+                assert_eq!(lex_prefix_range(table, &(x,)), range);
+                assert_eq!(lex_prefix_range(pairs, &(x,)), range);
+
+                for y in 0..7 {
+                    let range = rows.bl_equal_range_by(|r| (r.0, r.1).cmp(&(x, y)));
+                    assert_eq!(lex_prefix_range(table, &(x, y)), range);
+                    assert_eq!(lex_prefix_range(pairs, &(x, y)), range);
+                    assert_eq!(lex_search(pairs, &(x, y)).is_ok(), !range.is_empty());
+
+                    for z in 0..11 {
+                        let key = (x, y, z);
+                        assert_eq!(lex_search(table, &key), rows.bl_binary_search(&key));
+                        assert_eq!(lex_lower_bound(table, &key), rows.bl_lower_bound(&key));
+                        assert_eq!(lex_upper_bound(table, &key), rows.bl_upper_bound(&key));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let a: [u32; 0] = [];
+        let b: [u32; 0] = [];
+        assert_eq!(lex_search((&a[..], &b[..]), &(1, 2)), Err(0));
+        assert_eq!(lex_prefix_range((&a[..], &b[..]), &(1,)), 0..0);
+    }
+
+    #[test]
+    #[should_panic(expected = "the columns must have the same length")]
+    fn test_length_mismatch() {
+        let a = [1, 2, 3];
+        let b = [1, 2];
+        let _ = lex_search((&a[..], &b[..]), &(1,));
+    }
+}
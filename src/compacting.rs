@@ -0,0 +1,454 @@
+//! A sorted map whose removals leave tombstones, for delete-heavy workloads.
+
+use alloc::vec::Vec;
+use core::{borrow::Borrow, fmt, iter::FusedIterator, ops::RangeBounds, slice};
+
+use crate::{resolve_range, SharBinarySearch, SharMap};
+
+/// The default fraction of tombstones at which a [`CompactingSharMap`] compacts itself.
+pub const DEFAULT_MAX_GARBAGE: f64 = 0.5;
+
+/// A map with unique keys, stored like a [`SharMap`] but removing entries by marking them as
+/// tombstones rather than shifting the rest of the vector.
+///
+/// A removal is then a search plus a flag, `O(log n)`. A tombstone keeps its key in place, so
+/// lookups are unchanged and simply treat it as absent, and iteration skips it. Inserting a
+/// key that is tombstoned revives its slot in `O(log n)` too, while inserting a new key still
+/// shifts elements like [`SharMap::insert`].
+///
+/// Once tombstones make up more than a configurable fraction of the stored entries (by default
+/// [`DEFAULT_MAX_GARBAGE`]), the removal that crossed it calls [`CompactingSharMap::compact`],
+/// which drops them all in one `O(n)` pass. Since that takes at least a fraction of `n`
+/// removals to come around again, removal stays `O(log n)` amortized.
+///
+/// ```
+/// use shar_search::compacting::CompactingSharMap;
+///
+/// let mut map: CompactingSharMap<u32, &str> = CompactingSharMap::with_max_garbage(0.25);
+/// for (k, v) in [(1, "a"), (2, "b"), (3, "c"), (4, "d")] {
+///     map.insert(k, v);
+/// }
+///
+/// assert_eq!(map.remove(&2), Some("b"));
+/// assert_eq!(map.len(), 3);
+/// assert_eq!(map.capacity_live_ratio(), 0.75);
+///
+/// // A second tombstone is over a quarter of the entries, so the map compacts.
+/// map.remove(&3);
+/// assert_eq!(map.capacity_live_ratio(), 1.0);
+/// assert!(map.iter().eq([(&1, &"a"), (&4, &"d")]));
+/// ```
+#[derive(Clone)]
+pub struct CompactingSharMap<K, V> {
+    /// Sorted by key, with `None` for a tombstone.
+    entries: Vec<(K, Option<V>)>,
+    live: usize,
+    max_garbage: f64,
+}
+
+impl<K, V> Default for CompactingSharMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for CompactingSharMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V> CompactingSharMap<K, V> {
+    /// Creates a new, empty map that compacts at [`DEFAULT_MAX_GARBAGE`].
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            live: 0,
+            max_garbage: DEFAULT_MAX_GARBAGE,
+        }
+    }
+
+    /// Creates a new, empty map that compacts itself once more than `max_garbage` of its
+    /// stored entries are tombstones. With `0.0`, every removal compacts, like a plain
+    /// [`SharMap`]; with `1.0`, the map never compacts on its own.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_garbage` is not between `0.0` and `1.0`.
+    pub fn with_max_garbage(max_garbage: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&max_garbage),
+            "max_garbage must be between 0 and 1"
+        );
+
+        Self {
+            max_garbage,
+            ..Self::new()
+        }
+    }
+
+    /// Returns the number of live entries in the map, not counting tombstones.
+    pub fn len(&self) -> usize {
+        self.live
+    }
+
+    /// Returns whether the map has no live entries.
+    pub fn is_empty(&self) -> bool {
+        self.live == 0
+    }
+
+    /// Returns the fraction of stored entries that are live, from `0.0` to `1.0`. The rest are
+    /// tombstones waiting to be compacted. An empty map has a ratio of `1.0`.
+    pub fn capacity_live_ratio(&self) -> f64 {
+        if self.entries.is_empty() {
+            1.0
+        } else {
+            self.live as f64 / self.entries.len() as f64
+        }
+    }
+
+    /// Drops every tombstone, in one pass.
+    pub fn compact(&mut self) {
+        if self.live < self.entries.len() {
+            self.entries.retain(|(_, v)| v.is_some());
+        }
+    }
+
+    /// Removes all entries, along with the tombstones.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.live = 0;
+    }
+
+    /// Returns an iterator over the live pairs of the map, in key order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            inner: self.entries.iter(),
+        }
+    }
+
+    /// Returns an iterator over the keys of the map, in order.
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> + '_ {
+        self.iter().map(|(k, _)| k)
+    }
+
+    /// Returns an iterator over the values of the map, in key order.
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> + '_ {
+        self.iter().map(|(_, v)| v)
+    }
+
+    /// Compacts the map and converts it into a [`SharMap`].
+    pub fn into_map(self) -> SharMap<K, V> {
+        let entries = self
+            .entries
+            .into_iter()
+            .filter_map(|(k, v)| Some((k, v?)))
+            .collect();
+        SharMap::from_sorted_vec_unchecked(entries)
+    }
+}
+
+impl<K: Ord, V> CompactingSharMap<K, V> {
+    fn search<Q>(&self, key: &Q) -> Result<usize, usize>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.entries
+            .bl_binary_search_by(|(k, _)| k.borrow().cmp(key))
+    }
+
+    /// Inserts a key-value pair, returning the previous value for the key if there was one.
+    ///
+    /// If the key is live, only its value is replaced, like [`SharMap::insert`]. If it is
+    /// tombstoned, its slot is revived with both the new key and value, as the old key belongs
+    /// to an entry that was already removed.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.search(&key) {
+            Ok(index) => {
+                let slot = &mut self.entries[index];
+                if slot.1.is_none() {
+                    *slot = (key, Some(value));
+                    self.live += 1;
+                    None
+                } else {
+                    slot.1.replace(value)
+                }
+            }
+            Err(index) => {
+                self.entries.insert(index, (key, Some(value)));
+                self.live += 1;
+                None
+            }
+        }
+    }
+
+    /// Returns whether the map contains `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Returns a reference to the value for `key`, if present.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search(key)
+            .ok()
+            .and_then(|index| self.entries[index].1.as_ref())
+    }
+
+    /// Returns a mutable reference to the value for `key`, if present.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search(key)
+            .ok()
+            .and_then(move |index| self.entries[index].1.as_mut())
+    }
+
+    /// Removes `key` from the map by tombstoning it, returning its value if it was present.
+    /// This compacts the map if the tombstones now exceed its threshold.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let index = self.search(key).ok()?;
+        let value = self.entries[index].1.take()?;
+        self.live -= 1;
+
+        let dead = self.entries.len() - self.live;
+        if dead as f64 > self.max_garbage * self.entries.len() as f64 {
+            self.compact();
+        }
+        Some(value)
+    }
+
+    /// Returns a double-ended iterator over the live pairs whose keys are within `range`, in
+    /// key order. If the start of the range lies after its end, the iterator is empty.
+    pub fn range<Q, R>(&self, range: R) -> Iter<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let indices = resolve_range(&self.entries, &range, |(k, _)| k.borrow());
+
+        Iter {
+            inner: self.entries[indices].iter(),
+        }
+    }
+}
+
+impl<K, V> From<SharMap<K, V>> for CompactingSharMap<K, V> {
+    fn from(map: SharMap<K, V>) -> Self {
+        let entries: Vec<_> = map
+            .into_vec()
+            .into_iter()
+            .map(|(k, v)| (k, Some(v)))
+            .collect();
+
+        Self {
+            live: entries.len(),
+            entries,
+            max_garbage: DEFAULT_MAX_GARBAGE,
+        }
+    }
+}
+
+/// An iterator over the live pairs of a [`CompactingSharMap`], in key order.
+///
+/// Created by [`CompactingSharMap::iter`] and [`CompactingSharMap::range`].
+#[derive(Clone)]
+pub struct Iter<'a, K, V> {
+    inner: slice::Iter<'a, (K, Option<V>)>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.find_map(|(k, v)| Some((k, v.as_ref()?)))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.inner.size_hint().1)
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .by_ref()
+            .rev()
+            .find_map(|(k, v)| Some((k, v.as_ref()?)))
+    }
+}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+#[cfg(test)]
+mod test {
+    use std::{cmp::Ordering, collections::BTreeMap};
+
+    use super::*;
+    use crate::test_util::XorShift;
+
+    /// Compares only by key, so which key object is stored is observable through the tag.
+    #[derive(Debug)]
+    struct Tagged(u32, char);
+
+    impl PartialEq for Tagged {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+
+    impl Eq for Tagged {}
+
+    impl PartialOrd for Tagged {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Tagged {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.0.cmp(&other.0)
+        }
+    }
+
+    #[test]
+    fn test_tombstones() {
+        let mut map = CompactingSharMap::with_max_garbage(1.0);
+        for k in 0..10 {
+            map.insert(k, k * 10);
+        }
+
+        assert_eq!(map.remove(&3), Some(30));
+        assert_eq!(map.remove(&3), None);
+        assert_eq!(map.remove(&42), None);
+        assert_eq!(map.get(&3), None);
+        assert!(!map.contains_key(&3));
+        assert_eq!(map.get_mut(&3), None);
+        assert_eq!(map.len(), 9);
+        assert_eq!(map.capacity_live_ratio(), 0.9);
+
+        map.compact();
+        assert_eq!(map.capacity_live_ratio(), 1.0);
+        assert_eq!(map.len(), 9);
+        assert_eq!(map.into_map().len(), 9);
+    }
+
+    #[test]
+    fn test_reinsert_after_tombstone() {
+        let mut map = CompactingSharMap::with_max_garbage(1.0);
+        map.insert(Tagged(1, 'a'), "first");
+        map.insert(Tagged(2, 'b'), "second");
+
+        // Replacing a live value keeps the stored key.
+        assert_eq!(map.insert(Tagged(1, 'c'), "replaced"), Some("first"));
+        assert_eq!(map.iter().next().unwrap().0 .1, 'a');
+
+        // Reviving a tombstone stores the new key, and is not a replacement.
+        assert_eq!(map.remove(&Tagged(1, '-')), Some("replaced"));
+        assert_eq!(map.insert(Tagged(1, 'd'), "revived"), None);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.capacity_live_ratio(), 1.0);
+        let (key, value) = map.iter().next().unwrap();
+        assert_eq!((key.1, *value), ('d', "revived"));
+        assert_eq!(map.remove(&Tagged(1, '-')), Some("revived"));
+    }
+
+    #[test]
+    fn test_range() {
+        let mut map = CompactingSharMap::with_max_garbage(1.0);
+        for k in 0..10 {
+            map.insert(k * 10, k);
+        }
+        // Tombstones at the ends of the ranges below, and inside them.
+        for k in [20, 40, 50, 90] {
+            map.remove(&k);
+        }
+
+        assert!(map.range(20..50).map(|(k, _)| *k).eq([30]));
+        assert!(map.range(20..=60).rev().map(|(k, _)| *k).eq([60, 30]));
+        assert!(map.range(85..).next().is_none());
+        assert!(map.range(..).map(|(k, _)| *k).eq([0, 10, 30, 60, 70, 80]));
+
+        // From both ends at once.
+        let mut range = map.range(10..=70);
+        assert_eq!(range.next(), Some((&10, &1)));
+        assert_eq!(range.next_back(), Some((&70, &7)));
+        assert_eq!(range.next_back(), Some((&60, &6)));
+        assert_eq!(range.next(), Some((&30, &3)));
+        assert_eq!(range.next(), None);
+        assert_eq!(range.next_back(), None);
+    }
+
+    #[test]
+    fn test_auto_compact() {
+        let mut map: CompactingSharMap<u32, ()> = CompactingSharMap::new();
+        for k in 0..100 {
+            map.insert(k, ());
+        }
+
+        // Half the entries may be tombstones before the map compacts.
+        for k in 0..50 {
+            map.remove(&k);
+        }
+        assert_eq!(map.capacity_live_ratio(), 0.5);
+        map.remove(&50);
+        assert_eq!(map.capacity_live_ratio(), 1.0);
+        assert_eq!(map.len(), 49);
+
+        // With no garbage allowed, every removal compacts.
+        let mut map = CompactingSharMap::with_max_garbage(0.0);
+        map.insert(1, ());
+        map.insert(2, ());
+        map.remove(&1);
+        assert_eq!(map.capacity_live_ratio(), 1.0);
+    }
+
+    #[test]
+    fn test_against_btree_map() {
+        let mut rng = XorShift::new(158);
+        let mut map = CompactingSharMap::with_max_garbage(0.3);
+        let mut reference = BTreeMap::new();
+
+        for _ in 0..5000 {
+            let key = rng.below(200);
+            if rng.below(3) == 0 {
+                let value = rng.below(100);
+                assert_eq!(map.insert(key, value), reference.insert(key, value));
+            } else {
+                assert_eq!(map.remove(&key), reference.remove(&key));
+            }
+            assert_eq!(map.len(), reference.len());
+            assert!(map.capacity_live_ratio() >= 0.7);
+
+            let (low, high) = (rng.below(200), rng.below(200));
+            if low <= high {
+                assert!(map.range(low..high).eq(reference.range(low..high)));
+            }
+        }
+        assert!(map.iter().eq(reference.iter()));
+    }
+
+    #[test]
+    #[should_panic(expected = "between 0 and 1")]
+    fn test_invalid_max_garbage() {
+        CompactingSharMap::<u8, u8>::with_max_garbage(1.5);
+    }
+}
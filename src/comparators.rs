@@ -0,0 +1,289 @@
+//! Composable comparators, for searching (and sorting) with orderings other than [`Ord`].
+//!
+//! Each function here returns a comparator: a closure `Fn(&T, &T) -> Ordering`. A comparator
+//! can be passed directly to [`slice::sort_by`] and to this crate's `_by` functions that take one,
+//! like [`join::inner_join_by`](crate::join::inner_join_by), so the same ordering is used to
+//! build sorted data and to search it. [`against`] binds one to a search key, for
+//! [`bl_binary_search_by`](crate::SharBinarySearch::bl_binary_search_by) and friends.
+//!
+//! ```
+//! use shar_search::{
+//!     comparators::{against, by_key, natural_ascii, reverse, then},
+//!     SharBinarySearch,
+//! };
+//!
+//! // Newest version first, then by file name in natural order.
+//! let natural = natural_ascii();
+//! let order = then(
+//!     reverse(by_key(|f: &(u32, &str)| f.0)),
+//!     move |a: &(u32, &str), b: &(u32, &str)| natural(a.1, b.1),
+//! );
+//!
+//! let mut files = vec![(1, "file10"), (2, "file2"), (1, "file9"), (2, "file1")];
+//! files.sort_by(&order);
+//! assert_eq!(files, [(2, "file1"), (2, "file2"), (1, "file9"), (1, "file10")]);
+//!
+//! assert_eq!(files.bl_binary_search_by(against(&(1, "file9"), &order)), Ok(2));
+//! ```
+
+use core::cmp::Ordering;
+
+/// Returns a comparator that orders by `cmp`, reversed.
+pub fn reverse<T, F>(cmp: F) -> impl Fn(&T, &T) -> Ordering
+where
+    T: ?Sized,
+    F: Fn(&T, &T) -> Ordering,
+{
+    move |a, b| cmp(b, a)
+}
+
+/// Returns a comparator that orders by the key extracted from each element by `f`.
+pub fn by_key<T, K, F>(f: F) -> impl Fn(&T, &T) -> Ordering
+where
+    T: ?Sized,
+    K: Ord,
+    F: Fn(&T) -> K,
+{
+    move |a, b| f(a).cmp(&f(b))
+}
+
+/// Returns a comparator that orders by `first`, breaking ties with `second`.
+pub fn then<T, F, G>(first: F, second: G) -> impl Fn(&T, &T) -> Ordering
+where
+    T: ?Sized,
+    F: Fn(&T, &T) -> Ordering,
+    G: Fn(&T, &T) -> Ordering,
+{
+    move |a, b| first(a, b).then_with(|| second(a, b))
+}
+
+/// Binds the comparator `cmp` to `key`, returning a function that orders an element relative to
+/// `key`, as taken by [`bl_binary_search_by`](crate::SharBinarySearch::bl_binary_search_by).
+pub fn against<'k, T, F>(key: &'k T, cmp: F) -> impl FnMut(&T) -> Ordering + 'k
+where
+    T: ?Sized,
+    F: Fn(&T, &T) -> Ordering + 'k,
+{
+    move |p| cmp(p, key)
+}
+
+/// Returns a comparator that orders ASCII strings naturally, comparing runs of digits by their
+/// numeric value, so `"file2"` comes before `"file10"`.
+///
+/// Digit runs are compared without parsing them, so they may be arbitrarily long, and nothing is
+/// allocated. Leading zeros do not change a run's value; strings that are equal apart from
+/// leading zeros are ordered by them at the first run where they differ, with fewer zeros first,
+/// so that only identical strings compare equal. All other bytes are compared by value.
+///
+/// ```
+/// use shar_search::comparators::natural_ascii;
+///
+/// let mut names = ["img12.png", "img10.png", "IMG3.png", "img2.png", "img02.png"];
+/// names.sort_by(natural_ascii());
+/// assert_eq!(names, ["IMG3.png", "img2.png", "img02.png", "img10.png", "img12.png"]);
+/// ```
+pub fn natural_ascii<S>() -> impl Fn(&S, &S) -> Ordering + Copy
+where
+    S: AsRef<[u8]> + ?Sized,
+{
+    |a, b| compare_natural(a.as_ref(), b.as_ref())
+}
+
+/// Returns a comparator that orders ASCII strings case-insensitively, as if their ASCII letters
+/// were all lowercase. Strings differing only in the case of ASCII letters compare equal.
+///
+/// ```
+/// use shar_search::{
+///     comparators::{against, caseless_ascii},
+///     SharBinarySearch,
+/// };
+///
+/// let mut headers = ["Content-Type", "accept", "X-Request-Id", "Host"];
+/// headers.sort_by(caseless_ascii());
+/// assert_eq!(headers, ["accept", "Content-Type", "Host", "X-Request-Id"]);
+///
+/// assert_eq!(headers.bl_binary_search_by(against(&"HOST", caseless_ascii())), Ok(2));
+/// ```
+pub fn caseless_ascii<S>() -> impl Fn(&S, &S) -> Ordering + Copy
+where
+    S: AsRef<[u8]> + ?Sized,
+{
+    |a, b| {
+        let a = a.as_ref().iter().map(u8::to_ascii_lowercase);
+        let b = b.as_ref().iter().map(u8::to_ascii_lowercase);
+        a.cmp(b)
+    }
+}
+
+/// Returns the length of the run of ASCII digits at the start of `bytes`.
+fn digit_run(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .position(|b| !b.is_ascii_digit())
+        .unwrap_or(bytes.len())
+}
+
+fn compare_natural(mut a: &[u8], mut b: &[u8]) -> Ordering {
+    // How the strings compare by their leading zeros, if they turn out otherwise equal.
+    let mut zeros = Ordering::Equal;
+
+    loop {
+        match (a.first(), b.first()) {
+            (None, None) => return zeros,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let (run_a, rest_a) = a.split_at(digit_run(a));
+                let (run_b, rest_b) = b.split_at(digit_run(b));
+
+                let value_a = trim_zeros(run_a);
+                let value_b = trim_zeros(run_b);
+
+                // Without leading zeros, a longer run is a larger number, and runs of the same
+                // length compare like their digits.
+                let order = value_a
+                    .len()
+                    .cmp(&value_b.len())
+                    .then_with(|| value_a.cmp(value_b));
+                if order.is_ne() {
+                    return order;
+                }
+
+                if zeros.is_eq() {
+                    zeros = run_a.len().cmp(&run_b.len());
+                }
+                a = rest_a;
+                b = rest_b;
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(y);
+                }
+                a = &a[1..];
+                b = &b[1..];
+            }
+        }
+    }
+}
+
+/// Returns `run` without its leading zeros.
+fn trim_zeros(run: &[u8]) -> &[u8] {
+    let zeros = run.iter().take_while(|&&b| b == b'0').count();
+    &run[zeros..]
+}
+
+#[cfg(test)]
+mod test {
+    use std::cmp::Ordering;
+
+    use super::{against, by_key, caseless_ascii, natural_ascii, reverse, then};
+    use crate::{test_util::XorShift, SharBinarySearch};
+
+    fn natural(a: &str, b: &str) -> Ordering {
+        natural_ascii()(a, b)
+    }
+
+    #[test]
+    fn test_natural_order() {
+        let sorted = [
+            "", "0", "00", "1", "01", "001", "2", "9", "10", "010", "99", "100", "a", "a0", "a1",
+            "a01", "a1b", "a1b2", "a1b10", "a2", "a10", "a10.5", "ab", "b",
+        ];
+
+        for (i, a) in sorted.iter().enumerate() {
+            for (j, b) in sorted.iter().enumerate() {
+                assert_eq!(natural(a, b), i.cmp(&j), "{a:?} {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_natural_long_runs() {
+        let big = "9".repeat(200);
+        let bigger = format!("1{}", "0".repeat(200));
+        let padded = format!("{}{big}", "0".repeat(500));
+
+        assert_eq!(natural(&big, &bigger), Ordering::Less);
+        assert_eq!(natural(&padded, &bigger), Ordering::Less);
+        assert_eq!(natural(&big, &padded), Ordering::Less);
+        assert_eq!(
+            natural(&format!("x{big}y1"), &format!("x{big}y01")),
+            Ordering::Less
+        );
+        assert_eq!(
+            natural(&format!("{big}8"), &format!("{big}9")),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn test_natural_is_total_order() {
+        let mut rng = XorShift::new(73);
+        let alphabet = b"0019a.";
+        let strings: Vec<String> = (0..150)
+            .map(|_| {
+                (0..rng.below(7))
+                    .map(|_| char::from(alphabet[rng.below(alphabet.len() as u64) as usize]))
+                    .collect()
+            })
+            .collect();
+
+        let mut sorted = strings.clone();
+        sorted.sort_by(natural_ascii());
+        for pair in sorted.windows(2) {
+            assert_ne!(natural(&pair[0], &pair[1]), Ordering::Greater);
+        }
+
+        for a in &strings {
+            for b in &strings {
+                let order = natural(a, b);
+                assert_eq!(order, natural(b, a).reverse(), "{a:?} {b:?}");
+                assert_eq!(order.is_eq(), a == b, "{a:?} {b:?}");
+            }
+        }
+
+        // Everything sorted must be found again with the same comparator.
+        for s in &strings {
+            let found = sorted.bl_binary_search_by(against(s, natural_ascii()));
+            assert_eq!(found.map(|i| &sorted[i]), Ok(s));
+        }
+    }
+
+    #[test]
+    fn test_caseless() {
+        let cmp = caseless_ascii();
+        assert_eq!(cmp("abc", "ABC"), Ordering::Equal);
+        assert_eq!(cmp("abc", "ABD"), Ordering::Less);
+        assert_eq!(cmp("Z", "a"), Ordering::Greater);
+        assert_eq!(cmp("_", "a"), Ordering::Less);
+        assert_eq!(caseless_ascii::<[u8]>()(b"\xff", b"A"), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_composition() {
+        let mut people = vec![("bob", 30), ("Alice", 25), ("carol", 30), ("alice", 40)];
+        let order = then(
+            reverse(by_key(|p: &(&str, u32)| p.1)),
+            by_key(|p: &(&str, u32)| p.0),
+        );
+
+        people.sort_by(&order);
+        assert_eq!(
+            people,
+            [("alice", 40), ("bob", 30), ("carol", 30), ("Alice", 25)]
+        );
+        assert_eq!(
+            people.bl_binary_search_by(against(&("carol", 30), &order)),
+            Ok(2)
+        );
+        assert_eq!(
+            people.bl_binary_search_by(against(&("dave", 30), &order)),
+            Err(3)
+        );
+
+        let mut values = vec![3, 1, 2];
+        values.sort_by(reverse(u32::cmp));
+        assert_eq!(values, [3, 2, 1]);
+        assert!(values.is_sorted_by(|a, b| reverse(u32::cmp)(a, b).is_le()));
+    }
+}
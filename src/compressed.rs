@@ -0,0 +1,506 @@
+//! Searches of sorted `u64`s compressed in blocks of deltas, such as posting lists or time
+//! indexes, decoding only the block that holds the answer.
+//!
+//! The sequence is split into blocks of [`BLOCK_LEN`] values (the last may be shorter). The
+//! first value of each block is kept uncompressed in `block_firsts`, and each block is stored as
+//! the differences between its consecutive values, each a LEB128 varint. The blocks are stored
+//! back to back in one buffer, with `n + 1` offsets into it for `n` blocks, in the same layout as
+//! a [`StrArena`]. A search runs the branchless search over `block_firsts` to pick the block,
+//! decodes that block into a buffer on the stack and finishes with a search within it.
+//!
+//! [`encode`] builds the three buffers from a sorted slice.
+//!
+//! ```
+//! # #[cfg(feature = "alloc")]
+//! # {
+//! use shar_search::compressed::encode;
+//!
+//! let timestamps: Vec<u64> = (0..1000).map(|i| 1_700_000_000 + i * 15).collect();
+//! let encoded = encode(&timestamps);
+//! assert!(encoded.bytes.len() < 1000);
+//!
+//! let index = encoded.index();
+//! assert_eq!(index.len(), 1000);
+//! assert_eq!(index.search(1_700_000_150), Ok(10));
+//! assert_eq!(index.search(1_700_000_151), Err(11));
+//! assert_eq!(index.get(999), 1_700_014_985);
+//! assert!(index.iter_from(998).eq([1_700_014_970, 1_700_014_985]));
+//! # }
+//! ```
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::{error::Error, fmt, iter::FusedIterator};
+
+use crate::{
+    arena::{ArenaError, StrArena},
+    columns::partition_point,
+    SharBinarySearch,
+};
+
+/// The number of values in every block but the last.
+pub const BLOCK_LEN: usize = 128;
+
+/// The error returned when compressed blocks can't be decoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockError {
+    /// The offsets are invalid for the buffer.
+    Offsets(ArenaError),
+    /// The number of blocks given by the offsets isn't the number of first values.
+    Shape {
+        /// The number of first values.
+        firsts: usize,
+        /// The number of blocks given by the offsets.
+        blocks: usize,
+    },
+    /// The block at this index ends part way through a varint, has a varint too large for a
+    /// `u64`, has values past `u64::MAX`, or holds more than [`BLOCK_LEN`] values.
+    Malformed(usize),
+    /// The block at this index holds fewer than [`BLOCK_LEN`] values, but isn't the last.
+    WrongLength {
+        /// The index of the block.
+        block: usize,
+        /// The number of values in the block.
+        len: usize,
+    },
+}
+
+impl fmt::Display for BlockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockError::Offsets(err) => write!(f, "invalid block offsets: {err}"),
+            BlockError::Shape { firsts, blocks } => {
+                write!(f, "{firsts} first values were given for {blocks} blocks")
+            }
+            BlockError::Malformed(block) => write!(f, "block {block} is malformed"),
+            BlockError::WrongLength { block, len } => {
+                write!(f, "block {block} holds {len} values")
+            }
+        }
+    }
+}
+
+impl Error for BlockError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BlockError::Offsets(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Reads the varint at `*pos`, advancing past it.
+#[inline]
+fn read_varint(bytes: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut value = 0_u64;
+
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(*pos)?;
+        *pos += 1;
+
+        // The tenth byte holds only the top bit.
+        if shift == 63 && byte > 1 {
+            return None;
+        }
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+
+    None
+}
+
+/// Decodes the block starting at `first` with the deltas in `bytes` into `out`, returning the
+/// number of values, or `None` if it is malformed or holds more than [`BLOCK_LEN`] values.
+fn decode(first: u64, bytes: &[u8], out: &mut [u64; BLOCK_LEN]) -> Option<usize> {
+    out[0] = first;
+
+    let mut len = 1;
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let delta = read_varint(bytes, &mut pos)?;
+        let value = out[len - 1].checked_add(delta)?;
+        *out.get_mut(len)? = value;
+        len += 1;
+    }
+
+    Some(len)
+}
+
+/// A read-only view of a sorted sequence of `u64`s compressed in blocks. See the
+/// [module documentation](self).
+#[derive(Clone, Copy, Debug)]
+pub struct BlockIndex<'a> {
+    firsts: &'a [u64],
+    blocks: StrArena<'a>,
+    len: usize,
+}
+
+impl<'a> BlockIndex<'a> {
+    /// Creates a view of the blocks starting with `block_firsts`, whose deltas are in `bytes`
+    /// delimited by `offsets`. Every block is decoded once to check it, so that searches can't
+    /// fail. Note it is assumed that the values are sorted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the offsets are invalid or there isn't one per first value (plus one),
+    /// if a block is malformed, or if a block other than the last doesn't hold exactly
+    /// [`BLOCK_LEN`] values.
+    pub fn new(
+        block_firsts: &'a [u64],
+        bytes: &'a [u8],
+        offsets: &'a [u32],
+    ) -> Result<Self, BlockError> {
+        let blocks = StrArena::new(bytes, offsets).map_err(BlockError::Offsets)?;
+        if blocks.len() != block_firsts.len() {
+            return Err(BlockError::Shape {
+                firsts: block_firsts.len(),
+                blocks: blocks.len(),
+            });
+        }
+
+        let mut buf = [0; BLOCK_LEN];
+        let mut len = 0;
+        for (block, (&first, deltas)) in block_firsts.iter().zip(blocks.iter()).enumerate() {
+            let block_len = decode(first, deltas, &mut buf).ok_or(BlockError::Malformed(block))?;
+            if block_len != BLOCK_LEN && block + 1 != block_firsts.len() {
+                return Err(BlockError::WrongLength {
+                    block,
+                    len: block_len,
+                });
+            }
+            len += block_len;
+        }
+
+        Ok(Self {
+            firsts: block_firsts,
+            blocks,
+            len,
+        })
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Decodes block `block` into `buf`, returning its values.
+    #[inline]
+    fn decode_block<'b>(&self, block: usize, buf: &'b mut [u64; BLOCK_LEN]) -> &'b [u64] {
+        let len = decode(self.firsts[block], self.blocks.get(block), buf)
+            .expect("blocks are checked when the index is created");
+        &buf[..len]
+    }
+
+    /// Returns value `index`, decoding its block up to it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn get(&self, index: usize) -> u64 {
+        assert!(
+            index < self.len,
+            "index {index} is out of bounds for {} values",
+            self.len
+        );
+
+        let deltas = self.blocks.get(index / BLOCK_LEN);
+        let mut value = self.firsts[index / BLOCK_LEN];
+        let mut pos = 0;
+        for _ in 0..index % BLOCK_LEN {
+            // Checked on creation, so these can't fail or overflow.
+            value += read_varint(deltas, &mut pos).unwrap();
+        }
+
+        value
+    }
+
+    /// Returns an iterator over the values from index `start` on, decoding a block at a time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start` is greater than the length.
+    pub fn iter_from(&self, start: usize) -> Iter<'a> {
+        assert!(
+            start <= self.len,
+            "start {start} is out of bounds for {} values",
+            self.len
+        );
+
+        Iter {
+            index: *self,
+            next: start,
+            block: usize::MAX,
+            buf: [0; BLOCK_LEN],
+        }
+    }
+
+    /// Binary searches for `key`. If there are multiple matches, the first is returned.
+    pub fn search(&self, key: u64) -> Result<usize, usize> {
+        // The first block starting at or after `key`. Everything before the block before it is
+        // less than `key`, so the first value not less than `key` is in that block, or is the
+        // first value of this one.
+        let block = partition_point(self.firsts.len(), |i| self.firsts[i] < key);
+
+        let (index, found) = match block.checked_sub(1) {
+            Some(prev) => {
+                let mut buf = [0; BLOCK_LEN];
+                let values = self.decode_block(prev, &mut buf);
+                match values.bl_binary_search(&key) {
+                    Ok(i) => (prev * BLOCK_LEN + i, true),
+                    // Past the end of the block, which is the start of the next, if any.
+                    Err(i) => (
+                        prev * BLOCK_LEN + i,
+                        i == values.len() && self.firsts.get(block) == Some(&key),
+                    ),
+                }
+            }
+            None => (0, self.firsts.first() == Some(&key)),
+        };
+
+        if found {
+            Ok(index)
+        } else {
+            Err(index)
+        }
+    }
+}
+
+/// An iterator over the values of a [`BlockIndex`], returned by
+/// [`iter_from`](BlockIndex::iter_from).
+#[derive(Clone, Debug)]
+pub struct Iter<'a> {
+    index: BlockIndex<'a>,
+    next: usize,
+    /// The block decoded into `buf`, or `usize::MAX` if none is.
+    block: usize,
+    buf: [u64; BLOCK_LEN],
+}
+
+impl Iterator for Iter<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.next == self.index.len {
+            return None;
+        }
+
+        let block = self.next / BLOCK_LEN;
+        if block != self.block {
+            self.index.decode_block(block, &mut self.buf);
+            self.block = block;
+        }
+
+        let value = self.buf[self.next % BLOCK_LEN];
+        self.next += 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.index.len - self.next;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+impl FusedIterator for Iter<'_> {}
+
+/// The buffers of a sequence compressed in blocks, returned by [`encode`].
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Encoded {
+    /// The first value of each block.
+    pub firsts: Vec<u64>,
+    /// The deltas of every block, back to back.
+    pub bytes: Vec<u8>,
+    /// The offsets of the blocks in `bytes`, one more than there are blocks.
+    pub offsets: Vec<u32>,
+}
+
+#[cfg(feature = "alloc")]
+impl Encoded {
+    /// Returns a view of these buffers for searching.
+    pub fn index(&self) -> BlockIndex<'_> {
+        BlockIndex::new(&self.firsts, &self.bytes, &self.offsets)
+            .expect("encoded blocks are well-formed")
+    }
+}
+
+/// Compresses the sorted `values` in blocks of [`BLOCK_LEN`].
+///
+/// # Panics
+///
+/// Panics if `values` isn't sorted, or if the compressed bytes are longer than `u32::MAX`.
+#[cfg(feature = "alloc")]
+pub fn encode(values: &[u64]) -> Encoded {
+    let mut encoded = Encoded::default();
+    if values.is_empty() {
+        return encoded;
+    }
+
+    encoded.offsets.push(0);
+    for block in values.chunks(BLOCK_LEN) {
+        encoded.firsts.push(block[0]);
+
+        for w in block.windows(2) {
+            let mut delta = w[1].checked_sub(w[0]).expect("values must be sorted");
+            while delta >= 0x80 {
+                encoded.bytes.push(delta as u8 | 0x80);
+                delta >>= 7;
+            }
+            encoded.bytes.push(delta as u8);
+        }
+
+        let offset = u32::try_from(encoded.bytes.len()).expect("compressed bytes are too long");
+        encoded.offsets.push(offset);
+    }
+
+    encoded
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod test {
+    use super::*;
+    use crate::test_util::XorShift;
+
+    fn random_sorted(rng: &mut XorShift, len: u64, max_gap: u64) -> Vec<u64> {
+        let mut value = rng.below(1000);
+        (0..len)
+            .map(|_| {
+                value += rng.below(max_gap + 1);
+                value
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut rng = XorShift::new(149);
+
+        for len in [0, 1, 2, 127, 128, 129, 256, 1000] {
+            for max_gap in [0, 3, 1 << 20, 1 << 40] {
+                let values = random_sorted(&mut rng, len, max_gap);
+                let encoded = encode(&values);
+                let index = encoded.index();
+
+                assert_eq!(index.len(), values.len());
+                assert!(index.iter_from(0).eq(values.iter().copied()));
+                for start in [0, len / 2, len.saturating_sub(1), len] {
+                    let start = start as usize;
+                    assert!(index.iter_from(start).eq(values[start..].iter().copied()));
+                    assert_eq!(index.iter_from(start).len(), values.len() - start);
+                }
+                for (i, &value) in values.iter().enumerate().step_by(7) {
+                    assert_eq!(index.get(i), value);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_search_against_vec() {
+        let mut rng = XorShift::new(1149);
+
+        for _ in 0..100 {
+            // Small gaps give long runs of duplicates, including across blocks.
+            let max_gap = [0, 1, 5, 1000][rng.below(4) as usize];
+            let len = rng.below(600);
+            let values = random_sorted(&mut rng, len, max_gap);
+            let encoded = encode(&values);
+            let index = encoded.index();
+
+            let max = values.last().copied().unwrap_or(0);
+            for _ in 0..100 {
+                let key = rng.below(max + 2);
+                assert_eq!(
+                    index.search(key),
+                    values.bl_binary_search(&key),
+                    "key {key}"
+                );
+            }
+            for &key in values.iter().step_by(13) {
+                assert_eq!(
+                    index.search(key),
+                    values.bl_binary_search(&key),
+                    "key {key}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_extremes() {
+        let values = [0, 0, 1, u64::MAX - 1, u64::MAX, u64::MAX];
+        let encoded = encode(&values);
+        let index = encoded.index();
+
+        assert_eq!(index.search(0), Ok(0));
+        assert_eq!(index.search(u64::MAX), Ok(4));
+        assert_eq!(index.search(2), Err(3));
+        assert_eq!(index.get(5), u64::MAX);
+
+        let empty = BlockIndex::new(&[], &[], &[]).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.search(5), Err(0));
+        assert_eq!(empty.iter_from(0).next(), None);
+    }
+
+    #[test]
+    fn test_malformed() {
+        // Truncated part way through a varint.
+        assert_eq!(
+            BlockIndex::new(&[5], &[0x80], &[0, 1]).unwrap_err(),
+            BlockError::Malformed(0)
+        );
+        // Eleven bytes, and a tenth byte with more than the top bit.
+        let mut long = [0xff; 11];
+        long[10] = 0x01;
+        assert_eq!(
+            BlockIndex::new(&[5], &long, &[0, 11]).unwrap_err(),
+            BlockError::Malformed(0)
+        );
+        let mut wide = [0xff; 10];
+        wide[9] = 0x02;
+        assert_eq!(
+            BlockIndex::new(&[5], &wide, &[0, 10]).unwrap_err(),
+            BlockError::Malformed(0)
+        );
+        // A delta past u64::MAX.
+        let mut max = [0xff; 10];
+        max[9] = 0x01;
+        assert_eq!(
+            BlockIndex::new(&[5], &max, &[0, 10]).unwrap_err(),
+            BlockError::Malformed(0)
+        );
+        assert!(BlockIndex::new(&[0], &max, &[0, 10]).is_ok());
+
+        // A block other than the last that is short, and a last block that is too long.
+        assert_eq!(
+            BlockIndex::new(&[1, 2], &[1, 1], &[0, 1, 2]).unwrap_err(),
+            BlockError::WrongLength { block: 0, len: 2 }
+        );
+        assert_eq!(
+            BlockIndex::new(&[1], &[0; BLOCK_LEN], &[0, BLOCK_LEN as u32]).unwrap_err(),
+            BlockError::Malformed(0)
+        );
+
+        assert_eq!(
+            BlockIndex::new(&[1, 2], &[], &[0, 0]).unwrap_err(),
+            BlockError::Shape {
+                firsts: 2,
+                blocks: 1
+            }
+        );
+        assert!(matches!(
+            BlockIndex::new(&[1], &[], &[0, 1]).unwrap_err(),
+            BlockError::Offsets(ArenaError::OutOfBounds { .. })
+        ));
+    }
+}
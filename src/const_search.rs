@@ -0,0 +1,279 @@
+//! `const fn` searches of sorted primitive slices, for resolving lookups into static tables at
+//! compile time.
+//!
+//! Trait methods taking closures can't be called in `const` contexts, so these are free
+//! functions, one per element type, following the same branchless structure as
+//! [`bl_binary_search`](crate::SharBinarySearch::bl_binary_search). Like it, they return the
+//! *first* match when there are several.
+//!
+//! ```
+//! use shar_search::const_search::{const_binary_search_str, is_sorted_str};
+//!
+//! #[derive(Clone, Copy, Debug, PartialEq)]
+//! enum Token {
+//!     Else,
+//!     Fn,
+//!     If,
+//!     Let,
+//!     While,
+//! }
+//!
+//! const KEYWORDS: [&str; 5] = ["else", "fn", "if", "let", "while"];
+//! const TOKENS: [Token; 5] = [Token::Else, Token::Fn, Token::If, Token::Let, Token::While];
+//!
+//! // Fails to compile if the table is ever edited out of order.
+//! const _: () = assert!(is_sorted_str(&KEYWORDS));
+//!
+//! const fn keyword(word: &str) -> Option<Token> {
+//!     match const_binary_search_str(&KEYWORDS, word) {
+//!         Some(i) => Some(TOKENS[i]),
+//!         None => None,
+//!     }
+//! }
+//!
+//! const LET: Option<Token> = keyword("let");
+//! assert_eq!(LET, Some(Token::Let));
+//! assert_eq!(keyword("loop"), None);
+//! ```
+
+use core::cmp::Ordering;
+
+use crate::bit_floor;
+
+/// Expands to the body of a `const` binary search of `$slice` for `$key`, where `$lt` and `$eq`
+/// compare two elements bound to `$a` and `$b`.
+macro_rules! const_search_body {
+    ($slice:ident, $key:ident, |$a:ident, $b:ident| $lt:expr, $eq:expr) => {{
+        if $slice.is_empty() {
+            return None;
+        }
+
+        let mut length = $slice.len();
+
+        let mut left = 0;
+        let right = length;
+
+        let mut step = bit_floor(length);
+
+        if step != length && {
+            let ($a, $b) = ($slice[step], $key);
+            $lt
+        } {
+            length -= step + 1;
+
+            if length == 0 {
+                return None;
+            }
+
+            step = length.next_power_of_two();
+            left = right - step;
+        }
+
+        while step > 1 {
+            step /= 2;
+            if {
+                let ($a, $b) = ($slice[left + step], $key);
+                $lt
+            } {
+                left += step;
+            }
+        }
+
+        if {
+            let ($a, $b) = ($slice[left], $key);
+            $lt
+        } {
+            left += 1;
+        }
+
+        if left < $slice.len() && {
+            let ($a, $b) = ($slice[left], $key);
+            $eq
+        } {
+            Some(left)
+        } else {
+            None
+        }
+    }};
+}
+
+/// Expands to the body of a `const` check that `$slice` is sorted in non-decreasing order,
+/// where `$lt` compares two elements, or their fields `$field` if given, bound to `$a` and `$b`.
+macro_rules! const_is_sorted_body {
+    ($slice:ident $(.$field:tt)?, |$a:ident, $b:ident| $lt:expr) => {{
+        let mut i = 1;
+        while i < $slice.len() {
+            let ($a, $b) = ($slice[i]$(.$field)?, $slice[i - 1]$(.$field)?);
+            if $lt {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }};
+}
+
+macro_rules! const_search_int {
+    ($($ty:ty => $search:ident, $is_sorted:ident, $is_sorted_by_first:ident;)*) => {$(
+        #[doc = concat!("Binary searches a sorted slice of `", stringify!($ty), "` for `key` in a")]
+        /// `const` context, returning the index of the first match, or `None` if there is none.
+        /// Note it is assumed that the slice is sorted.
+        pub const fn $search(slice: &[$ty], key: $ty) -> Option<usize> {
+            const_search_body!(slice, key, |a, b| a < b, a == b)
+        }
+
+        #[doc = concat!("Returns whether a slice of `", stringify!($ty), "` is sorted in")]
+        /// non-decreasing order, in a `const` context.
+        pub const fn $is_sorted(slice: &[$ty]) -> bool {
+            const_is_sorted_body!(slice, |a, b| a < b)
+        }
+
+        #[doc = concat!("Returns whether entries keyed by `", stringify!($ty), "` are sorted by")]
+        /// key in non-decreasing order, in a `const` context.
+        pub const fn $is_sorted_by_first<V>(entries: &[($ty, V)]) -> bool {
+            const_is_sorted_body!(entries.0, |a, b| a < b)
+        }
+    )*};
+}
+
+const_search_int! {
+    u8 => const_binary_search_u8, is_sorted_u8, is_sorted_by_first_u8;
+    u32 => const_binary_search_u32, is_sorted_u32, is_sorted_by_first_u32;
+    i32 => const_binary_search_i32, is_sorted_i32, is_sorted_by_first_i32;
+    u64 => const_binary_search_u64, is_sorted_u64, is_sorted_by_first_u64;
+    i64 => const_binary_search_i64, is_sorted_i64, is_sorted_by_first_i64;
+}
+
+/// Compares two byte strings lexicographically, as `<[u8]>::cmp` does.
+const fn cmp_bytes(a: &[u8], b: &[u8]) -> Ordering {
+    let mut i = 0;
+    while i < a.len() && i < b.len() {
+        if a[i] != b[i] {
+            return if a[i] < b[i] {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            };
+        }
+        i += 1;
+    }
+
+    if a.len() < b.len() {
+        Ordering::Less
+    } else if a.len() > b.len() {
+        Ordering::Greater
+    } else {
+        Ordering::Equal
+    }
+}
+
+/// Binary searches a sorted slice of strings for `key` in a `const` context, returning the index
+/// of the first match, or `None` if there is none. Strings are compared by their bytes, which
+/// orders them the same as `str`'s `Ord`. Note it is assumed that the slice is sorted.
+pub const fn const_binary_search_str(slice: &[&str], key: &str) -> Option<usize> {
+    const_search_body!(
+        slice,
+        key,
+        |a, b| cmp_bytes(a.as_bytes(), b.as_bytes()).is_lt(),
+        cmp_bytes(a.as_bytes(), b.as_bytes()).is_eq()
+    )
+}
+
+/// Returns whether a slice of strings is sorted in non-decreasing order, in a `const` context.
+pub const fn is_sorted_str(slice: &[&str]) -> bool {
+    const_is_sorted_body!(slice, |a, b| cmp_bytes(a.as_bytes(), b.as_bytes()).is_lt())
+}
+
+/// Returns whether entries keyed by strings are sorted by key in non-decreasing order, in a
+/// `const` context.
+pub const fn is_sorted_by_first_str<V>(entries: &[(&str, V)]) -> bool {
+    const_is_sorted_body!(entries.0, |a, b| cmp_bytes(a.as_bytes(), b.as_bytes())
+        .is_lt())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{test_util::XorShift, SharBinarySearch};
+
+    const PRIMES: [u32; 8] = [2, 3, 5, 7, 11, 13, 17, 19];
+    const SIGNED: [i64; 5] = [i64::MIN, -40, 0, 0, i64::MAX];
+    const WORDS: [&str; 6] = ["", "a", "ab", "abc", "b", "\u{e9}"];
+
+    const _: () = assert!(is_sorted_u32(&PRIMES));
+    const _: () = assert!(is_sorted_i64(&SIGNED));
+    const _: () = assert!(is_sorted_str(&WORDS));
+    const _: () = assert!(!is_sorted_u8(&[1, 3, 2]));
+    const _: () = assert!(!is_sorted_str(&["b", "a"]));
+    const _: () = assert!(is_sorted_by_first_u32(&[(1, "b"), (1, "a"), (4, "c")]));
+    const _: () = assert!(!is_sorted_by_first_i64(&[(0, ()), (-1, ())]));
+    const _: () = assert!(is_sorted_by_first_str(&[("a", 2), ("b", 1)]));
+    const _: () = assert!(!is_sorted_by_first_str(&[("ab", 1), ("a", 2)]));
+
+    const FOUND_13: Option<usize> = const_binary_search_u32(&PRIMES, 13);
+    const MISSED_4: Option<usize> = const_binary_search_u32(&PRIMES, 4);
+    const FIRST_ZERO: Option<usize> = const_binary_search_i64(&SIGNED, 0);
+    const MIN: Option<usize> = const_binary_search_i64(&SIGNED, i64::MIN);
+    const EMPTY_WORD: Option<usize> = const_binary_search_str(&WORDS, "");
+    const ACCENT: Option<usize> = const_binary_search_str(&WORDS, "\u{e9}");
+    const PREFIX: Option<usize> = const_binary_search_str(&WORDS, "abcd");
+    const NOTHING: Option<usize> = const_binary_search_u8(&[], 0);
+
+    #[test]
+    fn test_const_evaluation() {
+        assert_eq!(FOUND_13, Some(5));
+        assert_eq!(MISSED_4, None);
+        assert_eq!(FIRST_ZERO, Some(2));
+        assert_eq!(MIN, Some(0));
+        assert_eq!(EMPTY_WORD, Some(0));
+        assert_eq!(ACCENT, Some(5));
+        assert_eq!(PREFIX, None);
+        assert_eq!(NOTHING, None);
+    }
+
+    #[test]
+    fn test_against_binary_search() {
+        let mut rng = XorShift::new(140);
+
+        for _ in 0..200 {
+            let mut slice: Vec<u32> = (0..rng.below(40)).map(|_| rng.below(50) as u32).collect();
+            slice.sort_unstable();
+            let wide: Vec<u64> = slice.iter().map(|&x| x as u64).collect();
+            let signed: Vec<i32> = slice.iter().map(|&x| x as i32 - 25).collect();
+            let bytes: Vec<u8> = slice.iter().map(|&x| x as u8).collect();
+            let strings: Vec<String> = slice.iter().map(|x| x.to_string()).collect();
+            let mut words: Vec<&str> = strings.iter().map(String::as_str).collect();
+            words.sort_unstable();
+
+            assert!(is_sorted_u32(&slice) && is_sorted_i32(&signed) && is_sorted_str(&words));
+
+            for x in 0..52 {
+                let expected = slice.bl_binary_search(&x).ok();
+                assert_eq!(const_binary_search_u32(&slice, x), expected);
+                assert_eq!(const_binary_search_u64(&wide, x as u64), expected);
+                assert_eq!(const_binary_search_i32(&signed, x as i32 - 25), expected);
+                assert_eq!(const_binary_search_u8(&bytes, x as u8), expected);
+
+                let word = x.to_string();
+                assert_eq!(
+                    const_binary_search_str(&words, &word),
+                    words.bl_binary_search(&word.as_str()).ok()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_cmp_bytes() {
+        for (a, b) in [
+            ("", ""),
+            ("", "a"),
+            ("ab", "a"),
+            ("abc", "abd"),
+            ("b", "abc"),
+        ] {
+            assert_eq!(cmp_bytes(a.as_bytes(), b.as_bytes()), a.cmp(b));
+            assert_eq!(cmp_bytes(b.as_bytes(), a.as_bytes()), b.cmp(a));
+        }
+    }
+}
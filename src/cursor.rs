@@ -0,0 +1,186 @@
+//! A search cursor that remembers where its last search ended.
+
+use core::cmp::Ordering;
+
+use crate::gallop::{gallop, gallop_back};
+
+/// A cursor over a sorted slice for answering a stream of searches whose keys arrive in roughly
+/// increasing (or decreasing) order, such as when replaying a log or scanning a time series.
+///
+/// Each [`seek`](SearchCursor::seek) gallops from where the previous one ended, in whichever
+/// direction the key lies, so a seek that moves the cursor `k` elements costs `O(log k)`
+/// comparisons. Keys may arrive in any order; keys far from the previous one just cost up to
+/// twice as much as a search from scratch.
+///
+/// ```
+/// use shar_search::SearchCursor;
+///
+/// let timestamps = [10, 20, 20, 30, 40, 50];
+/// let mut cursor = SearchCursor::new(&timestamps);
+///
+/// assert_eq!(cursor.seek(&20), Ok(1));
+/// assert_eq!(cursor.seek(&35), Err(4));
+/// assert_eq!(cursor.peek(), Some(&40));
+/// assert_eq!(cursor.seek(&15), Err(1));
+/// ```
+#[derive(Debug)]
+pub struct SearchCursor<'a, T> {
+    slice: &'a [T],
+    position: usize,
+}
+
+impl<T> Clone for SearchCursor<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for SearchCursor<'_, T> {}
+
+impl<'a, T> SearchCursor<'a, T> {
+    /// Creates a cursor at the start of `slice`. Note it is assumed that the slice is sorted.
+    pub fn new(slice: &'a [T]) -> Self {
+        Self { slice, position: 0 }
+    }
+
+    /// Returns the slice this cursor searches.
+    pub fn slice(&self) -> &'a [T] {
+        self.slice
+    }
+
+    /// Returns the cursor's position: the index returned by the last seek, or 0 if there has
+    /// been none since the cursor was created or moved to the start.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the element at the cursor's position, or `None` if it is at the end of the
+    /// slice.
+    pub fn peek(&self) -> Option<&'a T> {
+        self.slice.get(self.position)
+    }
+
+    /// Moves the cursor back to the start of the slice.
+    pub fn advance_to_start(&mut self) {
+        self.position = 0;
+    }
+
+    /// Searches for `key` starting from the cursor's position and moves the cursor to the
+    /// result, returning the same result as
+    /// [`bl_binary_search`](crate::SharBinarySearch::bl_binary_search): if there are multiple
+    /// matches, the *first* is returned.
+    pub fn seek(&mut self, key: &T) -> Result<usize, usize>
+    where
+        T: Ord,
+    {
+        self.seek_by(|p| p.cmp(key))
+    }
+
+    /// Searches with a comparator function, which returns the ordering of an element relative
+    /// to the target, starting from the cursor's position. See [`SearchCursor::seek`].
+    pub fn seek_by<F>(&mut self, mut f: F) -> Result<usize, usize>
+    where
+        F: FnMut(&T) -> Ordering,
+    {
+        let (before, after) = self.slice.split_at(self.position);
+
+        self.position = match after.first().map(&mut f) {
+            Some(Ordering::Less) => self.position + gallop(after, |x| f(x).is_lt()),
+            // The target is at or before the cursor's position.
+            _ => gallop_back(before, |x| f(x).is_lt()),
+        };
+
+        match self.peek().map(f) {
+            Some(Ordering::Equal) => Ok(self.position),
+            _ => Err(self.position),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+
+    use super::SearchCursor;
+    use crate::{test_util::XorShift, SharBinarySearch};
+
+    fn replay(slice: &[u32], keys: &[u32]) {
+        let mut cursor = SearchCursor::new(slice);
+        for key in keys {
+            let expected = slice.bl_binary_search(key);
+            assert_eq!(cursor.seek(key), expected, "{key}");
+            assert_eq!(cursor.position(), expected.unwrap_or_else(|i| i));
+        }
+    }
+
+    #[test]
+    fn test_replays_match_stateless_search() {
+        let mut rng = XorShift::new(53);
+
+        // Runs of duplicates, so seeks must land on the leftmost match.
+        let mut slice: Vec<u32> = (0..2000).map(|_| rng.below(700) as u32).collect();
+        slice.sort();
+
+        let mut sorted: Vec<u32> = (0..500).map(|_| rng.below(720) as u32).collect();
+        sorted.sort();
+        replay(&slice, &sorted);
+
+        let mut nearly_sorted = sorted.clone();
+        for _ in 0..50 {
+            let i = rng.below(nearly_sorted.len() as u64 - 3) as usize;
+            nearly_sorted.swap(i, i + rng.below(3) as usize + 1);
+        }
+        replay(&slice, &nearly_sorted);
+
+        let alternating: Vec<u32> = (0..500)
+            .map(|i| if i % 2 == 0 { i } else { 720 - i })
+            .collect();
+        replay(&slice, &alternating);
+
+        let random: Vec<u32> = (0..500).map(|_| rng.below(720) as u32).collect();
+        replay(&slice, &random);
+
+        replay(&[], &[0, 3, 1]);
+        replay(&[5], &[5, 0, 9, 5]);
+    }
+
+    #[test]
+    fn test_peek_and_start() {
+        let slice = [1, 3, 3, 5];
+        let mut cursor = SearchCursor::new(&slice[..]);
+        assert_eq!(cursor.peek(), Some(&1));
+
+        assert_eq!(cursor.seek(&9), Err(4));
+        assert_eq!(cursor.peek(), None);
+
+        assert_eq!(cursor.seek(&3), Ok(1));
+        assert_eq!(cursor.peek(), Some(&3));
+
+        cursor.advance_to_start();
+        assert_eq!(cursor.position(), 0);
+        assert_eq!(cursor.seek(&4), Err(3));
+    }
+
+    #[test]
+    fn test_short_moves_are_cheap() {
+        let slice: Vec<u32> = (0..1_000_000).collect();
+        let comparisons = Cell::new(0);
+        let mut cursor = SearchCursor::new(&slice[..]);
+
+        for key in (500_000..500_100).chain((400_000..400_100).rev()) {
+            assert_eq!(cursor.seek(&500_000), Ok(500_000));
+            comparisons.set(0);
+            let result = cursor.seek_by(|x| {
+                comparisons.set(comparisons.get() + 1);
+                x.cmp(&key)
+            });
+            assert_eq!(result, Ok(key as usize));
+            let distance = (key as usize).abs_diff(500_000);
+            assert!(
+                comparisons.get() <= 2 * (distance + 1).ilog2() as usize + 8,
+                "{} comparisons to move {distance}",
+                comparisons.get(),
+            );
+        }
+    }
+}
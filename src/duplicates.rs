@@ -0,0 +1,133 @@
+//! Policies for handling duplicates when building sorted containers from unsorted input.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::{error::Error, fmt};
+
+#[cfg(feature = "alloc")]
+use crate::{raw, validate};
+
+/// What to do with elements that compare equal when building a sorted container from unsorted
+/// input, such as with [`SortedVec::from_unsorted_iter`](crate::SortedVec::from_unsorted_iter).
+///
+/// For maps, elements compare equal when their keys do, so [`DuplicatePolicy::KeepFirst`] and
+/// [`DuplicatePolicy::KeepLast`] choose which value survives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DuplicatePolicy {
+    /// Keep every element. Sets and maps cannot hold duplicates, so for them this behaves like
+    /// [`DuplicatePolicy::KeepFirst`].
+    KeepAll,
+    /// Keep the element that came first in the input.
+    KeepFirst,
+    /// Keep the element that came last in the input.
+    KeepLast,
+    /// Fail with a [`DuplicateError`] if any two elements compare equal.
+    Error,
+}
+
+/// The error returned when building a sorted container with [`DuplicatePolicy::Error`] from
+/// input that contains duplicates.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuplicateError<K> {
+    pub(crate) key: K,
+}
+
+impl<K> DuplicateError<K> {
+    /// Returns the duplicated key. If several keys are duplicated, this is the smallest.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Consumes the error, returning the duplicated key.
+    pub fn into_key(self) -> K {
+        self.key
+    }
+}
+
+impl<K: fmt::Debug> fmt::Display for DuplicateError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "duplicate key {:?} in input", self.key)
+    }
+}
+
+impl<K: fmt::Debug> Error for DuplicateError<K> {}
+
+/// Sorts `vec` by `key` and resolves duplicate keys according to `policy`, in place.
+///
+/// [`DuplicatePolicy::KeepFirst`] and [`DuplicatePolicy::KeepLast`] need a stable sort so that
+/// equal keys stay in input order before deduplicating; the other policies do not care about
+/// the order of equal keys, so they use the faster unstable sort. `keep_all` says whether the
+/// container can hold duplicates at all.
+///
+/// On [`DuplicatePolicy::Error`], the duplicated element with the smallest key is removed from
+/// `vec` and returned.
+#[cfg(feature = "alloc")]
+pub(crate) fn sort_with_policy<T, K, F>(
+    vec: &mut Vec<T>,
+    policy: DuplicatePolicy,
+    keep_all: bool,
+    key: F,
+) -> Result<(), T>
+where
+    K: Ord + ?Sized,
+    F: Fn(&T) -> &K,
+{
+    match policy {
+        DuplicatePolicy::KeepAll if keep_all => {
+            vec.sort_unstable_by(|a, b| key(a).cmp(key(b)));
+        }
+        DuplicatePolicy::KeepAll | DuplicatePolicy::KeepFirst => {
+            vec.sort_by(|a, b| key(a).cmp(key(b)));
+            vec.dedup_by(|later, kept| key(later) == key(kept));
+        }
+        DuplicatePolicy::KeepLast => {
+            vec.sort_by(|a, b| key(a).cmp(key(b)));
+            raw::dedup_keep_last_by(vec, |later, kept| key(later) == key(kept));
+        }
+        DuplicatePolicy::Error => {
+            vec.sort_unstable_by(|a, b| key(a).cmp(key(b)));
+            if let Some(index) = validate::first_unsorted_by(vec, |a, b| key(a) < key(b)) {
+                return Err(vec.swap_remove(index));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod test {
+    use super::{sort_with_policy, DuplicatePolicy};
+
+    #[test]
+    fn test_policies() {
+        let input = vec![(3, 'a'), (1, 'b'), (3, 'c'), (2, 'd'), (1, 'e'), (3, 'f')];
+        fn key(pair: &(u32, char)) -> &u32 {
+            &pair.0
+        }
+
+        let mut vec = input.clone();
+        sort_with_policy(&mut vec, DuplicatePolicy::KeepAll, true, key).unwrap();
+        assert!(vec.iter().map(key).eq(&[1, 1, 2, 3, 3, 3]));
+
+        let mut vec = input.clone();
+        sort_with_policy(&mut vec, DuplicatePolicy::KeepAll, false, key).unwrap();
+        assert_eq!(vec, [(1, 'b'), (2, 'd'), (3, 'a')]);
+
+        let mut vec = input.clone();
+        sort_with_policy(&mut vec, DuplicatePolicy::KeepFirst, true, key).unwrap();
+        assert_eq!(vec, [(1, 'b'), (2, 'd'), (3, 'a')]);
+
+        let mut vec = input.clone();
+        sort_with_policy(&mut vec, DuplicatePolicy::KeepLast, true, key).unwrap();
+        assert_eq!(vec, [(1, 'e'), (2, 'd'), (3, 'f')]);
+
+        let mut vec = input;
+        let err = sort_with_policy(&mut vec, DuplicatePolicy::Error, true, key).unwrap_err();
+        assert_eq!(err.0, 1);
+
+        let mut vec = vec![(2, 'a'), (1, 'b')];
+        sort_with_policy(&mut vec, DuplicatePolicy::Error, true, key).unwrap();
+        assert_eq!(vec, [(1, 'b'), (2, 'a')]);
+    }
+}
@@ -0,0 +1,225 @@
+//! A C interface to the searches over slices of primitive integers, enabled by the `ffi`
+//! feature.
+//!
+//! Each supported type `T` gets three functions, declared in `include/shar_search.h`:
+//!
+//! - `size_t shar_lower_bound_T(const T *ptr, size_t len, T key)`, the index of the first
+//!   element not less than `key`.
+//! - `size_t shar_upper_bound_T(const T *ptr, size_t len, T key)`, the index of the first
+//!   element greater than `key`.
+//! - `bool shar_search_T(const T *ptr, size_t len, T key, size_t *out_index)`, which returns
+//!   whether `key` was found and writes the index of its first occurrence, or the index where it
+//!   could be inserted, to `out_index`.
+//!
+//! A null `ptr` is treated as an empty array, whatever `len` is, and `out_index` may be null if
+//! the index isn't needed. To link against these from C, build the crate as a `staticlib` or
+//! `cdylib`, e.g. with `cargo rustc --release --features ffi --crate-type staticlib`.
+
+use core::slice;
+
+use crate::SharBinarySearch;
+
+/// Returns the `len` elements at `ptr` as a slice, or an empty slice if `ptr` is null.
+///
+/// # Safety
+///
+/// If `ptr` is not null, it must point to `len` initialized, readable elements that aren't
+/// mutated for the returned lifetime.
+unsafe fn as_slice<'a, T>(ptr: *const T, len: usize) -> &'a [T] {
+    if ptr.is_null() || len == 0 {
+        &[]
+    } else {
+        // SAFETY: upheld by the caller.
+        unsafe { slice::from_raw_parts(ptr, len) }
+    }
+}
+
+macro_rules! ffi_searches {
+    ($($ty:ty => $c_ty:literal: $lower:ident, $upper:ident, $search:ident;)*) => {
+        $(
+            #[doc = concat!("Returns the index of the first element of the sorted `", stringify!($ty), "`")]
+            /// array at `ptr` that is not less than `key`, or `len` if there is none.
+            ///
+            /// # Safety
+            ///
+            /// If `ptr` is not null, it must point to `len` initialized, readable elements.
+            #[no_mangle]
+            pub unsafe extern "C" fn $lower(ptr: *const $ty, len: usize, key: $ty) -> usize {
+                // SAFETY: upheld by the caller.
+                let slice = unsafe { as_slice(ptr, len) };
+                slice.bl_partition_point(|&p| p < key)
+            }
+
+            #[doc = concat!("Returns the index of the first element of the sorted `", stringify!($ty), "`")]
+            /// array at `ptr` that is greater than `key`, or `len` if there is none.
+            ///
+            /// # Safety
+            ///
+            /// If `ptr` is not null, it must point to `len` initialized, readable elements.
+            #[no_mangle]
+            pub unsafe extern "C" fn $upper(ptr: *const $ty, len: usize, key: $ty) -> usize {
+                // SAFETY: upheld by the caller.
+                let slice = unsafe { as_slice(ptr, len) };
+                slice.bl_partition_point(|&p| p <= key)
+            }
+
+            #[doc = concat!("Searches the sorted `", stringify!($ty), "` array at `ptr` for `key`, returning")]
+            /// whether it was found. Unless `out_index` is null, the index of the first match, or
+            /// the index where `key` could be inserted to keep the array sorted, is written to it.
+            ///
+            /// # Safety
+            ///
+            /// If `ptr` is not null, it must point to `len` initialized, readable elements. If
+            /// `out_index` is not null, it must be valid for writes.
+            #[no_mangle]
+            pub unsafe extern "C" fn $search(
+                ptr: *const $ty,
+                len: usize,
+                key: $ty,
+                out_index: *mut usize,
+            ) -> bool {
+                // SAFETY: upheld by the caller.
+                let slice = unsafe { as_slice(ptr, len) };
+                let (found, index) = match slice.bl_binary_search(&key) {
+                    Ok(index) => (true, index),
+                    Err(index) => (false, index),
+                };
+
+                if !out_index.is_null() {
+                    // SAFETY: upheld by the caller.
+                    unsafe { out_index.write(index) };
+                }
+
+                found
+            }
+        )*
+
+        /// The Rust and C names of each type with exported searches, in header order.
+        #[cfg(test)]
+        const TYPES: &[(&str, &str)] = &[$((stringify!($ty), $c_ty)),*];
+    };
+}
+
+ffi_searches! {
+    u8 => "uint8_t": shar_lower_bound_u8, shar_upper_bound_u8, shar_search_u8;
+    u16 => "uint16_t": shar_lower_bound_u16, shar_upper_bound_u16, shar_search_u16;
+    u32 => "uint32_t": shar_lower_bound_u32, shar_upper_bound_u32, shar_search_u32;
+    u64 => "uint64_t": shar_lower_bound_u64, shar_upper_bound_u64, shar_search_u64;
+    i8 => "int8_t": shar_lower_bound_i8, shar_upper_bound_i8, shar_search_i8;
+    i16 => "int16_t": shar_lower_bound_i16, shar_upper_bound_i16, shar_search_i16;
+    i32 => "int32_t": shar_lower_bound_i32, shar_upper_bound_i32, shar_search_i32;
+    i64 => "int64_t": shar_lower_bound_i64, shar_upper_bound_i64, shar_search_i64;
+}
+
+#[cfg(test)]
+mod test {
+    use std::ptr;
+
+    use super::*;
+    use crate::test_util::XorShift;
+
+    /// Renders the C header declaring every exported function.
+    fn header() -> String {
+        let mut out = String::from(
+            "/* Generated from src/ffi.rs: run `SHAR_BLESS=1 cargo test --features ffi` after\n \
+             * changing the exported functions to update it. */\n\n\
+             #ifndef SHAR_SEARCH_H\n#define SHAR_SEARCH_H\n\n\
+             #include <stdbool.h>\n#include <stddef.h>\n#include <stdint.h>\n\n\
+             #ifdef __cplusplus\nextern \"C\" {\n#endif\n",
+        );
+
+        for (ty, c_ty) in TYPES {
+            out += &format!(
+                "\nsize_t shar_lower_bound_{ty}(const {c_ty} *ptr, size_t len, {c_ty} key);\n\
+                 size_t shar_upper_bound_{ty}(const {c_ty} *ptr, size_t len, {c_ty} key);\n\
+                 bool shar_search_{ty}(const {c_ty} *ptr, size_t len, {c_ty} key, \
+                 size_t *out_index);\n"
+            );
+        }
+
+        out + "\n#ifdef __cplusplus\n}\n#endif\n\n#endif /* SHAR_SEARCH_H */\n"
+    }
+
+    #[test]
+    fn test_header_in_sync() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/include/shar_search.h");
+        let expected = header();
+
+        if std::env::var_os("SHAR_BLESS").is_some() {
+            std::fs::write(path, &expected).unwrap();
+        }
+
+        let checked_in = std::fs::read_to_string(path).unwrap();
+        assert!(
+            checked_in == expected,
+            "include/shar_search.h is out of date, rerun with SHAR_BLESS=1 to update it"
+        );
+    }
+
+    #[test]
+    fn test_null_and_empty() {
+        let mut index = 7;
+
+        unsafe {
+            assert_eq!(shar_lower_bound_u64(ptr::null(), 10, 3), 0);
+            assert_eq!(shar_upper_bound_u64(ptr::null(), 10, 3), 0);
+            assert!(!shar_search_u64(ptr::null(), 10, 3, &mut index));
+            assert_eq!(index, 0);
+
+            let array = [1_i32, 2, 3];
+            assert_eq!(shar_lower_bound_i32(array.as_ptr(), 0, 3), 0);
+            assert!(!shar_search_i32(array.as_ptr(), 0, 3, &mut index));
+            assert!(shar_search_i32(array.as_ptr(), 3, 3, ptr::null_mut()));
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        // Declared as C code would see them, so the calls go through the C ABI.
+        extern "C" {
+            fn shar_lower_bound_u32(ptr: *const u32, len: usize, key: u32) -> usize;
+            fn shar_upper_bound_u32(ptr: *const u32, len: usize, key: u32) -> usize;
+            fn shar_search_u32(ptr: *const u32, len: usize, key: u32, out: *mut usize) -> bool;
+            fn shar_search_i8(ptr: *const i8, len: usize, key: i8, out: *mut usize) -> bool;
+        }
+
+        let mut rng = XorShift::new(141);
+
+        for _ in 0..100 {
+            let mut array: Vec<u32> = (0..rng.below(40)).map(|_| rng.below(30) as u32).collect();
+            array.sort_unstable();
+            let signed: Vec<i8> = array.iter().map(|&x| x as i8 - 15).collect();
+
+            for key in 0..32 {
+                let mut index = usize::MAX;
+                let found =
+                    unsafe { shar_search_u32(array.as_ptr(), array.len(), key, &mut index) };
+                assert_eq!(
+                    if found { Ok(index) } else { Err(index) },
+                    array.bl_binary_search(&key)
+                );
+
+                let lower = array.partition_point(|&p| p < key);
+                let upper = array.partition_point(|&p| p <= key);
+                unsafe {
+                    assert_eq!(
+                        shar_lower_bound_u32(array.as_ptr(), array.len(), key),
+                        lower
+                    );
+                    assert_eq!(
+                        shar_upper_bound_u32(array.as_ptr(), array.len(), key),
+                        upper
+                    );
+                }
+
+                let key = key as i8 - 15;
+                let found =
+                    unsafe { shar_search_i8(signed.as_ptr(), signed.len(), key, &mut index) };
+                assert_eq!(
+                    if found { Ok(index) } else { Err(index) },
+                    signed.bl_binary_search(&key)
+                );
+            }
+        }
+    }
+}
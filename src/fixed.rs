@@ -0,0 +1,548 @@
+//! A sorted vector with a capacity fixed at compile time, for targets without an allocator.
+//!
+//! [`FixedSortedVec`] keeps its elements inline in an array of `N` slots, so it never
+//! allocates and works without the `alloc` feature. Inserting into a full vector fails with a
+//! [`CapacityFull`] error that hands the element back.
+//!
+//! For example, firmware can dispatch incoming CAN frames through a table of routes, built once
+//! at startup. Everything here is in `core`, so this builds with `--no-default-features` for
+//! targets such as `thumbv7em-none-eabihf`:
+//!
+//! ```
+//! use shar_search::{fixed::FixedSortedVec, SharBinarySearch};
+//!
+//! #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+//! enum Action {
+//!     SetLed,
+//!     ReadAdc,
+//!     Reset,
+//! }
+//!
+//! /// Routes by frame identifier.
+//! fn routes() -> FixedSortedVec<(u16, Action), 4> {
+//!     let mut routes = FixedSortedVec::new();
+//!     for route in [(0x200, Action::ReadAdc), (0x100, Action::SetLed), (0x7ff, Action::Reset)] {
+//!         routes.try_insert(route).expect("too many routes");
+//!     }
+//!     routes
+//! }
+//!
+//! fn dispatch(routes: &[(u16, Action)], id: u16) -> Option<Action> {
+//!     let index = routes.bl_binary_search_by_key(&id, |(id, _)| *id).ok()?;
+//!     Some(routes[index].1)
+//! }
+//!
+//! let mut routes = routes();
+//! assert_eq!(dispatch(&routes, 0x200), Some(Action::ReadAdc));
+//! assert_eq!(dispatch(&routes, 0x201), None);
+//!
+//! // One more route fits, then the table is full.
+//! assert_eq!(routes.try_insert((0x300, Action::ReadAdc)), Ok(2));
+//! let full = routes.try_insert((0x400, Action::ReadAdc)).unwrap_err();
+//! assert_eq!(full.into_item(), (0x400, Action::ReadAdc));
+//! ```
+//!
+//! A table that never changes can be a `static` instead, built at compile time with
+//! [`FixedSortedVec::from_sorted_array`] and placed in flash rather than RAM:
+//!
+//! ```
+//! use shar_search::{
+//!     const_search::is_sorted_by_first_u32, fixed::FixedSortedVec, SharBinarySearch,
+//! };
+//!
+//! #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+//! enum Action {
+//!     SetLed,
+//!     ReadAdc,
+//!     Reset,
+//! }
+//!
+//! const ROUTE_LIST: [(u32, Action); 3] = [
+//!     (0x100, Action::SetLed),
+//!     (0x200, Action::ReadAdc),
+//!     (0x7ff, Action::Reset),
+//! ];
+//! // The constructor can't check the order, so check it when compiling.
+//! const _: () = assert!(is_sorted_by_first_u32(&ROUTE_LIST));
+//!
+//! static ROUTES: FixedSortedVec<(u32, Action), 3> = FixedSortedVec::from_sorted_array(ROUTE_LIST);
+//!
+//! fn dispatch(id: u32) -> Option<Action> {
+//!     let index = ROUTES.bl_binary_search_by_key(&id, |(id, _)| *id).ok()?;
+//!     Some(ROUTES[index].1)
+//! }
+//!
+//! assert_eq!(dispatch(0x7ff), Some(Action::Reset));
+//! assert_eq!(dispatch(0x300), None);
+//! assert!(ROUTES.is_full());
+//! ```
+
+use core::{
+    borrow::Borrow,
+    error::Error,
+    fmt,
+    mem::{ManuallyDrop, MaybeUninit},
+    ops::{Deref, RangeBounds},
+    ptr, slice,
+};
+
+use crate::{resolve_range, SharBinarySearch};
+
+/// The error returned when inserting into a full [`FixedSortedVec`]. It holds the rejected
+/// item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CapacityFull<T>(pub T);
+
+impl<T> CapacityFull<T> {
+    /// Consumes the error, returning the rejected item.
+    pub fn into_item(self) -> T {
+        self.0
+    }
+}
+
+impl<T> fmt::Display for CapacityFull<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the sorted vector is at capacity")
+    }
+}
+
+impl<T: fmt::Debug> Error for CapacityFull<T> {}
+
+/// A sorted vector of at most `N` elements, stored inline, allowing duplicates.
+///
+/// Equal elements are kept in insertion order. The vector dereferences to a sorted slice, so
+/// all the slice search methods are available on it directly.
+pub struct FixedSortedVec<T, const N: usize> {
+    /// Invariant: `buf[..len]` is initialized and sorted, and `buf[len..]` is not.
+    buf: [MaybeUninit<T>; N],
+    len: usize,
+}
+
+impl<T, const N: usize> FixedSortedVec<T, N> {
+    /// Creates a new, empty sorted vector.
+    pub const fn new() -> Self {
+        Self {
+            buf: [const { MaybeUninit::uninit() }; N],
+            len: 0,
+        }
+    }
+
+    /// Creates a sorted vector holding `items`, in a `const` context, so that a table known at
+    /// compile time can be a `static`. Note it is assumed that `items` is sorted: a `const fn`
+    /// can't compare them, but the [`const_search`](crate::const_search) checks can, as in the
+    /// [module](crate::fixed) example.
+    ///
+    /// # Panics
+    ///
+    /// Panics, at compile time in a `const` or `static`, if `M` is greater than `N`.
+    pub const fn from_sorted_array<const M: usize>(items: [T; M]) -> Self {
+        assert!(M <= N, "the items must fit in the capacity");
+
+        let items = ManuallyDrop::new(items);
+        let mut buf = [const { MaybeUninit::uninit() }; N];
+        // The items move into the buffer, and `ManuallyDrop` keeps them from being dropped
+        // where they were.
+        unsafe {
+            ptr::copy_nonoverlapping(
+                (&raw const items).cast::<T>(),
+                buf.as_mut_ptr().cast::<T>(),
+                M,
+            );
+        }
+
+        Self { buf, len: M }
+    }
+
+    /// Returns the number of elements.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the vector is empty.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns whether the vector is full, so inserting into it would fail.
+    pub const fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Returns the number of elements the vector can hold: `N`.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the elements as a sorted slice.
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.buf.as_ptr().cast(), self.len) }
+    }
+
+    /// Returns an iterator over the elements in order.
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    /// Removes all elements.
+    pub fn clear(&mut self) {
+        let initialized: *mut [T] = self.initialized_mut();
+        // Forget the elements before dropping them, so that if a drop panics, none of them is
+        // dropped twice. The rest are still dropped while unwinding.
+        self.len = 0;
+        unsafe { ptr::drop_in_place(initialized) };
+    }
+
+    /// Removes and returns the element at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove_index(&mut self, index: usize) -> T {
+        assert!(
+            index < self.len,
+            "removal index (is {index}) should be < len (is {})",
+            self.len
+        );
+
+        unsafe {
+            let base = self.buf.as_mut_ptr().cast::<T>();
+            let removed = ptr::read(base.add(index));
+            ptr::copy(base.add(index + 1), base.add(index), self.len - index - 1);
+            self.len -= 1;
+            removed
+        }
+    }
+
+    fn initialized_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.buf.as_mut_ptr().cast(), self.len) }
+    }
+}
+
+impl<T: Ord, const N: usize> FixedSortedVec<T, N> {
+    /// Inserts `value` after any elements equal to it, returning the index it was inserted at.
+    ///
+    /// The position is found before anything is moved, so if `T`'s comparison panics, the
+    /// vector is left unchanged.
+    ///
+    /// # Errors
+    ///
+    /// If the vector is full, returns `value` in a [`CapacityFull`] and leaves the vector
+    /// unchanged.
+    pub fn try_insert(&mut self, value: T) -> Result<usize, CapacityFull<T>> {
+        if self.is_full() {
+            return Err(CapacityFull(value));
+        }
+
+        let index = self.as_slice().bl_upper_bound(&value);
+        unsafe {
+            let base = self.buf.as_mut_ptr().cast::<T>();
+            ptr::copy(base.add(index), base.add(index + 1), self.len - index);
+            ptr::write(base.add(index), value);
+        }
+        self.len += 1;
+
+        Ok(index)
+    }
+
+    /// Binary searches for `x`. If there are multiple matches, the *first* is returned.
+    pub fn search<Q>(&self, x: &Q) -> Result<usize, usize>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.as_slice().bl_binary_search_by(|p| p.borrow().cmp(x))
+    }
+
+    /// Returns whether the vector contains an element equal to `x`.
+    pub fn contains<Q>(&self, x: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search(x).is_ok()
+    }
+
+    /// Removes and returns the first element equal to `x`, if any.
+    pub fn remove<Q>(&mut self, x: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search(x).ok().map(|index| self.remove_index(index))
+    }
+
+    /// Returns the subslice of elements within `range`, resolved with the branchless search.
+    /// If the start of the range lies after its end, the subslice is empty.
+    pub fn range<Q, R>(&self, range: R) -> &[T]
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let slice = self.as_slice();
+        &slice[resolve_range(slice, &range, |p| p.borrow())]
+    }
+}
+
+impl<T, const N: usize> Drop for FixedSortedVec<T, N> {
+    fn drop(&mut self) {
+        unsafe { ptr::drop_in_place(self.initialized_mut()) };
+    }
+}
+
+impl<T, const N: usize> Default for FixedSortedVec<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Deref for FixedSortedVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for FixedSortedVec<T, N> {
+    fn clone(&self) -> Self {
+        let mut clone = Self::new();
+        for item in self.iter() {
+            // Pushed one at a time, so if a clone panics, the ones before it are dropped.
+            clone.buf[clone.len].write(item.clone());
+            clone.len += 1;
+        }
+        clone
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for FixedSortedVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a FixedSortedVec<T, N> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        cell::Cell,
+        cmp::Ordering,
+        ops::Bound,
+        panic::{catch_unwind, AssertUnwindSafe},
+    };
+
+    use super::{CapacityFull, FixedSortedVec};
+    use crate::test_util::XorShift;
+
+    /// Counts its drops in a shared counter, and can be made to panic when compared or dropped.
+    #[derive(Debug)]
+    struct Counted<'a> {
+        key: u32,
+        drops: &'a Cell<usize>,
+        panic_on_cmp: bool,
+        panic_on_drop: bool,
+    }
+
+    impl<'a> Counted<'a> {
+        fn new(key: u32, drops: &'a Cell<usize>) -> Self {
+            Self {
+                key,
+                drops,
+                panic_on_cmp: false,
+                panic_on_drop: false,
+            }
+        }
+    }
+
+    impl Clone for Counted<'_> {
+        fn clone(&self) -> Self {
+            Self::new(self.key, self.drops)
+        }
+    }
+
+    impl Drop for Counted<'_> {
+        fn drop(&mut self) {
+            self.drops.set(self.drops.get() + 1);
+            if self.panic_on_drop {
+                panic!("drop failed");
+            }
+        }
+    }
+
+    impl PartialEq for Counted<'_> {
+        fn eq(&self, other: &Self) -> bool {
+            self.cmp(other).is_eq()
+        }
+    }
+
+    impl Eq for Counted<'_> {}
+
+    impl PartialOrd for Counted<'_> {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Counted<'_> {
+        fn cmp(&self, other: &Self) -> Ordering {
+            if self.panic_on_cmp || other.panic_on_cmp {
+                panic!("comparison failed");
+            }
+            self.key.cmp(&other.key)
+        }
+    }
+
+    #[test]
+    fn test_against_sorted() {
+        let mut rng = XorShift::new(169);
+
+        for _ in 0..200 {
+            let mut fixed: FixedSortedVec<u32, 16> = FixedSortedVec::new();
+            let mut reference: Vec<u32> = Vec::new();
+
+            for _ in 0..rng.below(40) {
+                let value = rng.below(20) as u32;
+                if rng.below(3) == 0 {
+                    let expected = reference
+                        .iter()
+                        .position(|x| *x == value)
+                        .map(|i| reference.remove(i));
+                    assert_eq!(fixed.remove(&value), expected);
+                } else if reference.len() < 16 {
+                    let index = reference.partition_point(|x| *x <= value);
+                    reference.insert(index, value);
+                    assert_eq!(fixed.try_insert(value), Ok(index));
+                } else {
+                    assert_eq!(fixed.try_insert(value), Err(CapacityFull(value)));
+                }
+
+                assert_eq!(fixed.as_slice(), reference);
+            }
+
+            for value in 0..21 {
+                assert_eq!(fixed.contains(&value), reference.contains(&value));
+                assert_eq!(
+                    fixed.search(&value).is_ok(),
+                    reference.binary_search(&value).is_ok()
+                );
+            }
+            let expected: Vec<_> = reference.iter().filter(|x| (5..12).contains(*x)).collect();
+            assert!(fixed.range(5..12).iter().eq(expected));
+        }
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut fixed: FixedSortedVec<&str, 2> = FixedSortedVec::default();
+        assert_eq!(fixed.capacity(), 2);
+        assert_eq!(fixed.try_insert("b"), Ok(0));
+        assert_eq!(fixed.try_insert("a"), Ok(0));
+        assert!(fixed.is_full());
+
+        let err = fixed.try_insert("c").unwrap_err();
+        assert_eq!(err.to_string(), "the sorted vector is at capacity");
+        assert_eq!(err.into_item(), "c");
+        assert_eq!(fixed.as_slice(), &["a", "b"]);
+
+        assert_eq!(
+            fixed.range::<&str, _>((Bound::Excluded("a"), Bound::Unbounded)),
+            &["b"]
+        );
+        assert_eq!(format!("{:?}", fixed.clone()), r#"["a", "b"]"#);
+        assert!(fixed.iter().eq(&fixed));
+
+        // A vector with no capacity is always full.
+        let mut empty: FixedSortedVec<u8, 0> = FixedSortedVec::new();
+        assert!(empty.is_empty() && empty.is_full());
+        assert_eq!(empty.try_insert(1), Err(CapacityFull(1)));
+    }
+
+    #[test]
+    fn test_drops() {
+        let drops = Cell::new(0);
+        let mut fixed: FixedSortedVec<Counted, 4> = FixedSortedVec::new();
+        for key in [3, 1, 2] {
+            fixed.try_insert(Counted::new(key, &drops)).unwrap();
+        }
+
+        // Removing hands the element back without dropping anything.
+        let removed = fixed.remove(&Counted::new(2, &drops)).unwrap();
+        assert_eq!(drops.get(), 1);
+        drop(removed);
+        assert_eq!(drops.get(), 2);
+
+        // Rejected items are handed back too.
+        fixed.try_insert(Counted::new(4, &drops)).unwrap();
+        fixed.try_insert(Counted::new(5, &drops)).unwrap();
+        let rejected = fixed.try_insert(Counted::new(6, &drops)).unwrap_err();
+        assert_eq!(drops.get(), 2);
+        drop(rejected);
+        assert_eq!(drops.get(), 3);
+
+        let clone = fixed.clone();
+        fixed.clear();
+        assert_eq!(drops.get(), 7);
+        assert!(fixed.is_empty());
+
+        // Only the initialized prefix is dropped.
+        drop(clone);
+        drop(fixed);
+        assert_eq!(drops.get(), 11);
+    }
+
+    #[test]
+    fn test_panic_safety() {
+        let drops = Cell::new(0);
+        let mut fixed: FixedSortedVec<Counted, 8> = FixedSortedVec::new();
+        for key in [10, 20, 30, 40] {
+            fixed.try_insert(Counted::new(key, &drops)).unwrap();
+        }
+
+        // A panicking comparison leaves the vector unchanged, and drops the value once.
+        let mut bad = Counted::new(25, &drops);
+        bad.panic_on_cmp = true;
+        assert!(catch_unwind(AssertUnwindSafe(|| fixed.try_insert(bad))).is_err());
+        assert_eq!(drops.get(), 1);
+        assert!(fixed.iter().map(|c| c.key).eq([10, 20, 30, 40]));
+
+        // A panicking drop still drops every other element, exactly once.
+        fixed.try_insert(Counted::new(15, &drops)).unwrap();
+        let mut bad = Counted::new(35, &drops);
+        bad.panic_on_drop = true;
+        fixed.try_insert(bad).unwrap();
+        assert!(catch_unwind(AssertUnwindSafe(|| drop(fixed))).is_err());
+        assert_eq!(drops.get(), 7);
+    }
+
+    #[test]
+    fn test_from_sorted_array() {
+        const PRIMES: FixedSortedVec<u32, 8> = FixedSortedVec::from_sorted_array([2, 3, 5, 7]);
+        let mut primes = PRIMES;
+        assert_eq!(primes.as_slice(), [2, 3, 5, 7]);
+        assert_eq!(primes.try_insert(11), Ok(4));
+
+        // The items move in without being dropped, and are dropped once with the vector.
+        let drops = Cell::new(0);
+        let items = [1, 2, 3].map(|key| Counted::new(key, &drops));
+        let fixed: FixedSortedVec<Counted, 3> = FixedSortedVec::from_sorted_array(items);
+        assert_eq!(drops.get(), 0);
+        assert!(fixed.is_full());
+        drop(fixed);
+        assert_eq!(drops.get(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "the items must fit in the capacity")]
+    fn test_from_sorted_array_too_long() {
+        FixedSortedVec::<u8, 2>::from_sorted_array([1, 2, 3]);
+    }
+}
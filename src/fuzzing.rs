@@ -0,0 +1,247 @@
+//! [`arbitrary`](https://docs.rs/arbitrary) support for the sorted containers, enabled with the
+//! `arbitrary` feature, for structure-aware fuzzing.
+//!
+//! The [`Arbitrary`] implementations generate elements and then establish the container's
+//! invariant, sorting (and deduplicating where the container requires it) the way the lenient
+//! [`serialization`](crate::serialization) does, so every generated container is valid and fuzz
+//! inputs aren't wasted on data that would be rejected.
+//!
+//! To fuzz code that must cope with data that is *not* sorted, [`AlmostSorted`] generates sorted
+//! data with at most one pair of elements swapped, and reports which.
+//!
+//! ```
+//! use arbitrary::{Arbitrary, Unstructured};
+//! use shar_search::SharSet;
+//!
+//! let mut u = Unstructured::new(&[1, 9, 1, 3, 1, 9, 1, 4, 0]);
+//! let set = SharSet::<u8>::arbitrary(&mut u).unwrap();
+//! assert!(set.as_slice().is_sorted());
+//! ```
+
+use alloc::vec::Vec;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+#[cfg(target_has_atomic = "ptr")]
+use crate::SortedArc;
+use crate::{raw, SharMap, SharMultiMap, SharSet, SortedVec};
+
+impl<'a, T: Arbitrary<'a> + Ord> Arbitrary<'a> for SortedVec<T> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut values = Vec::<T>::arbitrary(u)?;
+        values.sort();
+        Ok(Self::from_sorted_vec_unchecked(values))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        Vec::<T>::size_hint(depth)
+    }
+}
+
+impl<'a, T: Arbitrary<'a> + Ord> Arbitrary<'a> for SharSet<T> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut values = Vec::<T>::arbitrary(u)?;
+        values.sort();
+        values.dedup();
+        Ok(Self::from_sorted_vec_unchecked(values))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        Vec::<T>::size_hint(depth)
+    }
+}
+
+impl<'a, K: Arbitrary<'a> + Ord, V: Arbitrary<'a>> Arbitrary<'a> for SharMap<K, V> {
+    /// If a key is generated more than once, the last value wins.
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut pairs = Vec::<(K, V)>::arbitrary(u)?;
+        pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+        raw::dedup_keep_last_by(&mut pairs, |(a, _), (b, _)| a == b);
+        Ok(Self::from_sorted_vec_unchecked(pairs))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        Vec::<(K, V)>::size_hint(depth)
+    }
+}
+
+impl<'a, K: Arbitrary<'a> + Ord, V: Arbitrary<'a>> Arbitrary<'a> for SharMultiMap<K, V> {
+    /// Pairs are stably sorted by key, so values for a key keep their generated order.
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut pairs = Vec::<(K, V)>::arbitrary(u)?;
+        pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok(Self::from_sorted_vec_unchecked(pairs))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        Vec::<(K, V)>::size_hint(depth)
+    }
+}
+
+/// A sorted slice, generated as a [`Vec`] that is then sorted.
+#[cfg(target_has_atomic = "ptr")]
+impl<'a, T: Arbitrary<'a> + Ord> Arbitrary<'a> for SortedArc<T> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::from_vec(Vec::<T>::arbitrary(u)?))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        Vec::<T>::size_hint(depth)
+    }
+}
+
+/// Sorted data with at most one pair of elements swapped, for differential fuzzing of code that
+/// must detect or tolerate unsorted input.
+///
+/// Whether to swap a pair, and which, is generated along with the data. A swap of two equal
+/// elements would leave the data sorted, so in that case nothing is swapped.
+///
+/// ```
+/// use arbitrary::{Arbitrary, Unstructured};
+/// use shar_search::{fuzzing::AlmostSorted, validate};
+///
+/// let bytes: Vec<u8> = (0..64_u32).map(|i| (i * 37) as u8).collect();
+/// let data = AlmostSorted::<u16>::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+///
+/// // The oracle a fuzz target checks against.
+/// let unsorted_at = validate::first_unsorted_at(data.as_slice());
+/// assert_eq!(unsorted_at.is_some(), data.swapped().is_some());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AlmostSorted<T> {
+    values: Vec<T>,
+    swapped: Option<(usize, usize)>,
+}
+
+impl<T> AlmostSorted<T> {
+    /// Returns the data.
+    pub fn as_slice(&self) -> &[T] {
+        &self.values
+    }
+
+    /// Returns the indices `i < j` of the swapped pair, or `None` if the data is sorted.
+    pub fn swapped(&self) -> Option<(usize, usize)> {
+        self.swapped
+    }
+
+    /// Returns whether the data is sorted, so no pair was swapped.
+    pub fn is_sorted(&self) -> bool {
+        self.swapped.is_none()
+    }
+
+    /// Consumes the generator, returning the data.
+    pub fn into_vec(self) -> Vec<T> {
+        self.values
+    }
+
+    /// Wraps the data in a [`SortedVec`] *without* checking that it is sorted, for testing how
+    /// code built on a `SortedVec` behaves when the invariant is broken.
+    ///
+    /// This is not `unsafe`, but if a pair was swapped, the searches of the returned vector
+    /// return unspecified results. Only use it in tests and fuzz targets.
+    pub fn into_sorted_vec_unchecked(self) -> SortedVec<T> {
+        SortedVec::from_sorted_vec_unchecked(self.values)
+    }
+}
+
+impl<'a, T: Arbitrary<'a> + Ord> Arbitrary<'a> for AlmostSorted<T> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let corrupt = bool::arbitrary(u)?;
+        let mut values = Vec::<T>::arbitrary(u)?;
+        values.sort();
+
+        let mut swapped = None;
+        if corrupt && values.len() >= 2 {
+            let (a, b) = (u.choose_index(values.len())?, u.choose_index(values.len())?);
+            let (i, j) = (a.min(b), a.max(b));
+            if values[i] != values[j] {
+                values.swap(i, j);
+                swapped = Some((i, j));
+            }
+        }
+
+        Ok(Self { values, swapped })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and(bool::size_hint(depth), Vec::<T>::size_hint(depth))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    use super::AlmostSorted;
+    use crate::{
+        test_util::XorShift, validate, SharMap, SharMultiMap, SharSet, SortedArc, SortedVec,
+    };
+
+    /// Generates a value of each type from many random buffers of varied lengths.
+    fn samples<T>(seed: u64, mut check: impl FnMut(T))
+    where
+        T: for<'a> Arbitrary<'a>,
+    {
+        let mut rng = XorShift::new(seed);
+        for _ in 0..2000 {
+            let bytes: Vec<u8> = (0..rng.below(256)).map(|_| rng.below(256) as u8).collect();
+            if let Ok(value) = T::arbitrary(&mut Unstructured::new(&bytes)) {
+                check(value);
+            }
+            if let Ok(value) = T::arbitrary_take_rest(Unstructured::new(&bytes)) {
+                check(value);
+            }
+        }
+    }
+
+    #[test]
+    fn test_containers_are_valid() {
+        let mut lens = Vec::new();
+        samples(170, |vec: SortedVec<u8>| {
+            assert!(vec.is_sorted());
+            lens.push(vec.len());
+        });
+        // The samples cover both empty and longer containers.
+        assert!(lens.contains(&0), "{lens:?}");
+        assert!(lens.iter().any(|len| *len > 5), "{lens:?}");
+
+        samples(1170, |set: SharSet<i16>| {
+            assert!(validate::is_strictly_sorted(set.as_slice()));
+        });
+        samples(2170, |map: SharMap<u8, u32>| {
+            assert!(validate::is_strictly_sorted(
+                &map.keys().collect::<Vec<_>>()
+            ));
+        });
+        samples(3170, |map: SharMultiMap<u8, u32>| {
+            assert!(map.as_slice().is_sorted_by_key(|(k, _)| *k));
+        });
+        samples(4170, |arc: SortedArc<(u8, bool)>| assert!(arc.is_sorted()));
+    }
+
+    #[test]
+    fn test_almost_sorted() {
+        let (mut sorted, mut swapped) = (0, 0);
+        samples(5170, |data: AlmostSorted<u8>| match data.swapped() {
+            None => {
+                assert!(data.as_slice().is_sorted());
+                sorted += 1;
+            }
+            Some((i, j)) => {
+                assert!(i < j);
+                assert!(data.as_slice()[i] > data.as_slice()[j]);
+                assert!(validate::first_unsorted_at(data.as_slice()).is_some());
+
+                let mut values = data.clone().into_vec();
+                values.swap(i, j);
+                assert!(values.is_sorted());
+                assert_eq!(data.into_sorted_vec_unchecked().len(), values.len());
+                swapped += 1;
+            }
+        });
+        assert!(
+            sorted > 100 && swapped > 100,
+            "{sorted} sorted, {swapped} swapped"
+        );
+    }
+}
@@ -0,0 +1,68 @@
+//! Exponential ("galloping") search, used where the answer is expected to be near the start.
+
+use crate::SharBinarySearch;
+
+/// Returns the partition point of `pred` in `slice`, like
+/// [`bl_partition_point`](SharBinarySearch::bl_partition_point), but probes indices `0, 1, 3, 7,
+/// ...` first and only runs the branchless search inside the bracketing window. Finding a
+/// partition point at index `k` costs `O(log k)` comparisons rather than `O(log n)`.
+pub(crate) fn gallop<T, P>(slice: &[T], mut pred: P) -> usize
+where
+    P: FnMut(&T) -> bool,
+{
+    // Invariant: `pred` holds for all of `slice[..bound / 2]`.
+    let mut bound = 1;
+    while bound <= slice.len() && pred(&slice[bound - 1]) {
+        bound = match bound.checked_mul(2) {
+            Some(next) => next,
+            None => return slice.len(),
+        };
+    }
+
+    let start = bound / 2;
+    let end = (bound - 1).min(slice.len());
+
+    start + slice[start..end].bl_partition_point(pred)
+}
+
+/// Returns the partition point of `pred` in `slice` like [`gallop`], but gallops from the end:
+/// finding a partition point `k` elements before the end costs `O(log k)` comparisons.
+pub(crate) fn gallop_back<T, P>(slice: &[T], mut pred: P) -> usize
+where
+    P: FnMut(&T) -> bool,
+{
+    // Invariant: `pred` fails for all of `slice[len - bound / 2..]`.
+    let len = slice.len();
+    let mut bound = 1;
+    while bound <= len && !pred(&slice[len - bound]) {
+        bound = match bound.checked_mul(2) {
+            Some(next) => next,
+            None => return 0,
+        };
+    }
+
+    let start = len.saturating_sub(bound - 1);
+    let end = len - bound / 2;
+
+    start + slice[start..end].bl_partition_point(pred)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{gallop, gallop_back};
+
+    #[test]
+    fn test_gallop() {
+        let empty: [u32; 0] = [];
+        assert_eq!(gallop(&empty, |_| true), 0);
+        assert_eq!(gallop_back(&empty, |_| true), 0);
+
+        for len in 0..70_u32 {
+            let slice: Vec<u32> = (0..len).collect();
+            for point in 0..=len {
+                assert_eq!(gallop(&slice, |x| *x < point), point as usize);
+                assert_eq!(gallop_back(&slice, |x| *x < point), point as usize);
+            }
+        }
+    }
+}
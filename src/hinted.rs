@@ -0,0 +1,250 @@
+//! A sorted slice with a search hint shared between threads, for queries that cluster around a
+//! slowly moving region.
+
+use core::{
+    cmp::Ordering,
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering as AtomicOrdering},
+};
+
+use crate::{
+    gallop::{gallop, gallop_back},
+    SharBinarySearch,
+};
+
+/// A sorted slice whose searches start near where the last one, from any thread, ended.
+///
+/// This suits many threads querying the same slice for keys that cluster around a slowly moving
+/// region, such as the current window of a time series. Each search compares the key to the
+/// element at the hint, then gallops towards it, so a search `k` elements from the hint costs
+/// about `2 log k` comparisons. If the key is more than about `√n` elements away, galloping
+/// would cost more than a search from scratch, so after one probe that far out, the rest of the
+/// slice in that direction is searched with the branchless search instead. A search therefore
+/// costs at most a few comparisons more than [`bl_binary_search`], however far off the hint is.
+///
+/// The hint is an [`AtomicUsize`] read and written with relaxed ordering, so threads may see
+/// each other's hints late or overwrite them, but the hint only chooses where a search starts:
+/// the result is always the same as from [`bl_binary_search`].
+///
+/// [`bl_binary_search`]: crate::SharBinarySearch::bl_binary_search
+///
+/// ```
+/// use shar_search::hinted::HintedSlice;
+///
+/// let timestamps: Vec<u64> = (0..1_000_000).map(|i| i * 10).collect();
+/// let hinted = HintedSlice::new(&timestamps);
+///
+/// std::thread::scope(|s| {
+///     for thread in 0..4 {
+///         let hinted = &hinted;
+///         s.spawn(move || {
+///             for t in 0..100 {
+///                 let key = 5_000_000 + thread * 1000 + t * 10;
+///                 assert_eq!(hinted.search(&key), Ok(key as usize / 10));
+///             }
+///         });
+///     }
+/// });
+/// ```
+pub struct HintedSlice<'a, T> {
+    slice: &'a [T],
+    hint: AtomicUsize,
+    /// How far from the hint to gallop before searching the rest of the slice from scratch.
+    limit: usize,
+}
+
+impl<T: fmt::Debug> fmt::Debug for HintedSlice<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HintedSlice")
+            .field("slice", &self.slice)
+            .field("hint", &self.hint())
+            .finish()
+    }
+}
+
+impl<'a, T> HintedSlice<'a, T> {
+    /// Wraps `slice`, with the hint at its start. Note it is assumed that the slice is sorted.
+    pub fn new(slice: &'a [T]) -> Self {
+        // Roughly the square root of the length, past which galloping costs more than a full
+        // search.
+        let limit = 1 << (usize::BITS - slice.len().leading_zeros()).div_ceil(2);
+
+        Self {
+            slice,
+            hint: AtomicUsize::new(0),
+            limit,
+        }
+    }
+
+    /// Returns the wrapped slice.
+    pub fn slice(&self) -> &'a [T] {
+        self.slice
+    }
+
+    /// Returns the current hint: the index returned by a recent search.
+    pub fn hint(&self) -> usize {
+        self.hint.load(AtomicOrdering::Relaxed)
+    }
+
+    /// Binary searches for `key`, starting from the hint, and moves the hint to the result.
+    /// Returns the same result as
+    /// [`bl_binary_search`](crate::SharBinarySearch::bl_binary_search): if there are multiple
+    /// matches, the *first* is returned.
+    pub fn search(&self, key: &T) -> Result<usize, usize>
+    where
+        T: Ord,
+    {
+        self.search_by(|p| p.cmp(key))
+    }
+
+    /// Binary searches with a comparator function, starting from the hint. See
+    /// [`HintedSlice::search`].
+    pub fn search_by<F>(&self, mut f: F) -> Result<usize, usize>
+    where
+        F: FnMut(&T) -> Ordering,
+    {
+        // However stale, the hint only needs to be in bounds.
+        let hint = self.hint().min(self.slice.len());
+        let (before, after) = self.slice.split_at(hint);
+
+        let index = match after.first().map(&mut f) {
+            Some(Ordering::Less) => hint + self.forward(after, |x| f(x).is_lt()),
+            // The target is at or before the hint.
+            _ => self.backward(before, |x| f(x).is_lt()),
+        };
+        self.hint.store(index, AtomicOrdering::Relaxed);
+
+        match self.slice.get(index).map(f) {
+            Some(Ordering::Equal) => Ok(index),
+            _ => Err(index),
+        }
+    }
+
+    /// Returns the partition point of `pred` in `slice`, galloping from its start if it is
+    /// within the limit and searching the rest from scratch otherwise.
+    fn forward<P>(&self, slice: &[T], mut pred: P) -> usize
+    where
+        P: FnMut(&T) -> bool,
+    {
+        if self.limit < slice.len() && pred(&slice[self.limit - 1]) {
+            self.limit + slice[self.limit..].bl_partition_point(pred)
+        } else {
+            gallop(&slice[..self.limit.min(slice.len())], pred)
+        }
+    }
+
+    /// Returns the partition point of `pred` in `slice`, galloping from its end if it is within
+    /// the limit and searching the rest from scratch otherwise.
+    fn backward<P>(&self, slice: &[T], mut pred: P) -> usize
+    where
+        P: FnMut(&T) -> bool,
+    {
+        let near = slice.len().saturating_sub(self.limit);
+        if near > 0 && !pred(&slice[near]) {
+            slice[..near].bl_partition_point(pred)
+        } else {
+            near + gallop_back(&slice[near..], pred)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::Cell, thread};
+
+    use super::*;
+    use crate::test_util::XorShift;
+
+    #[test]
+    fn test_matches_plain_search() {
+        let mut rng = XorShift::new(156);
+
+        for len in [0, 1, 2, 3, 10, 100, 1000] {
+            let mut slice: Vec<u32> = (0..len).map(|_| rng.below(len / 2 + 1) as u32).collect();
+            slice.sort_unstable();
+            let hinted = HintedSlice::new(&slice[..]);
+
+            for _ in 0..500 {
+                // Both nearby keys and keys anywhere.
+                let key = if rng.below(2) == 0 {
+                    let near = slice.get(hinted.hint()).copied().unwrap_or(0);
+                    near + rng.below(5) as u32 - 2.min(near)
+                } else {
+                    rng.below(len / 2 + 2) as u32
+                };
+                let expected = slice.bl_binary_search(&key);
+                assert_eq!(hinted.search(&key), expected, "len {len}, key {key}");
+                assert_eq!(hinted.hint(), expected.unwrap_or_else(|i| i));
+            }
+        }
+    }
+
+    #[test]
+    fn test_stale_hints() {
+        let slice: Vec<u32> = (0..1000).map(|i| i / 4).collect();
+        let hinted = HintedSlice::new(&slice[..]);
+
+        for hint in [0, 1, 500, 999, 1000, 5000, usize::MAX] {
+            for key in [0, 1, 124, 125, 249, 250, 400] {
+                hinted.hint.store(hint, AtomicOrdering::Relaxed);
+                assert_eq!(hinted.search(&key), slice.bl_binary_search(&key));
+            }
+        }
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let slice: Vec<u32> = (0..1 << 20).collect();
+        let hinted = HintedSlice::new(&slice[..]);
+        let comparisons = Cell::new(0);
+        let count = |key: u32| {
+            comparisons.set(0);
+            let result = hinted.search_by(|x| {
+                comparisons.set(comparisons.get() + 1);
+                x.cmp(&key)
+            });
+            assert_eq!(result, Ok(key as usize));
+            comparisons.get()
+        };
+
+        // Near the hint, galloping is cheap.
+        count(500_000);
+        assert!(count(500_003) <= 8);
+        assert!(count(499_990) <= 14);
+
+        // Far from it, in either direction, a search costs at most a few comparisons more than
+        // one from scratch, which makes up to `log₂ n + 3`.
+        for key in [0, 1_000_000, 3, (1 << 20) - 1, 700_000, 200_000] {
+            assert!(count(key) <= 20 + 3 + 3, "key {key}");
+        }
+    }
+
+    #[test]
+    fn test_threads() {
+        let mut rng = XorShift::new(1156);
+        let mut slice: Vec<u64> = (0..100_000).map(|_| rng.below(50_000)).collect();
+        slice.sort_unstable();
+        let hinted = HintedSlice::new(&slice[..]);
+
+        thread::scope(|s| {
+            for thread in 0..8 {
+                let (hinted, slice) = (&hinted, &slice);
+                s.spawn(move || {
+                    let mut rng = XorShift::new(thread);
+                    // Each thread drifts through its own region, so the shared hint is
+                    // constantly pulled between them.
+                    let mut center = thread * 6000;
+                    for _ in 0..20_000 {
+                        center = (center + rng.below(20)).saturating_sub(9);
+                        let key = if rng.below(10) == 0 {
+                            rng.below(50_001)
+                        } else {
+                            center + rng.below(50)
+                        };
+                        assert_eq!(hinted.search(&key), slice.bl_binary_search(&key));
+                    }
+                });
+            }
+        });
+    }
+}
@@ -0,0 +1,225 @@
+//! Searches returning their results as a narrower integer than `usize`.
+//!
+//! A `Result<usize, usize>` takes 16 bytes on 64-bit targets, and a `Result<u32, u32>` 8, so
+//! storing results of searches over slices that are known to be short can take half the memory
+//! or less. This is only worth it when many results are kept around, such as in a precomputed
+//! lookup table or a column of join results. For results that are used right away, it makes no
+//! difference, as the search itself runs in `usize` either way and only the result is converted.
+//!
+//! ```
+//! use shar_search::index::SharIndexSearch;
+//!
+//! let stops = [0, 120, 380, 410, 900];
+//! let legs: Vec<Result<u16, u16>> = [50, 380, 1000]
+//!     .iter()
+//!     .map(|x| stops.bl_binary_search_idx(x))
+//!     .collect();
+//!
+//! assert_eq!(legs, [Err(1), Ok(2), Err(5)]);
+//! assert_eq!(std::mem::size_of_val(&legs[0]), 4);
+//! ```
+
+use core::{cmp::Ordering, fmt::Debug, hash::Hash};
+
+use crate::SharBinarySearch;
+
+mod sealed {
+    pub trait Sealed {
+        /// Converts an index known to be at most `MAX`.
+        fn from_usize(index: usize) -> Self;
+    }
+}
+
+/// An unsigned integer type that search results can be returned as. Implemented for `u16`,
+/// `u32` and `usize`.
+pub trait SearchIndex: sealed::Sealed + Copy + Ord + Hash + Debug {
+    /// The largest index this type can represent, as a `usize`. Slices searched with this index
+    /// type must be at most this long, as the insertion point can be the slice's length.
+    const MAX: usize;
+
+    /// Converts this index back to a `usize`, for indexing into the slice.
+    fn to_usize(self) -> usize;
+}
+
+macro_rules! impl_search_index {
+    ($($ty:ty),*) => {$(
+        impl sealed::Sealed for $ty {
+            #[inline]
+            fn from_usize(index: usize) -> Self {
+                index as $ty
+            }
+        }
+
+        impl SearchIndex for $ty {
+            const MAX: usize = if (<$ty>::MAX as u128) < usize::MAX as u128 {
+                <$ty>::MAX as usize
+            } else {
+                usize::MAX
+            };
+
+            #[inline]
+            fn to_usize(self) -> usize {
+                self as usize
+            }
+        }
+    )*};
+}
+
+impl_search_index!(u16, u32, usize);
+
+/// Converts a search result over a slice of length `len` to index type `I`.
+///
+/// # Panics
+///
+/// Panics if `len` is greater than `I::MAX`.
+#[inline]
+fn convert<I: SearchIndex>(len: usize, result: Result<usize, usize>) -> Result<I, I> {
+    assert!(
+        len <= I::MAX,
+        "slice of length {len} is too long for indices of at most {}",
+        I::MAX
+    );
+
+    match result {
+        Ok(index) => Ok(I::from_usize(index)),
+        Err(index) => Err(I::from_usize(index)),
+    }
+}
+
+/// Trait for binary searches returning their results as a [`SearchIndex`] type.
+///
+/// Each method behaves exactly like its counterpart on [`SharBinarySearch`] without the `_idx`
+/// suffix, except for the type of the result.
+///
+/// # Panics
+///
+/// Every method panics if the slice is longer than `I::MAX`, as the result might not fit. This
+/// is checked once per search, before searching.
+pub trait SharIndexSearch<T> {
+    /// Binary searches this slice for a given element, returning the index of the first match
+    /// or the insertion point as an `I`. See
+    /// [`bl_binary_search`](SharBinarySearch::bl_binary_search).
+    fn bl_binary_search_idx<I: SearchIndex>(&self, x: &T) -> Result<I, I>
+    where
+        T: Ord;
+
+    /// Binary searches this slice with a comparator function, returning the result as an `I`.
+    /// See [`bl_binary_search_by`](SharBinarySearch::bl_binary_search_by).
+    fn bl_binary_search_by_idx<'a, I, F>(&'a self, f: F) -> Result<I, I>
+    where
+        T: 'a,
+        I: SearchIndex,
+        F: FnMut(&'a T) -> Ordering;
+
+    /// Binary searches this slice with a key extraction function, returning the result as an
+    /// `I`. See [`bl_binary_search_by_key`](SharBinarySearch::bl_binary_search_by_key).
+    fn bl_binary_search_by_key_idx<'a, I, B, F>(&'a self, b: &B, f: F) -> Result<I, I>
+    where
+        T: 'a,
+        I: SearchIndex,
+        F: FnMut(&'a T) -> B,
+        B: Ord;
+}
+
+impl<T> SharIndexSearch<T> for [T] {
+    #[inline]
+    fn bl_binary_search_idx<I: SearchIndex>(&self, x: &T) -> Result<I, I>
+    where
+        T: Ord,
+    {
+        convert(self.len(), self.bl_binary_search(x))
+    }
+
+    #[inline]
+    fn bl_binary_search_by_idx<'a, I, F>(&'a self, f: F) -> Result<I, I>
+    where
+        T: 'a,
+        I: SearchIndex,
+        F: FnMut(&'a T) -> Ordering,
+    {
+        convert(self.len(), self.bl_binary_search_by(f))
+    }
+
+    #[inline]
+    fn bl_binary_search_by_key_idx<'a, I, B, F>(&'a self, b: &B, f: F) -> Result<I, I>
+    where
+        T: 'a,
+        I: SearchIndex,
+        F: FnMut(&'a T) -> B,
+        B: Ord,
+    {
+        convert(self.len(), self.bl_binary_search_by_key(b, f))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::panic;
+
+    use super::*;
+    use crate::test_util::XorShift;
+
+    #[test]
+    fn test_empty() {
+        let empty: [u8; 0] = [];
+        assert_eq!(empty.bl_binary_search_idx::<u16>(&1), Err(0));
+        assert_eq!(
+            empty.bl_binary_search_by_idx::<u32, _>(|p| p.cmp(&1)),
+            Err(0)
+        );
+        assert_eq!(
+            empty.bl_binary_search_by_key_idx::<usize, _, _>(&1, |&p| p),
+            Err(0)
+        );
+    }
+
+    #[test]
+    fn test_u16_boundary() {
+        let longest: Vec<u32> = (0..65_535).collect();
+        assert_eq!(longest.bl_binary_search_idx::<u16>(&0), Ok(0));
+        assert_eq!(longest.bl_binary_search_idx::<u16>(&65_534), Ok(65_534));
+        assert_eq!(longest.bl_binary_search_idx::<u16>(&70_000), Err(65_535));
+
+        let too_long: Vec<u32> = (0..65_536).collect();
+        for key in [0, 65_535, 70_000] {
+            let result = panic::catch_unwind(|| too_long.bl_binary_search_idx::<u16>(&key));
+            assert!(result.is_err());
+        }
+        assert_eq!(too_long.bl_binary_search_idx::<u32>(&70_000), Err(65_536));
+    }
+
+    fn widen<I: SearchIndex>(result: Result<I, I>) -> Result<usize, usize> {
+        result.map(I::to_usize).map_err(I::to_usize)
+    }
+
+    #[test]
+    fn test_against_usize_results() {
+        let mut rng = XorShift::new(144);
+
+        for _ in 0..100 {
+            let mut slice: Vec<(u32, char)> = (0..rng.below(50))
+                .map(|_| (rng.below(60) as u32, 'x'))
+                .collect();
+            slice.sort_unstable();
+
+            for x in 0..62 {
+                let expected = slice.bl_binary_search_by_key(&x, |p| p.0);
+                let narrow = slice.bl_binary_search_by_key_idx::<u16, _, _>(&x, |p| p.0);
+                let by = slice.bl_binary_search_by_idx::<u32, _>(|p| p.0.cmp(&x));
+
+                assert_eq!(widen(narrow), expected);
+                assert_eq!(widen(by), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_max() {
+        assert_eq!(<u16 as SearchIndex>::MAX, 65_535);
+        assert_eq!(
+            <u32 as SearchIndex>::MAX as u64,
+            u64::from(u32::MAX).min(usize::MAX as u64)
+        );
+        assert_eq!(<usize as SearchIndex>::MAX, usize::MAX);
+    }
+}
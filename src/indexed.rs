@@ -0,0 +1,390 @@
+//! Searches through an argsort permutation, for data that cannot be reordered.
+//!
+//! Given `data` and a permutation `order` of its indices such that `data[order[0]]`,
+//! `data[order[1]]`, ... is sorted by some key, these search the data in that order without
+//! moving it. This suits parallel arrays that must stay in their original order, or several
+//! orders over the same data. Results are positions *within `order`*; map one back to the data
+//! with [`data_index`].
+//!
+//! Each probe loads `order[i]` and then `data[order[i]]`, two dependent loads where a search of
+//! sorted data has one. To hide some of that latency, while comparing one element the search
+//! prefetches the element behind each of the two positions it may probe next (on x86-64; on
+//! other targets, it only loads their indices early).
+//!
+//! ```
+//! use shar_search::indexed::{data_index, equal_range_via, search_via};
+//!
+//! let names = ["carol", "alice", "dave", "bob", "alice"];
+//! let ages = [35, 30, 41, 25, 52];
+//! // The indices of `names` in name order.
+//! let order = [1, 4, 3, 0, 2];
+//!
+//! let position = search_via(&names, &order, &"bob", |name| *name).unwrap();
+//! assert_eq!(ages[data_index(&order, position).unwrap()], 25);
+//!
+//! let alices = equal_range_via(&names, &order, &"alice", |name| *name);
+//! assert_eq!(order[alices].iter().map(|&i| ages[i as usize]).sum::<u32>(), 82);
+//! ```
+
+#[cfg(feature = "alloc")]
+use alloc::vec;
+use core::ops::Range;
+#[cfg(feature = "alloc")]
+use core::{error::Error, fmt};
+
+/// Hints that the element of `data` behind `order[position]` will be read soon, if both exist.
+#[inline(always)]
+fn prefetch<T>(data: &[T], order: &[u32], position: usize) {
+    if let Some(element) = order.get(position).and_then(|&i| data.get(i as usize)) {
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: a prefetch is only a hint, so it never faults, and the pointer is valid anyway.
+        unsafe {
+            use core::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+            _mm_prefetch::<_MM_HINT_T0>((element as *const T).cast());
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        let _ = element;
+    }
+}
+
+/// Returns the first position of `order` whose element does not satisfy `pred`, with
+/// branchless halving. Note it is assumed that the data is partitioned by `pred` in that order.
+fn partition_point_via<T, P>(data: &[T], order: &[u32], mut pred: P) -> usize
+where
+    P: FnMut(&T) -> bool,
+{
+    if order.is_empty() {
+        return 0;
+    }
+    let mut at = |position: usize| pred(&data[order[position] as usize]);
+
+    let mut base = 0;
+    let mut size = order.len();
+    while size > 1 {
+        let half = size / 2;
+        // The next probe is half of the remaining size past either the base or this probe.
+        let next_half = (size - half) / 2;
+        prefetch(data, order, base + next_half);
+        prefetch(data, order, base + half + next_half);
+
+        if at(base + half) {
+            base += half;
+        }
+        size -= half;
+    }
+
+    base + usize::from(at(base))
+}
+
+/// Returns the first position in `order` whose element's key is not less than `key`. Note it
+/// is assumed that `order` sorts `data` by `extract`.
+///
+/// # Panics
+///
+/// Panics if a probed index of `order` is out of bounds for `data`.
+pub fn lower_bound_via<T, K, F>(data: &[T], order: &[u32], key: &K, extract: F) -> usize
+where
+    F: Fn(&T) -> K,
+    K: Ord,
+{
+    partition_point_via(data, order, |x| extract(x) < *key)
+}
+
+/// Returns the first position in `order` whose element's key is greater than `key`. Note it is
+/// assumed that `order` sorts `data` by `extract`.
+///
+/// # Panics
+///
+/// Panics if a probed index of `order` is out of bounds for `data`.
+pub fn upper_bound_via<T, K, F>(data: &[T], order: &[u32], key: &K, extract: F) -> usize
+where
+    F: Fn(&T) -> K,
+    K: Ord,
+{
+    partition_point_via(data, order, |x| extract(x) <= *key)
+}
+
+/// Binary searches `data` in the order given by `order` for an element whose key is `key`,
+/// returning its position in `order`. Note it is assumed that `order` sorts `data` by
+/// `extract`.
+///
+/// As with [`bl_binary_search`](crate::SharBinarySearch::bl_binary_search), if there are
+/// multiple matches, the *first* is returned, and otherwise the position where an element with
+/// that key would be inserted into the order.
+///
+/// # Panics
+///
+/// Panics if a probed index of `order` is out of bounds for `data`.
+pub fn search_via<T, K, F>(data: &[T], order: &[u32], key: &K, extract: F) -> Result<usize, usize>
+where
+    F: Fn(&T) -> K,
+    K: Ord,
+{
+    let position = lower_bound_via(data, order, key, &extract);
+    match order.get(position) {
+        Some(&index) if extract(&data[index as usize]) == *key => Ok(position),
+        _ => Err(position),
+    }
+}
+
+/// Returns the positions in `order` whose elements' keys equal `key`. Note it is assumed that
+/// `order` sorts `data` by `extract`.
+///
+/// # Panics
+///
+/// Panics if a probed index of `order` is out of bounds for `data`.
+pub fn equal_range_via<T, K, F>(data: &[T], order: &[u32], key: &K, extract: F) -> Range<usize>
+where
+    F: Fn(&T) -> K,
+    K: Ord,
+{
+    let start = lower_bound_via(data, order, key, &extract);
+    let end = start + upper_bound_via(data, &order[start..], key, &extract);
+    start..end
+}
+
+/// Returns the index into the data of the element at `position` in `order`, or `None` if
+/// `position` is out of bounds, as for the insertion point past the last element.
+pub fn data_index(order: &[u32], position: usize) -> Option<usize> {
+    order.get(position).map(|&index| index as usize)
+}
+
+/// The error returned by [`Argsorted::try_new`] when `order` is not a permutation that sorts
+/// the data.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrderError {
+    /// `order` and the data have different lengths.
+    Length {
+        /// The length of `order`.
+        order: usize,
+        /// The length of the data.
+        data: usize,
+    },
+    /// The index at this position of `order` is out of bounds for the data.
+    OutOfBounds(usize),
+    /// The index at this position of `order` already appeared at an earlier position.
+    Repeated(usize),
+    /// The element behind this position of `order` has a smaller key than the one before it.
+    Unsorted(usize),
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for OrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Length { order, data } => {
+                write!(f, "order has {order} indices but the data {data} elements")
+            }
+            Self::OutOfBounds(position) => {
+                write!(f, "index at position {position} is out of bounds")
+            }
+            Self::Repeated(position) => write!(f, "index at position {position} is repeated"),
+            Self::Unsorted(position) => write!(
+                f,
+                "element at position {position} is less than its predecessor"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Error for OrderError {}
+
+/// Data with a validated permutation that sorts it by a key, for repeated searches through the
+/// permutation. See the [module documentation](self).
+///
+/// ```
+/// use shar_search::indexed::{Argsorted, OrderError};
+///
+/// let prices = [30, 10, 20, 10];
+/// let by_price = Argsorted::try_new(&prices, &[1, 3, 2, 0], |p| *p).unwrap();
+/// assert_eq!(by_price.equal_range(&10), 0..2);
+/// assert_eq!(by_price.get(2), Some(&20));
+///
+/// let err = Argsorted::try_new(&prices, &[1, 3, 0, 2], |p| *p).unwrap_err();
+/// assert_eq!(err, OrderError::Unsorted(3));
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Clone, Copy)]
+pub struct Argsorted<'a, T, F> {
+    data: &'a [T],
+    order: &'a [u32],
+    extract: F,
+}
+
+#[cfg(feature = "alloc")]
+impl<T: fmt::Debug, F> fmt::Debug for Argsorted<'_, T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Argsorted")
+            .field("data", &self.data)
+            .field("order", &self.order)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T, K, F> Argsorted<'a, T, F>
+where
+    F: Fn(&T) -> K,
+    K: Ord,
+{
+    /// Checks that `order` is a permutation of `0..data.len()` that sorts `data` by `extract`,
+    /// in one pass over `order`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OrderError`] for the first position of `order` that breaks either property.
+    pub fn try_new(data: &'a [T], order: &'a [u32], extract: F) -> Result<Self, OrderError> {
+        if order.len() != data.len() {
+            return Err(OrderError::Length {
+                order: order.len(),
+                data: data.len(),
+            });
+        }
+
+        let mut seen = vec![false; data.len()];
+        let mut prev: Option<K> = None;
+        for (position, &index) in order.iter().enumerate() {
+            let seen = seen
+                .get_mut(index as usize)
+                .ok_or(OrderError::OutOfBounds(position))?;
+            if core::mem::replace(seen, true) {
+                return Err(OrderError::Repeated(position));
+            }
+
+            let key = extract(&data[index as usize]);
+            if prev.as_ref().is_some_and(|prev| key < *prev) {
+                return Err(OrderError::Unsorted(position));
+            }
+            prev = Some(key);
+        }
+
+        Ok(Self {
+            data,
+            order,
+            extract,
+        })
+    }
+
+    /// Returns the data, in its original order.
+    pub fn data(&self) -> &'a [T] {
+        self.data
+    }
+
+    /// Returns the permutation.
+    pub fn order(&self) -> &'a [u32] {
+        self.order
+    }
+
+    /// Returns the element at `position` in sorted order, or `None` if it is out of bounds.
+    pub fn get(&self, position: usize) -> Option<&'a T> {
+        data_index(self.order, position).map(|index| &self.data[index])
+    }
+
+    /// Returns an iterator over the elements in sorted order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &'a T> + ExactSizeIterator + '_ {
+        self.order.iter().map(|&index| &self.data[index as usize])
+    }
+
+    /// Binary searches for an element whose key is `key`. See [`search_via`].
+    pub fn search(&self, key: &K) -> Result<usize, usize> {
+        search_via(self.data, self.order, key, &self.extract)
+    }
+
+    /// Returns the first position whose element's key is not less than `key`.
+    pub fn lower_bound(&self, key: &K) -> usize {
+        lower_bound_via(self.data, self.order, key, &self.extract)
+    }
+
+    /// Returns the first position whose element's key is greater than `key`.
+    pub fn upper_bound(&self, key: &K) -> usize {
+        upper_bound_via(self.data, self.order, key, &self.extract)
+    }
+
+    /// Returns the positions whose elements' keys equal `key`.
+    pub fn equal_range(&self, key: &K) -> Range<usize> {
+        equal_range_via(self.data, self.order, key, &self.extract)
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod test {
+    use super::*;
+    use crate::{test_util::XorShift, SharBinarySearch};
+
+    /// Random data with many duplicate keys, and the stable argsort of it.
+    fn argsorted(rng: &mut XorShift, len: usize) -> (Vec<(u32, u32)>, Vec<u32>) {
+        let data: Vec<(u32, u32)> = (0..len as u32)
+            .map(|i| (rng.below(len as u64 / 4 + 1) as u32, i))
+            .collect();
+        let mut order: Vec<u32> = (0..len as u32).collect();
+        order.sort_by_key(|&i| data[i as usize].0);
+        (data, order)
+    }
+
+    #[test]
+    fn test_against_sorted_copy() {
+        let mut rng = XorShift::new(160);
+
+        for len in [0, 1, 2, 3, 10, 100, 1000] {
+            let (data, order) = argsorted(&mut rng, len);
+            let keys: Vec<u32> = order.iter().map(|&i| data[i as usize].0).collect();
+            let view = Argsorted::try_new(&data, &order, |x| x.0).unwrap();
+
+            for key in 0..len as u32 / 4 + 3 {
+                let expected = keys.bl_binary_search(&key);
+                assert_eq!(search_via(&data, &order, &key, |x| x.0), expected);
+                assert_eq!(view.search(&key), expected);
+                assert_eq!(view.lower_bound(&key), keys.bl_lower_bound(&key));
+                assert_eq!(view.upper_bound(&key), keys.bl_upper_bound(&key));
+
+                // Duplicates are all in the range, and only they are.
+                let range = view.equal_range(&key);
+                assert_eq!(range.len(), data.iter().filter(|x| x.0 == key).count());
+                assert!(view
+                    .iter()
+                    .skip(range.start)
+                    .take(range.len())
+                    .all(|x| x.0 == key));
+            }
+        }
+    }
+
+    #[test]
+    fn test_data_index() {
+        let data = [5, 3, 9];
+        let order = [1, 0, 2];
+        let position = search_via(&data, &order, &5, |x| *x).unwrap();
+        assert_eq!(position, 1);
+        assert_eq!(data_index(&order, position), Some(0));
+        assert_eq!(data_index(&order, 3), None);
+        assert_eq!(search_via(&data, &order, &10, |x| *x), Err(3));
+    }
+
+    #[test]
+    fn test_try_new_errors() {
+        let data = [10, 20, 30];
+        let try_new = |order: &'static [u32]| Argsorted::try_new(&data, order, |x| *x).err();
+
+        assert_eq!(try_new(&[0, 1, 2]), None);
+        assert_eq!(try_new(&[0, 1, 3]), Some(OrderError::OutOfBounds(2)));
+        assert_eq!(try_new(&[0, u32::MAX, 1]), Some(OrderError::OutOfBounds(1)));
+        assert_eq!(try_new(&[0, 1, 1]), Some(OrderError::Repeated(2)));
+        assert_eq!(try_new(&[0, 2, 1]), Some(OrderError::Unsorted(2)));
+        assert_eq!(
+            try_new(&[0, 1]),
+            Some(OrderError::Length { order: 2, data: 3 })
+        );
+        assert_eq!(
+            OrderError::OutOfBounds(2).to_string(),
+            "index at position 2 is out of bounds"
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_out_of_bounds_unchecked() {
+        search_via(&[1, 2], &[0, 7], &2, |x| *x).ok();
+    }
+}
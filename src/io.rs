@@ -0,0 +1,470 @@
+//! Searches of sorted fixed-size records in a file, read with [`Read`] and [`Seek`] rather than
+//! mapped into memory.
+//!
+//! A [`FileSearcher`] treats its reader as a sequence of records of `record_size` bytes each,
+//! sorted by a key that a [`KeyCodec`] decodes from a range of each record's bytes. Each probe
+//! of a search seeks to a record and reads just its key into a buffer that is reused from
+//! probe to probe, so a search makes `O(log n)` small reads and no allocations. Indices are
+//! `u64`, so files larger than the address space can be searched on 32-bit targets too.
+//!
+//! ```
+//! use std::io::Cursor;
+//!
+//! use shar_search::io::{FileSearcher, KeyCodec};
+//!
+//! // Records of a big-endian `u32` key followed by a one-byte tag.
+//! let bytes = [0, 0, 0, 3, b'a', 0, 0, 0, 5, b'b', 0, 0, 1, 0, b'c'];
+//! let mut searcher = FileSearcher::new(Cursor::new(bytes), 5, KeyCodec::u32_be(0))?;
+//!
+//! assert_eq!(searcher.len(), 3);
+//! assert_eq!(searcher.search(&5)?, Ok(1));
+//! assert_eq!(searcher.search(&4)?, Err(1));
+//! assert_eq!(searcher.read_record(2)?, [0, 0, 1, 0, b'c']);
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::{
+    cmp::Ordering,
+    fmt,
+    io::{self, ErrorKind, Read, Seek, SeekFrom},
+    vec,
+    vec::Vec,
+};
+
+/// How to decode the key of a record from `width` bytes at `offset` in it.
+pub struct KeyCodec<K> {
+    offset: usize,
+    width: usize,
+    decode: fn(&[u8]) -> K,
+}
+
+// Not derived, which would require `K: Copy`.
+impl<K> Clone for KeyCodec<K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K> Copy for KeyCodec<K> {}
+
+impl<K> fmt::Debug for KeyCodec<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyCodec")
+            .field("offset", &self.offset)
+            .field("width", &self.width)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<K> KeyCodec<K> {
+    /// Decodes the key by calling `decode` with the `width` bytes at `offset` in each record.
+    /// To decode the key from the whole record, pass an offset of 0 and the record size.
+    pub const fn custom(offset: usize, width: usize, decode: fn(&[u8]) -> K) -> Self {
+        Self {
+            offset,
+            width,
+            decode,
+        }
+    }
+
+    /// Returns the offset of the key in each record.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the width of the key, in bytes.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the function decoding the key from its bytes.
+    pub(crate) fn decoder(&self) -> fn(&[u8]) -> K {
+        self.decode
+    }
+}
+
+impl KeyCodec<u32> {
+    /// A little-endian `u32` at `offset`.
+    pub const fn u32_le(offset: usize) -> Self {
+        Self::custom(offset, 4, |bytes| {
+            u32::from_le_bytes(bytes.try_into().unwrap())
+        })
+    }
+
+    /// A big-endian `u32` at `offset`.
+    pub const fn u32_be(offset: usize) -> Self {
+        Self::custom(offset, 4, |bytes| {
+            u32::from_be_bytes(bytes.try_into().unwrap())
+        })
+    }
+}
+
+impl KeyCodec<u64> {
+    /// A little-endian `u64` at `offset`.
+    pub const fn u64_le(offset: usize) -> Self {
+        Self::custom(offset, 8, |bytes| {
+            u64::from_le_bytes(bytes.try_into().unwrap())
+        })
+    }
+
+    /// A big-endian `u64` at `offset`.
+    pub const fn u64_be(offset: usize) -> Self {
+        Self::custom(offset, 8, |bytes| {
+            u64::from_be_bytes(bytes.try_into().unwrap())
+        })
+    }
+}
+
+/// Returns an error unless records of `record_size` bytes are nonempty and fit the key.
+pub(crate) fn check_layout<K>(record_size: usize, codec: &KeyCodec<K>) -> io::Result<()> {
+    if record_size == 0 {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "records must be at least one byte",
+        ));
+    }
+    if codec.width == 0 || codec.offset.saturating_add(codec.width) > record_size {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "key of {} bytes at offset {} doesn't fit in records of {record_size} bytes",
+                codec.width, codec.offset
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the number of records of `record_size` bytes in `bytes`, or an error if the last
+/// record is cut short.
+pub(crate) fn record_count(bytes: u64, record_size: usize) -> io::Result<u64> {
+    let record_bytes = record_size as u64;
+    if !bytes.is_multiple_of(record_bytes) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "the last record has {} of its {record_size} bytes",
+                bytes % record_bytes
+            ),
+        ));
+    }
+    Ok(bytes / record_bytes)
+}
+
+/// Returns an error unless `index` is less than `len`.
+pub(crate) fn check_index(index: u64, len: u64) -> io::Result<()> {
+    if index < len {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("record {index} is out of bounds of {len} records"),
+        ))
+    }
+}
+
+/// Searches a reader of fixed-size records sorted by key. See the
+/// [module documentation](self).
+#[derive(Debug)]
+pub struct FileSearcher<R, K> {
+    reader: R,
+    record_size: usize,
+    len: u64,
+    codec: KeyCodec<K>,
+    buffer: Vec<u8>,
+}
+
+impl<R: Read + Seek, K> FileSearcher<R, K> {
+    /// Creates a searcher over the records of `record_size` bytes in `reader`, from its start
+    /// to its end, with keys decoded by `codec`. Note it is assumed that the records are
+    /// sorted by key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`InvalidInput`](ErrorKind::InvalidInput) if `record_size` is
+    /// zero or the key doesn't fit in a record, of kind [`InvalidData`](ErrorKind::InvalidData)
+    /// if the length of the reader isn't a multiple of `record_size`, which means the last
+    /// record is cut short, and any error from seeking to the end of the reader.
+    pub fn new(mut reader: R, record_size: usize, codec: KeyCodec<K>) -> io::Result<Self> {
+        check_layout(record_size, &codec)?;
+        let len = record_count(reader.seek(SeekFrom::End(0))?, record_size)?;
+
+        Ok(Self {
+            reader,
+            record_size,
+            len,
+            codec,
+            buffer: vec![0; record_size],
+        })
+    }
+
+    /// Returns the number of records.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns whether there are no records.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the size of each record, in bytes.
+    pub fn record_size(&self) -> usize {
+        self.record_size
+    }
+
+    /// Returns the reader, positioned wherever the last read left it.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads `len` bytes from `offset` in record `index` into the start of the buffer.
+    fn read_at(&mut self, index: u64, offset: usize, len: usize) -> io::Result<&[u8]> {
+        let position = index * self.record_size as u64 + offset as u64;
+        self.reader.seek(SeekFrom::Start(position))?;
+        let bytes = &mut self.buffer[..len];
+        self.reader.read_exact(bytes)?;
+        Ok(bytes)
+    }
+
+    /// Reads record `index`, into a buffer that the next read reuses.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`InvalidInput`](ErrorKind::InvalidInput) if `index` is out of
+    /// bounds, and any error from seeking or reading, such as
+    /// [`UnexpectedEof`](ErrorKind::UnexpectedEof) if the reader has been truncated.
+    pub fn read_record(&mut self, index: u64) -> io::Result<&[u8]> {
+        check_index(index, self.len)?;
+        self.read_at(index, 0, self.record_size)
+    }
+
+    /// Reads the key of record `index`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`read_record`](Self::read_record).
+    pub fn read_key(&mut self, index: u64) -> io::Result<K> {
+        check_index(index, self.len)?;
+        self.key_at(index)
+    }
+
+    fn key_at(&mut self, index: u64) -> io::Result<K> {
+        let KeyCodec {
+            offset,
+            width,
+            decode,
+        } = self.codec;
+        self.read_at(index, offset, width).map(decode)
+    }
+
+    /// Binary searches the records for `key`, returning the index of the match or where it
+    /// would be inserted, as with [`bl_binary_search`](crate::SharBinarySearch::bl_binary_search).
+    /// If there are multiple matches, the first is returned.
+    ///
+    /// This reads `⌈log₂(n + 1)⌉` keys at most, with one seek and read each.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from seeking or reading, in which case the search is abandoned and the
+    /// searcher can still be used.
+    pub fn search(&mut self, key: &K) -> io::Result<Result<u64, u64>>
+    where
+        K: Ord,
+    {
+        // The first index whose key isn't less than `key` is in `low..=high`, and `found` is
+        // whether the key at `high` equals it.
+        let (mut low, mut high, mut found) = (0, self.len, false);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match self.key_at(mid)?.cmp(key) {
+                Ordering::Less => low = mid + 1,
+                ordering => (high, found) = (mid, ordering.is_eq()),
+            }
+        }
+
+        Ok(if found { Ok(low) } else { Err(low) })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        fs::{self, File},
+        io::{self, Cursor, ErrorKind, Read, Seek, SeekFrom},
+        vec::Vec,
+    };
+
+    use super::{FileSearcher, KeyCodec};
+    use crate::{
+        reference::{self, assert_matches_reference, inputs},
+        test_util::TempFile,
+        SharBinarySearch,
+    };
+
+    /// Returns records of a little-endian `u32` key at offset 2, padded to 7 bytes with each
+    /// record's index.
+    fn records(keys: &[u32]) -> Vec<u8> {
+        keys.iter()
+            .enumerate()
+            .flat_map(|(i, key)| {
+                let mut record = [i as u8; 7];
+                record[2..6].copy_from_slice(&key.to_le_bytes());
+                record
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_against_reference() {
+        assert_matches_reference!(
+            for (keys, x) in inputs::slices_with_keys(179) =>
+            FileSearcher::new(Cursor::new(records(&keys)), 7, KeyCodec::u32_le(2))
+                .unwrap()
+                .search(&x)
+                .unwrap()
+                .map(|i| i as usize)
+                .map_err(|i| i as usize),
+            reference::binary_search(&keys, &x),
+        );
+    }
+
+    #[test]
+    fn test_file_against_slice_search() {
+        let keys: Vec<u32> = (0..5000).map(|i| i / 3 * 7).collect();
+        let file = TempFile::new("file_against_slice", &records(&keys));
+        let mut searcher =
+            FileSearcher::new(File::open(file.path()).unwrap(), 7, KeyCodec::u32_le(2)).unwrap();
+        assert_eq!(searcher.len(), 5000);
+
+        // Load the whole file and search the keys in memory instead.
+        let loaded: Vec<u32> = fs::read(file.path())
+            .unwrap()
+            .chunks_exact(7)
+            .map(|record| u32::from_le_bytes(record[2..6].try_into().unwrap()))
+            .collect();
+        for x in 0..keys.last().unwrap() + 2 {
+            let expected = loaded.bl_binary_search(&x);
+            let expected = expected.map(|i| i as u64).map_err(|i| i as u64);
+            assert_eq!(searcher.search(&x).unwrap(), expected);
+        }
+
+        assert_eq!(searcher.read_record(4).unwrap(), &records(&keys)[28..35]);
+        assert_eq!(searcher.read_key(4999).unwrap(), keys[4999]);
+    }
+
+    #[test]
+    fn test_codecs() {
+        let mut bytes = Vec::new();
+        for key in [1_u64, 256, 65_536, u64::MAX] {
+            bytes.extend_from_slice(&key.to_be_bytes());
+            bytes.extend_from_slice(&key.to_le_bytes());
+        }
+
+        let mut be = FileSearcher::new(Cursor::new(&bytes), 16, KeyCodec::u64_be(0)).unwrap();
+        assert_eq!(be.search(&65_536).unwrap(), Ok(2));
+        assert_eq!(be.search(&u64::MAX).unwrap(), Ok(3));
+        let mut le = FileSearcher::new(Cursor::new(&bytes), 16, KeyCodec::u64_le(8)).unwrap();
+        assert_eq!(le.search(&256).unwrap(), Ok(1));
+        assert_eq!(le.search(&257).unwrap(), Err(2));
+
+        // The low halves of the big-endian keys, then a whole record as the key.
+        let mut low = FileSearcher::new(Cursor::new(&bytes), 16, KeyCodec::u32_be(4)).unwrap();
+        assert_eq!(low.read_key(3).unwrap(), u32::MAX);
+        let codec = KeyCodec::custom(0, 16, |record: &[u8]| record.to_vec());
+        let mut whole = FileSearcher::new(Cursor::new(&bytes), 16, codec).unwrap();
+        assert_eq!(whole.search(&bytes[16..32].to_vec()).unwrap(), Ok(1));
+        assert_eq!(whole.search(&vec![0; 16]).unwrap(), Err(0));
+    }
+
+    #[test]
+    fn test_short_files() {
+        let mut empty = FileSearcher::new(Cursor::new([]), 4, KeyCodec::u32_le(0)).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.search(&0).unwrap(), Err(0));
+        let error = empty.read_record(0).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+
+        let mut one =
+            FileSearcher::new(Cursor::new(records(&[9])), 7, KeyCodec::u32_le(2)).unwrap();
+        assert_eq!(one.search(&9).unwrap(), Ok(0));
+        assert_eq!(one.search(&10).unwrap(), Err(1));
+
+        // Shorter than a single record, and a trailing partial record.
+        let error = FileSearcher::new(Cursor::new([0; 3]), 4, KeyCodec::u32_le(0)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        let file = TempFile::new("partial", &records(&[1, 2, 3])[..20]);
+        let error = FileSearcher::new(File::open(file.path()).unwrap(), 7, KeyCodec::u32_le(2))
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_invalid_layouts() {
+        for (record_size, codec) in [
+            (0, KeyCodec::u32_le(0)),
+            (4, KeyCodec::u32_le(1)),
+            (8, KeyCodec::custom(0, 0, |_| 0)),
+            (8, KeyCodec::custom(usize::MAX, 2, |_| 0)),
+        ] {
+            let error = FileSearcher::new(Cursor::new([0; 8]), record_size, codec).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        }
+    }
+
+    /// A reader whose read number `fail_on` fails, counting from zero.
+    struct Failing<R> {
+        inner: R,
+        reads: usize,
+        fail_on: usize,
+    }
+
+    impl<R: Read> Read for Failing<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.reads += 1;
+            if self.reads - 1 == self.fail_on {
+                return Err(io::Error::other("disk on fire"));
+            }
+            self.inner.read(buf)
+        }
+    }
+
+    impl<R: Seek> Seek for Failing<R> {
+        fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+            self.inner.seek(position)
+        }
+    }
+
+    #[test]
+    fn test_errors_mid_search() {
+        let keys: Vec<u32> = (0..1000).collect();
+        let reader = Failing {
+            inner: Cursor::new(records(&keys)),
+            reads: 0,
+            fail_on: 3,
+        };
+        let mut searcher = FileSearcher::new(reader, 7, KeyCodec::u32_le(2)).unwrap();
+        let error = searcher.search(&500).unwrap_err();
+        assert_eq!(error.to_string(), "disk on fire");
+        // The next search starts over.
+        assert_eq!(searcher.search(&500).unwrap(), Ok(500));
+
+        // A file truncated after the searcher measured it.
+        let file = TempFile::new("truncated", &records(&keys));
+        let mut searcher =
+            FileSearcher::new(File::open(file.path()).unwrap(), 7, KeyCodec::u32_le(2)).unwrap();
+        File::options()
+            .write(true)
+            .open(file.path())
+            .unwrap()
+            .set_len(70)
+            .unwrap();
+        assert_eq!(searcher.read_key(5).unwrap(), 5);
+        let error = searcher.search(&999).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(
+            searcher.read_record(10).unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+    }
+}
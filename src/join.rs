@@ -0,0 +1,593 @@
+//! Equality joins of two sorted slices.
+//!
+//! The joins walk both slices together like a merge, but once one side has been stepped
+//! through several times in a row, they skip over its non-matching elements by galloping. Joining
+//! a small slice with a large one then only compares `O(m log(n / m))` elements rather than
+//! `O(m + n)`, while evenly interleaved slices are joined at the speed of a plain linear join.
+//!
+//! When a key appears several times on both sides, every element of the run in `a` is paired
+//! with every element of the run in `b`. Pairs are produced in key order, then in order of
+//! their position in `a`, then of their position in `b`.
+//!
+//! The semi-join and anti-join instead filter `a`, keeping the elements whose key does or does
+//! not appear in `b`. Each element of `a` is kept or dropped on its own, and repeated keys in
+//! `b` do not cause an element to be kept twice.
+
+use core::{cmp::Ordering, iter::FusedIterator, ops::Range};
+
+use crate::gallop::gallop;
+
+/// The number of consecutive steps through one side before the join starts galloping.
+const MIN_GALLOP: usize = 7;
+
+/// Returns an iterator over the pairs of equal elements from the sorted slices `a` and `b`.
+///
+/// ```
+/// use shar_search::join::inner_join;
+///
+/// let a = [1, 2, 2, 4, 7];
+/// let b = [2, 2, 3, 4];
+/// let pairs: Vec<_> = inner_join(&a, &b).map(|(x, y)| (*x, *y)).collect();
+/// assert_eq!(pairs, [(2, 2), (2, 2), (2, 2), (2, 2), (4, 4)]);
+/// ```
+pub fn inner_join<'a, T: Ord>(
+    a: &'a [T],
+    b: &'a [T],
+) -> InnerJoin<'a, T, T, impl FnMut(&T, &T) -> Ordering> {
+    inner_join_by(a, b, T::cmp)
+}
+
+/// Returns an iterator over the pairs of matching elements from the sorted slices `a` and
+/// `b`, where `compare(x, y)` returns the ordering of an element of `a` relative to an
+/// element of `b`. See [`inner_join`].
+pub fn inner_join_by<'a, A, B, F>(a: &'a [A], b: &'a [B], compare: F) -> InnerJoin<'a, A, B, F>
+where
+    F: FnMut(&A, &B) -> Ordering,
+{
+    InnerJoin {
+        indices: join_indices_by(a, b, compare),
+    }
+}
+
+/// Returns an iterator over the pairs of elements from the sorted slices `a` and `b` whose
+/// keys, as returned by `f`, are equal. See [`inner_join`].
+pub fn inner_join_by_key<'a, T, K, F>(
+    a: &'a [T],
+    b: &'a [T],
+    mut f: F,
+) -> InnerJoin<'a, T, T, impl FnMut(&T, &T) -> Ordering>
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    inner_join_by(a, b, move |x, y| f(x).cmp(&f(y)))
+}
+
+/// Returns an iterator over the index pairs `(i, j)` such that `a[i] == b[j]`, for the sorted
+/// slices `a` and `b`. The pairs are in the same order as those of [`inner_join`].
+///
+/// ```
+/// use shar_search::join::join_indices;
+///
+/// let pairs: Vec<_> = join_indices(&[1, 3, 3, 5], &[3, 4, 5]).collect();
+/// assert_eq!(pairs, [(1, 0), (2, 0), (3, 2)]);
+/// ```
+pub fn join_indices<'a, T: Ord>(
+    a: &'a [T],
+    b: &'a [T],
+) -> JoinIndices<'a, T, T, impl FnMut(&T, &T) -> Ordering> {
+    join_indices_by(a, b, T::cmp)
+}
+
+/// Returns an iterator over the index pairs of matching elements from the sorted slices `a`
+/// and `b`, with a comparator function. See [`join_indices`] and [`inner_join_by`].
+pub fn join_indices_by<'a, A, B, F>(a: &'a [A], b: &'a [B], compare: F) -> JoinIndices<'a, A, B, F>
+where
+    F: FnMut(&A, &B) -> Ordering,
+{
+    JoinIndices {
+        a,
+        b,
+        next_a: 0,
+        next_b: 0,
+        a_run: 0..0,
+        b_run: 0..0,
+        b_index: 0,
+        compare,
+    }
+}
+
+/// Returns an iterator over the index pairs of elements from the sorted slices `a` and `b`
+/// whose keys, as returned by `f`, are equal. See [`join_indices`].
+pub fn join_indices_by_key<'a, T, K, F>(
+    a: &'a [T],
+    b: &'a [T],
+    mut f: F,
+) -> JoinIndices<'a, T, T, impl FnMut(&T, &T) -> Ordering>
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    join_indices_by(a, b, move |x, y| f(x).cmp(&f(y)))
+}
+
+/// An iterator over the index pairs of matching elements from two sorted slices.
+///
+/// Created by [`join_indices`], [`join_indices_by`], and [`join_indices_by_key`].
+pub struct JoinIndices<'a, A, B, F> {
+    a: &'a [A],
+    b: &'a [B],
+    /// Where to continue looking for the next matching runs.
+    next_a: usize,
+    next_b: usize,
+    /// The matching runs currently being paired up. `a_run` is empty once they are exhausted.
+    a_run: Range<usize>,
+    b_run: Range<usize>,
+    b_index: usize,
+    compare: F,
+}
+
+impl<A, B, F> JoinIndices<'_, A, B, F>
+where
+    F: FnMut(&A, &B) -> Ordering,
+{
+    /// Finds the next pair of matching runs, returning whether there is one.
+    fn find_runs(&mut self) -> bool {
+        let (a, b, compare) = (self.a, self.b, &mut self.compare);
+        let (mut i, mut j) = (self.next_a, self.next_b);
+
+        // Like a merge, step one element at a time until one side keeps falling behind, and
+        // only then gallop through it.
+        let (mut a_streak, mut b_streak) = (0, 0);
+
+        while i < a.len() && j < b.len() {
+            match compare(&a[i], &b[j]) {
+                Ordering::Less => {
+                    b_streak = 0;
+                    if a_streak < MIN_GALLOP {
+                        i += 1;
+                        a_streak += 1;
+                    } else {
+                        let run = 1 + gallop(&a[i + 1..], |x| compare(x, &b[j]).is_lt());
+                        i += run;
+                        if run < MIN_GALLOP {
+                            a_streak = 0;
+                        }
+                    }
+                }
+                Ordering::Greater => {
+                    a_streak = 0;
+                    if b_streak < MIN_GALLOP {
+                        j += 1;
+                        b_streak += 1;
+                    } else {
+                        let run = 1 + gallop(&b[j + 1..], |y| compare(&a[i], y).is_gt());
+                        j += run;
+                        if run < MIN_GALLOP {
+                            b_streak = 0;
+                        }
+                    }
+                }
+                Ordering::Equal => {
+                    // Most keys are not repeated, so check the next element before galloping.
+                    let mut a_end = i + 1;
+                    if a.get(a_end).is_some_and(|x| compare(x, &b[j]).is_eq()) {
+                        a_end += 1 + gallop(&a[a_end + 1..], |x| compare(x, &b[j]).is_eq());
+                    }
+                    let mut b_end = j + 1;
+                    if b.get(b_end).is_some_and(|y| compare(&a[i], y).is_eq()) {
+                        b_end += 1 + gallop(&b[b_end + 1..], |y| compare(&a[i], y).is_eq());
+                    }
+
+                    self.a_run = i..a_end;
+                    self.b_run = j..b_end;
+                    self.b_index = j;
+                    self.next_a = a_end;
+                    self.next_b = b_end;
+                    return true;
+                }
+            }
+        }
+
+        self.next_a = a.len();
+        self.next_b = b.len();
+        false
+    }
+}
+
+impl<A, B, F> Iterator for JoinIndices<'_, A, B, F>
+where
+    F: FnMut(&A, &B) -> Ordering,
+{
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.a_run.is_empty() && !self.find_runs() {
+            return None;
+        }
+
+        let pair = (self.a_run.start, self.b_index);
+
+        self.b_index += 1;
+        if self.b_index == self.b_run.end {
+            self.b_index = self.b_run.start;
+            self.a_run.start += 1;
+        }
+
+        Some(pair)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Only the rest of the current runs is known to be matching.
+        let current = match self.a_run.len() {
+            0 => 0,
+            rows => rows * self.b_run.len() - (self.b_index - self.b_run.start),
+        };
+        let rest = (self.a.len() - self.next_a).checked_mul(self.b.len() - self.next_b);
+
+        (current, rest.and_then(|rest| rest.checked_add(current)))
+    }
+}
+
+impl<A, B, F> FusedIterator for JoinIndices<'_, A, B, F> where F: FnMut(&A, &B) -> Ordering {}
+
+/// An iterator over the pairs of matching elements from two sorted slices.
+///
+/// Created by [`inner_join`], [`inner_join_by`], and [`inner_join_by_key`].
+pub struct InnerJoin<'a, A, B, F> {
+    indices: JoinIndices<'a, A, B, F>,
+}
+
+impl<'a, A, B, F> Iterator for InnerJoin<'a, A, B, F>
+where
+    F: FnMut(&A, &B) -> Ordering,
+{
+    type Item = (&'a A, &'a B);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (i, j) = self.indices.next()?;
+        Some((&self.indices.a[i], &self.indices.b[j]))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.indices.size_hint()
+    }
+}
+
+impl<A, B, F> FusedIterator for InnerJoin<'_, A, B, F> where F: FnMut(&A, &B) -> Ordering {}
+
+/// Returns an iterator over the elements of the sorted slice `a` that are equal to some
+/// element of the sorted slice `b`.
+///
+/// ```
+/// use shar_search::join::semi_join;
+///
+/// let kept: Vec<_> = semi_join(&[1, 2, 2, 3, 5], &[2, 2, 5, 8]).copied().collect();
+/// assert_eq!(kept, [2, 2, 5]);
+/// ```
+pub fn semi_join<'a, T: Ord>(
+    a: &'a [T],
+    b: &'a [T],
+) -> SemiJoin<'a, T, T, impl FnMut(&T, &T) -> Ordering> {
+    semi_join_by(a, b, T::cmp)
+}
+
+/// Returns an iterator over the elements of the sorted slice `a` that match some element of
+/// the sorted slice `b`, where `compare(x, y)` returns the ordering of an element of `a`
+/// relative to an element of `b`. See [`semi_join`].
+pub fn semi_join_by<'a, A, B, F>(a: &'a [A], b: &'a [B], compare: F) -> SemiJoin<'a, A, B, F>
+where
+    F: FnMut(&A, &B) -> Ordering,
+{
+    SemiJoin {
+        runs: MembershipRuns::new(a, b, compare),
+    }
+}
+
+/// Returns an iterator over the elements of the sorted slice `a` whose key, as returned by `f`,
+/// is the key of some element of the sorted slice `b`. See [`semi_join`].
+pub fn semi_join_by_key<'a, T, K, F>(
+    a: &'a [T],
+    b: &'a [T],
+    mut f: F,
+) -> SemiJoin<'a, T, T, impl FnMut(&T, &T) -> Ordering>
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    semi_join_by(a, b, move |x, y| f(x).cmp(&f(y)))
+}
+
+/// Returns an iterator over the elements of the sorted slice `a` that are not equal to any
+/// element of the sorted slice `b`.
+///
+/// ```
+/// use shar_search::join::anti_join;
+///
+/// let kept: Vec<_> = anti_join(&[1, 2, 2, 3, 5], &[2, 2, 5, 8]).copied().collect();
+/// assert_eq!(kept, [1, 3]);
+/// ```
+pub fn anti_join<'a, T: Ord>(
+    a: &'a [T],
+    b: &'a [T],
+) -> AntiJoin<'a, T, T, impl FnMut(&T, &T) -> Ordering> {
+    anti_join_by(a, b, T::cmp)
+}
+
+/// Returns an iterator over the elements of the sorted slice `a` that do not match any element
+/// of the sorted slice `b`, where `compare(x, y)` returns the ordering of an element of `a`
+/// relative to an element of `b`. See [`anti_join`].
+pub fn anti_join_by<'a, A, B, F>(a: &'a [A], b: &'a [B], compare: F) -> AntiJoin<'a, A, B, F>
+where
+    F: FnMut(&A, &B) -> Ordering,
+{
+    AntiJoin {
+        runs: MembershipRuns::new(a, b, compare),
+    }
+}
+
+/// Returns an iterator over the elements of the sorted slice `a` whose key, as returned by `f`,
+/// is not the key of any element of the sorted slice `b`. See [`anti_join`].
+pub fn anti_join_by_key<'a, T, K, F>(
+    a: &'a [T],
+    b: &'a [T],
+    mut f: F,
+) -> AntiJoin<'a, T, T, impl FnMut(&T, &T) -> Ordering>
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    anti_join_by(a, b, move |x, y| f(x).cmp(&f(y)))
+}
+
+/// Splits `a` into runs of elements that either all match some element of `b` or all match
+/// none, so that the semi-join and anti-join can emit or skip whole runs at once.
+struct MembershipRuns<'a, A, B, F> {
+    a: &'a [A],
+    b: &'a [B],
+    /// The current run is `a[a_index..run_end]`.
+    a_index: usize,
+    run_end: usize,
+    run_matches: bool,
+    /// Everything in `b` before this is less than `a[a_index]`.
+    b_index: usize,
+    compare: F,
+}
+
+impl<'a, A, B, F> MembershipRuns<'a, A, B, F>
+where
+    F: FnMut(&A, &B) -> Ordering,
+{
+    fn new(a: &'a [A], b: &'a [B], compare: F) -> Self {
+        Self {
+            a,
+            b,
+            a_index: 0,
+            run_end: 0,
+            run_matches: false,
+            b_index: 0,
+            compare,
+        }
+    }
+
+    /// Returns the next element of `a` whose membership in `b` is `matches`.
+    fn next_with(&mut self, matches: bool) -> Option<&'a A> {
+        loop {
+            if self.a_index < self.run_end {
+                if self.run_matches == matches {
+                    self.a_index += 1;
+                    return Some(&self.a[self.a_index - 1]);
+                }
+                self.a_index = self.run_end;
+            }
+
+            if self.a_index == self.a.len() {
+                return None;
+            }
+            self.find_run();
+        }
+    }
+
+    fn find_run(&mut self) {
+        let (a, b, compare) = (self.a, self.b, &mut self.compare);
+        let i = self.a_index;
+
+        self.b_index += gallop(&b[self.b_index..], |y| compare(&a[i], y).is_gt());
+
+        let Some(next_b) = b.get(self.b_index) else {
+            // Nothing in `b` is left to match the rest of `a`.
+            self.run_end = a.len();
+            self.run_matches = false;
+            return;
+        };
+
+        // `a[i]` is not greater than `next_b`, so either it matches and so does the rest of its
+        // run of equal elements, or it falls before `next_b` along with everything up to it.
+        self.run_matches = compare(&a[i], next_b).is_eq();
+        self.run_end = i
+            + 1
+            + if self.run_matches {
+                gallop(&a[i + 1..], |x| compare(x, next_b).is_eq())
+            } else {
+                gallop(&a[i + 1..], |x| compare(x, next_b).is_lt())
+            };
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.a.len() - self.a_index))
+    }
+}
+
+/// An iterator over the elements of a sorted slice that match some element of another.
+///
+/// Created by [`semi_join`], [`semi_join_by`], and [`semi_join_by_key`].
+pub struct SemiJoin<'a, A, B, F> {
+    runs: MembershipRuns<'a, A, B, F>,
+}
+
+impl<'a, A, B, F> Iterator for SemiJoin<'a, A, B, F>
+where
+    F: FnMut(&A, &B) -> Ordering,
+{
+    type Item = &'a A;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runs.next_with(true)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.runs.size_hint()
+    }
+}
+
+impl<A, B, F> FusedIterator for SemiJoin<'_, A, B, F> where F: FnMut(&A, &B) -> Ordering {}
+
+/// An iterator over the elements of a sorted slice that match no element of another.
+///
+/// Created by [`anti_join`], [`anti_join_by`], and [`anti_join_by_key`].
+pub struct AntiJoin<'a, A, B, F> {
+    runs: MembershipRuns<'a, A, B, F>,
+}
+
+impl<'a, A, B, F> Iterator for AntiJoin<'a, A, B, F>
+where
+    F: FnMut(&A, &B) -> Ordering,
+{
+    type Item = &'a A;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runs.next_with(false)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.runs.size_hint()
+    }
+}
+
+impl<A, B, F> FusedIterator for AntiJoin<'_, A, B, F> where F: FnMut(&A, &B) -> Ordering {}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        anti_join, anti_join_by_key, inner_join, inner_join_by, inner_join_by_key, join_indices,
+        semi_join, semi_join_by_key,
+    };
+    use crate::reference::{self, assert_matches_reference, inputs};
+
+    #[test]
+    fn test_duplicate_runs() {
+        let a = [1, 2, 2, 2, 5, 6, 6];
+        let b = [0, 2, 2, 5, 6, 6, 6, 9];
+
+        let pairs: Vec<_> = join_indices(&a, &b).collect();
+        assert_eq!(
+            pairs,
+            [
+                (1, 1),
+                (1, 2),
+                (2, 1),
+                (2, 2),
+                (3, 1),
+                (3, 2),
+                (4, 3),
+                (5, 4),
+                (5, 5),
+                (5, 6),
+                (6, 4),
+                (6, 5),
+                (6, 6),
+            ]
+        );
+        assert_eq!(pairs, reference::join_indices(&a, &b));
+
+        // Swapping the sides swaps the pairs, in an order that is again sorted.
+        let mut swapped: Vec<_> = join_indices(&b, &a).map(|(j, i)| (i, j)).collect();
+        swapped.sort();
+        assert_eq!(swapped, pairs);
+    }
+
+    #[test]
+    fn test_against_reference() {
+        assert_matches_reference!(
+            for (a, b) in inputs::slice_pairs(31) =>
+            join_indices(&a, &b).collect::<Vec<_>>(),
+            reference::join_indices(&a, &b),
+        );
+        assert_matches_reference!(
+            for (a, b) in inputs::slice_pairs(32) =>
+            inner_join(&a, &b).collect::<Vec<_>>(),
+            reference::join_indices(&a, &b)
+                .into_iter()
+                .map(|(i, j)| (&a[i], &b[j]))
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn test_by_key() {
+        let a = [(1, 'a'), (3, 'b'), (3, 'c'), (8, 'd')];
+        let b = [(3, 'x'), (4, 'y'), (8, 'z')];
+
+        let tags: Vec<_> = inner_join_by_key(&a, &b, |&(k, _)| k)
+            .map(|(x, y)| (x.1, y.1))
+            .collect();
+        assert_eq!(tags, [('b', 'x'), ('c', 'x'), ('d', 'z')]);
+
+        // Joining slices of different types.
+        let names = ["kiwi", "apple", "cherry"];
+        let lengths = [4, 5, 6, 6];
+        let matched: Vec<_> = inner_join_by(&lengths, &names, |l, n| l.cmp(&n.len()))
+            .map(|(_, n)| *n)
+            .collect();
+        assert_eq!(matched, ["kiwi", "apple", "cherry", "cherry"]);
+    }
+
+    #[test]
+    fn test_skewed() {
+        let big: Vec<u32> = (0..100_000).collect();
+        let small = [7, 7, 50_000, 99_999, 100_000];
+
+        let pairs: Vec<_> = join_indices(&small, &big).collect();
+        assert_eq!(pairs, [(0, 7), (1, 7), (2, 50_000), (3, 99_999)]);
+
+        let pairs: Vec<_> = join_indices(&big, &small).collect();
+        assert_eq!(pairs, [(7, 0), (7, 1), (50_000, 2), (99_999, 3)]);
+    }
+
+    #[test]
+    fn test_semi_anti_against_reference() {
+        assert_matches_reference!(
+            for (a, b) in inputs::slice_pairs(37) =>
+            semi_join(&a, &b).collect::<Vec<_>>(),
+            reference::filter_by_membership(&a, &b, true),
+        );
+        assert_matches_reference!(
+            for (a, b) in inputs::slice_pairs(38) =>
+            anti_join(&a, &b).collect::<Vec<_>>(),
+            reference::filter_by_membership(&a, &b, false),
+        );
+    }
+
+    #[test]
+    fn test_semi_anti_duplicates() {
+        let a = [1, 1, 2, 4, 4, 4, 7];
+        let b = [1, 1, 1, 4, 4, 9];
+
+        assert!(semi_join(&a, &b).eq(&[1, 1, 4, 4, 4]));
+        assert!(anti_join(&a, &b).eq(&[2, 7]));
+        assert!(semi_join(&a, &[]).next().is_none());
+        assert!(anti_join(&a, &[]).eq(&a));
+        assert!(anti_join(&[], &b).next().is_none());
+
+        let pairs = [(1, 'a'), (2, 'b'), (2, 'c'), (3, 'd')];
+        let allow = [(2, 'x'), (3, 'y')];
+        let tags: String = semi_join_by_key(&pairs, &allow, |&(k, _)| k)
+            .map(|&(_, t)| t)
+            .collect();
+        assert_eq!(tags, "bcd");
+        let tags: String = anti_join_by_key(&pairs, &allow, |&(k, _)| k)
+            .map(|&(_, t)| t)
+            .collect();
+        assert_eq!(tags, "a");
+    }
+}
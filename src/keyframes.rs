@@ -0,0 +1,469 @@
+//! Keyframe tracks for animation and game timelines, sampled by time every frame.
+//!
+//! A [`KeyframeTrack`] holds values at non-decreasing times. Sampling it at a time returns the
+//! keyframes on either side and how far between them the time lies, leaving the interpolation
+//! itself to the caller. Outside its keyframes, a track does what its [`Boundary`] says.
+//!
+//! ```
+//! use shar_search::keyframes::{Boundary, KeyframeTrack};
+//!
+//! // A sprite that moves right, then up, then back to the start, once a second.
+//! let track = KeyframeTrack::new(
+//!     vec![(0.0, [0.0, 0.0]), (0.5, [10.0, 0.0]), (0.75, [10.0, 5.0]), (1.0, [0.0, 0.0])],
+//!     Boundary::Loop,
+//! )
+//! .unwrap();
+//!
+//! let position = |t: f32| {
+//!     let sample = track.sample(t);
+//!     let [x0, y0] = *sample.prev;
+//!     let [x1, y1] = *sample.next;
+//!     [x0 + (x1 - x0) * sample.alpha, y0 + (y1 - y0) * sample.alpha]
+//! };
+//!
+//! assert_eq!(position(0.25), [5.0, 0.0]);
+//! assert_eq!(position(0.625), [10.0, 2.5]);
+//! assert_eq!(position(1.25), [5.0, 0.0]);
+//!
+//! // Playing the clip frame by frame costs O(1) amortized per frame.
+//! let mut cursor = track.cursor();
+//! for frame in 0..120 {
+//!     let t = frame as f32 / 60.0;
+//!     assert_eq!(cursor.sample(t), track.sample(t));
+//! }
+//! ```
+
+use alloc::vec::Vec;
+use core::{error::Error, fmt};
+
+use crate::{
+    gallop::{gallop, gallop_back},
+    validate, SharBinarySearch,
+};
+
+/// What a [`KeyframeTrack`] does with a time before its first keyframe or after its last.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Boundary {
+    /// Hold the first keyframe before the track and the last one after it.
+    Clamp,
+    /// Repeat the track, jumping from its last keyframe back to its first. The time of the last
+    /// keyframe is the time of the first in the next repetition.
+    Loop,
+    /// Repeat the track, playing it backwards every other time.
+    Mirror,
+}
+
+/// The error returned when the keyframes given to [`KeyframeTrack::new`] are invalid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyframeError {
+    /// There are no keyframes.
+    Empty,
+    /// The time of the keyframe at this index is infinite or NaN.
+    NonFinite(usize),
+    /// The time of the keyframe at this index is less than its predecessor's.
+    Decreasing(usize),
+}
+
+impl fmt::Display for KeyframeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyframeError::Empty => write!(f, "track has no keyframes"),
+            KeyframeError::NonFinite(index) => {
+                write!(f, "time of keyframe at index {index} is not finite")
+            }
+            KeyframeError::Decreasing(index) => {
+                write!(
+                    f,
+                    "time of keyframe at index {index} is less than its predecessor's"
+                )
+            }
+        }
+    }
+}
+
+impl Error for KeyframeError {}
+
+/// The keyframes around a time, and how far between them it lies. Returned by
+/// [`KeyframeTrack::sample`] and [`TrackCursor::sample`].
+///
+/// On or outside the track's keyframes, `prev` and `next` may be the same keyframe, and
+/// `alpha` is then 0.
+#[derive(Debug, PartialEq)]
+pub struct Sample<'a, T> {
+    /// The value of the last keyframe at or before the time.
+    pub prev: &'a T,
+    /// The value of the first keyframe after the time.
+    pub next: &'a T,
+    /// How far the time lies from `prev` to `next`, from 0 up to 1.
+    pub alpha: f32,
+}
+
+impl<T> Clone for Sample<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Sample<'_, T> {}
+
+/// Values at non-decreasing times, sampled by time. See the [`keyframes`](crate::keyframes)
+/// module.
+///
+/// A time exactly on a keyframe samples that keyframe with `alpha` 0. Keyframes may share a
+/// time, which makes the track jump: just before the time, it moves towards the first of them,
+/// and from the time on, it starts from the last. A NaN time samples the first keyframe, as does
+/// an infinite one with [`Boundary::Loop`] or [`Boundary::Mirror`]. A track whose keyframes all
+/// share one time, such as one with a single keyframe, has nothing to repeat, so it samples as
+/// with [`Boundary::Clamp`] whatever its boundary.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyframeTrack<T> {
+    times: Vec<f32>,
+    values: Vec<T>,
+    boundary: Boundary,
+}
+
+impl<T> KeyframeTrack<T> {
+    /// Creates a track from `(time, value)` keyframes in non-decreasing order of time, with
+    /// `boundary` saying what to do outside them.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`KeyframeError`] if there are no keyframes, or if their times are not finite
+    /// and non-decreasing.
+    pub fn new(keyframes: Vec<(f32, T)>, boundary: Boundary) -> Result<Self, KeyframeError> {
+        let (times, values): (Vec<f32>, Vec<T>) = keyframes.into_iter().unzip();
+        if times.is_empty() {
+            return Err(KeyframeError::Empty);
+        }
+        if let Some(index) = times.iter().position(|t| !t.is_finite()) {
+            return Err(KeyframeError::NonFinite(index));
+        }
+        if let Some(index) = validate::first_unsorted_by(&times, |a, b| a <= b) {
+            return Err(KeyframeError::Decreasing(index));
+        }
+
+        Ok(Self {
+            times,
+            values,
+            boundary,
+        })
+    }
+
+    /// Returns the times of the keyframes, in non-decreasing order.
+    pub fn times(&self) -> &[f32] {
+        &self.times
+    }
+
+    /// Returns the values of the keyframes, in the same order as
+    /// [`times`](KeyframeTrack::times).
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    /// Returns the behavior outside the keyframes.
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    /// Returns the keyframes around time `t`.
+    pub fn sample(&self, t: f32) -> Sample<'_, T> {
+        let local = self.local_time(t);
+        self.sample_at(self.times.bl_partition_point(|&time| time <= local), local)
+    }
+
+    /// Returns a cursor for sampling the track at times that change a little at a time, such
+    /// as once per frame.
+    pub fn cursor(&self) -> TrackCursor<'_, T> {
+        TrackCursor {
+            track: self,
+            position: 0,
+        }
+    }
+
+    /// Maps `t` into the track's keyframes according to its boundary.
+    fn local_time(&self, t: f32) -> f32 {
+        let first = self.times[0];
+        let last = self.times[self.times.len() - 1];
+        let duration = last - first;
+
+        match self.boundary {
+            _ if duration == 0.0 => t,
+            Boundary::Clamp => t,
+            Boundary::Loop => {
+                let local = first + rem_euclid(t - first, duration);
+                // Rounding can land on the end of the track, which is its start. A NaN stays
+                // NaN, to hold the first keyframe.
+                if local >= last {
+                    first
+                } else {
+                    local
+                }
+            }
+            Boundary::Mirror => {
+                let phase = rem_euclid(t - first, 2.0 * duration);
+                let phase = if phase > duration {
+                    2.0 * duration - phase
+                } else {
+                    phase
+                };
+                let local = first + phase;
+                if local > last {
+                    last
+                } else {
+                    local
+                }
+            }
+        }
+    }
+
+    /// Returns the sample at `local`, where `index` is the number of keyframes at or before it.
+    fn sample_at(&self, index: usize, local: f32) -> Sample<'_, T> {
+        let Some(prev) = index.checked_sub(1) else {
+            return self.hold(0);
+        };
+        if index == self.times.len() {
+            return self.hold(prev);
+        }
+
+        // The times differ, as `local` lies at or after the one and before the other.
+        let (t0, t1) = (self.times[prev], self.times[index]);
+        Sample {
+            prev: &self.values[prev],
+            next: &self.values[index],
+            alpha: (local - t0) / (t1 - t0),
+        }
+    }
+
+    fn hold(&self, index: usize) -> Sample<'_, T> {
+        Sample {
+            prev: &self.values[index],
+            next: &self.values[index],
+            alpha: 0.0,
+        }
+    }
+}
+
+/// Returns the least non-negative remainder of `x` divided by a positive `period`, like
+/// `f32::rem_euclid`, which needs `std`.
+fn rem_euclid(x: f32, period: f32) -> f32 {
+    let remainder = x % period;
+    if remainder < 0.0 {
+        remainder + period
+    } else {
+        remainder
+    }
+}
+
+/// A cursor for sampling a [`KeyframeTrack`] during playback. Created by
+/// [`KeyframeTrack::cursor`].
+///
+/// Like a [`SearchCursor`](crate::SearchCursor), each sample gallops from the keyframe of the
+/// previous one, so a sample `k` keyframes away costs `O(log k)` comparisons. Playing a track
+/// forwards or backwards at any frame rate therefore costs `O(1)` amortized per frame, plus
+/// `O(log n)` each time a looping track wraps around. Samples are always the same as from
+/// [`KeyframeTrack::sample`].
+#[derive(Debug)]
+pub struct TrackCursor<'a, T> {
+    track: &'a KeyframeTrack<T>,
+    /// The number of keyframes at or before the last sample's time.
+    position: usize,
+}
+
+impl<T> Clone for TrackCursor<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for TrackCursor<'_, T> {}
+
+impl<'a, T> TrackCursor<'a, T> {
+    /// Returns the track this cursor samples.
+    pub fn track(&self) -> &'a KeyframeTrack<T> {
+        self.track
+    }
+
+    /// Returns the keyframes around time `t`, searching from those of the previous sample.
+    pub fn sample(&mut self, t: f32) -> Sample<'a, T> {
+        let track = self.track;
+        let local = track.local_time(t);
+        let (before, after) = track.times.split_at(self.position);
+
+        self.position = match after.first() {
+            Some(&time) if time <= local => self.position + gallop(after, |&time| time <= local),
+            // The time is before the keyframe after the previous sample.
+            _ => gallop_back(before, |&time| time <= local),
+        };
+        track.sample_at(self.position, local)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Boundary, KeyframeError, KeyframeTrack, Sample};
+    use crate::test_util::XorShift;
+
+    fn parts<T: Copy>(sample: Sample<'_, T>) -> (T, T, f32) {
+        (*sample.prev, *sample.next, sample.alpha)
+    }
+
+    #[test]
+    fn test_validation() {
+        let empty: Vec<(f32, u8)> = Vec::new();
+        assert_eq!(
+            KeyframeTrack::new(empty, Boundary::Clamp),
+            Err(KeyframeError::Empty)
+        );
+        assert_eq!(
+            KeyframeTrack::new(vec![(0.0, 1), (f32::NAN, 2)], Boundary::Clamp),
+            Err(KeyframeError::NonFinite(1))
+        );
+        assert_eq!(
+            KeyframeTrack::new(vec![(f32::NEG_INFINITY, 1)], Boundary::Loop),
+            Err(KeyframeError::NonFinite(0))
+        );
+        assert_eq!(
+            KeyframeTrack::new(
+                vec![(0.0, 1), (1.0, 2), (1.0, 3), (0.5, 4)],
+                Boundary::Clamp
+            ),
+            Err(KeyframeError::Decreasing(3))
+        );
+        assert_eq!(
+            KeyframeError::Decreasing(3).to_string(),
+            "time of keyframe at index 3 is less than its predecessor's"
+        );
+    }
+
+    #[test]
+    fn test_clamp() {
+        let track =
+            KeyframeTrack::new(vec![(1.0, 10), (2.0, 20), (4.0, 40)], Boundary::Clamp).unwrap();
+
+        assert_eq!(parts(track.sample(0.0)), (10, 10, 0.0));
+        assert_eq!(parts(track.sample(f32::NEG_INFINITY)), (10, 10, 0.0));
+        assert_eq!(parts(track.sample(f32::NAN)), (10, 10, 0.0));
+        // Exact hits.
+        assert_eq!(parts(track.sample(1.0)), (10, 20, 0.0));
+        assert_eq!(parts(track.sample(2.0)), (20, 40, 0.0));
+        assert_eq!(parts(track.sample(4.0)), (40, 40, 0.0));
+        // Between keyframes.
+        assert_eq!(parts(track.sample(1.5)), (10, 20, 0.5));
+        assert_eq!(parts(track.sample(3.5)), (20, 40, 0.75));
+        // After the last keyframe.
+        assert_eq!(parts(track.sample(7.0)), (40, 40, 0.0));
+        assert_eq!(parts(track.sample(f32::INFINITY)), (40, 40, 0.0));
+    }
+
+    #[test]
+    fn test_shared_times() {
+        // A jump from 20 to 30 at time 2.
+        let keyframes = vec![(0.0, 0), (2.0, 20), (2.0, 25), (2.0, 30), (4.0, 40)];
+        let track = KeyframeTrack::new(keyframes, Boundary::Clamp).unwrap();
+
+        assert_eq!(parts(track.sample(1.0)), (0, 20, 0.5));
+        assert_eq!(parts(track.sample(2.0)), (30, 40, 0.0));
+        assert_eq!(parts(track.sample(3.0)), (30, 40, 0.5));
+
+        // Shared times at the ends hold the outermost keyframe.
+        let track = KeyframeTrack::new(
+            vec![(0.0, 1), (0.0, 2), (1.0, 3), (1.0, 4)],
+            Boundary::Clamp,
+        )
+        .unwrap();
+        assert_eq!(parts(track.sample(-1.0)), (1, 1, 0.0));
+        assert_eq!(parts(track.sample(0.0)), (2, 3, 0.0));
+        assert_eq!(parts(track.sample(1.0)), (4, 4, 0.0));
+    }
+
+    #[test]
+    fn test_single_time() {
+        for boundary in [Boundary::Clamp, Boundary::Loop, Boundary::Mirror] {
+            let single = KeyframeTrack::new(vec![(3.0, 'a')], boundary).unwrap();
+            for t in [f32::NEG_INFINITY, -5.0, 3.0, 8.0, f32::INFINITY, f32::NAN] {
+                assert_eq!(parts(single.sample(t)), ('a', 'a', 0.0));
+            }
+
+            let shared = KeyframeTrack::new(vec![(3.0, 'a'), (3.0, 'b')], boundary).unwrap();
+            assert_eq!(parts(shared.sample(2.0)), ('a', 'a', 0.0));
+            assert_eq!(parts(shared.sample(3.0)), ('b', 'b', 0.0));
+            assert_eq!(parts(shared.sample(4.0)), ('b', 'b', 0.0));
+        }
+    }
+
+    #[test]
+    fn test_loop() {
+        let track =
+            KeyframeTrack::new(vec![(1.0, 10), (2.0, 20), (3.0, 30)], Boundary::Loop).unwrap();
+
+        assert_eq!(parts(track.sample(1.5)), (10, 20, 0.5));
+        // The end of one repetition is the start of the next.
+        assert_eq!(parts(track.sample(3.0)), (10, 20, 0.0));
+        assert_eq!(parts(track.sample(3.5)), (10, 20, 0.5));
+        assert_eq!(parts(track.sample(6.5)), (20, 30, 0.5));
+        // Before the track, it repeats backwards in time.
+        assert_eq!(parts(track.sample(0.5)), (20, 30, 0.5));
+        assert_eq!(parts(track.sample(-3.0)), (10, 20, 0.0));
+        // Just before a repetition ends.
+        let end = track.sample(2.999);
+        assert_eq!((end.prev, end.next), (&20, &30));
+        assert!(end.alpha > 0.99 && end.alpha < 1.0);
+
+        assert_eq!(parts(track.sample(f32::INFINITY)), (10, 10, 0.0));
+        assert_eq!(parts(track.sample(f32::NAN)), (10, 10, 0.0));
+    }
+
+    #[test]
+    fn test_mirror() {
+        let track =
+            KeyframeTrack::new(vec![(1.0, 10), (2.0, 20), (3.0, 30)], Boundary::Mirror).unwrap();
+
+        assert_eq!(parts(track.sample(1.5)), (10, 20, 0.5));
+        assert_eq!(parts(track.sample(3.0)), (30, 30, 0.0));
+        // Backwards from the end.
+        assert_eq!(parts(track.sample(3.5)), (20, 30, 0.5));
+        assert_eq!(parts(track.sample(4.0)), (20, 30, 0.0));
+        assert_eq!(parts(track.sample(5.0)), (10, 20, 0.0));
+        // Forwards again.
+        assert_eq!(parts(track.sample(5.25)), (10, 20, 0.25));
+        // Before the track, it mirrors at its start.
+        assert_eq!(parts(track.sample(0.5)), (10, 20, 0.5));
+        assert_eq!(parts(track.sample(-1.0)), (30, 30, 0.0));
+
+        assert_eq!(parts(track.sample(f32::NEG_INFINITY)), (10, 10, 0.0));
+        assert_eq!(parts(track.sample(f32::NAN)), (10, 10, 0.0));
+    }
+
+    #[test]
+    fn test_cursor_matches_stateless_sample() {
+        let mut rng = XorShift::new(59);
+
+        for boundary in [Boundary::Clamp, Boundary::Loop, Boundary::Mirror] {
+            // Keyframes with shared times, over 0 to about 50 seconds.
+            let mut time = 0.0;
+            let keyframes: Vec<(f32, u32)> = (0..200)
+                .map(|i| {
+                    if rng.below(4) != 0 {
+                        time += rng.below(500) as f32 / 1000.0;
+                    }
+                    (time, i)
+                })
+                .collect();
+            let track = KeyframeTrack::new(keyframes, boundary).unwrap();
+
+            // Playing forwards, backwards, skipping around, and past the ends.
+            let forwards = (0..6000).map(|frame| frame as f32 / 60.0 - 10.0);
+            let backwards = (0..6000).rev().map(|frame| frame as f32 / 60.0 - 10.0);
+            let skipping: Vec<f32> = (0..500)
+                .map(|_| rng.below(200_000) as f32 / 1000.0)
+                .collect();
+            let mut times: Vec<f32> = forwards.chain(backwards).chain(skipping).collect();
+            times.extend(track.times().to_vec());
+
+            let mut cursor = track.cursor();
+            for t in times {
+                assert_eq!(cursor.sample(t), track.sample(t), "{boundary:?} at {t}");
+            }
+        }
+    }
+}
@@ -0,0 +1,461 @@
+//! Layouts of sorted elements that keep the first levels of a search close together.
+//!
+//! Each level of a binary search of a large sorted slice loads a cache line far from the one
+//! before, and which one isn't known until the comparison before it is done. These layouts
+//! reorder the elements of a sorted slice into an implicit tree, so that the top levels share a
+//! few cache lines and the children of a node are next to each other:
+//!
+//! - [`Eytzinger`] stores a binary search tree in breadth-first order, the root first and the
+//!   children of node `k` at `2k + 1` and `2k + 2`.
+//! - [`STree`] stores a static B-tree of `B` keys per node, with the children of node `k` at
+//!   `k * (B + 1) + 1` through `k * (B + 1) + B + 1`. A node of 16 `u32`s is one cache line,
+//!   which is searched as a whole before moving on.
+//!
+//! Both are built once, from a sorted slice, in `O(n)` time. With the `rayon` feature,
+//! `Eytzinger::par_from_sorted` and `STree::par_from_sorted` build the same layouts on all
+//! threads, computing where each element goes independently.
+//!
+//! ```
+//! use shar_search::layout::{Eytzinger, STree};
+//!
+//! let sorted = [1, 3, 5, 7, 9, 11];
+//! let eytzinger = Eytzinger::from_sorted(&sorted);
+//! assert_eq!(eytzinger.as_slice(), &[7, 3, 11, 1, 5, 9]);
+//! assert_eq!(eytzinger.lower_bound(&4), Some(&5));
+//!
+//! let stree: STree<_, 2> = STree::from_sorted(&sorted);
+//! assert_eq!(stree.lower_bound(&9), Some(&9));
+//! assert_eq!(stree.lower_bound(&12), None);
+//! ```
+
+use alloc::vec::Vec;
+use core::mem::MaybeUninit;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// A sorted slice stored as a binary search tree in breadth-first order.
+///
+/// Node `k` has children `2k + 1` and `2k + 2`, so the nodes of the first levels are at the
+/// start, and the four grandchildren of a node are adjacent. Every level but the last is full,
+/// and the last is filled from the left.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Eytzinger<T> {
+    tree: Vec<T>,
+}
+
+impl<T> Eytzinger<T> {
+    /// Lays out the elements of `sorted`, which must be sorted in ascending order. If it isn't,
+    /// searches return unspecified results.
+    pub fn from_sorted(sorted: &[T]) -> Self
+    where
+        T: Clone,
+    {
+        let mut tree = Vec::with_capacity(sorted.len());
+        let mut next = 0;
+        fill_eytzinger(
+            &mut tree.spare_capacity_mut()[..sorted.len()],
+            0,
+            sorted,
+            &mut next,
+        );
+        debug_assert_eq!(next, sorted.len());
+        // SAFETY: the in-order traversal visits each of the `sorted.len()` nodes once, and
+        // writes each. If a clone panics, the length is still zero, and the clones so far leak.
+        unsafe { tree.set_len(sorted.len()) };
+        Self { tree }
+    }
+
+    /// Lays out the elements of `sorted` like [`from_sorted`](Eytzinger::from_sorted), on all
+    /// of rayon's threads. The layout is the same.
+    ///
+    /// Which element goes to each node follows from the node's index alone, so each node is
+    /// filled independently.
+    #[cfg(feature = "rayon")]
+    pub fn par_from_sorted(sorted: &[T]) -> Self
+    where
+        T: Clone + Send + Sync,
+    {
+        let len = sorted.len();
+        let mut tree = Vec::with_capacity(len);
+        tree.spare_capacity_mut()[..len]
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(k, slot)| {
+                slot.write(sorted[eytzinger_rank(k, len)].clone());
+            });
+        // SAFETY: each of the `len` slots was written once, through its own reference. If a
+        // clone panics, the panic reaches here before the length is set.
+        unsafe { tree.set_len(len) };
+        Self { tree }
+    }
+
+    /// Returns the elements in layout order.
+    pub fn as_slice(&self) -> &[T] {
+        &self.tree
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns `true` if there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Returns the first element in sorted order that is not less than `x`, or `None` if all
+    /// of them are less.
+    pub fn lower_bound(&self, x: &T) -> Option<&T>
+    where
+        T: Ord,
+    {
+        // One-based, so that the children of `j` are `2j` and `2j + 1`.
+        let mut j = 1;
+        while j <= self.tree.len() {
+            j = 2 * j + usize::from(self.tree[j - 1] < *x);
+        }
+        // Each step right was past an element less than `x`, so the answer is the node of the
+        // last step left: drop the trailing steps right, then that step.
+        j >>= j.trailing_ones() + 1;
+        j.checked_sub(1).map(|k| &self.tree[k])
+    }
+
+    /// Returns `true` if some element equals `x`.
+    pub fn contains(&self, x: &T) -> bool
+    where
+        T: Ord,
+    {
+        self.lower_bound(x) == Some(x)
+    }
+}
+
+/// Writes the subtree rooted at node `k` from `sorted[*next..]`, in order.
+fn fill_eytzinger<T: Clone>(tree: &mut [MaybeUninit<T>], k: usize, sorted: &[T], next: &mut usize) {
+    if k < tree.len() {
+        fill_eytzinger(tree, 2 * k + 1, sorted, next);
+        tree[k].write(sorted[*next].clone());
+        *next += 1;
+        fill_eytzinger(tree, 2 * k + 2, sorted, next);
+    }
+}
+
+/// Returns the index in sorted order of the element at node `k` of the Eytzinger layout of
+/// `len` elements.
+#[cfg(any(feature = "rayon", test))]
+fn eytzinger_rank(k: usize, len: usize) -> usize {
+    let j = k + 1;
+    let depth = j.ilog2();
+    let levels = len.ilog2() + 1;
+    // The rank if the last level were full, where node `j` is the middle of its subtree.
+    let full = ((2 * (j - (1 << depth)) + 1) << (levels - 1 - depth)) - 1;
+    // The last level would hold the even ranks of a full tree, but only has its first `last`
+    // nodes. Each missing one before `full` lowers the rank by one.
+    let last = len + 1 - (1 << (levels - 1));
+    full - full.div_ceil(2).saturating_sub(last)
+}
+
+/// A sorted slice stored as a static B-tree of `B` keys per node.
+///
+/// Node `k` holds `B` keys in ascending order, and its `B + 1` children are `k * (B + 1) + 1`
+/// through `k * (B + 1) + B + 1`, with the keys of child `i` between keys `i - 1` and `i`.
+/// Searching a node compares `x` with all of its keys at once, without branching, and the
+/// default of 16 keys fills a cache line with `u32`s.
+///
+/// There are `len.div_ceil(B)` nodes, and the slots of the last keys in order past `len` are
+/// padded with copies of the greatest element.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct STree<T, const B: usize = 16> {
+    keys: Vec<T>,
+    len: usize,
+}
+
+impl<T, const B: usize> STree<T, B> {
+    /// Lays out the elements of `sorted`, which must be sorted in ascending order. If it isn't,
+    /// searches return unspecified results.
+    ///
+    /// # Panics
+    ///
+    /// Panics at compile time if `B` is zero.
+    pub fn from_sorted(sorted: &[T]) -> Self
+    where
+        T: Clone,
+    {
+        const { assert!(B > 0, "a node must hold at least one key") };
+
+        let nodes = sorted.len().div_ceil(B);
+        let mut keys = Vec::with_capacity(nodes * B);
+        let mut next = 0;
+        let slots = &mut keys.spare_capacity_mut()[..nodes * B];
+        fill_stree::<T, B>(slots, nodes, 0, sorted, &mut next);
+        debug_assert_eq!(next, nodes * B);
+        // SAFETY: the in-order traversal visits each of the `nodes * B` slots once, and writes
+        // each. If a clone panics, the length is still zero, and the clones so far leak.
+        unsafe { keys.set_len(nodes * B) };
+        Self {
+            keys,
+            len: sorted.len(),
+        }
+    }
+
+    /// Lays out the elements of `sorted` like [`from_sorted`](STree::from_sorted), on all of
+    /// rayon's threads. The layout is the same.
+    ///
+    /// Each node is filled independently: the number of keys before its subtree in sorted order
+    /// follows from the sizes of the subtrees to its left, which follow from node indices alone.
+    ///
+    /// # Panics
+    ///
+    /// Panics at compile time if `B` is zero.
+    #[cfg(feature = "rayon")]
+    pub fn par_from_sorted(sorted: &[T]) -> Self
+    where
+        T: Clone + Send + Sync,
+    {
+        const { assert!(B > 0, "a node must hold at least one key") };
+
+        let len = sorted.len();
+        let nodes = len.div_ceil(B);
+        let mut keys = Vec::with_capacity(nodes * B);
+        keys.spare_capacity_mut()[..nodes * B]
+            .par_chunks_mut(B)
+            .enumerate()
+            .for_each(|(k, node)| {
+                let mut rank = stree_start::<B>(k, nodes);
+                for (i, slot) in node.iter_mut().enumerate() {
+                    rank += B * stree_subtree_nodes::<B>(k * (B + 1) + i + 1, nodes);
+                    slot.write(sorted[rank.min(len - 1)].clone());
+                    rank += 1;
+                }
+            });
+        // SAFETY: each of the `nodes * B` slots was written once, through its own reference.
+        // If a clone panics, the panic reaches here before the length is set.
+        unsafe { keys.set_len(nodes * B) };
+        Self { keys, len }
+    }
+
+    /// Returns the keys in layout order, node by node, including the padding.
+    pub fn as_slice(&self) -> &[T] {
+        &self.keys
+    }
+
+    /// Returns the number of elements, not counting the padding.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the first element in sorted order that is not less than `x`, or `None` if all
+    /// of them are less.
+    pub fn lower_bound(&self, x: &T) -> Option<&T>
+    where
+        T: Ord,
+    {
+        let nodes = self.keys.len() / B;
+        let mut found = None;
+        let mut k = 0;
+        while k < nodes {
+            let node = &self.keys[k * B..(k + 1) * B];
+            // The keys of a node are sorted, so those less than `x` are a prefix.
+            let i = node.iter().map(|key| usize::from(key < x)).sum::<usize>();
+            // The padding comes after the greatest element in order, so it is never the first
+            // key not less than `x` once a search is done.
+            if let Some(key) = node.get(i) {
+                found = Some(key);
+            }
+            k = k * (B + 1) + i + 1;
+        }
+        found
+    }
+
+    /// Returns `true` if some element equals `x`.
+    pub fn contains(&self, x: &T) -> bool
+    where
+        T: Ord,
+    {
+        self.lower_bound(x) == Some(x)
+    }
+}
+
+/// Writes the subtree rooted at node `k` of `nodes` from `sorted[*next..]`, in order. Slots past
+/// the end of `sorted` get its last element.
+fn fill_stree<T: Clone, const B: usize>(
+    keys: &mut [MaybeUninit<T>],
+    nodes: usize,
+    k: usize,
+    sorted: &[T],
+    next: &mut usize,
+) {
+    if k < nodes {
+        for i in 0..B {
+            fill_stree::<T, B>(keys, nodes, k * (B + 1) + i + 1, sorted, next);
+            keys[k * B + i].write(sorted[(*next).min(sorted.len() - 1)].clone());
+            *next += 1;
+        }
+        fill_stree::<T, B>(keys, nodes, k * (B + 1) + B + 1, sorted, next);
+    }
+}
+
+/// Returns the number of nodes in the subtree rooted at node `k` of `nodes`.
+#[cfg(any(feature = "rayon", test))]
+fn stree_subtree_nodes<const B: usize>(k: usize, nodes: usize) -> usize {
+    // The subtree's nodes on each level are a contiguous range.
+    let (mut count, mut first, mut last) = (0, k, k);
+    while first < nodes {
+        count += last.min(nodes - 1) - first + 1;
+        first = first * (B + 1) + 1;
+        last = last * (B + 1) + B + 1;
+    }
+    count
+}
+
+/// Returns the number of keys before the subtree rooted at node `k` of `nodes`, in order.
+#[cfg(any(feature = "rayon", test))]
+fn stree_start<const B: usize>(k: usize, nodes: usize) -> usize {
+    if k == 0 {
+        return 0;
+    }
+    let (parent, child) = ((k - 1) / (B + 1), (k - 1) % (B + 1));
+    let left: usize = (0..child)
+        .map(|i| B * stree_subtree_nodes::<B>(parent * (B + 1) + i + 1, nodes))
+        .sum();
+    stree_start::<B>(parent, nodes) + left + child
+}
+
+#[cfg(test)]
+mod test {
+    use super::{eytzinger_rank, stree_start, stree_subtree_nodes, Eytzinger, STree};
+
+    /// Sizes around the powers of two and of `B + 1`, where the last level changes shape.
+    fn sizes() -> impl Iterator<Item = usize> {
+        if cfg!(miri) {
+            0..20
+        } else {
+            0..300
+        }
+    }
+
+    #[test]
+    fn test_eytzinger_layout() {
+        let sorted: Vec<u32> = (0..10).collect();
+        let eytzinger = Eytzinger::from_sorted(&sorted);
+        assert_eq!(eytzinger.as_slice(), &[6, 3, 8, 1, 5, 7, 9, 0, 2, 4]);
+        assert!(Eytzinger::<u32>::from_sorted(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_eytzinger_rank() {
+        for len in sizes() {
+            let sorted: Vec<usize> = (0..len).collect();
+            let eytzinger = Eytzinger::from_sorted(&sorted);
+            for (k, &rank) in eytzinger.as_slice().iter().enumerate() {
+                assert_eq!(eytzinger_rank(k, len), rank, "node {k} of {len}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_eytzinger_lower_bound() {
+        for len in sizes() {
+            let sorted: Vec<u32> = (0..len as u32).map(|i| i * 2).collect();
+            let eytzinger = Eytzinger::from_sorted(&sorted);
+            for x in 0..=len as u32 * 2 + 1 {
+                let expected = sorted.get(sorted.partition_point(|&e| e < x));
+                assert_eq!(eytzinger.lower_bound(&x), expected, "{x} in {len}");
+                assert_eq!(eytzinger.contains(&x), x % 2 == 0 && x < len as u32 * 2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_stree_layout() {
+        let sorted: Vec<u32> = (0..7).collect();
+        let stree: STree<_, 2> = STree::from_sorted(&sorted);
+        // The root, its first two children, and the first child of its first child; the last
+        // key in order is padding.
+        assert_eq!(stree.as_slice(), &[2, 5, 0, 1, 3, 4, 6, 6]);
+        assert_eq!(stree.len(), 7);
+        assert!(STree::<u32>::from_sorted(&[]).as_slice().is_empty());
+    }
+
+    #[test]
+    fn test_stree_start() {
+        fn check<const B: usize>() {
+            for len in sizes() {
+                let sorted: Vec<usize> = (0..len).collect();
+                let stree: STree<_, B> = STree::from_sorted(&sorted);
+                let nodes = len.div_ceil(B);
+                for (k, node) in stree.as_slice().chunks(B).enumerate() {
+                    let before = stree_start::<B>(k, nodes)
+                        + B * stree_subtree_nodes::<B>(k * (B + 1) + 1, nodes);
+                    assert_eq!(node[0], before.min(len - 1), "node {k} of {len}");
+                }
+            }
+        }
+        check::<1>();
+        check::<2>();
+        check::<3>();
+        check::<16>();
+    }
+
+    #[test]
+    fn test_stree_lower_bound() {
+        fn check<const B: usize>() {
+            for len in sizes() {
+                let sorted: Vec<u32> = (0..len as u32).map(|i| i * 2).collect();
+                let stree: STree<_, B> = STree::from_sorted(&sorted);
+                for x in 0..=len as u32 * 2 + 1 {
+                    let expected = sorted.get(sorted.partition_point(|&e| e < x));
+                    assert_eq!(stree.lower_bound(&x), expected, "{x} in {len}");
+                    assert_eq!(stree.contains(&x), x % 2 == 0 && x < len as u32 * 2);
+                }
+            }
+        }
+        check::<1>();
+        check::<2>();
+        check::<3>();
+        check::<16>();
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let sorted = [1, 2, 2, 2, 2, 3, 3, 5];
+        let eytzinger = Eytzinger::from_sorted(&sorted);
+        let stree: STree<_, 3> = STree::from_sorted(&sorted);
+        for x in 0..7 {
+            let expected = sorted.get(sorted.partition_point(|&e| e < x));
+            assert_eq!(eytzinger.lower_bound(&x), expected);
+            assert_eq!(stree.lower_bound(&x), expected);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn test_parallel_matches_sequential() {
+        // Strings, so that Miri checks each slot is written once and dropped once.
+        for len in sizes() {
+            let sorted: Vec<String> = (0..len).map(|i| format!("{i:04}")).collect();
+            assert_eq!(
+                Eytzinger::par_from_sorted(&sorted),
+                Eytzinger::from_sorted(&sorted)
+            );
+            assert_eq!(
+                STree::<_, 1>::par_from_sorted(&sorted),
+                STree::from_sorted(&sorted)
+            );
+            assert_eq!(
+                STree::<_, 3>::par_from_sorted(&sorted),
+                STree::from_sorted(&sorted)
+            );
+            assert_eq!(
+                STree::<_, 16>::par_from_sorted(&sorted),
+                STree::from_sorted(&sorted)
+            );
+        }
+    }
+}
@@ -0,0 +1,437 @@
+//! Piecewise-linear interpolation between sorted breakpoints, such as calibration curves and
+//! gamma ramps.
+//!
+//! A [`LerpTable`] holds strictly increasing `x` values and their `y` values. Between two
+//! neighbouring breakpoints it interpolates linearly, and outside them it does what its
+//! [`OutOfRange`] policy says.
+//!
+//! ```
+//! use shar_search::lerp::{LerpTable, OutOfRange};
+//!
+//! // Sensor readings against the temperatures they were calibrated at.
+//! let readings = [0.0, 0.25, 1.0];
+//! let celsius = [-20.0, 25.0, 100.0];
+//! let table = LerpTable::new(&readings, &celsius, OutOfRange::Clamp).unwrap();
+//!
+//! assert_eq!(table.eval(0.25), 25.0);
+//! assert_eq!(table.eval(0.625), 62.5);
+//! assert_eq!(table.eval(-0.5), -20.0);
+//! assert_eq!(table.eval(2.0), 100.0);
+//! ```
+
+use alloc::vec::Vec;
+use core::{cmp::Ordering, error::Error, fmt};
+
+use crate::{batch::DEFAULT_INTERLEAVE, gallop::gallop, SharBatchSearch, SharBinarySearch};
+
+/// What a [`LerpTable`] does with an `x` below its first breakpoint or above its last.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OutOfRange {
+    /// Return the `y` of the nearest breakpoint.
+    Clamp,
+    /// Continue the line through the two nearest breakpoints. A table with one breakpoint is
+    /// constant.
+    Extrapolate,
+    /// Fail with an [`OutOfRangeError`].
+    Error,
+}
+
+/// The error returned when the breakpoints given to [`LerpTable::new`] are invalid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LerpError {
+    /// There are no breakpoints.
+    Empty,
+    /// The `x` and `y` slices have different lengths.
+    LengthMismatch {
+        /// The number of `x` values.
+        x: usize,
+        /// The number of `y` values.
+        y: usize,
+    },
+    /// The `x` value at this index is infinite or NaN.
+    NonFinite(usize),
+    /// The `x` value at this index is not greater than its predecessor.
+    NotIncreasing(usize),
+}
+
+impl fmt::Display for LerpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LerpError::Empty => write!(f, "table has no breakpoints"),
+            LerpError::LengthMismatch { x, y } => {
+                write!(f, "table has {x} x values but {y} y values")
+            }
+            LerpError::NonFinite(index) => write!(f, "x value at index {index} is not finite"),
+            LerpError::NotIncreasing(index) => {
+                write!(
+                    f,
+                    "x value at index {index} is not greater than its predecessor"
+                )
+            }
+        }
+    }
+}
+
+impl Error for LerpError {}
+
+/// The error returned when evaluating a [`LerpTable`] with the [`OutOfRange::Error`] policy
+/// outside its breakpoints.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OutOfRangeError {
+    x: f64,
+}
+
+impl OutOfRangeError {
+    /// Returns the `x` that was out of range.
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+}
+
+impl fmt::Display for OutOfRangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "x = {} is outside the table's breakpoints", self.x)
+    }
+}
+
+impl Error for OutOfRangeError {}
+
+/// A piecewise-linear function through strictly increasing breakpoints. See the
+/// [`lerp`](crate::lerp) module.
+///
+/// An `x` exactly on a breakpoint returns the stored `y` as is, without rounding through the
+/// interpolation, and neighbouring breakpoints with the same `y` give exactly that `y` between
+/// them. A NaN `x` evaluates to NaN whatever the policy.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LerpTable {
+    x: Vec<f64>,
+    y: Vec<f64>,
+    out_of_range: OutOfRange,
+}
+
+impl LerpTable {
+    /// Creates a table from parallel slices of `x` and `y` values, of [`f64`] or [`f32`], with
+    /// `out_of_range` saying what to do outside the breakpoints.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LerpError`] if the slices are empty or of different lengths, or if the `x`
+    /// values are not finite and strictly increasing.
+    pub fn new<F: Copy + Into<f64>>(
+        x: &[F],
+        y: &[F],
+        out_of_range: OutOfRange,
+    ) -> Result<Self, LerpError> {
+        if x.len() != y.len() {
+            return Err(LerpError::LengthMismatch {
+                x: x.len(),
+                y: y.len(),
+            });
+        }
+        if x.is_empty() {
+            return Err(LerpError::Empty);
+        }
+
+        let x: Vec<f64> = x.iter().map(|&v| v.into()).collect();
+        if let Some(index) = x.iter().position(|v| !v.is_finite()) {
+            return Err(LerpError::NonFinite(index));
+        }
+        if let Some(index) = x.windows(2).position(|pair| pair[0] >= pair[1]) {
+            return Err(LerpError::NotIncreasing(index + 1));
+        }
+
+        Ok(Self {
+            x,
+            y: y.iter().map(|&v| v.into()).collect(),
+            out_of_range,
+        })
+    }
+
+    /// Returns the `x` values of the breakpoints, in increasing order.
+    pub fn x(&self) -> &[f64] {
+        &self.x
+    }
+
+    /// Returns the `y` values of the breakpoints, in the same order as [`x`](LerpTable::x).
+    pub fn y(&self) -> &[f64] {
+        &self.y
+    }
+
+    /// Returns the policy for values outside the breakpoints.
+    pub fn out_of_range(&self) -> OutOfRange {
+        self.out_of_range
+    }
+
+    /// Returns the value at `x`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the policy is [`OutOfRange::Error`] and `x` is outside the breakpoints. Use
+    /// [`LerpTable::try_eval`] to handle this case.
+    pub fn eval(&self, x: f64) -> f64 {
+        match self.try_eval(x) {
+            Ok(y) => y,
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    /// Returns the value at `x`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OutOfRangeError`] if the policy is [`OutOfRange::Error`] and `x` is outside
+    /// the breakpoints. The other policies never fail.
+    ///
+    /// ```
+    /// use shar_search::lerp::{LerpTable, OutOfRange};
+    ///
+    /// let table = LerpTable::new(&[0.0_f32, 1.0], &[0.0, 255.0], OutOfRange::Error).unwrap();
+    /// assert_eq!(table.try_eval(0.2), Ok(51.0));
+    /// assert_eq!(table.try_eval(1.5).unwrap_err().x(), 1.5);
+    /// ```
+    pub fn try_eval(&self, x: f64) -> Result<f64, OutOfRangeError> {
+        self.eval_at(self.x.bl_partition_point(|&v| v <= x), x)
+    }
+
+    /// Returns the value at each of `xs`, in the same order, interleaving the searches as
+    /// [`bl_binary_search_batch`](SharBatchSearch::bl_binary_search_batch) does. The values can
+    /// be in any order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the policy is [`OutOfRange::Error`] and any of `xs` is outside the breakpoints.
+    pub fn eval_many(&self, xs: &[f64]) -> Vec<f64> {
+        // Never reporting equality makes each search return the partition point of `v <= x`.
+        let compare = |v: &f64, x: &f64| {
+            if v <= x {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            }
+        };
+
+        self.x
+            .bl_binary_search_batch_by::<DEFAULT_INTERLEAVE, _, _>(xs, compare)
+            .into_iter()
+            .zip(xs)
+            .map(|(result, &x)| self.unwrap_at(result.unwrap_or_else(|index| index), x))
+            .collect()
+    }
+
+    /// Returns the value at each of `sorted_xs`, in the same order. Each search gallops forward
+    /// from the previous one, so evaluating `q` values costs `O(q log(n / q))` comparisons.
+    /// Note it is assumed that the values are sorted and hold no NaNs; otherwise the results
+    /// are unspecified.
+    ///
+    /// In debug builds, this panics if `sorted_xs` is not sorted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the policy is [`OutOfRange::Error`] and any of `sorted_xs` is outside the
+    /// breakpoints.
+    ///
+    /// ```
+    /// use shar_search::lerp::{LerpTable, OutOfRange};
+    ///
+    /// let gamma = LerpTable::new(&[0.0, 0.5, 1.0], &[0.0, 0.25, 1.0], OutOfRange::Clamp).unwrap();
+    /// let ramp: Vec<f64> = (0..=4).map(|i| f64::from(i) / 4.0).collect();
+    /// assert_eq!(gamma.eval_sorted(&ramp), [0.0, 0.125, 0.25, 0.625, 1.0]);
+    /// ```
+    pub fn eval_sorted(&self, sorted_xs: &[f64]) -> Vec<f64> {
+        debug_assert!(sorted_xs.is_sorted(), "the values must be sorted");
+
+        // Everything before `start` is at most the previous value, and so at most this one.
+        let mut start = 0;
+        sorted_xs
+            .iter()
+            .map(|&x| {
+                start += gallop(&self.x[start..], |&v| v <= x);
+                self.unwrap_at(start, x)
+            })
+            .collect()
+    }
+
+    fn unwrap_at(&self, index: usize, x: f64) -> f64 {
+        match self.eval_at(index, x) {
+            Ok(y) => y,
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    /// Returns the value at `x`, where `index` is the number of breakpoints at most `x`.
+    fn eval_at(&self, index: usize, x: f64) -> Result<f64, OutOfRangeError> {
+        let (xs, ys) = (&self.x, &self.y);
+        let last = xs.len() - 1;
+
+        if x.is_nan() {
+            return Ok(f64::NAN);
+        }
+        // `x` is on a breakpoint, or between it and the next.
+        let segment = match index.checked_sub(1) {
+            Some(i) if xs[i] == x => return Ok(ys[i]),
+            Some(i) if i < last => i,
+            _ => match self.out_of_range {
+                OutOfRange::Error => return Err(OutOfRangeError { x }),
+                OutOfRange::Clamp => return Ok(if index == 0 { ys[0] } else { ys[last] }),
+                OutOfRange::Extrapolate if last == 0 => return Ok(ys[0]),
+                OutOfRange::Extrapolate => index.clamp(1, last) - 1,
+            },
+        };
+
+        let (x0, x1, y0, y1) = (xs[segment], xs[segment + 1], ys[segment], ys[segment + 1]);
+        if y0 == y1 {
+            return Ok(y0);
+        }
+        Ok(y0 + (y1 - y0) * ((x - x0) / (x1 - x0)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LerpError, LerpTable, OutOfRange};
+    use crate::reference::{self, assert_matches_reference, inputs};
+
+    const POLICIES: [OutOfRange; 3] = [
+        OutOfRange::Clamp,
+        OutOfRange::Extrapolate,
+        OutOfRange::Error,
+    ];
+
+    #[test]
+    fn test_two_points() {
+        let clamp = LerpTable::new(&[1.0, 3.0], &[10.0, 20.0], OutOfRange::Clamp).unwrap();
+        let extrapolate =
+            LerpTable::new(&[1.0, 3.0], &[10.0, 20.0], OutOfRange::Extrapolate).unwrap();
+        let error = LerpTable::new(&[1.0, 3.0], &[10.0, 20.0], OutOfRange::Error).unwrap();
+
+        for table in [&clamp, &extrapolate, &error] {
+            assert_eq!(table.eval(1.0), 10.0);
+            assert_eq!(table.eval(2.0), 15.0);
+            assert_eq!(table.eval(3.0), 20.0);
+            assert!(table.eval(f64::NAN).is_nan());
+        }
+        assert_eq!(clamp.eval(0.0), 10.0);
+        assert_eq!(clamp.eval(f64::INFINITY), 20.0);
+        assert_eq!(extrapolate.eval(0.0), 5.0);
+        assert_eq!(extrapolate.eval(5.0), 30.0);
+        assert_eq!(error.try_eval(0.5).unwrap_err().x(), 0.5);
+        assert!(error.try_eval(3.5).is_err());
+        assert_eq!(
+            error.try_eval(f64::NEG_INFINITY).unwrap_err().x(),
+            f64::NEG_INFINITY
+        );
+    }
+
+    #[test]
+    fn test_single_point() {
+        for policy in [OutOfRange::Clamp, OutOfRange::Extrapolate] {
+            let table = LerpTable::new(&[2.0], &[7.0], policy).unwrap();
+            assert_eq!(table.eval_many(&[-1.0, 2.0, 9.0]), [7.0, 7.0, 7.0]);
+        }
+        let table = LerpTable::new(&[2.0], &[7.0], OutOfRange::Error).unwrap();
+        assert_eq!(table.try_eval(2.0), Ok(7.0));
+        assert!(table.try_eval(2.5).is_err());
+    }
+
+    #[test]
+    fn test_plateaus() {
+        let y = [0.1, 0.7, 0.7, 0.7, 0.3];
+        let table = LerpTable::new(&[0.0, 1.0, 2.0, 3.0, 4.0], &y, OutOfRange::Clamp).unwrap();
+        for x in [1.0, 1.1, 1.5, 2.0, 2.999, 3.0] {
+            assert_eq!(table.eval(x), 0.7);
+        }
+        assert_eq!(table.eval_sorted(&[1.1, 2.5, 2.9]), [0.7, 0.7, 0.7]);
+    }
+
+    #[test]
+    fn test_exact_hits_do_not_drift() {
+        // Values whose interpolation from either neighbour would round.
+        let x = [0.1, 0.3, 0.7, 1.3, 1e9];
+        let y = [0.1 + 0.2, 1.0 / 3.0, std::f64::consts::PI, 1e-300, -7.7];
+        let table = LerpTable::new(&x, &y, OutOfRange::Error).unwrap();
+        for (xi, yi) in x.iter().zip(&y) {
+            assert_eq!(table.eval(*xi).to_bits(), yi.to_bits());
+        }
+        let expected: Vec<u64> = y.iter().map(|v| v.to_bits()).collect();
+        let bits = |values: Vec<f64>| values.iter().map(|v| v.to_bits()).collect::<Vec<_>>();
+        assert_eq!(bits(table.eval_many(&x)), expected);
+        assert_eq!(bits(table.eval_sorted(&x)), expected);
+    }
+
+    #[test]
+    fn test_f32_input() {
+        let table = LerpTable::new(&[0.0_f32, 0.1], &[0.2, 0.4], OutOfRange::Clamp).unwrap();
+        assert_eq!(table.eval(f64::from(0.1_f32)), f64::from(0.4_f32));
+    }
+
+    #[test]
+    fn test_invalid_tables() {
+        let new = |x: &[f64], y: &[f64]| LerpTable::new(x, y, OutOfRange::Clamp).unwrap_err();
+        assert_eq!(new(&[], &[]), LerpError::Empty);
+        assert_eq!(
+            new(&[1.0, 2.0], &[1.0]),
+            LerpError::LengthMismatch { x: 2, y: 1 }
+        );
+        assert_eq!(new(&[1.0, 1.0], &[1.0, 2.0]), LerpError::NotIncreasing(1));
+        assert_eq!(
+            new(&[1.0, 3.0, 2.0], &[0.0; 3]),
+            LerpError::NotIncreasing(2)
+        );
+        assert_eq!(new(&[1.0, f64::NAN], &[0.0; 2]), LerpError::NonFinite(1));
+        assert_eq!(
+            new(&[f64::NEG_INFINITY, 0.0], &[0.0; 2]),
+            LerpError::NonFinite(0)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "x = 4 is outside the table's breakpoints")]
+    fn test_eval_panics_out_of_range() {
+        let table = LerpTable::new(&[1.0, 3.0], &[10.0, 20.0], OutOfRange::Error).unwrap();
+        table.eval_many(&[2.0, 4.0]);
+    }
+
+    #[test]
+    fn test_uneven_spacing_against_reference() {
+        let cases = inputs::curves(184).flat_map(|(x, y, queries)| {
+            POLICIES.map(|policy| (x.clone(), y.clone(), queries.clone(), policy))
+        });
+        let sorted = |queries: &[f64]| {
+            let mut sorted = queries.to_vec();
+            sorted.sort_by(f64::total_cmp);
+            sorted
+        };
+
+        // The panicking evaluations are only compared for the policies that never fail.
+        assert_matches_reference!(
+            for (x, y, queries, policy) in cases =>
+            {
+                let table = LerpTable::new(&x, &y, policy).unwrap();
+                let try_eval = |q: &[f64]| -> Vec<_> {
+                    q.iter().map(|&q| table.try_eval(q).ok()).collect()
+                };
+                let sorted = sorted(&queries);
+                (
+                    try_eval(&queries),
+                    try_eval(&sorted),
+                    (policy != OutOfRange::Error)
+                        .then(|| (table.eval_many(&queries), table.eval_sorted(&sorted))),
+                )
+            },
+            {
+                let lerp = |q: &[f64]| -> Vec<_> {
+                    q.iter().map(|&q| reference::lerp(&x, &y, policy, q)).collect()
+                };
+                let sorted = sorted(&queries);
+                (
+                    lerp(&queries),
+                    lerp(&sorted),
+                    (policy != OutOfRange::Error).then(|| {
+                        let unwrap = |q: &[f64]| lerp(q).into_iter().map(Option::unwrap).collect();
+                        (unwrap(&queries), unwrap(&sorted))
+                    }),
+                )
+            },
+        );
+    }
+}
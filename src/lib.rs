@@ -2,11 +2,197 @@
 //! [Beautiful Branchless Binary Search](https://probablydance.com/2023/04/27/beautiful-branchless-binary-search/) and
 //! [Beautiful Binary Search in D](https://muscar.eu/shar-binary-search-meta.html).
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![deny(missing_docs)]
 
-use std::cmp::Ordering;
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use core::{
+    cmp::Ordering,
+    error::Error,
+    fmt,
+    ops::{Bound, Range, RangeBounds},
+};
+
+use runs::Runs;
+
+#[cfg(feature = "alloc")]
+pub mod align;
+pub mod approx;
+pub mod arena;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "tokio")]
+pub mod async_io;
+pub mod auto;
+pub mod batch;
+#[cfg(feature = "alloc")]
+pub mod bins;
+pub mod bisect;
+mod bitonic;
+pub mod caseless;
+#[cfg(feature = "icu")]
+pub mod collated;
+pub mod columns;
+#[cfg(feature = "alloc")]
+pub mod compacting;
+pub mod comparators;
+pub mod compressed;
+pub mod const_search;
+pub mod cursor;
+pub mod diff;
+pub mod duplicates;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fixed;
+mod gallop;
+#[cfg(target_has_atomic = "ptr")]
+pub mod hinted;
+pub mod index;
+pub mod indexed;
+#[cfg(feature = "io")]
+pub mod io;
+pub mod join;
+#[cfg(feature = "alloc")]
+pub mod keyframes;
+#[cfg(feature = "alloc")]
+pub mod lerp;
+#[cfg(feature = "alloc")]
+pub mod layout;
+#[cfg(feature = "alloc")]
+pub mod lpm;
+#[cfg(feature = "alloc")]
+pub mod map;
+pub mod matrix;
+pub mod merge;
+pub mod multi;
+#[cfg(feature = "alloc")]
+pub mod multimap;
+pub mod neighbors;
+pub mod packed;
+#[cfg(feature = "alloc")]
+pub mod partition;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod quantiles;
+mod quaternary;
+#[cfg(feature = "alloc")]
+pub mod range_map;
+#[cfg(feature = "alloc")]
+pub mod ranked;
+#[cfg(feature = "alloc")]
+mod raw;
+#[cfg(test)]
+mod reference;
+pub mod result;
+#[cfg(feature = "alloc")]
+pub mod rle;
+mod rotated;
+#[cfg(feature = "alloc")]
+pub mod router;
+pub mod runs;
+pub mod searcher;
+pub mod select;
+#[cfg(feature = "alloc")]
+pub mod set;
+#[cfg(feature = "simd")]
+pub mod simd;
+#[cfg(feature = "smallvec")]
+pub mod small;
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+pub mod sorted_arc;
+#[cfg(feature = "alloc")]
+pub mod sorted_log;
+pub mod sorted_ops;
+#[cfg(feature = "alloc")]
+pub mod sorted_vec;
+#[cfg(feature = "alloc")]
+pub mod srcmap;
+#[cfg(feature = "alloc")]
+pub mod staged;
+#[cfg(feature = "alloc")]
+pub mod stats;
+#[cfg(feature = "alloc")]
+pub mod step;
+#[cfg(feature = "alloc")]
+pub mod stream;
+pub mod table;
+#[cfg(test)]
+mod test_util;
+#[cfg(any(feature = "chrono", feature = "time"))]
+pub mod timestamps;
+#[cfg(feature = "trace")]
+pub mod trace;
+pub mod tuple;
+pub mod unbounded;
+#[cfg(feature = "uuid")]
+pub mod uuids;
+pub mod validate;
+#[cfg(feature = "alloc")]
+pub mod vec_ext;
+#[cfg(feature = "semver")]
+pub mod versions;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod weights;
+pub mod zonemap;
+
+#[cfg(feature = "arbitrary")]
+pub mod fuzzing;
+#[cfg(feature = "ordered-float")]
+pub mod ordered;
+#[cfg(all(feature = "serde", feature = "alloc"))]
+pub mod serialization;
+
+pub use batch::SharBatchSearch;
+pub use cursor::SearchCursor;
+pub use duplicates::{DuplicateError, DuplicatePolicy};
+#[cfg(feature = "alloc")]
+pub use map::SharMap;
+#[cfg(feature = "alloc")]
+pub use multimap::SharMultiMap;
+pub use neighbors::Neighbors;
+#[cfg(feature = "alloc")]
+pub use range_map::SharRangeMap;
+pub use result::SearchResultExt;
+pub use searcher::Searcher;
+#[cfg(feature = "alloc")]
+pub use set::SharSet;
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+pub use sorted_arc::SortedArc;
+#[cfg(feature = "alloc")]
+pub use sorted_log::SortedLog;
+#[cfg(feature = "alloc")]
+pub use sorted_vec::SortedVec;
+#[cfg(feature = "alloc")]
+pub use staged::StagedSortedVec;
+pub use tuple::SharTupleSearch;
+#[cfg(feature = "alloc")]
+pub use vec_ext::VecSortedExt;
 
 /// Trait for using Shar's binary search.
+///
+/// This is implemented for slices, and for [`Vec`], [`Box<[T]>`](Box), [`Rc<[T]>`](alloc::rc::Rc),
+/// [`Arc<[T]>`](alloc::sync::Arc) and [`Cow<[T]>`](alloc::borrow::Cow) by forwarding to their
+/// slice, so that these containers can be passed where the trait is a bound:
+///
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use std::sync::Arc;
+///
+/// use shar_search::SharBinarySearch;
+///
+/// fn rank<S: SharBinarySearch<u64> + ?Sized>(sorted: &S, x: u64) -> usize {
+///     sorted.bl_lower_bound(&x)
+/// }
+///
+/// let table: Arc<[u64]> = Arc::from([2, 4, 8, 16]);
+/// assert_eq!(rank(&table, 5), 2);
+/// assert_eq!(rank(&vec![1, 2, 3], 3), 2);
+/// # }
+/// ```
 pub trait SharBinarySearch<T> {
     /// Binary searches this slice with a comparator function. Note it is assumed that the slice it is sorted.
     ///
@@ -33,6 +219,31 @@ pub trait SharBinarySearch<T> {
         self.bl_binary_search_by(|p| p.cmp(x))
     }
 
+    /// Searches this slice for a given element like
+    /// [`bl_binary_search`](SharBinarySearch::bl_binary_search), but quartering the slice at each
+    /// level instead of halving it. Note it is assumed that the slice is sorted.
+    ///
+    /// Each level compares the key to three pivots, whose loads don't depend on each other, so
+    /// the CPU can issue them together. This takes half as many levels of dependent loads as
+    /// the binary search, but half again as many comparisons. In the `quaternary` benchmark,
+    /// searching `u32`s, it was faster at every size, from about 4× on 16 KiB to 1.5× on
+    /// 512 MiB, but with elements that are expensive to compare, the extra comparisons may
+    /// cost more than they save.
+    ///
+    /// As with `bl_binary_search`, if there are multiple matches, the *first* is returned.
+    ///
+    /// ```
+    /// use shar_search::SharBinarySearch;
+    ///
+    /// let slice = [1, 3, 3, 3, 5, 8, 13];
+    /// assert_eq!(slice.bl_binary_search_4ary(&3), Ok(1));
+    /// assert_eq!(slice.bl_binary_search_4ary(&4), Err(4));
+    /// assert_eq!(slice.bl_binary_search_4ary(&21), Err(7));
+    /// ```
+    fn bl_binary_search_4ary(&self, x: &T) -> Result<usize, usize>
+    where
+        T: Ord;
+
     /// Binary searches this slice with a key extraction function. Note it is assumed that the slice it is sorted.
     ///
     /// Note that if there are multiple matches, then the *first*
@@ -48,76 +259,820 @@ pub trait SharBinarySearch<T> {
     {
         self.bl_binary_search_by(|k| f(k).cmp(b))
     }
+
+    /// Binary searches this slice with a comparator function like
+    /// [`bl_binary_search_by`](SharBinarySearch::bl_binary_search_by), but first compares the
+    /// key to the first and last elements, to return at once for keys outside of the slice's
+    /// range. Note it is assumed that the slice is sorted.
+    ///
+    /// A key less than the first element returns `Err(0)`, one equal to it `Ok(0)`, and one
+    /// greater than the last element `Err(len)`. A key equal to the last element still runs the
+    /// full search, so that the *first* of a run of equal elements at the end is returned.
+    ///
+    /// This helps when most keys are out of range, such as keys routed to the wrong shard: those
+    /// take two comparisons instead of a full descent. When most keys are in range, it hurts,
+    /// adding two comparisons (and two branches, which mispredict when in-range and
+    /// out-of-range keys are mixed unpredictably) to every search.
+    fn bl_binary_search_bounded_by<'a, F>(&'a self, f: F) -> Result<usize, usize>
+    where
+        T: 'a,
+        F: FnMut(&'a T) -> Ordering;
+
+    /// Binary searches this slice for a given element, first checking it against the first and
+    /// last elements. See
+    /// [`bl_binary_search_bounded_by`](SharBinarySearch::bl_binary_search_bounded_by).
+    ///
+    /// ```
+    /// use shar_search::SharBinarySearch;
+    ///
+    /// let slice = [10, 20, 20, 30, 30];
+    /// assert_eq!(slice.bl_binary_search_bounded(&5), Err(0));
+    /// assert_eq!(slice.bl_binary_search_bounded(&10), Ok(0));
+    /// assert_eq!(slice.bl_binary_search_bounded(&30), Ok(3));
+    /// assert_eq!(slice.bl_binary_search_bounded(&99), Err(5));
+    /// ```
+    #[inline]
+    fn bl_binary_search_bounded(&self, x: &T) -> Result<usize, usize>
+    where
+        T: Ord,
+    {
+        self.bl_binary_search_bounded_by(|p| p.cmp(x))
+    }
+
+    /// Binary searches this slice with a key extraction function, first checking the key
+    /// against the first and last elements. See
+    /// [`bl_binary_search_bounded_by`](SharBinarySearch::bl_binary_search_bounded_by).
+    #[inline]
+    fn bl_binary_search_bounded_by_key<'a, B, F>(&'a self, b: &B, mut f: F) -> Result<usize, usize>
+    where
+        T: 'a,
+        F: FnMut(&'a T) -> B,
+        B: Ord,
+    {
+        self.bl_binary_search_bounded_by(|k| f(k).cmp(b))
+    }
+
+    /// Returns the index of the partition point according to the given predicate (the index
+    /// of the first element of the second partition). Note it is assumed that the slice is
+    /// partitioned, i.e. all elements for which `pred` returns `true` come first.
+    #[inline]
+    fn bl_partition_point<'a, P>(&'a self, mut pred: P) -> usize
+    where
+        T: 'a,
+        P: FnMut(&'a T) -> bool,
+    {
+        match self.bl_binary_search_by(|p| {
+            if pred(p) {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            }
+        }) {
+            Ok(index) | Err(index) => index,
+        }
+    }
+
+    /// Returns the index of the first element that is not less than `x` using a comparator
+    /// function, where `f` returns the ordering of an element relative to the target.
+    /// Note it is assumed that the slice it is sorted.
+    #[inline]
+    fn bl_lower_bound_by<'a, F>(&'a self, mut f: F) -> usize
+    where
+        T: 'a,
+        F: FnMut(&'a T) -> Ordering,
+    {
+        self.bl_partition_point(|p| f(p).is_lt())
+    }
+
+    /// Returns the index of the first element that is greater than `x` using a comparator
+    /// function, where `f` returns the ordering of an element relative to the target.
+    /// Note it is assumed that the slice it is sorted.
+    #[inline]
+    fn bl_upper_bound_by<'a, F>(&'a self, mut f: F) -> usize
+    where
+        T: 'a,
+        F: FnMut(&'a T) -> Ordering,
+    {
+        self.bl_partition_point(|p| f(p).is_le())
+    }
+
+    /// Returns the index of the first element that is not less than `x`. Note it is assumed
+    /// that the slice it is sorted.
+    #[inline]
+    fn bl_lower_bound(&self, x: &T) -> usize
+    where
+        T: Ord,
+    {
+        self.bl_lower_bound_by(|p| p.cmp(x))
+    }
+
+    /// Returns the index of the first element that is greater than `x`. Note it is assumed
+    /// that the slice it is sorted.
+    #[inline]
+    fn bl_upper_bound(&self, x: &T) -> usize
+    where
+        T: Ord,
+    {
+        self.bl_upper_bound_by(|p| p.cmp(x))
+    }
+
+    /// Returns the lower bound for `b` with a key extraction function. Note it is assumed that
+    /// the slice it is sorted.
+    #[inline]
+    fn bl_lower_bound_by_key<'a, B, F>(&'a self, b: &B, mut f: F) -> usize
+    where
+        T: 'a,
+        F: FnMut(&'a T) -> B,
+        B: Ord,
+    {
+        self.bl_lower_bound_by(|k| f(k).cmp(b))
+    }
+
+    /// Returns the upper bound for `b` with a key extraction function. Note it is assumed that
+    /// the slice it is sorted.
+    #[inline]
+    fn bl_upper_bound_by_key<'a, B, F>(&'a self, b: &B, mut f: F) -> usize
+    where
+        T: 'a,
+        F: FnMut(&'a T) -> B,
+        B: Ord,
+    {
+        self.bl_upper_bound_by(|k| f(k).cmp(b))
+    }
+
+    /// Returns the range of indices of all elements equal to `x`, using a comparator function.
+    /// The range is empty (and starts at the insertion point) if there are no matches. Note it
+    /// is assumed that the slice it is sorted.
+    #[inline]
+    fn bl_equal_range_by<'a, F>(&'a self, mut f: F) -> Range<usize>
+    where
+        T: 'a,
+        F: FnMut(&'a T) -> Ordering,
+    {
+        let start = self.bl_lower_bound_by(&mut f);
+        let end = self.bl_upper_bound_by(f);
+
+        start..end
+    }
+
+    /// Returns the range of indices of all elements equal to `x`. The range is empty (and
+    /// starts at the insertion point) if there are no matches. Note it is assumed that the
+    /// slice it is sorted.
+    #[inline]
+    fn bl_equal_range(&self, x: &T) -> Range<usize>
+    where
+        T: Ord,
+    {
+        self.bl_equal_range_by(|p| p.cmp(x))
+    }
+
+    /// Returns the range of indices of all elements whose key equals `b`, using a key
+    /// extraction function. Note it is assumed that the slice it is sorted.
+    #[inline]
+    fn bl_equal_range_by_key<'a, B, F>(&'a self, b: &B, mut f: F) -> Range<usize>
+    where
+        T: 'a,
+        F: FnMut(&'a T) -> B,
+        B: Ord,
+    {
+        self.bl_equal_range_by(|k| f(k).cmp(b))
+    }
+
+    /// Binary searches this slice with a comparator function, returning the match and its index
+    /// if there is one, or the insertion point and the elements either side of it if not. See
+    /// [`bl_search_or_neighbors`](SharBinarySearch::bl_search_or_neighbors).
+    fn bl_search_or_neighbors_by<'a, F>(&'a self, f: F) -> Result<(usize, &'a T), Neighbors<'a, T>>
+    where
+        T: 'a,
+        F: FnMut(&'a T) -> Ordering;
+
+    /// Binary searches this slice for a given element, returning the match and its index if
+    /// there is one, or the insertion point and the elements either side of it if not. Note it
+    /// is assumed that the slice is sorted.
+    ///
+    /// As with [`bl_binary_search`](SharBinarySearch::bl_binary_search), if there are multiple
+    /// matches, the *first* is returned. The neighbors are read from the insertion point, without
+    /// searching again.
+    ///
+    /// ```
+    /// use shar_search::SharBinarySearch;
+    ///
+    /// let slice = [10, 20, 30];
+    /// assert_eq!(slice.bl_search_or_neighbors(&20).ok(), Some((1, &20)));
+    ///
+    /// let missed = slice.bl_search_or_neighbors(&25).unwrap_err();
+    /// assert_eq!(missed.insertion, 2);
+    /// assert_eq!((missed.before, missed.after), (Some(&20), Some(&30)));
+    /// ```
+    #[inline]
+    fn bl_search_or_neighbors<'a>(&'a self, x: &T) -> Result<(usize, &'a T), Neighbors<'a, T>>
+    where
+        T: Ord + 'a,
+    {
+        self.bl_search_or_neighbors_by(|p| p.cmp(x))
+    }
+
+    /// Binary searches this slice for `b` with a key extraction function, returning the match
+    /// and its index if there is one, or the insertion point and the elements either side of it
+    /// if not. See [`bl_search_or_neighbors`](SharBinarySearch::bl_search_or_neighbors).
+    #[inline]
+    fn bl_search_or_neighbors_by_key<'a, B, F>(
+        &'a self,
+        b: &B,
+        mut f: F,
+    ) -> Result<(usize, &'a T), Neighbors<'a, T>>
+    where
+        T: 'a,
+        F: FnMut(&'a T) -> B,
+        B: Ord,
+    {
+        self.bl_search_or_neighbors_by(|k| f(k).cmp(b))
+    }
+
+    /// Returns an iterator over the runs of equal elements in this slice, yielding the start
+    /// index of each run together with the run. Note it is assumed that the slice is sorted.
+    ///
+    /// ```
+    /// use shar_search::SharBinarySearch;
+    ///
+    /// let slice = [1, 1, 1, 2, 4, 4];
+    /// let runs: Vec<_> = slice.bl_runs().collect();
+    /// assert_eq!(runs, [(0, &[1, 1, 1][..]), (3, &[2]), (4, &[4, 4])]);
+    /// ```
+    fn bl_runs(&self) -> Runs<'_, T, impl FnMut(&T, &T) -> bool>
+    where
+        T: Ord;
+
+    /// Returns an iterator over the runs of elements with equal keys in this slice, using a key
+    /// extraction function. Note it is assumed that the slice is sorted by the key. See
+    /// [`bl_runs`](SharBinarySearch::bl_runs).
+    fn bl_runs_by_key<B, F>(&self, f: F) -> Runs<'_, T, impl FnMut(&T, &T) -> bool>
+    where
+        F: FnMut(&T) -> B,
+        B: Ord;
+
+    /// Searches this slice for a given element with the algorithm that is usually fastest for
+    /// its size, as chosen by [`SearchTuning::DEFAULT`](auto::SearchTuning::DEFAULT): a linear
+    /// scan for short slices, and the branchless search otherwise. Note it is assumed that the
+    /// slice is sorted.
+    ///
+    /// The result is always the same as [`bl_binary_search`](SharBinarySearch::bl_binary_search),
+    /// so if there are multiple matches, the *first* is returned. See the [`auto`] module.
+    #[inline]
+    fn bl_search_auto(&self, x: &T) -> Result<usize, usize>
+    where
+        T: Ord,
+    {
+        self.bl_search_auto_with(x, &auto::SearchTuning::DEFAULT)
+    }
+
+    /// Searches this slice for a given element with the algorithm `tuning` chooses for its
+    /// size. See [`bl_search_auto`](SharBinarySearch::bl_search_auto).
+    fn bl_search_auto_with(&self, x: &T, tuning: &auto::SearchTuning) -> Result<usize, usize>
+    where
+        T: Ord;
+
+    /// Returns the rotation point of this rotated sorted slice: the index `p` where its sorted
+    /// order starts, so that `self[p..]` followed by `self[..p]` is sorted. This is 0 for an
+    /// unrotated or empty slice. A rotated sorted slice is a sorted slice whose elements have
+    /// been rotated left by some amount, such as `[7, 9, 12, 1, 3, 5]`.
+    ///
+    /// This takes `O(log n)` comparisons, comparing midpoints to the last element, unless the
+    /// first and last elements of the slice are equal. The rotation point cannot then be found
+    /// without looking at every element in general (as in `[2, 2, 1, 2, 2, 2]`), so this falls
+    /// back to a linear scan, and the result is still correct.
+    ///
+    /// ```
+    /// use shar_search::SharBinarySearch;
+    ///
+    /// let slice = [7, 9, 12, 1, 3, 5];
+    /// let pivot = slice.bl_rotation_point();
+    /// assert_eq!(pivot, 3);
+    ///
+    /// let (high, low) = slice.split_at(pivot);
+    /// assert_eq!(low.bl_binary_search(&3), Ok(1));
+    /// assert_eq!(high.bl_binary_search(&12), Ok(2));
+    /// ```
+    fn bl_rotation_point(&self) -> usize
+    where
+        T: Ord,
+    {
+        self.bl_rotation_point_by(T::cmp)
+    }
+
+    /// Returns the rotation point of this rotated sorted slice, using a comparator function
+    /// that orders the elements. See [`bl_rotation_point`](SharBinarySearch::bl_rotation_point).
+    fn bl_rotation_point_by<F>(&self, compare: F) -> usize
+    where
+        F: FnMut(&T, &T) -> Ordering;
+
+    /// Returns the rotation point of this rotated sorted slice, using a key extraction
+    /// function. See [`bl_rotation_point`](SharBinarySearch::bl_rotation_point).
+    fn bl_rotation_point_by_key<B, F>(&self, mut f: F) -> usize
+    where
+        F: FnMut(&T) -> B,
+        B: Ord,
+    {
+        self.bl_rotation_point_by(|x, y| f(x).cmp(&f(y)))
+    }
+
+    /// Binary searches this rotated sorted slice for a given element, after finding its
+    /// [rotation point](SharBinarySearch::bl_rotation_point). This takes `O(log n)` comparisons
+    /// unless the first and last elements of the slice are equal.
+    ///
+    /// If there are multiple matches, the first in sorted order is returned. If there is no
+    /// match, `Err` holds an index at which `x` could be inserted to keep the slice a rotated
+    /// sorted slice. An unrotated slice gives the same results as
+    /// [`bl_binary_search`](SharBinarySearch::bl_binary_search).
+    ///
+    /// ```
+    /// use shar_search::SharBinarySearch;
+    ///
+    /// let slice = [7, 9, 12, 1, 3, 5];
+    /// assert_eq!(slice.bl_binary_search_rotated(&9), Ok(1));
+    /// assert_eq!(slice.bl_binary_search_rotated(&3), Ok(4));
+    /// assert_eq!(slice.bl_binary_search_rotated(&4), Err(5));
+    /// ```
+    fn bl_binary_search_rotated(&self, x: &T) -> Result<usize, usize>
+    where
+        T: Ord,
+    {
+        self.bl_binary_search_rotated_by(x, T::cmp)
+    }
+
+    /// Binary searches this rotated sorted slice for a given element, using a comparator
+    /// function that orders the elements. See
+    /// [`bl_binary_search_rotated`](SharBinarySearch::bl_binary_search_rotated).
+    fn bl_binary_search_rotated_by<F>(&self, x: &T, compare: F) -> Result<usize, usize>
+    where
+        F: FnMut(&T, &T) -> Ordering;
+
+    /// Binary searches this rotated sorted slice for `b` with a key extraction function. See
+    /// [`bl_binary_search_rotated`](SharBinarySearch::bl_binary_search_rotated).
+    fn bl_binary_search_rotated_by_key<B, F>(&self, b: &B, f: F) -> Result<usize, usize>
+    where
+        F: FnMut(&T) -> B,
+        B: Ord;
+    /// Returns the index of the peak of this bitonic slice: its maximum, where it stops
+    /// increasing and starts decreasing, as in `[1, 4, 8, 5, 2]`. Either side of the peak may be
+    /// empty, so a strictly increasing slice peaks at its last element and a strictly
+    /// decreasing one at its first. Returns 0 for an empty slice.
+    ///
+    /// This takes `O(log n)` comparisons of neighbouring elements, which requires the slice to
+    /// be *strictly* bitonic. With equal neighbours, the maximum cannot be found without
+    /// looking at every element in general (as in `[1, 2, 2, 2, 3, 1]`), so the result is then
+    /// only a local maximum: an element greater than the one before it and not less than the
+    /// one after it. In particular, it is 0 for a slice of equal elements.
+    ///
+    /// ```
+    /// use shar_search::SharBinarySearch;
+    ///
+    /// let sweep = [1, 4, 8, 5, 2];
+    /// assert_eq!(sweep.bl_peak(), 2);
+    /// assert_eq!([1, 2, 3].bl_peak(), 2);
+    /// assert_eq!([3, 2, 1].bl_peak(), 0);
+    /// ```
+    fn bl_peak(&self) -> usize
+    where
+        T: Ord,
+    {
+        self.bl_peak_by(T::cmp)
+    }
+
+    /// Returns the index of the peak of this bitonic slice, using a comparator function that
+    /// orders the elements. See [`bl_peak`](SharBinarySearch::bl_peak).
+    fn bl_peak_by<F>(&self, compare: F) -> usize
+    where
+        F: FnMut(&T, &T) -> Ordering;
+
+    /// Returns the index of the peak of this bitonic slice, using a key extraction function.
+    /// See [`bl_peak`](SharBinarySearch::bl_peak).
+    fn bl_peak_by_key<B, F>(&self, mut f: F) -> usize
+    where
+        F: FnMut(&T) -> B,
+        B: Ord,
+    {
+        self.bl_peak_by(|x, y| f(x).cmp(&f(y)))
+    }
+
+    /// Searches this bitonic slice for a given element, after finding its
+    /// [peak](SharBinarySearch::bl_peak), by binary searching the increasing side up to and
+    /// including the peak, and then the decreasing side after it. This takes `O(log n)`
+    /// comparisons.
+    ///
+    /// Returns the index of a match, or `None` if there is none. If `x` is on both sides of the
+    /// peak, the match on the increasing side is returned, and within a side, the first.
+    ///
+    /// ```
+    /// use shar_search::SharBinarySearch;
+    ///
+    /// let sweep = [1, 4, 6, 9, 6, 2];
+    /// assert_eq!(sweep.bl_binary_search_bitonic(&9), Some(3));
+    /// assert_eq!(sweep.bl_binary_search_bitonic(&6), Some(2));
+    /// assert_eq!(sweep.bl_binary_search_bitonic(&2), Some(5));
+    /// assert_eq!(sweep.bl_binary_search_bitonic(&5), None);
+    /// ```
+    fn bl_binary_search_bitonic(&self, x: &T) -> Option<usize>
+    where
+        T: Ord,
+    {
+        self.bl_binary_search_bitonic_by(x, T::cmp)
+    }
+
+    /// Searches this bitonic slice for a given element, using a comparator function that
+    /// orders the elements. See
+    /// [`bl_binary_search_bitonic`](SharBinarySearch::bl_binary_search_bitonic).
+    fn bl_binary_search_bitonic_by<F>(&self, x: &T, compare: F) -> Option<usize>
+    where
+        F: FnMut(&T, &T) -> Ordering;
+
+    /// Searches this bitonic slice for `b` with a key extraction function. See
+    /// [`bl_binary_search_bitonic`](SharBinarySearch::bl_binary_search_bitonic).
+    fn bl_binary_search_bitonic_by_key<B, F>(&self, b: &B, f: F) -> Option<usize>
+    where
+        F: FnMut(&T) -> B,
+        B: Ord;
+}
+
+/// Resolves `range` into the range of indices of `slice` whose keys (as returned by `key`) fall
+/// within it. If the start of the range lies after its end, the returned range is empty.
+pub(crate) fn resolve_range<T, Q, R, F>(slice: &[T], range: &R, mut key: F) -> Range<usize>
+where
+    Q: Ord + ?Sized,
+    R: RangeBounds<Q> + ?Sized,
+    F: FnMut(&T) -> &Q,
+{
+    let start = match range.start_bound() {
+        Bound::Included(x) => slice.bl_partition_point(|p| key(p) < x),
+        Bound::Excluded(x) => slice.bl_partition_point(|p| key(p) <= x),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(x) => slice.bl_partition_point(|p| key(p) <= x),
+        Bound::Excluded(x) => slice.bl_partition_point(|p| key(p) < x),
+        Bound::Unbounded => slice.len(),
+    };
+
+    start..end.max(start)
 }
 
+/// The error returned when data that must be sorted is not, such as when constructing a
+/// [`SortedArc`](sorted_arc::SortedArc) or a [`CaselessSorted`](caseless::CaselessSorted).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnsortedError {
+    index: usize,
+}
+
+impl UnsortedError {
+    /// Returns the index of the first element that is less than its predecessor.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl fmt::Display for UnsortedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "element at index {} is less than its predecessor",
+            self.index
+        )
+    }
+}
+
+impl Error for UnsortedError {}
+
 /// Note: this cannot be called with `length = 0`!
 #[inline]
 const fn bit_floor(length: usize) -> usize {
     1_usize << (usize::BITS - length.leading_zeros() - 1)
 }
 
+/// Runs Shar's search over the indices `0..len`, where `compare(i)` returns the ordering of
+/// element `i` relative to the key. `compare` is only ever called with indices less than `len`.
+///
+/// This is the search behind every slice search, written over indices so that the probes can
+/// also be observed, as by the `trace` feature.
+#[inline]
+pub(crate) fn search_indices<F>(len: usize, mut compare: F) -> Result<usize, usize>
+where
+    F: FnMut(usize) -> Ordering,
+{
+    if len == 0 {
+        return Err(0);
+    }
+
+    let mut length = len;
+
+    let mut left = 0;
+    let right = length;
+
+    let mut step = bit_floor(length);
+
+    if step != length && compare(step).is_lt() {
+        length -= step + 1;
+
+        if length == 0 {
+            return Err(right);
+        }
+
+        step = length.next_power_of_two();
+        left = right - step;
+    }
+
+    // TODO: This needs to loop unroll... bleh.
+    loop {
+        step /= 2;
+        if step == 0 {
+            break;
+        } else if compare(left + step).is_lt() {
+            left += step;
+        }
+    }
+
+    match compare(left) {
+        Ordering::Less => {
+            if left + 1 >= len {
+                Err(left + 1)
+            } else {
+                match compare(left + 1) {
+                    Ordering::Less => Err(left + 1),
+                    Ordering::Equal => Ok(left + 1),
+                    Ordering::Greater => Err(left + 1),
+                }
+            }
+        }
+        Ordering::Equal => Ok(left),
+        Ordering::Greater => Err(left),
+    }
+}
+
 impl<T> SharBinarySearch<T> for [T] {
     #[inline]
     fn bl_binary_search_by<'a, F>(&'a self, mut f: F) -> Result<usize, usize>
     where
         F: FnMut(&'a T) -> Ordering,
     {
-        if self.is_empty() {
+        // `search_indices` only probes indices less than the length.
+        search_indices(self.len(), |i| f(unsafe { self.get_unchecked(i) }))
+    }
+
+    #[inline]
+    fn bl_binary_search_4ary(&self, x: &T) -> Result<usize, usize>
+    where
+        T: Ord,
+    {
+        quaternary::search_4ary(self, x)
+    }
+
+    #[inline]
+    fn bl_binary_search_bounded_by<'a, F>(&'a self, mut f: F) -> Result<usize, usize>
+    where
+        F: FnMut(&'a T) -> Ordering,
+    {
+        let (Some(first), Some(last)) = (self.first(), self.last()) else {
             return Err(0);
+        };
+
+        match f(first) {
+            Ordering::Greater => return Err(0),
+            Ordering::Equal => return Ok(0),
+            Ordering::Less => {}
+        }
+        if f(last).is_lt() {
+            return Err(self.len());
         }
 
-        let mut length = self.len();
+        // Equal to the last element may still be a run that starts earlier.
+        self.bl_binary_search_by(f)
+    }
 
-        let mut left = 0;
-        let right = length;
+    fn bl_search_or_neighbors_by<'a, F>(&'a self, f: F) -> Result<(usize, &'a T), Neighbors<'a, T>>
+    where
+        F: FnMut(&'a T) -> Ordering,
+    {
+        match self.bl_binary_search_by(f) {
+            Ok(index) => Ok((index, &self[index])),
+            Err(index) => Err(Neighbors::new(self, index)),
+        }
+    }
 
-        let mut step = bit_floor(length);
+    fn bl_runs(&self) -> Runs<'_, T, impl FnMut(&T, &T) -> bool>
+    where
+        T: Ord,
+    {
+        Runs::new(self, T::eq)
+    }
 
-        if step != length && f(unsafe { self.get_unchecked(step) }).is_lt() {
-            length -= step + 1;
+    fn bl_runs_by_key<B, F>(&self, mut f: F) -> Runs<'_, T, impl FnMut(&T, &T) -> bool>
+    where
+        F: FnMut(&T) -> B,
+        B: Ord,
+    {
+        Runs::new(self, move |a, b| f(a) == f(b))
+    }
 
-            if length == 0 {
-                return Err(right);
+    fn bl_search_auto_with(&self, x: &T, tuning: &auto::SearchTuning) -> Result<usize, usize>
+    where
+        T: Ord,
+    {
+        auto::search(self, x, tuning)
+    }
+
+    fn bl_rotation_point_by<F>(&self, compare: F) -> usize
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        rotated::rotation_point_by(self, compare)
+    }
+
+    fn bl_binary_search_rotated_by<F>(&self, x: &T, mut compare: F) -> Result<usize, usize>
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let pivot = self.bl_rotation_point_by(&mut compare);
+        rotated::search_rotated_by(self, pivot, |p| compare(p, x))
+    }
+
+    fn bl_binary_search_rotated_by_key<B, F>(&self, b: &B, mut f: F) -> Result<usize, usize>
+    where
+        F: FnMut(&T) -> B,
+        B: Ord,
+    {
+        let pivot = self.bl_rotation_point_by_key(&mut f);
+        rotated::search_rotated_by(self, pivot, |p| f(p).cmp(b))
+    }
+
+    fn bl_peak_by<F>(&self, compare: F) -> usize
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        bitonic::peak_by(self, compare)
+    }
+
+    fn bl_binary_search_bitonic_by<F>(&self, x: &T, mut compare: F) -> Option<usize>
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let peak = self.bl_peak_by(&mut compare);
+        bitonic::search_bitonic_by(self, peak, |p| compare(p, x))
+    }
+
+    fn bl_binary_search_bitonic_by_key<B, F>(&self, b: &B, mut f: F) -> Option<usize>
+    where
+        F: FnMut(&T) -> B,
+        B: Ord,
+    {
+        let peak = self.bl_peak_by_key(&mut f);
+        bitonic::search_bitonic_by(self, peak, |p| f(p).cmp(b))
+    }
+}
+
+/// Implements [`SharBinarySearch`] for a container by forwarding to the slice it dereferences
+/// to. Method calls reach the slice through auto-deref anyway, but generic code bounded on
+/// `SharBinarySearch<T>` needs the container itself to implement the trait.
+#[cfg(feature = "alloc")]
+macro_rules! forward_to_slice {
+    (impl<$($lifetime:lifetime,)? T $(: $bound:path)?> for $container:ty) => {
+        impl<$($lifetime,)? T $(: $bound)?> SharBinarySearch<T> for $container {
+            #[inline]
+            fn bl_binary_search_by<'a, F>(&'a self, f: F) -> Result<usize, usize>
+            where
+                F: FnMut(&'a T) -> Ordering,
+            {
+                (**self).bl_binary_search_by(f)
             }
 
-            step = length.next_power_of_two();
-            left = right - step;
-        }
+            #[inline]
+            fn bl_binary_search_4ary(&self, x: &T) -> Result<usize, usize>
+            where
+                T: Ord,
+            {
+                (**self).bl_binary_search_4ary(x)
+            }
 
-        // TODO: This needs to loop unroll... bleh.
-        loop {
-            step /= 2;
-            if step == 0 {
-                break;
-            } else if f(unsafe { self.get_unchecked(left + step) }).is_lt() {
-                left += step;
+            #[inline]
+            fn bl_binary_search_bounded_by<'a, F>(&'a self, f: F) -> Result<usize, usize>
+            where
+                F: FnMut(&'a T) -> Ordering,
+            {
+                (**self).bl_binary_search_bounded_by(f)
             }
-        }
 
-        match f(unsafe { self.get_unchecked(left) }) {
-            Ordering::Less => {
-                if left + 1 >= self.len() {
-                    Err(left + 1)
-                } else {
-                    match f(unsafe { self.get_unchecked(left + 1) }) {
-                        Ordering::Less => Err(left + 1),
-                        Ordering::Equal => Ok(left + 1),
-                        Ordering::Greater => Err(left + 1),
-                    }
-                }
+            #[inline]
+            fn bl_search_or_neighbors_by<'a, F>(
+                &'a self,
+                f: F,
+            ) -> Result<(usize, &'a T), Neighbors<'a, T>>
+            where
+                F: FnMut(&'a T) -> Ordering,
+            {
+                (**self).bl_search_or_neighbors_by(f)
+            }
+
+            #[inline]
+            fn bl_runs(&self) -> Runs<'_, T, impl FnMut(&T, &T) -> bool>
+            where
+                T: Ord,
+            {
+                (**self).bl_runs()
+            }
+
+            #[inline]
+            fn bl_runs_by_key<B, F>(&self, f: F) -> Runs<'_, T, impl FnMut(&T, &T) -> bool>
+            where
+                F: FnMut(&T) -> B,
+                B: Ord,
+            {
+                (**self).bl_runs_by_key(f)
+            }
+
+            #[inline]
+            fn bl_search_auto_with(
+                &self,
+                x: &T,
+                tuning: &auto::SearchTuning,
+            ) -> Result<usize, usize>
+            where
+                T: Ord,
+            {
+                (**self).bl_search_auto_with(x, tuning)
+            }
+
+            #[inline]
+            fn bl_rotation_point_by<F>(&self, compare: F) -> usize
+            where
+                F: FnMut(&T, &T) -> Ordering,
+            {
+                (**self).bl_rotation_point_by(compare)
+            }
+
+            #[inline]
+            fn bl_binary_search_rotated_by<F>(&self, x: &T, compare: F) -> Result<usize, usize>
+            where
+                F: FnMut(&T, &T) -> Ordering,
+            {
+                (**self).bl_binary_search_rotated_by(x, compare)
+            }
+
+            #[inline]
+            fn bl_binary_search_rotated_by_key<B, F>(&self, b: &B, f: F) -> Result<usize, usize>
+            where
+                F: FnMut(&T) -> B,
+                B: Ord,
+            {
+                (**self).bl_binary_search_rotated_by_key(b, f)
+            }
+
+            #[inline]
+            fn bl_peak_by<F>(&self, compare: F) -> usize
+            where
+                F: FnMut(&T, &T) -> Ordering,
+            {
+                (**self).bl_peak_by(compare)
+            }
+
+            #[inline]
+            fn bl_binary_search_bitonic_by<F>(&self, x: &T, compare: F) -> Option<usize>
+            where
+                F: FnMut(&T, &T) -> Ordering,
+            {
+                (**self).bl_binary_search_bitonic_by(x, compare)
+            }
+
+            #[inline]
+            fn bl_binary_search_bitonic_by_key<B, F>(&self, b: &B, f: F) -> Option<usize>
+            where
+                F: FnMut(&T) -> B,
+                B: Ord,
+            {
+                (**self).bl_binary_search_bitonic_by_key(b, f)
             }
-            Ordering::Equal => Ok(left),
-            Ordering::Greater => Err(left),
         }
-    }
+    };
 }
 
+#[cfg(feature = "alloc")]
+forward_to_slice!(impl<T> for alloc::vec::Vec<T>);
+#[cfg(feature = "alloc")]
+forward_to_slice!(impl<T> for alloc::boxed::Box<[T]>);
+#[cfg(feature = "alloc")]
+forward_to_slice!(impl<T> for alloc::rc::Rc<[T]>);
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+forward_to_slice!(impl<T> for alloc::sync::Arc<[T]>);
+#[cfg(feature = "alloc")]
+forward_to_slice!(impl<'b, T: Clone> for alloc::borrow::Cow<'b, [T]>);
+
 /// Tests taken from std.
 #[cfg(test)]
 mod test {
     use std::cmp::Ordering;
 
-    use crate::{bit_floor, SharBinarySearch};
+    use crate::{
+        bit_floor,
+        reference::{self, assert_matches_reference, inputs},
+        SharBinarySearch,
+    };
 
     #[test]
     fn test_bit_floor() {
@@ -137,6 +1092,7 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::match_like_matches_macro)]
     fn test_binary_search() {
         let b: [i32; 0] = [];
         assert_eq!(b.bl_binary_search(&5), Err(0));
@@ -210,6 +1166,37 @@ mod test {
     }
 
     #[test]
+    // The exact number of comparisons per search, so that a change to the probe sequence shows
+    // up here. For these lengths, std's search takes one fewer for the last element and for a
+    // miss, and the same otherwise.
+    fn test_comparison_counts() {
+        let cases: [(usize, [usize; 3]); 5] = [
+            // Length, then comparisons for the first element, the last, and a miss in the middle.
+            (1, [1, 1, 1]),
+            (7, [4, 4, 5]),
+            (64, [7, 8, 8]),
+            (1000, [11, 12, 12]),
+            (1 << 20, [21, 22, 22]),
+        ];
+
+        for (len, expected) in cases {
+            let slice: Vec<u64> = (0..len as u64).map(|i| i * 2).collect();
+            let keys = [0, slice[len - 1], slice[len / 2] + 1];
+
+            for (x, expected) in keys.into_iter().zip(expected) {
+                let mut comparisons = 0;
+                let result = slice.bl_binary_search_by(|p| {
+                    comparisons += 1;
+                    p.cmp(&x)
+                });
+                assert_eq!(result, slice.binary_search(&x));
+                assert_eq!(comparisons, expected, "length {len}, key {x}");
+            }
+        }
+    }
+
+    #[test]
+    #[allow(clippy::useless_vec, clippy::map_identity)]
     fn test_binary_search_lifetime() {
         #[allow(dead_code)]
         #[derive(Debug)]
@@ -237,4 +1224,228 @@ mod test {
         let r = xs.bl_binary_search_by_key(&key, |e| &e.topic);
         assert_eq!(Ok(1), r.map(|i| i));
     }
+
+    #[test]
+    fn test_bounds() {
+        let b: [i32; 0] = [];
+        assert_eq!(b.bl_lower_bound(&1), 0);
+        assert_eq!(b.bl_upper_bound(&1), 0);
+        assert_eq!(b.bl_equal_range(&1), 0..0);
+
+        let b = [1, 3, 3, 3, 7];
+        assert_eq!(b.bl_lower_bound(&0), 0);
+        assert_eq!(b.bl_upper_bound(&0), 0);
+        assert_eq!(b.bl_lower_bound(&3), 1);
+        assert_eq!(b.bl_upper_bound(&3), 4);
+        assert_eq!(b.bl_equal_range(&3), 1..4);
+        assert_eq!(b.bl_equal_range(&4), 4..4);
+        assert_eq!(b.bl_equal_range(&7), 4..5);
+        assert_eq!(b.bl_equal_range(&8), 5..5);
+
+        let b = [(1, 'a'), (2, 'b'), (2, 'c'), (5, 'd')];
+        assert_eq!(b.bl_lower_bound_by_key(&2, |&(k, _)| k), 1);
+        assert_eq!(b.bl_upper_bound_by_key(&2, |&(k, _)| k), 3);
+        assert_eq!(b.bl_equal_range_by_key(&5, |&(k, _)| k), 3..4);
+    }
+
+    #[test]
+    fn test_bounds_against_reference() {
+        assert_matches_reference!(
+            for (slice, x) in inputs::slices_with_keys(1) =>
+            slice.bl_lower_bound(&x),
+            reference::lower_bound(&slice, &x),
+        );
+        assert_matches_reference!(
+            for (slice, x) in inputs::slices_with_keys(2) =>
+            slice.bl_upper_bound(&x),
+            reference::upper_bound(&slice, &x),
+        );
+        assert_matches_reference!(
+            for (slice, x) in inputs::slices_with_keys(3) =>
+            slice.bl_equal_range(&x),
+            reference::equal_range(&slice, &x),
+        );
+        assert_matches_reference!(
+            for (slice, x) in inputs::slices_with_keys(4) =>
+            slice.bl_partition_point(|p| *p < x),
+            reference::partition_point(&slice, |p| *p < x),
+        );
+    }
+
+    #[test]
+    fn test_searches_against_reference() {
+        assert_matches_reference!(
+            for (slice, x) in inputs::slices_with_keys(5) =>
+            slice.bl_binary_search(&x),
+            reference::binary_search(&slice, &x),
+        );
+        assert_matches_reference!(
+            for (slice, x) in inputs::slices_with_keys(6) =>
+            slice.bl_binary_search_by_key(&x, |p| *p),
+            reference::binary_search(&slice, &x),
+        );
+        assert_matches_reference!(
+            for (slice, x) in inputs::slices_with_keys(7) =>
+            slice.bl_binary_search_4ary(&x),
+            reference::binary_search(&slice, &x),
+        );
+        assert_matches_reference!(
+            for (slice, x) in inputs::slices_with_keys(8) =>
+            slice.bl_search_auto(&x),
+            reference::binary_search(&slice, &x),
+        );
+        assert_matches_reference!(
+            for (slice, x) in inputs::slices_with_keys(9) =>
+            slice.bl_search_or_neighbors(&x).map(|(i, _)| i).map_err(|n| n.into_insertion()),
+            reference::binary_search(&slice, &x),
+        );
+    }
+
+    #[test]
+    fn test_bounded() {
+        let b: [i32; 0] = [];
+        assert_eq!(b.bl_binary_search_bounded(&1), Err(0));
+
+        let b = [4];
+        assert_eq!(b.bl_binary_search_bounded(&3), Err(0));
+        assert_eq!(b.bl_binary_search_bounded(&4), Ok(0));
+        assert_eq!(b.bl_binary_search_bounded(&5), Err(1));
+
+        // A run of equal elements at either end.
+        let b = [2, 2, 2, 5, 9, 9, 9];
+        assert_eq!(b.bl_binary_search_bounded(&2), Ok(0));
+        assert_eq!(b.bl_binary_search_bounded(&9), Ok(4));
+        let b = [7, 7, 7];
+        assert_eq!(b.bl_binary_search_bounded(&7), Ok(0));
+
+        let b = [(1, 'a'), (3, 'b'), (3, 'c')];
+        assert_eq!(b.bl_binary_search_bounded_by_key(&3, |&(k, _)| k), Ok(1));
+    }
+
+    #[test]
+    fn test_bounded_against_reference() {
+        assert_matches_reference!(
+            for (slice, x) in inputs::slices_with_keys(10) =>
+            slice.bl_binary_search_bounded(&x),
+            reference::binary_search(&slice, &x),
+        );
+    }
+
+    #[test]
+    fn test_resolve_range_against_reference() {
+        use std::ops::Bound::{Excluded, Included, Unbounded};
+
+        use crate::resolve_range;
+
+        let bounds = |x: u32| [Included(x), Excluded(x), Unbounded];
+        let ranges = inputs::slices_with_keys(11).flat_map(|(slice, x)| {
+            bounds(x).into_iter().flat_map(move |start| {
+                let slice = slice.clone();
+                bounds(x / 2 + 3).map(move |end| (slice.clone(), (start, end)))
+            })
+        });
+        assert_matches_reference!(
+            for (slice, range) in ranges =>
+            resolve_range(&slice, &range, |p| p),
+            reference::range(&slice, &range),
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_resolve_range() {
+        use std::ops::Bound;
+
+        use crate::resolve_range;
+
+        let b = [1, 3, 5, 7, 9];
+        assert_eq!(resolve_range(&b, &(3..7), |p| p), 1..3);
+        assert_eq!(resolve_range(&b, &(3..=7), |p| p), 1..4);
+        assert_eq!(resolve_range(&b, &(4..), |p| p), 2..5);
+        assert_eq!(resolve_range(&b, &(..), |p| p), 0..5);
+        assert_eq!(resolve_range(&b, &(..=1), |p| p), 0..1);
+        assert_eq!(
+            resolve_range(&b, &(Bound::Included(8), Bound::Excluded(2)), |p| p),
+            4..4
+        );
+    }
+
+    /// Searches any sorted container through a generic bound, returning an element borrowed
+    /// from it.
+    #[cfg(feature = "alloc")]
+    fn find<'a, S>(sorted: &'a S, topic: &str) -> Option<&'a (String, u32)>
+    where
+        S: SharBinarySearch<(String, u32)> + ?Sized,
+    {
+        sorted
+            .bl_search_or_neighbors_by(|(t, _)| t.as_str().cmp(topic))
+            .ok()
+            .map(|(_, entry)| entry)
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_containers_satisfy_generic_bounds() {
+        use std::{borrow::Cow, rc::Rc, sync::Arc, thread};
+
+        fn lower_bound<S: SharBinarySearch<u64> + ?Sized>(sorted: &S, x: u64) -> usize {
+            sorted.bl_lower_bound(&x)
+        }
+
+        let values = vec![1_u64, 3, 3, 8];
+        let boxed: Box<[u64]> = values.clone().into();
+        let rc: Rc<[u64]> = values.clone().into();
+        let arc: Arc<[u64]> = values.clone().into();
+        let borrowed: Cow<'_, [u64]> = Cow::Borrowed(&values);
+        let owned: Cow<'_, [u64]> = Cow::Owned(values.clone());
+
+        assert_eq!(lower_bound(&values, 3), 1);
+        assert_eq!(lower_bound(&boxed, 3), 1);
+        assert_eq!(lower_bound(&rc, 4), 3);
+        assert_eq!(lower_bound(&arc, 9), 4);
+        assert_eq!(lower_bound(&borrowed, 0), 0);
+        assert_eq!(lower_bound(&owned, 8), 3);
+        assert_eq!(lower_bound(&values[..], 3), 1);
+        assert_eq!(lower_bound(&*arc, 3), 1);
+
+        let handles: Vec<_> = (0..4)
+            .map(|x| {
+                let arc = Arc::clone(&arc);
+                thread::spawn(move || lower_bound(&arc, x))
+            })
+            .collect();
+        let results: Vec<usize> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(results, [0, 0, 1, 1]);
+
+        // Elements found through the bound borrow from the container.
+        let entries: Arc<[(String, u32)]> = vec![("a".into(), 1), ("c".into(), 3)].into();
+        let found = find(&entries, "c");
+        assert_eq!(found, Some(&("c".to_string(), 3)));
+        assert_eq!(find(&entries.to_vec(), "b"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_container_method_calls_still_infer() {
+        // The element type is only known from the call.
+        let mut values = Vec::new();
+        assert_eq!(values.bl_binary_search(&7_i64), Err(0));
+        values.push(7);
+        assert_eq!(values.bl_binary_search(&7), Ok(0));
+
+        // A key borrowed from the elements, through a reference to the vector.
+        let topics = vec![("abc".to_string(), 1), ("def".to_string(), 2)];
+        let by_ref = &topics;
+        let key: &str = "def";
+        assert_eq!(by_ref.bl_binary_search_by_key(&key, |e| &e.0), Ok(1));
+        assert_eq!(topics.bl_equal_range_by_key(&"abc", |e| e.0.as_str()), 0..1);
+
+        let nested: Box<[Vec<u8>]> = vec![vec![1], vec![1, 2], vec![3]].into();
+        assert_eq!(nested.bl_binary_search(&vec![1, 2]), Ok(1));
+        let runs: Vec<_> = nested
+            .bl_runs_by_key(|v| v[0])
+            .map(|(start, _)| start)
+            .collect();
+        assert_eq!(runs, [0, 2]);
+    }
 }
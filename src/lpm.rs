@@ -0,0 +1,377 @@
+//! Longest-prefix-match lookups in sorted tables of address prefixes, such as routing tables.
+
+use alloc::vec::Vec;
+use core::{error::Error, fmt, slice};
+
+use crate::SharBinarySearch;
+
+/// An address type that prefixes can be formed from: [`u32`] for IPv4 and [`u128`] for IPv6.
+pub trait Address: Copy + Ord + fmt::Debug {
+    /// The number of bits in an address.
+    const BITS: u8;
+
+    /// Returns the mask whose first `len` bits are set. `len` must be at most [`Address::BITS`].
+    fn mask(len: u8) -> Self;
+
+    /// Returns the bitwise AND of two addresses.
+    fn and(self, other: Self) -> Self;
+
+    /// Returns the bitwise OR of an address and the complement of another.
+    fn or_not(self, other: Self) -> Self;
+}
+
+macro_rules! impl_address {
+    ($($ty:ty),*) => {
+        $(
+            impl Address for $ty {
+                const BITS: u8 = <$ty>::BITS as u8;
+
+                fn mask(len: u8) -> Self {
+                    (!0 as $ty)
+                        .checked_shl(u32::from(<Self as Address>::BITS - len))
+                        .unwrap_or(0)
+                }
+
+                fn and(self, other: Self) -> Self {
+                    self & other
+                }
+
+                fn or_not(self, other: Self) -> Self {
+                    self | !other
+                }
+            }
+        )*
+    };
+}
+
+impl_address!(u32, u128);
+
+/// The error returned for an invalid prefix or table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrefixError {
+    /// The prefix length is longer than the address.
+    LengthTooLong(u8),
+    /// The address has bits set past the prefix length.
+    HostBitsSet,
+    /// The same prefix appears more than once in a table.
+    Duplicate,
+}
+
+impl fmt::Display for PrefixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrefixError::LengthTooLong(len) => {
+                write!(f, "prefix length {len} is longer than the address")
+            }
+            PrefixError::HostBitsSet => write!(f, "address has bits set past the prefix length"),
+            PrefixError::Duplicate => write!(f, "duplicate prefix in table"),
+        }
+    }
+}
+
+impl Error for PrefixError {}
+
+/// An address prefix, such as `10.0.0.0/8`: all the addresses whose first `len` bits match
+/// those of `addr`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Prefix<A> {
+    addr: A,
+    len: u8,
+}
+
+impl<A: Address> Prefix<A> {
+    /// Creates a prefix of the first `len` bits of `addr`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `len` is longer than the address, or if `addr` has bits set past
+    /// the first `len`.
+    pub fn new(addr: A, len: u8) -> Result<Self, PrefixError> {
+        if len > A::BITS {
+            return Err(PrefixError::LengthTooLong(len));
+        }
+        if addr.and(A::mask(len)) != addr {
+            return Err(PrefixError::HostBitsSet);
+        }
+
+        Ok(Self { addr, len })
+    }
+
+    /// Returns the first address of the prefix.
+    pub fn addr(&self) -> A {
+        self.addr
+    }
+
+    /// Returns the prefix length, in bits.
+    pub fn prefix_len(&self) -> u8 {
+        self.len
+    }
+
+    /// Returns the last address of the prefix.
+    pub fn last(&self) -> A {
+        self.addr.or_not(A::mask(self.len))
+    }
+
+    /// Returns whether `addr` is in the prefix.
+    pub fn contains(&self, addr: A) -> bool {
+        addr.and(A::mask(self.len)) == self.addr
+    }
+}
+
+/// A table of address prefixes, each with a value, for finding the most specific prefix that
+/// contains an address.
+///
+/// The prefixes are stored sorted by address and then by length, along with the index of each
+/// one's closest enclosing prefix. A [`longest_match`](PrefixTable::longest_match) binary
+/// searches for the last prefix starting at or before the address, then walks up the enclosing
+/// prefixes until one contains the address. It takes `O(log n + d)` time, where `d` is the
+/// nesting depth of the table, which is at most 33 for IPv4 and 129 for IPv6.
+///
+/// ```
+/// use std::net::Ipv4Addr;
+/// use shar_search::lpm::PrefixTable;
+///
+/// let ip = |s: &str| u32::from(s.parse::<Ipv4Addr>().unwrap());
+/// let table = PrefixTable::new([
+///     (ip("0.0.0.0"), 0, "default"),
+///     (ip("10.0.0.0"), 8, "internal"),
+///     (ip("10.1.0.0"), 16, "lab"),
+/// ])
+/// .unwrap();
+///
+/// assert_eq!(table.longest_match(ip("10.1.2.3")).map(|(_, v)| *v), Some("lab"));
+/// assert_eq!(table.longest_match(ip("10.2.0.1")).map(|(_, v)| *v), Some("internal"));
+/// assert_eq!(table.longest_match(ip("8.8.8.8")).map(|(_, v)| *v), Some("default"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct PrefixTable<A, V> {
+    entries: Vec<(Prefix<A>, V)>,
+    /// The index of the closest enclosing prefix of each entry.
+    parents: Vec<Option<usize>>,
+}
+
+impl<A: Address, V> PrefixTable<A, V> {
+    /// Creates a table from `(address, prefix length, value)` entries in any order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any entry is not a valid [`Prefix`], or if a prefix appears more
+    /// than once.
+    pub fn new<I: IntoIterator<Item = (A, u8, V)>>(entries: I) -> Result<Self, PrefixError> {
+        let mut entries = entries
+            .into_iter()
+            .map(|(addr, len, value)| Ok((Prefix::new(addr, len)?, value)))
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.sort_unstable_by_key(|(prefix, _)| *prefix);
+
+        if entries.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            return Err(PrefixError::Duplicate);
+        }
+
+        // Prefixes either nest or are disjoint, so the enclosing prefixes of each entry are
+        // those still open on a stack when it is reached.
+        let mut open: Vec<usize> = Vec::new();
+        let parents = entries
+            .iter()
+            .enumerate()
+            .map(|(i, (prefix, _))| {
+                while let Some(&top) = open.last() {
+                    if entries[top].0.contains(prefix.addr) {
+                        break;
+                    }
+                    open.pop();
+                }
+                let parent = open.last().copied();
+                open.push(i);
+                parent
+            })
+            .collect();
+
+        Ok(Self { entries, parents })
+    }
+
+    /// Returns the number of prefixes in the table.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether the table is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the value of exactly `prefix`, if it is in the table.
+    pub fn get(&self, prefix: &Prefix<A>) -> Option<&V> {
+        let index = self
+            .entries
+            .bl_binary_search_by(|(p, _)| p.cmp(prefix))
+            .ok()?;
+        Some(&self.entries[index].1)
+    }
+
+    /// Returns the most specific prefix that contains `addr`, along with its value.
+    pub fn longest_match(&self, addr: A) -> Option<(&Prefix<A>, &V)> {
+        // The last prefix starting at or before `addr`. It is the most specific match if it
+        // contains `addr`, and otherwise one of its enclosing prefixes may.
+        let after = self.entries.bl_partition_point(|(p, _)| p.addr <= addr);
+        let mut candidate = after.checked_sub(1);
+
+        while let Some(index) = candidate {
+            let (prefix, value) = &self.entries[index];
+            if prefix.contains(addr) {
+                return Some((prefix, value));
+            }
+            candidate = self.parents[index];
+        }
+
+        None
+    }
+
+    /// Returns an iterator over the prefixes and their values, sorted by address and then by
+    /// length.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&Prefix<A>, &V)> + ExactSizeIterator {
+        self.entries.iter().map(|(p, v)| (p, v))
+    }
+}
+
+impl<'a, A, V> IntoIterator for &'a PrefixTable<A, V> {
+    type Item = &'a (Prefix<A>, V);
+    type IntoIter = slice::Iter<'a, (Prefix<A>, V)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::{Address, Prefix, PrefixError, PrefixTable};
+    use crate::test_util::XorShift;
+
+    fn v4(s: &str) -> u32 {
+        u32::from(s.parse::<Ipv4Addr>().unwrap())
+    }
+
+    fn lookup<V: Copy>(table: &PrefixTable<u32, V>, addr: &str) -> Option<V> {
+        table.longest_match(v4(addr)).map(|(_, v)| *v)
+    }
+
+    #[test]
+    fn test_nested_prefixes() {
+        let table = PrefixTable::new([
+            (v4("10.1.2.0"), 24, 24),
+            (v4("10.0.0.0"), 8, 8),
+            (v4("10.1.0.0"), 16, 16),
+            (v4("10.1.2.3"), 32, 32),
+            (v4("10.1.3.0"), 24, 124),
+            (v4("192.168.0.0"), 16, 1),
+        ])
+        .unwrap();
+
+        assert_eq!(lookup(&table, "10.1.2.3"), Some(32));
+        assert_eq!(lookup(&table, "10.1.2.4"), Some(24));
+        assert_eq!(lookup(&table, "10.1.2.255"), Some(24));
+        assert_eq!(lookup(&table, "10.1.3.1"), Some(124));
+        assert_eq!(lookup(&table, "10.1.4.0"), Some(16));
+        assert_eq!(lookup(&table, "10.200.0.0"), Some(8));
+        assert_eq!(lookup(&table, "10.0.0.0"), Some(8));
+        assert_eq!(lookup(&table, "192.168.255.255"), Some(1));
+
+        // Addresses matching nothing, before, between and after the prefixes.
+        assert_eq!(lookup(&table, "9.255.255.255"), None);
+        assert_eq!(lookup(&table, "11.0.0.0"), None);
+        assert_eq!(lookup(&table, "255.255.255.255"), None);
+        assert_eq!(lookup(&table, "0.0.0.0"), None);
+    }
+
+    #[test]
+    fn test_default_route() {
+        let table = PrefixTable::new([(0, 0, "default"), (v4("10.0.0.0"), 8, "ten")]).unwrap();
+        assert_eq!(lookup(&table, "0.0.0.0"), Some("default"));
+        assert_eq!(lookup(&table, "11.0.0.0"), Some("default"));
+        assert_eq!(lookup(&table, "255.255.255.255"), Some("default"));
+        assert_eq!(lookup(&table, "10.9.9.9"), Some("ten"));
+
+        let empty: PrefixTable<u32, ()> = PrefixTable::new([]).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.longest_match(0), None);
+    }
+
+    #[test]
+    fn test_ipv6() {
+        let v6 = |s: &str| u128::from(s.parse::<Ipv6Addr>().unwrap());
+        let table = PrefixTable::new([
+            (0, 0, "default"),
+            (v6("2001:db8::"), 32, "doc"),
+            (v6("2001:db8::1"), 128, "host"),
+        ])
+        .unwrap();
+
+        let lookup = |s: &str| table.longest_match(v6(s)).map(|(_, v)| *v);
+        assert_eq!(lookup("2001:db8::1"), Some("host"));
+        assert_eq!(lookup("2001:db8::2"), Some("doc"));
+        assert_eq!(lookup("::1"), Some("default"));
+
+        let (prefix, _) = table.longest_match(v6("2001:db8:ffff::")).unwrap();
+        assert_eq!(prefix.last(), v6("2001:db8:ffff:ffff:ffff:ffff:ffff:ffff"));
+    }
+
+    #[test]
+    fn test_validation() {
+        assert_eq!(Prefix::new(0_u32, 33), Err(PrefixError::LengthTooLong(33)));
+        assert_eq!(
+            Prefix::new(v4("10.0.0.1"), 8),
+            Err(PrefixError::HostBitsSet)
+        );
+        assert!(Prefix::new(u128::MAX, 128).is_ok());
+
+        assert_eq!(
+            PrefixTable::new([(v4("10.0.0.0"), 8, 1), (v4("10.0.0.0"), 8, 2)]).err(),
+            Some(PrefixError::Duplicate)
+        );
+        assert_eq!(
+            PrefixTable::new([(v4("11.0.0.0"), 7, 1)]).err(),
+            Some(PrefixError::HostBitsSet)
+        );
+
+        let table = PrefixTable::new([(v4("10.0.0.0"), 8, 1), (v4("10.0.0.0"), 16, 2)]).unwrap();
+        assert_eq!(
+            table.get(&Prefix::new(v4("10.0.0.0"), 16).unwrap()),
+            Some(&2)
+        );
+        assert_eq!(table.get(&Prefix::new(v4("10.0.0.0"), 12).unwrap()), None);
+    }
+
+    #[test]
+    fn test_against_naive() {
+        let mut rng = XorShift::new(67);
+
+        for _ in 0..50 {
+            // Short addresses in the top bits, so prefixes nest often.
+            let entries: Vec<(u32, u8, usize)> = (0..rng.below(40) as usize)
+                .map(|i| {
+                    let len = rng.below(9) as u8;
+                    let addr = (rng.below(256) as u32) << 24;
+                    (addr & <u32 as Address>::mask(len), len, i)
+                })
+                .collect();
+            let mut unique = entries.clone();
+            unique.sort_by_key(|&(addr, len, _)| (addr, len));
+            unique.dedup_by_key(|&mut (addr, len, _)| (addr, len));
+
+            let table = PrefixTable::new(unique.iter().copied()).unwrap();
+
+            for top in 0..256 {
+                let addr = (top << 24) | rng.below(1 << 24) as u32;
+                let expected = unique
+                    .iter()
+                    .filter(|&&(a, len, _)| Prefix::new(a, len).unwrap().contains(addr))
+                    .max_by_key(|&&(_, len, _)| len)
+                    .map(|&(_, _, v)| v);
+                assert_eq!(table.longest_match(addr).map(|(_, v)| *v), expected);
+            }
+        }
+    }
+}
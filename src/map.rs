@@ -0,0 +1,1234 @@
+//! A sorted map backed by a [`Vec`] of key-value pairs, using Shar's algorithm for lookups.
+
+use alloc::vec::{Drain, Vec};
+use core::{
+    borrow::Borrow,
+    cmp::Ordering,
+    fmt,
+    iter::FusedIterator,
+    ops::{ControlFlow, Index, RangeBounds},
+    slice,
+};
+
+use crate::{
+    duplicates::sort_with_policy, raw, resolve_range, DuplicateError, DuplicatePolicy,
+    SharBinarySearch,
+};
+
+/// A map with unique keys, stored as contiguous `(K, V)` pairs sorted by key.
+///
+/// Lookups use Shar's branchless binary search, while iteration is just walking the
+/// underlying slice. Insertions and removals are `O(n)` due to shifting elements; use
+/// [`Extend`] to insert many pairs at once.
+///
+/// # Migrating from `BTreeMap`
+///
+/// Most of [`BTreeMap`](alloc::collections::BTreeMap)'s methods have an equivalent here with the
+/// same signature and semantics, differing only in cost:
+///
+/// | `BTreeMap` | `SharMap` | Cost here |
+/// |---|---|---|
+/// | `new`, `len`, `is_empty`, `clear` | same | `O(1)`, except `clear` |
+/// | `get`, `get_key_value`, `get_mut`, `contains_key` | same | `O(log n)` |
+/// | `insert`, `remove`, `remove_entry` | same | `O(n)` |
+/// | `first_key_value`, `last_key_value` | same | `O(1)` |
+/// | `pop_last`, `last_entry` | same | `O(1)` |
+/// | `pop_first` | same | `O(n)` |
+/// | `first_entry` | same, removing through it is `O(n)` | `O(1)` |
+/// | `retain` | same | `O(n)` |
+/// | `iter`, `iter_mut`, `keys`, `values`, `values_mut` | same | |
+/// | `range`, `range_mut` | same | `O(log n)` to start |
+/// | `extend` | same, with a single merge | `O(n + k log k)` |
+/// | `append` | [`SharMap::merge_keeping_right`], taking `other` by value | `O(n + m)` |
+///
+/// It also implements the same standard traits, with the same semantics: `map[&key]` panics if
+/// the key is missing, [`FromIterator`] and [`Extend`] keep the last value of a repeated key,
+/// iterating by value or by reference is in ascending key order, [`Debug`](fmt::Debug) formats
+/// as a map, and maps are equal when their pairs are, as well as [`Clone`] and [`Default`].
+///
+/// There is no general `entry` API, `split_off` or `extract_if` yet, and
+/// [`SharMap::drain_range`], [`SharMap::update_range`], [`SharMap::merge_with`] and
+/// [`SharMap::remove_sorted_keys`] have no `BTreeMap` equivalent.
+#[derive(Clone)]
+pub struct SharMap<K, V> {
+    inner: Vec<(K, V)>,
+}
+
+impl<K, V> Default for SharMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> SharMap<K, V> {
+    /// Creates a new, empty map.
+    pub const fn new() -> Self {
+        Self { inner: Vec::new() }
+    }
+
+    /// Creates a new, empty map with space for at least `capacity` pairs.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Creates a map from a vector of pairs whose keys are already sorted and unique.
+    #[allow(dead_code)]
+    pub(crate) fn from_sorted_vec_unchecked(inner: Vec<(K, V)>) -> Self {
+        Self { inner }
+    }
+
+    /// Returns the number of pairs in the map.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns whether the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Removes all pairs.
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    /// Returns all pairs as a slice sorted by key.
+    pub fn as_slice(&self) -> &[(K, V)] {
+        &self.inner
+    }
+
+    /// Consumes the map, returning the underlying [`Vec`] of pairs sorted by key.
+    pub fn into_vec(self) -> Vec<(K, V)> {
+        self.inner
+    }
+
+    /// Returns the first pair of the map, the one with the smallest key.
+    ///
+    /// ```
+    /// use shar_search::SharMap;
+    ///
+    /// let mut map = SharMap::new();
+    /// assert_eq!(map.first_key_value(), None);
+    /// map.insert(1, "b");
+    /// map.insert(2, "a");
+    /// assert_eq!(map.first_key_value(), Some((&1, &"b")));
+    /// ```
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        self.inner.first().map(|(k, v)| (k, v))
+    }
+
+    /// Returns the last pair of the map, the one with the largest key.
+    ///
+    /// ```
+    /// use shar_search::SharMap;
+    ///
+    /// let mut map = SharMap::new();
+    /// map.insert(1, "b");
+    /// map.insert(2, "a");
+    /// assert_eq!(map.last_key_value(), Some((&2, &"a")));
+    /// ```
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        self.inner.last().map(|(k, v)| (k, v))
+    }
+
+    /// Removes and returns the first pair of the map, the one with the smallest key.
+    ///
+    /// Unlike with a `BTreeMap`, this is `O(n)`, as every other pair is shifted down; to drain
+    /// the map in key order, prefer iterating over it or [`SharMap::drain_range`].
+    ///
+    /// ```
+    /// use shar_search::SharMap;
+    ///
+    /// let mut map = SharMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(2, "b");
+    /// while let Some((key, _val)) = map.pop_first() {
+    ///     assert!(map.iter().all(|(k, _v)| *k > key));
+    /// }
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        (!self.inner.is_empty()).then(|| self.inner.remove(0))
+    }
+
+    /// Removes and returns the last pair of the map, the one with the largest key, in `O(1)`.
+    ///
+    /// ```
+    /// use shar_search::SharMap;
+    ///
+    /// let mut map = SharMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(2, "b");
+    /// while let Some((key, _val)) = map.pop_last() {
+    ///     assert!(map.iter().all(|(k, _v)| *k < key));
+    /// }
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        self.inner.pop()
+    }
+
+    /// Returns the first entry of the map, for inspecting or modifying it in place.
+    ///
+    /// ```
+    /// use shar_search::SharMap;
+    ///
+    /// let mut map = SharMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(2, "b");
+    /// if let Some(mut entry) = map.first_entry() {
+    ///     if *entry.key() > 0 {
+    ///         entry.insert("first");
+    ///     }
+    /// }
+    /// assert_eq!(*map.get(&1).unwrap(), "first");
+    /// assert_eq!(*map.get(&2).unwrap(), "b");
+    /// ```
+    pub fn first_entry(&mut self) -> Option<OccupiedEntry<'_, K, V>> {
+        if self.inner.is_empty() {
+            return None;
+        }
+        Some(OccupiedEntry {
+            inner: &mut self.inner,
+            index: 0,
+        })
+    }
+
+    /// Returns the last entry of the map, for inspecting or modifying it in place.
+    ///
+    /// ```
+    /// use shar_search::SharMap;
+    ///
+    /// let mut map = SharMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(2, "b");
+    /// if let Some(mut entry) = map.last_entry() {
+    ///     if *entry.key() > 0 {
+    ///         entry.insert("last");
+    ///     }
+    /// }
+    /// assert_eq!(*map.get(&1).unwrap(), "a");
+    /// assert_eq!(*map.get(&2).unwrap(), "last");
+    /// ```
+    pub fn last_entry(&mut self) -> Option<OccupiedEntry<'_, K, V>> {
+        let index = self.inner.len().checked_sub(1)?;
+        Some(OccupiedEntry {
+            inner: &mut self.inner,
+            index,
+        })
+    }
+
+    /// Retains only the pairs for which `f` returns `true`, visiting them in key order, in one
+    /// `O(n)` pass.
+    ///
+    /// ```
+    /// use shar_search::SharMap;
+    ///
+    /// let mut map: SharMap<i32, i32> = SharMap::new();
+    /// map.extend((0..8).map(|x| (x, x * 10)));
+    /// // Keep only the elements with even-numbered keys.
+    /// map.retain(|&k, _| k % 2 == 0);
+    /// assert!(map.into_vec().into_iter().eq(vec![(0, 0), (2, 20), (4, 40), (6, 60)]));
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        self.inner.retain_mut(|(k, v)| f(k, v));
+    }
+
+    /// Returns an iterator over the pairs of the map, in key order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            inner: self.inner.iter(),
+        }
+    }
+
+    /// Returns an iterator over the pairs of the map in key order, with mutable references to
+    /// the values.
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            inner: self.inner.iter_mut(),
+        }
+    }
+
+    /// Returns an iterator over the keys of the map, in order.
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> + ExactSizeIterator + '_ {
+        self.inner.iter().map(|(k, _)| k)
+    }
+
+    /// Returns an iterator over the values of the map, in key order.
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> + ExactSizeIterator + '_ {
+        self.inner.iter().map(|(_, v)| v)
+    }
+
+    /// Returns an iterator over mutable references to the values of the map, in key order.
+    pub fn values_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut V> + ExactSizeIterator {
+        self.inner.iter_mut().map(|(_, v)| v)
+    }
+}
+
+impl<K: Ord, V> SharMap<K, V> {
+    fn search<Q>(&self, key: &Q) -> Result<usize, usize>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.inner.bl_binary_search_by(|(k, _)| k.borrow().cmp(key))
+    }
+
+    /// Creates a map from unsorted pairs, resolving repeated keys according to `policy`:
+    /// [`DuplicatePolicy::KeepFirst`] keeps the value that came first in the input and
+    /// [`DuplicatePolicy::KeepLast`] the one that came last. A map never holds duplicate keys,
+    /// so [`DuplicatePolicy::KeepAll`] behaves like [`DuplicatePolicy::KeepFirst`].
+    ///
+    /// The pairs are collected into a single [`Vec`] that is sorted by key and deduplicated in
+    /// place, with a stable sort wherever the input order of equal keys matters.
+    ///
+    /// # Errors
+    ///
+    /// With [`DuplicatePolicy::Error`], returns the smallest key that appears more than once.
+    ///
+    /// ```
+    /// use shar_search::{DuplicatePolicy, SharMap};
+    ///
+    /// let pairs = [("b", 1), ("a", 2), ("b", 3)];
+    /// let map = SharMap::from_unsorted_iter(pairs, DuplicatePolicy::KeepLast).unwrap();
+    /// assert_eq!(map.get("b"), Some(&3));
+    ///
+    /// let err = SharMap::from_unsorted_iter(pairs, DuplicatePolicy::Error).err().unwrap();
+    /// assert_eq!(err.into_key(), "b");
+    /// ```
+    pub fn from_unsorted_iter<I: IntoIterator<Item = (K, V)>>(
+        iter: I,
+        policy: DuplicatePolicy,
+    ) -> Result<Self, DuplicateError<K>> {
+        let mut inner: Vec<(K, V)> = iter.into_iter().collect();
+        sort_with_policy(&mut inner, policy, false, |(k, _)| k)
+            .map_err(|(key, _)| DuplicateError { key })?;
+        Ok(Self { inner })
+    }
+
+    /// Inserts a key-value pair, returning the previous value for the key if there was one.
+    /// The key itself is not updated if it was already present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.search(&key) {
+            Ok(index) => Some(core::mem::replace(&mut self.inner[index].1, value)),
+            Err(index) => {
+                self.inner.insert(index, (key, value));
+                None
+            }
+        }
+    }
+
+    /// Returns whether the map contains `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search(key).is_ok()
+    }
+
+    /// Returns a reference to the value for `key`, if present.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get_key_value(key).map(|(_, v)| v)
+    }
+
+    /// Returns references to the stored key and the value for `key`, if present.
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search(key).ok().map(|index| {
+            let (k, v) = &self.inner[index];
+            (k, v)
+        })
+    }
+
+    /// Returns a mutable reference to the value for `key`, if present.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search(key)
+            .ok()
+            .map(move |index| &mut self.inner[index].1)
+    }
+
+    /// Removes `key` from the map, returning its value if it was present.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    /// Removes `key` from the map, returning the stored key and value if it was present.
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search(key).ok().map(|index| self.inner.remove(index))
+    }
+
+    /// Removes every key in `keys` from the map, returning how many entries were removed. Note
+    /// it is assumed that `keys` is sorted; listing a key more than once is harmless.
+    ///
+    /// The map is compacted in a single pass that gallops over the entries between keys, so this
+    /// takes `O(m log(n / m))` comparisons and `O(n)` moves, rather than the `O(m * n)` moves of
+    /// repeated [`SharMap::remove`] calls.
+    pub fn remove_sorted_keys<Q>(&mut self, keys: &[Q]) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord,
+    {
+        raw::remove_sorted_keys_by(&mut self.inner, keys, |(k, _)| k.borrow())
+    }
+
+    /// Returns a double-ended iterator over the pairs whose keys are within `range`, in key
+    /// order. If the start of the range lies after its end, the iterator is empty.
+    pub fn range<Q, R>(&self, range: R) -> Iter<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let indices = resolve_range(&self.inner, &range, |(k, _)| k.borrow());
+
+        Iter {
+            inner: self.inner[indices].iter(),
+        }
+    }
+
+    /// Returns a double-ended iterator over the pairs whose keys are within `range`, in key
+    /// order, with mutable references to the values.
+    pub fn range_mut<Q, R>(&mut self, range: R) -> IterMut<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let indices = resolve_range(&self.inner, &range, |(k, _)| k.borrow());
+
+        IterMut {
+            inner: self.inner[indices].iter_mut(),
+        }
+    }
+
+    /// Calls `f` on each pair whose key is within `range`, in key order, with a mutable reference
+    /// to the value, until `f` returns [`ControlFlow::Break`]. Returns the number of pairs `f` was
+    /// called on, including the one it broke on.
+    ///
+    /// Both ends of the range are resolved with the branchless search, and nothing is allocated.
+    /// Only the values can be changed, so the keys stay sorted.
+    ///
+    /// ```
+    /// use std::ops::ControlFlow;
+    ///
+    /// use shar_search::map::SharMap;
+    ///
+    /// let mut prices = SharMap::new();
+    /// prices.extend([("apple", 120), ("banana", 40), ("cherry", 300)]);
+    ///
+    /// // Raise the prices of everything from "b" onwards by 10%.
+    /// let repriced = prices.update_range("b".., |_, price| {
+    ///     *price += *price / 10;
+    ///     ControlFlow::Continue(())
+    /// });
+    /// assert_eq!(repriced, 2);
+    /// assert_eq!(prices.get("cherry"), Some(&330));
+    /// ```
+    pub fn update_range<Q, R, F>(&mut self, range: R, mut f: F) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+        F: FnMut(&K, &mut V) -> ControlFlow<()>,
+    {
+        let indices = resolve_range(&self.inner, &range, |(k, _)| k.borrow());
+
+        let mut touched = 0;
+        for (k, v) in &mut self.inner[indices] {
+            touched += 1;
+            if f(k, v).is_break() {
+                break;
+            }
+        }
+        touched
+    }
+
+    /// Removes the pairs whose keys are within `range`, returning them in key order as an
+    /// iterator.
+    ///
+    /// Both ends of the range are resolved with the branchless search. As with [`Vec::drain`],
+    /// the whole range is removed even if the iterator is dropped before it is fully consumed.
+    /// If the iterator is leaked (e.g. with [`std::mem::forget`]), the map is left valid but
+    /// may lose the elements after the range as well.
+    pub fn drain_range<Q, R>(&mut self, range: R) -> Drain<'_, (K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let indices = resolve_range(&self.inner, &range, |(k, _)| k.borrow());
+        self.inner.drain(indices)
+    }
+
+    /// Moves every pair of `other` into this map with a single linear merge, calling
+    /// `resolve(key, ours, theirs)` for each key present in both to decide the value kept. The
+    /// key kept is this map's.
+    ///
+    /// This is `O(n + m)`, unlike inserting the pairs of `other` one at a time, which is
+    /// `O(n * m)`.
+    ///
+    /// # Panics
+    ///
+    /// If `resolve` panics, the panic is propagated and both maps are left empty. Every key and
+    /// value is still dropped exactly once.
+    ///
+    /// ```
+    /// use shar_search::SharMap;
+    ///
+    /// let mut stock = SharMap::new();
+    /// stock.extend([("apple", 3), ("pear", 1)]);
+    /// let mut delivery = SharMap::new();
+    /// delivery.extend([("apple", 10), ("plum", 4)]);
+    ///
+    /// stock.merge_with(delivery, |_, ours, theirs| ours + theirs);
+    /// assert!(stock.iter().eq([(&"apple", &13), (&"pear", &1), (&"plum", &4)]));
+    /// ```
+    pub fn merge_with<F>(&mut self, other: SharMap<K, V>, resolve: F)
+    where
+        F: FnMut(&K, V, V) -> V,
+    {
+        let ours = core::mem::take(&mut self.inner);
+        self.inner = merge_entries(ours, other.inner, resolve);
+    }
+
+    /// Returns a new map with the pairs of both `a` and `b`, calling `resolve(key, a_value,
+    /// b_value)` with clones of the values for each key present in both. Like
+    /// [`SharMap::merge_with`], but leaves both maps as they are.
+    pub fn merged<F>(a: &SharMap<K, V>, b: &SharMap<K, V>, mut resolve: F) -> SharMap<K, V>
+    where
+        K: Clone,
+        V: Clone,
+        F: FnMut(&K, V, V) -> V,
+    {
+        let mut inner = Vec::with_capacity(a.len() + b.len());
+        let (mut a, mut b) = (a.inner.iter().peekable(), b.inner.iter().peekable());
+
+        while let (Some((a_key, _)), Some((b_key, _))) = (a.peek(), b.peek()) {
+            match a_key.cmp(b_key) {
+                Ordering::Less => inner.extend(a.next().cloned()),
+                Ordering::Greater => inner.extend(b.next().cloned()),
+                Ordering::Equal => {
+                    let ((key, a_value), (_, b_value)) = (a.next().unwrap(), b.next().unwrap());
+                    let value = resolve(key, a_value.clone(), b_value.clone());
+                    inner.push((key.clone(), value));
+                }
+            }
+        }
+        inner.extend(a.cloned());
+        inner.extend(b.cloned());
+
+        SharMap { inner }
+    }
+
+    /// Moves every pair of `other` into this map, keeping this map's value for keys present in
+    /// both. See [`SharMap::merge_with`].
+    pub fn merge_keeping_left(&mut self, other: SharMap<K, V>) {
+        self.merge_with(other, |_, ours, _| ours);
+    }
+
+    /// Moves every pair of `other` into this map, keeping the value from `other` for keys
+    /// present in both, like [`BTreeMap::append`]. See [`SharMap::merge_with`].
+    ///
+    /// [`BTreeMap::append`]: alloc::collections::BTreeMap::append
+    pub fn merge_keeping_right(&mut self, other: SharMap<K, V>) {
+        self.merge_with(other, |_, _, theirs| theirs);
+    }
+}
+
+/// Merges two vectors of pairs, each sorted by key without repeats, calling `resolve` for each
+/// key in both. Everything is moved out of the inputs as it is merged, so a panic in `resolve`
+/// drops the merged pairs and the rest of both inputs normally.
+fn merge_entries<K: Ord, V, F>(left: Vec<(K, V)>, right: Vec<(K, V)>, mut resolve: F) -> Vec<(K, V)>
+where
+    F: FnMut(&K, V, V) -> V,
+{
+    let mut out = Vec::with_capacity(left.len() + right.len());
+    let (mut left, mut right) = (left.into_iter().peekable(), right.into_iter().peekable());
+
+    while let (Some((left_key, _)), Some((right_key, _))) = (left.peek(), right.peek()) {
+        match left_key.cmp(right_key) {
+            Ordering::Less => out.extend(left.next()),
+            Ordering::Greater => out.extend(right.next()),
+            Ordering::Equal => {
+                let ((key, ours), (_, theirs)) = (left.next().unwrap(), right.next().unwrap());
+                let value = resolve(&key, ours, theirs);
+                out.push((key, value));
+            }
+        }
+    }
+    out.extend(left);
+    out.extend(right);
+
+    out
+}
+
+impl<K: Ord, V> Extend<(K, V)> for SharMap<K, V> {
+    /// Inserts all pairs with a single merge (see [`SortedVec::insert_many`]), so extending by
+    /// `k` pairs is `O(n + k log k)`. If a key appears more than once, the last value wins,
+    /// whether the earlier one was already in the map or earlier in `iter`. As with
+    /// [`insert`](SharMap::insert), the key itself is not updated: the one already in the map,
+    /// or else the first in `iter`, is kept.
+    ///
+    /// [`SortedVec::insert_many`]: crate::SortedVec::insert_many
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let mut batch: Vec<(K, V)> = iter.into_iter().collect();
+        batch.sort_by(|(a, _), (b, _)| a.cmp(b));
+        // The merge is stable, so the first of each run of equal keys is the oldest.
+        raw::merge_sorted_batch(&mut self.inner, batch, |(a, _), (b, _)| a < b);
+        raw::dedup_keep_last_value_by(&mut self.inner, |a, b| a == b);
+    }
+}
+
+impl<'a, K: Ord + Copy + 'a, V: Copy + 'a> Extend<(&'a K, &'a V)> for SharMap<K, V> {
+    /// Inserts copies of all pairs with a single merge. See [`SharMap::extend`].
+    fn extend<I: IntoIterator<Item = (&'a K, &'a V)>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().map(|(&k, &v)| (k, v)));
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for SharMap<K, V> {
+    /// Collects the pairs, then sorts and deduplicates them once. If a key appears more than
+    /// once, the last value wins, as with [`BTreeMap`](alloc::collections::BTreeMap).
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut inner: Vec<(K, V)> = iter.into_iter().collect();
+        // Stable, so the last of equal keys is the last inserted.
+        inner.sort_by(|(a, _), (b, _)| a.cmp(b));
+        raw::dedup_keep_last_by(&mut inner, |(a, _), (b, _)| a == b);
+        Self { inner }
+    }
+}
+
+impl<K, V> IntoIterator for SharMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = alloc::vec::IntoIter<(K, V)>;
+
+    /// Consumes the map, returning its pairs in ascending key order.
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a SharMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a mut SharMap<K, V> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K, Q, V> Index<&Q> for SharMap<K, V>
+where
+    K: Borrow<Q> + Ord,
+    Q: Ord + ?Sized,
+{
+    type Output = V;
+
+    /// Returns a reference to the value for `key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is not in the map.
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found")
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for SharMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: PartialEq, V: PartialEq> PartialEq for SharMap<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<K: Eq, V: Eq> Eq for SharMap<K, V> {}
+
+/// An entry of a [`SharMap`] that is known to be present, for inspecting or modifying it in
+/// place.
+///
+/// Created by [`SharMap::first_entry`] and [`SharMap::last_entry`].
+pub struct OccupiedEntry<'a, K, V> {
+    inner: &'a mut Vec<(K, V)>,
+    index: usize,
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for OccupiedEntry<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OccupiedEntry")
+            .field("key", self.key())
+            .field("value", self.get())
+            .finish()
+    }
+}
+
+impl<'a, K, V> OccupiedEntry<'a, K, V> {
+    /// Returns the entry's key.
+    pub fn key(&self) -> &K {
+        &self.inner[self.index].0
+    }
+
+    /// Returns a reference to the entry's value.
+    pub fn get(&self) -> &V {
+        &self.inner[self.index].1
+    }
+
+    /// Returns a mutable reference to the entry's value.
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.inner[self.index].1
+    }
+
+    /// Converts the entry into a mutable reference to its value, borrowed from the map.
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.inner[self.index].1
+    }
+
+    /// Replaces the entry's value, returning the old one.
+    pub fn insert(&mut self, value: V) -> V {
+        core::mem::replace(self.get_mut(), value)
+    }
+
+    /// Removes the entry from the map, returning its key and value. This is `O(1)` for the
+    /// last entry and `O(n)` for any other.
+    pub fn remove_entry(self) -> (K, V) {
+        self.inner.remove(self.index)
+    }
+
+    /// Removes the entry from the map, returning its value. See
+    /// [`OccupiedEntry::remove_entry`].
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+}
+
+/// An iterator over the pairs of a [`SharMap`], in key order.
+///
+/// Created by [`SharMap::iter`] and [`SharMap::range`], and the same methods of
+/// `SmallSharMap` with the `smallvec` feature.
+#[derive(Clone)]
+pub struct Iter<'a, K, V> {
+    pub(crate) inner: slice::Iter<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, v)| (k, v))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(k, v)| (k, v))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+/// An iterator over the pairs of a [`SharMap`] in key order, with mutable references to the
+/// values. Keys are only handed out immutably so the map's order cannot be broken.
+///
+/// Created by [`SharMap::iter_mut`] and [`SharMap::range_mut`], and the same methods of
+/// `SmallSharMap` with the `smallvec` feature.
+pub struct IterMut<'a, K, V> {
+    pub(crate) inner: slice::IterMut<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, v)| (&*k, v))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IterMut<'_, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(k, v)| (&*k, v))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        cell::Cell,
+        collections::BTreeMap,
+        ops::{Bound, ControlFlow},
+        panic::{self, AssertUnwindSafe},
+        rc::Rc,
+    };
+
+    use super::SharMap;
+    use crate::{test_util::XorShift, DuplicatePolicy};
+
+    #[test]
+    fn test_insert_get_remove() {
+        let mut map = SharMap::new();
+        assert_eq!(map.insert("b", 2), None);
+        assert_eq!(map.insert("a", 1), None);
+        assert_eq!(map.insert("b", 3), Some(2));
+        assert_eq!(map.get("b"), Some(&3));
+        assert_eq!(map.get("c"), None);
+
+        *map.get_mut("a").unwrap() += 10;
+        assert_eq!(map.as_slice(), &[("a", 11), ("b", 3)]);
+        assert_eq!(map.remove("a"), Some(11));
+        assert_eq!(map.remove("a"), None);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_range() {
+        let mut map: SharMap<u32, u32> = SharMap::new();
+        map.extend((0..10).map(|i| (i * 10, i)));
+
+        assert!(map.range(20..50).map(|(k, _)| *k).eq([20, 30, 40]));
+        assert!(map.range(..=20).rev().map(|(k, _)| *k).eq([20, 10, 0]));
+
+        for (_, v) in map.range_mut(85..) {
+            *v = 100;
+        }
+        assert_eq!(map.get(&80), Some(&8));
+        assert_eq!(map.get(&90), Some(&100));
+    }
+
+    #[test]
+    fn test_extend_last_wins() {
+        let mut map = SharMap::new();
+        let mut reference = BTreeMap::new();
+
+        let batches = [
+            vec![(5, 'a'), (1, 'b'), (5, 'c')],
+            vec![(3, 'd'), (5, 'e'), (0, 'f'), (3, 'g')],
+            vec![(9, 'h'), (1, 'i'), (1, 'j'), (4, 'k')],
+        ];
+
+        for batch in batches {
+            map.extend(batch.clone());
+            reference.extend(batch);
+            assert!(map.iter().eq(reference.iter()));
+        }
+    }
+
+    #[test]
+    fn test_from_unsorted_iter() {
+        // Each key appears several times, interleaved with the others.
+        let pairs = [
+            (2, "two-a"),
+            (1, "one-a"),
+            (3, "three-a"),
+            (2, "two-b"),
+            (1, "one-b"),
+            (2, "two-c"),
+            (3, "three-b"),
+            (4, "four"),
+        ];
+
+        let first = SharMap::from_unsorted_iter(pairs, DuplicatePolicy::KeepFirst).unwrap();
+        assert_eq!(
+            first.as_slice(),
+            &[(1, "one-a"), (2, "two-a"), (3, "three-a"), (4, "four")]
+        );
+
+        let all = SharMap::from_unsorted_iter(pairs, DuplicatePolicy::KeepAll).unwrap();
+        assert_eq!(all.as_slice(), first.as_slice());
+
+        let last = SharMap::from_unsorted_iter(pairs, DuplicatePolicy::KeepLast).unwrap();
+        assert_eq!(
+            last.as_slice(),
+            &[(1, "one-b"), (2, "two-c"), (3, "three-b"), (4, "four")]
+        );
+
+        let err = SharMap::from_unsorted_iter(pairs, DuplicatePolicy::Error)
+            .err()
+            .unwrap();
+        assert_eq!(err.key(), &1);
+        assert_eq!(err.to_string(), "duplicate key 1 in input");
+
+        let pairs = [(5, 'a'), (9, 'b'), (7, 'c'), (9, 'd')];
+        let err = SharMap::from_unsorted_iter(pairs, DuplicatePolicy::Error)
+            .err()
+            .unwrap();
+        assert_eq!(err.into_key(), 9);
+
+        let unique =
+            SharMap::from_unsorted_iter([(3, 'c'), (1, 'a'), (2, 'b')], DuplicatePolicy::Error);
+        assert_eq!(unique.unwrap().as_slice(), &[(1, 'a'), (2, 'b'), (3, 'c')]);
+    }
+
+    #[test]
+    fn test_extend_keeps_key() {
+        // Equal keys in different allocations, to tell which one is kept.
+        let old = Rc::new(1);
+        let mut map = SharMap::new();
+        map.insert(Rc::clone(&old), 'a');
+        map.extend([(Rc::new(2), 'b'), (Rc::new(1), 'c'), (Rc::new(1), 'd')]);
+
+        assert!(Rc::ptr_eq(&map.as_slice()[0].0, &old));
+        assert_eq!(map.get(&1), Some(&'d'));
+        assert_eq!(map.get(&2), Some(&'b'));
+    }
+
+    #[test]
+    fn test_extend_all_duplicates() {
+        let mut map = SharMap::new();
+        map.insert(1, 0);
+        map.extend((1..=5).map(|v| (1, v)));
+        assert_eq!(map.as_slice(), &[(1, 5)]);
+    }
+
+    #[test]
+    fn test_drain_range() {
+        let mut map: SharMap<u64, char> = SharMap::new();
+        map.extend([(10, 'a'), (20, 'b'), (30, 'c'), (40, 'd')]);
+
+        // Flush everything older than a timestamp.
+        let flushed: Vec<_> = map.drain_range(..30).collect();
+        assert_eq!(flushed, [(10, 'a'), (20, 'b')]);
+        assert_eq!(map.as_slice(), &[(30, 'c'), (40, 'd')]);
+        assert_eq!(map.drain_range(31..40).count(), 0);
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_update_range() {
+        let mut map = SharMap::new();
+        map.extend((0..10).map(|k| (k * 10, k)));
+
+        let touched = map.update_range(20..50, |_, v| {
+            *v += 100;
+            ControlFlow::Continue(())
+        });
+        assert_eq!(touched, 3);
+        assert!(map
+            .values()
+            .copied()
+            .eq([0, 1, 102, 103, 104, 5, 6, 7, 8, 9]));
+
+        // Empty ranges, including inverted ones, never call the closure.
+        let mut calls = 0;
+        let mut count = |_: &u32, _: &mut u32| {
+            calls += 1;
+            ControlFlow::Continue(())
+        };
+        assert_eq!(map.update_range(21..30, &mut count), 0);
+        assert_eq!(map.update_range(100.., &mut count), 0);
+        assert_eq!(
+            map.update_range((Bound::Excluded(50), Bound::Excluded(40)), &mut count),
+            0
+        );
+        assert_eq!(SharMap::<u32, u32>::new().update_range(.., &mut count), 0);
+        assert_eq!(calls, 0);
+
+        // The whole map.
+        let reset = map.update_range(.., |_, v| {
+            *v = 0;
+            ControlFlow::Continue(())
+        });
+        assert_eq!(reset, 10);
+        assert!(map.values().all(|v| *v == 0));
+    }
+
+    #[test]
+    fn test_update_range_break() {
+        let mut map = SharMap::new();
+        map.extend((0..10).map(|k| (k, 0)));
+
+        // Stops at the first key past 5, counting the pair it broke on.
+        let mut seen = Vec::new();
+        let touched = map.update_range(3.., |k, v| {
+            seen.push(*k);
+            *v = 1;
+            if *k >= 5 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        assert_eq!(touched, 3);
+        assert_eq!(seen, [3, 4, 5]);
+        assert!(map.values().copied().eq([0, 0, 0, 1, 1, 1, 0, 0, 0, 0]));
+
+        // Breaking on the first pair still counts it.
+        assert_eq!(map.update_range(..=7, |_, _| ControlFlow::Break(())), 1);
+    }
+
+    #[test]
+    fn test_merge_with() {
+        let mut rng = XorShift::new(165);
+
+        for _ in 0..200 {
+            let mut left = SharMap::new();
+            left.extend((0..rng.below(50)).map(|_| (rng.below(100), rng.below(1000))));
+            let mut right = SharMap::new();
+            right.extend((0..rng.below(50)).map(|_| (rng.below(100), rng.below(1000))));
+
+            let mut expected: BTreeMap<_, _> = left.iter().map(|(k, v)| (*k, *v)).collect();
+            for (k, v) in right.iter() {
+                expected
+                    .entry(*k)
+                    .and_modify(|ours| *ours = *ours * 1000 + v)
+                    .or_insert(*v);
+            }
+
+            let combine = |_: &u64, ours: u64, theirs: u64| ours * 1000 + theirs;
+            let merged = SharMap::merged(&left, &right, combine);
+            assert!(merged.iter().eq(expected.iter()));
+
+            let mut keeping_right = left.clone();
+            keeping_right.merge_keeping_right(right.clone());
+            let mut appended: BTreeMap<_, _> = left.iter().map(|(k, v)| (*k, *v)).collect();
+            appended.append(&mut right.iter().map(|(k, v)| (*k, *v)).collect());
+            assert!(keeping_right.iter().eq(appended.iter()));
+
+            let mut keeping_left = right.clone();
+            keeping_left.merge_keeping_left(left.clone());
+            assert!(keeping_left.keys().eq(appended.keys()));
+            assert!(right.iter().all(|(k, v)| keeping_left.get(k) == Some(v)));
+
+            left.merge_with(right, combine);
+            assert_eq!(left.as_slice(), merged.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_merge_with_resolver_calls() {
+        let mut left = SharMap::new();
+        left.extend([(1, "a"), (3, "c"), (5, "e")]);
+        let mut right = SharMap::new();
+        right.extend([(2, "B"), (3, "C"), (5, "E"), (6, "F")]);
+
+        let mut calls = Vec::new();
+        left.merge_with(right, |k, ours, theirs| {
+            calls.push((*k, ours, theirs));
+            theirs
+        });
+        assert_eq!(calls, [(3, "c", "C"), (5, "e", "E")]);
+        assert!(left.values().eq(&["a", "B", "C", "E", "F"]));
+
+        left.merge_with(SharMap::new(), |_, _, _| unreachable!());
+        let mut empty = SharMap::new();
+        empty.merge_keeping_left(left.clone());
+        assert_eq!(empty.as_slice(), left.as_slice());
+    }
+
+    #[test]
+    fn test_merge_with_panic_safety() {
+        /// Counts how many times values are dropped.
+        struct Counted(Rc<Cell<usize>>);
+
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let drops = Rc::new(Cell::new(0));
+        let mut left = SharMap::new();
+        left.extend((0..20).map(|k| (k * 2, Counted(drops.clone()))));
+        let mut right = SharMap::new();
+        right.extend((0..20).map(|k| (k * 3, Counted(drops.clone()))));
+
+        // Panic on the third shared key, after the first two were combined, dropping one of
+        // their values each.
+        let mut shared = 0;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            left.merge_with(right, |_, ours, theirs| {
+                shared += 1;
+                if shared == 3 {
+                    panic!("resolver failed");
+                }
+                drop(theirs);
+                ours
+            })
+        }));
+
+        assert!(result.is_err());
+        assert!(left.is_empty());
+        assert_eq!(drops.get(), 40);
+        drop(left);
+        assert_eq!(drops.get(), 40);
+    }
+
+    #[test]
+    fn test_first_last() {
+        let mut map = SharMap::new();
+        let mut reference = BTreeMap::new();
+        for (k, v) in [(3, 'c'), (1, 'a'), (4, 'd'), (2, 'b')] {
+            map.insert(k, v);
+            reference.insert(k, v);
+        }
+
+        assert_eq!(map.first_key_value(), reference.first_key_value());
+        assert_eq!(map.last_key_value(), reference.last_key_value());
+        assert_eq!(map.pop_first(), reference.pop_first());
+        assert_eq!(map.pop_last(), reference.pop_last());
+        assert!(map.iter().eq(reference.iter()));
+
+        // Removing through the entries.
+        assert_eq!(map.first_entry().unwrap().remove_entry(), (2, 'b'));
+        let mut last = map.last_entry().unwrap();
+        assert_eq!((last.key(), last.get()), (&3, &'c'));
+        *last.get_mut() = 'x';
+        assert_eq!(*last.into_mut(), 'x');
+        assert_eq!(map.last_entry().unwrap().remove(), 'x');
+
+        assert!(map.is_empty());
+        assert_eq!(map.pop_first(), None);
+        assert_eq!(map.pop_last(), None);
+        assert!(map.first_entry().is_none());
+        assert!(map.last_entry().is_none());
+        assert_eq!(map.first_key_value(), None);
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut map: SharMap<u32, u32> = SharMap::new();
+        map.extend((0..100).map(|k| (k, k)));
+        let mut reference: BTreeMap<u32, u32> = (0..100).map(|k| (k, k)).collect();
+
+        let mut visited = Vec::new();
+        map.retain(|&k, v| {
+            visited.push(k);
+            *v += 1;
+            k % 3 == 0
+        });
+        reference.retain(|&k, v| {
+            *v += 1;
+            k % 3 == 0
+        });
+
+        assert!(visited.is_sorted());
+        assert!(map.iter().eq(reference.iter()));
+        assert_eq!(map.get(&3), Some(&4));
+    }
+
+    #[test]
+    fn test_remove_sorted_keys() {
+        let mut map: SharMap<String, Vec<u32>> = SharMap::new();
+        map.extend((0..10).map(|i| (format!("k{i}"), vec![i])));
+
+        let keys = ["k1", "k3", "k3", "k9", "missing"].map(String::from);
+        assert_eq!(map.remove_sorted_keys(&keys), 3);
+        assert_eq!(map.len(), 7);
+        assert!(keys.iter().all(|k| !map.contains_key(k)));
+        assert_eq!(map.get("k2"), Some(&vec![2]));
+        assert_eq!(map.remove_sorted_keys::<String>(&[]), 0);
+    }
+
+    #[test]
+    fn test_std_traits_match_btreemap() {
+        let mut rng = XorShift::new(187);
+        for _ in 0..50 {
+            let pairs: Vec<(u8, u32)> = (0..rng.below(60))
+                .map(|i| (rng.below(20) as u8, i as u32))
+                .collect();
+            let more: Vec<(u8, u32)> = (0..rng.below(30))
+                .map(|i| (rng.below(25) as u8, 1000 + i as u32))
+                .collect();
+
+            let mut map: SharMap<u8, u32> = pairs.iter().copied().collect();
+            let mut reference: BTreeMap<u8, u32> = pairs.iter().copied().collect();
+            assert!(map.iter().eq(reference.iter()));
+
+            map.extend(more.iter().copied());
+            reference.extend(more.iter().copied());
+            assert!(map.iter().eq(reference.iter()));
+            assert_eq!(format!("{map:?}"), format!("{reference:?}"));
+
+            for (key, value) in &reference {
+                assert_eq!(map[key], *value);
+            }
+            for (_, value) in &mut map {
+                *value += 1;
+            }
+            let owned: Vec<(u8, u32)> = map.clone().into_iter().collect();
+            assert!(owned.windows(2).all(|w| w[0].0 < w[1].0));
+            assert!(owned
+                .iter()
+                .map(|&(k, v)| (k, v - 1))
+                .eq(reference.into_iter()));
+        }
+    }
+
+    #[test]
+    fn test_extend_by_reference_and_eq() {
+        let source: SharMap<&str, u8> = [("b", 2), ("a", 1)].into_iter().collect();
+        let mut copy = SharMap::new();
+        copy.extend(&source);
+        assert_eq!(copy, source);
+        assert_eq!(SharMap::<&str, u8>::default(), SharMap::new());
+
+        copy.insert("c", 3);
+        assert_ne!(copy, source);
+        assert_eq!(copy["c"], 3);
+        assert_eq!(format!("{source:?}"), r#"{"a": 1, "b": 2}"#);
+    }
+
+    #[test]
+    #[should_panic(expected = "key not found")]
+    fn test_index_panics_on_missing_key() {
+        let map: SharMap<String, u8> = [("a".to_string(), 1)].into_iter().collect();
+        assert_eq!(map["a"], 1);
+        let _ = map["b"];
+    }
+}
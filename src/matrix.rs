@@ -0,0 +1,212 @@
+//! Searching row-major matrices stored as flat slices with a fixed number of columns.
+//!
+//! [`search_strict`] handles matrices whose flattened elements are sorted: each row is sorted,
+//! and starts at or after the end of the row before it. [`search_rows`] handles the weaker case
+//! where only each row is sorted, and finds the key in every row.
+//!
+//! ```
+//! # #[cfg(feature = "alloc")]
+//! # {
+//! use shar_search::matrix::{search_rows, search_strict};
+//!
+//! let strict = [
+//!     1, 3, 5, //
+//!     7, 9, 11, //
+//! ];
+//! assert_eq!(search_strict(&strict, 3, &9), Ok((1, 1)));
+//! assert_eq!(search_strict(&strict, 3, &6), Err((1, 0)));
+//!
+//! let rows = [
+//!     1, 4, 9, //
+//!     2, 3, 4, //
+//!     0, 5, 6, //
+//! ];
+//! assert_eq!(search_rows(&rows, 3, &4), [(0, 1), (1, 2)]);
+//! # }
+//! ```
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+use crate::SharBinarySearch;
+
+/// Panics unless `data` holds a whole number of rows of `cols` elements.
+fn check_shape<T>(data: &[T], cols: usize) {
+    assert!(cols != 0, "the number of columns must be nonzero");
+    assert!(
+        data.len().is_multiple_of(cols),
+        "{} elements do not fill rows of {cols} columns",
+        data.len()
+    );
+}
+
+/// Binary searches a matrix whose flattened elements are sorted for `key`, returning its
+/// `(row, column)`.
+///
+/// The matrix is `data` in row-major order with `cols` columns. Since its flattened elements
+/// are sorted, this is a single binary search over all of them: if `key` is found, the position
+/// of its first occurrence is returned, and otherwise the position where it could be inserted,
+/// which is `(rows, 0)` if it is greater than every element.
+///
+/// # Panics
+///
+/// Panics if `cols` is zero or `data.len()` isn't a multiple of `cols`.
+pub fn search_strict<T: Ord>(
+    data: &[T],
+    cols: usize,
+    key: &T,
+) -> Result<(usize, usize), (usize, usize)> {
+    search_strict_by(data, cols, |p| p.cmp(key))
+}
+
+/// Binary searches a matrix whose flattened elements are sorted with a comparator function,
+/// which returns the ordering of an element relative to the target. See [`search_strict`].
+///
+/// # Panics
+///
+/// Panics if `cols` is zero or `data.len()` isn't a multiple of `cols`.
+pub fn search_strict_by<T, F>(
+    data: &[T],
+    cols: usize,
+    f: F,
+) -> Result<(usize, usize), (usize, usize)>
+where
+    F: FnMut(&T) -> Ordering,
+{
+    check_shape(data, cols);
+    let position = |index: usize| (index / cols, index % cols);
+    data.bl_binary_search_by(f).map(position).map_err(position)
+}
+
+/// Binary searches each row of a matrix whose rows are sorted for `key`, returning the
+/// `(row, column)` of its first occurrence in each row that has it, in row order.
+///
+/// The matrix is `data` in row-major order with `cols` columns. The rows are searched side by
+/// side with [`search_in_each`](crate::multi::search_in_each), so their cache misses overlap.
+///
+/// # Panics
+///
+/// Panics if `cols` is zero or `data.len()` isn't a multiple of `cols`.
+#[cfg(feature = "alloc")]
+pub fn search_rows<T: Ord>(data: &[T], cols: usize, key: &T) -> Vec<(usize, usize)> {
+    search_rows_by(data, cols, |p| p.cmp(key))
+}
+
+/// Binary searches each row of a matrix whose rows are sorted with a comparator function, which
+/// returns the ordering of an element relative to the target. See [`search_rows`].
+///
+/// # Panics
+///
+/// Panics if `cols` is zero or `data.len()` isn't a multiple of `cols`.
+#[cfg(feature = "alloc")]
+pub fn search_rows_by<T, F>(data: &[T], cols: usize, f: F) -> Vec<(usize, usize)>
+where
+    F: FnMut(&T) -> Ordering,
+{
+    check_shape(data, cols);
+    let rows: Vec<&[T]> = data.chunks_exact(cols).collect();
+    crate::multi::search_in_each_by(&rows, f)
+        .into_iter()
+        .enumerate()
+        .filter_map(|(row, result)| Some((row, result.ok()?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::search_strict;
+    use crate::reference::{self, assert_matches_reference, inputs};
+
+    #[test]
+    fn test_strict_shapes() {
+        let empty: [u32; 0] = [];
+        assert_eq!(search_strict(&empty, 3, &1), Err((0, 0)));
+
+        let row = [1, 3, 5, 7];
+        assert_eq!(search_strict(&row, 4, &5), Ok((0, 2)));
+        assert_eq!(search_strict(&row, 4, &0), Err((0, 0)));
+        assert_eq!(search_strict(&row, 4, &8), Err((1, 0)));
+
+        let column = [1, 3, 5, 7];
+        assert_eq!(search_strict(&column, 1, &5), Ok((2, 0)));
+        assert_eq!(search_strict(&column, 1, &6), Err((3, 0)));
+        assert_eq!(search_strict(&column, 1, &8), Err((4, 0)));
+
+        let square = [1, 2, 4, 4];
+        assert_eq!(search_strict(&square, 2, &4), Ok((1, 0)));
+        assert_eq!(search_strict(&square, 2, &3), Err((1, 0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "the number of columns must be nonzero")]
+    fn test_zero_columns() {
+        let _ = search_strict(&[0_u32; 0], 0, &1);
+    }
+
+    #[test]
+    #[should_panic(expected = "5 elements do not fill rows of 2 columns")]
+    fn test_ragged() {
+        let _ = search_strict(&[1, 2, 3, 4, 5], 2, &1);
+    }
+
+    #[test]
+    fn test_strict_against_flattened() {
+        for cols in 1..=4 {
+            let split = |index: usize| (index / cols, index % cols);
+            assert_matches_reference!(
+                for (slice, x) in inputs::slices_with_keys(cols as u64)
+                    .filter(|(slice, _)| slice.len().is_multiple_of(cols)) =>
+                search_strict(&slice, cols, &x),
+                reference::binary_search(&slice, &x).map(split).map_err(split),
+            );
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    mod rows {
+        use std::vec::Vec;
+
+        use super::super::search_rows;
+        use crate::test_util::XorShift;
+
+        #[test]
+        fn test_rows_shapes() {
+            let empty: [u32; 0] = [];
+            assert_eq!(search_rows(&empty, 2, &1), []);
+
+            let row = [1, 3, 3, 7];
+            assert_eq!(search_rows(&row, 4, &3), [(0, 1)]);
+            assert_eq!(search_rows(&row, 4, &4), []);
+
+            let column = [3, 1, 3, 7];
+            assert_eq!(search_rows(&column, 1, &3), [(0, 0), (2, 0)]);
+        }
+
+        #[test]
+        #[should_panic(expected = "3 elements do not fill rows of 2 columns")]
+        fn test_rows_ragged() {
+            let _ = search_rows(&[1, 2, 3], 2, &1);
+        }
+
+        #[test]
+        fn test_rows_against_flattened() {
+            let mut rng = XorShift::new(189);
+            for _ in 0..300 {
+                let rows = rng.below(40) as usize;
+                let cols = 1 + rng.below(20) as usize;
+                let mut data: Vec<u64> = (0..rows * cols).map(|_| rng.below(30)).collect();
+                data.chunks_exact_mut(cols).for_each(<[u64]>::sort_unstable);
+
+                let key = rng.below(30);
+                let expected: Vec<(usize, usize)> = (0..rows)
+                    .filter_map(|row| {
+                        let cells = &data[row * cols..(row + 1) * cols];
+                        Some((row, cells.iter().position(|&x| x == key)?))
+                    })
+                    .collect();
+                assert_eq!(search_rows(&data, cols, &key), expected, "{data:?} {key}");
+            }
+        }
+    }
+}
@@ -0,0 +1,328 @@
+//! Merging two sorted slices, galloping through long runs from one side.
+//!
+//! A plain two-pointer merge compares every element, which is wasteful when one side is much
+//! smaller than the other: merging a handful of elements into a huge slice should mostly be
+//! bulk copies. The merges here start out comparing element by element, and once one side has
+//! won several comparisons in a row they switch to galloping, using exponential probes followed
+//! by the branchless search to find how far that side can be copied at once. When the sides are
+//! interleaved evenly, galloping is switched back off so the merge degrades to the plain linear
+//! merge.
+//!
+//! All merges are stable: elements of `a` are placed before equal elements of `b`.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::{cmp::Ordering, iter::FusedIterator, ops::Range};
+
+use crate::gallop::gallop;
+
+/// The number of consecutive wins from one side before the merge starts galloping.
+const MIN_GALLOP: usize = 7;
+
+/// Which input a run of merged elements comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Side {
+    A,
+    B,
+}
+
+/// The state of a merge, producing the output as runs of elements from either input.
+#[derive(Clone, Debug)]
+struct Runs {
+    a_index: usize,
+    b_index: usize,
+    last_winner: Side,
+    streak: usize,
+}
+
+impl Runs {
+    fn new() -> Self {
+        Self {
+            a_index: 0,
+            b_index: 0,
+            last_winner: Side::A,
+            streak: 0,
+        }
+    }
+
+    #[inline]
+    fn record(&mut self, side: Side, range: Range<usize>) -> (Side, Range<usize>) {
+        if side == self.last_winner {
+            self.streak += range.len();
+        } else {
+            self.last_winner = side;
+            self.streak = range.len();
+        }
+
+        match side {
+            Side::A => self.a_index = range.end,
+            Side::B => self.b_index = range.end,
+        }
+
+        (side, range)
+    }
+
+    /// Returns the next run of the merged output, or `None` once both inputs are exhausted.
+    #[inline]
+    fn next_run<T, F>(&mut self, a: &[T], b: &[T], compare: &mut F) -> Option<(Side, Range<usize>)>
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let (i, j) = (self.a_index, self.b_index);
+
+        if i == a.len() {
+            if j == b.len() {
+                return None;
+            }
+            return Some(self.record(Side::B, j..b.len()));
+        } else if j == b.len() {
+            return Some(self.record(Side::A, i..a.len()));
+        }
+
+        if self.streak < MIN_GALLOP {
+            let side = if compare(&b[j], &a[i]).is_lt() {
+                Side::B
+            } else {
+                Side::A
+            };
+            let start = if side == Side::A { i } else { j };
+
+            return Some(self.record(side, start..start + 1));
+        }
+
+        // Everything in `a` that is not greater than `b[j]` can go first, then everything in
+        // `b` that is less than the next element of `a`.
+        let a_run = gallop(&a[i..], |x| compare(&b[j], x).is_ge());
+        let (side, range) = if a_run > 0 {
+            (Side::A, i..i + a_run)
+        } else {
+            let b_run = gallop(&b[j..], |y| compare(y, &a[i]).is_lt());
+            (Side::B, j..j + b_run)
+        };
+
+        // A short run means the inputs are interleaving, so stop galloping.
+        if range.len() < MIN_GALLOP {
+            self.streak = 0;
+        }
+
+        Some(self.record(side, range))
+    }
+}
+
+/// Merges the sorted slices `a` and `b` into `out`, appending to its existing contents.
+///
+/// Elements of `a` are placed before equal elements of `b`. Long runs from one side are found
+/// by galloping and copied in bulk, so merging a small slice into a large one only compares
+/// `O(m log(n / m))` elements.
+///
+/// ```
+/// use shar_search::merge::merge_sorted;
+///
+/// let mut out = Vec::new();
+/// merge_sorted(&[1, 4, 9], &[2, 3, 4, 10], &mut out);
+/// assert_eq!(out, [1, 2, 3, 4, 4, 9, 10]);
+/// ```
+#[cfg(feature = "alloc")]
+pub fn merge_sorted<T: Ord + Clone>(a: &[T], b: &[T], out: &mut Vec<T>) {
+    merge_sorted_by(a, b, out, T::cmp);
+}
+
+/// Merges the sorted slices `a` and `b` into `out` with a comparator function. See
+/// [`merge_sorted`].
+#[cfg(feature = "alloc")]
+pub fn merge_sorted_by<T, F>(a: &[T], b: &[T], out: &mut Vec<T>, mut compare: F)
+where
+    T: Clone,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    out.reserve(a.len() + b.len());
+
+    let mut runs = Runs::new();
+    while let Some((side, range)) = runs.next_run(a, b, &mut compare) {
+        let source = match side {
+            Side::A => &a[range],
+            Side::B => &b[range],
+        };
+
+        // Single elements are common while not galloping, and cheaper to push directly.
+        if let [element] = source {
+            out.push(element.clone());
+        } else {
+            out.extend_from_slice(source);
+        }
+    }
+}
+
+/// Merges the sorted slices `a` and `b` into `out` with a key extraction function. See
+/// [`merge_sorted`].
+#[cfg(feature = "alloc")]
+pub fn merge_sorted_by_key<T, K, F>(a: &[T], b: &[T], out: &mut Vec<T>, mut f: F)
+where
+    T: Clone,
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    merge_sorted_by(a, b, out, |x, y| f(x).cmp(&f(y)));
+}
+
+/// Returns an iterator over the merged contents of the sorted slices `a` and `b`. See
+/// [`merge_sorted`].
+///
+/// ```
+/// use shar_search::merge::merge_iter;
+///
+/// let merged: Vec<_> = merge_iter(&[1, 5], &[2, 3]).copied().collect();
+/// assert_eq!(merged, [1, 2, 3, 5]);
+/// ```
+pub fn merge_iter<'a, T: Ord>(
+    a: &'a [T],
+    b: &'a [T],
+) -> Merge<'a, T, impl FnMut(&T, &T) -> Ordering> {
+    merge_iter_by(a, b, T::cmp)
+}
+
+/// Returns an iterator over the merged contents of the sorted slices `a` and `b`, with a
+/// comparator function. See [`merge_sorted`].
+pub fn merge_iter_by<'a, T, F>(a: &'a [T], b: &'a [T], compare: F) -> Merge<'a, T, F>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    Merge {
+        a,
+        b,
+        runs: Runs::new(),
+        current: [].iter(),
+        compare,
+    }
+}
+
+/// Returns an iterator over the merged contents of the sorted slices `a` and `b`, with a key
+/// extraction function. See [`merge_sorted`].
+pub fn merge_iter_by_key<'a, T, K, F>(
+    a: &'a [T],
+    b: &'a [T],
+    mut f: F,
+) -> Merge<'a, T, impl FnMut(&T, &T) -> Ordering>
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    merge_iter_by(a, b, move |x, y| f(x).cmp(&f(y)))
+}
+
+/// An iterator over the merged contents of two sorted slices.
+///
+/// Created by [`merge_iter`], [`merge_iter_by`], and [`merge_iter_by_key`].
+pub struct Merge<'a, T, F> {
+    a: &'a [T],
+    b: &'a [T],
+    runs: Runs,
+    current: core::slice::Iter<'a, T>,
+    compare: F,
+}
+
+impl<'a, T, F> Iterator for Merge<'a, T, F>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(next) = self.current.next() {
+            return Some(next);
+        }
+
+        let (side, range) = self.runs.next_run(self.a, self.b, &mut self.compare)?;
+        self.current = match side {
+            Side::A => self.a[range].iter(),
+            Side::B => self.b[range].iter(),
+        };
+
+        self.current.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.current.len()
+            + (self.a.len() - self.runs.a_index)
+            + (self.b.len() - self.runs.b_index);
+
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T, F> ExactSizeIterator for Merge<'_, T, F> where F: FnMut(&T, &T) -> Ordering {}
+
+impl<T, F> FusedIterator for Merge<'_, T, F> where F: FnMut(&T, &T) -> Ordering {}
+
+#[cfg(all(test, feature = "alloc"))]
+mod test {
+    use super::{merge_iter, merge_iter_by_key, merge_sorted, merge_sorted_by_key};
+    use crate::reference::{self, assert_matches_reference, inputs};
+
+    #[test]
+    fn test_against_reference() {
+        assert_matches_reference!(
+            for (a, b) in inputs::slice_pairs(88) =>
+            {
+                let mut out = Vec::new();
+                merge_sorted(&a, &b, &mut out);
+                (out, merge_iter(&a, &b).copied().collect::<Vec<_>>())
+            },
+            (reference::merge(&a, &b), reference::merge(&a, &b)),
+        );
+    }
+
+    #[test]
+    fn test_merge_sizes() {
+        let big: Vec<u32> = (0..2000).map(|i| i * 3).collect();
+        let smalls: [&[u32]; 6] = [
+            &[],
+            &[0],
+            &[5998, 9000],
+            &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10],
+            &[100, 100, 101, 2500, 4000],
+            &[0, 3, 6, 7000],
+        ];
+
+        for small in smalls {
+            let expected = reference::merge(&big, small);
+
+            let mut out = Vec::new();
+            merge_sorted(&big, small, &mut out);
+            assert_eq!(out, expected);
+
+            out.clear();
+            merge_sorted(small, &big, &mut out);
+            assert_eq!(out, expected);
+
+            assert!(merge_iter(&big, small).eq(expected.iter()));
+            assert_eq!(merge_iter(small, &big).len(), expected.len());
+        }
+
+        // Balanced, evenly interleaved inputs.
+        let evens: Vec<u32> = (0..500).map(|i| i * 2).collect();
+        let odds: Vec<u32> = (0..500).map(|i| i * 2 + 1).collect();
+        let mut out = vec![42];
+        merge_sorted(&evens, &odds, &mut out);
+        assert_eq!(out[0], 42);
+        assert_eq!(&out[1..], (0..1000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_merge_stability() {
+        let a: Vec<(u32, char)> = [1, 2, 2, 2, 3, 5, 5, 5, 5, 5, 5, 5, 5, 5]
+            .into_iter()
+            .map(|k| (k, 'a'))
+            .collect();
+        let b: Vec<(u32, char)> = [0, 2, 2, 5, 5, 6].into_iter().map(|k| (k, 'b')).collect();
+
+        let mut out = Vec::new();
+        merge_sorted_by_key(&a, &b, &mut out, |&(k, _)| k);
+
+        let mut expected = [a.clone(), b.clone()].concat();
+        expected.sort_by_key(|&(k, _)| k);
+        assert_eq!(out, expected);
+
+        assert!(merge_iter_by_key(&a, &b, |&(k, _)| k).eq(expected.iter()));
+    }
+}
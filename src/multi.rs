@@ -0,0 +1,493 @@
+//! Searching for one key in many sorted slices at once, and intersecting many sorted slices.
+//!
+//! Like the [batch searches](crate::batch), the searches here are interleaved: a group of
+//! slices is descended side by side, one level per round, so that the cache misses of the
+//! different slices overlap. Shorter slices are searched as if padded to the length of the
+//! longest one in their group, which costs them a few extra, cache-friendly rounds.
+//!
+//! How much this gains over searching the slices one after another depends on the CPU: the
+//! branchless scalar search has no mispredictions, so an out-of-order core can already overlap
+//! consecutive searches of different slices to some degree.
+
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
+use core::cmp::Ordering;
+#[cfg(feature = "alloc")]
+use core::iter::FusedIterator;
+
+use crate::batch::{BatchSizeMismatch, DEFAULT_INTERLEAVE};
+#[cfg(feature = "alloc")]
+use crate::gallop::gallop;
+use crate::SharBinarySearch;
+
+/// Binary searches each of `slices` for `key`, returning the results in the same order as
+/// `slices`. Note it is assumed that every slice is sorted.
+///
+/// As with [`bl_binary_search`](crate::SharBinarySearch::bl_binary_search), if a slice has
+/// multiple matches, the *first* is returned.
+///
+/// ```
+/// use shar_search::multi::search_in_each;
+///
+/// let a = [1, 3, 5];
+/// let b = [3, 3];
+/// let c: [i32; 0] = [];
+/// assert_eq!(search_in_each(&[&a, &b, &c], &3), [Ok(1), Ok(0), Err(0)]);
+/// ```
+#[cfg(feature = "alloc")]
+pub fn search_in_each<T: Ord>(slices: &[&[T]], key: &T) -> Vec<Result<usize, usize>> {
+    search_in_each_by(slices, |p| p.cmp(key))
+}
+
+/// Binary searches each of `slices` with a comparator function, which returns the ordering of
+/// an element relative to the target. See [`search_in_each`].
+#[cfg(feature = "alloc")]
+pub fn search_in_each_by<T, F>(slices: &[&[T]], f: F) -> Vec<Result<usize, usize>>
+where
+    F: FnMut(&T) -> Ordering,
+{
+    let mut results = vec![Err(0); slices.len()];
+    match search_in_each_by_into(slices, &mut results, f) {
+        Ok(()) => results,
+        Err(_) => unreachable!("there is one result per slice"),
+    }
+}
+
+/// Binary searches each of `slices` for `key` like [`search_in_each`], writing the results to
+/// `out` instead of allocating.
+///
+/// # Errors
+///
+/// Returns [`BatchSizeMismatch`] without writing anything if `out` and `slices` have different
+/// lengths.
+pub fn search_in_each_into<T: Ord>(
+    slices: &[&[T]],
+    key: &T,
+    out: &mut [Result<usize, usize>],
+) -> Result<(), BatchSizeMismatch> {
+    search_in_each_by_into(slices, out, |p| p.cmp(key))
+}
+
+/// Binary searches each of `slices` with a comparator function like [`search_in_each_by`],
+/// writing the results to `out` instead of allocating.
+///
+/// # Errors
+///
+/// Returns [`BatchSizeMismatch`] without writing anything if `out` and `slices` have different
+/// lengths.
+pub fn search_in_each_by_into<T, F>(
+    slices: &[&[T]],
+    out: &mut [Result<usize, usize>],
+    mut f: F,
+) -> Result<(), BatchSizeMismatch>
+where
+    F: FnMut(&T) -> Ordering,
+{
+    if out.len() != slices.len() {
+        return Err(BatchSizeMismatch {
+            keys: slices.len(),
+            out: out.len(),
+        });
+    }
+
+    const W: usize = DEFAULT_INTERLEAVE;
+
+    for (group, group_out) in slices.chunks(W).zip(out.chunks_mut(W)) {
+        // Every search in the group takes the same steps, as if all the slices were padded to
+        // the longest one with elements greater than the target. Probes past the end of a
+        // shorter slice then never move its search, which finishes within its own length.
+        let mut bases = [0_usize; W];
+        let mut size = group.iter().map(|slice| slice.len()).max().unwrap_or(0);
+
+        while size > 1 {
+            let half = size / 2;
+            for (base, slice) in bases.iter_mut().zip(group) {
+                let mid = *base + half;
+                if mid < slice.len() && f(unsafe { slice.get_unchecked(mid) }).is_lt() {
+                    *base = mid;
+                }
+            }
+            size -= half;
+        }
+
+        for ((&base, slice), result) in bases.iter().zip(group).zip(group_out) {
+            *result = match slice.get(base).map(&mut f) {
+                None => Err(0),
+                Some(Ordering::Less) => match slice.get(base + 1) {
+                    Some(next) if f(next).is_eq() => Ok(base + 1),
+                    _ => Err(base + 1),
+                },
+                Some(Ordering::Equal) => Ok(base),
+                Some(Ordering::Greater) => Err(base),
+            };
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the number of elements less than `key` across all of the sorted `shards`.
+///
+/// This is the index at which `key` would be inserted into the merge of the shards, before any
+/// equal elements. The shards are searched side by side like in [`search_in_each`], so their
+/// cache misses overlap, but nothing is allocated.
+///
+/// ```
+/// use shar_search::multi::global_rank;
+///
+/// let a = [1, 3, 5];
+/// let b = [3, 3];
+/// let c: [i32; 0] = [];
+/// assert_eq!(global_rank(&[&a, &b, &c], &3), 1);
+/// assert_eq!(global_rank(&[&a, &b, &c], &4), 4);
+/// ```
+pub fn global_rank<T: Ord>(shards: &[&[T]], key: &T) -> u64 {
+    let mut results = [Err(0); DEFAULT_INTERLEAVE];
+    shards
+        .chunks(DEFAULT_INTERLEAVE)
+        .map(|group| {
+            let out = &mut results[..group.len()];
+            match search_in_each_into(group, key, out) {
+                Ok(()) => out
+                    .iter()
+                    .map(|&(Ok(index) | Err(index))| index as u64)
+                    .sum::<u64>(),
+                Err(_) => unreachable!("there is one result per shard"),
+            }
+        })
+        .sum()
+}
+
+/// Returns the element at index `k` of the merge of the sorted `shards`, which is the element
+/// with `k` elements before it in the sorted order of all of the shards together.
+///
+/// Equal elements are interchangeable, so which shard a returned duplicate comes from is
+/// unspecified.
+///
+/// The answer is the greatest element whose [global rank](global_rank) is at most `k`. This is
+/// a binary search over values using the per-shard ranks, but with the candidate values taken
+/// from the shards rather than from the range between the smallest and greatest element: a
+/// generic `T: Ord` has no midpoint to bisect that range at, and a midpoint need not even be an
+/// element. So each shard is binary searched in turn for its greatest element of global rank at
+/// most `k`, ranking every probe across all of the shards, and only the part of a shard above
+/// the best element found so far is searched.
+///
+/// Each probe is a [`global_rank`] of `O(s log n)` comparisons for `s` shards of length `n`,
+/// and each shard takes `O(log n)` probes, so this takes `O(s^2 log^2 n)` comparisons at most.
+/// For integer keys spanning `U` values, bisecting the value range itself with [`global_rank`]
+/// takes `O(s log n log U)` instead, which is fewer when there are many shards.
+///
+/// ```
+/// use shar_search::multi::global_select;
+///
+/// let a = [1, 3, 5];
+/// let b = [3, 3];
+/// let c: [i32; 0] = [];
+/// let merged = [1, 3, 3, 3, 5];
+/// for (k, x) in merged.iter().enumerate() {
+///     assert_eq!(global_select(&[&a, &b, &c], k as u64), x);
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `k` is not less than the total length of the shards.
+pub fn global_select<'a, T: Ord>(shards: &[&'a [T]], k: u64) -> &'a T {
+    let total: u64 = shards.iter().map(|shard| shard.len() as u64).sum();
+    assert!(k < total, "k is {k} but the shards have {total} elements");
+
+    let mut best: Option<&'a T> = None;
+    for &shard in shards {
+        let start = best.map_or(0, |best| shard.bl_partition_point(|x| x <= best));
+        let rest = &shard[start..];
+        let end = rest.bl_partition_point(|x| global_rank(shards, x) <= k);
+        if let Some(found) = end.checked_sub(1) {
+            best = Some(&rest[found]);
+        }
+    }
+
+    // The smallest element of all has global rank 0, so some shard finds a candidate.
+    best.expect("a shard holds an element of global rank at most `k`")
+}
+
+/// Returns an iterator over the elements common to all of the sorted `lists`, in ascending
+/// order.
+///
+/// The lists are treated as sets: an element appears once in the output however many times it
+/// appears in each list, and the output references its first occurrence in the shortest list.
+/// The intersection of no lists is empty.
+///
+/// The shortest list drives the intersection. Each of its elements is searched for in the other
+/// lists by galloping from where the previous search in that list ended, and when a list holds
+/// no match, the driver skips ahead to that list's next element. Intersecting lists whose
+/// shortest has length `m` thus costs `O(k m log(n / m))` comparisons at most, and stops as
+/// soon as any list is exhausted.
+///
+/// ```
+/// use shar_search::multi::intersect_k;
+///
+/// let a = [1, 2, 3, 5, 8, 13];
+/// let b = [2, 3, 5, 7, 11, 13];
+/// let c = [0, 3, 3, 6, 9, 12, 13];
+/// let common: Vec<_> = intersect_k(&[&a, &b, &c]).copied().collect();
+/// assert_eq!(common, [3, 13]);
+/// ```
+#[cfg(feature = "alloc")]
+pub fn intersect_k<'a, T: Ord>(
+    lists: &[&'a [T]],
+) -> IntersectK<'a, T, impl FnMut(&T, &T) -> Ordering> {
+    intersect_k_by(lists, T::cmp)
+}
+
+/// Returns an iterator over the elements common to all of the sorted `lists` with a comparator
+/// function. See [`intersect_k`].
+#[cfg(feature = "alloc")]
+pub fn intersect_k_by<'a, T, F>(lists: &[&'a [T]], compare: F) -> IntersectK<'a, T, F>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut lists = lists.to_vec();
+    if let Some(shortest) = (0..lists.len()).min_by_key(|&i| lists[i].len()) {
+        lists.swap(0, shortest);
+    }
+
+    IntersectK {
+        positions: vec![0; lists.len()],
+        lists,
+        compare,
+    }
+}
+
+/// An iterator over the elements common to several sorted slices.
+///
+/// Created by [`intersect_k`] and [`intersect_k_by`].
+#[cfg(feature = "alloc")]
+pub struct IntersectK<'a, T, F> {
+    /// The shortest list comes first and drives the intersection.
+    lists: Vec<&'a [T]>,
+    /// Everything in each list before its position is less than the next candidate.
+    positions: Vec<usize>,
+    compare: F,
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T, F> IntersectK<'a, T, F>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    fn finish(&mut self) -> Option<&'a T> {
+        if let (Some(driver), Some(position)) = (self.lists.first(), self.positions.first_mut()) {
+            *position = driver.len();
+        }
+        None
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T, F> Iterator for IntersectK<'a, T, F>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (&driver, others) = self.lists.split_first()?;
+        let compare = &mut self.compare;
+
+        'candidates: loop {
+            let i = self.positions[0];
+            let candidate = driver.get(i)?;
+
+            for (&list, position) in others.iter().zip(&mut self.positions[1..]) {
+                *position += gallop(&list[*position..], |x| compare(x, candidate).is_lt());
+
+                let Some(found) = list.get(*position) else {
+                    return self.finish();
+                };
+
+                if compare(found, candidate).is_gt() {
+                    // Nothing in the driver before `found` can be in this list.
+                    self.positions[0] = i + gallop(&driver[i..], |x| compare(x, found).is_lt());
+                    continue 'candidates;
+                }
+            }
+
+            self.positions[0] = i + 1 + gallop(&driver[i + 1..], |x| compare(x, candidate).is_eq());
+            return Some(candidate);
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match (self.lists.first(), self.positions.first()) {
+            (Some(driver), Some(position)) => (0, Some(driver.len() - position)),
+            _ => (0, Some(0)),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T, F> FusedIterator for IntersectK<'_, T, F> where F: FnMut(&T, &T) -> Ordering {}
+
+#[cfg(all(test, feature = "alloc"))]
+mod test {
+    use std::cell::Cell;
+
+    use super::{
+        global_rank, global_select, intersect_k, intersect_k_by, search_in_each,
+        search_in_each_into,
+    };
+    use crate::{
+        reference::{self, assert_matches_reference, inputs},
+        test_util::XorShift,
+        SharBinarySearch,
+    };
+
+    #[test]
+    fn test_matches_per_slice_search() {
+        let mut rng = XorShift::new(41);
+
+        // Slices of many different lengths, including empty ones, with duplicates.
+        let slices: Vec<Vec<u32>> = (0..37)
+            .map(|i| {
+                let len = match i % 5 {
+                    0 => 0,
+                    1 => 1,
+                    _ => rng.below(300),
+                };
+                let mut slice: Vec<u32> = (0..len).map(|_| rng.below(200) as u32).collect();
+                slice.sort();
+                slice
+            })
+            .collect();
+        let refs: Vec<&[u32]> = slices.iter().map(Vec::as_slice).collect();
+
+        for key in 0..205 {
+            let expected: Vec<_> = refs.iter().map(|s| s.bl_binary_search(&key)).collect();
+            assert_eq!(search_in_each(&refs, &key), expected);
+
+            let mut out = vec![Ok(usize::MAX); refs.len()];
+            search_in_each_into(&refs, &key, &mut out).unwrap();
+            assert_eq!(out, expected);
+        }
+
+        assert!(search_in_each::<u32>(&[], &1).is_empty());
+    }
+
+    #[test]
+    fn test_into_size_mismatch() {
+        let a = [1, 2, 3];
+        let mut out = [Ok(usize::MAX); 3];
+        let err = search_in_each_into(&[&a, &a], &2, &mut out).unwrap_err();
+        assert_eq!((err.keys(), err.out()), (2, 3));
+        assert_eq!(out, [Ok(usize::MAX); 3]);
+    }
+
+    #[test]
+    fn test_intersect_against_reference() {
+        assert_matches_reference!(
+            for lists in inputs::sorted_lists(43) =>
+            {
+                let refs: Vec<&[u32]> = lists.iter().map(Vec::as_slice).collect();
+                intersect_k(&refs).copied().collect::<Vec<_>>()
+            },
+            {
+                let refs: Vec<&[u32]> = lists.iter().map(Vec::as_slice).collect();
+                reference::intersect_k(&refs)
+            },
+        );
+    }
+
+    #[test]
+    fn test_intersect_edge_cases() {
+        let a: Vec<u32> = (0..1000).collect();
+        let b: Vec<u32> = (0..1000).map(|i| i * 3).collect();
+        let c: Vec<u32> = (1000..2000).collect();
+
+        assert_eq!(intersect_k::<u32>(&[]).next(), None);
+        assert!(intersect_k(&[&a, &[]]).next().is_none());
+        assert!(intersect_k(&[&a, &a, &a]).eq(&a));
+        assert!(intersect_k(&[&[1, 1, 2, 2, 2][..], &[1, 2, 2]]).eq(&[1, 2]));
+        assert!(intersect_k(&[&a, &b]).copied().eq((0..334).map(|i| i * 3)));
+
+        // Disjoint lists exit after the first candidate exhausts a list.
+        let comparisons = Cell::new(0);
+        let result: Vec<_> = intersect_k_by(&[&a, &c], |x, y| {
+            comparisons.set(comparisons.get() + 1);
+            x.cmp(y)
+        })
+        .collect();
+        assert!(result.is_empty());
+        assert!(comparisons.get() < 30, "{} comparisons", comparisons.get());
+    }
+
+    #[test]
+    fn test_global_rank_and_select_against_sorted() {
+        let mut rng = XorShift::new(47);
+
+        for shard_count in [1, 2, 5, 17, 33] {
+            for _ in 0..10 {
+                let range = rng.below(200) + 1;
+                // Empty shards, and duplicates within and across shards.
+                let shards: Vec<Vec<u32>> = (0..shard_count)
+                    .map(|_| {
+                        let len = match rng.below(4) {
+                            0 => 0,
+                            _ => rng.below(50),
+                        };
+                        let mut shard: Vec<u32> =
+                            (0..len).map(|_| rng.below(range) as u32).collect();
+                        shard.sort();
+                        shard
+                    })
+                    .collect();
+                let refs: Vec<&[u32]> = shards.iter().map(Vec::as_slice).collect();
+                let mut all: Vec<u32> = shards.concat();
+                all.sort();
+
+                for key in 0..=range as u32 {
+                    let expected = all.partition_point(|&x| x < key) as u64;
+                    assert_eq!(global_rank(&refs, &key), expected);
+                }
+                for (k, x) in all.iter().enumerate().step_by(shard_count) {
+                    assert_eq!(global_select(&refs, k as u64), x);
+                }
+                if let Some(last) = all.last() {
+                    assert_eq!(global_select(&refs, all.len() as u64 - 1), last);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_global_rank_and_select_edge_cases() {
+        assert_eq!(global_rank::<u32>(&[], &1), 0);
+        assert_eq!(global_rank::<u32>(&[&[], &[]], &1), 0);
+
+        // One value spanning every shard.
+        let a = [7; 3];
+        let b = [7; 5];
+        let shards: [&[u32]; 4] = [&[], &a, &b, &[]];
+        assert_eq!(global_rank(&shards, &7), 0);
+        assert_eq!(global_rank(&shards, &8), 8);
+        for k in 0..8 {
+            assert_eq!(*global_select(&shards, k), 7);
+        }
+
+        // More shards than fit in one interleaved group.
+        let many: Vec<&[u32]> = vec![&b; 100];
+        assert_eq!(global_rank(&many, &8), 500);
+        assert_eq!(*global_select(&many, 499), 7);
+    }
+
+    #[test]
+    #[should_panic(expected = "k is 3 but the shards have 3 elements")]
+    fn test_global_select_out_of_range() {
+        global_select(&[&[1, 2][..], &[], &[3]], 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "k is 0 but the shards have 0 elements")]
+    fn test_global_select_no_elements() {
+        global_select::<u32>(&[&[], &[]], 0);
+    }
+}
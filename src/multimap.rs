@@ -0,0 +1,271 @@
+//! A sorted multimap backed by a [`Vec`] of key-value pairs, using Shar's algorithm for lookups.
+
+use alloc::vec::{Drain, Vec};
+use core::{
+    borrow::Borrow,
+    iter::FusedIterator,
+    ops::{Range, RangeBounds},
+    slice,
+};
+
+use crate::{resolve_range, SharBinarySearch};
+
+/// A map that allows multiple values per key, stored as contiguous `(K, V)` pairs sorted by key.
+///
+/// Pairs with equal keys are kept in insertion order, so all values for a key form a contiguous
+/// subslice that is found with a branchless lower and upper bound.
+#[derive(Clone)]
+pub struct SharMultiMap<K, V> {
+    inner: Vec<(K, V)>,
+}
+
+impl<K, V> Default for SharMultiMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> SharMultiMap<K, V> {
+    /// Creates a new, empty multimap.
+    pub const fn new() -> Self {
+        Self { inner: Vec::new() }
+    }
+
+    /// Creates a new, empty multimap with space for at least `capacity` pairs.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the total number of key-value pairs in the multimap.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns whether the multimap is empty.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Returns all key-value pairs as a slice sorted by key.
+    pub fn as_slice(&self) -> &[(K, V)] {
+        &self.inner
+    }
+
+    /// Returns an iterator over all key-value pairs, sorted by key.
+    pub fn iter(&self) -> slice::Iter<'_, (K, V)> {
+        self.inner.iter()
+    }
+}
+
+impl<K: Ord, V> SharMultiMap<K, V> {
+    fn equal_range<Q>(&self, key: &Q) -> Range<usize>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.inner.bl_equal_range_by(|(k, _)| k.borrow().cmp(key))
+    }
+
+    /// Creates a multimap from a vector that is already in the multimap's canonical order.
+    #[allow(dead_code)]
+    pub(crate) fn from_sorted_vec_unchecked(inner: Vec<(K, V)>) -> Self {
+        Self { inner }
+    }
+
+    /// Returns the number of distinct keys in the multimap.
+    pub fn key_count(&self) -> usize {
+        self.iter_groups().count()
+    }
+
+    /// Inserts a key-value pair. The pair is placed after any existing pairs with an equal key,
+    /// so values for a key are kept in insertion order.
+    pub fn insert(&mut self, key: K, value: V) {
+        let index = self.inner.bl_upper_bound_by(|(k, _)| k.cmp(&key));
+        self.inner.insert(index, (key, value));
+    }
+
+    /// Returns whether the multimap contains at least one value for `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.inner
+            .bl_binary_search_by(|(k, _)| k.borrow().cmp(key))
+            .is_ok()
+    }
+
+    /// Returns all pairs with a key equal to `key`, in insertion order. Returns an empty slice
+    /// if the key is not present.
+    pub fn get_all<Q>(&self, key: &Q) -> &[(K, V)]
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        &self.inner[self.equal_range(key)]
+    }
+
+    /// Removes all pairs with a key equal to `key`, returning their values in insertion order.
+    pub fn remove_key<Q>(&mut self, key: &Q) -> Vec<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let range = self.equal_range(key);
+        self.inner.drain(range).map(|(_, v)| v).collect()
+    }
+
+    /// Removes the first pair with a key equal to `key` and a value equal to `value`, returning
+    /// it if one was found.
+    pub fn remove_entry<Q>(&mut self, key: &Q, value: &V) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        V: PartialEq,
+    {
+        let range = self.equal_range(key);
+        let offset = self.inner[range.clone()]
+            .iter()
+            .position(|(_, v)| v == value)?;
+
+        Some(self.inner.remove(range.start + offset))
+    }
+
+    /// Removes the pairs whose keys are within `range`, returning them in order as an iterator.
+    ///
+    /// Both ends of the range are resolved with the branchless search. As with [`Vec::drain`],
+    /// the whole range is removed even if the iterator is dropped before it is fully consumed.
+    /// If the iterator is leaked (e.g. with [`std::mem::forget`]), the multimap is left valid but
+    /// may lose the elements after the range as well.
+    pub fn drain_range<Q, R>(&mut self, range: R) -> Drain<'_, (K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let indices = resolve_range(&self.inner, &range, |(k, _)| k.borrow());
+        self.inner.drain(indices)
+    }
+
+    /// Returns an iterator over each distinct key and the pairs that share it, in key order.
+    pub fn iter_groups(&self) -> Groups<'_, K, V> {
+        Groups {
+            remaining: &self.inner,
+        }
+    }
+}
+
+/// An iterator over the groups of pairs sharing a key in a [`SharMultiMap`].
+///
+/// Created by [`SharMultiMap::iter_groups`].
+#[derive(Clone)]
+pub struct Groups<'a, K, V> {
+    remaining: &'a [(K, V)],
+}
+
+impl<'a, K: Ord, V> Iterator for Groups<'a, K, V> {
+    type Item = (&'a K, &'a [(K, V)]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, _) = self.remaining.first()?;
+        let end = self.remaining.bl_upper_bound_by(|(k, _)| k.cmp(key));
+        let (group, rest) = self.remaining.split_at(end);
+        self.remaining = rest;
+
+        Some((key, group))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (
+            usize::from(!self.remaining.is_empty()),
+            Some(self.remaining.len()),
+        )
+    }
+}
+
+impl<K: Ord, V> FusedIterator for Groups<'_, K, V> {}
+
+#[cfg(test)]
+mod test {
+    use super::SharMultiMap;
+
+    #[test]
+    fn test_insertion_order_within_key() {
+        let mut map = SharMultiMap::new();
+        map.insert("b", 1);
+        map.insert("a", 2);
+        map.insert("b", 3);
+        map.insert("c", 4);
+        map.insert("a", 5);
+        map.insert("b", 6);
+
+        assert_eq!(map.len(), 6);
+        assert_eq!(map.key_count(), 3);
+        assert_eq!(map.get_all("a"), &[("a", 2), ("a", 5)]);
+        assert_eq!(map.get_all("b"), &[("b", 1), ("b", 3), ("b", 6)]);
+        assert_eq!(map.get_all("c"), &[("c", 4)]);
+    }
+
+    #[test]
+    fn test_missing_keys() {
+        let mut map: SharMultiMap<u32, u32> = SharMultiMap::new();
+        assert!(map.get_all(&1).is_empty());
+        assert!(map.remove_key(&1).is_empty());
+
+        map.insert(2, 20);
+        map.insert(4, 40);
+        assert!(map.get_all(&0).is_empty());
+        assert!(map.get_all(&3).is_empty());
+        assert!(map.get_all(&5).is_empty());
+        assert!(!map.contains_key(&3));
+        assert!(map.contains_key(&4));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut map = SharMultiMap::new();
+        for (k, v) in [(1, 'a'), (2, 'b'), (1, 'c'), (2, 'd'), (1, 'a')] {
+            map.insert(k, v);
+        }
+
+        assert_eq!(map.remove_entry(&1, &'a'), Some((1, 'a')));
+        assert_eq!(map.get_all(&1), &[(1, 'c'), (1, 'a')]);
+        assert_eq!(map.remove_entry(&1, &'z'), None);
+        assert_eq!(map.remove_entry(&3, &'a'), None);
+
+        assert_eq!(map.remove_key(&2), vec!['b', 'd']);
+        assert_eq!(map.as_slice(), &[(1, 'c'), (1, 'a')]);
+        assert_eq!(map.key_count(), 1);
+    }
+
+    #[test]
+    fn test_iter_groups() {
+        let mut map = SharMultiMap::new();
+        for (k, v) in [(3, 0), (1, 1), (3, 2), (2, 3), (3, 4)] {
+            map.insert(k, v);
+        }
+
+        let groups: Vec<_> = map
+            .iter_groups()
+            .map(|(k, group)| (*k, group.iter().map(|(_, v)| *v).collect::<Vec<_>>()))
+            .collect();
+        assert_eq!(groups, vec![(1, vec![1]), (2, vec![3]), (3, vec![0, 2, 4])]);
+
+        let empty: SharMultiMap<u32, u32> = SharMultiMap::new();
+        assert_eq!(empty.iter_groups().next(), None);
+        assert_eq!(empty.key_count(), 0);
+    }
+
+    #[test]
+    fn test_drain_range() {
+        let mut map = SharMultiMap::new();
+        for (k, v) in [(1, 'a'), (2, 'b'), (1, 'c'), (3, 'd')] {
+            map.insert(k, v);
+        }
+
+        assert!(map.drain_range(..=1).eq([(1, 'a'), (1, 'c')]));
+        assert_eq!(map.as_slice(), &[(2, 'b'), (3, 'd')]);
+    }
+}
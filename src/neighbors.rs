@@ -0,0 +1,176 @@
+//! The elements either side of a missed search, returned by
+//! [`bl_search_or_neighbors`](crate::SharBinarySearch::bl_search_or_neighbors).
+
+/// The result of a search that found no match: the insertion point, and the elements either
+/// side of it.
+///
+/// `before` is the element just before the insertion point, and `after` the element at it, so
+/// either is `None` at the corresponding end of the slice (and both are for an empty slice).
+#[derive(Debug)]
+pub struct Neighbors<'a, T> {
+    /// The index where the target could be inserted to keep the slice sorted.
+    pub insertion: usize,
+    /// The element at `insertion - 1`, the greatest element less than the target.
+    pub before: Option<&'a T>,
+    /// The element at `insertion`, the least element greater than the target.
+    pub after: Option<&'a T>,
+}
+
+impl<T> Clone for Neighbors<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Neighbors<'_, T> {}
+
+impl<'a, T> Neighbors<'a, T> {
+    /// Creates the neighbors of insertion point `insertion` in `slice`.
+    pub(crate) fn new(slice: &'a [T], insertion: usize) -> Self {
+        Self {
+            insertion,
+            before: insertion.checked_sub(1).map(|i| &slice[i]),
+            after: slice.get(insertion),
+        }
+    }
+
+    /// Returns the neighbor closest to the target along with its index, as measured by `dist`,
+    /// which returns an element's distance from the target. On a tie, the element before the
+    /// insertion point is returned. Returns `None` only for an empty slice.
+    ///
+    /// ```
+    /// use shar_search::SharBinarySearch;
+    ///
+    /// let altitudes: [i32; 4] = [0, 250, 400, 1000];
+    ///
+    /// let missed = altitudes.bl_search_or_neighbors(&330).unwrap_err();
+    /// assert_eq!(missed.closest(|a| a.abs_diff(330)), Some((2, &400)));
+    /// ```
+    pub fn closest<D, F>(&self, mut dist: F) -> Option<(usize, &'a T)>
+    where
+        D: PartialOrd,
+        F: FnMut(&T) -> D,
+    {
+        let before = self.before.map(|b| (self.insertion - 1, b));
+        let after = self.after.map(|a| (self.insertion, a));
+
+        match (before, after) {
+            (Some(b), Some(a)) if dist(a.1) < dist(b.1) => Some(a),
+            (Some(b), _) => Some(b),
+            (None, a) => a,
+        }
+    }
+
+    /// Returns the insertion point, as in the `Err` of
+    /// [`bl_binary_search`](crate::SharBinarySearch::bl_binary_search).
+    pub fn into_insertion(self) -> usize {
+        self.insertion
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::ptr;
+
+    use crate::{test_util::XorShift, SharBinarySearch};
+
+    #[test]
+    fn test_neighbors_point_into_slice() {
+        let slice = [10, 20, 20, 30];
+
+        let found = slice.bl_search_or_neighbors(&20).unwrap();
+        assert_eq!(found.0, 1);
+        assert!(ptr::eq(found.1, &slice[1]));
+
+        let missed = slice.bl_search_or_neighbors(&25).err().unwrap();
+        assert_eq!(missed.insertion, 3);
+        assert!(ptr::eq(missed.before.unwrap(), &slice[2]));
+        assert!(ptr::eq(missed.after.unwrap(), &slice[3]));
+
+        let first = slice.bl_search_or_neighbors(&5).err().unwrap();
+        assert_eq!(first.insertion, 0);
+        assert!(first.before.is_none());
+        assert!(ptr::eq(first.after.unwrap(), &slice[0]));
+
+        let last = slice.bl_search_or_neighbors(&35).err().unwrap();
+        assert_eq!(last.insertion, 4);
+        assert!(ptr::eq(last.before.unwrap(), &slice[3]));
+        assert!(last.after.is_none());
+
+        let empty: [u32; 0] = [];
+        let none = empty.bl_search_or_neighbors(&1).err().unwrap();
+        assert_eq!(none.insertion, 0);
+        assert!(none.before.is_none() && none.after.is_none());
+        assert_eq!(none.closest(|&x| x), None);
+    }
+
+    fn ptrs<T>(element: Option<&T>) -> Option<*const T> {
+        element.map(ptr::from_ref)
+    }
+
+    #[test]
+    fn test_against_binary_search() {
+        let mut rng = XorShift::new(83);
+
+        for _ in 0..100 {
+            let mut slice: Vec<u32> = (0..rng.below(30)).map(|_| rng.below(40) as u32).collect();
+            slice.sort_unstable();
+            let pairs: Vec<(u32, char)> = slice.iter().map(|&x| (x, 'x')).collect();
+
+            for x in 0..42 {
+                let expected = slice.bl_binary_search(&x);
+                let found = slice.bl_search_or_neighbors(&x);
+                let by_key = pairs.bl_search_or_neighbors_by_key(&x, |p| p.0);
+
+                match (expected, found, by_key) {
+                    (Ok(i), Ok((j, element)), Ok((k, pair))) => {
+                        assert_eq!((i, j, k), (i, i, i));
+                        assert!(ptr::eq(element, &slice[i]));
+                        assert!(ptr::eq(pair, &pairs[i]));
+                    }
+                    (Err(i), Err(neighbors), Err(key_neighbors)) => {
+                        assert_eq!(neighbors.into_insertion(), i);
+                        assert_eq!(key_neighbors.insertion, i);
+                        let before = i.checked_sub(1);
+                        assert_eq!(ptrs(neighbors.before), ptrs(before.map(|j| &slice[j])));
+                        assert_eq!(ptrs(neighbors.after), ptrs(slice.get(i)));
+                        assert_eq!(ptrs(key_neighbors.before), ptrs(before.map(|j| &pairs[j])));
+                        assert_eq!(ptrs(key_neighbors.after), ptrs(pairs.get(i)));
+                    }
+                    other => panic!("mismatched results for {x}: {other:?}"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_closest() {
+        let slice = [1.0_f64, 2.0, 4.0];
+        let dist = |x: f64| move |p: &f64| (p - x).abs();
+
+        let missed = slice
+            .bl_search_or_neighbors_by(|p| p.total_cmp(&2.9))
+            .err()
+            .unwrap();
+        assert_eq!(missed.closest(dist(2.9)), Some((1, &2.0)));
+
+        // Exactly between the neighbors, the one before wins.
+        let tie = slice
+            .bl_search_or_neighbors_by(|p| p.total_cmp(&3.0))
+            .err()
+            .unwrap();
+        assert_eq!(tie.closest(dist(3.0)), Some((1, &2.0)));
+
+        let high = slice
+            .bl_search_or_neighbors_by(|p| p.total_cmp(&9.0))
+            .err()
+            .unwrap();
+        assert_eq!(high.closest(dist(9.0)), Some((2, &4.0)));
+
+        let low = slice
+            .bl_search_or_neighbors_by(|p| p.total_cmp(&-1.0))
+            .err()
+            .unwrap();
+        assert!(ptr::eq(low.closest(dist(-1.0)).unwrap().1, &slice[0]));
+    }
+}
@@ -0,0 +1,147 @@
+//! Interoperability with [`ordered_float`], enabled with the `ordered-float` feature.
+//!
+//! [`OrderedFloat`] and [`NotNan`] are totally ordered, so slices of them already work with
+//! [`SharBinarySearch`](crate::SharBinarySearch). [`SharFloatSearch`] adds searches that mix
+//! wrapped and plain floats, without wrapping every element or key by hand.
+//!
+//! ```
+//! use ordered_float::NotNan;
+//! use shar_search::SharBinarySearch;
+//!
+//! struct Reading {
+//!     celsius: NotNan<f64>,
+//!     station: &'static str,
+//! }
+//!
+//! // NaNs are rejected when the readings are built, not during the search.
+//! let reading = |c: f64, station| NotNan::new(c).map(|celsius| Reading { celsius, station });
+//! assert!(reading(f64::NAN, "broken").is_err());
+//!
+//! let readings = [reading(-3.5, "a").unwrap(), reading(12.0, "b").unwrap()];
+//! let key = NotNan::new(12.0).unwrap();
+//! let index = readings.bl_binary_search_by_key(&key, |r| r.celsius).unwrap();
+//! assert_eq!(readings[index].station, "b");
+//! ```
+
+use ordered_float::{NotNan, OrderedFloat};
+
+use crate::SharBinarySearch;
+
+/// Trait for searching slices of wrapped floats with plain float keys, and slices of plain
+/// floats with [`OrderedFloat`] keys.
+///
+/// Elements and keys are compared with [`OrderedFloat`]'s total order, in which NaN is greater
+/// than every other value and equal to itself, and `-0.0` equals `0.0`.
+pub trait SharFloatSearch<K> {
+    /// Binary searches this slice for `key`, comparing with [`OrderedFloat`]'s total order.
+    /// Note it is assumed that the slice is sorted in that order.
+    ///
+    /// As with [`bl_binary_search`](crate::SharBinarySearch::bl_binary_search), if there are
+    /// multiple matches, the *first* is returned.
+    ///
+    /// ```
+    /// use ordered_float::OrderedFloat;
+    /// use shar_search::ordered::SharFloatSearch;
+    ///
+    /// let wrapped = [OrderedFloat(0.5), OrderedFloat(1.5), OrderedFloat(f64::NAN)];
+    /// assert_eq!(wrapped.bl_binary_search_of(1.5), Ok(1));
+    /// assert_eq!(wrapped.bl_binary_search_of(f64::NAN), Ok(2));
+    ///
+    /// let plain = [0.5, 1.5, 2.5];
+    /// assert_eq!(plain.bl_binary_search_of(OrderedFloat(2.0)), Err(2));
+    /// ```
+    fn bl_binary_search_of(&self, key: K) -> Result<usize, usize>;
+}
+
+macro_rules! impl_float_search {
+    ($($float:ty),*) => {
+        $(
+            impl SharFloatSearch<$float> for [OrderedFloat<$float>] {
+                fn bl_binary_search_of(&self, key: $float) -> Result<usize, usize> {
+                    self.bl_binary_search(&OrderedFloat(key))
+                }
+            }
+
+            impl SharFloatSearch<$float> for [NotNan<$float>] {
+                fn bl_binary_search_of(&self, key: $float) -> Result<usize, usize> {
+                    let key = OrderedFloat(key);
+                    self.bl_binary_search_by(|p| OrderedFloat(p.into_inner()).cmp(&key))
+                }
+            }
+
+            impl SharFloatSearch<OrderedFloat<$float>> for [$float] {
+                fn bl_binary_search_of(&self, key: OrderedFloat<$float>) -> Result<usize, usize> {
+                    self.bl_binary_search_by(|p| OrderedFloat(*p).cmp(&key))
+                }
+            }
+        )*
+    };
+}
+
+impl_float_search!(f32, f64);
+
+#[cfg(test)]
+mod test {
+    use ordered_float::{NotNan, OrderedFloat};
+
+    use super::SharFloatSearch;
+    use crate::SharBinarySearch;
+
+    #[test]
+    fn test_wrapped_slices() {
+        let ordered: Vec<OrderedFloat<f64>> = [-1.0, -0.0, 2.0, 2.0, f64::INFINITY, f64::NAN]
+            .into_iter()
+            .map(OrderedFloat)
+            .collect();
+
+        assert_eq!(ordered.bl_binary_search(&OrderedFloat(2.0)), Ok(2));
+        assert_eq!(ordered.bl_binary_search(&OrderedFloat(0.0)), Ok(1));
+        assert_eq!(ordered.bl_binary_search_of(2.0), Ok(2));
+        assert_eq!(ordered.bl_binary_search_of(0.0), Ok(1));
+        assert_eq!(ordered.bl_binary_search_of(1.0), Err(2));
+        assert_eq!(ordered.bl_binary_search_of(f64::NAN), Ok(5));
+
+        let not_nan: Vec<NotNan<f32>> = [-2.5_f32, 0.0, 7.25]
+            .into_iter()
+            .map(|x| NotNan::new(x).unwrap())
+            .collect();
+
+        assert_eq!(not_nan.bl_binary_search(&NotNan::new(7.25).unwrap()), Ok(2));
+        assert_eq!(not_nan.bl_binary_search_of(-2.5), Ok(0));
+        assert_eq!(not_nan.bl_binary_search_of(-0.0), Ok(1));
+        assert_eq!(not_nan.bl_binary_search_of(f32::NAN), Err(3));
+    }
+
+    #[test]
+    fn test_plain_slices() {
+        let plain = [-1.0_f32, 0.0, 3.0, 3.0, 4.5];
+        assert_eq!(plain.bl_binary_search_of(OrderedFloat(3.0)), Ok(2));
+        assert_eq!(plain.bl_binary_search_of(OrderedFloat(-0.0)), Ok(1));
+        assert_eq!(plain.bl_binary_search_of(OrderedFloat(5.0)), Err(5));
+    }
+
+    #[test]
+    fn test_nan_rejected_at_boundary() {
+        #[derive(Debug)]
+        struct Sample {
+            at: NotNan<f64>,
+        }
+
+        let raw = [0.25, f64::NAN, 1.0];
+        assert!(raw.iter().map(|&x| NotNan::new(x)).any(|x| x.is_err()));
+
+        let samples: Vec<Sample> = raw
+            .iter()
+            .filter_map(|&x| NotNan::new(x).ok())
+            .map(|at| Sample { at })
+            .collect();
+        assert_eq!(samples.len(), 2);
+
+        let key = NotNan::new(1.0).unwrap();
+        assert_eq!(samples.bl_binary_search_by_key(&key, |s| s.at), Ok(1));
+        assert_eq!(
+            samples.bl_lower_bound_by_key(&NotNan::new(0.5).unwrap(), |s| s.at),
+            1
+        );
+    }
+}
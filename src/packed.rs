@@ -0,0 +1,364 @@
+//! Searches of sorted integers bit-packed at a fixed width, without unpacking them.
+//!
+//! A [`PackedInts`] borrows `len` values of `bits_per_value` bits each, stored back to back in a
+//! byte buffer. The buffer is read as a little-endian stream of bits: value `i` occupies bits
+//! `i * bits_per_value..(i + 1) * bits_per_value`, where bit `j` is bit `j % 8` of byte `j / 8`,
+//! and the lowest bit of each value comes first. Values may span byte (and word) boundaries.
+//! Each probe of a search extracts only the value it compares, and widths of 8, 16, 32 and 64
+//! bits, whose values are whole little-endian integers, are read directly.
+//!
+//! ```
+//! use shar_search::packed::PackedInts;
+//!
+//! // The 4-bit values 1, 3, 3, 7 and 12, low nibble first.
+//! let bytes = [0x31, 0x73, 0x0c];
+//! let packed = PackedInts::new(&bytes, 4, 5).unwrap();
+//!
+//! assert_eq!(packed.get(3), 7);
+//! assert_eq!(packed.search(3), Ok(1));
+//! assert_eq!(packed.search(8), Err(4));
+//! assert_eq!(packed.search(100), Err(5));
+//! ```
+
+use core::{error::Error, fmt};
+
+use crate::{columns::partition_point, SharBinarySearch};
+
+/// The error returned when a buffer can't hold the packed values it is said to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PackedError {
+    /// The width isn't between 1 and 64 bits.
+    InvalidWidth(u8),
+    /// The buffer is shorter than the values need.
+    TooShort {
+        /// The number of bytes needed.
+        needed: usize,
+        /// The length of the buffer.
+        len: usize,
+    },
+}
+
+impl fmt::Display for PackedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackedError::InvalidWidth(bits) => {
+                write!(f, "width of {bits} bits is not between 1 and 64")
+            }
+            PackedError::TooShort { needed, len } => {
+                write!(
+                    f,
+                    "buffer of {len} bytes is shorter than the {needed} needed"
+                )
+            }
+        }
+    }
+}
+
+impl Error for PackedError {}
+
+/// Reads the little-endian `u64` at `start`, treating bytes past the end of `bytes` as zero.
+#[inline]
+fn read_u64_le(bytes: &[u8], start: usize) -> u64 {
+    match bytes.get(start..start + 8) {
+        Some(word) => u64::from_le_bytes(word.try_into().unwrap()),
+        None => {
+            let mut word = [0; 8];
+            let tail = &bytes[start.min(bytes.len())..];
+            word[..tail.len()].copy_from_slice(tail);
+            u64::from_le_bytes(word)
+        }
+    }
+}
+
+/// A read-only, sorted sequence of unsigned integers bit-packed at a fixed width. See the
+/// [module documentation](self).
+#[derive(Clone, Copy, Debug)]
+pub struct PackedInts<'a> {
+    bytes: &'a [u8],
+    bits: u8,
+    len: usize,
+}
+
+impl<'a> PackedInts<'a> {
+    /// Creates a view of `len` values of `bits_per_value` bits each packed into `bytes`,
+    /// checking that the width is valid and that `bytes` is long enough. Note it is assumed that
+    /// the values are sorted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `bits_per_value` isn't between 1 and 64, or if `bytes` is shorter
+    /// than `(len * bits_per_value).div_ceil(8)`.
+    pub fn new(bytes: &'a [u8], bits_per_value: u8, len: usize) -> Result<Self, PackedError> {
+        if !(1..=64).contains(&bits_per_value) {
+            return Err(PackedError::InvalidWidth(bits_per_value));
+        }
+
+        let needed = len
+            .checked_mul(usize::from(bits_per_value))
+            .map_or(usize::MAX, |bits| bits.div_ceil(8));
+        if bytes.len() < needed {
+            return Err(PackedError::TooShort {
+                needed,
+                len: bytes.len(),
+            });
+        }
+
+        Ok(Self {
+            bytes,
+            bits: bits_per_value,
+            len,
+        })
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the width of each value, in bits.
+    pub fn bits_per_value(&self) -> u8 {
+        self.bits
+    }
+
+    /// Returns value `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    #[inline]
+    pub fn get(&self, index: usize) -> u64 {
+        assert!(
+            index < self.len,
+            "index {index} is out of bounds for {} packed values",
+            self.len
+        );
+
+        match self.bits {
+            8 => self.get_u8(index),
+            16 => self.get_u16(index),
+            32 => self.get_u32(index),
+            64 => self.get_u64(index),
+            _ => self.get_unaligned(index),
+        }
+    }
+
+    #[inline]
+    fn get_u8(&self, index: usize) -> u64 {
+        u64::from(self.bytes[index])
+    }
+
+    #[inline]
+    fn get_u16(&self, index: usize) -> u64 {
+        let start = index * 2;
+        u64::from(u16::from_le_bytes(
+            self.bytes[start..start + 2].try_into().unwrap(),
+        ))
+    }
+
+    #[inline]
+    fn get_u32(&self, index: usize) -> u64 {
+        let start = index * 4;
+        u64::from(u32::from_le_bytes(
+            self.bytes[start..start + 4].try_into().unwrap(),
+        ))
+    }
+
+    #[inline]
+    fn get_u64(&self, index: usize) -> u64 {
+        let start = index * 8;
+        u64::from_le_bytes(self.bytes[start..start + 8].try_into().unwrap())
+    }
+
+    /// Extracts value `index` of any width, which may start part way through a byte and, at
+    /// widths over 57 bits, span nine bytes.
+    #[inline]
+    fn get_unaligned(&self, index: usize) -> u64 {
+        let bits = u32::from(self.bits);
+        let bit = index * bits as usize;
+        let start = bit / 8;
+        let shift = (bit % 8) as u32;
+
+        let mut value = read_u64_le(self.bytes, start) >> shift;
+        if shift + bits > 64 {
+            value |= u64::from(self.bytes[start + 8]) << (64 - shift);
+        }
+
+        value & (u64::MAX >> (64 - bits))
+    }
+
+    /// Returns an iterator over the values, in order.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = u64> + DoubleEndedIterator + '_ {
+        (0..self.len).map(|index| self.get(index))
+    }
+
+    /// Binary searches for `key`. If there are multiple matches, the first is returned.
+    ///
+    /// The width is matched once per search rather than once per probe, so that each probe of
+    /// an aligned width is a single load.
+    pub fn search(&self, key: u64) -> Result<usize, usize> {
+        match self.bits {
+            8 => match u8::try_from(key) {
+                Ok(key) => self.bytes[..self.len].bl_binary_search(&key),
+                Err(_) => Err(self.len),
+            },
+            16 => self.search_with(key, |index| self.get_u16(index)),
+            32 => self.search_with(key, |index| self.get_u32(index)),
+            64 => self.search_with(key, |index| self.get_u64(index)),
+            _ => self.search_with(key, |index| self.get_unaligned(index)),
+        }
+    }
+
+    #[inline]
+    fn search_with<G>(&self, key: u64, get: G) -> Result<usize, usize>
+    where
+        G: Fn(usize) -> u64,
+    {
+        let index = partition_point(self.len, |index| get(index) < key);
+
+        if index < self.len && get(index) == key {
+            Ok(index)
+        } else {
+            Err(index)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::XorShift;
+
+    /// Packs `values` at `bits` bits each, in the layout `PackedInts` reads.
+    fn pack(values: &[u64], bits: u8) -> Vec<u8> {
+        let mut bytes = vec![0; (values.len() * usize::from(bits)).div_ceil(8)];
+
+        for (i, &value) in values.iter().enumerate() {
+            for b in 0..usize::from(bits) {
+                if value >> b & 1 == 1 {
+                    let bit = i * usize::from(bits) + b;
+                    bytes[bit / 8] |= 1 << (bit % 8);
+                }
+            }
+        }
+
+        bytes
+    }
+
+    fn mask(bits: u8) -> u64 {
+        u64::MAX >> (64 - bits)
+    }
+
+    #[test]
+    fn test_boundary_indices() {
+        for bits in [1, 7, 8, 13, 16, 20, 32, 33, 57, 58, 63, 64] {
+            let max = mask(bits);
+            let values = [0, 1, max / 2, max - 1, max];
+            let bytes = pack(&values, bits);
+            let packed = PackedInts::new(&bytes, bits, values.len()).unwrap();
+
+            assert_eq!(packed.get(0), 0, "bits {bits}");
+            assert_eq!(packed.get(4), max, "bits {bits}");
+            assert!(packed.iter().eq(values), "bits {bits}");
+            assert_eq!(packed.search(0), Ok(0), "bits {bits}");
+            assert_eq!(packed.search(max), Ok(4), "bits {bits}");
+            if bits < 64 {
+                assert_eq!(packed.search(max + 1), Err(5), "bits {bits}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_straddling_widths() {
+        // 7 bits straddles bytes, 13 bits spans up to three and 33 bits straddles words.
+        for bits in [7, 13, 33] {
+            let values: Vec<u64> = (0..40).map(|i| i * 3 % (mask(bits) + 1)).collect();
+            let bytes = pack(&values, bits);
+            let packed = PackedInts::new(&bytes, bits, values.len()).unwrap();
+
+            for (i, &value) in values.iter().enumerate() {
+                assert_eq!(packed.get(i), value, "bits {bits}, index {i}");
+                assert_eq!(packed.search(value), Ok(i), "bits {bits}, index {i}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_trailing_bytes_ignored() {
+        // Bits past the last value belong to nothing, even in the same byte.
+        let mut bytes = pack(&[1, 2, 3], 5);
+        *bytes.last_mut().unwrap() |= 0x80;
+        bytes.push(0xff);
+
+        let packed = PackedInts::new(&bytes, 5, 3).unwrap();
+        assert!(packed.iter().eq([1, 2, 3]));
+        assert_eq!(packed.search(31), Err(3));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_get_out_of_bounds() {
+        PackedInts::new(&[0xff], 4, 2).unwrap().get(2);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(
+            PackedInts::new(&[], 0, 0).unwrap_err(),
+            PackedError::InvalidWidth(0)
+        );
+        assert_eq!(
+            PackedInts::new(&[0; 16], 65, 1).unwrap_err(),
+            PackedError::InvalidWidth(65)
+        );
+        assert_eq!(
+            PackedInts::new(&[0; 2], 20, 1).unwrap_err(),
+            PackedError::TooShort { needed: 3, len: 2 }
+        );
+        assert!(PackedInts::new(&[], 20, usize::MAX).is_err());
+
+        let empty = PackedInts::new(&[], 20, 0).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.search(0), Err(0));
+    }
+
+    #[test]
+    fn test_fuzz_against_unpacked() {
+        let mut rng = XorShift::new(148);
+
+        for bits in 1..=64 {
+            for _ in 0..20 {
+                let range = mask(bits).min(200);
+                let mut values: Vec<u64> = (0..rng.below(40))
+                    .map(|_| {
+                        // Spread the values over the whole width, not just its low bits.
+                        let value = rng.below(range + 1);
+                        value * (mask(bits) / range)
+                    })
+                    .collect();
+                values.sort_unstable();
+
+                let bytes = pack(&values, bits);
+                let packed = PackedInts::new(&bytes, bits, values.len()).unwrap();
+                assert!(packed.iter().eq(values.iter().copied()));
+
+                for _ in 0..20 {
+                    let key = match values.get(rng.below(values.len() as u64 + 1) as usize) {
+                        Some(&value) if rng.below(2) == 0 => value,
+                        Some(&value) => value.wrapping_add(1),
+                        None => rng.below(range + 2),
+                    };
+                    assert_eq!(
+                        packed.search(key),
+                        values.bl_binary_search(&key),
+                        "bits {bits}, key {key}"
+                    );
+                }
+            }
+        }
+    }
+}
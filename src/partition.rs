@@ -0,0 +1,130 @@
+//! Splitting a sorted slice into buckets at a list of cut points, for histogramming and range
+//! sharding.
+
+use alloc::vec::Vec;
+use core::{cmp::Ordering, ops::Range};
+
+use crate::gallop::gallop;
+
+/// Splits the sorted `data` at each of the sorted `cuts`, returning the index ranges of the
+/// `cuts.len() + 1` buckets: the elements less than `cuts[0]`, those in `[cuts[0], cuts[1])`, and
+/// so on, up to those at least the last cut. The ranges tile `0..data.len()` in order.
+///
+/// Each cut's lower bound is found by galloping from the previous one, so splitting `n` elements
+/// at `c` cuts costs `O(c log(n / c))` comparisons. Repeated cuts give empty buckets, and cuts
+/// outside the data give empty buckets at either end.
+///
+/// In debug builds, this panics if `cuts` is not sorted.
+///
+/// ```
+/// use shar_search::partition::split_by_cuts;
+///
+/// let data = [1, 2, 5, 5, 8, 13];
+/// assert_eq!(split_by_cuts(&data, &[5, 10]), [0..2, 2..5, 5..6]);
+/// assert_eq!(split_by_cuts(&data, &[0, 20]), [0..0, 0..6, 6..6]);
+/// ```
+pub fn split_by_cuts<T: Ord>(data: &[T], cuts: &[T]) -> Vec<Range<usize>> {
+    debug_assert!(cuts.is_sorted(), "the cuts must be sorted");
+    split_by_cuts_by(data, cuts, T::cmp)
+}
+
+/// Splits the sorted `data` at each of the sorted `cuts` with a comparator function, which
+/// returns the ordering of an element relative to a cut. Note it is assumed that the cuts are
+/// sorted. See [`split_by_cuts`].
+pub fn split_by_cuts_by<T, C, F>(data: &[T], cuts: &[C], mut compare: F) -> Vec<Range<usize>>
+where
+    F: FnMut(&T, &C) -> Ordering,
+{
+    let mut ranges = Vec::with_capacity(cuts.len() + 1);
+    let mut start = 0;
+
+    for cut in cuts {
+        let end = start + gallop(&data[start..], |x| compare(x, cut).is_lt());
+        ranges.push(start..end);
+        start = end;
+    }
+    ranges.push(start..data.len());
+
+    ranges
+}
+
+/// Splits the sorted `data` at each of the sorted `cuts`, comparing the key extracted from each
+/// element by `f` to the cuts. See [`split_by_cuts`].
+///
+/// ```
+/// use shar_search::partition::split_by_cuts_by_key;
+///
+/// let events = [(1, "a"), (4, "b"), (6, "c"), (9, "d")];
+/// assert_eq!(split_by_cuts_by_key(&events, &[5], |e| e.0), [0..2, 2..4]);
+/// ```
+pub fn split_by_cuts_by_key<T, K, F>(data: &[T], cuts: &[K], mut f: F) -> Vec<Range<usize>>
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    debug_assert!(cuts.is_sorted(), "the cuts must be sorted");
+    split_by_cuts_by(data, cuts, |x, cut| f(x).cmp(cut))
+}
+
+#[cfg(test)]
+mod test {
+    use std::ops::Range;
+
+    use super::{split_by_cuts, split_by_cuts_by_key};
+    use crate::test_util::XorShift;
+
+    /// Checks that `ranges` tile `0..len` and that each bucket holds exactly the right elements.
+    fn check(data: &[u32], cuts: &[u32], ranges: &[Range<usize>]) {
+        assert_eq!(ranges.len(), cuts.len() + 1);
+        assert_eq!(ranges.first().unwrap().start, 0);
+        assert_eq!(ranges.last().unwrap().end, data.len());
+        for pair in ranges.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+
+        for (i, range) in ranges.iter().enumerate() {
+            assert!(range.start <= range.end);
+            for x in &data[range.clone()] {
+                assert!(i == 0 || *x >= cuts[i - 1]);
+                assert!(i == cuts.len() || *x < cuts[i]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_random_cuts() {
+        let mut rng = XorShift::new(59);
+
+        for _ in 0..300 {
+            let range = rng.below(100) + 1;
+            let mut data: Vec<u32> = (0..rng.below(60))
+                .map(|_| rng.below(range) as u32)
+                .collect();
+            let mut cuts: Vec<u32> = (0..rng.below(12))
+                .map(|_| rng.below(range + 20) as u32)
+                .collect();
+            data.sort();
+            cuts.sort();
+
+            check(&data, &cuts, &split_by_cuts(&data, &cuts));
+        }
+    }
+
+    #[test]
+    fn test_edge_cases() {
+        let data = [10, 20, 20, 30];
+
+        let whole = split_by_cuts(&data, &[]);
+        assert_eq!((whole.len(), whole[0].clone()), (1, 0..4));
+        assert_eq!(split_by_cuts(&[], &[1, 2]), [0..0, 0..0, 0..0]);
+        assert_eq!(split_by_cuts(&data, &[0, 5]), [0..0, 0..0, 0..4]);
+        assert_eq!(split_by_cuts(&data, &[40, 50]), [0..4, 4..4, 4..4]);
+        assert_eq!(
+            split_by_cuts(&data, &[20, 20, 20, 21]),
+            [0..1, 1..1, 1..1, 1..3, 3..4]
+        );
+
+        let by_key = split_by_cuts_by_key(&data, &[2, 3], |x| x / 10);
+        assert_eq!(by_key, [0..1, 1..3, 3..4]);
+    }
+}
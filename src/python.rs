@@ -0,0 +1,412 @@
+//! A Python extension module, enabled by the `python` feature, exposing `searchsorted`-style
+//! searches of sorted arrays of `int`s or `float`s.
+//!
+//! Build and install it into the current virtualenv with `maturin develop --features python`,
+//! after which it is importable as `shar_search`:
+//!
+//! ```python
+//! import array
+//! import shar_search
+//!
+//! data = array.array("q", [10, 20, 20, 30])
+//! shar_search.search(data, 20)                 # 1, the first match
+//! shar_search.search(data, 25)                 # None
+//! shar_search.lower_bound(data, 25)            # 3
+//! shar_search.upper_bound_batch(data, [5, 20]) # [0, 3]
+//! ```
+//!
+//! The module has:
+//!
+//! - `search(data, key)`, the index of the first element equal to `key`, or `None`.
+//! - `lower_bound(data, key)`, the index of the first element not less than `key`, like NumPy's
+//!   `searchsorted(data, key, side="left")`.
+//! - `upper_bound(data, key)`, the index of the first element greater than `key`, like
+//!   `searchsorted(data, key, side="right")`.
+//! - `search_batch`, `lower_bound_batch` and `upper_bound_batch`, which take a sequence of keys
+//!   and return a list with the result for each.
+//!
+//! `data` must be sorted, and is either a one-dimensional object supporting the buffer protocol,
+//! such as a NumPy array, `array.array` or `memoryview`, or a sequence of `int`s or `float`s.
+//! Keys must be of the same kind as `data`'s elements. `float`s are compared with
+//! [`f64::total_cmp`], which orders `NaN` after every other value, as NumPy's sort does.
+//!
+//! # Buffers and the GIL
+//!
+//! Buffers of 64-bit integers (NumPy's `int64`, `array.array("q")`) and of doubles (`float64`,
+//! `array.array("d")`) are searched in place when they are C-contiguous; anything else, whether
+//! a list or a buffer of another element type, is first copied into a temporary array. Batch
+//! keys are read the same way.
+//!
+//! Batch searches with at least [`DETACH_THRESHOLD`] keys release the GIL while searching, so
+//! other Python threads can run alongside them. The buffers are kept exported for the duration,
+//! so they can't be resized or freed, but their contents can still be written by other threads.
+//! Doing so while a search is running gives unspecified results, so don't: as with any NumPy
+//! operation that releases the GIL, it's up to the caller not to mutate arrays that are in use.
+
+use std::{cmp::Ordering, slice};
+
+use pyo3::{
+    buffer::{Element, PyBuffer},
+    exceptions::{PyTypeError, PyValueError},
+    prelude::*,
+};
+
+use crate::{batch::DEFAULT_INTERLEAVE, SharBatchSearch, SharBinarySearch};
+
+/// The number of keys from which batch searches release the GIL. Below it, the cost of
+/// releasing and reacquiring it outweighs the search itself.
+pub const DETACH_THRESHOLD: usize = 4096;
+
+/// Elements passed in from Python, either borrowed from an exported buffer or copied.
+enum Elements<T: Element> {
+    Borrowed(PyBuffer<T>),
+    Copied(Vec<T>),
+}
+
+impl<T> Elements<T>
+where
+    T: Element + Copy + for<'a, 'py> FromPyObject<'a, 'py>,
+{
+    /// Reads `obj` as a one-dimensional buffer of `T`, or failing that, a sequence of `T`.
+    /// Returns `None` if it's neither.
+    fn extract(obj: &Bound<'_, PyAny>) -> PyResult<Option<Self>> {
+        if let Ok(buffer) = PyBuffer::<T>::get(obj) {
+            if buffer.dimensions() != 1 {
+                return Err(PyValueError::new_err("expected a one-dimensional array"));
+            }
+
+            let aligned = buffer.buf_ptr().cast::<T>().is_aligned();
+            return if buffer.is_c_contiguous() && aligned {
+                Ok(Some(Elements::Borrowed(buffer)))
+            } else {
+                Ok(Some(Elements::Copied(buffer.to_vec(obj.py())?)))
+            };
+        }
+
+        Ok(obj.extract::<Vec<T>>().ok().map(Elements::Copied))
+    }
+
+    fn as_slice(&self) -> &[T] {
+        match self {
+            Elements::Borrowed(buffer) if buffer.item_count() > 0 => {
+                // SAFETY: the buffer was checked to be C-contiguous and aligned, and holds
+                // `item_count` elements of `T`. It stays exported while borrowed from `self`.
+                unsafe { slice::from_raw_parts(buffer.buf_ptr().cast(), buffer.item_count()) }
+            }
+            Elements::Borrowed(_) => &[],
+            Elements::Copied(elements) => elements,
+        }
+    }
+}
+
+/// A sorted array passed in from Python.
+enum Data {
+    Int(Elements<i64>),
+    Float(Elements<f64>),
+}
+
+impl Data {
+    fn extract(obj: &Bound<'_, PyAny>) -> PyResult<Self> {
+        if let Some(ints) = Elements::extract(obj)? {
+            Ok(Data::Int(ints))
+        } else if let Some(floats) = Elements::extract(obj)? {
+            Ok(Data::Float(floats))
+        } else {
+            Err(PyTypeError::new_err(
+                "expected a sequence or buffer of ints or floats",
+            ))
+        }
+    }
+}
+
+/// The raw parts of a slice, for moving it into a closure that runs without the GIL.
+struct RawSlice<T>(*const T, usize);
+
+// SAFETY: only created from slices of `i64` or `f64`, which are `Send`.
+unsafe impl<T> Send for RawSlice<T> {}
+
+/// Searches `data` for each of `keys` with `compare`, releasing the GIL if there are enough keys.
+fn batch<T, F>(
+    py: Python<'_>,
+    data: &Elements<T>,
+    keys: &Bound<'_, PyAny>,
+    compare: F,
+) -> PyResult<Vec<Result<usize, usize>>>
+where
+    T: Element + Copy + for<'a, 'py> FromPyObject<'a, 'py>,
+    F: Fn(&T, &T) -> Ordering + Send,
+{
+    let keys = Elements::<T>::extract(keys)?.ok_or_else(|| {
+        PyTypeError::new_err("expected keys of the same kind as the array's elements")
+    })?;
+    let (data, keys) = (data.as_slice(), keys.as_slice());
+
+    if keys.len() < DETACH_THRESHOLD {
+        return Ok(data.bl_binary_search_batch_by::<DEFAULT_INTERLEAVE, _, _>(keys, compare));
+    }
+
+    let raw = (
+        RawSlice(data.as_ptr(), data.len()),
+        RawSlice(keys.as_ptr(), keys.len()),
+    );
+    Ok(py.detach(move || {
+        let (data, keys) = raw;
+        // SAFETY: both slices outlive the closure, as their owners are borrowed by this
+        // function, and buffers stay exported (so can't be freed) until their owners drop.
+        let (data, keys) = unsafe {
+            (
+                slice::from_raw_parts(data.0, data.1),
+                slice::from_raw_parts(keys.0, keys.1),
+            )
+        };
+        data.bl_binary_search_batch_by::<DEFAULT_INTERLEAVE, _, _>(keys, compare)
+    }))
+}
+
+/// Which index a search returns.
+#[derive(Clone, Copy)]
+enum Side {
+    /// The first element equal to the key, if any.
+    Match,
+    /// The first element not less than the key.
+    Lower,
+    /// The first element greater than the key.
+    Upper,
+}
+
+/// Adapts `compare` to search for `side`. For the bounds, it never returns `Equal`, so
+/// searches always end in `Err` with the bound.
+fn by_side<T, F>(side: Side, compare: F) -> impl Fn(&T, &T) -> Ordering + Send
+where
+    F: Fn(&T, &T) -> Ordering + Send,
+{
+    move |p, k| match (compare(p, k), side) {
+        (Ordering::Equal, Side::Match) => Ordering::Equal,
+        (Ordering::Less, _) | (Ordering::Equal, Side::Upper) => Ordering::Less,
+        _ => Ordering::Greater,
+    }
+}
+
+fn search_one(
+    data: &Bound<'_, PyAny>,
+    key: &Bound<'_, PyAny>,
+    side: Side,
+) -> PyResult<Result<usize, usize>> {
+    Ok(match Data::extract(data)? {
+        Data::Int(ints) => {
+            let (key, compare) = (key.extract::<i64>()?, by_side(side, i64::cmp));
+            ints.as_slice().bl_binary_search_by(|p| compare(p, &key))
+        }
+        Data::Float(floats) => {
+            let (key, compare) = (key.extract::<f64>()?, by_side(side, f64::total_cmp));
+            floats.as_slice().bl_binary_search_by(|p| compare(p, &key))
+        }
+    })
+}
+
+fn search_many(
+    data: &Bound<'_, PyAny>,
+    keys: &Bound<'_, PyAny>,
+    side: Side,
+) -> PyResult<Vec<Result<usize, usize>>> {
+    let py = data.py();
+    match Data::extract(data)? {
+        Data::Int(ints) => batch(py, &ints, keys, by_side(side, i64::cmp)),
+        Data::Float(floats) => batch(py, &floats, keys, by_side(side, f64::total_cmp)),
+    }
+}
+
+fn index(result: Result<usize, usize>) -> usize {
+    result.unwrap_or_else(|index| index)
+}
+
+/// Returns the index of the first element of sorted `data` equal to `key`, or `None`.
+#[pyfunction]
+fn search(data: &Bound<'_, PyAny>, key: &Bound<'_, PyAny>) -> PyResult<Option<usize>> {
+    Ok(search_one(data, key, Side::Match)?.ok())
+}
+
+/// Returns the index of the first element of sorted `data` not less than `key`.
+#[pyfunction]
+fn lower_bound(data: &Bound<'_, PyAny>, key: &Bound<'_, PyAny>) -> PyResult<usize> {
+    Ok(index(search_one(data, key, Side::Lower)?))
+}
+
+/// Returns the index of the first element of sorted `data` greater than `key`.
+#[pyfunction]
+fn upper_bound(data: &Bound<'_, PyAny>, key: &Bound<'_, PyAny>) -> PyResult<usize> {
+    Ok(index(search_one(data, key, Side::Upper)?))
+}
+
+/// Returns `search(data, key)` for each of `keys`.
+#[pyfunction]
+fn search_batch(data: &Bound<'_, PyAny>, keys: &Bound<'_, PyAny>) -> PyResult<Vec<Option<usize>>> {
+    Ok(search_many(data, keys, Side::Match)?
+        .into_iter()
+        .map(Result::ok)
+        .collect())
+}
+
+/// Returns `lower_bound(data, key)` for each of `keys`.
+#[pyfunction]
+fn lower_bound_batch(data: &Bound<'_, PyAny>, keys: &Bound<'_, PyAny>) -> PyResult<Vec<usize>> {
+    Ok(search_many(data, keys, Side::Lower)?
+        .into_iter()
+        .map(index)
+        .collect())
+}
+
+/// Returns `upper_bound(data, key)` for each of `keys`.
+#[pyfunction]
+fn upper_bound_batch(data: &Bound<'_, PyAny>, keys: &Bound<'_, PyAny>) -> PyResult<Vec<usize>> {
+    Ok(search_many(data, keys, Side::Upper)?
+        .into_iter()
+        .map(index)
+        .collect())
+}
+
+/// The `shar_search` Python module.
+#[pymodule]
+pub fn shar_search(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(search, module)?)?;
+    module.add_function(wrap_pyfunction!(lower_bound, module)?)?;
+    module.add_function(wrap_pyfunction!(upper_bound, module)?)?;
+    module.add_function(wrap_pyfunction!(search_batch, module)?)?;
+    module.add_function(wrap_pyfunction!(lower_bound_batch, module)?)?;
+    module.add_function(wrap_pyfunction!(upper_bound_batch, module)?)?;
+    module.add("DETACH_THRESHOLD", DETACH_THRESHOLD)
+}
+
+#[cfg(test)]
+mod test {
+    use std::ffi::CStr;
+
+    use pyo3::types::{PyDict, PyModule};
+
+    use super::*;
+
+    /// Runs `code` with the module importable as `shar_search`, and `array` imported.
+    fn run(code: &CStr) {
+        Python::initialize();
+        Python::attach(|py| {
+            let module = PyModule::new(py, "shar_search").unwrap();
+            shar_search(&module).unwrap();
+
+            let modules = py.import("sys").unwrap().getattr("modules").unwrap();
+            modules.set_item("shar_search", &module).unwrap();
+
+            let locals = PyDict::new(py);
+            locals
+                .set_item("array", py.import("array").unwrap())
+                .unwrap();
+
+            if let Err(err) = py.run(code, Some(&locals), None) {
+                let traceback = err.traceback(py).and_then(|t| t.format().ok());
+                panic!("{}{err}", traceback.unwrap_or_default());
+            }
+        });
+    }
+
+    #[test]
+    fn test_searches() {
+        run(cr#"
+from shar_search import *
+
+for data in ([10, 20, 20, 30], array.array("q", [10, 20, 20, 30]), (10.0, 20.0, 20.0, 30.0)):
+    assert search(data, 20) == 1
+    assert search(data, 25) is None
+    assert [lower_bound(data, k) for k in (5, 10, 20, 25, 30, 35)] == [0, 0, 1, 3, 3, 4]
+    assert [upper_bound(data, k) for k in (5, 10, 20, 25, 30, 35)] == [0, 1, 3, 3, 4, 4]
+    assert search_batch(data, [30, 5, 20]) == [3, None, 1]
+    assert lower_bound_batch(data, [20, 35]) == [1, 4]
+    assert upper_bound_batch(data, array.array("q", [20, 35])) == [3, 4]
+
+assert search([], 1) is None and lower_bound([], 1) == 0 and upper_bound_batch([], [1]) == [0]
+"#);
+    }
+
+    #[test]
+    fn test_buffers() {
+        run(cr#"
+from shar_search import *
+
+data = array.array("q", range(0, 100, 2))
+floats = array.array("d", [x / 4 for x in range(100)])
+strided = memoryview(data)[::3]
+narrow = array.array("i", range(50))
+
+for k in range(-1, 101):
+    assert lower_bound(strided, k) == lower_bound(list(strided), k)
+    assert lower_bound(narrow, k) == min(max(k, 0), 50)
+    assert search(floats, k / 8) == (k // 2 if k % 2 == 0 and k >= 0 else None)
+
+assert search(memoryview(data), 98) == 49
+assert search(floats, 0) == 0
+
+nan = float("nan")
+with_nan = array.array("d", sorted([1.0, -0.0, 0.0, nan, float("inf")], key=lambda x: (x != x, x)))
+assert search(with_nan, nan) == 4 and lower_bound(with_nan, float("inf")) == 3
+
+for bad, error in ((memoryview(array.array("q", range(4))).cast("B").cast("q", [2, 2]), ValueError),
+                   ("abc", TypeError), ([1, "a"], TypeError)):
+    try:
+        search(bad, 1)
+    except error:
+        pass
+    else:
+        raise AssertionError(repr(bad))
+
+try:
+    search(data, 1.5)
+except TypeError:
+    pass
+else:
+    raise AssertionError("float key for int data")
+"#);
+    }
+
+    #[test]
+    fn test_large_batches() {
+        run(cr#"
+from shar_search import *
+
+data = array.array("q", (x * 3 for x in range(10000)))
+keys = array.array("q", range(-5, 30005))
+assert len(keys) >= DETACH_THRESHOLD
+
+assert search_batch(data, keys) == [search(data, k) for k in keys]
+assert lower_bound_batch(data, keys) == [lower_bound(data, k) for k in keys]
+assert upper_bound_batch(data, list(keys)) == [upper_bound(data, k) for k in keys]
+
+floats = array.array("d", (x / 2 for x in data))
+assert lower_bound_batch(floats, [k / 2 for k in keys]) == lower_bound_batch(data, keys)
+"#);
+    }
+
+    #[test]
+    fn test_contiguous_buffers_are_borrowed() {
+        Python::initialize();
+        Python::attach(|py| {
+            let data = py
+                .import("array")
+                .unwrap()
+                .call_method1("array", ("q", vec![1_i64, 2, 3]))
+                .unwrap();
+
+            let buffer = PyBuffer::<i64>::get(&data).unwrap();
+            let elements = Elements::<i64>::extract(&data).unwrap().unwrap();
+            assert!(matches!(elements, Elements::Borrowed(_)));
+            assert_eq!(
+                elements.as_slice().as_ptr(),
+                buffer.buf_ptr().cast_const().cast()
+            );
+            assert_eq!(elements.as_slice(), [1, 2, 3]);
+
+            let list = vec![1_i64, 2, 3].into_pyobject(py).unwrap().into_any();
+            assert!(matches!(
+                Elements::<i64>::extract(&list),
+                Ok(Some(Elements::Copied(_)))
+            ));
+        });
+    }
+}
@@ -0,0 +1,368 @@
+//! Quantiles and percentile ranks of sorted numeric samples.
+//!
+//! A quantile `q` of a sorted sample of `n` values falls at the fractional index
+//! `h = (n - 1) * q`. Unless `h` is a whole number, it falls between two values, and the
+//! [`QuantileMethod`] decides what to return. The methods match NumPy's `method` argument to
+//! `numpy.quantile` of the same name, and return the same results.
+//!
+//! Every value is converted to `f64` before interpolating, so integers beyond `2^53` lose
+//! precision.
+//!
+//! ```
+//! use shar_search::quantiles::{percentile_rank, quantile, QuantileMethod};
+//!
+//! let mut latencies_ms = [12, 9, 30, 11, 10, 250, 14, 13, 11, 12, 15];
+//! latencies_ms.sort_unstable();
+//!
+//! assert_eq!(quantile(&latencies_ms, 0.5, QuantileMethod::Linear), Ok(12.0));
+//! assert_eq!(quantile(&latencies_ms, 0.95, QuantileMethod::Linear), Ok(140.0));
+//! assert_eq!(quantile(&latencies_ms, 0.95, QuantileMethod::Lower), Ok(30.0));
+//! // 8 of the 11 samples are at most 14ms.
+//! assert_eq!(percentile_rank(&latencies_ms, 14.0).map(f64::round), Ok(73.0));
+//! ```
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::{error::Error, fmt};
+
+#[cfg(feature = "alloc")]
+use crate::batch::{SharBatchSearch, DEFAULT_INTERLEAVE};
+use crate::SharBinarySearch;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// A numeric type that quantiles can be taken of. Implemented for the primitive integers and
+/// floats.
+pub trait Sample: sealed::Sealed + Copy + PartialOrd {
+    /// Converts the value to an `f64`, rounding to the nearest representable value.
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! impl_sample {
+    ($($ty:ty),*) => {$(
+        impl sealed::Sealed for $ty {}
+
+        impl Sample for $ty {
+            #[inline]
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+        }
+    )*};
+}
+
+impl_sample!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+/// How to pick a quantile that falls between two values `a` and `b` of the sample, at the
+/// fractional index `h`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum QuantileMethod {
+    /// Interpolates linearly between `a` and `b`. This is R's type 7 and NumPy's default.
+    #[default]
+    Linear,
+    /// The value at the nearest index, rounding half to even as NumPy does.
+    Nearest,
+    /// The lower of the two values, `a`.
+    Lower,
+    /// The higher of the two values, `b`.
+    Higher,
+    /// The mean of `a` and `b`.
+    Midpoint,
+}
+
+/// The error returned when a quantile or rank cannot be computed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QuantileError {
+    /// The sample is empty.
+    Empty,
+    /// The quantile is not between `0.0` and `1.0`, or is NaN.
+    OutOfRange(f64),
+}
+
+impl fmt::Display for QuantileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "the sample is empty"),
+            Self::OutOfRange(q) => write!(f, "quantile {q} is not between 0 and 1"),
+        }
+    }
+}
+
+impl Error for QuantileError {}
+
+/// Interpolates between `a` and `b` as NumPy does, from whichever end is closer, so that the
+/// result is exact at both ends and monotonic in `t`.
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    let diff = b - a;
+    if t >= 0.5 {
+        b - diff * (1.0 - t)
+    } else {
+        a + diff * t
+    }
+}
+
+/// Returns the quantile `q` of a non-empty `sorted`, with `q` already validated.
+fn quantile_unchecked<T: Sample>(sorted: &[T], q: f64, method: QuantileMethod) -> f64 {
+    let h = (sorted.len() - 1) as f64 * q;
+    // `h` is non-negative, so truncating is flooring.
+    let low = (h as usize).min(sorted.len() - 1);
+    let fraction = h - low as f64;
+    let high = (low + usize::from(fraction > 0.0)).min(sorted.len() - 1);
+    let (a, b) = (sorted[low].to_f64(), sorted[high].to_f64());
+
+    match method {
+        QuantileMethod::Linear => lerp(a, b, fraction),
+        QuantileMethod::Nearest => {
+            if fraction > 0.5 || (fraction == 0.5 && low % 2 == 1) {
+                b
+            } else {
+                a
+            }
+        }
+        QuantileMethod::Lower => a,
+        QuantileMethod::Higher => b,
+        QuantileMethod::Midpoint => lerp(a, b, 0.5),
+    }
+}
+
+fn check_quantile(q: f64) -> Result<(), QuantileError> {
+    if (0.0..=1.0).contains(&q) {
+        Ok(())
+    } else {
+        Err(QuantileError::OutOfRange(q))
+    }
+}
+
+/// Returns the quantile `q` of `sorted`, from `0.0` for the smallest value to `1.0` for the
+/// largest, picked by `method`. Note it is assumed that `sorted` is sorted.
+///
+/// # Errors
+///
+/// Returns an error if `sorted` is empty or `q` is not between `0.0` and `1.0`.
+pub fn quantile<T: Sample>(
+    sorted: &[T],
+    q: f64,
+    method: QuantileMethod,
+) -> Result<f64, QuantileError> {
+    if sorted.is_empty() {
+        return Err(QuantileError::Empty);
+    }
+    check_quantile(q)?;
+    Ok(quantile_unchecked(sorted, q, method))
+}
+
+/// Returns each of the quantiles `qs` of `sorted`, picked by `method`. Note it is assumed that
+/// `sorted` is sorted. See [`quantile`].
+///
+/// ```
+/// use shar_search::quantiles::{quantiles, QuantileMethod};
+///
+/// let sorted: Vec<u32> = (1..=1000).collect();
+/// let [p50, p95, p999] = quantiles(&sorted, &[0.5, 0.95, 0.999], QuantileMethod::Linear)
+///     .unwrap()
+///     .try_into()
+///     .unwrap();
+/// assert_eq!((p50, p95, p999), (500.5, 950.05, 999.001));
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if `sorted` is empty or any of `qs` is not between `0.0` and `1.0`, in
+/// which case none are computed.
+#[cfg(feature = "alloc")]
+pub fn quantiles<T: Sample>(
+    sorted: &[T],
+    qs: &[f64],
+    method: QuantileMethod,
+) -> Result<Vec<f64>, QuantileError> {
+    if sorted.is_empty() {
+        return Err(QuantileError::Empty);
+    }
+    qs.iter().try_for_each(|&q| check_quantile(q))?;
+    Ok(qs
+        .iter()
+        .map(|&q| quantile_unchecked(sorted, q, method))
+        .collect())
+}
+
+/// Returns the percentage of `sorted` that is less than or equal to `value`, from `0.0` to
+/// `100.0`, found with the branchless search. This is SciPy's `percentileofscore` with
+/// `kind="weak"`, and the inverse of the [`QuantileMethod::Higher`] quantile. Note it is
+/// assumed that `sorted` is sorted and has no NaNs; a NaN `value` ranks `0.0`.
+///
+/// # Errors
+///
+/// Returns an error if `sorted` is empty.
+pub fn percentile_rank<T: Sample>(sorted: &[T], value: f64) -> Result<f64, QuantileError> {
+    if sorted.is_empty() {
+        return Err(QuantileError::Empty);
+    }
+    let rank = sorted.bl_partition_point(|x| x.to_f64() <= value);
+    Ok(to_percent(rank, sorted.len()))
+}
+
+/// Returns the percentile rank of each of `values` in `sorted`. See [`percentile_rank`].
+///
+/// If `values` are sorted, each search gallops forward from the previous one; otherwise, the
+/// searches are interleaved.
+///
+/// # Errors
+///
+/// Returns an error if `sorted` is empty.
+#[cfg(feature = "alloc")]
+pub fn percentile_ranks<T: Sample>(
+    sorted: &[T],
+    values: &[f64],
+) -> Result<Vec<f64>, QuantileError> {
+    if sorted.is_empty() {
+        return Err(QuantileError::Empty);
+    }
+
+    // Never `Equal`, so every result is the `Err` of the first element not counted.
+    let counted = |x: &T, value: &f64| {
+        if x.to_f64() <= *value {
+            core::cmp::Ordering::Less
+        } else {
+            core::cmp::Ordering::Greater
+        }
+    };
+    let results = if values.is_sorted() {
+        sorted.bl_binary_search_sorted_keys_by(values, counted)
+    } else {
+        sorted.bl_binary_search_batch_by::<DEFAULT_INTERLEAVE, _, _>(values, counted)
+    };
+
+    Ok(results
+        .into_iter()
+        .map(|result| to_percent(result.unwrap_or_else(|rank| rank), sorted.len()))
+        .collect())
+}
+
+fn to_percent(rank: usize, n: usize) -> f64 {
+    100.0 * rank as f64 / n as f64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Checks against results from NumPy 1.26's `numpy.quantile`.
+    #[test]
+    fn test_numpy_reference() {
+        use QuantileMethod::*;
+
+        let one_to_ten: Vec<f64> = (1..=10).map(f64::from).collect();
+        let cases: [(f64, [f64; 5]); 6] = [
+            // q, then linear, nearest, lower, higher and midpoint.
+            (0.0, [1.0, 1.0, 1.0, 1.0, 1.0]),
+            (0.25, [3.25, 3.0, 3.0, 4.0, 3.5]),
+            (0.5, [5.5, 5.0, 5.0, 6.0, 5.5]),
+            (0.95, [9.55, 10.0, 9.0, 10.0, 9.5]),
+            (0.999, [9.991, 10.0, 9.0, 10.0, 9.5]),
+            (1.0, [10.0, 10.0, 10.0, 10.0, 10.0]),
+        ];
+        for (q, expected) in cases {
+            for (method, expected) in [Linear, Nearest, Lower, Higher, Midpoint]
+                .into_iter()
+                .zip(expected)
+            {
+                let actual = quantile(&one_to_ten, q, method).unwrap();
+                assert!(
+                    (actual - expected).abs() < 1e-12,
+                    "{method:?} at {q}: {actual}"
+                );
+            }
+        }
+
+        // The example from NumPy's documentation, flattened and sorted.
+        let sorted = [1, 2, 3, 4, 7, 10];
+        assert_eq!(quantile(&sorted, 0.5, Linear), Ok(3.5));
+        assert_eq!(quantile(&sorted, 0.25, Linear), Ok(2.25));
+        // `h = 2.5` rounds to the even index 2, and `h = 1.5` to 2 as well.
+        assert_eq!(quantile(&sorted, 0.5, Nearest), Ok(3.0));
+        assert_eq!(quantile(&sorted, 0.3, Nearest), Ok(3.0));
+    }
+
+    #[test]
+    fn test_small_samples() {
+        for method in [
+            QuantileMethod::Linear,
+            QuantileMethod::Nearest,
+            QuantileMethod::Lower,
+            QuantileMethod::Higher,
+            QuantileMethod::Midpoint,
+        ] {
+            for q in [0.0, 0.3, 0.5, 1.0] {
+                assert_eq!(quantile(&[7_i8], q, method), Ok(7.0));
+            }
+        }
+
+        let pair = [-2.0_f32, 6.0];
+        assert_eq!(quantile(&pair, 0.25, QuantileMethod::Linear), Ok(0.0));
+        assert_eq!(quantile(&pair, 0.5, QuantileMethod::Linear), Ok(2.0));
+        // Exactly halfway rounds to the even index, 0.
+        assert_eq!(quantile(&pair, 0.5, QuantileMethod::Nearest), Ok(-2.0));
+        assert_eq!(quantile(&pair, 0.51, QuantileMethod::Nearest), Ok(6.0));
+        assert_eq!(quantile(&pair, 0.5, QuantileMethod::Midpoint), Ok(2.0));
+        assert_eq!(quantile(&pair, 0.01, QuantileMethod::Higher), Ok(6.0));
+        assert_eq!(quantile(&pair, 0.99, QuantileMethod::Lower), Ok(-2.0));
+    }
+
+    #[test]
+    fn test_errors() {
+        let empty: [u32; 0] = [];
+        assert_eq!(
+            quantile(&empty, 0.5, QuantileMethod::Linear),
+            Err(QuantileError::Empty)
+        );
+        assert_eq!(percentile_rank(&empty, 1.0), Err(QuantileError::Empty));
+        for q in [-0.1, 1.1, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                quantile(&[1], q, QuantileMethod::Linear),
+                Err(QuantileError::OutOfRange(_))
+            ));
+        }
+        assert_eq!(
+            QuantileError::OutOfRange(1.5).to_string(),
+            "quantile 1.5 is not between 0 and 1"
+        );
+    }
+
+    #[test]
+    fn test_percentile_rank() {
+        let sorted = [1, 2, 2, 3, 5];
+        assert_eq!(percentile_rank(&sorted, 0.0), Ok(0.0));
+        assert_eq!(percentile_rank(&sorted, 2.0), Ok(60.0));
+        assert_eq!(percentile_rank(&sorted, 2.5), Ok(60.0));
+        assert_eq!(percentile_rank(&sorted, 5.0), Ok(100.0));
+        assert_eq!(percentile_rank(&sorted, f64::NAN), Ok(0.0));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_batched() {
+        let sorted: Vec<u64> = (0..101).map(|i| i * i).collect();
+        let qs = [0.9, 0.1, 0.5, 1.0, 0.0];
+        for method in [QuantileMethod::Linear, QuantileMethod::Nearest] {
+            let batched = quantiles(&sorted, &qs, method).unwrap();
+            for (q, actual) in qs.iter().zip(batched) {
+                assert_eq!(quantile(&sorted, *q, method), Ok(actual));
+            }
+        }
+        assert_eq!(
+            quantiles(&sorted, &[0.5, 2.0], QuantileMethod::Linear),
+            Err(QuantileError::OutOfRange(2.0))
+        );
+
+        let mut values = vec![50.0, 0.0, 10_000.0, 2500.0, 2501.0, -1.0];
+        for _ in 0..2 {
+            let ranks = percentile_ranks(&sorted, &values).unwrap();
+            for (value, rank) in values.iter().zip(ranks) {
+                assert_eq!(percentile_rank(&sorted, *value), Ok(rank));
+            }
+            values.sort_by(f64::total_cmp);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! A branchless search that quarters the slice at each level instead of halving it.
+
+use core::cmp::Ordering;
+
+/// Searches `slice` for `x`, probing three pivots per level.
+///
+/// Halving takes about `log₂ n` levels, each waiting on the load of the level before. Quartering
+/// takes half as many, at the cost of three comparisons per level rather than one. The three
+/// probes of a level don't depend on each other, so they are loaded together, and when the
+/// slice is out of cache, their misses overlap.
+///
+/// Like [`search_indices`](crate::search_indices), this returns the *first* match.
+#[inline]
+pub(crate) fn search_4ary<T: Ord>(slice: &[T], x: &T) -> Result<usize, usize> {
+    if slice.is_empty() {
+        return Err(0);
+    }
+
+    // The index of the first element not less than `x` is in `base..=base + size`.
+    let mut base = 0;
+    let mut size = slice.len();
+
+    while size >= 4 {
+        let quarter = size / 4;
+        // SAFETY: `base + 3 * quarter < base + size <= slice.len()`.
+        let (first, second, third) = unsafe {
+            (
+                slice.get_unchecked(base + quarter),
+                slice.get_unchecked(base + 2 * quarter),
+                slice.get_unchecked(base + 3 * quarter),
+            )
+        };
+        // The slice is sorted, so the pivots less than `x` are a prefix of the three, and
+        // counting them picks the quarter.
+        let less = (first < x) as usize + (second < x) as usize + (third < x) as usize;
+        base += less * quarter;
+        // The last quarter takes the remainder.
+        size -= 3 * quarter;
+    }
+
+    // At most two more halvings.
+    while size > 1 {
+        let half = size / 2;
+        // SAFETY: `base + half < base + size <= slice.len()`.
+        if unsafe { slice.get_unchecked(base + half) } < x {
+            base += half;
+        }
+        size -= half;
+    }
+
+    // SAFETY: `size` is 1, so `base < slice.len()`.
+    match unsafe { slice.get_unchecked(base) }.cmp(x) {
+        Ordering::Less if base + 1 < slice.len() && slice[base + 1] == *x => Ok(base + 1),
+        Ordering::Less => Err(base + 1),
+        Ordering::Equal => Ok(base),
+        Ordering::Greater => Err(base),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::search_4ary;
+    use crate::{
+        reference::{self, assert_matches_reference, inputs},
+        test_util::XorShift,
+        SharBinarySearch,
+    };
+
+    #[test]
+    fn test_against_reference() {
+        assert_matches_reference!(
+            for (slice, x) in inputs::slices_with_keys(172) =>
+            slice.bl_binary_search_4ary(&x),
+            reference::binary_search(&slice, &x),
+        );
+
+        // Every length up to a few levels, to cover each shape of the final window.
+        let mut rng = XorShift::new(172);
+        let lengths = (0..=1025_u32).flat_map(|len| {
+            // Distinct even values, so there are misses between every pair, and long runs of
+            // duplicates, which must return the first of the run.
+            let distinct: Vec<u32> = (0..len).map(|i| i * 2).collect();
+            let mut runs: Vec<u32> = (0..len).map(|_| rng.below(8) as u32 * 2).collect();
+            runs.sort_unstable();
+            let keys = (0..=len * 2 + 1).map(move |x| (distinct.clone(), x));
+            keys.chain((0..=17).map(move |x| (runs.clone(), x)))
+        });
+        assert_matches_reference!(
+            for (slice, x) in lengths =>
+            slice.bl_binary_search_4ary(&x),
+            reference::binary_search(&slice, &x),
+        );
+    }
+
+    #[test]
+    fn test_small() {
+        assert_eq!(search_4ary(&[] as &[i32], &1), Err(0));
+        assert_eq!(search_4ary(&[1], &0), Err(0));
+        assert_eq!(search_4ary(&[1], &1), Ok(0));
+        assert_eq!(search_4ary(&[1], &2), Err(1));
+        assert_eq!(search_4ary(&[1, 1, 1, 1, 1], &1), Ok(0));
+        assert_eq!(search_4ary(&[0, 1, 1, 1, 1], &1), Ok(1));
+        assert_eq!(search_4ary(&[0, 0, 0, 0, 1], &1), Ok(4));
+    }
+}
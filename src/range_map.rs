@@ -0,0 +1,492 @@
+//! A map from non-overlapping half-open key ranges to values, such as byte ranges to permissions
+//! or time ranges to configurations.
+
+use alloc::vec::Vec;
+use core::{borrow::Borrow, fmt, iter::FusedIterator, ops::Range, slice};
+
+use crate::SharBinarySearch;
+
+/// A map from non-overlapping, non-empty half-open ranges of keys to values, stored as
+/// contiguous `(Range<K>, V)` pairs sorted by start.
+///
+/// Inserting a range overwrites whatever it overlaps: ranges it covers are removed, and ranges
+/// that stick out on either side are truncated (or split in two, if the new range is inside
+/// one), like the [`rangemap`](https://docs.rs/rangemap) crate. Lookups find the range
+/// containing a key with Shar's branchless binary search.
+///
+/// [`SharRangeMap::insert`] keeps neighbouring ranges separate even if they have equal values,
+/// so each range stays as inserted. [`SharRangeMap::insert_coalescing`] instead merges the new
+/// range with touching neighbours of equal value.
+///
+/// ```
+/// use shar_search::SharRangeMap;
+///
+/// let mut permissions = SharRangeMap::new();
+/// permissions.insert(0..4096, "r-x");
+/// permissions.insert(4096..8192, "rw-");
+/// permissions.insert(1024..2048, "r--");
+///
+/// assert_eq!(permissions.get(&1500), Some(&"r--"));
+/// assert_eq!(permissions.get(&3000), Some(&"r-x"));
+/// assert_eq!(permissions.get(&9000), None);
+/// assert!(permissions
+///     .iter()
+///     .map(|(range, _)| range.clone())
+///     .eq([0..1024, 1024..2048, 2048..4096, 4096..8192]));
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct SharRangeMap<K, V> {
+    inner: Vec<(Range<K>, V)>,
+}
+
+impl<K, V> Default for SharRangeMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> SharRangeMap<K, V> {
+    /// Creates a new, empty map.
+    pub const fn new() -> Self {
+        Self { inner: Vec::new() }
+    }
+
+    /// Returns the number of ranges in the map.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns whether the map has no ranges.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Removes every range from the map.
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    /// Returns the ranges and their values as a slice, sorted by start.
+    pub fn as_slice(&self) -> &[(Range<K>, V)] {
+        &self.inner
+    }
+
+    /// Returns an iterator over the ranges and their values, in order.
+    pub fn iter(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (&Range<K>, &V)> + ExactSizeIterator + '_ {
+        self.inner.iter().map(|(range, value)| (range, value))
+    }
+}
+
+impl<K: Ord, V> SharRangeMap<K, V> {
+    /// Returns the range containing `key` and its value, or `None` if no range contains it.
+    pub fn get_range_value<Q>(&self, key: &Q) -> Option<(&Range<K>, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let after = self
+            .inner
+            .bl_partition_point(|(range, _)| range.start.borrow() <= key);
+        let (range, value) = &self.inner[after.checked_sub(1)?];
+        (key < range.end.borrow()).then_some((range, value))
+    }
+
+    /// Returns the value of the range containing `key`, or `None` if no range contains it.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get_range_value(key).map(|(_, value)| value)
+    }
+
+    /// Returns whether some range contains `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get_range_value(key).is_some()
+    }
+
+    /// Returns the index range of the entries overlapping `range`.
+    fn overlapping(&self, range: &Range<K>) -> Range<usize> {
+        let start = self.inner.bl_partition_point(|(r, _)| r.end <= range.start);
+        let end = start + self.inner[start..].bl_partition_point(|(r, _)| r.start < range.end);
+        start..end
+    }
+
+    /// Returns an iterator over the parts of `outer` that no range covers, in order.
+    ///
+    /// ```
+    /// use shar_search::SharRangeMap;
+    ///
+    /// let mut booked = SharRangeMap::new();
+    /// booked.insert(9..10, "standup");
+    /// booked.insert(13..15, "review");
+    ///
+    /// let free: Vec<_> = booked.gaps(&(8..18)).collect();
+    /// assert_eq!(free, [8..9, 10..13, 15..18]);
+    /// ```
+    pub fn gaps<'a>(&'a self, outer: &Range<K>) -> Gaps<'a, K, V>
+    where
+        K: Clone,
+    {
+        let overlapping = self.overlapping(outer);
+        Gaps {
+            entries: self.inner[overlapping].iter(),
+            next_start: (outer.start < outer.end).then(|| outer.start.clone()),
+            end: outer.end.clone(),
+        }
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> SharRangeMap<K, V> {
+    /// Removes `range` from the map: ranges it covers are removed, and ranges that overlap it
+    /// are truncated, or split in two if they stick out on both sides. An empty `range` does
+    /// nothing.
+    ///
+    /// ```
+    /// use shar_search::SharRangeMap;
+    ///
+    /// let mut map = SharRangeMap::new();
+    /// map.insert(0..10, 'a');
+    /// map.insert(10..20, 'b');
+    /// map.remove(5..12);
+    ///
+    /// assert!(map.iter().map(|(r, v)| (r.clone(), *v)).eq([(0..5, 'a'), (12..20, 'b')]));
+    /// ```
+    pub fn remove(&mut self, range: Range<K>) {
+        if range.start >= range.end {
+            return;
+        }
+        let Range { mut start, mut end } = self.overlapping(&range);
+        if start == end {
+            return;
+        }
+
+        let first = &mut self.inner[start];
+        if first.0.start < range.start && range.end < first.0.end {
+            // Split the only overlapping range around the removed one.
+            let right = (range.end..first.0.end.clone(), first.1.clone());
+            first.0.end = range.start;
+            self.inner.insert(start + 1, right);
+            return;
+        }
+        if first.0.start < range.start {
+            first.0.end = range.start.clone();
+            start += 1;
+        }
+        if let Some(last) = self.inner[start..end].last_mut() {
+            if range.end < last.0.end {
+                last.0.start = range.end;
+                end -= 1;
+            }
+        }
+        self.inner.drain(start..end);
+    }
+
+    /// Maps every key in `range` to `value`, overwriting whatever it overlaps (see
+    /// [`SharRangeMap::remove`]). Neighbouring ranges are kept separate even if they have the
+    /// same value. An empty `range` does nothing.
+    pub fn insert(&mut self, range: Range<K>, value: V) {
+        self.insert_at(range, value);
+    }
+
+    /// Removes `range` and inserts it with `value`, returning its index, or `None` if it was
+    /// empty.
+    fn insert_at(&mut self, range: Range<K>, value: V) -> Option<usize> {
+        if range.start >= range.end {
+            return None;
+        }
+        self.remove(range.clone());
+        let index = self
+            .inner
+            .bl_partition_point(|(r, _)| r.start < range.start);
+        self.inner.insert(index, (range, value));
+        Some(index)
+    }
+}
+
+impl<K: Ord + Clone, V: Clone + Eq> SharRangeMap<K, V> {
+    /// Maps every key in `range` to `value` like [`SharRangeMap::insert`], then merges it with
+    /// the ranges just before and after it if they touch it and have an equal value. If every
+    /// insertion coalesces, no two touching ranges have equal values.
+    ///
+    /// ```
+    /// use shar_search::SharRangeMap;
+    ///
+    /// let mut map = SharRangeMap::new();
+    /// map.insert_coalescing(0..5, true);
+    /// map.insert_coalescing(10..15, true);
+    /// map.insert_coalescing(5..10, true);
+    /// map.insert_coalescing(7..8, false);
+    ///
+    /// let ranges: Vec<_> = map.iter().map(|(r, v)| (r.clone(), *v)).collect();
+    /// assert_eq!(ranges, [(0..7, true), (7..8, false), (8..15, true)]);
+    /// ```
+    pub fn insert_coalescing(&mut self, range: Range<K>, value: V) {
+        let Some(mut index) = self.insert_at(range, value) else {
+            return;
+        };
+
+        let touches = |left: &(Range<K>, V), right: &(Range<K>, V)| {
+            left.0.end == right.0.start && left.1 == right.1
+        };
+        if index > 0 && touches(&self.inner[index - 1], &self.inner[index]) {
+            let (range, _) = self.inner.remove(index);
+            index -= 1;
+            self.inner[index].0.end = range.end;
+        }
+        if index + 1 < self.inner.len() && touches(&self.inner[index], &self.inner[index + 1]) {
+            let (range, _) = self.inner.remove(index + 1);
+            self.inner[index].0.end = range.end;
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for SharRangeMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the parts of a range that no range of a [`SharRangeMap`] covers, in order.
+///
+/// Created by [`SharRangeMap::gaps`].
+#[derive(Clone, Debug)]
+pub struct Gaps<'a, K, V> {
+    /// The entries overlapping the outer range.
+    entries: slice::Iter<'a, (Range<K>, V)>,
+    /// The start of the next gap, or `None` once the iterator is exhausted.
+    next_start: Option<K>,
+    end: K,
+}
+
+impl<K: Ord + Clone, V> Iterator for Gaps<'_, K, V> {
+    type Item = Range<K>;
+
+    fn next(&mut self) -> Option<Range<K>> {
+        let mut start = self.next_start.take()?;
+        // The entries are sorted and overlap the outer range, so each ends past the last.
+        for (range, _) in self.entries.by_ref() {
+            if start < range.start {
+                self.next_start = Some(range.end.clone());
+                return Some(start..range.start.clone());
+            }
+            start = range.end.clone();
+        }
+        (start < self.end).then(|| start..self.end.clone())
+    }
+}
+
+impl<K: Ord + Clone, V> FusedIterator for Gaps<'_, K, V> {}
+
+#[cfg(test)]
+mod test {
+    use std::ops::Range;
+
+    use super::SharRangeMap;
+    use crate::test_util::XorShift;
+
+    fn ranges(map: &SharRangeMap<u32, char>) -> Vec<(Range<u32>, char)> {
+        map.as_slice().to_vec()
+    }
+
+    /// A map of `10..20 -> 'a'` and `30..40 -> 'b'`, with `range` inserted as `'x'`.
+    fn inserted(range: Range<u32>) -> Vec<(Range<u32>, char)> {
+        let mut map = SharRangeMap::new();
+        map.insert(10..20, 'a');
+        map.insert(30..40, 'b');
+        map.insert(range, 'x');
+        ranges(&map)
+    }
+
+    #[test]
+    fn test_insert_overlap_cases() {
+        // Exact match.
+        assert_eq!(inserted(10..20), [(10..20, 'x'), (30..40, 'b')]);
+        // Covering one range, and covering both with room either side.
+        assert_eq!(inserted(5..25), [(5..25, 'x'), (30..40, 'b')]);
+        assert_eq!(inserted(0..50), [(0..50, 'x')]);
+        assert_eq!(inserted(10..40), [(10..40, 'x')]);
+        // Covered by a range, sharing neither end, its start, or its end.
+        assert_eq!(
+            inserted(12..15),
+            [(10..12, 'a'), (12..15, 'x'), (15..20, 'a'), (30..40, 'b')]
+        );
+        assert_eq!(
+            inserted(10..15),
+            [(10..15, 'x'), (15..20, 'a'), (30..40, 'b')]
+        );
+        assert_eq!(
+            inserted(15..20),
+            [(10..15, 'a'), (15..20, 'x'), (30..40, 'b')]
+        );
+        // Overlapping the left of a range, and the right.
+        assert_eq!(
+            inserted(5..15),
+            [(5..15, 'x'), (15..20, 'a'), (30..40, 'b')]
+        );
+        assert_eq!(
+            inserted(15..25),
+            [(10..15, 'a'), (15..25, 'x'), (30..40, 'b')]
+        );
+        // Overlapping the right of one range and the left of the next.
+        assert_eq!(
+            inserted(15..35),
+            [(10..15, 'a'), (15..35, 'x'), (35..40, 'b')]
+        );
+        // Touching without overlapping, and in a gap.
+        assert_eq!(
+            inserted(20..30),
+            [(10..20, 'a'), (20..30, 'x'), (30..40, 'b')]
+        );
+        assert_eq!(
+            inserted(0..10),
+            [(0..10, 'x'), (10..20, 'a'), (30..40, 'b')]
+        );
+        assert_eq!(
+            inserted(40..41),
+            [(10..20, 'a'), (30..40, 'b'), (40..41, 'x')]
+        );
+        // Empty ranges are ignored.
+        assert_eq!(inserted(15..15), [(10..20, 'a'), (30..40, 'b')]);
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 18..12;
+        assert_eq!(inserted(reversed), [(10..20, 'a'), (30..40, 'b')]);
+    }
+
+    #[test]
+    fn test_remove_and_lookups() {
+        let mut map = SharRangeMap::new();
+        map.insert(10..20, 'a');
+        map.insert(30..40, 'b');
+
+        assert_eq!(map.get(&9), None);
+        assert_eq!(map.get(&10), Some(&'a'));
+        assert_eq!(map.get(&19), Some(&'a'));
+        assert_eq!(map.get(&20), None);
+        assert_eq!(map.get_range_value(&35), Some((&(30..40), &'b')));
+        assert!(!map.contains_key(&40));
+
+        map.remove(12..14);
+        assert_eq!(ranges(&map), [(10..12, 'a'), (14..20, 'a'), (30..40, 'b')]);
+        map.remove(0..11);
+        map.remove(35..100);
+        assert_eq!(ranges(&map), [(11..12, 'a'), (14..20, 'a'), (30..35, 'b')]);
+        map.remove(20..30);
+        map.remove(11..35);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    #[allow(clippy::single_range_in_vec_init)]
+    fn test_gaps() {
+        let mut map = SharRangeMap::new();
+        map.insert(10..20, 'a');
+        map.insert(20..25, 'b');
+        map.insert(30..40, 'c');
+
+        let gaps = |outer: Range<u32>| map.gaps(&outer).collect::<Vec<_>>();
+        assert_eq!(gaps(0..50), [0..10, 25..30, 40..50]);
+        assert_eq!(gaps(15..35), [25..30]);
+        assert_eq!(gaps(12..18), []);
+        assert_eq!(gaps(26..28), [26..28]);
+        assert_eq!(gaps(40..40), []);
+        assert_eq!(gaps(5..10), [5..10]);
+        assert_eq!(
+            SharRangeMap::<u32, ()>::new()
+                .gaps(&(1..3))
+                .collect::<Vec<_>>(),
+            [1..3]
+        );
+    }
+
+    #[test]
+    fn test_coalescing() {
+        let mut map = SharRangeMap::new();
+        map.insert_coalescing(0..10, 'a');
+        map.insert_coalescing(20..30, 'a');
+        map.insert_coalescing(10..20, 'a');
+        assert_eq!(ranges(&map), [(0..30, 'a')]);
+
+        map.insert_coalescing(5..8, 'a');
+        assert_eq!(ranges(&map), [(0..30, 'a')]);
+        map.insert_coalescing(30..35, 'b');
+        map.insert_coalescing(35..40, 'a');
+        assert_eq!(ranges(&map), [(0..30, 'a'), (30..35, 'b'), (35..40, 'a')]);
+        map.insert_coalescing(30..35, 'a');
+        assert_eq!(ranges(&map), [(0..40, 'a')]);
+
+        // Without coalescing, equal neighbours stay apart.
+        let mut map = SharRangeMap::new();
+        map.insert(0..10, 'a');
+        map.insert(10..20, 'a');
+        assert_eq!(ranges(&map), [(0..10, 'a'), (10..20, 'a')]);
+    }
+
+    #[test]
+    fn test_against_point_model() {
+        const DOMAIN: u32 = 48;
+
+        let mut rng = XorShift::new(188);
+        for coalescing in [false, true] {
+            for _ in 0..200 {
+                let mut map = SharRangeMap::new();
+                let mut model: [Option<char>; DOMAIN as usize] = [None; DOMAIN as usize];
+
+                for _ in 0..rng.below(25) {
+                    let start = rng.below(u64::from(DOMAIN)) as u32;
+                    let end = (start + rng.below(16) as u32).min(DOMAIN);
+                    let value = (b'a' + rng.below(3) as u8) as char;
+
+                    let new = match rng.below(4) {
+                        0 => {
+                            map.remove(start..end);
+                            None
+                        }
+                        _ if coalescing => {
+                            map.insert_coalescing(start..end, value);
+                            Some(value)
+                        }
+                        _ => {
+                            map.insert(start..end, value);
+                            Some(value)
+                        }
+                    };
+                    model[start as usize..end as usize].fill(new);
+                }
+
+                let slice = map.as_slice();
+                assert!(slice.iter().all(|(r, _)| r.start < r.end));
+                assert!(slice.windows(2).all(|w| w[0].0.end <= w[1].0.start));
+                if coalescing {
+                    assert!(slice
+                        .windows(2)
+                        .all(|w| w[0].0.end < w[1].0.start || w[0].1 != w[1].1));
+                }
+                for key in 0..DOMAIN {
+                    assert_eq!(map.get(&key), model[key as usize].as_ref(), "key {key}");
+                }
+
+                let outer_start = rng.below(u64::from(DOMAIN)) as u32;
+                let outer = outer_start..DOMAIN;
+                let covered: Vec<bool> = outer.clone().map(|k| map.contains_key(&k)).collect();
+                for gap in map.gaps(&outer) {
+                    assert!(
+                        outer.start <= gap.start && gap.end <= outer.end && gap.start < gap.end
+                    );
+                    assert!(gap.clone().all(|k| !covered[(k - outer.start) as usize]));
+                    assert!(
+                        gap.start == outer.start || covered[(gap.start - 1 - outer.start) as usize]
+                    );
+                }
+                let uncovered: usize = map.gaps(&outer).map(|gap| gap.len()).sum();
+                assert_eq!(uncovered, covered.iter().filter(|&&c| !c).count());
+            }
+        }
+    }
+}
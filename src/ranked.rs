@@ -0,0 +1,385 @@
+//! A sorted container that answers rank and select queries quickly while it is being mutated.
+
+use alloc::vec::Vec;
+use core::{borrow::Borrow, fmt};
+
+use crate::{bit_floor, SharBinarySearch};
+
+/// The number of elements a block is split back down to. Blocks are split when they grow past
+/// twice this.
+const LOAD: usize = 512;
+
+/// A sorted collection that allows duplicates, like [`SortedVec`](crate::SortedVec), whose
+/// insertions and removals don't shift every later element, so that
+/// [`rank`](RankedSortedVec::rank) and [`select`](RankedSortedVec::select) queries stay cheap
+/// when interleaved with them.
+///
+/// The elements are stored in order in blocks of between one and `2 * LOAD` elements, where
+/// `LOAD` is 512, and a Fenwick tree over the blocks' lengths gives the number of elements
+/// before any block in `O(log b)` for `b` blocks. An operation finds its block with the
+/// branchless search, over the blocks' last elements by key or down the Fenwick tree by
+/// position, and then works within the block:
+///
+/// - [`insert`](Self::insert) and [`remove`](Self::remove) shift at most `2 * LOAD` elements and
+///   update the tree in `O(log b)`.
+/// - [`rank`](Self::rank), [`select`](Self::select) and [`contains`](Self::contains) are
+///   `O(log n)`, with no shifting.
+///
+/// The tree is rebuilt, in `O(b)`, only when the blocks change: when a block grows past
+/// `2 * LOAD` elements and is split in half, or when a block is emptied and dropped. A split
+/// leaves two blocks that each take `LOAD` more insertions to split again, so rebuilds cost
+/// `O(n / LOAD^2)` per insertion amortized, which is below the cost of the shifting for any `n`
+/// up to about `LOAD^3`, over 100 million.
+///
+/// The memory overhead over a [`Vec`] is, per block, its [`Vec`] header (three words), one word
+/// in the tree, and its spare capacity, which is at most about half of it after a split. When
+/// the blocks hold at least `LOAD` elements, as insertions alone leave them, the headers and
+/// tree take under a hundredth of a word per element, and the spare capacity is about what a
+/// [`Vec`] that grows by doubling has. Removals can leave blocks smaller, down to one element.
+///
+/// ```
+/// use shar_search::ranked::RankedSortedVec;
+///
+/// let mut scores = RankedSortedVec::new();
+/// for score in [70, 95, 80, 80, 60] {
+///     scores.insert(score);
+/// }
+///
+/// assert_eq!(scores.rank(&80), 2);
+/// assert_eq!(scores.select(2), Some(&80));
+/// scores.remove(&60);
+/// assert_eq!(scores.rank(&80), 1);
+/// assert!(scores.iter().eq(&[70, 80, 80, 95]));
+/// ```
+#[derive(Clone)]
+pub struct RankedSortedVec<T> {
+    /// The elements, in order, in blocks that are never empty.
+    blocks: Vec<Vec<T>>,
+    /// The Fenwick tree over the blocks' lengths, where element `i` covers the blocks
+    /// `(i & (i + 1))..=i`.
+    tree: Vec<usize>,
+    len: usize,
+    load: usize,
+}
+
+impl<T> Default for RankedSortedVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RankedSortedVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> RankedSortedVec<T> {
+    /// Creates a new, empty collection.
+    pub const fn new() -> Self {
+        Self::with_load(LOAD)
+    }
+
+    /// Creates a new, empty collection whose blocks split past `2 * load` elements, so that
+    /// tests can exercise splits with few elements.
+    pub(crate) const fn with_load(load: usize) -> Self {
+        Self {
+            blocks: Vec::new(),
+            tree: Vec::new(),
+            len: 0,
+            load,
+        }
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes all elements.
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+        self.tree.clear();
+        self.len = 0;
+    }
+
+    /// Returns an iterator over the elements, in order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + '_ {
+        self.blocks.iter().flatten()
+    }
+
+    /// Returns the element at position `k` in sorted order, or `None` if there are not more
+    /// than `k` elements.
+    pub fn select(&self, k: usize) -> Option<&T> {
+        if k >= self.len {
+            return None;
+        }
+
+        let (block, offset) = self.find_position(k);
+        Some(&self.blocks[block][offset])
+    }
+
+    /// Rebuilds the Fenwick tree from the blocks' lengths.
+    fn rebuild(&mut self) {
+        self.tree.clear();
+        self.tree.extend(self.blocks.iter().map(Vec::len));
+
+        for i in 0..self.tree.len() {
+            let parent = i | (i + 1);
+            if parent < self.tree.len() {
+                self.tree[parent] += self.tree[i];
+            }
+        }
+    }
+
+    /// Adds `delta` to the length of block `block` in the Fenwick tree.
+    fn add(&mut self, mut block: usize, delta: isize) {
+        while block < self.tree.len() {
+            self.tree[block] = self.tree[block].wrapping_add_signed(delta);
+            block |= block + 1;
+        }
+    }
+
+    /// Returns the number of elements in the blocks before `block`.
+    fn prefix(&self, mut block: usize) -> usize {
+        let mut sum = 0;
+        while block > 0 {
+            sum += self.tree[block - 1];
+            block &= block - 1;
+        }
+        sum
+    }
+
+    /// Returns the block holding position `k`, which must be in bounds, and its offset within
+    /// the block, by descending the Fenwick tree.
+    fn find_position(&self, mut k: usize) -> (usize, usize) {
+        let mut block = 0;
+        let mut step = bit_floor(self.tree.len());
+
+        while step > 0 {
+            if block + step <= self.tree.len() && self.tree[block + step - 1] <= k {
+                block += step;
+                k -= self.tree[block - 1];
+            }
+            step /= 2;
+        }
+
+        (block, k)
+    }
+}
+
+impl<T: Ord> RankedSortedVec<T> {
+    /// Returns the block holding the first element not less than `x`, or the number of blocks
+    /// if there is none.
+    fn find_block<Q>(&self, x: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        // Blocks are never empty.
+        self.blocks
+            .bl_partition_point(|block| block[block.len() - 1].borrow() < x)
+    }
+
+    /// Inserts `value` after any elements equal to it, returning its position in sorted order.
+    pub fn insert(&mut self, value: T) -> usize {
+        if self.blocks.is_empty() {
+            let mut block = Vec::with_capacity(self.load);
+            block.push(value);
+            self.blocks.push(block);
+            self.tree.push(1);
+            self.len = 1;
+            return 0;
+        }
+
+        // The first block with a greater last element, or the last block.
+        let block = self
+            .blocks
+            .bl_partition_point(|block| block[block.len() - 1] <= value)
+            .min(self.blocks.len() - 1);
+        let offset = self.blocks[block].bl_upper_bound(&value);
+        let position = self.prefix(block) + offset;
+
+        self.blocks[block].insert(offset, value);
+        self.len += 1;
+
+        if self.blocks[block].len() > 2 * self.load {
+            let tail = self.blocks[block].split_off(self.load);
+            self.blocks.insert(block + 1, tail);
+            self.rebuild();
+        } else {
+            self.add(block, 1);
+        }
+
+        position
+    }
+
+    /// Removes and returns the first element equal to `x`, if any.
+    pub fn remove<Q>(&mut self, x: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let block = self.find_block(x);
+        let offset = self
+            .blocks
+            .get(block)?
+            .bl_binary_search_by(|p| p.borrow().cmp(x))
+            .ok()?;
+
+        let value = self.blocks[block].remove(offset);
+        self.len -= 1;
+
+        if self.blocks[block].is_empty() {
+            self.blocks.remove(block);
+            self.rebuild();
+        } else {
+            self.add(block, -1);
+        }
+
+        Some(value)
+    }
+
+    /// Returns the number of elements less than `x`, which is the position of the first element
+    /// equal to `x` if there is one.
+    pub fn rank<Q>(&self, x: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let block = self.find_block(x);
+        match self.blocks.get(block) {
+            Some(elements) => {
+                self.prefix(block) + elements.bl_lower_bound_by(|p| p.borrow().cmp(x))
+            }
+            None => self.len,
+        }
+    }
+
+    /// Returns whether there is an element equal to `x`.
+    pub fn contains<Q>(&self, x: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.blocks
+            .get(self.find_block(x))
+            .is_some_and(|block| block.bl_binary_search_by(|p| p.borrow().cmp(x)).is_ok())
+    }
+}
+
+impl<T: Ord> Extend<T> for RankedSortedVec<T> {
+    /// Extends the collection by inserting each element in turn.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<T: Ord> FromIterator<T> for RankedSortedVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut vec = Self::new();
+        vec.extend(iter);
+        vec
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::XorShift;
+
+    /// Checks `vec` against the sorted `model`, including the Fenwick tree.
+    fn check(vec: &RankedSortedVec<u32>, model: &[u32]) {
+        assert_eq!(vec.len(), model.len());
+        assert!(vec.iter().eq(model));
+        assert!(vec.blocks.iter().all(|block| !block.is_empty()));
+        assert!(vec.blocks.iter().all(|block| block.len() <= 2 * vec.load));
+        for block in 0..=vec.blocks.len() {
+            let expected: usize = vec.blocks[..block].iter().map(Vec::len).sum();
+            assert_eq!(vec.prefix(block), expected);
+        }
+    }
+
+    #[test]
+    fn test_against_model() {
+        let mut rng = XorShift::new(155);
+
+        for load in [1, 2, 4, 16] {
+            let mut vec = RankedSortedVec::with_load(load);
+            let mut model: Vec<u32> = Vec::new();
+
+            for _ in 0..3000 {
+                let x = rng.below(200) as u32;
+                match rng.below(6) {
+                    0..=2 => {
+                        let position = model.partition_point(|p| *p <= x);
+                        model.insert(position, x);
+                        assert_eq!(vec.insert(x), position);
+                    }
+                    3 => {
+                        let expected = model
+                            .binary_search(&x)
+                            .ok()
+                            .map(|_| model.remove(model.partition_point(|p| *p < x)));
+                        assert_eq!(vec.remove(&x), expected);
+                    }
+                    4 => {
+                        assert_eq!(vec.rank(&x), model.partition_point(|p| *p < x));
+                        assert_eq!(vec.contains(&x), model.contains(&x));
+                    }
+                    _ => {
+                        let k = rng.below(model.len() as u64 + 2) as usize;
+                        assert_eq!(vec.select(k), model.get(k));
+                    }
+                }
+            }
+            check(&vec, &model);
+
+            // Drain it back down to nothing, emptying every block.
+            while let Some(&x) = model.get(rng.below(model.len() as u64 + 1) as usize) {
+                model.remove(model.partition_point(|p| *p < x));
+                assert_eq!(vec.remove(&x), Some(x));
+                assert_eq!(vec.rank(&x), model.partition_point(|p| *p < x));
+            }
+            check(&vec, &model);
+        }
+    }
+
+    #[test]
+    fn test_duplicates_across_blocks() {
+        let mut vec = RankedSortedVec::with_load(2);
+        vec.extend([5; 20]);
+        vec.extend([3, 7]);
+        assert!(vec.blocks.len() > 3);
+
+        assert_eq!(vec.rank(&5), 1);
+        assert_eq!(vec.rank(&7), 21);
+        assert_eq!(vec.insert(5), 21);
+        assert_eq!(vec.select(21), Some(&5));
+        assert_eq!(vec.select(22), Some(&7));
+        assert_eq!(vec.select(23), None);
+    }
+
+    #[test]
+    fn test_empty() {
+        let mut vec = RankedSortedVec::<String>::new();
+        assert!(vec.is_empty());
+        assert_eq!(vec.rank("a"), 0);
+        assert_eq!(vec.select(0), None);
+        assert!(!vec.contains("a"));
+        assert_eq!(vec.remove("a"), None);
+
+        vec.insert("b".to_string());
+        assert!(vec.contains("b"));
+        vec.clear();
+        assert_eq!(vec.rank("c"), 0);
+        assert_eq!(format!("{vec:?}"), "[]");
+    }
+}
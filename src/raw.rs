@@ -0,0 +1,360 @@
+//! Internal routines shared by the sorted containers.
+
+use alloc::vec::Vec;
+use core::{ptr, slice};
+
+use crate::gallop::gallop;
+
+/// Merges the sorted `batch` into the sorted `vec` with a single backwards pass: `vec` is grown
+/// once and elements are written from the end, so the whole merge is `O(n + k)` moves. Elements
+/// of `vec` are placed before equal elements of `batch`.
+///
+/// If `is_less` panics, every element is still owned by `vec` exactly once, so it remains safe
+/// to use and drop, although its order is unspecified.
+pub(crate) fn merge_sorted_batch<T, F>(vec: &mut Vec<T>, mut batch: Vec<T>, mut is_less: F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let a_len = vec.len();
+    let b_len = batch.len();
+
+    if b_len == 0 {
+        return;
+    }
+
+    vec.reserve(b_len);
+
+    // Ownership of the batch's elements moves to the hole; the batch keeps only its buffer,
+    // which must outlive the hole (locals are dropped in reverse order).
+    unsafe { batch.set_len(0) };
+
+    let mut hole = MergeHole {
+        total: a_len + b_len,
+        a_remaining: a_len,
+        b: batch.as_ptr(),
+        b_remaining: b_len,
+        vec,
+    };
+
+    let base = hole.vec.as_mut_ptr();
+
+    // Invariant: `vec[..a_remaining]` and `b[..b_remaining]` are unmerged, `vec[a_remaining +
+    // b_remaining..total]` holds the merged tail, and the gap in between is uninitialized.
+    while hole.a_remaining > 0 && hole.b_remaining > 0 {
+        unsafe {
+            let a_last = base.add(hole.a_remaining - 1);
+            let b_last = hole.b.add(hole.b_remaining - 1);
+            let dst = base.add(hole.a_remaining + hole.b_remaining - 1);
+
+            if is_less(&*b_last, &*a_last) {
+                ptr::copy(a_last, dst, 1);
+                hole.a_remaining -= 1;
+            } else {
+                ptr::copy_nonoverlapping(b_last, dst, 1);
+                hole.b_remaining -= 1;
+            }
+        }
+    }
+}
+
+/// Tracks the state of an in-progress merge; dropping it (on completion or on unwind) moves any
+/// unmerged batch elements into the gap and restores the vector's length.
+struct MergeHole<'a, T> {
+    vec: &'a mut Vec<T>,
+    b: *const T,
+    a_remaining: usize,
+    b_remaining: usize,
+    total: usize,
+}
+
+impl<T> Drop for MergeHole<'_, T> {
+    fn drop(&mut self) {
+        unsafe {
+            let gap = self.vec.as_mut_ptr().add(self.a_remaining);
+            ptr::copy_nonoverlapping(self.b, gap, self.b_remaining);
+            self.vec.set_len(self.total);
+        }
+    }
+}
+
+/// Removes consecutive elements that `same` considers equal, keeping the *last* element of each
+/// run in the position of the first.
+pub(crate) fn dedup_keep_last_by<T, F>(vec: &mut Vec<T>, mut same: F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    vec.dedup_by(|later, kept| {
+        if same(later, kept) {
+            core::mem::swap(later, kept);
+            true
+        } else {
+            false
+        }
+    });
+}
+
+/// Removes consecutive pairs whose keys `same` considers equal, keeping the *first* key of each
+/// run with the *last* value, as inserting into a map that already has the key does.
+pub(crate) fn dedup_keep_last_value_by<K, V, F>(vec: &mut Vec<(K, V)>, mut same: F)
+where
+    F: FnMut(&K, &K) -> bool,
+{
+    vec.dedup_by(|(later_key, later_value), (kept_key, kept_value)| {
+        if same(later_key, kept_key) {
+            core::mem::swap(later_value, kept_value);
+            true
+        } else {
+            false
+        }
+    });
+}
+
+/// Removes every element of the sorted `vec` whose key (as returned by `key`) is in the sorted
+/// `keys`, returning how many were removed. Listing a key more than once is harmless.
+///
+/// The vector is walked once: each step gallops over the elements kept before the next key and
+/// over its run of matches, and then through `keys` to the next key that could match, moving
+/// kept runs down with a single copy each. This is `O(m log(n / m))` comparisons and `O(n)`
+/// moves.
+///
+/// If `key`'s comparisons or an element's drop panic, every element not yet removed is kept,
+/// so the vector remains safe to use and drop.
+pub(crate) fn remove_sorted_keys_by<T, Q, F>(vec: &mut Vec<T>, keys: &[Q], mut key: F) -> usize
+where
+    Q: Ord,
+    F: FnMut(&T) -> &Q,
+{
+    debug_assert!(keys.is_sorted(), "the keys must be sorted");
+
+    let len = vec.len();
+    let mut hole = CompactHole::new(vec, 0);
+    let base = hole.vec.as_mut_ptr();
+
+    let mut next = 0;
+    while next < keys.len() && hole.read < len {
+        let target = &keys[next];
+        // Invariant: `vec[..write]` holds the kept elements, `vec[read..len]` the unvisited
+        // ones, and the gap in between is logically uninitialized.
+        let rest = unsafe { slice::from_raw_parts(base.add(hole.read), len - hole.read) };
+
+        let kept = gallop(rest, |e| key(e) < target);
+        let matched = gallop(&rest[kept..], |e| key(e) <= target);
+
+        unsafe {
+            ptr::copy(base.add(hole.read), base.add(hole.write), kept);
+            hole.write += kept;
+            hole.read += kept;
+
+            let removed = ptr::slice_from_raw_parts_mut(base.add(hole.read), matched);
+            hole.read += matched;
+            ptr::drop_in_place(removed);
+        }
+
+        if hole.read < len {
+            let element = unsafe { &*base.add(hole.read) };
+            next += gallop(&keys[next..], |k| k < key(element));
+        }
+    }
+
+    let removed = hole.read - hole.write;
+    drop(hole);
+    removed
+}
+
+/// Moves the unvisited tail down over the gap left by removed elements and restores the
+/// vector's length when dropped, including on unwinding.
+pub(crate) struct CompactHole<'a, T> {
+    pub(crate) vec: &'a mut Vec<T>,
+    pub(crate) read: usize,
+    pub(crate) write: usize,
+    len: usize,
+}
+
+impl<'a, T> CompactHole<'a, T> {
+    /// Takes ownership of the elements of `vec` from `start` on. The hole owns them; `vec` is
+    /// only handed back its length when the hole is dropped, so if the hole is leaked, `vec`
+    /// keeps just the elements before `start`.
+    pub(crate) fn new(vec: &'a mut Vec<T>, start: usize) -> Self {
+        let len = vec.len();
+        unsafe { vec.set_len(start) };
+        Self {
+            vec,
+            read: start,
+            write: start,
+            len,
+        }
+    }
+}
+
+impl<T> Drop for CompactHole<'_, T> {
+    fn drop(&mut self) {
+        let base = self.vec.as_mut_ptr();
+        let tail = self.len - self.read;
+        unsafe {
+            ptr::copy(base.add(self.read), base.add(self.write), tail);
+            self.vec.set_len(self.write + tail);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        cell::Cell,
+        panic::{catch_unwind, AssertUnwindSafe},
+    };
+
+    use super::{
+        dedup_keep_last_by, dedup_keep_last_value_by, merge_sorted_batch, remove_sorted_keys_by,
+    };
+
+    #[test]
+    fn test_merge() {
+        let mut vec = vec![1, 3, 5, 7];
+        merge_sorted_batch(&mut vec, vec![0, 3, 4, 8, 9], |a, b| a < b);
+        assert_eq!(vec, [0, 1, 3, 3, 4, 5, 7, 8, 9]);
+
+        let mut vec = vec![];
+        merge_sorted_batch(&mut vec, vec![1, 2], |a, b| a < b);
+        assert_eq!(vec, [1, 2]);
+
+        let mut vec = vec![1, 2];
+        merge_sorted_batch(&mut vec, vec![], |a, b| a < b);
+        assert_eq!(vec, [1, 2]);
+    }
+
+    #[test]
+    fn test_merge_is_stable() {
+        let mut vec = vec![(1, 'a'), (2, 'a'), (2, 'b')];
+        merge_sorted_batch(&mut vec, vec![(1, 'x'), (2, 'x')], |a, b| a.0 < b.0);
+        assert_eq!(vec, [(1, 'a'), (1, 'x'), (2, 'a'), (2, 'b'), (2, 'x')]);
+    }
+
+    #[test]
+    fn test_dedup_keep_last() {
+        let mut vec = vec![(1, 'a'), (1, 'b'), (2, 'c'), (3, 'd'), (3, 'e'), (3, 'f')];
+        dedup_keep_last_by(&mut vec, |a, b| a.0 == b.0);
+        assert_eq!(vec, [(1, 'b'), (2, 'c'), (3, 'f')]);
+    }
+
+    #[test]
+    fn test_dedup_keep_last_value() {
+        // Keys that compare equal by their first field, told apart by their second.
+        let mut vec = vec![((1, 'a'), 0), ((1, 'b'), 1), ((2, 'c'), 2), ((2, 'd'), 3)];
+        dedup_keep_last_value_by(&mut vec, |a, b| a.0 == b.0);
+        assert_eq!(vec, [((1, 'a'), 1), ((2, 'c'), 3)]);
+    }
+
+    #[test]
+    fn test_remove_sorted_keys() {
+        let mut rng = crate::test_util::XorShift::new(101);
+
+        for _ in 0..200 {
+            let mut vec: Vec<u32> = (0..rng.below(60)).map(|_| rng.below(30) as u32).collect();
+            let mut keys: Vec<u32> = (0..rng.below(20)).map(|_| rng.below(32) as u32).collect();
+            vec.sort_unstable();
+            keys.sort_unstable();
+
+            let mut expected = vec.clone();
+            expected.retain(|x| !keys.contains(x));
+            let removed = vec.len() - expected.len();
+
+            assert_eq!(remove_sorted_keys_by(&mut vec, &keys, |x| x), removed);
+            assert_eq!(vec, expected);
+        }
+    }
+
+    #[test]
+    fn test_remove_sorted_keys_drops() {
+        use std::rc::Rc;
+
+        let tracked: Vec<Rc<u32>> = [1, 2, 2, 3, 5, 5, 5, 8].into_iter().map(Rc::new).collect();
+        let mut vec = tracked.clone();
+
+        let removed = remove_sorted_keys_by(&mut vec, &[2, 2, 4, 5], |x| &**x);
+        assert_eq!(removed, 5);
+        assert_eq!(vec.iter().map(|x| **x).collect::<Vec<_>>(), [1, 3, 8]);
+
+        // Removed elements are dropped exactly once, and kept ones not at all.
+        let counts: Vec<usize> = tracked.iter().map(Rc::strong_count).collect();
+        assert_eq!(counts, [2, 1, 1, 2, 1, 1, 1, 2]);
+        drop(vec);
+        assert!(tracked.iter().all(|x| Rc::strong_count(x) == 1));
+    }
+
+    #[test]
+    fn test_remove_sorted_keys_panic_safety() {
+        use std::rc::Rc;
+
+        let tracked: Vec<Rc<u32>> = (0..40).map(|i| Rc::new(i / 2)).collect();
+        let keys: Vec<u32> = (0..20).filter(|k| k % 3 == 0).collect();
+
+        for panic_at in 0..30 {
+            let mut vec = tracked.clone();
+            let calls = Cell::new(0);
+
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                remove_sorted_keys_by(&mut vec, &keys, |x| {
+                    calls.set(calls.get() + 1);
+                    if calls.get() == panic_at {
+                        panic!("key failed");
+                    }
+                    &**x
+                })
+            }));
+
+            // Elements removed before the panic are gone, and the rest are kept in order.
+            assert_eq!(result.is_err(), calls.get() == panic_at);
+            assert!(vec.is_sorted());
+            assert!(vec.len() >= tracked.len() - keys.len() * 2);
+            drop(vec);
+            assert!(tracked.iter().all(|x| Rc::strong_count(x) == 1));
+        }
+    }
+
+    #[test]
+    fn test_merge_panic_safety() {
+        thread_local! {
+            static DROPS: Cell<usize> = const { Cell::new(0) };
+            static COMPARISONS: Cell<usize> = const { Cell::new(0) };
+        }
+
+        struct Bomb(u32);
+
+        impl Drop for Bomb {
+            fn drop(&mut self) {
+                DROPS.with(|d| d.set(d.get() + 1));
+            }
+        }
+
+        // Merging these inputs takes 10 comparisons, so every iteration panics partway.
+        for panic_at in 0..10 {
+            DROPS.with(|d| d.set(0));
+            COMPARISONS.with(|c| c.set(0));
+
+            let mut vec: Vec<Bomb> = (0..6).map(|i| Bomb(i * 2)).collect();
+            let batch: Vec<Bomb> = (0..5).map(|i| Bomb(i * 3)).collect();
+
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                merge_sorted_batch(&mut vec, batch, |a, b| {
+                    let count = COMPARISONS.with(|c| c.replace(c.get() + 1));
+                    if count == panic_at {
+                        panic!("comparison failed");
+                    }
+                    a.0 < b.0
+                });
+            }));
+
+            assert!(result.is_err());
+            assert_eq!(vec.len(), 11);
+            assert_eq!(DROPS.with(|d| d.get()), 0);
+
+            let mut values: Vec<u32> = vec.iter().map(|b| b.0).collect();
+            values.sort();
+            assert_eq!(values, [0, 0, 2, 3, 4, 6, 6, 8, 9, 10, 12]);
+
+            drop(vec);
+            assert_eq!(DROPS.with(|d| d.get()), 11);
+        }
+    }
+}
@@ -0,0 +1,682 @@
+//! Slow but obviously correct implementations of the crate's operations, for tests to check the
+//! real ones against.
+//!
+//! Each function here is a linear scan or a brute-force loop over every pair, with no cleverness
+//! that could share a bug with the implementation under test. [`assert_matches_reference!`]
+//! checks a crate function against one of these over every input from a generator, and
+//! [`inputs`] has generators covering the usual edge cases: empty slices, duplicates, and keys
+//! before, between, and after the elements.
+//!
+//! Adding a new operation then takes one function here and one macro invocation in its tests:
+//!
+//! ```ignore
+//! assert_matches_reference!(
+//!     for (slice, x) in inputs::slices_with_keys(1) =>
+//!     slice.bl_lower_bound(&x),
+//!     reference::lower_bound(&slice, &x),
+//! );
+//! ```
+
+// Some operations are only built, and so only tested, with `alloc`.
+#![cfg_attr(not(feature = "alloc"), allow(dead_code))]
+
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    ops::{Bound, Range, RangeBounds},
+    vec::Vec,
+};
+
+/// Checks an expression against its reference for every input from a generator, reporting the
+/// input that fails.
+///
+/// The syntax is `for PATTERN in INPUTS => ACTUAL, EXPECTED`, where `INPUTS` is any iterator of
+/// `Clone + Debug` values, and `ACTUAL` and `EXPECTED` are evaluated with `PATTERN` bound to each.
+macro_rules! assert_matches_reference {
+    (for $pattern:pat in $inputs:expr => $actual:expr, $expected:expr $(,)?) => {{
+        let mut cases = 0_usize;
+        for input in $inputs {
+            // Formatting every input would be slower than the checks, so keep a copy to report.
+            let $pattern = ::core::clone::Clone::clone(&input);
+            let (actual, expected) = ($actual, $expected);
+            assert!(
+                actual == expected,
+                "input: {input:?}\n  actual: {actual:?}\nexpected: {expected:?}",
+            );
+            cases += 1;
+        }
+        assert!(cases > 0, "the generator produced no inputs");
+    }};
+}
+
+pub(crate) use assert_matches_reference;
+
+/// Generators of test inputs, all seeded so that failures are reproducible.
+pub(crate) mod inputs {
+    use std::{string::String, vec::Vec};
+
+    use crate::test_util::XorShift;
+
+    /// Returns sorted slices: every slice of length up to 6 over a few distinct values, which
+    /// covers the edge cases exhaustively, followed by random longer ones with and without
+    /// duplicates.
+    pub(crate) fn sorted_slices(seed: u64) -> impl Iterator<Item = Vec<u32>> {
+        let exhaustive = (0..=6_u32).flat_map(|len| {
+            // The nondecreasing sequences of `len` steps of 0 or 1 from 1, in odd values so
+            // that even keys fall between them.
+            (0..1_u32 << len).map(move |steps| {
+                let mut value = 1;
+                (0..len)
+                    .map(|bit| {
+                        value += 2 * ((steps >> bit) & 1);
+                        value
+                    })
+                    .collect()
+            })
+        });
+
+        let mut rng = XorShift::new(seed);
+        let random = (0..200).map(move |round| {
+            let len = rng.below(if round % 4 == 0 { 1100 } else { 70 });
+            let mut slice: Vec<u32> = match round % 3 {
+                // Long runs of duplicates.
+                0 => (0..len)
+                    .map(|_| rng.below(1 + len / 4) as u32 * 2 + 1)
+                    .collect(),
+                // Mostly distinct.
+                1 => (0..len)
+                    .map(|_| rng.below(4 * len + 1) as u32 * 2 + 1)
+                    .collect(),
+                // Distinct, with gaps of varying widths.
+                _ => {
+                    let mut value = 1;
+                    (0..len)
+                        .map(|_| {
+                            value += 2 * (1 + rng.below(3) as u32);
+                            value
+                        })
+                        .collect()
+                }
+            };
+            slice.sort_unstable();
+            slice
+        });
+
+        exhaustive.chain(random)
+    }
+
+    /// Returns each of [`sorted_slices`] with each key from below its first element to above
+    /// its last, including every element and every gap.
+    pub(crate) fn slices_with_keys(seed: u64) -> impl Iterator<Item = (Vec<u32>, u32)> {
+        sorted_slices(seed).flat_map(|slice| {
+            let last = slice.last().copied().unwrap_or(0);
+            // Long slices have too many keys to try them all.
+            let step = (slice.len() / 64).max(1);
+            (0..=last + 1)
+                .step_by(step)
+                .map(move |x| (slice.clone(), x))
+        })
+    }
+
+    /// Returns pairs of sorted slices, of similar and of very different lengths, over the same
+    /// range of values so that they overlap.
+    pub(crate) fn slice_pairs(seed: u64) -> impl Iterator<Item = (Vec<u32>, Vec<u32>)> {
+        let mut rng = XorShift::new(seed);
+        (0..400).map(move |round| {
+            let (a_len, b_len) = match round % 3 {
+                0 => (rng.below(60), rng.below(60)),
+                1 => (rng.below(6), rng.below(600)),
+                _ => (rng.below(600), rng.below(6)),
+            };
+            let values = rng.below(80) + 1;
+            let mut sorted = |len| {
+                let mut slice: Vec<u32> = (0..len).map(|_| rng.below(values) as u32).collect();
+                slice.sort_unstable();
+                slice
+            };
+            (sorted(a_len), sorted(b_len))
+        })
+    }
+
+    /// Returns the minimums and maximums of each of [`sorted_slices`] split into blocks of 1 to
+    /// 8 elements, so that both are non-decreasing and neighbouring blocks can share a value.
+    pub(crate) fn blocks(seed: u64) -> impl Iterator<Item = (Vec<u32>, Vec<u32>)> {
+        let mut rng = XorShift::new(seed);
+        sorted_slices(seed).map(move |slice| {
+            let (mut mins, mut maxs) = (Vec::new(), Vec::new());
+            let mut rest = &slice[..];
+            while !rest.is_empty() {
+                let len = (1 + rng.below(8) as usize).min(rest.len());
+                mins.push(rest[0]);
+                maxs.push(rest[len - 1]);
+                rest = &rest[len..];
+            }
+            (mins, maxs)
+        })
+    }
+
+    /// Returns the `x` and `y` values of piecewise-linear curves with up to 40 breakpoints, and
+    /// `x` values to evaluate them at, on the breakpoints and a little to either side of them.
+    /// The gaps between breakpoints range from a millionth to a million, so segments differ
+    /// wildly in width.
+    pub(crate) fn curves(seed: u64) -> impl Iterator<Item = (Vec<f64>, Vec<f64>, Vec<f64>)> {
+        let mut rng = XorShift::new(seed);
+        (1..40).flat_map(|len| [len; 3]).map(move |len| {
+            let mut next = -1e6;
+            let x: Vec<f64> = (0..len)
+                .map(|_| {
+                    next += 10_f64.powi(rng.below(13) as i32 - 6);
+                    next
+                })
+                .collect();
+            let y: Vec<f64> = (0..len).map(|_| rng.below(100) as f64 - 50.0).collect();
+            let queries = (0..50)
+                .map(|_| {
+                    let i = rng.below(len as u64) as usize;
+                    match rng.below(3) {
+                        0 => x[i],
+                        1 => x[i] + 10_f64.powi(rng.below(13) as i32 - 7),
+                        _ => x[i] - 10_f64.powi(rng.below(13) as i32 - 7),
+                    }
+                })
+                .collect();
+            (x, y, queries)
+        })
+    }
+
+    /// Returns sets of 1, 2, 3 or 10 sorted slices over the same small range of values, dense
+    /// enough that they intersect, with duplicates.
+    pub(crate) fn sorted_lists(seed: u64) -> impl Iterator<Item = Vec<Vec<u32>>> {
+        let mut rng = XorShift::new(seed);
+        [1, 2, 3, 10]
+            .into_iter()
+            .flat_map(|count| std::iter::repeat_n(count, 50))
+            .map(move |count| {
+                let values = rng.below(100) + 1;
+                (0..count)
+                    .map(|_| {
+                        let len = rng.below(values * 2);
+                        let mut list: Vec<u32> =
+                            (0..len).map(|_| rng.below(values) as u32).collect();
+                        list.sort_unstable();
+                        list
+                    })
+                    .collect()
+            })
+    }
+
+    /// Returns texts mixing every kind of line ending with characters of 1 to 4 bytes, and a
+    /// line separator that does not end a line.
+    pub(crate) fn texts(seed: u64) -> impl Iterator<Item = String> {
+        const PIECES: [&str; 8] = ["a", "bc", "\n", "\r\n", "\r", "é", "😀", "\u{2028}"];
+
+        let mut rng = XorShift::new(seed);
+        (0..100).map(move |_| {
+            (0..rng.below(60))
+                .map(|_| PIECES[rng.below(PIECES.len() as u64) as usize])
+                .collect()
+        })
+    }
+
+    /// Returns pairs of slices sorted by [`f32::total_cmp`], of multiples of an eighth mixed
+    /// with both zeros, both infinities, NaNs of both signs, subnormals and the extremes.
+    pub(crate) fn f32_slice_pairs(seed: u64) -> impl Iterator<Item = (Vec<f32>, Vec<f32>)> {
+        const SPECIALS: [f32; 13] = [
+            f32::NEG_INFINITY,
+            -f32::MAX,
+            -1.5,
+            -f32::MIN_POSITIVE,
+            -1e-45,
+            -0.0,
+            0.0,
+            1e-45,
+            1.0,
+            f32::MAX,
+            f32::INFINITY,
+            f32::NAN,
+            -f32::NAN,
+        ];
+
+        let mut rng = XorShift::new(seed);
+        (0..300).map(move |_| {
+            let (a_len, b_len) = (rng.below(100), rng.below(100));
+            let mut sorted = |len| {
+                let mut slice: Vec<f32> = (0..len)
+                    .map(|_| match rng.below(3) {
+                        0 => SPECIALS[rng.below(SPECIALS.len() as u64) as usize],
+                        _ => (rng.below(2000) as f32 - 1000.0) / 8.0,
+                    })
+                    .collect();
+                slice.sort_by(f32::total_cmp);
+                slice
+            };
+            (sorted(a_len), sorted(b_len))
+        })
+    }
+}
+
+/// Returns the index of the first element equal to `x`, or where `x` would be inserted.
+pub(crate) fn binary_search<T: Ord>(slice: &[T], x: &T) -> Result<usize, usize> {
+    let index = lower_bound(slice, x);
+    match slice.get(index) {
+        Some(p) if p == x => Ok(index),
+        _ => Err(index),
+    }
+}
+
+/// Returns the index of the first element for which `pred` is false.
+pub(crate) fn partition_point<T>(slice: &[T], mut pred: impl FnMut(&T) -> bool) -> usize {
+    slice.iter().position(|p| !pred(p)).unwrap_or(slice.len())
+}
+
+/// Returns the index of the first element not less than `x`.
+pub(crate) fn lower_bound<T: Ord>(slice: &[T], x: &T) -> usize {
+    partition_point(slice, |p| p < x)
+}
+
+/// Returns the index of the first element greater than `x`.
+pub(crate) fn upper_bound<T: Ord>(slice: &[T], x: &T) -> usize {
+    partition_point(slice, |p| p <= x)
+}
+
+/// Returns the indices of the elements equal to `x`.
+pub(crate) fn equal_range<T: Ord>(slice: &[T], x: &T) -> Range<usize> {
+    let start = lower_bound(slice, x);
+    start..start + slice[start..].iter().take_while(|p| *p == x).count()
+}
+
+/// Returns the indices of the elements within `range`.
+pub(crate) fn range<T: Ord>(slice: &[T], range: &impl RangeBounds<T>) -> Range<usize> {
+    let start = partition_point(slice, |p| !past_start(range, p));
+    let end = start
+        + slice[start..]
+            .iter()
+            .take_while(|p| range.contains(p))
+            .count();
+    start..end
+}
+
+/// Returns whether `p` is at or after the start of `range`.
+fn past_start<T: Ord>(range: &impl RangeBounds<T>, p: &T) -> bool {
+    match range.start_bound() {
+        Bound::Included(start) => p >= start,
+        Bound::Excluded(start) => p > start,
+        Bound::Unbounded => true,
+    }
+}
+
+/// Returns the index of the first element of the second sorted run of a rotated sorted slice,
+/// or 0 if it is not rotated.
+pub(crate) fn rotation_point<T: Ord>(slice: &[T]) -> usize {
+    slice
+        .windows(2)
+        .position(|w| w[0] > w[1])
+        .map_or(0, |i| i + 1)
+}
+
+/// Returns the index of the first element equal to `x` in the sorted order of a rotated sorted
+/// slice, or `None` if there is none.
+pub(crate) fn search_rotated<T: Ord>(slice: &[T], x: &T) -> Option<usize> {
+    let pivot = rotation_point(slice);
+    (pivot..slice.len())
+        .chain(0..pivot)
+        .find(|&i| slice[i] == *x)
+}
+
+/// Returns the index of the first maximum of a slice, or 0 if it is empty.
+pub(crate) fn peak<T: Ord>(slice: &[T]) -> usize {
+    slice
+        .iter()
+        .max()
+        .and_then(|max| slice.iter().position(|x| x == max))
+        .unwrap_or(0)
+}
+
+/// Returns the runs of equal elements, with their start indices.
+pub(crate) fn runs<T: Eq>(slice: &[T]) -> Vec<(usize, &[T])> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for i in 1..=slice.len() {
+        if i == slice.len() || slice[i] != slice[start] {
+            runs.push((start, &slice[start..i]));
+            start = i;
+        }
+    }
+    runs
+}
+
+/// Returns each distinct element with the number of times it appears.
+pub(crate) fn counts<T: Ord>(slice: &[T]) -> Vec<(&T, u64)> {
+    let mut counts = BTreeMap::new();
+    for x in slice {
+        *counts.entry(x).or_insert(0) += 1;
+    }
+    counts.into_iter().collect()
+}
+
+/// Returns the index of the first element that is less than the one before it.
+pub(crate) fn first_unsorted_at<T: Ord>(slice: &[T]) -> Option<usize> {
+    first_unsorted_by(slice, |a, b| a <= b)
+}
+
+/// Returns the index of the first element that is not `in_order` after the one before it.
+pub(crate) fn first_unsorted_by<T>(
+    slice: &[T],
+    mut in_order: impl FnMut(&T, &T) -> bool,
+) -> Option<usize> {
+    (1..slice.len()).find(|&i| !in_order(&slice[i - 1], &slice[i]))
+}
+
+/// Returns, for each query, how many elements of `sample` compare less than it, or also equal
+/// to it if `inclusive`.
+pub(crate) fn ranks_by<T>(
+    sample: &[T],
+    queries: &[T],
+    inclusive: bool,
+    compare: impl Fn(&T, &T) -> Ordering,
+) -> Vec<usize> {
+    queries
+        .iter()
+        .map(|q| {
+            sample
+                .iter()
+                .filter(|p| match compare(p, q) {
+                    Ordering::Less => true,
+                    Ordering::Equal => inclusive,
+                    Ordering::Greater => false,
+                })
+                .count()
+        })
+        .collect()
+}
+
+/// Returns, for each query in turn, the index of the closest element of `reference` within
+/// `tolerance`, the lower element and then the lower index on a tie. If `unique_matches`, an
+/// element matched by one query is not matched by a later one.
+pub(crate) fn nearest_within(
+    reference: &[u64],
+    queries: &[u64],
+    tolerance: u64,
+    unique_matches: bool,
+) -> Vec<Option<usize>> {
+    let mut used = vec![false; reference.len()];
+    queries
+        .iter()
+        .map(|&q| {
+            let chosen = (0..reference.len())
+                .filter(|&i| !used[i] && reference[i].abs_diff(q) <= tolerance)
+                .min_by_key(|&i| (reference[i].abs_diff(q), reference[i], i))?;
+            if unique_matches {
+                used[chosen] = true;
+            }
+            Some(chosen)
+        })
+        .collect()
+}
+
+/// Returns the index pairs of equal elements, in order of `a` and then of `b`.
+pub(crate) fn join_indices<T: Ord>(a: &[T], b: &[T]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            if x == y {
+                pairs.push((i, j));
+            }
+        }
+    }
+    pairs
+}
+
+/// Returns the elements of `a` that are (or, if `keep` is false, are not) in `b`.
+pub(crate) fn filter_by_membership<'a, T: Ord>(a: &'a [T], b: &[T], keep: bool) -> Vec<&'a T> {
+    a.iter().filter(|x| b.contains(x) == keep).collect()
+}
+
+/// Returns the elements of both slices in sorted order, those of `a` before equal ones of `b`.
+pub(crate) fn merge<T: Ord + Clone>(a: &[T], b: &[T]) -> Vec<T> {
+    let mut merged = [a, b].concat();
+    // A stable sort keeps the elements of `a` first.
+    merged.sort();
+    merged
+}
+
+/// Returns, for each value, the index of the first edge it is less than (or, if
+/// `right_closed`, not greater than), or the number of edges if there is none.
+pub(crate) fn digitize<T: Ord>(edges: &[T], values: &[T], right_closed: bool) -> Vec<usize> {
+    values
+        .iter()
+        .map(|value| {
+            edges
+                .iter()
+                .position(|edge| match right_closed {
+                    false => value < edge,
+                    true => value <= edge,
+                })
+                .unwrap_or(edges.len())
+        })
+        .collect()
+}
+
+/// Returns how many values [`digitize`] puts in each of the bins around the edges.
+pub(crate) fn histogram<T: Ord>(edges: &[T], values: &[T], right_closed: bool) -> Vec<u64> {
+    let mut counts = vec![0; edges.len() + 1];
+    for bin in digitize(edges, values, right_closed) {
+        counts[bin] += 1;
+    }
+    counts
+}
+
+/// Returns the zero-based line and column of `offset`, clamped to the end of `text`, by walking
+/// the text from its start. An offset inside a character has the column of its start.
+#[cfg(feature = "alloc")]
+pub(crate) fn line_col(text: &str, unit: crate::srcmap::ColumnUnit, offset: u32) -> (u32, u32) {
+    use crate::srcmap::ColumnUnit;
+
+    let offset = (offset as usize).min(text.len());
+    let (mut line, mut start) = (0, 0);
+    for (i, byte) in text.bytes().enumerate().take(offset) {
+        if byte == b'\n' {
+            (line, start) = (line + 1, i + 1);
+        }
+    }
+    let col = match unit {
+        ColumnUnit::Bytes => (offset - start) as u32,
+        _ => text[start..]
+            .char_indices()
+            .take_while(|&(i, c)| start + i + c.len_utf8() <= offset)
+            .map(|(_, c)| match unit {
+                ColumnUnit::Utf16 => c.len_utf16() as u32,
+                _ => 1,
+            })
+            .sum(),
+    };
+    (line, col)
+}
+
+/// Returns the blocks whose interval from `mins[i]` to `maxs[i]` overlaps `query`, treating the
+/// values as reals: doubling everything puts a value between any two integers.
+pub(crate) fn candidate_blocks(
+    mins: &[u32],
+    maxs: &[u32],
+    query: &impl RangeBounds<u32>,
+) -> Vec<usize> {
+    let double = |bound: Bound<&u32>| bound.map(|&x| 2 * u64::from(x));
+    let query = (double(query.start_bound()), double(query.end_bound()));
+    (0..mins.len())
+        .filter(|&i| (2 * u64::from(mins[i])..=2 * u64::from(maxs[i])).any(|x| query.contains(&x)))
+        .collect()
+}
+
+/// Returns the value at `x` of the curve through the breakpoints `xs` and `ys`, finding the
+/// segment with a linear scan, or `None` if `x` is outside them and `out_of_range` is
+/// [`OutOfRange::Error`](crate::lerp::OutOfRange::Error).
+#[cfg(feature = "alloc")]
+pub(crate) fn lerp(
+    xs: &[f64],
+    ys: &[f64],
+    out_of_range: crate::lerp::OutOfRange,
+    x: f64,
+) -> Option<f64> {
+    use crate::lerp::OutOfRange;
+
+    if let Some(i) = xs.iter().position(|&v| v == x) {
+        return Some(ys[i]);
+    }
+    let last = xs.len() - 1;
+    let segment = match xs.iter().position(|&v| v > x) {
+        Some(i) if i > 0 => i - 1,
+        _ if out_of_range == OutOfRange::Error => return None,
+        _ if last == 0 => return Some(ys[0]),
+        Some(_) if out_of_range == OutOfRange::Clamp => return Some(ys[0]),
+        None if out_of_range == OutOfRange::Clamp => return Some(ys[last]),
+        Some(_) => 0,
+        None => last - 1,
+    };
+    let (x0, x1, y0, y1) = (xs[segment], xs[segment + 1], ys[segment], ys[segment + 1]);
+    Some(if y0 == y1 {
+        y0
+    } else {
+        y0 + (y1 - y0) * ((x - x0) / (x1 - x0))
+    })
+}
+
+/// Returns the distinct values that are in every list, in sorted order.
+pub(crate) fn intersect_k<T: Ord + Clone>(lists: &[&[T]]) -> Vec<T> {
+    let Some((first, rest)) = lists.split_first() else {
+        return Vec::new();
+    };
+    let mut values: Vec<T> = first
+        .iter()
+        .filter(|x| rest.iter().all(|list| list.contains(x)))
+        .cloned()
+        .collect();
+    values.sort();
+    values.dedup();
+    values
+}
+
+/// Returns each value with how many times it is in `a` and in `b`.
+fn paired_counts<T: Ord + Clone>(a: &[T], b: &[T]) -> BTreeMap<T, (usize, usize)> {
+    let mut counts = BTreeMap::<T, (usize, usize)>::new();
+    for x in a {
+        counts.entry(x.clone()).or_default().0 += 1;
+    }
+    for x in b {
+        counts.entry(x.clone()).or_default().1 += 1;
+    }
+    counts
+}
+
+/// Returns the sorted values that `count` says to include, each as many times as it says, given
+/// how many times the value is in `a` and in `b`.
+pub(crate) fn combine<T: Ord + Clone>(
+    a: &[T],
+    b: &[T],
+    count: impl Fn(usize, usize) -> usize,
+) -> Vec<T> {
+    paired_counts(a, b)
+        .into_iter()
+        .flat_map(|(x, (in_a, in_b))| std::iter::repeat_n(x, count(in_a, in_b)))
+        .collect()
+}
+
+/// The set and multiset operations, as counts for [`combine`].
+pub(crate) mod ops {
+    /// Once if in both.
+    pub(crate) fn intersection(a: usize, b: usize) -> usize {
+        usize::from(a > 0 && b > 0)
+    }
+
+    /// Once if in either.
+    pub(crate) fn union(a: usize, b: usize) -> usize {
+        usize::from(a > 0 || b > 0)
+    }
+
+    /// Once if only in `a`.
+    pub(crate) fn difference(a: usize, b: usize) -> usize {
+        usize::from(a > 0 && b == 0)
+    }
+
+    /// Once if in exactly one.
+    pub(crate) fn symmetric_difference(a: usize, b: usize) -> usize {
+        usize::from((a > 0) != (b > 0))
+    }
+
+    /// As many times as in whichever has fewer.
+    pub(crate) fn intersection_multiset(a: usize, b: usize) -> usize {
+        a.min(b)
+    }
+
+    /// As many times as in whichever has more.
+    pub(crate) fn union_multiset(a: usize, b: usize) -> usize {
+        a.max(b)
+    }
+
+    /// As many more times as in `a` than in `b`.
+    pub(crate) fn difference_multiset(a: usize, b: usize) -> usize {
+        a.saturating_sub(b)
+    }
+
+    /// As many more times as in one than in the other.
+    pub(crate) fn symmetric_difference_multiset(a: usize, b: usize) -> usize {
+        a.abs_diff(b)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_generators_cover_edge_cases() {
+        let slices: Vec<Vec<u32>> = inputs::sorted_slices(1).collect();
+        assert!(slices.iter().any(Vec::is_empty));
+        assert!(slices.iter().any(|s| s.len() > 1000));
+        assert!(slices.iter().all(|s| s.is_sorted()));
+        // Runs of duplicates, and slices without any.
+        assert!(slices
+            .iter()
+            .any(|s| s.len() > 3 && s.iter().all(|x| *x == s[0])));
+        assert!(slices
+            .iter()
+            .any(|s| s.len() > 50 && s.windows(2).all(|w| w[0] < w[1])));
+    }
+
+    #[test]
+    fn test_references() {
+        let slice = [1, 3, 3, 5];
+        assert_eq!(binary_search(&slice, &3), Ok(1));
+        assert_eq!(binary_search(&slice, &4), Err(3));
+        assert_eq!(equal_range(&slice, &3), 1..3);
+        assert_eq!(range(&slice, &(2..=3)), 1..3);
+        assert_eq!(range(&slice, &(4..)), 3..4);
+        assert_eq!(rotation_point(&[5, 7, 1, 3]), 2);
+        assert_eq!(search_rotated(&[5, 7, 1, 3, 3], &3), Some(3));
+        assert_eq!(peak(&[1, 9, 4, 9]), 1);
+        assert_eq!(counts(&slice), [(&1, 1), (&3, 2), (&5, 1)]);
+        assert_eq!(first_unsorted_at(&[1, 3, 2]), Some(2));
+        assert_eq!(first_unsorted_by(&[1, 3, 3], |a, b| a < b), Some(2));
+        assert_eq!(ranks_by(&slice, &[3, 0], true, i32::cmp), [3, 0]);
+        assert_eq!(
+            nearest_within(&[10, 20], &[15, 16], 5, true),
+            [Some(0), Some(1)]
+        );
+        assert_eq!(digitize(&[1, 3, 3], &[0, 3, 4], false), [0, 3, 3]);
+        assert_eq!(histogram(&[1, 3, 3], &[0, 3, 4], true), [1, 1, 0, 1]);
+        #[cfg(feature = "alloc")]
+        assert_eq!(
+            line_col("ab\ncd", crate::srcmap::ColumnUnit::Chars, 4),
+            (1, 1)
+        );
+        assert_eq!(candidate_blocks(&[1, 5], &[4, 8], &(4..5)), [0]);
+        assert!(
+            candidate_blocks(&[1, 5], &[4, 8], &(Bound::Excluded(4), Bound::Excluded(5)))
+                .is_empty()
+        );
+        assert_eq!(intersect_k(&[&[1, 3, 3, 5][..], &[3, 5, 5]]), [3, 5]);
+        assert_eq!(
+            combine(&[1, 1, 2], &[1, 3], ops::union_multiset),
+            [1, 1, 2, 3]
+        );
+    }
+}
@@ -0,0 +1,340 @@
+//! Combinators on the `Result<usize, usize>` that the searches return.
+//!
+//! A search returns `Ok` with the index of a match, or `Err` with the insertion point: the
+//! index where the key could be inserted to keep the slice sorted. [`SearchResultExt`] names
+//! the common ways of taking that apart, so callers don't each write the same `match`.
+//!
+//! ```
+//! use shar_search::{SearchResultExt, SharBinarySearch};
+//!
+//! let primes = [2, 3, 5, 7, 11];
+//!
+//! let hit = primes.bl_binary_search(&5);
+//! assert!(hit.found());
+//! assert_eq!(hit.element(&primes), Some(&5));
+//!
+//! let miss = primes.bl_binary_search(&6);
+//! assert_eq!(miss.index_or_insertion(), 3);
+//! assert_eq!(miss.neighbors(&primes), (Some(&5), Some(&7)));
+//! ```
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "alloc")]
+use crate::gallop::gallop;
+
+/// Where [`SearchResultExt::insert_into_with`] inserts a value that matched, relative to the
+/// run of elements equal to the match.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RunPosition {
+    /// Before the run, at the index of the match. This keeps the searches' leftmost convention:
+    /// searching for the value afterwards finds the newly inserted element.
+    Before,
+    /// After the run, so that equal elements stay in insertion order.
+    After,
+}
+
+/// Extension trait for the `Result<usize, usize>` returned by the searches. See the
+/// [`result`](crate::result) module.
+///
+/// The searches return the *first* of a run of equal elements on a hit, and the helpers that
+/// care about runs assume that, as well as that any slice passed in is the one searched.
+pub trait SearchResultExt {
+    /// Returns the index of the match on a hit, or the insertion point on a miss. Either way,
+    /// this is the index of the first element not less than the key, where inserting it keeps
+    /// the slice sorted.
+    ///
+    /// ```
+    /// use shar_search::{SearchResultExt, SharBinarySearch};
+    ///
+    /// let slice = [1, 3, 3, 5];
+    /// assert_eq!(slice.bl_binary_search(&3).index_or_insertion(), 1);
+    /// assert_eq!(slice.bl_binary_search(&4).index_or_insertion(), 3);
+    /// assert_eq!(slice.bl_binary_search(&9).index_or_insertion(), 4);
+    /// ```
+    fn index_or_insertion(self) -> usize;
+
+    /// Returns whether the search found a match.
+    fn found(self) -> bool;
+
+    /// Returns the index of the match on a hit, or `None` on a miss.
+    fn found_index(self) -> Option<usize>;
+
+    /// Returns the matched element of `slice` on a hit, or `None` on a miss. Also returns
+    /// `None` if the index is out of bounds, as when `slice` is not the slice searched.
+    fn element<T>(self, slice: &[T]) -> Option<&T>;
+
+    /// Returns the elements either side of the result in `slice`, as `(before, after)`. Either
+    /// is `None` past the corresponding end of the slice.
+    ///
+    /// On a miss at insertion point `i`, these are the elements at `i - 1` and `i`: the greatest
+    /// element less than the key and the least greater, as in [`Neighbors`](crate::Neighbors).
+    ///
+    /// On a hit at `i`, these are the elements at `i - 1` and `i + 1`, either side of the match
+    /// itself. As the match is the first of its run, `before` is less than it, but `after` is
+    /// another equal element if the run is longer than one.
+    ///
+    /// ```
+    /// use shar_search::{SearchResultExt, SharBinarySearch};
+    ///
+    /// let slice = [10, 20, 20, 30];
+    /// assert_eq!(slice.bl_binary_search(&15).neighbors(&slice), (Some(&10), Some(&20)));
+    /// assert_eq!(slice.bl_binary_search(&20).neighbors(&slice), (Some(&10), Some(&20)));
+    /// assert_eq!(slice.bl_binary_search(&30).neighbors(&slice), (Some(&20), None));
+    /// assert_eq!(slice.bl_binary_search(&5).neighbors(&slice), (None, Some(&10)));
+    /// ```
+    fn neighbors<T>(self, slice: &[T]) -> (Option<&T>, Option<&T>);
+
+    /// Inserts `value` into `vec` at [`index_or_insertion`](SearchResultExt::index_or_insertion)
+    /// and returns the index it was inserted at. On a hit, this is before the run of elements
+    /// equal to the match, like [`RunPosition::Before`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is greater than the length of `vec`.
+    ///
+    /// ```
+    /// use shar_search::{SearchResultExt, SharBinarySearch};
+    ///
+    /// let mut vec = vec![1, 3, 5];
+    /// let index = vec.bl_binary_search(&4).insert_into(&mut vec, 4);
+    /// assert_eq!(index, 2);
+    /// assert_eq!(vec, [1, 3, 4, 5]);
+    /// ```
+    #[cfg(feature = "alloc")]
+    fn insert_into<T>(self, vec: &mut Vec<T>, value: T) -> usize;
+
+    /// Inserts `value` into `vec` and returns the index it was inserted at: at the insertion
+    /// point on a miss, and on a hit before or after the run of elements equal (by `==`) to the
+    /// match, as `position` says.
+    ///
+    /// Finding the end of the run gallops from the match, so this costs `O(log r)` comparisons
+    /// for a run of length `r`, besides shifting the elements after the insertion.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is greater than the length of `vec`.
+    ///
+    /// ```
+    /// use shar_search::{result::RunPosition, SearchResultExt, SharBinarySearch};
+    ///
+    /// let mut vec = vec![1, 3, 3, 5];
+    /// let index = vec.bl_binary_search(&3).insert_into_with(&mut vec, 3, RunPosition::After);
+    /// assert_eq!(index, 3);
+    /// ```
+    #[cfg(feature = "alloc")]
+    fn insert_into_with<T: PartialEq>(
+        self,
+        vec: &mut Vec<T>,
+        value: T,
+        position: RunPosition,
+    ) -> usize;
+
+    /// Inserts `value` into `vec` like
+    /// [`insert_into_with`](SearchResultExt::insert_into_with), but with the run of a hit made
+    /// of the elements that `same_run(match, element)` says belong with the match. For a
+    /// search by key, this compares the keys.
+    ///
+    /// ```
+    /// use shar_search::{result::RunPosition, SearchResultExt, SharBinarySearch};
+    ///
+    /// let mut events = vec![(1, "a"), (2, "b"), (2, "c"), (3, "d")];
+    /// let hit = events.bl_binary_search_by_key(&2, |e| e.0);
+    /// let same_key = |a: &(u8, &str), b: &(u8, &str)| a.0 == b.0;
+    /// let index = hit.insert_into_by(&mut events, (2, "e"), RunPosition::After, same_key);
+    /// assert_eq!(index, 3);
+    /// assert_eq!(events, [(1, "a"), (2, "b"), (2, "c"), (2, "e"), (3, "d")]);
+    /// ```
+    #[cfg(feature = "alloc")]
+    fn insert_into_by<T, F>(
+        self,
+        vec: &mut Vec<T>,
+        value: T,
+        position: RunPosition,
+        same_run: F,
+    ) -> usize
+    where
+        F: FnMut(&T, &T) -> bool;
+}
+
+impl SearchResultExt for Result<usize, usize> {
+    #[inline]
+    fn index_or_insertion(self) -> usize {
+        match self {
+            Ok(index) | Err(index) => index,
+        }
+    }
+
+    #[inline]
+    fn found(self) -> bool {
+        self.is_ok()
+    }
+
+    #[inline]
+    fn found_index(self) -> Option<usize> {
+        self.ok()
+    }
+
+    #[inline]
+    fn element<T>(self, slice: &[T]) -> Option<&T> {
+        slice.get(self.ok()?)
+    }
+
+    #[inline]
+    fn neighbors<T>(self, slice: &[T]) -> (Option<&T>, Option<&T>) {
+        let (before, after) = match self {
+            Ok(index) => (index.checked_sub(1), index.checked_add(1)),
+            Err(index) => (index.checked_sub(1), Some(index)),
+        };
+        (
+            before.and_then(|i| slice.get(i)),
+            after.and_then(|i| slice.get(i)),
+        )
+    }
+
+    #[cfg(feature = "alloc")]
+    #[inline]
+    fn insert_into<T>(self, vec: &mut Vec<T>, value: T) -> usize {
+        let index = self.index_or_insertion();
+        vec.insert(index, value);
+        index
+    }
+
+    #[cfg(feature = "alloc")]
+    #[inline]
+    fn insert_into_with<T: PartialEq>(
+        self,
+        vec: &mut Vec<T>,
+        value: T,
+        position: RunPosition,
+    ) -> usize {
+        self.insert_into_by(vec, value, position, T::eq)
+    }
+
+    #[cfg(feature = "alloc")]
+    fn insert_into_by<T, F>(
+        self,
+        vec: &mut Vec<T>,
+        value: T,
+        position: RunPosition,
+        mut same_run: F,
+    ) -> usize
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        let index = match (self, position) {
+            (Ok(index), RunPosition::After) => {
+                let run = &vec[index..];
+                index + gallop(run, |x| same_run(&run[0], x))
+            }
+            (Ok(index) | Err(index), _) => index,
+        };
+        vec.insert(index, value);
+        index
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SearchResultExt;
+    use crate::{
+        reference::{self, assert_matches_reference, inputs},
+        SharBinarySearch,
+    };
+
+    #[test]
+    fn test_hits_and_misses_at_the_ends() {
+        let slice = [2, 4, 4, 4, 6];
+        let search = |x| slice.bl_binary_search(&x);
+
+        // Index 0, as a hit and as a miss.
+        assert_eq!(search(2).index_or_insertion(), 0);
+        assert_eq!(search(2).found_index(), Some(0));
+        assert_eq!(search(2).neighbors(&slice), (None, Some(&4)));
+        assert_eq!(search(1).index_or_insertion(), 0);
+        assert!(!search(1).found());
+        assert_eq!(search(1).element(&slice), None);
+        assert_eq!(search(1).neighbors(&slice), (None, Some(&2)));
+
+        // The last index as a hit, and `len` as a miss.
+        assert_eq!(search(6).element(&slice), Some(&6));
+        assert_eq!(search(6).neighbors(&slice), (Some(&4), None));
+        assert_eq!(search(7).index_or_insertion(), 5);
+        assert_eq!(search(7).found_index(), None);
+        assert_eq!(search(7).neighbors(&slice), (Some(&6), None));
+
+        // A run of duplicates, hit at its first element.
+        assert_eq!(search(4).found_index(), Some(1));
+        assert_eq!(search(4).neighbors(&slice), (Some(&2), Some(&4)));
+        assert_eq!(search(5).neighbors(&slice), (Some(&4), Some(&6)));
+
+        let empty: [i32; 0] = [];
+        assert_eq!(empty.bl_binary_search(&1).neighbors(&empty), (None, None));
+        assert_eq!(empty.bl_binary_search(&1).index_or_insertion(), 0);
+    }
+
+    #[test]
+    fn test_foreign_indices() {
+        // Results applied to a slice other than the one searched stay in bounds.
+        assert_eq!(Ok(5).element(&[1, 2]), None);
+        assert_eq!(Ok(usize::MAX).neighbors(&[1, 2]), (None, None));
+        assert_eq!(Err(9).neighbors(&[1, 2]), (None, None));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_insert_into() {
+        use super::RunPosition;
+
+        let base = vec![1, 3, 3, 3, 5];
+        let inserted = |x: i32, position: Option<RunPosition>| {
+            let mut vec = base.clone();
+            let result = vec.bl_binary_search(&x);
+            let index = match position {
+                None => result.insert_into(&mut vec, x),
+                Some(position) => result.insert_into_with(&mut vec, x, position),
+            };
+            assert!(vec.is_sorted());
+            (index, vec.len())
+        };
+
+        assert_eq!(inserted(0, None), (0, 6));
+        assert_eq!(inserted(9, None), (5, 6));
+        assert_eq!(inserted(3, None), (1, 6));
+        assert_eq!(inserted(3, Some(RunPosition::Before)), (1, 6));
+        assert_eq!(inserted(3, Some(RunPosition::After)), (4, 6));
+        assert_eq!(inserted(1, Some(RunPosition::After)), (1, 6));
+        assert_eq!(inserted(5, Some(RunPosition::After)), (5, 6));
+        assert_eq!(inserted(9, Some(RunPosition::After)), (5, 6));
+        assert_eq!(inserted(0, Some(RunPosition::After)), (0, 6));
+
+        // Insertions after the run keep equal elements in insertion order.
+        let mut log: Vec<(u8, usize)> = Vec::new();
+        for (i, key) in [2, 1, 2, 2, 1].into_iter().enumerate() {
+            let result = log.bl_binary_search_by_key(&key, |e| e.0);
+            result.insert_into_by(&mut log, (key, i), RunPosition::After, |a, b| a.0 == b.0);
+        }
+        assert_eq!(log, [(1, 1), (1, 4), (2, 0), (2, 2), (2, 3)]);
+    }
+
+    #[test]
+    fn test_against_reference() {
+        assert_matches_reference!(
+            for (slice, x) in inputs::slices_with_keys(186) =>
+            {
+                let result = slice.bl_binary_search(&x);
+                (result.index_or_insertion(), result.element(&slice), result.neighbors(&slice))
+            },
+            {
+                let expected = reference::binary_search(&slice, &x);
+                let (index, before, after) = match expected {
+                    Ok(i) => (i, i.checked_sub(1), i + 1),
+                    Err(i) => (i, i.checked_sub(1), i),
+                };
+                let element = expected.ok().map(|i| &slice[i]);
+                (index, element, (before.and_then(|i| slice.get(i)), slice.get(after)))
+            },
+        );
+    }
+}
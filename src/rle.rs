@@ -0,0 +1,270 @@
+//! Searches of sorted data stored as runs of equal values, in the positions of the decoded data.
+//!
+//! An [`RleSorted`] holds each distinct value once, with the number of times it repeats, and the
+//! running totals of those counts. Positions and ranks in the decoded data are found by
+//! searching the running totals or the values, without decoding anything, and the counts can
+//! add up to anything that fits in a `u64`.
+//!
+//! ```
+//! use shar_search::rle::RleSorted;
+//!
+//! let rle = RleSorted::new(&[(10, 3), (20, 1_000_000), (30, 2)]).unwrap();
+//! assert_eq!(rle.len(), 1_000_005);
+//!
+//! assert_eq!(rle.get(2), Some(&10));
+//! assert_eq!(rle.get(1_000_002), Some(&20));
+//! assert_eq!(rle.get(1_000_003), Some(&30));
+//! assert_eq!(rle.search(&20), Ok(3));
+//! assert_eq!(rle.search(&25), Err(1_000_003));
+//! ```
+
+use alloc::vec::Vec;
+use core::{error::Error, fmt};
+
+use crate::SharBinarySearch;
+
+/// The error returned when runs are invalid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RleError {
+    /// The value of the run at this index isn't greater than the one before it.
+    NotIncreasing(usize),
+    /// The run at this index has a count of zero.
+    ZeroCount(usize),
+    /// The total count overflows a `u64` at the run at this index.
+    Overflow(usize),
+}
+
+impl fmt::Display for RleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RleError::NotIncreasing(index) => {
+                write!(
+                    f,
+                    "the value of run {index} is not greater than its predecessor"
+                )
+            }
+            RleError::ZeroCount(index) => write!(f, "run {index} has a count of zero"),
+            RleError::Overflow(index) => {
+                write!(f, "the total count overflows at run {index}")
+            }
+        }
+    }
+}
+
+impl Error for RleError {}
+
+/// A sorted sequence stored as runs of equal values. See the [module documentation](self).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RleSorted<T> {
+    values: Vec<T>,
+    /// The position just past the end of each run.
+    ends: Vec<u64>,
+}
+
+impl<T: Ord> RleSorted<T> {
+    /// Creates a sequence from `(value, count)` runs, checking that the values are strictly
+    /// increasing and the counts are nonzero.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a value isn't greater than the one before it, if a count is zero, or
+    /// if the counts add up to more than `u64::MAX`.
+    pub fn new(runs: &[(T, u64)]) -> Result<Self, RleError>
+    where
+        T: Clone,
+    {
+        if let Some(index) = runs.windows(2).position(|w| w[0].0 >= w[1].0) {
+            return Err(RleError::NotIncreasing(index + 1));
+        }
+
+        let mut total = 0_u64;
+        let ends = runs
+            .iter()
+            .enumerate()
+            .map(|(i, &(_, count))| {
+                if count == 0 {
+                    return Err(RleError::ZeroCount(i));
+                }
+                total = total.checked_add(count).ok_or(RleError::Overflow(i))?;
+                Ok(total)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            values: runs.iter().map(|(value, _)| value.clone()).collect(),
+            ends,
+        })
+    }
+
+    /// Encodes `sorted` as runs of equal values. Note it is assumed that the slice is sorted.
+    ///
+    /// In debug builds, this panics if `sorted` is not sorted.
+    pub fn encode(sorted: &[T]) -> Self
+    where
+        T: Clone,
+    {
+        debug_assert!(sorted.is_sorted(), "the slice must be sorted");
+
+        let (values, ends) = sorted
+            .bl_runs()
+            .map(|(start, run)| (run[0].clone(), (start + run.len()) as u64))
+            .unzip();
+
+        Self { values, ends }
+    }
+
+    /// Returns the number of elements in the decoded sequence.
+    pub fn len(&self) -> u64 {
+        self.ends.last().copied().unwrap_or(0)
+    }
+
+    /// Returns whether the sequence is empty.
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// Returns the number of runs.
+    pub fn runs_len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns an iterator over the runs, as `(value, count)` pairs.
+    pub fn runs(&self) -> impl Iterator<Item = (&T, u64)> + '_ {
+        let starts = core::iter::once(0).chain(self.ends.iter().copied());
+        self.values
+            .iter()
+            .zip(self.ends.iter().zip(starts))
+            .map(|(value, (end, start))| (value, end - start))
+    }
+
+    /// Returns the element at position `index` of the decoded sequence, or `None` if it is out
+    /// of bounds.
+    pub fn get(&self, index: u64) -> Option<&T> {
+        let run = self.ends.bl_partition_point(|&end| end <= index);
+        self.values.get(run)
+    }
+
+    /// Returns the number of elements less than `v`, which is the position of the first `v` in
+    /// the decoded sequence if there is one.
+    pub fn rank(&self, v: &T) -> u64 {
+        self.start(self.values.bl_lower_bound(v))
+    }
+
+    /// Binary searches for `v`, returning its first position in the decoded sequence, or the
+    /// position where it could be inserted.
+    pub fn search(&self, v: &T) -> Result<u64, u64> {
+        self.values
+            .bl_binary_search(v)
+            .map(|run| self.start(run))
+            .map_err(|run| self.start(run))
+    }
+
+    /// Returns the position where run `run` starts, or the length if there are no more runs.
+    fn start(&self, run: usize) -> u64 {
+        match run.checked_sub(1) {
+            Some(prev) => self.ends[prev],
+            None => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::XorShift;
+
+    #[test]
+    fn test_huge_counts() {
+        let half = u64::MAX / 2;
+        let rle = RleSorted::new(&[(1, half), (5, half), (9, 1)]).unwrap();
+        assert_eq!(rle.len(), u64::MAX);
+
+        assert_eq!(rle.get(0), Some(&1));
+        assert_eq!(rle.get(half - 1), Some(&1));
+        assert_eq!(rle.get(half), Some(&5));
+        assert_eq!(rle.get(u64::MAX - 2), Some(&5));
+        assert_eq!(rle.get(u64::MAX - 1), Some(&9));
+        assert_eq!(rle.get(u64::MAX), None);
+
+        assert_eq!(rle.search(&9), Ok(u64::MAX - 1));
+        assert_eq!(rle.search(&10), Err(u64::MAX));
+        assert_eq!(rle.rank(&5), half);
+
+        assert_eq!(
+            RleSorted::new(&[(1, half), (5, half), (9, 2)]),
+            Err(RleError::Overflow(2))
+        );
+    }
+
+    #[test]
+    fn test_single_runs() {
+        let rle = RleSorted::new(&[(2, 1), (4, 1), (6, 1)]).unwrap();
+        assert_eq!(rle, RleSorted::encode(&[2, 4, 6]));
+
+        for (i, v) in [2, 4, 6].iter().enumerate() {
+            assert_eq!(rle.get(i as u64), Some(v));
+            assert_eq!(rle.search(v), Ok(i as u64));
+        }
+        assert_eq!(rle.get(3), None);
+    }
+
+    #[test]
+    fn test_missing_values() {
+        let rle = RleSorted::new(&[(10, 3), (20, 2), (30, 4)]).unwrap();
+
+        assert_eq!(rle.search(&5), Err(0));
+        assert_eq!(rle.search(&15), Err(3));
+        assert_eq!(rle.search(&25), Err(5));
+        assert_eq!(rle.search(&35), Err(9));
+        assert_eq!(rle.rank(&15), 3);
+        assert_eq!(rle.rank(&35), 9);
+        assert!(rle.runs().eq([(&10, 3), (&20, 2), (&30, 4)]));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(
+            RleSorted::new(&[(1, 2), (1, 3)]),
+            Err(RleError::NotIncreasing(1))
+        );
+        assert_eq!(
+            RleSorted::new(&[(1, 2), (3, 1), (2, 3)]),
+            Err(RleError::NotIncreasing(2))
+        );
+        assert_eq!(
+            RleSorted::new(&[(1, 2), (3, 0)]),
+            Err(RleError::ZeroCount(1))
+        );
+
+        let empty = RleSorted::<u8>::new(&[]).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.len(), 0);
+        assert_eq!(empty.get(0), None);
+        assert_eq!(empty.search(&1), Err(0));
+    }
+
+    #[test]
+    fn test_against_decoded() {
+        let mut rng = XorShift::new(151);
+
+        for _ in 0..100 {
+            let mut decoded: Vec<u32> = (0..rng.below(80)).map(|_| rng.below(30) as u32).collect();
+            decoded.sort_unstable();
+            let rle = RleSorted::encode(&decoded);
+
+            assert_eq!(rle.len(), decoded.len() as u64);
+            assert_eq!(rle.runs().map(|(_, count)| count).sum::<u64>(), rle.len());
+            for (i, v) in decoded.iter().enumerate() {
+                assert_eq!(rle.get(i as u64), Some(v));
+            }
+            for v in 0..32 {
+                let expected = decoded.bl_binary_search(&v);
+                assert_eq!(
+                    rle.search(&v),
+                    expected.map(|i| i as u64).map_err(|i| i as u64)
+                );
+                assert_eq!(rle.rank(&v), decoded.bl_lower_bound(&v) as u64);
+            }
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! Searching sorted slices that have been rotated by an unknown amount.
+
+use core::cmp::Ordering;
+
+use crate::SharBinarySearch;
+
+/// Returns the index of the first element of the second sorted run of `slice`, or 0 if the
+/// slice is not rotated.
+pub(crate) fn rotation_point_by<T, F>(slice: &[T], mut compare: F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let (Some(first), Some(last)) = (slice.first(), slice.last()) else {
+        return 0;
+    };
+
+    match compare(first, last) {
+        Ordering::Less => 0,
+        // Everything in the first run is at least `first`, so greater than `last`, and
+        // everything in the second run is at most `last`.
+        Ordering::Greater => slice.bl_partition_point(|x| compare(x, last).is_gt()),
+        Ordering::Equal => slice
+            .windows(2)
+            .position(|pair| compare(&pair[0], &pair[1]).is_gt())
+            .map_or(0, |i| i + 1),
+    }
+}
+
+/// Searches `slice`, rotated at `pivot`, with a comparator function, which returns the ordering
+/// of an element relative to the target.
+pub(crate) fn search_rotated_by<T, F>(slice: &[T], pivot: usize, mut f: F) -> Result<usize, usize>
+where
+    F: FnMut(&T) -> Ordering,
+{
+    let (first_run, second_run) = slice.split_at(pivot);
+
+    match second_run.last() {
+        Some(last) if !first_run.is_empty() && f(last).is_lt() => first_run.bl_binary_search_by(f),
+        _ => match second_run.bl_binary_search_by(f) {
+            Ok(index) => Ok(pivot + index),
+            Err(index) => Err(pivot + index),
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        reference::{self, assert_matches_reference, inputs},
+        SharBinarySearch,
+    };
+
+    /// Returns whether `slice` is a rotated sorted slice: at most one of its elements is greater
+    /// than the next, wrapping around at the end.
+    fn is_rotated_sorted(slice: &[u32]) -> bool {
+        let n = slice.len();
+        (0..n).filter(|&i| slice[i] > slice[(i + 1) % n]).count() <= 1
+    }
+
+    fn check(rotated: &[u32]) {
+        let n = rotated.len();
+        let pivot = rotated.bl_rotation_point();
+        let logical: Vec<u32> = rotated[pivot..]
+            .iter()
+            .chain(&rotated[..pivot])
+            .copied()
+            .collect();
+        assert!(logical.is_sorted(), "{rotated:?}");
+
+        for key in 0..=logical.last().map_or(1, |&x| x + 1) {
+            match rotated.bl_binary_search_rotated(&key) {
+                Ok(i) => {
+                    let first = logical.bl_lower_bound(&key);
+                    assert_eq!(i, (pivot + first) % n, "{rotated:?} {key}");
+                }
+                Err(i) => {
+                    assert!(!rotated.contains(&key), "{rotated:?} {key}");
+                    let mut inserted = rotated.to_vec();
+                    inserted.insert(i, key);
+                    assert!(is_rotated_sorted(&inserted), "{rotated:?} {key} {i}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_rotation() {
+        let bases: [&[u32]; 6] = [
+            &[],
+            &[5],
+            &[1, 3],
+            &[1, 3, 5, 7, 9, 12],
+            &[1, 2, 2, 2, 3, 3, 8],
+            &[2, 2, 2, 2, 2],
+        ];
+
+        for base in bases {
+            for amount in 0..=base.len() {
+                let mut rotated = base.to_vec();
+                rotated.rotate_left(amount % base.len().max(1));
+                check(&rotated);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rotation_point() {
+        let bases: [&[u32]; 4] = [&[4], &[1, 2], &[1, 3, 5, 7, 9, 12], &[0, 10, 20, 30, 40]];
+
+        for base in bases {
+            let n = base.len();
+            for amount in 0..=n {
+                let mut rotated = base.to_vec();
+                rotated.rotate_left(amount % n);
+                assert_eq!(rotated.bl_rotation_point(), (n - amount) % n, "{rotated:?}");
+            }
+        }
+
+        let empty: [u32; 0] = [];
+        assert_eq!(empty.bl_rotation_point(), 0);
+        assert_eq!([3, 3, 3, 3].bl_rotation_point(), 0);
+        assert_eq!([3, 3].bl_rotation_point(), 0);
+        assert_eq!([3, 3, 1, 2].bl_rotation_point(), 2);
+        assert_eq!([2, 3, 3, 1, 1].bl_rotation_point(), 3);
+        assert_eq!([1, 1, 2, 1].bl_rotation_point(), 3);
+
+        let by_key = [(9, 'a'), (1, 'b'), (5, 'c')];
+        assert_eq!(by_key.bl_rotation_point_by_key(|x| x.0), 1);
+    }
+
+    #[test]
+    fn test_unrotated_matches_search() {
+        assert_matches_reference!(
+            for (slice, x) in inputs::slices_with_keys(120) =>
+            slice.bl_binary_search_rotated(&x),
+            slice.bl_binary_search(&x),
+        );
+    }
+
+    #[test]
+    fn test_against_reference() {
+        let rotations = || {
+            inputs::sorted_slices(121)
+                .filter(|slice| slice.len() < 100)
+                .flat_map(|slice| {
+                    (0..slice.len().max(1)).map(move |amount| {
+                        let mut rotated = slice.clone();
+                        rotated.rotate_left(amount % slice.len().max(1));
+                        rotated
+                    })
+                })
+        };
+        assert_matches_reference!(
+            for slice in rotations() =>
+            slice.bl_rotation_point(),
+            reference::rotation_point(&slice),
+        );
+
+        let keys = rotations().flat_map(|slice| {
+            let last = slice.iter().max().map_or(0, |x| x + 1);
+            (0..=last).map(move |x| (slice.clone(), x))
+        });
+        assert_matches_reference!(
+            for (slice, x) in keys =>
+            slice.bl_binary_search_rotated(&x).ok(),
+            reference::search_rotated(&slice, &x),
+        );
+    }
+
+    #[test]
+    fn test_by_and_by_key() {
+        let slice = [(7, 'a'), (9, 'b'), (12, 'c'), (1, 'd'), (3, 'e'), (5, 'f')];
+
+        assert_eq!(slice.bl_binary_search_rotated_by_key(&9, |x| x.0), Ok(1));
+        assert_eq!(slice.bl_binary_search_rotated_by_key(&3, |x| x.0), Ok(4));
+        assert_eq!(slice.bl_binary_search_rotated_by_key(&6, |x| x.0), Err(0));
+        assert_eq!(
+            slice.bl_binary_search_rotated_by(&(10, 'z'), |a, b| a.0.cmp(&b.0)),
+            Err(2)
+        );
+    }
+
+    #[test]
+    fn test_boundary_duplicates() {
+        // The first and last elements are equal, so the rotation point is found by scanning.
+        let slice = [2, 2, 1, 2, 2, 2];
+        assert_eq!(slice.bl_rotation_point(), 2);
+        assert_eq!(slice.bl_binary_search_rotated(&1), Ok(2));
+        assert_eq!(slice.bl_binary_search_rotated(&2), Ok(3));
+        check(&slice);
+    }
+}
@@ -0,0 +1,319 @@
+//! Routing keys to shards by a sorted list of split points.
+//!
+//! A [`BoundaryRouter`] with `n` boundaries splits the keys into `n + 1` shards: shard `0` holds
+//! the keys below the first boundary, shard `i` those between boundaries `i - 1` and `i`, and
+//! shard `n` those above the last. A key equal to a boundary goes to the shard on the side
+//! chosen by [`BoundaryOwner`].
+//!
+//! ```
+//! use shar_search::router::{BoundaryOwner, BoundaryRouter};
+//!
+//! struct Event {
+//!     user_id: u32,
+//!     action: &'static str,
+//! }
+//!
+//! let router = BoundaryRouter::new(vec![1000, 2000, 3000], BoundaryOwner::Right).unwrap();
+//! let events = [
+//!     Event { user_id: 2500, action: "login" },
+//!     Event { user_id: 17, action: "view" },
+//!     Event { user_id: 3000, action: "buy" },
+//!     Event { user_id: 999, action: "logout" },
+//!     Event { user_id: 2000, action: "view" },
+//! ];
+//!
+//! let mut shards: Vec<Vec<Event>> = (0..router.shard_count()).map(|_| Vec::new()).collect();
+//! for event in events {
+//!     shards[router.route(&event.user_id)].push(event);
+//! }
+//!
+//! let actions: Vec<Vec<&str>> = shards
+//!     .iter()
+//!     .map(|shard| shard.iter().map(|event| event.action).collect())
+//!     .collect();
+//! assert_eq!(actions, [vec!["view", "logout"], vec![], vec!["login", "view"], vec!["buy"]]);
+//! ```
+
+use alloc::vec::Vec;
+use core::{borrow::Borrow, error::Error, fmt, ops::Bound};
+
+use crate::{validate, SharBatchSearch, SharBinarySearch};
+
+/// Which of the two shards meeting at a boundary a key equal to it is routed to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BoundaryOwner {
+    /// The shard below the boundary, so that each shard's range is `(lower, upper]`.
+    Left,
+    /// The shard above the boundary, so that each shard's range is `[lower, upper)`.
+    Right,
+}
+
+/// The error returned when a router's boundaries, or a
+/// [`StepFunction`](crate::step::StepFunction)'s breakpoints, are not strictly increasing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoundaryError {
+    pub(crate) index: usize,
+}
+
+impl BoundaryError {
+    /// Returns the index of the first boundary that is not greater than its predecessor.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl fmt::Display for BoundaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "boundary at index {} is not greater than its predecessor",
+            self.index
+        )
+    }
+}
+
+impl Error for BoundaryError {}
+
+/// Routes keys to shards by a list of strictly increasing boundaries. See the
+/// [`router`](crate::router) module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BoundaryRouter<K> {
+    boundaries: Vec<K>,
+    owner: BoundaryOwner,
+}
+
+impl<K: Ord> BoundaryRouter<K> {
+    /// Creates a router with the given boundaries, where keys equal to a boundary go to the
+    /// shard on the side of `owner`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BoundaryError`] with the index of the first boundary that is not greater than
+    /// its predecessor, if any.
+    pub fn new(boundaries: Vec<K>, owner: BoundaryOwner) -> Result<Self, BoundaryError> {
+        match validate::first_unsorted_by(&boundaries, |a, b| a < b) {
+            Some(index) => Err(BoundaryError { index }),
+            None => Ok(Self::from_boundaries_unchecked(boundaries, owner)),
+        }
+    }
+
+    /// Returns the number of shards, one more than the number of boundaries.
+    pub fn shard_count(&self) -> usize {
+        self.boundaries.len() + 1
+    }
+
+    /// Returns the shard that `key` is routed to.
+    ///
+    /// ```
+    /// use shar_search::router::{BoundaryOwner, BoundaryRouter};
+    ///
+    /// let right = BoundaryRouter::new(vec![10, 20], BoundaryOwner::Right).unwrap();
+    /// assert_eq!([5, 10, 15, 20, 25].map(|key| right.route(&key)), [0, 1, 1, 2, 2]);
+    ///
+    /// let left = BoundaryRouter::new(vec![10, 20], BoundaryOwner::Left).unwrap();
+    /// assert_eq!([5, 10, 15, 20, 25].map(|key| left.route(&key)), [0, 0, 1, 1, 2]);
+    /// ```
+    pub fn route<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match self.owner {
+            BoundaryOwner::Left => self.boundaries.bl_partition_point(|b| b.borrow() < key),
+            BoundaryOwner::Right => self.boundaries.bl_partition_point(|b| b.borrow() <= key),
+        }
+    }
+
+    /// Returns the shard of each of `sorted_keys`, in the same order. Each search gallops
+    /// forward from the previous one, as with
+    /// [`bl_binary_search_sorted_keys`](SharBatchSearch::bl_binary_search_sorted_keys). Note it
+    /// is assumed that the keys are sorted; unsorted keys give unspecified results.
+    ///
+    /// In debug builds, this panics if `sorted_keys` is not sorted.
+    pub fn route_many(&self, sorted_keys: &[K]) -> Vec<usize> {
+        // The boundaries are distinct, so a match is the only boundary equal to the key.
+        let past_match = usize::from(self.owner == BoundaryOwner::Right);
+        self.boundaries
+            .bl_binary_search_sorted_keys(sorted_keys)
+            .into_iter()
+            .map(|result| result.map_or_else(|index| index, |index| index + past_match))
+            .collect()
+    }
+}
+
+impl<K> BoundaryRouter<K> {
+    /// Creates a router with the given boundaries. Note it is assumed that they are strictly
+    /// increasing.
+    pub(crate) fn from_boundaries_unchecked(boundaries: Vec<K>, owner: BoundaryOwner) -> Self {
+        Self { boundaries, owner }
+    }
+
+    /// Returns the boundaries, in increasing order.
+    pub fn boundaries(&self) -> &[K] {
+        &self.boundaries
+    }
+
+    /// Returns which shard keys equal to a boundary are routed to.
+    pub fn owner(&self) -> BoundaryOwner {
+        self.owner
+    }
+
+    /// Returns the bounds of the keys routed to shard `shard`, as `(start, end)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shard` is not less than [`shard_count`](BoundaryRouter::shard_count).
+    ///
+    /// ```
+    /// use std::ops::Bound::{Excluded, Included, Unbounded};
+    ///
+    /// use shar_search::router::{BoundaryOwner, BoundaryRouter};
+    ///
+    /// let router = BoundaryRouter::new(vec![10, 20], BoundaryOwner::Right).unwrap();
+    /// assert_eq!(router.shard_range(0), (Unbounded, Excluded(&10)));
+    /// assert_eq!(router.shard_range(1), (Included(&10), Excluded(&20)));
+    /// assert_eq!(router.shard_range(2), (Included(&20), Unbounded));
+    /// ```
+    pub fn shard_range(&self, shard: usize) -> (Bound<&K>, Bound<&K>) {
+        assert!(
+            shard <= self.boundaries.len(),
+            "shard {shard} out of range for {} shards",
+            self.boundaries.len() + 1
+        );
+
+        let lower = match (shard.checked_sub(1), self.owner) {
+            (None, _) => Bound::Unbounded,
+            (Some(previous), BoundaryOwner::Left) => Bound::Excluded(&self.boundaries[previous]),
+            (Some(previous), BoundaryOwner::Right) => Bound::Included(&self.boundaries[previous]),
+        };
+        let upper = match (self.boundaries.get(shard), self.owner) {
+            (None, _) => Bound::Unbounded,
+            (Some(boundary), BoundaryOwner::Left) => Bound::Included(boundary),
+            (Some(boundary), BoundaryOwner::Right) => Bound::Excluded(boundary),
+        };
+
+        (lower, upper)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::ops::{Bound, RangeBounds};
+
+    use super::{BoundaryOwner, BoundaryRouter};
+    use crate::{
+        reference::{self, assert_matches_reference, inputs},
+        test_util::XorShift,
+    };
+
+    const OWNERS: [BoundaryOwner; 2] = [BoundaryOwner::Left, BoundaryOwner::Right];
+
+    #[test]
+    fn test_keys_equal_to_boundaries() {
+        let left = BoundaryRouter::new(vec![0, 10, 20], BoundaryOwner::Left).unwrap();
+        let right = BoundaryRouter::new(vec![0, 10, 20], BoundaryOwner::Right).unwrap();
+
+        for (i, boundary) in [0, 10, 20].into_iter().enumerate() {
+            assert_eq!(left.route(&boundary), i);
+            assert_eq!(right.route(&boundary), i + 1);
+            assert_eq!(left.route(&(boundary + 1)), i + 1);
+            assert_eq!(right.route(&(boundary - 1)), i);
+        }
+        assert_eq!(
+            left.route_many(&[-1, 0, 10, 10, 20, 21]),
+            [0, 0, 1, 1, 2, 3]
+        );
+        assert_eq!(
+            right.route_many(&[-1, 0, 10, 10, 20, 21]),
+            [0, 1, 2, 2, 3, 3]
+        );
+
+        assert_eq!(
+            left.shard_range(1),
+            (Bound::Excluded(&0), Bound::Included(&10))
+        );
+        assert_eq!(
+            right.shard_range(1),
+            (Bound::Included(&0), Bound::Excluded(&10))
+        );
+    }
+
+    #[test]
+    fn test_edge_cases() {
+        for owner in OWNERS {
+            let single = BoundaryRouter::<u8>::new(vec![], owner).unwrap();
+            assert_eq!(single.shard_count(), 1);
+            assert_eq!(single.route(&0), 0);
+            assert_eq!(single.route(&u8::MAX), 0);
+            assert_eq!(single.route_many(&[0, 1, 2]), [0, 0, 0]);
+            assert_eq!(single.shard_range(0), (Bound::Unbounded, Bound::Unbounded));
+
+            let names = BoundaryRouter::new(vec!["g".to_string(), "p".to_string()], owner).unwrap();
+            assert_eq!(names.route("a"), 0);
+            assert_eq!(names.route("m"), 1);
+            assert_eq!(names.route("z"), 2);
+        }
+    }
+
+    #[test]
+    fn test_rejects_unsorted_boundaries() {
+        let err = BoundaryRouter::new(vec![1, 5, 5, 9], BoundaryOwner::Right).unwrap_err();
+        assert_eq!(err.index(), 2);
+        assert_eq!(
+            err.to_string(),
+            "boundary at index 2 is not greater than its predecessor"
+        );
+        let err = BoundaryRouter::new(vec![3, 2], BoundaryOwner::Left).unwrap_err();
+        assert_eq!(err.index(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "shard 3 out of range for 3 shards")]
+    fn test_shard_range_out_of_range() {
+        let router = BoundaryRouter::new(vec![1, 2], BoundaryOwner::Left).unwrap();
+        router.shard_range(3);
+    }
+
+    #[test]
+    fn test_route_lands_in_shard_range() {
+        let mut rng = XorShift::new(182);
+        for owner in OWNERS {
+            for _ in 0..50 {
+                let mut boundaries: Vec<u64> = (0..rng.below(12)).map(|_| rng.below(40)).collect();
+                boundaries.sort_unstable();
+                boundaries.dedup();
+                let router = BoundaryRouter::new(boundaries, owner).unwrap();
+
+                let keys: Vec<u64> = (0..45).collect();
+                let shards = router.route_many(&keys);
+                for (key, &shard) in keys.iter().zip(&shards) {
+                    assert_eq!(router.route(key), shard);
+                    assert!(router.shard_range(shard).contains(key), "{key} in {shard}");
+                }
+                assert!(shards.is_sorted());
+            }
+        }
+    }
+
+    #[test]
+    fn test_against_reference() {
+        for owner in OWNERS {
+            assert_matches_reference!(
+                for (keys, x) in inputs::slices_with_keys(182) =>
+                {
+                    let mut boundaries = keys.clone();
+                    boundaries.dedup();
+                    BoundaryRouter::new(boundaries, owner).unwrap().route(&x)
+                },
+                {
+                    let mut boundaries = keys.clone();
+                    boundaries.dedup();
+                    match (reference::binary_search(&boundaries, &x), owner) {
+                        (Ok(i), BoundaryOwner::Right) => i + 1,
+                        (Ok(i) | Err(i), _) => i,
+                    }
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! Iterating over the runs of equal elements in a sorted slice.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+
+use crate::gallop::{gallop, gallop_back};
+
+/// An iterator over the runs of equal elements in a sorted slice, yielding the start index of
+/// each run together with the run.
+///
+/// The end of each run is found by galloping, with doubling probes followed by a branchless
+/// search of the bracketing window, so a run of length `k` costs `O(log k)` comparisons. This
+/// beats a linear scan when runs are long, but costs a few more comparisons when most runs are
+/// only one or two elements long.
+///
+/// Created by [`SharBinarySearch::bl_runs`](crate::SharBinarySearch::bl_runs) and
+/// [`SharBinarySearch::bl_runs_by_key`](crate::SharBinarySearch::bl_runs_by_key).
+pub struct Runs<'a, T, F> {
+    slice: &'a [T],
+    /// The index of `slice` in the original slice.
+    offset: usize,
+    /// Returns whether two elements belong in the same run.
+    same: F,
+}
+
+impl<'a, T, F> Runs<'a, T, F>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    pub(crate) fn new(slice: &'a [T], same: F) -> Self {
+        Self {
+            slice,
+            offset: 0,
+            same,
+        }
+    }
+}
+
+impl<'a, T, F> Iterator for Runs<'a, T, F>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    type Item = (usize, &'a [T]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (first, rest) = self.slice.split_first()?;
+        let len = 1 + gallop(rest, |x| (self.same)(first, x));
+
+        let (run, rest) = self.slice.split_at(len);
+        let start = self.offset;
+        self.slice = rest;
+        self.offset += len;
+
+        Some((start, run))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::from(!self.slice.is_empty()), Some(self.slice.len()))
+    }
+}
+
+impl<T, F> DoubleEndedIterator for Runs<'_, T, F>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (last, rest) = self.slice.split_last()?;
+        let start = gallop_back(rest, |x| !(self.same)(x, last));
+
+        let (rest, run) = self.slice.split_at(start);
+        self.slice = rest;
+
+        Some((self.offset + start, run))
+    }
+}
+
+impl<T, F> FusedIterator for Runs<'_, T, F> where F: FnMut(&T, &T) -> bool {}
+
+/// Collapses a sorted slice into its distinct elements, each with the length of its run. Note it
+/// is assumed that the slice is sorted.
+///
+/// Each run is found by galloping, as in [`Runs`], so a slice of `n` elements with `k` distinct
+/// values takes `O(k log n)` comparisons: a slice that is almost entirely one value is counted
+/// in a few dozen.
+///
+/// ```
+/// use shar_search::runs::count_runs;
+///
+/// let status_codes = [200, 200, 200, 200, 404, 500, 500];
+/// assert_eq!(count_runs(&status_codes), [(&200, 4), (&404, 1), (&500, 2)]);
+/// ```
+#[cfg(feature = "alloc")]
+pub fn count_runs<T: Ord>(slice: &[T]) -> Vec<(&T, u64)> {
+    Runs::new(slice, T::eq)
+        .map(|(_, run)| (&run[0], run.len() as u64))
+        .collect()
+}
+
+/// Collapses a slice sorted by a key into the first element of each run of equal keys, with the
+/// length of the run. Note it is assumed that the slice is sorted by the key. See
+/// [`count_runs`].
+///
+/// ```
+/// use shar_search::runs::count_runs_by_key;
+///
+/// let words = ["ant", "ape", "bee", "cat", "cow", "cub"];
+/// let by_initial = count_runs_by_key(&words, |w| w.as_bytes()[0]);
+/// assert_eq!(by_initial, [(&"ant", 2), (&"bee", 1), (&"cat", 3)]);
+/// ```
+#[cfg(feature = "alloc")]
+pub fn count_runs_by_key<T, B, F>(slice: &[T], mut f: F) -> Vec<(&T, u64)>
+where
+    F: FnMut(&T) -> B,
+    B: Ord,
+{
+    Runs::new(slice, move |a, b| f(a) == f(b))
+        .map(|(_, run)| (&run[0], run.len() as u64))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "alloc")]
+    use super::{count_runs, count_runs_by_key};
+    use crate::{
+        reference::{self, assert_matches_reference, inputs},
+        SharBinarySearch,
+    };
+
+    /// Collects the runs by alternating between the two ends of the iterator.
+    fn from_both_ends(slice: &[u32]) -> Vec<(usize, &[u32])> {
+        let mut runs = slice.bl_runs();
+        let (mut front, mut back) = (Vec::new(), Vec::new());
+        loop {
+            match (runs.next(), runs.next_back()) {
+                (Some(f), Some(b)) => {
+                    front.push(f);
+                    back.push(b);
+                }
+                (Some(f), None) => front.push(f),
+                _ => break,
+            }
+        }
+        front.extend(back.into_iter().rev());
+        front
+    }
+
+    #[test]
+    fn test_against_reference() {
+        assert_matches_reference!(
+            for slice in inputs::sorted_slices(61) =>
+            slice.bl_runs().collect::<Vec<_>>(),
+            reference::runs(&slice),
+        );
+        assert_matches_reference!(
+            for slice in inputs::sorted_slices(62) =>
+            {
+                let mut backwards: Vec<_> = slice.bl_runs().rev().collect();
+                backwards.reverse();
+                backwards
+            },
+            reference::runs(&slice),
+        );
+        assert_matches_reference!(
+            for slice in inputs::sorted_slices(63) =>
+            from_both_ends(&slice),
+            reference::runs(&slice),
+        );
+    }
+
+    #[test]
+    fn test_by_key() {
+        let slice = [(1, 'a'), (1, 'b'), (2, 'c'), (5, 'd'), (5, 'e'), (5, 'f')];
+        let runs: Vec<_> = slice
+            .bl_runs_by_key(|x| x.0)
+            .map(|(start, run)| (start, run.len()))
+            .collect();
+        assert_eq!(runs, [(0, 2), (2, 1), (3, 3)]);
+
+        let empty: [u32; 0] = [];
+        assert_eq!(empty.bl_runs().next(), None);
+        assert_eq!(empty.bl_runs().next_back(), None);
+    }
+
+    #[test]
+    fn test_huge_run() {
+        let mut slice = vec![7_u32; 1_000_000];
+        slice.push(8);
+
+        let runs: Vec<_> = slice
+            .bl_runs()
+            .map(|(start, run)| (start, run.len()))
+            .collect();
+        assert_eq!(runs, [(0, 1_000_000), (1_000_000, 1)]);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_count_runs() {
+        assert_matches_reference!(
+            for slice in inputs::sorted_slices(167) =>
+            count_runs(&slice),
+            reference::counts(&slice),
+        );
+        assert_matches_reference!(
+            for slice in inputs::sorted_slices(168) =>
+            count_runs_by_key(&slice, |x| x / 4),
+            slice
+                .chunk_by(|a, b| a / 4 == b / 4)
+                .map(|run| (&run[0], run.len() as u64))
+                .collect::<Vec<_>>(),
+        );
+
+        assert!(count_runs::<u32>(&[]).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_count_runs_comparisons() {
+        // A few gigantic runs take a few comparisons each, rather than one per element.
+        let slice: Vec<u32> = (0..1_000_000).map(|i| i / 250_000).collect();
+        let mut comparisons = 0;
+        let counts = count_runs_by_key(&slice, |x| {
+            comparisons += 1;
+            *x
+        });
+        assert_eq!(
+            counts,
+            [(&0, 250_000), (&1, 250_000), (&2, 250_000), (&3, 250_000)]
+        );
+        assert!(comparisons < 4 * 2 * 2 * 40, "{comparisons} comparisons");
+    }
+}
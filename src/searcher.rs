@@ -0,0 +1,488 @@
+//! A reusable search configuration, with its options encoded in its type.
+
+use core::{cmp::Ordering, fmt, marker::PhantomData, ops::Range};
+
+use crate::SharBinarySearch;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// The order a [`Searcher`] expects slices to be sorted in: [`Ascending`] or [`Descending`].
+pub trait Direction: sealed::Sealed {
+    #[doc(hidden)]
+    fn orient(order: Ordering) -> Ordering;
+}
+
+/// Which match a [`Searcher`] returns when there are several: [`Leftmost`], [`Rightmost`] or
+/// [`AnyMatch`].
+pub trait MatchSelection: sealed::Sealed {
+    #[doc(hidden)]
+    fn select<T, F: FnMut(&T) -> Ordering>(slice: &[T], f: F) -> Result<usize, usize>;
+}
+
+/// Whether a [`Searcher`] checks that slices are sorted: [`Unchecked`] or [`ValidateDebug`].
+pub trait Validation: sealed::Sealed {
+    #[doc(hidden)]
+    fn validate<T, F: FnMut(&T) -> Ordering>(slice: &[T], f: F);
+}
+
+/// Slices are sorted in ascending order.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Ascending;
+
+/// Slices are sorted in descending order.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Descending;
+
+/// Searches return the first match.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Leftmost;
+
+/// Searches return the last match.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Rightmost;
+
+/// Searches may return any match. This currently returns the first, but callers should not
+/// rely on that.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AnyMatch;
+
+/// Slices are assumed to be sorted.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Unchecked;
+
+/// In debug builds, every search checks that the slice is sorted relative to the key, and
+/// panics if not. Release builds skip the check.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ValidateDebug;
+
+impl sealed::Sealed for Ascending {}
+impl sealed::Sealed for Descending {}
+impl sealed::Sealed for Leftmost {}
+impl sealed::Sealed for Rightmost {}
+impl sealed::Sealed for AnyMatch {}
+impl sealed::Sealed for Unchecked {}
+impl sealed::Sealed for ValidateDebug {}
+
+impl Direction for Ascending {
+    #[inline]
+    fn orient(order: Ordering) -> Ordering {
+        order
+    }
+}
+
+impl Direction for Descending {
+    #[inline]
+    fn orient(order: Ordering) -> Ordering {
+        order.reverse()
+    }
+}
+
+impl MatchSelection for Leftmost {
+    #[inline]
+    fn select<T, F: FnMut(&T) -> Ordering>(slice: &[T], f: F) -> Result<usize, usize> {
+        slice.bl_binary_search_by(f)
+    }
+}
+
+impl MatchSelection for Rightmost {
+    #[inline]
+    fn select<T, F: FnMut(&T) -> Ordering>(slice: &[T], mut f: F) -> Result<usize, usize> {
+        let end = slice.bl_partition_point(|p| f(p).is_le());
+        match end.checked_sub(1) {
+            Some(last) if f(&slice[last]).is_eq() => Ok(last),
+            _ => Err(end),
+        }
+    }
+}
+
+impl MatchSelection for AnyMatch {
+    #[inline]
+    fn select<T, F: FnMut(&T) -> Ordering>(slice: &[T], f: F) -> Result<usize, usize> {
+        slice.bl_binary_search_by(f)
+    }
+}
+
+impl Validation for Unchecked {
+    #[inline]
+    fn validate<T, F: FnMut(&T) -> Ordering>(_: &[T], _: F) {}
+}
+
+impl Validation for ValidateDebug {
+    #[inline]
+    fn validate<T, F: FnMut(&T) -> Ordering>(slice: &[T], f: F) {
+        debug_assert!(
+            slice.iter().map(f).is_sorted(),
+            "the slice must be sorted in the searcher's direction"
+        );
+    }
+}
+
+/// A reusable, zero-sized search configuration: the direction slices are sorted in, which match
+/// to return when there are several, and whether to check that slices are sorted.
+///
+/// The options are encoded in the searcher's type, so choosing them costs nothing at run time.
+/// A searcher is built with [`Searcher::new`], which starts ascending, leftmost and unchecked,
+/// followed by the option methods, which are `const`:
+///
+/// ```
+/// use shar_search::{
+///     comparators::{against, natural_ascii},
+///     searcher::{Descending, Rightmost, ValidateDebug},
+///     Searcher,
+/// };
+///
+/// /// How the project searches its newest-first logs.
+/// const NEWEST_FIRST: Searcher<Descending, Rightmost, ValidateDebug> =
+///     Searcher::new().descending().rightmost().validate_debug();
+///
+/// let timestamps = [50, 40, 40, 40, 10];
+/// assert_eq!(NEWEST_FIRST.search(&timestamps, &40), Ok(3));
+/// assert_eq!(NEWEST_FIRST.search(&timestamps, &30), Err(4));
+/// assert_eq!(NEWEST_FIRST.equal_range(&timestamps, &40), 1..4);
+///
+/// let files = ["file10", "file2", "file1"];
+/// assert_eq!(NEWEST_FIRST.search_by(&files, against(&"file2", natural_ascii())), Ok(1));
+/// ```
+pub struct Searcher<D = Ascending, M = Leftmost, V = Unchecked> {
+    _options: PhantomData<(D, M, V)>,
+}
+
+impl<D, M, V> Clone for Searcher<D, M, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<D, M, V> Copy for Searcher<D, M, V> {}
+
+impl<D, M, V> fmt::Debug for Searcher<D, M, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Searcher")
+            .field("direction", &core::any::type_name::<D>())
+            .field("matches", &core::any::type_name::<M>())
+            .field("validation", &core::any::type_name::<V>())
+            .finish()
+    }
+}
+
+impl Default for Searcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Searcher {
+    /// Creates a searcher for ascending slices that returns the first match and does not check
+    /// that slices are sorted.
+    pub const fn new() -> Self {
+        Searcher {
+            _options: PhantomData,
+        }
+    }
+}
+
+impl<D, M, V> Searcher<D, M, V> {
+    const fn with<D2, M2, V2>(self) -> Searcher<D2, M2, V2> {
+        Searcher {
+            _options: PhantomData,
+        }
+    }
+
+    /// Expects slices sorted in ascending order.
+    pub const fn ascending(self) -> Searcher<Ascending, M, V> {
+        self.with()
+    }
+
+    /// Expects slices sorted in descending order.
+    pub const fn descending(self) -> Searcher<Descending, M, V> {
+        self.with()
+    }
+
+    /// Returns the first match when there are several.
+    pub const fn leftmost(self) -> Searcher<D, Leftmost, V> {
+        self.with()
+    }
+
+    /// Returns the last match when there are several.
+    pub const fn rightmost(self) -> Searcher<D, Rightmost, V> {
+        self.with()
+    }
+
+    /// Returns any match when there are several.
+    pub const fn any(self) -> Searcher<D, AnyMatch, V> {
+        self.with()
+    }
+
+    /// Checks that slices are sorted in debug builds.
+    pub const fn validate_debug(self) -> Searcher<D, M, ValidateDebug> {
+        self.with()
+    }
+
+    /// Does not check that slices are sorted.
+    pub const fn unchecked(self) -> Searcher<D, M, Unchecked> {
+        self.with()
+    }
+}
+
+impl<D: Direction, M: MatchSelection, V: Validation> Searcher<D, M, V> {
+    /// Returns `f` oriented so that the slice is ascending relative to the target, after
+    /// validating the slice if configured to.
+    #[inline]
+    fn oriented<T, F>(slice: &[T], mut f: F) -> impl FnMut(&T) -> Ordering
+    where
+        F: FnMut(&T) -> Ordering,
+    {
+        V::validate(slice, |p| D::orient(f(p)));
+        move |p| D::orient(f(p))
+    }
+
+    /// Binary searches `slice` for `key`, returning the configured match if there are several.
+    #[inline]
+    pub fn search<T: Ord>(&self, slice: &[T], key: &T) -> Result<usize, usize> {
+        self.search_by(slice, |p| p.cmp(key))
+    }
+
+    /// Binary searches `slice` with a comparator function, which returns the ordering of an
+    /// element relative to the target. See [`Searcher::search`].
+    #[inline]
+    pub fn search_by<T, F>(&self, slice: &[T], f: F) -> Result<usize, usize>
+    where
+        F: FnMut(&T) -> Ordering,
+    {
+        M::select(slice, Self::oriented(slice, f))
+    }
+
+    /// Binary searches `slice` for `b` with a key extraction function. See
+    /// [`Searcher::search`].
+    #[inline]
+    pub fn search_by_key<T, B, F>(&self, slice: &[T], b: &B, mut f: F) -> Result<usize, usize>
+    where
+        F: FnMut(&T) -> B,
+        B: Ord,
+    {
+        self.search_by(slice, |p| f(p).cmp(b))
+    }
+
+    /// Returns the index of the first element of `slice` that does not come before `key` in the
+    /// searcher's direction.
+    #[inline]
+    pub fn lower_bound<T: Ord>(&self, slice: &[T], key: &T) -> usize {
+        self.lower_bound_by(slice, |p| p.cmp(key))
+    }
+
+    /// Returns the lower bound with a comparator function. See [`Searcher::lower_bound`].
+    #[inline]
+    pub fn lower_bound_by<T, F>(&self, slice: &[T], f: F) -> usize
+    where
+        F: FnMut(&T) -> Ordering,
+    {
+        let mut f = Self::oriented(slice, f);
+        slice.bl_partition_point(|p| f(p).is_lt())
+    }
+
+    /// Returns the lower bound for `b` with a key extraction function. See
+    /// [`Searcher::lower_bound`].
+    #[inline]
+    pub fn lower_bound_by_key<T, B, F>(&self, slice: &[T], b: &B, mut f: F) -> usize
+    where
+        F: FnMut(&T) -> B,
+        B: Ord,
+    {
+        self.lower_bound_by(slice, |p| f(p).cmp(b))
+    }
+
+    /// Returns the index of the first element of `slice` that comes after `key` in the
+    /// searcher's direction.
+    #[inline]
+    pub fn upper_bound<T: Ord>(&self, slice: &[T], key: &T) -> usize {
+        self.upper_bound_by(slice, |p| p.cmp(key))
+    }
+
+    /// Returns the upper bound with a comparator function. See [`Searcher::upper_bound`].
+    #[inline]
+    pub fn upper_bound_by<T, F>(&self, slice: &[T], f: F) -> usize
+    where
+        F: FnMut(&T) -> Ordering,
+    {
+        let mut f = Self::oriented(slice, f);
+        slice.bl_partition_point(|p| f(p).is_le())
+    }
+
+    /// Returns the upper bound for `b` with a key extraction function. See
+    /// [`Searcher::upper_bound`].
+    #[inline]
+    pub fn upper_bound_by_key<T, B, F>(&self, slice: &[T], b: &B, mut f: F) -> usize
+    where
+        F: FnMut(&T) -> B,
+        B: Ord,
+    {
+        self.upper_bound_by(slice, |p| f(p).cmp(b))
+    }
+
+    /// Returns the range of indices of all elements of `slice` equal to `key`. The range is
+    /// empty (and starts at the insertion point) if there are no matches.
+    #[inline]
+    pub fn equal_range<T: Ord>(&self, slice: &[T], key: &T) -> Range<usize> {
+        self.equal_range_by(slice, |p| p.cmp(key))
+    }
+
+    /// Returns the range of matches with a comparator function. See
+    /// [`Searcher::equal_range`].
+    #[inline]
+    pub fn equal_range_by<T, F>(&self, slice: &[T], f: F) -> Range<usize>
+    where
+        F: FnMut(&T) -> Ordering,
+    {
+        slice.bl_equal_range_by(Self::oriented(slice, f))
+    }
+
+    /// Returns the range of matches for `b` with a key extraction function. See
+    /// [`Searcher::equal_range`].
+    #[inline]
+    pub fn equal_range_by_key<T, B, F>(&self, slice: &[T], b: &B, mut f: F) -> Range<usize>
+    where
+        F: FnMut(&T) -> B,
+        B: Ord,
+    {
+        self.equal_range_by(slice, |p| f(p).cmp(b))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Direction, MatchSelection, Searcher, Validation};
+    use crate::{
+        comparators::{against, caseless_ascii, reverse},
+        test_util::XorShift,
+        SharBinarySearch,
+    };
+
+    /// Checks every method of `searcher` against the standalone methods, for `data` sorted in
+    /// the searcher's direction.
+    fn check<D, M, V>(searcher: Searcher<D, M, V>, data: &[u32], descending: bool, rightmost: bool)
+    where
+        D: Direction,
+        M: MatchSelection,
+        V: Validation,
+    {
+        for key in 0..12 {
+            let (lower, upper, leftmost) = if descending {
+                (
+                    data.bl_partition_point(|&p| p > key),
+                    data.bl_partition_point(|&p| p >= key),
+                    data.bl_binary_search_by(|p| key.cmp(p)),
+                )
+            } else {
+                (
+                    data.bl_lower_bound(&key),
+                    data.bl_upper_bound(&key),
+                    data.bl_binary_search(&key),
+                )
+            };
+            let expected = match leftmost {
+                Ok(_) if rightmost => Ok(upper - 1),
+                found => found,
+            };
+
+            assert_eq!(searcher.search(data, &key), expected);
+            assert_eq!(searcher.search_by(data, |p| p.cmp(&key)), expected);
+            assert_eq!(searcher.search_by_key(data, &key, |&p| p), expected);
+
+            assert_eq!(searcher.lower_bound(data, &key), lower);
+            assert_eq!(searcher.lower_bound_by(data, |p| p.cmp(&key)), lower);
+            assert_eq!(searcher.lower_bound_by_key(data, &key, |&p| p), lower);
+
+            assert_eq!(searcher.upper_bound(data, &key), upper);
+            assert_eq!(searcher.upper_bound_by(data, |p| p.cmp(&key)), upper);
+            assert_eq!(searcher.upper_bound_by_key(data, &key, |&p| p), upper);
+
+            assert_eq!(searcher.equal_range(data, &key), lower..upper);
+            assert_eq!(searcher.equal_range_by(data, |p| p.cmp(&key)), lower..upper);
+            assert_eq!(
+                searcher.equal_range_by_key(data, &key, |&p| p),
+                lower..upper
+            );
+        }
+    }
+
+    #[test]
+    fn test_every_combination() {
+        let mut rng = XorShift::new(79);
+
+        for _ in 0..50 {
+            let mut ascending: Vec<u32> =
+                (0..rng.below(40)).map(|_| rng.below(10) as u32).collect();
+            ascending.sort_unstable();
+            let mut descending = ascending.clone();
+            descending.reverse();
+            let (asc, desc) = (&ascending[..], &descending[..]);
+
+            let s = Searcher::new();
+            check(s, asc, false, false);
+            check(s.leftmost(), asc, false, false);
+            check(s.rightmost(), asc, false, true);
+            check(s.any(), asc, false, false);
+            check(s.validate_debug(), asc, false, false);
+            check(s.rightmost().validate_debug(), asc, false, true);
+            check(s.any().validate_debug(), asc, false, false);
+
+            let s = Searcher::new().descending();
+            check(s, desc, true, false);
+            check(s.rightmost(), desc, true, true);
+            check(s.any(), desc, true, false);
+            check(s.validate_debug(), desc, true, false);
+            check(s.rightmost().validate_debug(), desc, true, true);
+            check(s.any().validate_debug(), desc, true, false);
+            check(
+                s.validate_debug().unchecked().ascending(),
+                asc,
+                false,
+                false,
+            );
+        }
+    }
+
+    #[test]
+    fn test_zero_sized_const() {
+        const SEARCHER: Searcher<super::Descending, super::Rightmost, super::ValidateDebug> =
+            Searcher::new().descending().rightmost().validate_debug();
+
+        assert_eq!(std::mem::size_of_val(&SEARCHER), 0);
+        assert_eq!(SEARCHER.search(&[5, 3, 3, 1], &3), Ok(2));
+        assert_eq!(Searcher::default().search(&[1, 3, 3, 5], &3), Ok(1));
+    }
+
+    #[test]
+    fn test_comparators() {
+        let headers = ["X-Request-Id", "Host", "content-type", "Accept"];
+        let searcher = Searcher::new().descending().validate_debug();
+
+        assert_eq!(
+            searcher.search_by(&headers, against(&"HOST", caseless_ascii())),
+            Ok(1)
+        );
+        // A reversed comparator on a descending slice searches it as ascending.
+        let ascending = Searcher::new().validate_debug();
+        assert_eq!(
+            ascending.search_by(&headers, against(&"host", reverse(caseless_ascii()))),
+            Ok(1)
+        );
+    }
+
+    #[test]
+    fn test_unchecked_skips_validation() {
+        let _ = Searcher::new().search(&[3, 1, 2], &2);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "the slice must be sorted in the searcher's direction")]
+    fn test_validate_debug() {
+        let _ = Searcher::new()
+            .descending()
+            .validate_debug()
+            .search(&[1, 2, 3], &2);
+    }
+}
@@ -0,0 +1,303 @@
+//! Selecting the k-th smallest element of two sorted slices without merging them.
+//!
+//! [`kth_of_two`] finds the element that would be at index `k` if the slices were merged, with
+//! a binary search over how many of the `k + 1` smallest elements come from each slice. This
+//! takes `O(log(min(m, n)))` comparisons for slices of lengths `m` and `n`, and doesn't
+//! allocate.
+//!
+//! ```
+//! use shar_search::select::{kth_of_two, kth_of_two_with_counts, median_of_two};
+//!
+//! let shard_a = [1, 4, 4, 9, 12];
+//! let shard_b = [2, 3, 4, 20];
+//!
+//! assert_eq!(kth_of_two(&shard_a, &shard_b, 0), &1);
+//! assert_eq!(kth_of_two(&shard_a, &shard_b, 5), &4);
+//! assert_eq!(median_of_two(&shard_a, &shard_b), Some((&4, &4)));
+//!
+//! // Each shard's share of the elements at most the answer.
+//! let kth = kth_of_two_with_counts(&shard_a, &shard_b, 7);
+//! assert_eq!((kth.value, kth.in_a, kth.in_b), (&12, 5, 3));
+//! ```
+
+use core::cmp::Ordering;
+
+use crate::{search_indices, SharBinarySearch};
+
+/// The k-th smallest element of two sorted slices, with how many elements of each are at most
+/// it. Returned by [`kth_of_two_with_counts`].
+///
+/// The counts are the ranks a distributed quantile query needs: with duplicates of `value`,
+/// `in_a + in_b` may exceed `k + 1`.
+#[derive(Debug)]
+pub struct KthOfTwo<'a, T> {
+    /// The k-th smallest element.
+    pub value: &'a T,
+    /// The number of elements of the first slice that are at most `value`.
+    pub in_a: usize,
+    /// The number of elements of the second slice that are at most `value`.
+    pub in_b: usize,
+}
+
+impl<T> Clone for KthOfTwo<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for KthOfTwo<'_, T> {}
+
+/// Returns the element at index `k` of the merge of the sorted slices `a` and `b`. Note it is
+/// assumed that both slices are sorted.
+///
+/// Equal elements are interchangeable, so which slice a returned duplicate comes from is
+/// unspecified.
+///
+/// # Panics
+///
+/// Panics if `k` is not less than `a.len() + b.len()`.
+pub fn kth_of_two<'a, T: Ord>(a: &'a [T], b: &'a [T], k: usize) -> &'a T {
+    kth_of_two_by(a, b, k, T::cmp)
+}
+
+/// Returns the element at index `k` of the merge of the sorted slices `a` and `b`, using a
+/// comparator function that orders the elements. See [`kth_of_two`].
+///
+/// # Panics
+///
+/// Panics if `k` is not less than `a.len() + b.len()`.
+pub fn kth_of_two_by<'a, T, F>(a: &'a [T], b: &'a [T], k: usize, mut compare: F) -> &'a T
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    assert!(
+        k < a.len() + b.len(),
+        "k is {k} but the slices have {} elements",
+        a.len() + b.len()
+    );
+
+    // Search the shorter slice for how many of the `k + 1` smallest elements it holds.
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    let lo = (k + 1).saturating_sub(long.len());
+    let hi = short.len().min(k + 1);
+
+    // Taking `i` elements of `short` takes too few if its next one is at most the last of the
+    // `k + 1 - i` taken from `long`. For `lo <= i < hi`, both of those elements exist.
+    let taken = match search_indices(hi - lo, |offset| {
+        let i = lo + offset;
+        match compare(&short[i], &long[k - i]) {
+            Ordering::Greater => Ordering::Greater,
+            _ => Ordering::Less,
+        }
+    }) {
+        Ok(offset) | Err(offset) => lo + offset,
+    };
+
+    match (taken.checked_sub(1), (k + 1 - taken).checked_sub(1)) {
+        (Some(i), Some(j)) if compare(&short[i], &long[j]).is_gt() => &short[i],
+        (_, Some(j)) => &long[j],
+        (Some(i), None) => &short[i],
+        (None, None) => unreachable!("`k + 1` elements are taken"),
+    }
+}
+
+/// Returns the element at index `k` of the merge of the sorted slices `a` and `b`, using a key
+/// extraction function. See [`kth_of_two`].
+///
+/// # Panics
+///
+/// Panics if `k` is not less than `a.len() + b.len()`.
+pub fn kth_of_two_by_key<'a, T, B, F>(a: &'a [T], b: &'a [T], k: usize, mut f: F) -> &'a T
+where
+    F: FnMut(&T) -> B,
+    B: Ord,
+{
+    kth_of_two_by(a, b, k, |x, y| f(x).cmp(&f(y)))
+}
+
+/// Returns the element at index `k` of the merge of the sorted slices `a` and `b` like
+/// [`kth_of_two`], along with how many elements of each slice are at most it.
+///
+/// ```
+/// use shar_search::select::kth_of_two_with_counts;
+///
+/// let a = [1, 5, 5, 7];
+/// let b = [5, 6];
+/// let kth = kth_of_two_with_counts(&a, &b, 1);
+/// assert_eq!((kth.value, kth.in_a, kth.in_b), (&5, 3, 1));
+/// ```
+///
+/// # Panics
+///
+/// Panics if `k` is not less than `a.len() + b.len()`.
+pub fn kth_of_two_with_counts<'a, T: Ord>(a: &'a [T], b: &'a [T], k: usize) -> KthOfTwo<'a, T> {
+    let value = kth_of_two(a, b, k);
+    KthOfTwo {
+        value,
+        in_a: a.bl_partition_point(|x| x <= value),
+        in_b: b.bl_partition_point(|x| x <= value),
+    }
+}
+
+/// Returns the two middle elements of the merge of the sorted slices `a` and `b`, which are the
+/// same element if they have an odd number of elements in total, or `None` if both are empty.
+///
+/// ```
+/// use shar_search::select::median_of_two;
+///
+/// assert_eq!(median_of_two(&[1, 3], &[2]), Some((&2, &2)));
+/// assert_eq!(median_of_two(&[1, 3], &[2, 4]), Some((&2, &3)));
+/// assert_eq!(median_of_two::<u32>(&[], &[]), None);
+/// ```
+pub fn median_of_two<'a, T: Ord>(a: &'a [T], b: &'a [T]) -> Option<(&'a T, &'a T)> {
+    median_of_two_by(a, b, T::cmp)
+}
+
+/// Returns the two middle elements of the merge of the sorted slices `a` and `b`, using a
+/// comparator function that orders the elements. See [`median_of_two`].
+pub fn median_of_two_by<'a, T, F>(a: &'a [T], b: &'a [T], mut compare: F) -> Option<(&'a T, &'a T)>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let len = a.len() + b.len();
+    let lower = kth_of_two_by(a, b, len.checked_sub(1)? / 2, &mut compare);
+    let upper = if len % 2 == 1 {
+        lower
+    } else {
+        kth_of_two_by(a, b, len / 2, &mut compare)
+    };
+    Some((lower, upper))
+}
+
+/// Returns the two middle elements of the merge of the sorted slices `a` and `b`, using a key
+/// extraction function. See [`median_of_two`].
+pub fn median_of_two_by_key<'a, T, B, F>(a: &'a [T], b: &'a [T], mut f: F) -> Option<(&'a T, &'a T)>
+where
+    F: FnMut(&T) -> B,
+    B: Ord,
+{
+    median_of_two_by(a, b, |x, y| f(x).cmp(&f(y)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{kth_of_two, kth_of_two_by_key, kth_of_two_with_counts, median_of_two};
+    use crate::test_util::XorShift;
+
+    fn merged(a: &[u64], b: &[u64]) -> Vec<u64> {
+        let mut merged = [a, b].concat();
+        merged.sort_unstable();
+        merged
+    }
+
+    fn check(a: &[u64], b: &[u64]) {
+        let merged = merged(a, b);
+        for (k, expected) in merged.iter().enumerate() {
+            assert_eq!(kth_of_two(a, b, k), expected, "{a:?} {b:?} {k}");
+            assert_eq!(kth_of_two(b, a, k), expected, "{b:?} {a:?} {k}");
+
+            let kth = kth_of_two_with_counts(a, b, k);
+            assert_eq!(kth.in_a, a.iter().filter(|&x| x <= expected).count());
+            assert_eq!(kth.in_b, b.iter().filter(|&x| x <= expected).count());
+        }
+    }
+
+    #[test]
+    fn test_ends() {
+        let a = [2, 4, 6];
+        let b = [1, 3, 5, 7];
+        assert_eq!(kth_of_two(&a, &b, 0), &1);
+        assert_eq!(kth_of_two(&a, &b, 6), &7);
+        assert_eq!(kth_of_two(&b, &a, 0), &1);
+        assert_eq!(kth_of_two(&b, &a, 6), &7);
+        check(&a, &b);
+    }
+
+    #[test]
+    fn test_one_empty() {
+        let a = [3, 5, 8];
+        assert_eq!(kth_of_two(&a, &[], 0), &3);
+        assert_eq!(kth_of_two(&[], &a, 2), &8);
+        check(&a, &[]);
+        check(&[], &[4]);
+    }
+
+    #[test]
+    #[should_panic(expected = "k is 3 but the slices have 3 elements")]
+    fn test_k_out_of_range() {
+        kth_of_two(&[1, 2], &[3], 3);
+    }
+
+    #[test]
+    fn test_duplicates_across_slices() {
+        let a = [1, 5, 5, 5, 5, 9];
+        let b = [5, 5, 5];
+        for k in 1..8 {
+            assert_eq!(kth_of_two(&a, &b, k), &5);
+        }
+        let kth = kth_of_two_with_counts(&a, &b, 1);
+        assert_eq!((kth.in_a, kth.in_b), (5, 3));
+        check(&a, &b);
+        check(&[7; 6], &[7; 4]);
+    }
+
+    #[test]
+    fn test_different_sizes() {
+        let long: Vec<u64> = (0..1000).map(|x| x * 2).collect();
+        check(&long, &[0]);
+        check(&long, &[1001]);
+        check(&long, &[5000]);
+        check(&[999, 999, 2001], &long);
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median_of_two::<u64>(&[], &[]), None);
+        assert_eq!(median_of_two(&[4], &[]), Some((&4, &4)));
+        assert_eq!(median_of_two(&[], &[1, 9]), Some((&1, &9)));
+        assert_eq!(
+            median_of_two(&[1, 2, 3], &[10, 20, 30, 40]),
+            Some((&10, &10))
+        );
+        assert_eq!(
+            median_of_two(&[1, 2, 3, 4], &[10, 20, 30, 40]),
+            Some((&4, &10))
+        );
+    }
+
+    #[test]
+    fn test_by_key() {
+        let a = [(1, 'a'), (6, 'b')];
+        let b = [(2, 'c'), (3, 'd'), (9, 'e')];
+        assert_eq!(kth_of_two_by_key(&a, &b, 2, |x| x.0), &(3, 'd'));
+        assert_eq!(kth_of_two_by_key(&a, &b, 3, |x| x.0), &(6, 'b'));
+    }
+
+    #[test]
+    fn test_against_merging() {
+        let mut rng = XorShift::new(191);
+        for _ in 0..500 {
+            let range = 1 + rng.below(50);
+            let (a_len, b_len) = if rng.below(4) == 0 {
+                (rng.below(3), rng.below(300))
+            } else {
+                (rng.below(40), rng.below(40))
+            };
+            let mut side = |len: u64| {
+                let mut values: Vec<u64> = (0..len).map(|_| rng.below(range)).collect();
+                values.sort_unstable();
+                values
+            };
+            let a = side(a_len);
+            let b = side(b_len);
+            check(&a, &b);
+
+            let merged = merged(&a, &b);
+            let len = merged.len();
+            let expected = len
+                .checked_sub(1)
+                .map(|_| (&merged[(len - 1) / 2], &merged[len / 2]));
+            assert_eq!(median_of_two(&a, &b), expected);
+        }
+    }
+}
@@ -0,0 +1,624 @@
+//! [Serde](https://serde.rs) support for the sorted containers, enabled with the `serde` feature.
+//!
+//! Containers serialize as plain sequences so that other consumers can read the data without
+//! knowing about this crate. Deserialization never trusts the input ordering: the
+//! [`Deserialize`] implementations (and [`deserialize`]) are lenient and re-establish the
+//! container's invariant by sorting (and deduplicating where the container requires it), while
+//! [`deserialize_strict`] rejects input that is not already in the container's canonical order
+//! with a [`serde::de::Error`]. Either can be selected per field with
+//! `#[serde(deserialize_with = "...")]`:
+//!
+//! ```
+//! # use serde::Deserialize;
+//! use shar_search::SharSet;
+//!
+//! #[derive(Deserialize)]
+//! struct Config {
+//!     #[serde(deserialize_with = "shar_search::serialization::deserialize_strict")]
+//!     allowed: SharSet<u32>,
+//! }
+//!
+//! let config: Config = serde_json::from_str(r#"{ "allowed": [1, 5, 9] }"#).unwrap();
+//! assert!(config.allowed.contains(&5));
+//!
+//! assert!(serde_json::from_str::<Config>(r#"{ "allowed": [5, 1, 9] }"#).is_err());
+//! ```
+
+use alloc::vec::Vec;
+use core::{fmt, marker::PhantomData};
+
+use serde::{
+    de::{Error, MapAccess, SeqAccess, Visitor},
+    ser::SerializeStruct,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    raw,
+    router::{BoundaryOwner, BoundaryRouter},
+    validate, SharMap, SharMultiMap, SharSet, SortedVec,
+};
+
+/// A container that can be deserialized with either a lenient or a strict ordering policy.
+pub trait SortedDeserialize<'de>: Sized {
+    /// Deserializes the container, sorting (and deduplicating where needed) the input.
+    fn deserialize_lenient<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+
+    /// Deserializes the container, returning an error if the input is not already in the
+    /// container's canonical order.
+    fn deserialize_strict<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+}
+
+/// Deserializes a sorted container, re-sorting (and deduplicating where needed) the input.
+///
+/// This is equivalent to the container's [`Deserialize`] implementation.
+pub fn deserialize<'de, D, C>(deserializer: D) -> Result<C, D::Error>
+where
+    D: Deserializer<'de>,
+    C: SortedDeserialize<'de>,
+{
+    C::deserialize_lenient(deserializer)
+}
+
+/// Deserializes a sorted container, returning an error if the input is unsorted or contains
+/// duplicates the container does not allow.
+pub fn deserialize_strict<'de, D, C>(deserializer: D) -> Result<C, D::Error>
+where
+    D: Deserializer<'de>,
+    C: SortedDeserialize<'de>,
+{
+    C::deserialize_strict(deserializer)
+}
+
+impl<T: Serialize> Serialize for SharSet<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.as_slice())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Ord> Deserialize<'de> for SharSet<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::deserialize_lenient(deserializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Ord> SortedDeserialize<'de> for SharSet<T> {
+    fn deserialize_lenient<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut values = Vec::<T>::deserialize(deserializer)?;
+        values.sort();
+        values.dedup();
+
+        Ok(Self::from_sorted_vec_unchecked(values))
+    }
+
+    fn deserialize_strict<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let values = Vec::<T>::deserialize(deserializer)?;
+
+        match validate::first_unsorted_by(&values, |a, b| a < b) {
+            Some(index) => Err(D::Error::custom(format_args!(
+                "set elements are not strictly increasing at index {index}"
+            ))),
+            None => Ok(Self::from_sorted_vec_unchecked(values)),
+        }
+    }
+}
+
+impl<T: Serialize> Serialize for SortedVec<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.as_slice())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Ord> Deserialize<'de> for SortedVec<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::deserialize_lenient(deserializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Ord> SortedDeserialize<'de> for SortedVec<T> {
+    fn deserialize_lenient<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut values = Vec::<T>::deserialize(deserializer)?;
+        values.sort();
+
+        Ok(Self::from_sorted_vec_unchecked(values))
+    }
+
+    fn deserialize_strict<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let values = Vec::<T>::deserialize(deserializer)?;
+
+        match validate::first_unsorted_by(&values, |a, b| a <= b) {
+            Some(index) => Err(D::Error::custom(format_args!(
+                "elements are not sorted at index {index}"
+            ))),
+            None => Ok(Self::from_sorted_vec_unchecked(values)),
+        }
+    }
+}
+
+/// Maps serialize as a map, in key order.
+impl<K: Serialize, V: Serialize> Serialize for SharMap<K, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+/// Collects the entries of a serialized map in their serialized order.
+struct PairsVisitor<K, V>(PhantomData<(K, V)>);
+
+impl<'de, K: Deserialize<'de>, V: Deserialize<'de>> Visitor<'de> for PairsVisitor<K, V> {
+    type Value = Vec<(K, V)>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a map")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        let mut pairs = Vec::with_capacity(access.size_hint().unwrap_or(0).min(4096));
+        while let Some(pair) = access.next_entry()? {
+            pairs.push(pair);
+        }
+
+        Ok(pairs)
+    }
+}
+
+impl<'de, K: Deserialize<'de> + Ord, V: Deserialize<'de>> Deserialize<'de> for SharMap<K, V> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::deserialize_lenient(deserializer)
+    }
+}
+
+impl<'de, K: Deserialize<'de> + Ord, V: Deserialize<'de>> SortedDeserialize<'de> for SharMap<K, V> {
+    /// If a key is repeated, the first key is kept with the last value, as when extending a
+    /// map.
+    fn deserialize_lenient<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut pairs = deserializer.deserialize_map(PairsVisitor::<K, V>(PhantomData))?;
+        // Stable, so each run of equal keys is in input order.
+        pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+        raw::dedup_keep_last_value_by(&mut pairs, |a, b| a == b);
+
+        Ok(Self::from_sorted_vec_unchecked(pairs))
+    }
+
+    fn deserialize_strict<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pairs = deserializer.deserialize_map(PairsVisitor(PhantomData))?;
+
+        match validate::first_unsorted_by(&pairs, |(a, _), (b, _)| a < b) {
+            Some(index) => Err(D::Error::custom(format_args!(
+                "map keys are not strictly increasing at index {index}"
+            ))),
+            None => Ok(Self::from_sorted_vec_unchecked(pairs)),
+        }
+    }
+}
+
+/// Multimaps serialize as a sequence of `(key, value)` pairs rather than a map, since many
+/// formats do not allow repeated map keys.
+impl<K: Serialize, V: Serialize> Serialize for SharMultiMap<K, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.as_slice())
+    }
+}
+
+impl<'de, K: Deserialize<'de> + Ord, V: Deserialize<'de>> Deserialize<'de> for SharMultiMap<K, V> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::deserialize_lenient(deserializer)
+    }
+}
+
+impl<'de, K: Deserialize<'de> + Ord, V: Deserialize<'de>> SortedDeserialize<'de>
+    for SharMultiMap<K, V>
+{
+    /// Pairs are stably sorted by key, so values for a key keep their serialized order.
+    fn deserialize_lenient<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut pairs = Vec::<(K, V)>::deserialize(deserializer)?;
+        pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        Ok(Self::from_sorted_vec_unchecked(pairs))
+    }
+
+    fn deserialize_strict<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pairs = Vec::<(K, V)>::deserialize(deserializer)?;
+
+        match validate::first_unsorted_by(&pairs, |(a, _), (b, _)| a <= b) {
+            Some(index) => Err(D::Error::custom(format_args!(
+                "multimap keys are not sorted at index {index}"
+            ))),
+            None => Ok(Self::from_sorted_vec_unchecked(pairs)),
+        }
+    }
+}
+
+/// Owners serialize as `"left"` or `"right"`.
+impl Serialize for BoundaryOwner {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(match self {
+            BoundaryOwner::Left => "left",
+            BoundaryOwner::Right => "right",
+        })
+    }
+}
+
+struct OwnerVisitor;
+
+impl Visitor<'_> for OwnerVisitor {
+    type Value = BoundaryOwner;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(r#""left" or "right""#)
+    }
+
+    fn visit_str<E: Error>(self, value: &str) -> Result<Self::Value, E> {
+        match value {
+            "left" => Ok(BoundaryOwner::Left),
+            "right" => Ok(BoundaryOwner::Right),
+            _ => Err(E::unknown_variant(value, &["left", "right"])),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BoundaryOwner {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(OwnerVisitor)
+    }
+}
+
+const ROUTER_FIELDS: &[&str] = &["boundaries", "owner"];
+
+/// Routers serialize as a struct of their `boundaries` and `owner`.
+impl<K: Serialize> Serialize for BoundaryRouter<K> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut router = serializer.serialize_struct("BoundaryRouter", ROUTER_FIELDS.len())?;
+        router.serialize_field("boundaries", self.boundaries())?;
+        router.serialize_field("owner", &self.owner())?;
+        router.end()
+    }
+}
+
+/// The fields of a serialized router, identified by name or, in formats without names, by
+/// position.
+enum RouterField {
+    Boundaries,
+    Owner,
+}
+
+struct RouterFieldVisitor;
+
+impl Visitor<'_> for RouterFieldVisitor {
+    type Value = RouterField;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a router field")
+    }
+
+    fn visit_u64<E: Error>(self, value: u64) -> Result<Self::Value, E> {
+        match value {
+            0 => Ok(RouterField::Boundaries),
+            1 => Ok(RouterField::Owner),
+            _ => Err(E::custom(format_args!(
+                "router field index {value} out of range"
+            ))),
+        }
+    }
+
+    fn visit_str<E: Error>(self, value: &str) -> Result<Self::Value, E> {
+        match value {
+            "boundaries" => Ok(RouterField::Boundaries),
+            "owner" => Ok(RouterField::Owner),
+            _ => Err(E::unknown_field(value, ROUTER_FIELDS)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for RouterField {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_identifier(RouterFieldVisitor)
+    }
+}
+
+/// Collects the boundaries and owner of a serialized router, without checking the order of
+/// the boundaries.
+struct RouterVisitor<K>(PhantomData<K>);
+
+impl<'de, K: Deserialize<'de>> Visitor<'de> for RouterVisitor<K> {
+    type Value = (Vec<K>, BoundaryOwner);
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a router")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        let boundaries = access
+            .next_element()?
+            .ok_or_else(|| A::Error::invalid_length(0, &self))?;
+        let owner = access
+            .next_element()?
+            .ok_or_else(|| A::Error::invalid_length(1, &self))?;
+
+        Ok((boundaries, owner))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        let (mut boundaries, mut owner) = (None, None);
+        while let Some(field) = access.next_key()? {
+            match field {
+                RouterField::Boundaries if boundaries.is_some() => {
+                    return Err(A::Error::duplicate_field("boundaries"));
+                }
+                RouterField::Boundaries => boundaries = Some(access.next_value()?),
+                RouterField::Owner if owner.is_some() => {
+                    return Err(A::Error::duplicate_field("owner"));
+                }
+                RouterField::Owner => owner = Some(access.next_value()?),
+            }
+        }
+
+        Ok((
+            boundaries.ok_or_else(|| A::Error::missing_field("boundaries"))?,
+            owner.ok_or_else(|| A::Error::missing_field("owner"))?,
+        ))
+    }
+}
+
+fn deserialize_router_parts<'de, D, K>(deserializer: D) -> Result<(Vec<K>, BoundaryOwner), D::Error>
+where
+    D: Deserializer<'de>,
+    K: Deserialize<'de>,
+{
+    deserializer.deserialize_struct("BoundaryRouter", ROUTER_FIELDS, RouterVisitor(PhantomData))
+}
+
+impl<'de, K: Deserialize<'de> + Ord> Deserialize<'de> for BoundaryRouter<K> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::deserialize_lenient(deserializer)
+    }
+}
+
+impl<'de, K: Deserialize<'de> + Ord> SortedDeserialize<'de> for BoundaryRouter<K> {
+    fn deserialize_lenient<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (mut boundaries, owner) = deserialize_router_parts(deserializer)?;
+        boundaries.sort();
+        boundaries.dedup();
+
+        Ok(Self::from_boundaries_unchecked(boundaries, owner))
+    }
+
+    fn deserialize_strict<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (boundaries, owner) = deserialize_router_parts(deserializer)?;
+
+        Self::new(boundaries, owner).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::Deserialize;
+
+    use crate::{
+        router::{BoundaryOwner, BoundaryRouter},
+        SharMap, SharMultiMap, SharSet, SortedVec,
+    };
+
+    #[derive(Deserialize)]
+    struct Strict {
+        #[serde(deserialize_with = "super::deserialize_strict")]
+        set: SharSet<i32>,
+        #[serde(deserialize_with = "super::deserialize_strict", default)]
+        map: SharMap<String, u32>,
+        #[serde(deserialize_with = "super::deserialize_strict", default)]
+        vec: SortedVec<i32>,
+        #[serde(deserialize_with = "super::deserialize_strict")]
+        multimap: SharMultiMap<u8, char>,
+    }
+
+    #[derive(Deserialize)]
+    struct Lenient {
+        #[serde(deserialize_with = "super::deserialize")]
+        set: SharSet<i32>,
+    }
+
+    fn set_of(values: impl IntoIterator<Item = i32>) -> SharSet<i32> {
+        let mut set = SharSet::new();
+        for value in values {
+            set.insert(value);
+        }
+        set
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let set = set_of([5, -3, 12, 0]);
+        let json = serde_json::to_string(&set).unwrap();
+        assert_eq!(json, "[-3,0,5,12]");
+        let back: SharSet<i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.as_slice(), set.as_slice());
+
+        let mut multimap = SharMultiMap::new();
+        multimap.insert("b".to_string(), 1);
+        multimap.insert("a".to_string(), 2);
+        multimap.insert("b".to_string(), 3);
+        let json = serde_json::to_string(&multimap).unwrap();
+        assert_eq!(json, r#"[["a",2],["b",1],["b",3]]"#);
+        let back: SharMultiMap<String, i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.as_slice(), multimap.as_slice());
+    }
+
+    #[test]
+    fn test_sorted_vec_and_map_round_trip() {
+        let mut vec = SortedVec::new();
+        vec.insert_many([3, 1, 3, 2]);
+        let json = serde_json::to_string(&vec).unwrap();
+        assert_eq!(json, "[1,2,3,3]");
+        let back: SortedVec<i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.as_slice(), vec.as_slice());
+        let bytes = postcard::to_allocvec(&vec).unwrap();
+        let back: SortedVec<i32> = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(back.as_slice(), vec.as_slice());
+
+        let mut map = SharMap::new();
+        map.extend([("b".to_string(), 2_u32), ("a".to_string(), 1)]);
+        let json = serde_json::to_string(&map).unwrap();
+        assert_eq!(json, r#"{"a":1,"b":2}"#);
+        let back: SharMap<String, u32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.as_slice(), map.as_slice());
+        let bytes = postcard::to_allocvec(&map).unwrap();
+        let back: SharMap<String, u32> = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(back.as_slice(), map.as_slice());
+    }
+
+    #[test]
+    fn test_postcard_round_trip() {
+        let set = set_of((0..100).map(|i| (i * 37) % 101));
+        let bytes = postcard::to_allocvec(&set).unwrap();
+        let back: SharSet<i32> = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(back.as_slice(), set.as_slice());
+
+        let mut multimap = SharMultiMap::new();
+        for (k, v) in [(3_u8, 'x'), (1, 'y'), (3, 'z')] {
+            multimap.insert(k, v);
+        }
+        let bytes = postcard::to_allocvec(&multimap).unwrap();
+        let back: SharMultiMap<u8, char> = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(back.as_slice(), multimap.as_slice());
+    }
+
+    #[test]
+    fn test_unsorted_input_is_repaired() {
+        let set: SharSet<i32> = serde_json::from_str("[9, 3, 7, 3, -1, 9]").unwrap();
+        assert_eq!(set.as_slice(), &[-1, 3, 7, 9]);
+        assert!(set.contains(&7));
+        assert!(!set.contains(&8));
+
+        let lenient: Lenient = serde_json::from_str(r#"{ "set": [2, 1, 2] }"#).unwrap();
+        assert_eq!(lenient.set.as_slice(), &[1, 2]);
+
+        let multimap: SharMultiMap<u8, char> =
+            serde_json::from_str(r#"[[2, "a"], [1, "b"], [2, "c"], [1, "d"]]"#).unwrap();
+        assert_eq!(multimap.get_all(&1), &[(1, 'b'), (1, 'd')]);
+        assert_eq!(multimap.get_all(&2), &[(2, 'a'), (2, 'c')]);
+
+        let vec: SortedVec<i32> = serde_json::from_str("[3, 1, 2, 1]").unwrap();
+        assert_eq!(vec.as_slice(), &[1, 1, 2, 3]);
+
+        let map: SharMap<String, u32> =
+            serde_json::from_str(r#"{ "z": 1, "a": 2, "z": 3 }"#).unwrap();
+        assert_eq!(
+            map.as_slice(),
+            &[("a".to_string(), 2), ("z".to_string(), 3)]
+        );
+        assert_eq!(map.get("z"), Some(&3));
+    }
+
+    /// A key that compares without regard to case, but remembers the case it was written in.
+    #[derive(Debug, Deserialize)]
+    #[serde(transparent)]
+    struct Caseless(String);
+
+    impl PartialEq for Caseless {
+        fn eq(&self, other: &Self) -> bool {
+            self.cmp(other).is_eq()
+        }
+    }
+
+    impl Eq for Caseless {}
+
+    impl PartialOrd for Caseless {
+        fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Caseless {
+        fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+            self.0.to_lowercase().cmp(&other.0.to_lowercase())
+        }
+    }
+
+    #[test]
+    fn test_repeated_key_keeps_first_key_and_last_value() {
+        let map: SharMap<Caseless, u32> =
+            serde_json::from_str(r#"{ "Key": 1, "b": 2, "KEY": 3, "key": 4 }"#).unwrap();
+        let pairs: Vec<(&str, u32)> = map.iter().map(|(k, &v)| (k.0.as_str(), v)).collect();
+        assert_eq!(pairs, [("b", 2), ("Key", 4)]);
+
+        // The same as extending a map.
+        let mut extended = SharMap::new();
+        let keys = ["Key", "b", "KEY", "key"].map(|k| Caseless(k.to_string()));
+        extended.extend(keys.into_iter().zip([1, 2, 3, 4]));
+        let extended: Vec<(&str, u32)> = extended.iter().map(|(k, &v)| (k.0.as_str(), v)).collect();
+        assert_eq!(extended, pairs);
+    }
+
+    #[test]
+    fn test_strict_rejects_bad_input() {
+        let ok: Strict = serde_json::from_str(
+            r#"{ "set": [1, 2, 3], "multimap": [[1, "a"], [1, "b"]], "map": { "a": 1, "b": 2 } }"#,
+        )
+        .unwrap();
+        assert_eq!(ok.set.as_slice(), &[1, 2, 3]);
+        assert_eq!(ok.multimap.len(), 2);
+        assert_eq!(ok.map.get("b"), Some(&2));
+
+        let unsorted = serde_json::from_str::<Strict>(r#"{ "set": [1, 3, 2], "multimap": [] }"#);
+        let Err(err) = unsorted else {
+            panic!("unsorted input should be rejected");
+        };
+        assert!(err
+            .to_string()
+            .contains("not strictly increasing at index 2"));
+
+        let duplicate = serde_json::from_str::<Strict>(r#"{ "set": [1, 1], "multimap": [] }"#);
+        assert!(duplicate.is_err());
+
+        let unsorted_map = serde_json::from_str::<Strict>(
+            r#"{ "set": [], "multimap": [], "map": { "b": 1, "a": 2 } }"#,
+        );
+        assert!(unsorted_map.is_err());
+
+        let duplicate_map = serde_json::from_str::<Strict>(
+            r#"{ "set": [], "multimap": [], "map": { "a": 1, "a": 2 } }"#,
+        );
+        assert!(duplicate_map.is_err());
+
+        let unsorted_vec =
+            serde_json::from_str::<Strict>(r#"{ "set": [], "multimap": [], "vec": [2, 1] }"#);
+        assert!(unsorted_vec.is_err());
+
+        let ok_vec =
+            serde_json::from_str::<Strict>(r#"{ "set": [], "multimap": [], "vec": [1, 1, 2] }"#);
+        assert_eq!(ok_vec.map(|s| s.vec.len()).ok(), Some(3));
+
+        let unsorted_keys =
+            serde_json::from_str::<Strict>(r#"{ "set": [], "multimap": [[2, "a"], [1, "b"]] }"#);
+        assert!(unsorted_keys.is_err());
+    }
+
+    #[test]
+    fn test_router_round_trip() {
+        let router = BoundaryRouter::new(vec![10_u32, 20, 30], BoundaryOwner::Left).unwrap();
+        let json = serde_json::to_string(&router).unwrap();
+        assert_eq!(json, r#"{"boundaries":[10,20,30],"owner":"left"}"#);
+        let back: BoundaryRouter<u32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, router);
+
+        let bytes = postcard::to_allocvec(&router).unwrap();
+        let back: BoundaryRouter<u32> = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(back, router);
+
+        let lenient: BoundaryRouter<u32> =
+            serde_json::from_str(r#"{"owner":"right","boundaries":[30,10,20,10]}"#).unwrap();
+        assert_eq!(lenient.boundaries(), &[10, 20, 30]);
+        assert_eq!(lenient.owner(), BoundaryOwner::Right);
+
+        let strict = |json| {
+            super::deserialize_strict::<_, BoundaryRouter<u32>>(
+                &mut serde_json::Deserializer::from_str(json),
+            )
+        };
+        assert!(strict(r#"{"boundaries":[1,2],"owner":"right"}"#).is_ok());
+        let err = strict(r#"{"boundaries":[1,1],"owner":"right"}"#).unwrap_err();
+        assert!(err.to_string().contains("boundary at index 1"));
+        assert!(strict(r#"{"boundaries":[1,2],"owner":"up"}"#).is_err());
+        assert!(strict(r#"{"boundaries":[1,2]}"#).is_err());
+    }
+}
@@ -0,0 +1,729 @@
+//! A sorted set backed by a [`Vec`], using Shar's algorithm for lookups.
+
+use alloc::vec::{Drain, Vec};
+use core::{
+    borrow::Borrow,
+    cmp::Ordering,
+    fmt,
+    iter::FusedIterator,
+    ops::{BitAnd, BitOr, BitXor, RangeBounds, Sub},
+    slice,
+};
+
+use crate::{
+    duplicates::sort_with_policy,
+    join::{self, AntiJoin, SemiJoin},
+    merge, raw, resolve_range,
+    sorted_vec::ExtractIf,
+    DuplicateError, DuplicatePolicy, SharBinarySearch,
+};
+
+/// A set of unique elements stored contiguously in sorted order.
+///
+/// Lookups use Shar's branchless binary search, while iteration is just walking the
+/// underlying slice. Insertions and removals are `O(n)` due to shifting elements.
+///
+/// As with a `BTreeSet`, two sets compare, order and hash by their elements in ascending order,
+/// whatever order they were inserted in, and the operators `&`, `|`, `-` and `^` on references
+/// build the intersection, union, difference and symmetric difference as new sets. These
+/// gallop through runs of elements that only one side has, so combining a small set with a
+/// large one is cheap. There is no mutable iteration, as changing elements in place could
+/// break the set's order.
+///
+/// ```
+/// use shar_search::SharSet;
+///
+/// let a: SharSet<u32> = [1, 2, 3].into_iter().collect();
+/// let b: SharSet<u32> = [4, 3, 2].into_iter().collect();
+///
+/// assert_eq!((&a & &b).as_slice(), &[2, 3]);
+/// assert_eq!((&a | &b).as_slice(), &[1, 2, 3, 4]);
+/// assert_eq!((&a - &b).as_slice(), &[1]);
+/// assert_eq!((&a ^ &b).as_slice(), &[1, 4]);
+/// assert!(a < b);
+/// ```
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SharSet<T> {
+    inner: Vec<T>,
+}
+
+impl<T> Default for SharSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> SharSet<T> {
+    /// Creates a new, empty set.
+    pub const fn new() -> Self {
+        Self { inner: Vec::new() }
+    }
+
+    /// Creates a new, empty set with space for at least `capacity` elements.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Creates a set from a vector that is already in the set's canonical order.
+    #[allow(dead_code)]
+    pub(crate) fn from_sorted_vec_unchecked(inner: Vec<T>) -> Self {
+        Self { inner }
+    }
+
+    /// Returns the number of elements in the set.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns whether the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Returns the elements of the set as a sorted slice.
+    pub fn as_slice(&self) -> &[T] {
+        &self.inner
+    }
+
+    /// Returns the smallest element in the set, if any.
+    pub fn first(&self) -> Option<&T> {
+        self.inner.first()
+    }
+
+    /// Returns the largest element in the set, if any.
+    pub fn last(&self) -> Option<&T> {
+        self.inner.last()
+    }
+
+    /// Returns an iterator over the elements of the set in ascending order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            inner: self.inner.iter(),
+        }
+    }
+
+    /// Returns a [`Cursor`] positioned at the start of the set.
+    pub fn cursor(&self) -> Cursor<'_, T> {
+        Cursor {
+            elements: &self.inner,
+            position: 0,
+        }
+    }
+}
+
+impl<T: Ord> SharSet<T> {
+    /// Creates a set from unsorted input, resolving elements that compare equal according to
+    /// `policy`. A set never holds duplicates, so [`DuplicatePolicy::KeepAll`] keeps the first
+    /// of each.
+    ///
+    /// The input is collected into a single [`Vec`] that is sorted and deduplicated in place,
+    /// with a stable sort for [`DuplicatePolicy::KeepFirst`] and [`DuplicatePolicy::KeepLast`]
+    /// so that "first" and "last" refer to the input order.
+    ///
+    /// # Errors
+    ///
+    /// With [`DuplicatePolicy::Error`], returns the smallest element that appears more than
+    /// once.
+    pub fn from_unsorted_iter<I: IntoIterator<Item = T>>(
+        iter: I,
+        policy: DuplicatePolicy,
+    ) -> Result<Self, DuplicateError<T>> {
+        let mut inner: Vec<T> = iter.into_iter().collect();
+        sort_with_policy(&mut inner, policy, false, |x| x).map_err(|key| DuplicateError { key })?;
+        Ok(Self { inner })
+    }
+
+    fn search<Q>(&self, x: &Q) -> Result<usize, usize>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.inner.bl_binary_search_by(|p| p.borrow().cmp(x))
+    }
+
+    /// Returns whether the set contains `x`.
+    pub fn contains<Q>(&self, x: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search(x).is_ok()
+    }
+
+    /// Returns a reference to the element in the set equal to `x`, if any.
+    pub fn get<Q>(&self, x: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search(x).ok().map(|index| &self.inner[index])
+    }
+
+    /// Adds `value` to the set. Returns whether the value was newly inserted; if an equal
+    /// element was already present, the set is left unchanged.
+    pub fn insert(&mut self, value: T) -> bool {
+        match self.search(&value) {
+            Ok(_) => false,
+            Err(index) => {
+                self.inner.insert(index, value);
+                true
+            }
+        }
+    }
+
+    /// Removes the element equal to `x` from the set, returning it if it was present.
+    pub fn take<Q>(&mut self, x: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search(x).ok().map(|index| self.inner.remove(index))
+    }
+
+    /// Removes the element equal to `x` from the set. Returns whether it was present.
+    pub fn remove<Q>(&mut self, x: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.take(x).is_some()
+    }
+
+    /// Returns a double-ended iterator over the elements within `range`, in ascending order.
+    ///
+    /// Both ends of the range are resolved with the branchless search; if the start of the
+    /// range lies after its end, the iterator is empty.
+    pub fn range<Q, R>(&self, range: R) -> Iter<'_, T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let indices = resolve_range(&self.inner, &range, |p| p.borrow());
+
+        Iter {
+            inner: self.inner[indices].iter(),
+        }
+    }
+
+    /// Returns an iterator over the elements of the set, starting at the first element that is
+    /// greater than or equal to `x`.
+    pub fn iter_from<Q>(&self, x: &Q) -> Iter<'_, T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let start = self.inner.bl_partition_point(|p| p.borrow() < x);
+
+        Iter {
+            inner: self.inner[start..].iter(),
+        }
+    }
+
+    /// Removes the elements within `range`, returning them in ascending order as an iterator.
+    ///
+    /// Both ends of the range are resolved with the branchless search. As with [`Vec::drain`],
+    /// the whole range is removed even if the iterator is dropped before it is fully consumed.
+    /// If the iterator is leaked (e.g. with [`std::mem::forget`]), the set is left valid but
+    /// may lose the elements after the range as well.
+    pub fn drain_range<Q, R>(&mut self, range: R) -> Drain<'_, T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let indices = resolve_range(&self.inner, &range, |p| p.borrow());
+        self.inner.drain(indices)
+    }
+
+    /// Returns an iterator that removes and yields the elements within `range` for which `pred`
+    /// returns `true`, in ascending order, like `BTreeSet::extract_if` restricted to a range.
+    ///
+    /// See [`SortedVec::extract_if`](crate::SortedVec::extract_if): `pred` is only called on
+    /// elements within the range, elements the iterator has not reached when it is dropped are
+    /// kept, and leaking it may lose the elements from the start of the range on.
+    ///
+    /// ```
+    /// use shar_search::SharSet;
+    ///
+    /// let mut set: SharSet<u32> = (1..=10).collect();
+    /// let squares: Vec<_> = set.extract_if(2.., |x| x.isqrt().pow(2) == *x).collect();
+    /// assert_eq!(squares, [4, 9]);
+    /// assert_eq!(set.len(), 8);
+    /// ```
+    pub fn extract_if<Q, R, F>(&mut self, range: R, pred: F) -> ExtractIf<'_, T, F>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+        F: FnMut(&T) -> bool,
+    {
+        let indices = resolve_range(&self.inner, &range, |p| p.borrow());
+        ExtractIf::new(&mut self.inner, indices, pred)
+    }
+
+    /// Returns an iterator over the elements of the set that also appear in the sorted slice
+    /// `other`, in ascending order. See [`join::semi_join`].
+    ///
+    /// ```
+    /// use shar_search::SharSet;
+    ///
+    /// let mut set = SharSet::new();
+    /// set.extend([1, 2, 3, 4]);
+    /// assert!(set.semi_join(&[2, 4, 4, 6]).eq(&[2, 4]));
+    /// assert!(set.anti_join(&[2, 4, 4, 6]).eq(&[1, 3]));
+    /// ```
+    pub fn semi_join<'a>(
+        &'a self,
+        other: &'a [T],
+    ) -> SemiJoin<'a, T, T, impl FnMut(&T, &T) -> Ordering> {
+        join::semi_join(&self.inner, other)
+    }
+
+    /// Returns an iterator over the elements of the set that do not appear in the sorted slice
+    /// `other`, in ascending order. See [`join::anti_join`].
+    pub fn anti_join<'a>(
+        &'a self,
+        other: &'a [T],
+    ) -> AntiJoin<'a, T, T, impl FnMut(&T, &T) -> Ordering> {
+        join::anti_join(&self.inner, other)
+    }
+}
+
+impl<T: Ord> Extend<T> for SharSet<T> {
+    /// Inserts all elements with a single merge (see [`SortedVec::insert_many`]), so extending
+    /// by `k` elements is `O(n + k log k)`. Elements already in the set are kept over equal
+    /// incoming ones, as with [`SharSet::insert`].
+    ///
+    /// [`SortedVec::insert_many`]: crate::SortedVec::insert_many
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let mut batch: Vec<T> = iter.into_iter().collect();
+        batch.sort();
+        raw::merge_sorted_batch(&mut self.inner, batch, |a, b| a < b);
+        self.inner.dedup();
+    }
+}
+
+impl<'a, T: Ord + Copy + 'a> Extend<&'a T> for SharSet<T> {
+    /// Inserts copies of all elements with a single merge. See [`SharSet::extend`].
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T: Ord> FromIterator<T> for SharSet<T> {
+    /// Collects the elements, then sorts and deduplicates them once. Of equal elements, the
+    /// first is kept.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut inner: Vec<T> = iter.into_iter().collect();
+        // Stable, so the first of equal elements is the one kept.
+        inner.sort();
+        inner.dedup();
+        Self { inner }
+    }
+}
+
+impl<T> IntoIterator for SharSet<T> {
+    type Item = T;
+    type IntoIter = alloc::vec::IntoIter<T>;
+
+    /// Consumes the set, returning its elements in ascending order.
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SharSet<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SharSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Ord + Clone> BitAnd<&SharSet<T>> for &SharSet<T> {
+    type Output = SharSet<T>;
+
+    /// Returns the intersection of the sets, cloning the elements of `self`.
+    fn bitand(self, rhs: &SharSet<T>) -> SharSet<T> {
+        SharSet::from_sorted_vec_unchecked(self.semi_join(&rhs.inner).cloned().collect())
+    }
+}
+
+impl<T: Ord + Clone> BitOr<&SharSet<T>> for &SharSet<T> {
+    type Output = SharSet<T>;
+
+    /// Returns the union of the sets. Of equal elements, the one from `self` is cloned.
+    fn bitor(self, rhs: &SharSet<T>) -> SharSet<T> {
+        let mut inner = Vec::new();
+        // The merge is stable and `dedup` keeps the first of each run, so `self` wins ties.
+        merge::merge_sorted(&self.inner, &rhs.inner, &mut inner);
+        inner.dedup();
+        SharSet::from_sorted_vec_unchecked(inner)
+    }
+}
+
+impl<T: Ord + Clone> Sub<&SharSet<T>> for &SharSet<T> {
+    type Output = SharSet<T>;
+
+    /// Returns the difference of the sets: the elements of `self` that are not in `rhs`.
+    fn sub(self, rhs: &SharSet<T>) -> SharSet<T> {
+        SharSet::from_sorted_vec_unchecked(self.anti_join(&rhs.inner).cloned().collect())
+    }
+}
+
+impl<T: Ord + Clone> BitXor<&SharSet<T>> for &SharSet<T> {
+    type Output = SharSet<T>;
+
+    /// Returns the symmetric difference of the sets: the elements in exactly one of them.
+    fn bitxor(self, rhs: &SharSet<T>) -> SharSet<T> {
+        let (left, right) = (self - rhs, rhs - self);
+        let mut inner = Vec::new();
+        merge::merge_sorted(&left.inner, &right.inner, &mut inner);
+        SharSet::from_sorted_vec_unchecked(inner)
+    }
+}
+
+/// An iterator over the elements of a [`SharSet`], in ascending order.
+///
+/// Created by [`SharSet::iter`], [`SharSet::range`], and [`SharSet::iter_from`].
+#[derive(Clone)]
+pub struct Iter<'a, T> {
+    inner: slice::Iter<'a, T>,
+}
+
+impl<'a, T> Iter<'a, T> {
+    /// Returns the remaining elements of the iterator as a slice.
+    pub fn as_slice(&self) -> &'a [T] {
+        self.inner.as_slice()
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+/// A resumable position within a [`SharSet`], for paging through the set in chunks.
+///
+/// A cursor only searches when it is explicitly repositioned with [`Cursor::seek`]; reading
+/// pages with [`Cursor::next_chunk`] just advances an index, so keeping a cursor alive across
+/// pages avoids redoing the search for each page.
+#[derive(Clone, Debug)]
+pub struct Cursor<'a, T> {
+    elements: &'a [T],
+    position: usize,
+}
+
+impl<'a, T> Cursor<'a, T> {
+    /// Returns the index of the next element the cursor will yield. This is equal to the length
+    /// of the set once the cursor is exhausted.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the next element without advancing the cursor.
+    pub fn peek(&self) -> Option<&'a T> {
+        self.elements.get(self.position)
+    }
+
+    /// Returns whether the cursor has reached the end of the set.
+    pub fn is_exhausted(&self) -> bool {
+        self.position >= self.elements.len()
+    }
+
+    /// Returns the next (up to) `n` elements and advances the cursor past them. Returns an
+    /// empty slice once the cursor is exhausted.
+    pub fn next_chunk(&mut self, n: usize) -> &'a [T] {
+        let start = self.position;
+        let end = start.saturating_add(n).min(self.elements.len());
+        self.position = end;
+
+        &self.elements[start..end]
+    }
+
+    /// Repositions the cursor at the first element that is greater than or equal to `x`. This
+    /// may move the cursor either forwards or backwards.
+    pub fn seek<Q>(&mut self, x: &Q)
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.position = self.elements.bl_partition_point(|p| p.borrow() < x);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::{hash_map::DefaultHasher, BTreeSet},
+        hash::{Hash, Hasher},
+        ops::Bound,
+    };
+
+    use super::SharSet;
+    use crate::{test_util::XorShift, DuplicatePolicy};
+
+    fn set_of(values: impl IntoIterator<Item = u32>) -> SharSet<u32> {
+        let mut set = SharSet::new();
+        for value in values {
+            set.insert(value);
+        }
+        set
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let mut set = set_of([5, 1, 3, 3, 9]);
+        assert_eq!(set.as_slice(), &[1, 3, 5, 9]);
+        assert!(!set.insert(5));
+        assert!(set.contains(&9));
+        assert!(set.remove(&3));
+        assert!(!set.remove(&3));
+        assert_eq!(set.get(&1), Some(&1));
+        assert_eq!(set.as_slice(), &[1, 5, 9]);
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut set = set_of([2, 4, 6]);
+        set.extend([5, 4, 1, 5, 8, 1]);
+        assert_eq!(set.as_slice(), &[1, 2, 4, 5, 6, 8]);
+
+        set.extend([4; 5]);
+        assert_eq!(set.len(), 6);
+    }
+
+    #[test]
+    fn test_from_unsorted_iter() {
+        let input = [5, 3, 9, 3, 1, 5, 5];
+
+        for policy in [
+            DuplicatePolicy::KeepAll,
+            DuplicatePolicy::KeepFirst,
+            DuplicatePolicy::KeepLast,
+        ] {
+            let set = SharSet::from_unsorted_iter(input, policy).unwrap();
+            assert_eq!(set.as_slice(), &[1, 3, 5, 9]);
+        }
+
+        let err = SharSet::from_unsorted_iter(input, DuplicatePolicy::Error)
+            .err()
+            .unwrap();
+        assert_eq!(err.key(), &3);
+        assert!(SharSet::from_unsorted_iter([2, 1], DuplicatePolicy::Error).is_ok());
+    }
+
+    #[test]
+    fn test_range() {
+        let set = set_of((0..20).map(|i| i * 2));
+
+        assert!(set.range(4..10).copied().eq([4, 6, 8]));
+        assert!(set.range(5..=10).copied().eq([6, 8, 10]));
+        assert!(set.range(..3).copied().eq([0, 2]));
+        assert!(set.range(35..).copied().eq([36, 38]));
+        assert!(set.range(7..7).next().is_none());
+        assert!(set
+            .range((Bound::Included(10), Bound::Excluded(4)))
+            .next()
+            .is_none());
+        assert!(set.range(4..10).rev().copied().eq([8, 6, 4]));
+
+        let mut iter = set.range(4..=10);
+        assert_eq!(iter.next(), Some(&4));
+        assert_eq!(iter.next_back(), Some(&10));
+        assert_eq!(iter.as_slice(), &[6, 8]);
+    }
+
+    #[test]
+    fn test_iter_from() {
+        let set = set_of([10, 20, 30]);
+
+        assert!(set.iter_from(&0).copied().eq([10, 20, 30]));
+        assert!(set.iter_from(&20).copied().eq([20, 30]));
+        assert!(set.iter_from(&21).copied().eq([30]));
+        assert!(set.iter_from(&31).next().is_none());
+    }
+
+    #[test]
+    fn test_cursor_paging() {
+        let set = set_of(0..10);
+        let mut cursor = set.cursor();
+
+        // Pages that line up exactly with the end of the set.
+        assert_eq!(cursor.next_chunk(5), &[0, 1, 2, 3, 4]);
+        assert_eq!(cursor.position(), 5);
+        assert_eq!(cursor.next_chunk(5), &[5, 6, 7, 8, 9]);
+        assert!(cursor.is_exhausted());
+        assert!(cursor.next_chunk(5).is_empty());
+
+        // Resume after a key that is present and one that is not.
+        cursor.seek(&4);
+        assert_eq!(cursor.peek(), Some(&4));
+        assert_eq!(cursor.next_chunk(3), &[4, 5, 6]);
+        assert_eq!(cursor.next_chunk(3), &[7, 8, 9]);
+
+        let set = set_of((0..10).map(|i| i * 10));
+        let mut cursor = set.cursor();
+        cursor.seek(&35);
+        assert_eq!(cursor.position(), 4);
+        assert_eq!(cursor.next_chunk(2), &[40, 50]);
+    }
+
+    #[test]
+    fn test_cursor_seek_backwards() {
+        let set = set_of((0..100).map(|i| i * 3));
+        let mut cursor = set.cursor();
+
+        cursor.seek(&150);
+        assert_eq!(cursor.next_chunk(2), &[150, 153]);
+        cursor.seek(&10);
+        assert_eq!(cursor.position(), 4);
+        assert_eq!(cursor.next_chunk(2), &[12, 15]);
+        cursor.seek(&0);
+        assert_eq!(cursor.position(), 0);
+        cursor.seek(&1000);
+        assert!(cursor.is_exhausted());
+    }
+
+    #[test]
+    fn test_empty() {
+        let set: SharSet<u32> = SharSet::new();
+
+        assert!(set.range(..).next().is_none());
+        assert!(set.range(1..5).next().is_none());
+        assert!(set.iter_from(&3).next().is_none());
+
+        let mut cursor = set.cursor();
+        assert!(cursor.is_exhausted());
+        assert!(cursor.next_chunk(10).is_empty());
+        cursor.seek(&3);
+        assert_eq!(cursor.position(), 0);
+        assert_eq!(cursor.peek(), None);
+    }
+
+    #[test]
+    fn test_drain_range() {
+        let mut set = set_of(0..10);
+        let mut drain = set.drain_range(3..7);
+        assert_eq!(drain.next(), Some(3));
+        drop(drain);
+        assert_eq!(set.as_slice(), &[0, 1, 2, 7, 8, 9]);
+        assert!(set.drain_range(..).eq([0, 1, 2, 7, 8, 9]));
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn test_extract_if() {
+        let mut set = set_of(0..10);
+        let mut extract = set.extract_if(2..8, |x| x % 3 == 0);
+        assert_eq!(extract.next(), Some(3));
+        drop(extract);
+        // 6 was not reached, so it is kept.
+        assert_eq!(set.as_slice(), &[0, 1, 2, 4, 5, 6, 7, 8, 9]);
+
+        assert_eq!(set.extract_if(.., |_| false).count(), 0);
+        assert!(set.extract_if(..5, |_| true).eq([0, 1, 2, 4]));
+        assert_eq!(set.as_slice(), &[5, 6, 7, 8, 9]);
+        assert!(set.extract_if(.., |_| true).eq([5, 6, 7, 8, 9]));
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn test_operators_against_btree_set() {
+        let mut rng = XorShift::new(162);
+
+        for _ in 0..200 {
+            // Sizes from empty up to lopsided, so the galloping paths are taken.
+            let max_y = if rng.below(2) == 0 { 5 } else { 300 };
+            let (x, y) = (rng.below(50), rng.below(max_y));
+            let a: Vec<u32> = (0..x).map(|_| rng.below(100) as u32).collect();
+            let b: Vec<u32> = (0..y).map(|_| rng.below(100) as u32).collect();
+
+            let (set_a, set_b): (SharSet<u32>, SharSet<u32>) =
+                (a.iter().copied().collect(), b.iter().copied().collect());
+            let (tree_a, tree_b): (BTreeSet<u32>, BTreeSet<u32>) =
+                (a.into_iter().collect(), b.into_iter().collect());
+
+            assert!((&set_a & &set_b).iter().eq((&tree_a & &tree_b).iter()));
+            assert!((&set_a | &set_b).iter().eq((&tree_a | &tree_b).iter()));
+            assert!((&set_a - &set_b).iter().eq((&tree_a - &tree_b).iter()));
+            assert!((&set_a ^ &set_b).iter().eq((&tree_a ^ &tree_b).iter()));
+
+            // The symmetric operators are symmetric.
+            assert_eq!(&set_a | &set_b, &set_b | &set_a);
+            assert_eq!(&set_a & &set_b, &set_b & &set_a);
+            assert_eq!(&set_a ^ &set_b, &set_b ^ &set_a);
+
+            assert_eq!(set_a.cmp(&set_b), tree_a.cmp(&tree_b));
+            assert_eq!(set_a == set_b, tree_a == tree_b);
+        }
+    }
+
+    #[test]
+    fn test_eq_and_hash_ignore_insertion_order() {
+        let hash = |set: &SharSet<u32>| {
+            let mut hasher = DefaultHasher::new();
+            set.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        let a = set_of([3, 1, 2]);
+        let b = set_of([2, 3, 1, 1]);
+        let mut c = SharSet::new();
+        c.extend(&[1, 2]);
+        c.extend([3]);
+
+        assert_eq!(a, b);
+        assert_eq!(a, c);
+        assert_eq!(hash(&a), hash(&b));
+        assert_eq!(hash(&a), hash(&c));
+        assert_ne!(a, set_of([1, 2]));
+        // Lexicographic, like `BTreeSet`: a missing element sorts before a present one.
+        assert!(set_of([1, 2]) < a);
+        assert!(set_of([1, 3]) > a);
+        assert!(SharSet::new() < set_of([0]));
+    }
+
+    #[test]
+    fn test_iterators_and_debug() {
+        let set: SharSet<u32> = [5, 1, 5, 3].into_iter().collect();
+        assert_eq!(format!("{set:?}"), "{1, 3, 5}");
+
+        let mut visited = Vec::new();
+        for x in &set {
+            visited.push(*x);
+        }
+        assert_eq!(visited, [1, 3, 5]);
+        assert_eq!(set.into_iter().rev().collect::<Vec<_>>(), [5, 3, 1]);
+    }
+}
@@ -0,0 +1,358 @@
+//! Batch lower bounds of `u32` and `f32` keys, with eight searches running in lockstep in the
+//! lanes of AVX2 registers.
+//!
+//! Like the [batch searches](crate::batch), the searches of a group share the same remaining
+//! length, so they advance one level per round. Here the probes of a round are fetched with a
+//! single gather, and each lane's base is updated with a masked blend, so the eight searches
+//! take no more instructions than one.
+//!
+//! AVX2 is detected at runtime. Without it, on other architectures, or for haystacks too long
+//! to index with the `i32` offsets of a gather, these fall back to the scalar interleaved
+//! [`bl_binary_search_batch_by_uninit`](SharBatchSearch::bl_binary_search_batch_by_uninit), and
+//! return the same results. AVX-512 is not used.
+//!
+//! ```
+//! use shar_search::simd::bl_batch_lower_bound_simd;
+//!
+//! let haystack = [10, 20, 20, 30];
+//! let mut out = [0; 3];
+//! bl_batch_lower_bound_simd(&haystack, &[20, 25, 40], &mut out).unwrap();
+//! assert_eq!(out, [1, 3, 4]);
+//! ```
+
+use core::{cmp::Ordering, mem::MaybeUninit};
+
+use crate::batch::{BatchSizeMismatch, SharBatchSearch, DEFAULT_INTERLEAVE};
+
+/// The number of searches in a group, one per lane of a 256-bit register of `u32`s.
+const LANES: usize = 8;
+
+/// The longest haystack a gather can index, since its offsets are `i32`s.
+const MAX_GATHER_LEN: usize = i32::MAX as usize + 1;
+
+/// Writes the lower bound of each of `keys` in `haystack` to `out`: the index of the first
+/// element not less than the key, or `haystack.len()` if there is none. Note it is assumed that
+/// `haystack` is sorted.
+///
+/// The results are the indices that
+/// [`bl_binary_search`](crate::SharBinarySearch::bl_binary_search) returns in either `Ok` or
+/// `Err`.
+///
+/// # Errors
+///
+/// Returns [`BatchSizeMismatch`] without writing anything if `out` and `keys` have different
+/// lengths.
+///
+/// # Panics
+///
+/// Panics if `haystack` is longer than `u32::MAX`, so that its lower bounds would not fit in a
+/// `u32`.
+pub fn bl_batch_lower_bound_simd(
+    haystack: &[u32],
+    keys: &[u32],
+    out: &mut [u32],
+) -> Result<(), BatchSizeMismatch> {
+    check_sizes(haystack.len(), keys.len(), out.len())?;
+
+    #[cfg(target_arch = "x86_64")]
+    if haystack.len() <= MAX_GATHER_LEN && std::is_x86_feature_detected!("avx2") {
+        // AVX2 is available, and the checks above hold.
+        unsafe { avx2::lower_bounds::<false>(haystack, keys, out) };
+        return Ok(());
+    }
+
+    lower_bounds_scalar(haystack, keys, out, u32::cmp);
+    Ok(())
+}
+
+/// Writes the lower bound of each of `keys` in `haystack` to `out`, in the order of
+/// [`f32::total_cmp`]. Note it is assumed that `haystack` is sorted that way, e.g. with
+/// `sort_by(f32::total_cmp)`. See [`bl_batch_lower_bound_simd`].
+///
+/// In that order, `-0.0` is less than `0.0`, and NaNs are ordered by sign and payload beyond
+/// the infinities. The SIMD search compares the floats by mapping their bits to integers in the
+/// same order.
+///
+/// ```
+/// use shar_search::simd::bl_batch_lower_bound_simd_f32;
+///
+/// let haystack = [-1.5, -0.0, 0.0, 2.25];
+/// let mut out = [0; 3];
+/// bl_batch_lower_bound_simd_f32(&haystack, &[0.0, -0.0, 3.0], &mut out).unwrap();
+/// assert_eq!(out, [2, 1, 4]);
+/// ```
+///
+/// # Errors
+///
+/// Returns [`BatchSizeMismatch`] without writing anything if `out` and `keys` have different
+/// lengths.
+///
+/// # Panics
+///
+/// Panics if `haystack` is longer than `u32::MAX`.
+pub fn bl_batch_lower_bound_simd_f32(
+    haystack: &[f32],
+    keys: &[f32],
+    out: &mut [u32],
+) -> Result<(), BatchSizeMismatch> {
+    check_sizes(haystack.len(), keys.len(), out.len())?;
+
+    #[cfg(target_arch = "x86_64")]
+    if haystack.len() <= MAX_GATHER_LEN && std::is_x86_feature_detected!("avx2") {
+        // `f32` and `u32` have the same size and alignment, and every bit pattern is a valid
+        // `u32`.
+        let (haystack, keys) = unsafe {
+            (
+                core::slice::from_raw_parts(haystack.as_ptr().cast::<u32>(), haystack.len()),
+                core::slice::from_raw_parts(keys.as_ptr().cast::<u32>(), keys.len()),
+            )
+        };
+        // AVX2 is available, and the checks above hold.
+        unsafe { avx2::lower_bounds::<true>(haystack, keys, out) };
+        return Ok(());
+    }
+
+    lower_bounds_scalar(haystack, keys, out, f32::total_cmp);
+    Ok(())
+}
+
+/// Checks that `out` has a slot per key, and that every lower bound fits in a `u32`.
+fn check_sizes(haystack: usize, keys: usize, out: usize) -> Result<(), BatchSizeMismatch> {
+    assert!(
+        u32::try_from(haystack).is_ok(),
+        "a haystack of {haystack} elements is too long for u32 lower bounds"
+    );
+    if keys == out {
+        Ok(())
+    } else {
+        Err(BatchSizeMismatch { keys, out })
+    }
+}
+
+/// Writes the lower bounds of `keys` to `out` with the scalar interleaved batch search.
+fn lower_bounds_scalar<T, F>(haystack: &[T], keys: &[T], out: &mut [u32], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    const CHUNK: usize = 256;
+    let mut results = [MaybeUninit::uninit(); CHUNK];
+
+    for (keys, out) in keys.chunks(CHUNK).zip(out.chunks_mut(CHUNK)) {
+        // Never reporting a match makes every result the `Err` of the lower bound.
+        let lower_bound = |p: &T, k: &T| match compare(p, k) {
+            Ordering::Less => Ordering::Less,
+            _ => Ordering::Greater,
+        };
+        let Ok(results) = haystack.bl_binary_search_batch_by_uninit::<DEFAULT_INTERLEAVE, _, _>(
+            keys,
+            &mut results,
+            lower_bound,
+        ) else {
+            unreachable!("the chunk of results is as long as the chunk of keys");
+        };
+        for (slot, result) in out.iter_mut().zip(results) {
+            let (Ok(index) | Err(index)) = *result;
+            // The haystack's length fits in a `u32`.
+            *slot = index as u32;
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use core::arch::x86_64::{
+        __m256i, _mm256_add_epi32, _mm256_blendv_epi8, _mm256_cmpgt_epi32, _mm256_i32gather_epi32,
+        _mm256_loadu_si256, _mm256_set1_epi32, _mm256_setzero_si256, _mm256_srai_epi32,
+        _mm256_srli_epi32, _mm256_storeu_si256, _mm256_sub_epi32, _mm256_xor_si256,
+    };
+
+    use super::LANES;
+
+    /// Maps each lane to an `i32` whose signed order is the order of the lane's value: the
+    /// unsigned order of `u32`s, or [`f32::total_cmp`] for the bits of `f32`s.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    fn to_signed<const F32: bool>(v: __m256i) -> __m256i {
+        if F32 {
+            // Flip every bit but the sign of negative floats, as `f32::total_cmp` does.
+            _mm256_xor_si256(v, _mm256_srli_epi32::<1>(_mm256_srai_epi32::<31>(v)))
+        } else {
+            _mm256_xor_si256(v, _mm256_set1_epi32(i32::MIN))
+        }
+    }
+
+    /// Returns the lower bounds of the eight `keys`, which are mapped by `to_signed`.
+    ///
+    /// # Safety
+    ///
+    /// `haystack` must be nonempty, and no longer than `MAX_GATHER_LEN`.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn lower_bounds_8<const F32: bool>(haystack: &[u32], keys: __m256i) -> __m256i {
+        let base_ptr = haystack.as_ptr().cast::<i32>();
+        let mut bases = _mm256_setzero_si256();
+        let mut size = haystack.len();
+
+        // Every lane's `base + size` stays at most `haystack.len()`, so the probes are in
+        // bounds, and fit in an `i32` since the haystack is no longer than `MAX_GATHER_LEN`.
+        while size > 1 {
+            let half = size / 2;
+            let mids = _mm256_add_epi32(bases, _mm256_set1_epi32(half as i32));
+            let probes = to_signed::<F32>(unsafe { _mm256_i32gather_epi32::<4>(base_ptr, mids) });
+            let less = _mm256_cmpgt_epi32(keys, probes);
+            bases = _mm256_blendv_epi8(bases, mids, less);
+            size -= half;
+        }
+
+        // A lane's lower bound is one past its base if the element there is less than its key.
+        let probes = to_signed::<F32>(unsafe { _mm256_i32gather_epi32::<4>(base_ptr, bases) });
+        _mm256_sub_epi32(bases, _mm256_cmpgt_epi32(keys, probes))
+    }
+
+    /// Writes the lower bounds of `keys` in `haystack` to `out`.
+    ///
+    /// # Safety
+    ///
+    /// AVX2 must be available, `haystack` must be no longer than `MAX_GATHER_LEN`, and `out`
+    /// must be as long as `keys`.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn lower_bounds<const F32: bool>(
+        haystack: &[u32],
+        keys: &[u32],
+        out: &mut [u32],
+    ) {
+        if haystack.is_empty() {
+            out.fill(0);
+            return;
+        }
+
+        let mut key_groups = keys.chunks_exact(LANES);
+        let mut out_groups = out.chunks_exact_mut(LANES);
+        for (group, group_out) in (&mut key_groups).zip(&mut out_groups) {
+            // Each group is exactly `LANES` `u32`s, the width of the unaligned load and store.
+            unsafe {
+                let keys = to_signed::<F32>(_mm256_loadu_si256(group.as_ptr().cast()));
+                let bounds = lower_bounds_8::<F32>(haystack, keys);
+                _mm256_storeu_si256(group_out.as_mut_ptr().cast(), bounds);
+            }
+        }
+
+        // Pad the last partial group with copies of its first key, and keep only the lanes of
+        // real keys.
+        let rest = key_groups.remainder();
+        if let Some(&first) = rest.first() {
+            let mut padded = [first; LANES];
+            padded[..rest.len()].copy_from_slice(rest);
+            let mut bounds = [0_u32; LANES];
+            unsafe {
+                let keys = to_signed::<F32>(_mm256_loadu_si256(padded.as_ptr().cast()));
+                let lanes = lower_bounds_8::<F32>(haystack, keys);
+                _mm256_storeu_si256(bounds.as_mut_ptr().cast(), lanes);
+            }
+            out_groups
+                .into_remainder()
+                .copy_from_slice(&bounds[..rest.len()]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{bl_batch_lower_bound_simd, bl_batch_lower_bound_simd_f32, lower_bounds_scalar};
+    use crate::reference::{self, assert_matches_reference, inputs};
+
+    /// Checks the SIMD and the scalar searches against the reference lower bounds, for each
+    /// haystack and its keys.
+    fn check(cases: impl Iterator<Item = (Vec<u32>, Vec<u32>)>) {
+        assert_matches_reference!(
+            for (haystack, keys) in cases =>
+            {
+                let mut simd = vec![u32::MAX; keys.len()];
+                bl_batch_lower_bound_simd(&haystack, &keys, &mut simd).unwrap();
+                let mut scalar = vec![u32::MAX; keys.len()];
+                lower_bounds_scalar(&haystack, &keys, &mut scalar, u32::cmp);
+                (simd, scalar)
+            },
+            {
+                let expected: Vec<u32> = keys
+                    .iter()
+                    .map(|k| reference::lower_bound(&haystack, k) as u32)
+                    .collect();
+                (expected.clone(), expected)
+            },
+        );
+    }
+
+    #[test]
+    fn test_shapes() {
+        let mut cases = vec![
+            (vec![], vec![]),
+            (vec![], vec![1, 2, 3]),
+            (vec![5], vec![4, 5, 6]),
+            (vec![1, 3, 3, 5, 8], vec![]),
+        ];
+        // Partial groups of every size, and a full one.
+        let haystack: Vec<u32> = (0..37).map(|x| x * 3).collect();
+        cases.extend((0..=17).map(|len| (haystack.clone(), (0..len).map(|x| x * 7).collect())));
+        check(cases.into_iter());
+    }
+
+    #[test]
+    fn test_extreme_keys() {
+        // The top bit of `u32` keys must be compared unsigned.
+        let haystack = vec![0, 1, 1 << 31, (1 << 31) + 1, u32::MAX - 1, u32::MAX];
+        let keys = vec![
+            0,
+            1,
+            2,
+            1 << 31,
+            (1 << 31) - 1,
+            u32::MAX,
+            u32::MAX - 1,
+            1 << 30,
+        ];
+        check([(haystack, keys.clone()), (vec![u32::MAX; 9], keys)].into_iter());
+    }
+
+    #[test]
+    fn test_size_mismatch() {
+        let mut out = [0; 2];
+        let error = bl_batch_lower_bound_simd(&[1, 2], &[1, 2, 3], &mut out).unwrap_err();
+        assert_eq!((error.keys(), error.out()), (3, 2));
+        assert_eq!(out, [0, 0]);
+        assert!(bl_batch_lower_bound_simd_f32(&[1.0], &[], &mut out).is_err());
+    }
+
+    #[test]
+    fn test_against_reference() {
+        // Haystacks searched for keys in the same range, and for every element and gap.
+        let every_key = inputs::sorted_slices(195).map(|haystack| {
+            let keys = (0..=haystack.last().map_or(0, |last| last + 1)).collect();
+            (haystack, keys)
+        });
+        check(inputs::slice_pairs(195).chain(every_key));
+    }
+
+    #[test]
+    fn test_f32_against_reference() {
+        assert_matches_reference!(
+            for (haystack, keys) in inputs::f32_slice_pairs(1195) =>
+            {
+                let mut simd = vec![u32::MAX; keys.len()];
+                bl_batch_lower_bound_simd_f32(&haystack, &keys, &mut simd).unwrap();
+                let mut scalar = vec![u32::MAX; keys.len()];
+                lower_bounds_scalar(&haystack, &keys, &mut scalar, f32::total_cmp);
+                (simd, scalar)
+            },
+            {
+                // The lower bound under the total order.
+                let expected: Vec<u32> = keys
+                    .iter()
+                    .map(|k| reference::partition_point(&haystack, |x| x.total_cmp(k).is_lt()))
+                    .map(|index| index as u32)
+                    .collect();
+                (expected.clone(), expected)
+            },
+        );
+    }
+}
@@ -0,0 +1,522 @@
+//! Sorted containers that store up to `N` elements inline, enabled by the `smallvec` feature.
+//!
+//! [`SortedSmallVec`] and [`SmallSharMap`] have the same API as [`SortedVec`](crate::SortedVec)
+//! and [`SharMap`](crate::SharMap), but are backed by a [`SmallVec`], so they only allocate once
+//! they grow past `N` elements. They move to the heap transparently, and never move back. For
+//! many tiny containers, such as per-row attribute maps, this avoids an allocation per container,
+//! which otherwise costs far more than searching a handful of elements.
+//!
+//! Lookups use the branchless search, like the heap-backed containers.
+//!
+//! ```
+//! use shar_search::small::SmallSharMap;
+//!
+//! let mut attributes: SmallSharMap<&str, u32, 4> = SmallSharMap::new();
+//! attributes.insert("width", 640);
+//! attributes.insert("height", 480);
+//! assert_eq!(attributes.get("height"), Some(&480));
+//! assert!(!attributes.spilled());
+//! ```
+
+use core::{
+    borrow::Borrow,
+    fmt, mem,
+    ops::{Deref, RangeBounds},
+};
+
+use smallvec::{Drain, SmallVec};
+
+use crate::{
+    map::{Iter, IterMut},
+    resolve_range, SharBinarySearch,
+};
+
+/// A [`SortedVec`](crate::SortedVec) that stores up to `N` elements inline, allowing duplicates.
+///
+/// Equal elements are kept in insertion order. The vector dereferences to a sorted slice, so
+/// all the slice search methods are available on it directly.
+#[derive(Clone)]
+pub struct SortedSmallVec<T, const N: usize> {
+    inner: SmallVec<[T; N]>,
+}
+
+impl<T, const N: usize> Default for SortedSmallVec<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Deref for SortedSmallVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.inner
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for SortedSmallVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.inner.iter()).finish()
+    }
+}
+
+impl<T, const N: usize> SortedSmallVec<T, N> {
+    /// Creates a new, empty sorted vector, with space for `N` elements inline.
+    pub fn new() -> Self {
+        Self {
+            inner: SmallVec::new(),
+        }
+    }
+
+    /// Creates a new, empty sorted vector with space for at least `capacity` elements, on the
+    /// heap if `capacity` is more than `N`.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: SmallVec::with_capacity(capacity),
+        }
+    }
+
+    /// Returns whether the elements have moved to the heap.
+    pub fn spilled(&self) -> bool {
+        self.inner.spilled()
+    }
+
+    /// Returns the elements as a sorted slice.
+    pub fn as_slice(&self) -> &[T] {
+        &self.inner
+    }
+
+    /// Consumes the sorted vector, returning the underlying [`SmallVec`].
+    pub fn into_inner(self) -> SmallVec<[T; N]> {
+        self.inner
+    }
+
+    /// Removes all elements. The elements stay on the heap if they had moved there.
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+}
+
+impl<T: Ord, const N: usize> SortedSmallVec<T, N> {
+    /// Inserts `value` after any elements equal to it, returning the index it was inserted at.
+    ///
+    /// If the vector is full, its elements are moved to the heap, without cloning them.
+    pub fn insert(&mut self, value: T) -> usize {
+        let index = self.inner.bl_upper_bound(&value);
+        self.inner.insert(index, value);
+        index
+    }
+
+    /// Inserts all elements of `items`, keeping the vector sorted. Existing elements are placed
+    /// before equal incoming ones, and equal incoming elements keep their relative order.
+    ///
+    /// The items are appended and the whole vector is sorted again with a stable sort, which
+    /// finds the existing elements already sorted.
+    pub fn insert_many<I: IntoIterator<Item = T>>(&mut self, items: I) {
+        self.inner.extend(items);
+        self.inner.sort();
+    }
+
+    /// Returns whether the vector contains an element equal to `x`.
+    pub fn contains<Q>(&self, x: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.inner
+            .bl_binary_search_by(|p| p.borrow().cmp(x))
+            .is_ok()
+    }
+
+    /// Removes and returns the first element equal to `x`, if any.
+    pub fn remove<Q>(&mut self, x: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.inner
+            .bl_binary_search_by(|p| p.borrow().cmp(x))
+            .ok()
+            .map(|index| self.inner.remove(index))
+    }
+
+    /// Removes and returns the element at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove_index(&mut self, index: usize) -> T {
+        self.inner.remove(index)
+    }
+
+    /// Returns the subslice of elements within `range`, resolved with the branchless search.
+    /// If the start of the range lies after its end, the subslice is empty.
+    pub fn range<Q, R>(&self, range: R) -> &[T]
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        &self.inner[resolve_range(&self.inner, &range, |p| p.borrow())]
+    }
+
+    /// Removes the elements within `range`, returning them in ascending order as an iterator.
+    /// See [`SortedVec::drain_range`](crate::SortedVec::drain_range).
+    pub fn drain_range<Q, R>(&mut self, range: R) -> Drain<'_, [T; N]>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let indices = resolve_range(&self.inner, &range, |p| p.borrow());
+        self.inner.drain(indices)
+    }
+}
+
+impl<T: Ord, const N: usize> Extend<T> for SortedSmallVec<T, N> {
+    /// Extends the vector using [`SortedSmallVec::insert_many`].
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.insert_many(iter);
+    }
+}
+
+/// A [`SharMap`](crate::SharMap) that stores up to `N` pairs inline.
+///
+/// It has the same lookups and iterators, which are the same types as the [`SharMap`]'s.
+///
+/// [`SharMap`]: crate::SharMap
+#[derive(Clone)]
+pub struct SmallSharMap<K, V, const N: usize> {
+    inner: SmallVec<[(K, V); N]>,
+}
+
+impl<K, V, const N: usize> Default for SmallSharMap<K, V, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, const N: usize> fmt::Debug for SmallSharMap<K, V, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.inner.iter().map(|(k, v)| (k, v)))
+            .finish()
+    }
+}
+
+impl<K, V, const N: usize> SmallSharMap<K, V, N> {
+    /// Creates a new, empty map, with space for `N` pairs inline.
+    pub fn new() -> Self {
+        Self {
+            inner: SmallVec::new(),
+        }
+    }
+
+    /// Creates a new, empty map with space for at least `capacity` pairs, on the heap if
+    /// `capacity` is more than `N`.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: SmallVec::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the number of pairs in the map.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns whether the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Returns whether the pairs have moved to the heap.
+    pub fn spilled(&self) -> bool {
+        self.inner.spilled()
+    }
+
+    /// Removes all pairs. The pairs stay on the heap if they had moved there.
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    /// Returns the pairs as a slice sorted by key.
+    pub fn as_slice(&self) -> &[(K, V)] {
+        &self.inner
+    }
+
+    /// Returns the pair with the smallest key, if any.
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        self.inner.first().map(|(k, v)| (k, v))
+    }
+
+    /// Returns the pair with the largest key, if any.
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        self.inner.last().map(|(k, v)| (k, v))
+    }
+
+    /// Removes and returns the pair with the largest key, if any.
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        self.inner.pop()
+    }
+
+    /// Keeps only the pairs for which `f` returns `true`, visiting them in key order.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        self.inner.retain(|(k, v)| f(k, v));
+    }
+
+    /// Returns an iterator over the pairs of the map, in key order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            inner: self.inner.iter(),
+        }
+    }
+
+    /// Returns an iterator over the pairs of the map in key order, with mutable references to
+    /// the values.
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            inner: self.inner.iter_mut(),
+        }
+    }
+
+    /// Returns an iterator over the keys of the map, in order.
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> + ExactSizeIterator + '_ {
+        self.inner.iter().map(|(k, _)| k)
+    }
+
+    /// Returns an iterator over the values of the map, in key order.
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> + ExactSizeIterator + '_ {
+        self.inner.iter().map(|(_, v)| v)
+    }
+
+    /// Returns an iterator over mutable references to the values of the map, in key order.
+    pub fn values_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut V> + ExactSizeIterator {
+        self.inner.iter_mut().map(|(_, v)| v)
+    }
+}
+
+impl<K: Ord, V, const N: usize> SmallSharMap<K, V, N> {
+    fn search<Q>(&self, key: &Q) -> Result<usize, usize>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.inner.bl_binary_search_by(|(k, _)| k.borrow().cmp(key))
+    }
+
+    /// Inserts a key-value pair, returning the previous value for the key if there was one.
+    /// The key itself is not updated if it was already present.
+    ///
+    /// If the map is full, its pairs are moved to the heap, without cloning them.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.search(&key) {
+            Ok(index) => Some(mem::replace(&mut self.inner[index].1, value)),
+            Err(index) => {
+                self.inner.insert(index, (key, value));
+                None
+            }
+        }
+    }
+
+    /// Returns whether the map contains `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search(key).is_ok()
+    }
+
+    /// Returns a reference to the value for `key`, if present.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search(key).ok().map(|index| &self.inner[index].1)
+    }
+
+    /// Returns the stored key and a reference to the value for `key`, if present.
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search(key).ok().map(|index| {
+            let (k, v) = &self.inner[index];
+            (k, v)
+        })
+    }
+
+    /// Returns a mutable reference to the value for `key`, if present.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search(key).ok().map(|index| &mut self.inner[index].1)
+    }
+
+    /// Removes `key` from the map, returning its value if it was present.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    /// Removes `key` from the map, returning the stored key and its value if it was present.
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search(key).ok().map(|index| self.inner.remove(index))
+    }
+
+    /// Returns a double-ended iterator over the pairs whose keys are within `range`, in key
+    /// order. If the start of the range lies after its end, the iterator is empty.
+    pub fn range<Q, R>(&self, range: R) -> Iter<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let indices = resolve_range(&self.inner, &range, |(k, _)| k.borrow());
+
+        Iter {
+            inner: self.inner[indices].iter(),
+        }
+    }
+
+    /// Returns a double-ended iterator over the pairs whose keys are within `range`, in key
+    /// order, with mutable references to the values.
+    pub fn range_mut<Q, R>(&mut self, range: R) -> IterMut<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let indices = resolve_range(&self.inner, &range, |(k, _)| k.borrow());
+
+        IterMut {
+            inner: self.inner[indices].iter_mut(),
+        }
+    }
+}
+
+impl<K: Ord, V, const N: usize> Extend<(K, V)> for SmallSharMap<K, V, N> {
+    /// Inserts all pairs, appending them and sorting the map again with a stable sort. If a key
+    /// appears more than once, the last value wins, whether the earlier one was already in the
+    /// map or earlier in `iter`.
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        self.inner.extend(iter);
+        self.inner.sort_by(|(a, _), (b, _)| a.cmp(b));
+        // `dedup_by` keeps the first of each run, so move each later value into it.
+        self.inner.dedup_by(|(later_key, later), (kept_key, kept)| {
+            let same = later_key == kept_key;
+            if same {
+                mem::swap(later, kept);
+            }
+            same
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use super::{SmallSharMap, SortedSmallVec};
+    use crate::test_util::XorShift;
+
+    /// A value that can't be cloned, to check that spilling moves elements.
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+    struct Unique(u32);
+
+    #[test]
+    fn test_spill_at_capacity() {
+        let mut vec: SortedSmallVec<Unique, 4> = SortedSmallVec::new();
+        for value in [3, 1, 4, 2] {
+            vec.insert(Unique(value));
+        }
+        assert_eq!(vec.len(), 4);
+        assert!(!vec.spilled());
+
+        vec.insert(Unique(0));
+        assert!(vec.spilled());
+        assert!(vec.iter().map(|u| u.0).eq(0..5));
+        assert!(vec.contains(&Unique(4)));
+        assert_eq!(vec.remove(&Unique(2)), Some(Unique(2)));
+        assert_eq!(vec.range(Unique(1)..Unique(4)), &[Unique(1), Unique(3)]);
+
+        let mut map: SmallSharMap<u32, Unique, 4> = SmallSharMap::new();
+        for key in [40, 10, 30, 20] {
+            assert_eq!(map.insert(key, Unique(key)), None);
+        }
+        assert!(!map.spilled());
+        assert_eq!(map.insert(20, Unique(21)), Some(Unique(20)));
+        assert!(!map.spilled());
+
+        assert_eq!(map.insert(25, Unique(25)), None);
+        assert!(map.spilled());
+        assert!(map.keys().copied().eq([10, 20, 25, 30, 40]));
+        assert_eq!(map.get(&20), Some(&Unique(21)));
+        assert_eq!(map.remove(&25), Some(Unique(25)));
+        // Shrinking back to `N` keeps the pairs on the heap.
+        assert!(map.spilled());
+    }
+
+    #[test]
+    fn test_map_against_btree_map() {
+        let mut rng = XorShift::new(168);
+
+        for _ in 0..200 {
+            let mut map: SmallSharMap<u64, u64, 8> = SmallSharMap::new();
+            let mut reference = BTreeMap::new();
+
+            for _ in 0..rng.below(20) {
+                let (k, v) = (rng.below(16), rng.below(100));
+                match rng.below(4) {
+                    0 => assert_eq!(map.remove(&k), reference.remove(&k)),
+                    1 => {
+                        map.extend([(k, v), (k + 1, v)]);
+                        reference.extend([(k, v), (k + 1, v)]);
+                    }
+                    _ => assert_eq!(map.insert(k, v), reference.insert(k, v)),
+                }
+                if map.len() > 8 {
+                    assert!(map.spilled());
+                }
+            }
+
+            assert!(map.iter().eq(reference.iter()));
+            assert!(map.range(4..12).eq(reference.range(4..12)));
+            assert_eq!(map.first_key_value(), reference.first_key_value());
+            assert_eq!(map.last_key_value(), reference.last_key_value());
+            for k in 0..17 {
+                assert_eq!(map.get(&k), reference.get(&k));
+                assert_eq!(map.contains_key(&k), reference.contains_key(&k));
+            }
+        }
+    }
+
+    #[test]
+    fn test_insert_many() {
+        let mut vec: SortedSmallVec<(u32, char), 8> = SortedSmallVec::new();
+        vec.insert_many([(2, 'a'), (1, 'b')]);
+        vec.extend([(2, 'c'), (0, 'd')]);
+        assert_eq!(vec.as_slice(), &[(0, 'd'), (1, 'b'), (2, 'a'), (2, 'c')]);
+        assert!(vec.drain_range((1, ' ')..(2, 'b')).eq([(1, 'b'), (2, 'a')]));
+        assert_eq!(format!("{vec:?}"), "[(0, 'd'), (2, 'c')]");
+
+        let mut map: SmallSharMap<u32, char, 2> = SmallSharMap::new();
+        map.extend([(1, 'a'), (2, 'b'), (1, 'c')]);
+        assert_eq!(format!("{map:?}"), "{1: 'c', 2: 'b'}");
+    }
+}
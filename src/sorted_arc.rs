@@ -0,0 +1,303 @@
+//! Cheaply cloneable, immutable sorted data shared through an [`Arc`].
+
+use alloc::{sync::Arc, vec::Vec};
+use core::{
+    borrow::Borrow,
+    fmt,
+    ops::{Bound, Deref, Range, RangeBounds},
+};
+
+pub use crate::UnsortedError;
+use crate::{resolve_range, validate, SharBinarySearch};
+
+/// An immutable sorted slice shared through an [`Arc<[T]>`](Arc), so clones are cheap and the
+/// data can be shared across threads without re-validating it.
+///
+/// A `SortedArc` may also be a view of part of a larger shared slice; [`SortedArc::subslice`]
+/// and [`SortedArc::range_arc`] create such views without copying.
+///
+/// ```
+/// use shar_search::SortedArc;
+///
+/// let table = SortedArc::from_vec(vec![30, 10, 20]);
+/// let shared = table.clone();
+///
+/// std::thread::spawn(move || assert_eq!(shared.search(&20), Ok(1)))
+///     .join()
+///     .unwrap();
+/// assert_eq!(&*table, &[10, 20, 30]);
+/// ```
+pub struct SortedArc<T> {
+    data: Arc<[T]>,
+    range: Range<usize>,
+}
+
+impl<T> Clone for SortedArc<T> {
+    fn clone(&self) -> Self {
+        Self {
+            data: Arc::clone(&self.data),
+            range: self.range.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SortedArc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+impl<T> Deref for SortedArc<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T> SortedArc<T> {
+    fn from_arc_unchecked(data: Arc<[T]>) -> Self {
+        let range = 0..data.len();
+        Self { data, range }
+    }
+
+    /// Returns the elements as a sorted slice.
+    pub fn as_slice(&self) -> &[T] {
+        &self.data[self.range.clone()]
+    }
+
+    /// Returns a view of the elements at the indices in `range`, sharing the same allocation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds, like slice indexing.
+    pub fn subslice<R: RangeBounds<usize>>(&self, range: R) -> Self {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len(),
+        };
+
+        // Indexing checks the bounds with the usual slice panics.
+        let _ = &self.as_slice()[start..end];
+
+        Self {
+            data: Arc::clone(&self.data),
+            range: self.range.start + start..self.range.start + end,
+        }
+    }
+
+    /// Returns whether two `SortedArc`s are views of the same allocation.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.data, &other.data)
+    }
+}
+
+impl<T: Ord> SortedArc<T> {
+    /// Creates a `SortedArc` from `vec`, sorting it first.
+    pub fn from_vec(mut vec: Vec<T>) -> Self {
+        vec.sort();
+        Self::from_arc_unchecked(vec.into())
+    }
+
+    /// Creates a `SortedArc` from a vector that is already sorted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `vec` is not sorted. Use [`SortedArc::try_from_sorted`] to handle this case.
+    pub fn from_sorted_vec(vec: Vec<T>) -> Self {
+        match Self::try_from_sorted(vec) {
+            Ok(sorted) => sorted,
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    /// Creates a `SortedArc` from a vector that is already sorted, returning an error with the
+    /// index of the first inversion if it is not.
+    pub fn try_from_sorted(vec: Vec<T>) -> Result<Self, UnsortedError> {
+        match validate::first_unsorted_by(&vec, |a, b| a <= b) {
+            Some(index) => Err(UnsortedError { index }),
+            None => Ok(Self::from_arc_unchecked(vec.into())),
+        }
+    }
+
+    /// Binary searches for `x` with Shar's algorithm. If there are multiple matches, the first
+    /// is returned.
+    pub fn search<Q>(&self, x: &Q) -> Result<usize, usize>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.as_slice().bl_binary_search_by(|p| p.borrow().cmp(x))
+    }
+
+    /// Returns whether the slice contains `x`.
+    pub fn contains<Q>(&self, x: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search(x).is_ok()
+    }
+
+    /// Returns the index of the first element that is not less than `x`.
+    pub fn lower_bound<Q>(&self, x: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.as_slice().bl_partition_point(|p| p.borrow() < x)
+    }
+
+    /// Returns the index of the first element that is greater than `x`.
+    pub fn upper_bound<Q>(&self, x: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.as_slice().bl_partition_point(|p| p.borrow() <= x)
+    }
+
+    /// Returns the range of indices of all elements equal to `x`.
+    pub fn equal_range<Q>(&self, x: &Q) -> Range<usize>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.lower_bound(x)..self.upper_bound(x)
+    }
+
+    /// Returns the subslice of elements within `range`. If the start of the range lies after
+    /// its end, the subslice is empty.
+    pub fn range<Q, R>(&self, range: R) -> &[T]
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let slice = self.as_slice();
+        &slice[resolve_range(slice, &range, |p| p.borrow())]
+    }
+
+    /// Returns a view of the elements within `range`, sharing the same allocation.
+    pub fn range_arc<Q, R>(&self, range: R) -> Self
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        self.subslice(resolve_range(self.as_slice(), &range, |p| p.borrow()))
+    }
+}
+
+impl<T: Ord> From<Vec<T>> for SortedArc<T> {
+    /// Creates a `SortedArc` with [`SortedArc::from_vec`].
+    fn from(vec: Vec<T>) -> Self {
+        Self::from_vec(vec)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{ops::Bound, thread};
+
+    use super::SortedArc;
+    use crate::SharBinarySearch;
+
+    #[test]
+    fn test_construction() {
+        let sorted = SortedArc::from_vec(vec![5, 3, 9, 3]);
+        assert_eq!(sorted.as_slice(), &[3, 3, 5, 9]);
+
+        let sorted = SortedArc::from_sorted_vec(vec![1, 2, 2, 4]);
+        assert_eq!(&*sorted, &[1, 2, 2, 4]);
+
+        let empty = SortedArc::<u32>::try_from_sorted(vec![]).unwrap();
+        assert!(empty.is_empty());
+
+        let err = SortedArc::try_from_sorted(vec![1, 3, 2, 4]).unwrap_err();
+        assert_eq!(err.index(), 2);
+        assert_eq!(
+            err.to_string(),
+            "element at index 2 is less than its predecessor"
+        );
+        assert_eq!(
+            SortedArc::try_from_sorted(vec![2, 1]).unwrap_err().index(),
+            1
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "element at index 1 is less than its predecessor")]
+    fn test_from_sorted_vec_panics() {
+        SortedArc::from_sorted_vec(vec![2, 1]);
+    }
+
+    #[test]
+    fn test_search_api() {
+        let sorted = SortedArc::from_vec(vec![10, 20, 20, 30, 40]);
+
+        assert_eq!(sorted.search(&20), Ok(1));
+        assert_eq!(sorted.search(&25), Err(3));
+        assert!(sorted.contains(&40));
+        assert_eq!(sorted.lower_bound(&20), 1);
+        assert_eq!(sorted.upper_bound(&20), 3);
+        assert_eq!(sorted.equal_range(&20), 1..3);
+        assert_eq!(sorted.range(15..35), &[20, 20, 30]);
+
+        // The slice search trait is available through `Deref`.
+        assert_eq!(sorted.bl_binary_search(&30), Ok(3));
+    }
+
+    #[test]
+    fn test_subslices() {
+        let sorted = SortedArc::from_vec((0..100).collect());
+
+        let view = sorted.subslice(10..20);
+        assert!(SortedArc::ptr_eq(&sorted, &view));
+        assert_eq!(view.len(), 10);
+        assert_eq!(view.search(&15), Ok(5));
+        assert_eq!(view.search(&25), Err(10));
+
+        let nested = view.subslice(2..=4);
+        assert_eq!(&*nested, &[12, 13, 14]);
+
+        let by_key = sorted.range_arc(95..);
+        assert_eq!(&*by_key, &[95, 96, 97, 98, 99]);
+        assert!(SortedArc::ptr_eq(&sorted, &by_key));
+        assert!(sorted
+            .range_arc((Bound::Included(50), Bound::Excluded(40)))
+            .is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_subslice_out_of_bounds() {
+        SortedArc::from_vec(vec![1, 2, 3]).subslice(2..5);
+    }
+
+    #[test]
+    fn test_cross_thread() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SortedArc<u64>>();
+
+        let sorted = SortedArc::from_vec((0..10_000_u64).map(|i| i * 7 % 10_007).collect());
+        let expected: Vec<_> = (0..1000).map(|key| sorted.search(&key)).collect();
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let sorted = sorted.clone();
+                thread::spawn(move || (0..1000).map(|key| sorted.search(&key)).collect::<Vec<_>>())
+            })
+            .collect();
+
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), expected);
+        }
+    }
+}
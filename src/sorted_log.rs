@@ -0,0 +1,318 @@
+//! An append-only sorted sequence, for data that arrives in order, such as log timestamps.
+
+use alloc::vec::Vec;
+use core::{borrow::Borrow, error::Error, fmt, ops::RangeBounds, slice};
+
+use crate::{resolve_range, SharBinarySearch};
+
+/// The error returned when appending an item that is less than the last item of a
+/// [`SortedLog`]. It holds the rejected item, so it can be retried or logged, along with a
+/// clone of the log's last item at the time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutOfOrder<T> {
+    item: T,
+    tail: T,
+}
+
+impl<T> OutOfOrder<T> {
+    /// Returns the rejected item.
+    pub fn item(&self) -> &T {
+        &self.item
+    }
+
+    /// Returns the last item of the log when the item was rejected.
+    pub fn tail(&self) -> &T {
+        &self.tail
+    }
+
+    /// Consumes the error, returning the rejected item.
+    pub fn into_item(self) -> T {
+        self.item
+    }
+}
+
+impl<T: fmt::Debug> fmt::Display for OutOfOrder<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "item {:?} is less than the last item {:?}",
+            self.item, self.tail
+        )
+    }
+}
+
+impl<T: fmt::Debug> Error for OutOfOrder<T> {}
+
+/// A sequence that only grows at its end, and only with items not less than its last, so it is
+/// always sorted.
+///
+/// An item *equal* to the last is accepted and placed after it, as several log entries can share
+/// a timestamp. Since the sequence is sorted by construction, it has the searches of a
+/// [`SortedVec`](crate::SortedVec) without its `O(n)` inserts, and its smallest and largest
+/// items are simply its first and last.
+///
+/// ```
+/// use shar_search::sorted_log::SortedLog;
+///
+/// // (timestamp in seconds, temperature in tenths of a degree)
+/// let mut buffer = SortedLog::new();
+/// buffer.append((100, 205)).unwrap();
+/// buffer.append((160, 210)).unwrap();
+/// buffer.append((160, 212)).unwrap();
+/// buffer.append((220, 207)).unwrap();
+///
+/// // A late reading is rejected, with what it lost to.
+/// let late = buffer.append((130, 200)).unwrap_err();
+/// assert_eq!(late.tail(), &(220, 207));
+///
+/// // Readings from 150s up to, but not including, 220s.
+/// let window = buffer.slice_between((150, i32::MIN)..(220, i32::MIN));
+/// assert_eq!(window, &[(160, 210), (160, 212)]);
+/// assert_eq!(buffer.max(), Some(&(220, 207)));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SortedLog<T> {
+    items: Vec<T>,
+}
+
+impl<T> Default for SortedLog<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> SortedLog<T> {
+    /// Creates a new, empty log.
+    pub const fn new() -> Self {
+        Self { items: Vec::new() }
+    }
+
+    /// Creates a new, empty log with space for at least `capacity` items.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            items: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the number of items.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns whether the log is empty.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns the items as a sorted slice.
+    pub fn as_slice(&self) -> &[T] {
+        &self.items
+    }
+
+    /// Returns an iterator over the items in order.
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.items.iter()
+    }
+
+    /// Returns the last item, the one most recently appended.
+    pub fn last(&self) -> Option<&T> {
+        self.items.last()
+    }
+
+    /// Returns the smallest item in `O(1)`: the first.
+    pub fn min(&self) -> Option<&T> {
+        self.items.first()
+    }
+
+    /// Returns the largest item in `O(1)`: the last. If several are equal, this is the last of
+    /// them.
+    pub fn max(&self) -> Option<&T> {
+        self.items.last()
+    }
+
+    /// Consumes the log, returning the underlying [`Vec`].
+    pub fn into_vec(self) -> Vec<T> {
+        self.items
+    }
+}
+
+impl<T: Ord> SortedLog<T> {
+    /// Appends `item`, which must not be less than the last item.
+    ///
+    /// # Errors
+    ///
+    /// If `item` is less than the last item, returns it in an [`OutOfOrder`] along with a clone
+    /// of the last item, and leaves the log unchanged.
+    pub fn append(&mut self, item: T) -> Result<(), OutOfOrder<T>>
+    where
+        T: Clone,
+    {
+        match self.items.last() {
+            Some(tail) if item < *tail => Err(OutOfOrder {
+                item,
+                tail: tail.clone(),
+            }),
+            _ => {
+                self.items.push(item);
+                Ok(())
+            }
+        }
+    }
+
+    /// Appends `item` without checking that it is not less than the last item.
+    ///
+    /// This is not `unsafe`, but if `item` is out of order, the searches of this log return
+    /// unspecified results. The order is checked in debug builds.
+    pub fn append_unchecked(&mut self, item: T) {
+        debug_assert!(
+            self.items.last().is_none_or(|tail| *tail <= item),
+            "item appended out of order"
+        );
+        self.items.push(item);
+    }
+
+    /// Appends every item of `items` in order, checking each like [`SortedLog::append`].
+    ///
+    /// # Errors
+    ///
+    /// At the first item that is out of order, stops and returns it in an [`OutOfOrder`]. The
+    /// items before it stay appended, and the ones after it are left in the iterator.
+    ///
+    /// ```
+    /// use shar_search::sorted_log::SortedLog;
+    ///
+    /// let mut log = SortedLog::new();
+    /// let err = log.extend_monotonic([1, 3, 2, 4]).unwrap_err();
+    /// assert_eq!((err.item(), err.tail()), (&2, &3));
+    /// assert_eq!(log.as_slice(), &[1, 3]);
+    /// ```
+    pub fn extend_monotonic<I>(&mut self, items: I) -> Result<(), OutOfOrder<T>>
+    where
+        I: IntoIterator<Item = T>,
+        T: Clone,
+    {
+        let mut items = items.into_iter();
+        self.items.reserve(items.size_hint().0);
+        items.try_for_each(|item| self.append(item))
+    }
+
+    /// Binary searches for `x`. If there are multiple matches, the *first* is returned.
+    pub fn search<Q>(&self, x: &Q) -> Result<usize, usize>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.items.bl_binary_search_by(|p| p.borrow().cmp(x))
+    }
+
+    /// Returns the subslice of items within `range`, resolved with the branchless search. If
+    /// the start of the range lies after its end, the subslice is empty.
+    pub fn slice_between<Q, R>(&self, range: R) -> &[T]
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        &self.items[resolve_range(&self.items, &range, |p| p.borrow())]
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SortedLog<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::ops::Bound;
+
+    use super::*;
+
+    #[test]
+    fn test_append() {
+        let mut log = SortedLog::new();
+        assert_eq!(log.min(), None);
+        assert_eq!(log.append(5), Ok(()));
+        assert_eq!(log.append(7), Ok(()));
+
+        let err = log.append(6).unwrap_err();
+        assert_eq!(err.item(), &6);
+        assert_eq!(err.tail(), &7);
+        assert_eq!(err.to_string(), "item 6 is less than the last item 7");
+        assert_eq!(err.into_item(), 6);
+
+        assert_eq!(log.as_slice(), &[5, 7]);
+        assert_eq!(
+            (log.min(), log.max(), log.last()),
+            (Some(&5), Some(&7), Some(&7))
+        );
+    }
+
+    #[test]
+    fn test_append_equal_to_last() {
+        // Equal items are allowed, and kept in append order.
+        let mut log = SortedLog::new();
+        for item in [(1, 'a'), (2, 'b'), (2, 'c'), (2, 'd')] {
+            log.append(item).unwrap();
+        }
+        assert!(log.append((2, 'a')).is_err());
+        assert_eq!(log.search(&(2, 'b')), Ok(1));
+        assert_eq!(log.max(), Some(&(2, 'd')));
+
+        let mut log = SortedLog::new();
+        log.extend_monotonic([3, 3, 3]).unwrap();
+        assert_eq!(log.search(&3), Ok(0));
+        assert_eq!(log.slice_between(3..=3).len(), 3);
+    }
+
+    #[test]
+    fn test_extend_monotonic_partial() {
+        let mut log = SortedLog::new();
+        log.extend_monotonic([1, 2]).unwrap();
+
+        let mut rest = vec![4, 5, 3, 6, 7].into_iter();
+        let err = log.extend_monotonic(&mut rest).unwrap_err();
+        assert_eq!((err.item(), err.tail()), (&3, &5));
+        assert_eq!(log.as_slice(), &[1, 2, 4, 5]);
+        // The items after the failure were not consumed.
+        assert!(rest.eq([6, 7]));
+
+        // A failure on the first item appends nothing.
+        assert!(log.extend_monotonic([0, 10]).is_err());
+        assert_eq!(log.len(), 4);
+        assert_eq!(log.extend_monotonic([]), Ok(()));
+    }
+
+    #[test]
+    fn test_search_and_slice_between() {
+        let mut log = SortedLog::with_capacity(8);
+        for item in [10, 20, 20, 30, 40] {
+            log.append_unchecked(item);
+        }
+
+        assert_eq!(log.search(&20), Ok(1));
+        assert_eq!(log.search(&25), Err(3));
+        assert_eq!(log.slice_between(15..35), &[20, 20, 30]);
+        assert_eq!(log.slice_between(20..), &[20, 20, 30, 40]);
+        assert_eq!(log.slice_between(..=10), &[10]);
+        assert_eq!(
+            log.slice_between((Bound::Included(35), Bound::Excluded(15))),
+            &[] as &[i32]
+        );
+        assert!(log.iter().eq(&log));
+        assert_eq!(log.into_vec(), [10, 20, 20, 30, 40]);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of order")]
+    #[cfg(debug_assertions)]
+    fn test_append_unchecked_out_of_order() {
+        let mut log = SortedLog::new();
+        log.append_unchecked(2);
+        log.append_unchecked(1);
+    }
+}
@@ -0,0 +1,681 @@
+//! Set algebra over plain sorted slices, without a container type.
+//!
+//! The functions here take two sorted slices, such as `&[u32]`s borrowed from elsewhere, and
+//! append their intersection, union, difference or symmetric difference to an output [`Vec`],
+//! or count it with the `_len` functions without materializing anything. Appending lets a
+//! caller reuse one buffer across many calls: clear it, and its capacity is kept.
+//!
+//! Like the [`merge`](crate::merge) and [`join`](crate::join) modules, the slices are walked
+//! together one element at a time until one side keeps falling behind, and then galloped
+//! through. Combining a small slice with a large one then only compares `O(m log(n / m))`
+//! elements, while evenly interleaved slices are combined at the speed of a plain merge.
+//!
+//! # Duplicates
+//!
+//! The plain functions use set semantics: the inputs may contain duplicates, but each value
+//! appears in the output at most once, as if the inputs had been deduplicated first. The
+//! `_multiset` functions instead count occurrences: if a value appears `a` times in `a` and `b`
+//! times in `b`, the intersection has `min(a, b)` of it, the union `max(a, b)`, the difference
+//! `a - b` (or none), and the symmetric difference `|a - b|`.
+//!
+//! Where equal elements are not identical, as with the `_by_key` functions, the output takes
+//! them from `a` when both slices have them, and from `b` for the surplus of the union and
+//! symmetric difference.
+//!
+//! ```
+//! # #[cfg(feature = "alloc")]
+//! # {
+//! use shar_search::sorted_ops::{intersection, intersection_len, union, union_multiset};
+//!
+//! let a = [1, 2, 2, 3, 5];
+//! let b = [2, 2, 2, 5, 8];
+//!
+//! let mut out = Vec::new();
+//! intersection(&a, &b, &mut out);
+//! assert_eq!(out, [2, 5]);
+//! assert_eq!(intersection_len(&a, &b), 2);
+//!
+//! out.clear();
+//! union(&a, &b, &mut out);
+//! assert_eq!(out, [1, 2, 3, 5, 8]);
+//!
+//! out.clear();
+//! union_multiset(&a, &b, &mut out);
+//! assert_eq!(out, [1, 2, 2, 2, 3, 5, 8]);
+//! # }
+//! ```
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::{cmp::Ordering, ops::Range};
+
+use crate::gallop::gallop;
+
+/// The number of consecutive steps through one side before the walk starts galloping.
+const MIN_GALLOP: usize = 7;
+
+/// A step of the walk through both slices.
+enum Step {
+    /// Elements of `a` that are less than everything left in `b`.
+    A(Range<usize>),
+    /// Elements of `b` that are less than everything left in `a`.
+    B(Range<usize>),
+    /// The runs of an equal value in `a` and in `b`.
+    // Only the operations that need `alloc` look at the runs.
+    #[cfg_attr(not(feature = "alloc"), allow(dead_code))]
+    Both(Range<usize>, Range<usize>),
+}
+
+/// Walks the sorted slices `a` and `b` together, passing each step, in order, to `visit`
+/// along with the comparator.
+#[inline]
+fn walk<T, F, V>(a: &[T], b: &[T], mut compare: F, mut visit: V)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    V: FnMut(Step, &mut F),
+{
+    let (mut i, mut j) = (0, 0);
+    let (mut a_streak, mut b_streak) = (0, 0);
+
+    while i < a.len() && j < b.len() {
+        let step = match compare(&a[i], &b[j]) {
+            Ordering::Less => {
+                b_streak = 0;
+                let end = if a_streak < MIN_GALLOP {
+                    a_streak += 1;
+                    i + 1
+                } else {
+                    let run = 1 + gallop(&a[i + 1..], |x| compare(x, &b[j]).is_lt());
+                    if run < MIN_GALLOP {
+                        a_streak = 0;
+                    }
+                    i + run
+                };
+                let range = i..end;
+                i = end;
+                Step::A(range)
+            }
+            Ordering::Greater => {
+                a_streak = 0;
+                let end = if b_streak < MIN_GALLOP {
+                    b_streak += 1;
+                    j + 1
+                } else {
+                    let run = 1 + gallop(&b[j + 1..], |y| compare(&a[i], y).is_gt());
+                    if run < MIN_GALLOP {
+                        b_streak = 0;
+                    }
+                    j + run
+                };
+                let range = j..end;
+                j = end;
+                Step::B(range)
+            }
+            Ordering::Equal => {
+                (a_streak, b_streak) = (0, 0);
+                // Most values are not repeated, so check the next element before galloping.
+                let mut a_end = i + 1;
+                if a.get(a_end).is_some_and(|x| compare(x, &b[j]).is_eq()) {
+                    a_end += 1 + gallop(&a[a_end + 1..], |x| compare(x, &b[j]).is_eq());
+                }
+                let mut b_end = j + 1;
+                if b.get(b_end).is_some_and(|y| compare(&a[i], y).is_eq()) {
+                    b_end += 1 + gallop(&b[b_end + 1..], |y| compare(&a[i], y).is_eq());
+                }
+                let step = Step::Both(i..a_end, j..b_end);
+                (i, j) = (a_end, b_end);
+                step
+            }
+        };
+        visit(step, &mut compare);
+    }
+
+    if i < a.len() {
+        visit(Step::A(i..a.len()), &mut compare);
+    } else if j < b.len() {
+        visit(Step::B(j..b.len()), &mut compare);
+    }
+}
+
+/// Calls `f` with the index of the first element of each run of equal elements that starts in
+/// `slice[range]`.
+#[inline]
+fn for_each_distinct<T, F>(
+    slice: &[T],
+    range: Range<usize>,
+    compare: &mut F,
+    mut f: impl FnMut(usize),
+) where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    for index in range {
+        if index == 0 || compare(&slice[index - 1], &slice[index]).is_ne() {
+            f(index);
+        }
+    }
+}
+
+/// Appends the elements of `slice[range]` to `out`, keeping one of each run of equal elements.
+#[cfg(feature = "alloc")]
+fn extend_distinct<T, F>(out: &mut Vec<T>, slice: &[T], range: Range<usize>, compare: &mut F)
+where
+    T: Clone,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    for_each_distinct(slice, range, compare, |index| {
+        out.push(slice[index].clone())
+    });
+}
+
+/// Returns the number of values in `slice[range]`, counting each run of equal elements once.
+fn count_distinct<T, F>(slice: &[T], range: Range<usize>, compare: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut count = 0;
+    for_each_distinct(slice, range, compare, |_| count += 1);
+    count
+}
+
+/// Defines the plain and `_by_key` forms of an operation, given its comparator form.
+macro_rules! operation {
+    (
+        $(#[$attr:meta])*
+        $name:ident, $by_key:ident, $by:ident, $summary:literal
+    ) => {
+        $(#[$attr])*
+        #[cfg(feature = "alloc")]
+        pub fn $name<T: Ord + Clone>(a: &[T], b: &[T], out: &mut Vec<T>) {
+            $by(a, b, out, T::cmp);
+        }
+
+        #[doc = concat!("Appends ", $summary, " of the sorted slices `a` and `b` to `out`,")]
+        #[doc = concat!("comparing the keys returned by `f`. See [`", stringify!($name), "`].")]
+        #[cfg(feature = "alloc")]
+        pub fn $by_key<T, K, F>(a: &[T], b: &[T], out: &mut Vec<T>, mut f: F)
+        where
+            T: Clone,
+            K: Ord,
+            F: FnMut(&T) -> K,
+        {
+            $by(a, b, out, |x, y| f(x).cmp(&f(y)));
+        }
+    };
+}
+
+/// Defines the plain and `_by_key` forms of a count, given its comparator form.
+macro_rules! count {
+    (
+        $(#[$attr:meta])*
+        $name:ident, $by_key:ident, $by:ident, $summary:literal
+    ) => {
+        $(#[$attr])*
+        pub fn $name<T: Ord>(a: &[T], b: &[T]) -> usize {
+            $by(a, b, T::cmp)
+        }
+
+        #[doc = concat!("Returns the number of values in ", $summary, " of the sorted slices")]
+        #[doc = concat!("`a` and `b`, comparing the keys returned by `f`.")]
+        #[doc = concat!("See [`", stringify!($name), "`].")]
+        pub fn $by_key<T, K, F>(a: &[T], b: &[T], mut f: F) -> usize
+        where
+            K: Ord,
+            F: FnMut(&T) -> K,
+        {
+            $by(a, b, |x, y| f(x).cmp(&f(y)))
+        }
+    };
+}
+
+operation!(
+    /// Appends the intersection of the sorted slices `a` and `b` to `out`: each value that is
+    /// in both, once, taken from `a`.
+    ///
+    /// ```
+    /// use shar_search::sorted_ops::intersection;
+    ///
+    /// let mut out = Vec::new();
+    /// intersection(&[1, 2, 2, 4, 7], &[2, 3, 4, 4], &mut out);
+    /// assert_eq!(out, [2, 4]);
+    /// ```
+    intersection, intersection_by_key, intersection_by, "the intersection"
+);
+
+#[cfg(feature = "alloc")]
+fn intersection_by<T: Clone, F>(a: &[T], b: &[T], out: &mut Vec<T>, compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    walk(a, b, compare, |step, _| {
+        if let Step::Both(a_run, _) = step {
+            out.push(a[a_run.start].clone());
+        }
+    });
+}
+
+operation!(
+    /// Appends the union of the sorted slices `a` and `b` to `out`: each value that is in
+    /// either, once, taken from `a` if it is in both.
+    ///
+    /// ```
+    /// use shar_search::sorted_ops::union;
+    ///
+    /// let mut out = Vec::new();
+    /// union(&[1, 2, 2, 4], &[2, 3, 4, 4], &mut out);
+    /// assert_eq!(out, [1, 2, 3, 4]);
+    /// ```
+    union, union_by_key, union_by, "the union"
+);
+
+#[cfg(feature = "alloc")]
+fn union_by<T: Clone, F>(a: &[T], b: &[T], out: &mut Vec<T>, compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    walk(a, b, compare, |step, compare| match step {
+        Step::A(range) => extend_distinct(out, a, range, compare),
+        Step::B(range) => extend_distinct(out, b, range, compare),
+        Step::Both(a_run, _) => out.push(a[a_run.start].clone()),
+    });
+}
+
+operation!(
+    /// Appends the difference of the sorted slices `a` and `b` to `out`: each value that is in
+    /// `a` but not in `b`, once.
+    ///
+    /// ```
+    /// use shar_search::sorted_ops::difference;
+    ///
+    /// let mut out = Vec::new();
+    /// difference(&[1, 1, 2, 4, 7], &[2, 3, 4], &mut out);
+    /// assert_eq!(out, [1, 7]);
+    /// ```
+    difference, difference_by_key, difference_by, "the difference"
+);
+
+#[cfg(feature = "alloc")]
+fn difference_by<T: Clone, F>(a: &[T], b: &[T], out: &mut Vec<T>, compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    walk(a, b, compare, |step, compare| {
+        if let Step::A(range) = step {
+            extend_distinct(out, a, range, compare);
+        }
+    });
+}
+
+operation!(
+    /// Appends the symmetric difference of the sorted slices `a` and `b` to `out`: each value
+    /// that is in exactly one of them, once.
+    ///
+    /// ```
+    /// use shar_search::sorted_ops::symmetric_difference;
+    ///
+    /// let mut out = Vec::new();
+    /// symmetric_difference(&[1, 2, 4, 7], &[2, 3, 3, 4], &mut out);
+    /// assert_eq!(out, [1, 3, 7]);
+    /// ```
+    symmetric_difference,
+    symmetric_difference_by_key,
+    symmetric_difference_by,
+    "the symmetric difference"
+);
+
+#[cfg(feature = "alloc")]
+fn symmetric_difference_by<T: Clone, F>(a: &[T], b: &[T], out: &mut Vec<T>, compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    walk(a, b, compare, |step, compare| match step {
+        Step::A(range) => extend_distinct(out, a, range, compare),
+        Step::B(range) => extend_distinct(out, b, range, compare),
+        Step::Both(..) => {}
+    });
+}
+
+operation!(
+    /// Appends the multiset intersection of the sorted slices `a` and `b` to `out`: each value
+    /// as many times as it is in whichever has fewer of it, taken from `a`.
+    ///
+    /// ```
+    /// use shar_search::sorted_ops::intersection_multiset;
+    ///
+    /// let mut out = Vec::new();
+    /// intersection_multiset(&[1, 2, 2, 4, 4], &[2, 2, 2, 4], &mut out);
+    /// assert_eq!(out, [2, 2, 4]);
+    /// ```
+    intersection_multiset,
+    intersection_multiset_by_key,
+    intersection_multiset_by,
+    "the multiset intersection"
+);
+
+#[cfg(feature = "alloc")]
+fn intersection_multiset_by<T: Clone, F>(a: &[T], b: &[T], out: &mut Vec<T>, compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    walk(a, b, compare, |step, _| {
+        if let Step::Both(a_run, b_run) = step {
+            let len = a_run.len().min(b_run.len());
+            out.extend_from_slice(&a[a_run.start..a_run.start + len]);
+        }
+    });
+}
+
+operation!(
+    /// Appends the multiset union of the sorted slices `a` and `b` to `out`: each value as many
+    /// times as it is in whichever has more of it, taken from `a` and then, for any surplus,
+    /// from `b`.
+    ///
+    /// ```
+    /// use shar_search::sorted_ops::union_multiset;
+    ///
+    /// let mut out = Vec::new();
+    /// union_multiset(&[1, 2, 2, 4], &[2, 2, 2, 3], &mut out);
+    /// assert_eq!(out, [1, 2, 2, 2, 3, 4]);
+    /// ```
+    union_multiset, union_multiset_by_key, union_multiset_by, "the multiset union"
+);
+
+#[cfg(feature = "alloc")]
+fn union_multiset_by<T: Clone, F>(a: &[T], b: &[T], out: &mut Vec<T>, compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    out.reserve(a.len().max(b.len()));
+    walk(a, b, compare, |step, _| match step {
+        Step::A(range) => out.extend_from_slice(&a[range]),
+        Step::B(range) => out.extend_from_slice(&b[range]),
+        Step::Both(a_run, b_run) => {
+            let surplus = b_run.start + a_run.len().min(b_run.len())..b_run.end;
+            out.extend_from_slice(&a[a_run]);
+            out.extend_from_slice(&b[surplus]);
+        }
+    });
+}
+
+operation!(
+    /// Appends the multiset difference of the sorted slices `a` and `b` to `out`: each value as
+    /// many more times as it is in `a` than in `b`.
+    ///
+    /// ```
+    /// use shar_search::sorted_ops::difference_multiset;
+    ///
+    /// let mut out = Vec::new();
+    /// difference_multiset(&[1, 2, 2, 2, 4], &[2, 4, 4], &mut out);
+    /// assert_eq!(out, [1, 2, 2]);
+    /// ```
+    difference_multiset,
+    difference_multiset_by_key,
+    difference_multiset_by,
+    "the multiset difference"
+);
+
+#[cfg(feature = "alloc")]
+fn difference_multiset_by<T: Clone, F>(a: &[T], b: &[T], out: &mut Vec<T>, compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    walk(a, b, compare, |step, _| match step {
+        Step::A(range) => out.extend_from_slice(&a[range]),
+        Step::Both(a_run, b_run) if a_run.len() > b_run.len() => {
+            out.extend_from_slice(&a[a_run.start + b_run.len()..a_run.end]);
+        }
+        _ => {}
+    });
+}
+
+operation!(
+    /// Appends the multiset symmetric difference of the sorted slices `a` and `b` to `out`:
+    /// each value as many more times as it is in one than in the other.
+    ///
+    /// ```
+    /// use shar_search::sorted_ops::symmetric_difference_multiset;
+    ///
+    /// let mut out = Vec::new();
+    /// symmetric_difference_multiset(&[1, 2, 2, 2, 4], &[2, 4, 4], &mut out);
+    /// assert_eq!(out, [1, 2, 2, 4]);
+    /// ```
+    symmetric_difference_multiset,
+    symmetric_difference_multiset_by_key,
+    symmetric_difference_multiset_by,
+    "the multiset symmetric difference"
+);
+
+#[cfg(feature = "alloc")]
+fn symmetric_difference_multiset_by<T: Clone, F>(a: &[T], b: &[T], out: &mut Vec<T>, compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    walk(a, b, compare, |step, _| match step {
+        Step::A(range) => out.extend_from_slice(&a[range]),
+        Step::B(range) => out.extend_from_slice(&b[range]),
+        Step::Both(a_run, b_run) => {
+            let common = a_run.len().min(b_run.len());
+            out.extend_from_slice(&a[a_run.start + common..a_run.end]);
+            out.extend_from_slice(&b[b_run.start + common..b_run.end]);
+        }
+    });
+}
+
+count!(
+    /// Returns the number of values in both of the sorted slices `a` and `b`, counting
+    /// duplicates once, without materializing the intersection.
+    ///
+    /// ```
+    /// use shar_search::sorted_ops::intersection_len;
+    ///
+    /// assert_eq!(intersection_len(&[1, 2, 2, 4, 7], &[2, 3, 4, 4]), 2);
+    /// ```
+    intersection_len, intersection_len_by_key, intersection_len_by, "the intersection"
+);
+
+fn intersection_len_by<T, F>(a: &[T], b: &[T], compare: F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut len = 0;
+    walk(a, b, compare, |step, _| {
+        if let Step::Both(..) = step {
+            len += 1;
+        }
+    });
+    len
+}
+
+count!(
+    /// Returns the number of values in either of the sorted slices `a` and `b`, counting
+    /// duplicates once, without materializing the union.
+    ///
+    /// The values of one slice that are not in the other still have to be looked at to count
+    /// their duplicates, so this is linear in the length of the slices.
+    ///
+    /// ```
+    /// use shar_search::sorted_ops::union_len;
+    ///
+    /// assert_eq!(union_len(&[1, 2, 2, 4], &[2, 3, 4, 4]), 4);
+    /// ```
+    union_len, union_len_by_key, union_len_by, "the union"
+);
+
+fn union_len_by<T, F>(a: &[T], b: &[T], compare: F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut len = 0;
+    walk(a, b, compare, |step, compare| match step {
+        Step::A(range) => len += count_distinct(a, range, compare),
+        Step::B(range) => len += count_distinct(b, range, compare),
+        Step::Both(..) => len += 1,
+    });
+    len
+}
+
+count!(
+    /// Returns the number of values in the sorted slice `a` but not in `b`, counting duplicates
+    /// once, without materializing the difference.
+    ///
+    /// The values of `a` that are not in `b` still have to be looked at to count their
+    /// duplicates, so this is linear in their number.
+    ///
+    /// ```
+    /// use shar_search::sorted_ops::difference_len;
+    ///
+    /// assert_eq!(difference_len(&[1, 1, 2, 4, 7], &[2, 3, 4]), 2);
+    /// ```
+    difference_len, difference_len_by_key, difference_len_by, "the difference"
+);
+
+fn difference_len_by<T, F>(a: &[T], b: &[T], compare: F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut len = 0;
+    walk(a, b, compare, |step, compare| {
+        if let Step::A(range) = step {
+            len += count_distinct(a, range, compare);
+        }
+    });
+    len
+}
+
+count!(
+    /// Returns the number of values in exactly one of the sorted slices `a` and `b`, counting
+    /// duplicates once, without materializing the symmetric difference.
+    ///
+    /// ```
+    /// use shar_search::sorted_ops::symmetric_difference_len;
+    ///
+    /// assert_eq!(symmetric_difference_len(&[1, 2, 4, 7], &[2, 3, 3, 4]), 3);
+    /// ```
+    symmetric_difference_len,
+    symmetric_difference_len_by_key,
+    symmetric_difference_len_by,
+    "the symmetric difference"
+);
+
+fn symmetric_difference_len_by<T, F>(a: &[T], b: &[T], compare: F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut len = 0;
+    walk(a, b, compare, |step, compare| match step {
+        Step::A(range) => len += count_distinct(a, range, compare),
+        Step::B(range) => len += count_distinct(b, range, compare),
+        Step::Both(..) => {}
+    });
+    len
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod test {
+    use alloc::vec::Vec;
+    use core::cell::Cell;
+
+    use super::*;
+    use crate::reference::{self, assert_matches_reference, inputs};
+
+    type Operation = fn(&[u32], &[u32], &mut Vec<u32>);
+    type Len = fn(&[u32], &[u32]) -> usize;
+    type Count = fn(usize, usize) -> usize;
+
+    fn apply(op: Operation, a: &[u32], b: &[u32]) -> Vec<u32> {
+        // Existing contents are kept.
+        let mut out = vec![u32::MAX];
+        op(a, b, &mut out);
+        assert_eq!(out.remove(0), u32::MAX);
+        out
+    }
+
+    #[test]
+    fn test_set_ops_against_reference() {
+        let ops: [(Operation, Len, Count); 4] = [
+            (intersection, intersection_len, reference::ops::intersection),
+            (union, union_len, reference::ops::union),
+            (difference, difference_len, reference::ops::difference),
+            (
+                symmetric_difference,
+                symmetric_difference_len,
+                reference::ops::symmetric_difference,
+            ),
+        ];
+
+        for (op, len, count) in ops {
+            assert_matches_reference!(
+                for (a, b) in inputs::slice_pairs(174) =>
+                (apply(op, &a, &b), len(&a, &b)),
+                {
+                    let expected = reference::combine(&a, &b, count);
+                    let len = expected.len();
+                    (expected, len)
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn test_multiset_ops_against_reference() {
+        let ops: [(Operation, Count); 4] = [
+            (intersection_multiset, reference::ops::intersection_multiset),
+            (union_multiset, reference::ops::union_multiset),
+            (difference_multiset, reference::ops::difference_multiset),
+            (
+                symmetric_difference_multiset,
+                reference::ops::symmetric_difference_multiset,
+            ),
+        ];
+
+        for (op, count) in ops {
+            assert_matches_reference!(
+                for (a, b) in inputs::slice_pairs(1174) =>
+                apply(op, &a, &b),
+                reference::combine(&a, &b, count),
+            );
+        }
+    }
+
+    #[test]
+    fn test_by_key_takes_from_a() {
+        let a = [(1, 'a'), (2, 'a'), (2, 'b'), (4, 'a')];
+        let b = [(2, 'x'), (2, 'y'), (2, 'z'), (3, 'x'), (4, 'x')];
+        let key = |(k, _): &(u8, char)| *k;
+
+        let mut out = Vec::new();
+        intersection_by_key(&a, &b, &mut out, key);
+        assert_eq!(out, [(2, 'a'), (4, 'a')]);
+
+        out.clear();
+        union_by_key(&a, &b, &mut out, key);
+        assert_eq!(out, [(1, 'a'), (2, 'a'), (3, 'x'), (4, 'a')]);
+
+        out.clear();
+        union_multiset_by_key(&a, &b, &mut out, key);
+        assert_eq!(
+            out,
+            [(1, 'a'), (2, 'a'), (2, 'b'), (2, 'z'), (3, 'x'), (4, 'a')]
+        );
+
+        out.clear();
+        symmetric_difference_multiset_by_key(&a, &b, &mut out, key);
+        assert_eq!(out, [(1, 'a'), (2, 'z'), (3, 'x')]);
+
+        assert_eq!(difference_len_by_key(&a, &b, key), 1);
+    }
+
+    #[test]
+    fn test_skewed_sizes_gallop() {
+        let large: Vec<u32> = (0..100_000).collect();
+        let small = [10, 50_000, 99_999];
+
+        let calls = Cell::new(0);
+        let len = intersection_len_by_key(&small, &large, |x| {
+            calls.set(calls.get() + 1);
+            *x
+        });
+        assert_eq!(len, 3);
+        // Each comparison extracts two keys.
+        assert!(calls.get() / 2 < 200, "{} comparisons", calls.get() / 2);
+    }
+}
@@ -0,0 +1,634 @@
+//! A sorted vector that allows duplicates, using Shar's algorithm for lookups.
+
+use alloc::vec::{Drain, Vec};
+use core::{
+    borrow::Borrow,
+    fmt,
+    iter::FusedIterator,
+    ops::{Deref, Range, RangeBounds},
+    ptr,
+};
+
+use crate::{
+    duplicates::sort_with_policy,
+    raw::{self, CompactHole},
+    resolve_range, DuplicateError, DuplicatePolicy, SharBinarySearch,
+};
+
+/// A [`Vec`] that keeps its elements in sorted order, allowing duplicates.
+///
+/// Equal elements are kept in insertion order. The vector dereferences to a sorted slice, so
+/// all the slice search methods are available on it directly.
+#[derive(Clone)]
+pub struct SortedVec<T> {
+    inner: Vec<T>,
+}
+
+impl<T> Default for SortedVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Deref for SortedVec<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.inner
+    }
+}
+
+impl<T> SortedVec<T> {
+    /// Creates a new, empty sorted vector.
+    pub const fn new() -> Self {
+        Self { inner: Vec::new() }
+    }
+
+    /// Creates a new, empty sorted vector with space for at least `capacity` elements.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Creates a sorted vector from a vector that is already sorted.
+    #[allow(dead_code)]
+    pub(crate) fn from_sorted_vec_unchecked(inner: Vec<T>) -> Self {
+        Self { inner }
+    }
+
+    /// Returns the elements as a sorted slice.
+    pub fn as_slice(&self) -> &[T] {
+        &self.inner
+    }
+
+    /// Consumes the sorted vector, returning the underlying [`Vec`].
+    pub fn into_vec(self) -> Vec<T> {
+        self.inner
+    }
+
+    /// Removes all elements.
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+}
+
+impl<T: Ord> SortedVec<T> {
+    /// Creates a sorted vector from unsorted input, resolving elements that compare equal
+    /// according to `policy`.
+    ///
+    /// The input is collected into a single [`Vec`] that is sorted and deduplicated in place.
+    /// [`DuplicatePolicy::KeepFirst`] and [`DuplicatePolicy::KeepLast`] use a stable sort, so
+    /// "first" and "last" refer to the input order; with [`DuplicatePolicy::KeepAll`], the
+    /// order of equal elements is unspecified.
+    ///
+    /// # Errors
+    ///
+    /// With [`DuplicatePolicy::Error`], returns the smallest element that appears more than
+    /// once.
+    ///
+    /// ```
+    /// use shar_search::{DuplicatePolicy, SortedVec};
+    ///
+    /// let vec = SortedVec::from_unsorted_iter([3, 1, 3, 2], DuplicatePolicy::KeepFirst).unwrap();
+    /// assert_eq!(vec.as_slice(), &[1, 2, 3]);
+    ///
+    /// let err = SortedVec::from_unsorted_iter([3, 1, 3, 2], DuplicatePolicy::Error).err().unwrap();
+    /// assert_eq!(err.key(), &3);
+    /// ```
+    pub fn from_unsorted_iter<I: IntoIterator<Item = T>>(
+        iter: I,
+        policy: DuplicatePolicy,
+    ) -> Result<Self, DuplicateError<T>> {
+        let mut inner: Vec<T> = iter.into_iter().collect();
+        sort_with_policy(&mut inner, policy, true, |x| x).map_err(|key| DuplicateError { key })?;
+        Ok(Self { inner })
+    }
+
+    /// Inserts `value` after any elements equal to it, returning the index it was inserted at.
+    ///
+    /// This is `O(n)` due to shifting elements; use [`SortedVec::insert_many`] to insert many
+    /// elements at once.
+    pub fn insert(&mut self, value: T) -> usize {
+        let index = self.inner.bl_upper_bound(&value);
+        self.inner.insert(index, value);
+        index
+    }
+
+    /// Inserts all elements of `items`, keeping the vector sorted.
+    ///
+    /// The incoming batch is collected and sorted, then merged into the vector with a single
+    /// backwards pass (the vector is grown once and written from the end), so inserting `k`
+    /// elements into a vector of length `n` is `O(n + k log k)` rather than the `O(k * n)` of
+    /// repeated [`SortedVec::insert`] calls. Existing elements are placed before equal
+    /// incoming ones, and equal incoming elements keep their relative order.
+    ///
+    /// If `T`'s comparison panics, the vector is left holding every element exactly once, but
+    /// possibly out of order.
+    pub fn insert_many<I: IntoIterator<Item = T>>(&mut self, items: I) {
+        let mut batch: Vec<T> = items.into_iter().collect();
+        batch.sort();
+        raw::merge_sorted_batch(&mut self.inner, batch, |a, b| a < b);
+    }
+
+    /// Returns whether the vector contains an element equal to `x`.
+    pub fn contains<Q>(&self, x: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.inner
+            .bl_binary_search_by(|p| p.borrow().cmp(x))
+            .is_ok()
+    }
+
+    /// Removes and returns the first element equal to `x`, if any.
+    pub fn remove<Q>(&mut self, x: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.inner
+            .bl_binary_search_by(|p| p.borrow().cmp(x))
+            .ok()
+            .map(|index| self.inner.remove(index))
+    }
+
+    /// Removes every element equal to one of `keys`, returning how many were removed. Note it is
+    /// assumed that `keys` is sorted. See
+    /// [`VecSortedExt::remove_sorted_keys`](crate::VecSortedExt::remove_sorted_keys).
+    pub fn remove_sorted_keys<Q>(&mut self, keys: &[Q]) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord,
+    {
+        raw::remove_sorted_keys_by(&mut self.inner, keys, T::borrow)
+    }
+
+    /// Removes and returns the element at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove_index(&mut self, index: usize) -> T {
+        self.inner.remove(index)
+    }
+
+    /// Returns the subslice of elements within `range`, resolved with the branchless search. If
+    /// the start of the range lies after its end, the subslice is empty.
+    pub fn range<Q, R>(&self, range: R) -> &[T]
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        &self.inner[resolve_range(&self.inner, &range, |p| p.borrow())]
+    }
+
+    /// Removes the elements within `range`, returning them in order as an iterator.
+    ///
+    /// Both ends of the range are resolved with the branchless search. As with [`Vec::drain`],
+    /// the whole range is removed even if the iterator is dropped before it is fully consumed.
+    /// If the iterator is leaked (e.g. with [`std::mem::forget`]), the vector is left valid but
+    /// may lose the elements after the range as well.
+    pub fn drain_range<Q, R>(&mut self, range: R) -> Drain<'_, T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let indices = resolve_range(&self.inner, &range, |p| p.borrow());
+        self.inner.drain(indices)
+    }
+
+    /// Returns an iterator that removes and yields the elements within `range` for which `pred`
+    /// returns `true`, in ascending order, like [`Vec::extract_if`] restricted to a range.
+    ///
+    /// Both ends of the range are resolved with the branchless search, so `pred` is only called
+    /// on elements within it. The elements that are kept are moved down over the removed ones as
+    /// the iterator advances, in a single pass. If the iterator is dropped before it is fully
+    /// consumed, the elements it has not reached yet are kept. As with
+    /// [`SortedVec::drain_range`], if the iterator is leaked (e.g. with [`std::mem::forget`]),
+    /// the vector is left valid but may lose the elements from the start of the range on.
+    ///
+    /// ```
+    /// use shar_search::SortedVec;
+    ///
+    /// let mut vec = SortedVec::new();
+    /// vec.insert_many(1..=10);
+    ///
+    /// let evens: Vec<_> = vec.extract_if(3..8, |x| x % 2 == 0).collect();
+    /// assert_eq!(evens, [4, 6]);
+    /// assert_eq!(vec.as_slice(), &[1, 2, 3, 5, 7, 8, 9, 10]);
+    /// ```
+    pub fn extract_if<Q, R, F>(&mut self, range: R, pred: F) -> ExtractIf<'_, T, F>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+        F: FnMut(&T) -> bool,
+    {
+        let indices = resolve_range(&self.inner, &range, |p| p.borrow());
+        ExtractIf::new(&mut self.inner, indices, pred)
+    }
+
+    /// Consumes the vector, collapsing each run of equal elements into its first element and the
+    /// length of the run. The runs are found as in [`count_runs`](crate::runs::count_runs), in
+    /// `O(k log n)` comparisons for `k` distinct elements.
+    ///
+    /// ```
+    /// use shar_search::SortedVec;
+    ///
+    /// let mut vec = SortedVec::new();
+    /// vec.insert_many(["b", "a", "b", "c", "b"].map(String::from));
+    /// let counts = vec.dedup_with_counts();
+    /// assert_eq!(counts, [("a".into(), 1), ("b".into(), 3), ("c".into(), 1)]);
+    /// ```
+    pub fn dedup_with_counts(self) -> Vec<(T, u64)> {
+        let lens: Vec<usize> = self.inner.bl_runs().map(|(_, run)| run.len()).collect();
+        collapse_runs(self.inner, lens)
+    }
+
+    /// Consumes the vector, collapsing each run of elements with equal keys into its first
+    /// element and the length of the run. Note it is assumed that the elements are also sorted
+    /// by the key, as they are for a key that only drops detail, like a timestamp's day. See
+    /// [`SortedVec::dedup_with_counts`].
+    pub fn dedup_with_counts_by_key<B, F>(self, f: F) -> Vec<(T, u64)>
+    where
+        F: FnMut(&T) -> B,
+        B: Ord,
+    {
+        let lens: Vec<usize> = self
+            .inner
+            .bl_runs_by_key(f)
+            .map(|(_, run)| run.len())
+            .collect();
+        collapse_runs(self.inner, lens)
+    }
+}
+
+/// Keeps the first element of each run of `vec`, whose runs have lengths `lens`, along with the
+/// length of its run, dropping the rest.
+fn collapse_runs<T>(vec: Vec<T>, lens: Vec<usize>) -> Vec<(T, u64)> {
+    let mut elements = vec.into_iter();
+    lens.into_iter()
+        .map(|len| {
+            let first = elements.next().unwrap();
+            if len > 1 {
+                // Drops the rest of the run in place.
+                elements.nth(len - 2);
+            }
+            (first, len as u64)
+        })
+        .collect()
+}
+
+impl<T: Ord> Extend<T> for SortedVec<T> {
+    /// Extends the vector using [`SortedVec::insert_many`].
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.insert_many(iter);
+    }
+}
+
+/// An iterator that removes the elements of a sorted vector within a range that match a
+/// predicate, yielding them in ascending order.
+///
+/// Created by [`SortedVec::extract_if`] and [`SharSet::extract_if`](crate::SharSet::extract_if).
+pub struct ExtractIf<'a, T, F> {
+    hole: CompactHole<'a, T>,
+    end: usize,
+    pred: F,
+}
+
+impl<'a, T, F> ExtractIf<'a, T, F> {
+    pub(crate) fn new(vec: &'a mut Vec<T>, range: Range<usize>, pred: F) -> Self {
+        Self {
+            hole: CompactHole::new(vec, range.start),
+            end: range.end,
+            pred,
+        }
+    }
+}
+
+impl<T, F> Iterator for ExtractIf<'_, T, F>
+where
+    F: FnMut(&T) -> bool,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let hole = &mut self.hole;
+        let base = hole.vec.as_mut_ptr();
+
+        while hole.read < self.end {
+            // Invariant: `vec[..write]` holds the kept elements, `vec[read..]` the unvisited
+            // ones, and the gap in between is logically uninitialized. If `pred` panics, the
+            // hole keeps the element it was called on.
+            let element = unsafe { &*base.add(hole.read) };
+            if (self.pred)(element) {
+                let removed = unsafe { ptr::read(element) };
+                hole.read += 1;
+                return Some(removed);
+            }
+
+            unsafe { ptr::copy(base.add(hole.read), base.add(hole.write), 1) };
+            hole.read += 1;
+            hole.write += 1;
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.end - self.hole.read))
+    }
+}
+
+impl<T, F> FusedIterator for ExtractIf<'_, T, F> where F: FnMut(&T) -> bool {}
+
+impl<T: fmt::Debug, F> fmt::Debug for ExtractIf<'_, T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unvisited = unsafe {
+            core::slice::from_raw_parts(
+                self.hole.vec.as_ptr().add(self.hole.read),
+                self.end - self.hole.read,
+            )
+        };
+        f.debug_tuple("ExtractIf").field(&unvisited).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        cmp::Ordering,
+        ops::Bound,
+        panic::{catch_unwind, AssertUnwindSafe},
+    };
+
+    use super::SortedVec;
+    use crate::{test_util::XorShift, DuplicatePolicy, SharBinarySearch};
+
+    /// Compares only by key, so order among equal keys is observable through the tag.
+    #[derive(Debug)]
+    struct Tagged(u32, char);
+
+    impl PartialEq for Tagged {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+
+    impl Eq for Tagged {}
+
+    impl PartialOrd for Tagged {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Tagged {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.0.cmp(&other.0)
+        }
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut vec = SortedVec::new();
+        assert_eq!(vec.insert(5), 0);
+        assert_eq!(vec.insert(1), 0);
+        assert_eq!(vec.insert(5), 2);
+        assert_eq!(vec.insert(3), 1);
+        assert_eq!(vec.as_slice(), &[1, 3, 5, 5]);
+        assert_eq!(vec.bl_binary_search(&5), Ok(2));
+        assert_eq!(vec.remove(&5), Some(5));
+        assert_eq!(vec.remove(&4), None);
+        assert_eq!(vec.range(2..), &[3, 5]);
+    }
+
+    #[test]
+    fn test_insert_many_overlapping() {
+        let mut vec = SortedVec::new();
+        vec.insert_many([10, 20, 30, 40]);
+        vec.insert_many([35, 5, 20, 25, 45, 10]);
+        assert_eq!(vec.as_slice(), &[5, 10, 10, 20, 20, 25, 30, 35, 40, 45]);
+
+        vec.insert_many([]);
+        assert_eq!(vec.len(), 10);
+    }
+
+    #[test]
+    fn test_insert_many_stable() {
+        let mut vec = SortedVec::new();
+        vec.insert_many([Tagged(1, 'a'), Tagged(2, 'b')]);
+        vec.insert_many([Tagged(2, 'c'), Tagged(1, 'd'), Tagged(2, 'e')]);
+
+        let tags: Vec<char> = vec.iter().map(|t| t.1).collect();
+        assert_eq!(tags, ['a', 'd', 'b', 'c', 'e']);
+    }
+
+    #[test]
+    fn test_from_unsorted_iter() {
+        let input = || {
+            [
+                Tagged(2, 'a'),
+                Tagged(1, 'b'),
+                Tagged(2, 'c'),
+                Tagged(3, 'd'),
+                Tagged(1, 'e'),
+                Tagged(2, 'f'),
+            ]
+        };
+        let tags = |vec: SortedVec<Tagged>| vec.iter().map(|t| t.1).collect::<String>();
+
+        let all = SortedVec::from_unsorted_iter(input(), DuplicatePolicy::KeepAll).unwrap();
+        assert!(all.iter().map(|t| t.0).eq([1, 1, 2, 2, 2, 3]));
+
+        let first = SortedVec::from_unsorted_iter(input(), DuplicatePolicy::KeepFirst).unwrap();
+        assert_eq!(tags(first), "bad");
+
+        let last = SortedVec::from_unsorted_iter(input(), DuplicatePolicy::KeepLast).unwrap();
+        assert_eq!(tags(last), "efd");
+
+        let Err(err) = SortedVec::from_unsorted_iter(input(), DuplicatePolicy::Error) else {
+            panic!("expected a duplicate error");
+        };
+        assert_eq!(err.key().0, 1);
+    }
+
+    #[test]
+    fn test_insert_many_all_duplicates() {
+        let mut vec = SortedVec::new();
+        vec.insert_many([7; 4]);
+        vec.insert_many([7; 3]);
+        assert_eq!(vec.as_slice(), &[7; 7]);
+    }
+
+    #[test]
+    fn test_drain_range() {
+        let mut vec = SortedVec::new();
+        vec.insert_many([1, 2, 2, 3, 5, 8, 13]);
+
+        let drained: Vec<_> = vec.drain_range(2..=5).collect();
+        assert_eq!(drained, [2, 2, 3, 5]);
+        assert_eq!(vec.as_slice(), &[1, 8, 13]);
+
+        // Empty ranges, both between elements and reversed.
+        assert_eq!(vec.drain_range(9..13).count(), 0);
+        assert_eq!(vec.drain_range(20..).count(), 0);
+        assert_eq!(vec.drain_range((Bound::Included(13), Bound::Excluded(8))).count(), 0);
+        assert_eq!(vec.as_slice(), &[1, 8, 13]);
+
+        assert!(vec.drain_range(..).eq([1, 8, 13]));
+        assert!(vec.is_empty());
+    }
+
+    #[test]
+    fn test_drain_range_partially_consumed() {
+        let mut vec = SortedVec::new();
+        vec.insert_many((0..10).map(|i| i.to_string()));
+
+        let mut drain = vec.drain_range::<str, _>((Bound::Included("2"), Bound::Excluded("6")));
+        assert_eq!(drain.next().as_deref(), Some("2"));
+        assert_eq!(drain.next_back().as_deref(), Some("5"));
+        drop(drain);
+        assert!(vec.iter().eq(["0", "1", "6", "7", "8", "9"]));
+
+        // Leaking the iterator must leave the vector in a valid state.
+        std::mem::forget(vec.drain_range::<str, _>((Bound::Included("7"), Bound::Unbounded)));
+        assert!(vec.len() <= 4);
+        assert!(vec.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn test_extract_if() {
+        let mut vec = SortedVec::new();
+        vec.insert_many((0..20).map(|i| i / 2));
+
+        // The predicate is only called within the range.
+        let mut seen = Vec::new();
+        let odd: Vec<_> = vec
+            .extract_if(3..=6, |x| {
+                seen.push(*x);
+                x % 2 == 1
+            })
+            .collect();
+        assert_eq!(seen, [3, 3, 4, 4, 5, 5, 6, 6]);
+        assert_eq!(odd, [3, 3, 5, 5]);
+        assert_eq!(
+            vec.as_slice(),
+            &[0, 0, 1, 1, 2, 2, 4, 4, 6, 6, 7, 7, 8, 8, 9, 9]
+        );
+
+        // Extracting nothing, within a range and from an empty one.
+        assert_eq!(vec.extract_if(.., |_| false).count(), 0);
+        assert_eq!(vec.extract_if(10.., |_| true).count(), 0);
+        assert_eq!(
+            vec.extract_if((Bound::Included(5), Bound::Excluded(3)), |_| true)
+                .count(),
+            0
+        );
+        assert_eq!(vec.len(), 16);
+
+        // Extracting everything.
+        assert!(vec
+            .extract_if(.., |_| true)
+            .eq([0, 0, 1, 1, 2, 2, 4, 4, 6, 6, 7, 7, 8, 8, 9, 9]));
+        assert!(vec.is_empty());
+    }
+
+    #[test]
+    fn test_extract_if_partially_consumed() {
+        let mut vec = SortedVec::new();
+        vec.insert_many((0..10).map(|i| i.to_string()));
+
+        // Dropping the iterator halfway keeps everything it has not reached.
+        let mut extract = vec.extract_if::<str, _, _>(.., |s| s != "1");
+        assert_eq!(extract.next().as_deref(), Some("0"));
+        assert_eq!(extract.next().as_deref(), Some("2"));
+        assert_eq!(extract.size_hint(), (0, Some(7)));
+        drop(extract);
+        assert!(vec.iter().eq(["1", "3", "4", "5", "6", "7", "8", "9"]));
+
+        // Leaking the iterator must leave the vector in a valid state, with at least the
+        // elements before the range.
+        let mut extract =
+            vec.extract_if::<str, _, _>((Bound::Included("5"), Bound::Unbounded), |_| true);
+        assert_eq!(extract.next().as_deref(), Some("5"));
+        std::mem::forget(extract);
+        assert!(vec.iter().eq(["1", "3", "4"]));
+    }
+
+    #[test]
+    fn test_extract_if_panicking_predicate() {
+        let mut vec = SortedVec::new();
+        vec.insert_many((0..10).map(|i| i.to_string()));
+
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            let extract = vec.extract_if::<str, _, _>(.., |s| match s.as_str() {
+                "5" => panic!("predicate failed"),
+                s => s < "3",
+            });
+            extract.count()
+        }));
+        assert!(result.is_err());
+        // Everything not yet extracted is kept, including the element the predicate panicked on.
+        assert!(vec.iter().eq(["3", "4", "5", "6", "7", "8", "9"]));
+    }
+
+    #[test]
+    fn test_dedup_with_counts() {
+        let mut rng = XorShift::new(1167);
+
+        for _ in 0..100 {
+            let values: Vec<u32> = (0..rng.below(100)).map(|_| rng.below(20) as u32).collect();
+            let vec = SortedVec::from_unsorted_iter(values, DuplicatePolicy::KeepAll).unwrap();
+
+            let expected: Vec<_> = vec
+                .chunk_by(|a, b| a == b)
+                .map(|run| (run[0], run.len() as u64))
+                .collect();
+            assert_eq!(vec.clone().dedup_with_counts(), expected);
+
+            let expected: Vec<_> = vec
+                .chunk_by(|a, b| a / 5 == b / 5)
+                .map(|run| (run[0], run.len() as u64))
+                .collect();
+            assert_eq!(vec.dedup_with_counts_by_key(|x| x / 5), expected);
+        }
+    }
+
+    #[test]
+    fn test_dedup_with_counts_keeps_first() {
+        let mut vec = SortedVec::new();
+        vec.insert_many([
+            Tagged(1, 'a'),
+            Tagged(2, 'b'),
+            Tagged(1, 'c'),
+            Tagged(2, 'd'),
+        ]);
+        vec.insert(Tagged(1, 'e'));
+
+        let counts: Vec<_> = vec
+            .dedup_with_counts()
+            .into_iter()
+            .map(|(Tagged(k, tag), count)| (k, tag, count))
+            .collect();
+        assert_eq!(counts, [(1, 'a', 3), (2, 'b', 2)]);
+        assert!(SortedVec::<u8>::new().dedup_with_counts().is_empty());
+    }
+
+    #[test]
+    fn test_remove_sorted_keys() {
+        let mut v = SortedVec::from_unsorted_iter(
+            ["pear", "apple", "fig", "fig", "kiwi"].map(String::from),
+            DuplicatePolicy::KeepAll,
+        )
+        .unwrap();
+
+        let keys = ["fig", "grape", "pear"].map(String::from);
+        assert_eq!(v.remove_sorted_keys(&keys), 3);
+        assert_eq!(v.as_slice(), ["apple", "kiwi"]);
+    }
+}
@@ -0,0 +1,412 @@
+//! Translating byte offsets in source text to lines and columns and back, as compilers,
+//! linters and language servers do for every diagnostic.
+//!
+//! A [`LineIndex`] holds the sorted byte offsets at which the lines of a text start. The line
+//! of an offset is the number of line starts at or before it, less one, so it is found with the
+//! branchless search. Columns count from the start of the line in the index's [`ColumnUnit`]:
+//! bytes, characters, or the UTF-16 code units that the Language Server Protocol uses by
+//! default.
+//!
+//! ```
+//! use shar_search::srcmap::{ColumnUnit, LineIndex};
+//!
+//! let source = "fn main() {\r\n    let é = \"😀\";\r\n}";
+//! let bytes = LineIndex::new(source, ColumnUnit::Bytes);
+//! let utf16 = LineIndex::new(source, ColumnUnit::Utf16);
+//!
+//! // The closing quote of the string.
+//! let quote = source.rfind('"').unwrap() as u32;
+//! assert_eq!(bytes.line_col(quote), (1, 18));
+//! assert_eq!(utf16.line_col(quote), (1, 15));
+//! assert_eq!(utf16.offset(1, 15), Some(quote));
+//! assert_eq!(bytes.line_range(2), 35..36);
+//! ```
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{gallop::gallop, SharBinarySearch};
+
+/// The unit that a [`LineIndex`] counts columns in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ColumnUnit {
+    /// Bytes of UTF-8.
+    Bytes,
+    /// Characters, that is, Unicode scalar values.
+    Chars,
+    /// UTF-16 code units, in which characters outside the Basic Multilingual Plane, such as
+    /// most emoji, take two columns.
+    Utf16,
+}
+
+impl ColumnUnit {
+    /// Returns the width of `text` in this unit.
+    fn width(self, text: &str) -> u32 {
+        match self {
+            ColumnUnit::Bytes => text.len() as u32,
+            ColumnUnit::Chars => text.chars().count() as u32,
+            ColumnUnit::Utf16 => text.chars().map(|c| c.len_utf16() as u32).sum(),
+        }
+    }
+}
+
+/// The line starts of a text, for translating between byte offsets and zero-based
+/// `(line, column)` positions. See the [`srcmap`](crate::srcmap) module.
+///
+/// Lines end after each `\n`, so a `\r\n` ends a line too, but a lone `\r` does not. A line
+/// terminator belongs to the line it ends: an offset on either byte of a `\r\n` is on that line,
+/// with the columns of the `\r` and the `\n` following on from the line's content. A text that
+/// ends with a line terminator has an empty last line after it, and an empty text has one empty
+/// line.
+///
+/// Columns in characters or UTF-16 code units are counted only between character boundaries,
+/// so an offset inside a multi-byte character has the column of that character's start.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineIndex<'a> {
+    text: &'a str,
+    /// The offset of the start of each line, starting with 0.
+    line_starts: Vec<u32>,
+    unit: ColumnUnit,
+}
+
+impl<'a> LineIndex<'a> {
+    /// Indexes the lines of `text`, with columns counted in `unit`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `text` is longer than `u32::MAX` bytes.
+    pub fn new(text: &'a str, unit: ColumnUnit) -> Self {
+        assert!(
+            u32::try_from(text.len()).is_ok(),
+            "the text must be at most u32::MAX bytes long"
+        );
+
+        let mut line_starts = Vec::with_capacity(text.len() / 32 + 1);
+        line_starts.push(0);
+        line_starts.extend(
+            text.bytes()
+                .enumerate()
+                .filter(|&(_, byte)| byte == b'\n')
+                .map(|(i, _)| i as u32 + 1),
+        );
+
+        Self {
+            text,
+            line_starts,
+            unit,
+        }
+    }
+
+    /// Returns the indexed text.
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Returns the unit that columns are counted in.
+    pub fn unit(&self) -> ColumnUnit {
+        self.unit
+    }
+
+    /// Returns the byte offset of the start of each line, in increasing order.
+    pub fn line_starts(&self) -> &[u32] {
+        &self.line_starts
+    }
+
+    /// Returns the number of lines, which is at least 1.
+    pub fn line_count(&self) -> u32 {
+        self.line_starts.len() as u32
+    }
+
+    /// Returns the byte range of `line`, including its line terminator, so that the ranges of
+    /// all the lines tile the text.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line` is not less than [`line_count`](LineIndex::line_count).
+    pub fn line_range(&self, line: u32) -> Range<u32> {
+        let line = line as usize;
+        assert!(
+            line < self.line_starts.len(),
+            "line is {line} but the text has {} lines",
+            self.line_starts.len()
+        );
+
+        let end = match self.line_starts.get(line + 1) {
+            Some(&next) => next,
+            None => self.text.len() as u32,
+        };
+        self.line_starts[line]..end
+    }
+
+    /// Returns the zero-based line and column of `byte_offset`. Offsets past the end of the
+    /// text are taken to be at its end.
+    pub fn line_col(&self, byte_offset: u32) -> (u32, u32) {
+        let offset = self.clamp(byte_offset);
+        let line = self
+            .line_starts
+            .bl_partition_point(|&start| start <= offset)
+            - 1;
+        let start = self.line_starts[line];
+        (line as u32, self.width(start, offset))
+    }
+
+    /// Returns the zero-based line and column of each of `byte_offsets`, in the same order. See
+    /// [`LineIndex::line_col`].
+    ///
+    /// If the offsets are sorted, as diagnostics usually are, each line is found by galloping
+    /// forward from the previous one, and each column is counted on from the previous one when
+    /// both are on the same line. Translating `q` sorted offsets into `n` lines then costs
+    /// `O(q log(n / q))` comparisons, plus a single pass over the text that they cover when
+    /// counting characters or UTF-16 code units.
+    ///
+    /// ```
+    /// use shar_search::srcmap::{ColumnUnit, LineIndex};
+    ///
+    /// let index = LineIndex::new("ab\ncd\nef", ColumnUnit::Bytes);
+    /// assert_eq!(index.line_cols(&[1, 3, 4, 8]), [(0, 1), (1, 0), (1, 1), (2, 2)]);
+    /// ```
+    pub fn line_cols(&self, byte_offsets: &[u32]) -> Vec<(u32, u32)> {
+        if !byte_offsets.is_sorted() {
+            return byte_offsets
+                .iter()
+                .map(|&offset| self.line_col(offset))
+                .collect();
+        }
+
+        // The line of the previous offset, and the offset and column it was counted up to.
+        let (mut line, mut counted, mut col) = (0, 0, 0);
+        byte_offsets
+            .iter()
+            .map(|&offset| {
+                let offset = self.clamp(offset);
+                let next = line + 1;
+                let later = gallop(&self.line_starts[next..], |&start| start <= offset);
+                if later > 0 {
+                    line = next + later - 1;
+                    (counted, col) = (self.line_starts[line], 0);
+                }
+
+                if self.unit == ColumnUnit::Bytes {
+                    col = offset - self.line_starts[line];
+                } else {
+                    col += self.width(counted, offset);
+                    counted = self.floor_char_boundary(offset);
+                }
+                (line as u32, col)
+            })
+            .collect()
+    }
+
+    /// Returns the byte offset of the zero-based `line` and `col`, or `None` if the line does
+    /// not exist or has no such column. The columns of a line run up to its end, past its line
+    /// terminator if it has one, and the last line also has a column at the end of the text.
+    /// A UTF-16 column in the middle of a character that takes two code units has no offset.
+    pub fn offset(&self, line: u32, col: u32) -> Option<u32> {
+        if line >= self.line_count() {
+            return None;
+        }
+        let range = self.line_range(line);
+        let text = &self.text[range.start as usize..range.end as usize];
+
+        let within = match self.unit {
+            ColumnUnit::Bytes => (col as usize <= text.len()).then_some(col as usize)?,
+            ColumnUnit::Chars => text
+                .char_indices()
+                .map(|(i, _)| i)
+                .chain([text.len()])
+                .nth(col as usize)?,
+            ColumnUnit::Utf16 => {
+                let (mut units, mut within) = (0, text.len());
+                for (i, c) in text.char_indices() {
+                    if units >= col {
+                        within = i;
+                        break;
+                    }
+                    units += c.len_utf16() as u32;
+                }
+                (units == col).then_some(within)?
+            }
+        };
+
+        // Only the last line has a column at its end: the end of any other line is the start
+        // of the next.
+        let offset = range.start + within as u32;
+        (offset < range.end || line + 1 == self.line_count()).then_some(offset)
+    }
+
+    fn clamp(&self, byte_offset: u32) -> u32 {
+        byte_offset.min(self.text.len() as u32)
+    }
+
+    /// Returns the greatest character boundary at or before `offset`.
+    fn floor_char_boundary(&self, offset: u32) -> u32 {
+        let mut offset = offset as usize;
+        while !self.text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        offset as u32
+    }
+
+    /// Returns the width of the text from the character boundary `start` up to `end`.
+    fn width(&self, start: u32, end: u32) -> u32 {
+        match self.unit {
+            ColumnUnit::Bytes => end - start,
+            unit => {
+                let end = self.floor_char_boundary(end);
+                unit.width(&self.text[start as usize..end as usize])
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ColumnUnit, LineIndex};
+    use crate::reference::{self, assert_matches_reference, inputs};
+
+    const UNITS: [ColumnUnit; 3] = [ColumnUnit::Bytes, ColumnUnit::Chars, ColumnUnit::Utf16];
+
+    #[test]
+    fn test_line_endings() {
+        for text in ["ab\ncd\nef", "ab\r\ncd\r\nef", "ab\ncd\r\nef\n", "ab\rcd"] {
+            let index = LineIndex::new(text, ColumnUnit::Bytes);
+            let ranges: Vec<_> = (0..index.line_count())
+                .map(|line| index.line_range(line))
+                .collect();
+            // The lines tile the text.
+            assert_eq!(ranges[0].start, 0);
+            assert_eq!(ranges.last().unwrap().end, text.len() as u32);
+            assert!(ranges.windows(2).all(|pair| pair[0].end == pair[1].start));
+        }
+
+        let index = LineIndex::new("ab\ncd\r\nef", ColumnUnit::Bytes);
+        assert_eq!(index.line_starts(), [0, 3, 7]);
+        assert_eq!(index.line_range(1), 3..7);
+        // Both bytes of the `\r\n` are on the line they end.
+        assert_eq!(index.line_col(5), (1, 2));
+        assert_eq!(index.line_col(6), (1, 3));
+        assert_eq!(index.line_col(7), (2, 0));
+        assert_eq!(index.offset(1, 3), Some(6));
+        assert_eq!(index.offset(1, 4), None);
+
+        // A trailing newline starts an empty last line, and a lone `\r` ends no line.
+        assert_eq!(
+            LineIndex::new("ab\n", ColumnUnit::Bytes).line_starts(),
+            [0, 3]
+        );
+        assert_eq!(LineIndex::new("a\rb", ColumnUnit::Bytes).line_count(), 1);
+    }
+
+    #[test]
+    fn test_empty_text() {
+        for unit in UNITS {
+            let index = LineIndex::new("", unit);
+            assert_eq!(index.line_count(), 1);
+            assert_eq!(index.line_range(0), 0..0);
+            assert_eq!(index.line_col(0), (0, 0));
+            assert_eq!(index.line_col(10), (0, 0));
+            assert_eq!(index.line_cols(&[0, 5]), [(0, 0), (0, 0)]);
+            assert_eq!(index.offset(0, 0), Some(0));
+            assert_eq!(index.offset(0, 1), None);
+            assert_eq!(index.offset(1, 0), None);
+        }
+    }
+
+    #[test]
+    fn test_past_the_end() {
+        let index = LineIndex::new("ab\ncd", ColumnUnit::Chars);
+        assert_eq!(index.line_col(5), (1, 2));
+        assert_eq!(index.line_col(6), (1, 2));
+        assert_eq!(index.line_col(u32::MAX), (1, 2));
+        assert_eq!(index.line_cols(&[4, u32::MAX]), [(1, 1), (1, 2)]);
+        assert_eq!(index.offset(1, 2), Some(5));
+        assert_eq!(index.offset(1, 3), None);
+        // The end of a line other than the last is the start of the next.
+        assert_eq!(index.offset(0, 3), None);
+        assert_eq!(index.offset(2, 0), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "line is 2 but the text has 2 lines")]
+    fn test_line_range_out_of_range() {
+        LineIndex::new("a\nb", ColumnUnit::Bytes).line_range(2);
+    }
+
+    #[test]
+    fn test_column_units() {
+        // 'é' is 2 bytes and 1 UTF-16 unit, '😀' is 4 bytes and 2 UTF-16 units.
+        let text = "x\né😀y";
+        let columns = |unit| {
+            let index = LineIndex::new(text, unit);
+            (2..=9)
+                .map(|offset| index.line_col(offset).1)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(columns(ColumnUnit::Bytes), [0, 1, 2, 3, 4, 5, 6, 7]);
+        // Offsets inside a character have the column of its start.
+        assert_eq!(columns(ColumnUnit::Chars), [0, 0, 1, 1, 1, 1, 2, 3]);
+        assert_eq!(columns(ColumnUnit::Utf16), [0, 0, 1, 1, 1, 1, 3, 4]);
+
+        let utf16 = LineIndex::new(text, ColumnUnit::Utf16);
+        assert_eq!(utf16.offset(1, 1), Some(4));
+        // The middle of the surrogate pair of '😀'.
+        assert_eq!(utf16.offset(1, 2), None);
+        assert_eq!(utf16.offset(1, 3), Some(8));
+        assert_eq!(utf16.offset(1, 4), Some(9));
+        assert_eq!(utf16.offset(1, 5), None);
+
+        let chars = LineIndex::new(text, ColumnUnit::Chars);
+        assert_eq!(chars.offset(1, 2), Some(8));
+        assert_eq!(chars.offset(1, 4), None);
+    }
+
+    #[test]
+    fn test_against_reference() {
+        let cases = inputs::texts(67).flat_map(|text| UNITS.map(|unit| (text.clone(), unit)));
+
+        // Every offset up to a few past the end: in order, and every seventh to skip lines, which
+        // take the incremental path, and in reverse, which does not.
+        let offset_lists = |text: &str| {
+            let offsets: Vec<u32> = (0..=text.len() as u32 + 2).collect();
+            let strided: Vec<u32> = offsets.iter().step_by(7).copied().collect();
+            let reversed: Vec<u32> = offsets.iter().rev().copied().collect();
+            [offsets, strided, reversed]
+        };
+
+        assert_matches_reference!(
+            for (text, unit) in cases =>
+            {
+                let index = LineIndex::new(&text, unit);
+                offset_lists(&text).map(|offsets| index.line_cols(&offsets))
+            },
+            offset_lists(&text).map(|offsets| {
+                offsets
+                    .iter()
+                    .map(|&offset| reference::line_col(&text, unit, offset))
+                    .collect::<Vec<_>>()
+            }),
+        );
+    }
+
+    #[test]
+    fn test_offsets_round_trip() {
+        for text in inputs::texts(68) {
+            let len = text.len() as u32;
+            for unit in UNITS {
+                let index = LineIndex::new(&text, unit);
+
+                // Every character boundary round-trips, and every position maps back to the
+                // start of its character.
+                for offset in 0..=len {
+                    let (line, col) = index.line_col(offset);
+                    let start = index.floor_char_boundary(offset);
+                    let expected = match unit {
+                        ColumnUnit::Bytes => offset,
+                        _ => start,
+                    };
+                    assert_eq!(index.offset(line, col), Some(expected), "{text:?} {offset}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,229 @@
+//! A two-level sorted set for write-heavy workloads.
+
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+
+use crate::{
+    merge::{merge_iter, Merge},
+    raw, SharBinarySearch,
+};
+
+/// The smallest staging buffer limit used by the default `√n` policy, so that small sets do
+/// not merge on nearly every insertion.
+const MIN_STAGING_LIMIT: usize = 16;
+
+/// A sorted set split into a large main run and a small sorted staging buffer.
+///
+/// New elements go into the staging buffer, which is merged into the main run once it grows
+/// past a limit (by default `√n`, where `n` is the length of the main run). Lookups do one
+/// branchless search in each level.
+///
+/// With the default limit, an insertion costs `O(√n)` amortized: `O(√n)` to shift elements
+/// within the staging buffer, plus an `O(n)` merge every `√n` insertions. Lookups are
+/// `O(log n)`. Removing an element that is still staged is `O(√n)`, while removing one from the
+/// main run is `O(n)`.
+///
+/// The two levels never hold equal elements, so the set contains each element at most once.
+#[derive(Clone)]
+pub struct StagedSortedVec<T> {
+    main: Vec<T>,
+    staging: Vec<T>,
+    staging_limit: Option<usize>,
+}
+
+impl<T> Default for StagedSortedVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> StagedSortedVec<T> {
+    /// Creates a new, empty set that merges its staging buffer once it holds more than `√n`
+    /// elements.
+    pub const fn new() -> Self {
+        Self {
+            main: Vec::new(),
+            staging: Vec::new(),
+            staging_limit: None,
+        }
+    }
+
+    /// Creates a new, empty set that merges its staging buffer once it holds more than `limit`
+    /// elements. A limit of 0 merges on every insertion.
+    pub const fn with_staging_limit(limit: usize) -> Self {
+        Self {
+            main: Vec::new(),
+            staging: Vec::new(),
+            staging_limit: Some(limit),
+        }
+    }
+
+    /// Returns the number of elements in the set.
+    pub fn len(&self) -> usize {
+        self.main.len() + self.staging.len()
+    }
+
+    /// Returns whether the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.main.is_empty() && self.staging.is_empty()
+    }
+
+    /// Returns the number of elements currently in the staging buffer.
+    pub fn staged_len(&self) -> usize {
+        self.staging.len()
+    }
+
+    fn staging_limit(&self) -> usize {
+        self.staging_limit
+            .unwrap_or_else(|| self.main.len().isqrt().max(MIN_STAGING_LIMIT))
+    }
+}
+
+impl<T: Ord> StagedSortedVec<T> {
+    /// Returns whether the set contains `x`.
+    pub fn contains<Q>(&self, x: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(x).is_some()
+    }
+
+    /// Returns a reference to the element equal to `x`, if any.
+    pub fn get<Q>(&self, x: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        if let Ok(index) = self.staging.bl_binary_search_by(|p| p.borrow().cmp(x)) {
+            return Some(&self.staging[index]);
+        }
+
+        self.main
+            .bl_binary_search_by(|p| p.borrow().cmp(x))
+            .ok()
+            .map(|index| &self.main[index])
+    }
+
+    /// Adds `value` to the set. Returns whether it was newly inserted; if an equal element was
+    /// already present in either level, the set is left unchanged.
+    pub fn insert(&mut self, value: T) -> bool {
+        if self.main.bl_binary_search(&value).is_ok() {
+            return false;
+        }
+
+        match self.staging.bl_binary_search(&value) {
+            Ok(_) => false,
+            Err(index) => {
+                self.staging.insert(index, value);
+                if self.staging.len() > self.staging_limit() {
+                    self.compact();
+                }
+                true
+            }
+        }
+    }
+
+    /// Removes the element equal to `x` from the set, returning it if it was present.
+    pub fn take<Q>(&mut self, x: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let take_from = |level: &mut Vec<T>| {
+            level
+                .bl_binary_search_by(|p| p.borrow().cmp(x))
+                .ok()
+                .map(|index| level.remove(index))
+        };
+
+        take_from(&mut self.staging).or_else(|| take_from(&mut self.main))
+    }
+
+    /// Removes the element equal to `x` from the set. Returns whether it was present.
+    pub fn remove<Q>(&mut self, x: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.take(x).is_some()
+    }
+
+    /// Merges the staging buffer into the main run. This is `O(n + s)` for a main run of
+    /// length `n` and a staging buffer of length `s`.
+    pub fn compact(&mut self) {
+        let staged = core::mem::take(&mut self.staging);
+        raw::merge_sorted_batch(&mut self.main, staged, |a, b| a < b);
+    }
+
+    /// Returns an iterator over the elements of both levels in ascending order. The levels are
+    /// merged lazily as the iterator advances.
+    pub fn iter(&self) -> Merge<'_, T, impl FnMut(&T, &T) -> core::cmp::Ordering> {
+        merge_iter(&self.main, &self.staging)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use super::StagedSortedVec;
+    use crate::test_util::XorShift;
+
+    #[test]
+    fn test_levels() {
+        let mut set = StagedSortedVec::with_staging_limit(3);
+        for value in [5, 1, 9] {
+            assert!(set.insert(value));
+        }
+        assert_eq!(set.staged_len(), 3);
+
+        // Exceeding the limit merges everything into the main run.
+        assert!(set.insert(7));
+        assert_eq!(set.staged_len(), 0);
+
+        // Duplicates are rejected whichever level holds the existing element.
+        assert!(!set.insert(7));
+        assert!(set.insert(3));
+        assert!(!set.insert(3));
+        assert_eq!(set.len(), 5);
+
+        // Removing from the staging buffer and from the main run.
+        assert!(set.remove(&3));
+        assert!(!set.contains(&3));
+        assert!(set.remove(&9));
+        assert!(!set.remove(&9));
+        assert!(set.iter().copied().eq([1, 5, 7]));
+
+        set.compact();
+        assert_eq!(set.get(&5), Some(&5));
+        assert!(set.iter().copied().eq([1, 5, 7]));
+    }
+
+    #[test]
+    fn test_against_btreeset() {
+        for (seed, limit) in [(1, None), (2, Some(0)), (3, Some(1)), (4, Some(8))] {
+            let mut rng = XorShift::new(seed);
+            let mut set = match limit {
+                Some(limit) => StagedSortedVec::with_staging_limit(limit),
+                None => StagedSortedVec::new(),
+            };
+            let mut model = BTreeSet::new();
+
+            for _ in 0..4000 {
+                let value = rng.below(500);
+                match rng.below(10) {
+                    0..=4 => assert_eq!(set.insert(value), model.insert(value)),
+                    5..=6 => assert_eq!(set.remove(&value), model.remove(&value)),
+                    7 => set.compact(),
+                    8 => assert!(set.iter().eq(model.iter())),
+                    _ => assert_eq!(set.contains(&value), model.contains(&value)),
+                }
+
+                assert_eq!(set.len(), model.len());
+            }
+
+            assert!(set.iter().eq(model.iter()));
+        }
+    }
+}
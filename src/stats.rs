@@ -0,0 +1,169 @@
+//! Ranks of query points in a sorted sample, and the empirical CDF built on them.
+//!
+//! The rank of a query is the number of sample elements below it, where "below" is either
+//! strictly less than (`inclusive == false`) or less than or equal to (`inclusive == true`). The
+//! two differ exactly by the number of ties, so with real data, which is full of ties, the
+//! choice matters: the empirical CDF `F(x) = P(X <= x)` uses inclusive ranks.
+//!
+//! If the queries are sorted, each rank is at least the previous one, so the searches gallop
+//! forward from one to the next. Otherwise, they are interleaved.
+//!
+//! ```
+//! use shar_search::stats::{ecdf, ranks};
+//!
+//! let sample = [1, 2, 2, 2, 5];
+//! assert_eq!(ranks(&sample, &[2, 0, 9], false), [1, 0, 5]);
+//! assert_eq!(ranks(&sample, &[2, 0, 9], true), [4, 0, 5]);
+//! assert_eq!(ecdf(&sample, &[2, 4]), [0.8, 0.8]);
+//! ```
+
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+use crate::batch::{SharBatchSearch, DEFAULT_INTERLEAVE};
+
+/// Returns the rank of each query in `sample` under `compare`.
+fn ranks_by<T, F>(sample: &[T], queries: &[T], inclusive: bool, compare: F) -> Vec<usize>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    // Never `Equal`, so every result is the `Err` of the first element not counted.
+    let counted = |p: &T, q: &T| {
+        let below = match compare(p, q) {
+            Ordering::Less => true,
+            Ordering::Equal => inclusive,
+            Ordering::Greater => false,
+        };
+        if below {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    };
+
+    let results = if queries.is_sorted_by(|a, b| compare(a, b).is_le()) {
+        sample.bl_binary_search_sorted_keys_by(queries, counted)
+    } else {
+        sample.bl_binary_search_batch_by::<DEFAULT_INTERLEAVE, _, _>(queries, counted)
+    };
+
+    results
+        .into_iter()
+        .map(|result| result.unwrap_or_else(|rank| rank))
+        .collect()
+}
+
+/// Divides each rank by the size of the sample.
+fn to_ecdf(ranks: Vec<usize>, n: usize) -> Vec<f64> {
+    ranks
+        .into_iter()
+        .map(|rank| rank as f64 / n as f64)
+        .collect()
+}
+
+/// Returns the rank of each of `queries` in `sample`: the number of elements less than it, or
+/// less than or equal to it if `inclusive`. Note it is assumed that `sample` is sorted.
+///
+/// The ranks are returned in the same order as `queries`.
+pub fn ranks<T: Ord>(sample: &[T], queries: &[T], inclusive: bool) -> Vec<usize> {
+    ranks_by(sample, queries, inclusive, T::cmp)
+}
+
+/// Returns the rank of each of `queries` in `sample`, ordering the floats by
+/// [`f64::total_cmp`]. Note it is assumed that `sample` is sorted that way, e.g. with
+/// `sort_by(f64::total_cmp)`. See [`ranks`].
+///
+/// Under the total order, `-0.0` is less than `0.0` and NaNs sort after every number (or, for
+/// negative NaNs, before).
+pub fn ranks_f64(sample: &[f64], queries: &[f64], inclusive: bool) -> Vec<usize> {
+    ranks_by(sample, queries, inclusive, f64::total_cmp)
+}
+
+/// Returns the empirical CDF of `sample` at each of `queries`: the fraction of elements less
+/// than or equal to it. Note it is assumed that `sample` is sorted.
+///
+/// If `sample` is empty, every value is NaN.
+pub fn ecdf<T: Ord>(sample: &[T], queries: &[T]) -> Vec<f64> {
+    to_ecdf(ranks(sample, queries, true), sample.len())
+}
+
+/// Returns the empirical CDF of `sample` at each of `queries`, ordering the floats by
+/// [`f64::total_cmp`]. See [`ecdf`] and [`ranks_f64`].
+pub fn ecdf_f64(sample: &[f64], queries: &[f64]) -> Vec<f64> {
+    to_ecdf(ranks_f64(sample, queries, true), sample.len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reference::{self, assert_matches_reference, inputs};
+
+    #[test]
+    fn test_against_reference() {
+        // Few distinct values, so nearly every query ties with some of the sample. The queries
+        // come sorted, which takes the sorted-keys search, and reversed, which takes the batch.
+        let cases = inputs::slice_pairs(152).flat_map(|(sample, queries)| {
+            let reversed: Vec<u32> = queries.iter().rev().copied().collect();
+            [false, true].into_iter().flat_map(move |inclusive| {
+                [
+                    (sample.clone(), queries.clone(), inclusive),
+                    (sample.clone(), reversed.clone(), inclusive),
+                ]
+            })
+        });
+
+        assert_matches_reference!(
+            for (sample, queries, inclusive) in cases =>
+            ranks(&sample, &queries, inclusive),
+            reference::ranks_by(&sample, &queries, inclusive, u32::cmp),
+        );
+    }
+
+    #[test]
+    fn test_f64_total_order() {
+        let mut sample = [
+            1.5,
+            -0.0,
+            0.0,
+            f64::NAN,
+            -1.0,
+            0.0,
+            f64::INFINITY,
+            1.5,
+            f64::NEG_INFINITY,
+        ];
+        sample.sort_by(f64::total_cmp);
+        assert_eq!(ranks_f64(&sample, &[0.0], false), [3]);
+        assert_eq!(ranks_f64(&sample, &[0.0], true), [5]);
+        assert_eq!(ecdf_f64(&sample, &[f64::NAN]), [1.0]);
+
+        // Widening keeps the total order, NaNs and signed zeros included.
+        let cases = inputs::f32_slice_pairs(153).flat_map(|(sample, queries)| {
+            let sample: Vec<f64> = sample.into_iter().map(f64::from).collect();
+            let queries: Vec<f64> = queries.into_iter().map(f64::from).collect();
+            let reversed: Vec<f64> = queries.iter().rev().copied().collect();
+            [false, true].into_iter().flat_map(move |inclusive| {
+                [
+                    (sample.clone(), queries.clone(), inclusive),
+                    (sample.clone(), reversed.clone(), inclusive),
+                ]
+            })
+        });
+        assert_matches_reference!(
+            for (sample, queries, inclusive) in cases =>
+            ranks_f64(&sample, &queries, inclusive),
+            reference::ranks_by(&sample, &queries, inclusive, f64::total_cmp),
+        );
+    }
+
+    #[test]
+    fn test_ecdf() {
+        let sample = [10, 20, 20, 30];
+        assert_eq!(
+            ecdf(&sample, &[5, 10, 20, 25, 30, 35]),
+            [0.0, 0.25, 0.75, 0.75, 1.0, 1.0]
+        );
+        assert_eq!(ecdf(&sample, &[]), Vec::<f64>::new());
+        assert!(ecdf(&[], &[1]).iter().all(|p| p.is_nan()));
+    }
+}
@@ -0,0 +1,342 @@
+//! Piecewise-constant functions of sorted breakpoints, such as tax brackets and pricing tiers.
+//!
+//! A [`StepFunction`] has an initial value for the keys below its first breakpoint, and each
+//! breakpoint's value holds from that breakpoint up to the next. A key exactly on a breakpoint
+//! takes that breakpoint's value, so each segment includes its left end.
+//!
+//! ```
+//! use shar_search::step::StepFunction;
+//!
+//! // Marginal tax rates in percent, from each income onward.
+//! let rates = StepFunction::new(
+//!     0,
+//!     vec![(11_000, 10), (44_725, 12), (95_375, 22), (182_100, 24)],
+//! )
+//! .unwrap();
+//!
+//! assert_eq!(*rates.eval(&5_000), 0);
+//! assert_eq!(*rates.eval(&11_000), 10);
+//! assert_eq!(*rates.eval(&50_000), 12);
+//! assert_eq!(*rates.eval(&1_000_000), 24);
+//! ```
+
+use alloc::vec::Vec;
+use core::{borrow::Borrow, ops::Bound};
+
+use crate::{
+    batch::DEFAULT_INTERLEAVE, duplicates::sort_with_policy, router::BoundaryError, validate,
+    DuplicateError, DuplicatePolicy, SharBatchSearch, SharBinarySearch,
+};
+
+/// A piecewise-constant function, defined by an initial value and strictly increasing
+/// breakpoints that each set the value from that key onward. See the [`step`](crate::step)
+/// module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StepFunction<K, V> {
+    initial: V,
+    breakpoints: Vec<(K, V)>,
+}
+
+impl<K, V> StepFunction<K, V> {
+    /// Creates the constant function with value `value`, which has no breakpoints.
+    pub fn constant(value: V) -> Self {
+        Self {
+            initial: value,
+            breakpoints: Vec::new(),
+        }
+    }
+
+    /// Returns the value of the keys below the first breakpoint, or of every key if there are
+    /// no breakpoints.
+    pub fn initial(&self) -> &V {
+        &self.initial
+    }
+
+    /// Returns the breakpoints and their values, in increasing order of key.
+    pub fn breakpoints(&self) -> &[(K, V)] {
+        &self.breakpoints
+    }
+
+    /// Returns each segment of the function in increasing order, as `(start, end, value)`.
+    /// There is one segment per breakpoint, after the initial segment below the first.
+    ///
+    /// ```
+    /// use std::ops::Bound::{Excluded, Included, Unbounded};
+    ///
+    /// use shar_search::step::StepFunction;
+    ///
+    /// let tiers = StepFunction::new("free", vec![(10, "basic"), (100, "pro")]).unwrap();
+    /// let segments: Vec<_> = tiers.ranges().collect();
+    /// assert_eq!(
+    ///     segments,
+    ///     [
+    ///         (Unbounded, Excluded(&10), &"free"),
+    ///         (Included(&10), Excluded(&100), &"basic"),
+    ///         (Included(&100), Unbounded, &"pro"),
+    ///     ]
+    /// );
+    /// ```
+    pub fn ranges(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (Bound<&K>, Bound<&K>, &V)> + ExactSizeIterator + '_ {
+        let breakpoints = &self.breakpoints;
+        (0..breakpoints.len() + 1).map(move |i| {
+            let end = match breakpoints.get(i) {
+                Some((key, _)) => Bound::Excluded(key),
+                None => Bound::Unbounded,
+            };
+            match i.checked_sub(1) {
+                Some(previous) => {
+                    let (key, value) = &breakpoints[previous];
+                    (Bound::Included(key), end, value)
+                }
+                None => (Bound::Unbounded, end, &self.initial),
+            }
+        })
+    }
+
+    /// Returns the value of the segment before `index`, where `index` is the number of
+    /// breakpoints at most a key.
+    fn value_before(&self, index: usize) -> &V {
+        match index.checked_sub(1) {
+            Some(previous) => &self.breakpoints[previous].1,
+            None => &self.initial,
+        }
+    }
+}
+
+impl<K: Ord, V> StepFunction<K, V> {
+    /// Creates a step function with value `initial` below the first of `breakpoints`, each of
+    /// which sets the value from its key onward.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BoundaryError`] with the index of the first breakpoint whose key is not greater
+    /// than its predecessor's, if any.
+    pub fn new(initial: V, breakpoints: Vec<(K, V)>) -> Result<Self, BoundaryError> {
+        match validate::first_unsorted_by(&breakpoints, |(a, _), (b, _)| a < b) {
+            Some(index) => Err(BoundaryError { index }),
+            None => Ok(Self {
+                initial,
+                breakpoints,
+            }),
+        }
+    }
+
+    /// Creates a step function from breakpoints in any order, resolving breakpoints with the
+    /// same key according to `policy`. [`DuplicatePolicy::KeepAll`] behaves like
+    /// [`DuplicatePolicy::KeepFirst`], as a key can only have one value.
+    ///
+    /// # Errors
+    ///
+    /// With [`DuplicatePolicy::Error`], returns the smallest key that appears more than once.
+    ///
+    /// ```
+    /// use shar_search::{step::StepFunction, DuplicatePolicy};
+    ///
+    /// let thresholds = [(30, "warn"), (10, "debug"), (40, "error"), (20, "info")];
+    /// let level = StepFunction::from_unsorted_iter("trace", thresholds, DuplicatePolicy::Error)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(*level.eval(&5), "trace");
+    /// assert_eq!(*level.eval(&20), "info");
+    /// assert_eq!(*level.eval(&39), "warn");
+    /// assert_eq!(*level.eval(&50), "error");
+    /// ```
+    pub fn from_unsorted_iter<I: IntoIterator<Item = (K, V)>>(
+        initial: V,
+        breakpoints: I,
+        policy: DuplicatePolicy,
+    ) -> Result<Self, DuplicateError<K>> {
+        let mut breakpoints: Vec<(K, V)> = breakpoints.into_iter().collect();
+        sort_with_policy(&mut breakpoints, policy, false, |(k, _)| k)
+            .map_err(|(key, _)| DuplicateError { key })?;
+        Ok(Self {
+            initial,
+            breakpoints,
+        })
+    }
+
+    /// Returns the value at `key`: that of the last breakpoint at most `key`, or the initial
+    /// value if there is none.
+    pub fn eval<Q>(&self, key: &Q) -> &V
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.value_before(self.partition(key))
+    }
+
+    /// Returns the value at each of `keys`, in the same order, interleaving the searches as
+    /// [`bl_binary_search_batch`](SharBatchSearch::bl_binary_search_batch) does. The keys can be
+    /// in any order.
+    ///
+    /// ```
+    /// use shar_search::step::StepFunction;
+    ///
+    /// let shipping = StepFunction::new(5.0, vec![(50, 2.5), (100, 0.0)]).unwrap();
+    /// assert_eq!(shipping.eval_many(&[120, 20, 50]), [&0.0, &5.0, &2.5]);
+    /// ```
+    pub fn eval_many(&self, keys: &[K]) -> Vec<&V> {
+        self.breakpoints
+            .bl_binary_search_batch_by::<DEFAULT_INTERLEAVE, _, _>(keys, |(k, _), key| k.cmp(key))
+            .into_iter()
+            // The breakpoints are distinct, so a match is the only breakpoint equal to the key.
+            .map(|result| self.value_before(result.map_or_else(|index| index, |index| index + 1)))
+            .collect()
+    }
+
+    /// Returns the breakpoint whose segment contains `key`, or `None` if `key` is below the
+    /// first breakpoint.
+    ///
+    /// ```
+    /// use shar_search::step::StepFunction;
+    ///
+    /// let tiers = StepFunction::new("free", vec![(10, "basic"), (100, "pro")]).unwrap();
+    /// assert_eq!(tiers.breakpoint_for(&9), None);
+    /// assert_eq!(tiers.breakpoint_for(&10), Some((&10, &"basic")));
+    /// assert_eq!(tiers.breakpoint_for(&99), Some((&10, &"basic")));
+    /// ```
+    pub fn breakpoint_for<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let previous = self.partition(key).checked_sub(1)?;
+        let (k, v) = &self.breakpoints[previous];
+        Some((k, v))
+    }
+
+    /// Returns the number of breakpoints at most `key`.
+    fn partition<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.breakpoints
+            .bl_partition_point(|(k, _)| k.borrow() <= key)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::ops::{Bound, RangeBounds};
+
+    use super::StepFunction;
+    use crate::{
+        reference::{assert_matches_reference, inputs},
+        test_util::XorShift,
+        DuplicatePolicy,
+    };
+
+    /// Evaluates the step function given by `initial` and `breakpoints` with a linear scan.
+    fn linear_eval<'a>(initial: &'a usize, breakpoints: &'a [(u32, usize)], key: u32) -> &'a usize {
+        breakpoints
+            .iter()
+            .take_while(|(k, _)| *k <= key)
+            .last()
+            .map_or(initial, |(_, v)| v)
+    }
+
+    /// Returns strictly increasing breakpoints from a sorted slice, valued by their position.
+    fn breakpoints_of(keys: &[u32]) -> Vec<(u32, usize)> {
+        let mut keys = keys.to_vec();
+        keys.dedup();
+        keys.into_iter().zip(1..).collect()
+    }
+
+    #[test]
+    fn test_keys_on_breakpoints() {
+        let f = StepFunction::new('a', vec![(10, 'b'), (20, 'c')]).unwrap();
+        assert_eq!(*f.eval(&9), 'a');
+        assert_eq!(*f.eval(&10), 'b');
+        assert_eq!(*f.eval(&19), 'b');
+        assert_eq!(*f.eval(&20), 'c');
+        assert_eq!(*f.eval(&i32::MAX), 'c');
+        assert_eq!(f.breakpoint_for(&20), Some((&20, &'c')));
+        assert_eq!(f.eval_many(&[20, 10, 9, 21]), [&'c', &'b', &'a', &'c']);
+    }
+
+    #[test]
+    fn test_constant() {
+        let f = StepFunction::<i32, _>::constant("only");
+        assert_eq!(*f.eval(&i32::MIN), "only");
+        assert_eq!(*f.eval(&0), "only");
+        assert_eq!(f.breakpoint_for(&0), None);
+        assert_eq!(f.eval_many(&[3, -3]), [&"only", &"only"]);
+        assert_eq!(
+            f.ranges().collect::<Vec<_>>(),
+            [(Bound::Unbounded, Bound::Unbounded, &"only")]
+        );
+        assert_eq!(StepFunction::new("only", vec![]).unwrap(), f);
+    }
+
+    #[test]
+    fn test_rejects_unsorted_breakpoints() {
+        let err = StepFunction::new(0, vec![(1, 1), (3, 2), (3, 3)]).unwrap_err();
+        assert_eq!(err.index(), 2);
+        let err = StepFunction::new(0, vec![(2, 1), (1, 2)]).unwrap_err();
+        assert_eq!(err.index(), 1);
+    }
+
+    #[test]
+    fn test_from_unsorted_iter() {
+        let input = [(3, 'x'), (1, 'y'), (3, 'z')];
+        let first = StepFunction::from_unsorted_iter('-', input, DuplicatePolicy::KeepFirst);
+        assert_eq!(first.unwrap().breakpoints(), &[(1, 'y'), (3, 'x')]);
+        let last = StepFunction::from_unsorted_iter('-', input, DuplicatePolicy::KeepLast);
+        assert_eq!(last.unwrap().breakpoints(), &[(1, 'y'), (3, 'z')]);
+        let err = StepFunction::from_unsorted_iter('-', input, DuplicatePolicy::Error);
+        assert_eq!(err.unwrap_err().into_key(), 3);
+    }
+
+    #[test]
+    fn test_against_linear_scan() {
+        assert_matches_reference!(
+            for (keys, x) in inputs::slices_with_keys(183) =>
+            {
+                let f = StepFunction::new(0, breakpoints_of(&keys)).unwrap();
+                (*f.eval(&x), f.breakpoint_for(&x).map(|(k, v)| (*k, *v)))
+            },
+            {
+                let breakpoints = breakpoints_of(&keys);
+                let value = *linear_eval(&0, &breakpoints, x);
+                let breakpoint = breakpoints.iter().rev().find(|(k, _)| *k <= x).copied();
+                (value, breakpoint)
+            },
+        );
+    }
+
+    #[test]
+    fn test_eval_many_and_ranges() {
+        let mut rng = XorShift::new(183);
+        for _ in 0..100 {
+            let input: Vec<(u32, usize)> = (0..rng.below(20))
+                .map(|i| (rng.below(50) as u32, i as usize + 1))
+                .collect();
+            let f = StepFunction::from_unsorted_iter(0, input, DuplicatePolicy::KeepLast).unwrap();
+            let keys: Vec<u32> = (0..30).map(|_| rng.below(60) as u32).collect();
+
+            let expected: Vec<&usize> = keys
+                .iter()
+                .map(|&key| linear_eval(f.initial(), f.breakpoints(), key))
+                .collect();
+            assert_eq!(f.eval_many(&keys), expected);
+
+            let ranges: Vec<_> = f.ranges().collect();
+            assert_eq!(ranges.len(), f.breakpoints().len() + 1);
+            for key in 0..60 {
+                let containing: Vec<_> = ranges
+                    .iter()
+                    .filter(|(start, end, _)| (*start, *end).contains(&key))
+                    .collect();
+                assert_eq!(
+                    containing.len(),
+                    1,
+                    "{key} should be in exactly one segment"
+                );
+                assert_eq!(containing[0].2, f.eval(&key));
+            }
+        }
+    }
+}
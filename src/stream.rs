@@ -0,0 +1,201 @@
+//! Lazily searching for the keys of an iterator.
+
+use alloc::vec::Vec;
+use core::{cmp::Ordering, iter::FusedIterator};
+
+use crate::SharBatchSearch;
+
+/// An iterator over the results of searching a sorted slice for each key of another iterator,
+/// yielding each key together with its result.
+///
+/// Keys are pulled from the underlying iterator a window of `W` at a time, and the searches of
+/// a window are interleaved like those of
+/// [`bl_binary_search_batch_by`](SharBatchSearch::bl_binary_search_batch_by). Results are
+/// yielded in the same order as the keys. With a window of 1, every key is searched for as
+/// soon as it is pulled.
+///
+/// Created by [`SharBatchSearch::bl_search_stream`] and
+/// [`SharBatchSearch::bl_search_stream_by`].
+pub struct SearchStream<'a, T, I: Iterator, F, const W: usize> {
+    slice: &'a [T],
+    keys: I,
+    keys_done: bool,
+    /// The keys of the current window, in reverse order so they can be popped off the end.
+    window: Vec<I::Item>,
+    results: [Result<usize, usize>; W],
+    next_result: usize,
+    compare: F,
+}
+
+impl<'a, T, I, F, const W: usize> SearchStream<'a, T, I, F, W>
+where
+    I: Iterator,
+    F: FnMut(&T, &I::Item) -> Ordering,
+{
+    pub(crate) fn new(slice: &'a [T], keys: I, compare: F) -> Self {
+        assert!(W > 0, "the window must be non-zero");
+
+        Self {
+            slice,
+            keys,
+            keys_done: false,
+            window: Vec::with_capacity(W),
+            results: [Err(0); W],
+            next_result: 0,
+            compare,
+        }
+    }
+
+    /// Pulls the next window of keys and searches for them all.
+    fn fill_window(&mut self) {
+        while self.window.len() < W {
+            match self.keys.next() {
+                Some(key) => self.window.push(key),
+                None => {
+                    self.keys_done = true;
+                    break;
+                }
+            }
+        }
+
+        let results = &mut self.results[..self.window.len()];
+        if self
+            .slice
+            .bl_binary_search_batch_by_into::<W, _, _>(&self.window, results, &mut self.compare)
+            .is_err()
+        {
+            unreachable!("the window never holds more than `W` keys");
+        }
+
+        self.window.reverse();
+        self.next_result = 0;
+    }
+}
+
+impl<T, I, F, const W: usize> Iterator for SearchStream<'_, T, I, F, W>
+where
+    I: Iterator,
+    F: FnMut(&T, &I::Item) -> Ordering,
+{
+    type Item = (I::Item, Result<usize, usize>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.window.is_empty() {
+            if self.keys_done {
+                return None;
+            }
+            self.fill_window();
+        }
+
+        let key = self.window.pop()?;
+        let result = self.results[self.next_result];
+        self.next_result += 1;
+
+        Some((key, result))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.keys_done {
+            return (self.window.len(), Some(self.window.len()));
+        }
+
+        let (lower, upper) = self.keys.size_hint();
+        (
+            lower.saturating_add(self.window.len()),
+            upper.and_then(|upper| upper.checked_add(self.window.len())),
+        )
+    }
+}
+
+impl<T, I, F, const W: usize> FusedIterator for SearchStream<'_, T, I, F, W>
+where
+    I: Iterator,
+    F: FnMut(&T, &I::Item) -> Ordering,
+{
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+
+    use crate::{SharBatchSearch, SharBinarySearch};
+
+    /// A key that cannot be cloned, to check that keys are moved through the stream.
+    #[derive(Debug, PartialEq)]
+    struct Key(u32);
+
+    #[test]
+    fn test_short_streams() {
+        let slice = [1, 3, 3, 5, 8];
+
+        for count in 0..=3 {
+            let keys = (0..count).map(|i| Key(i * 3));
+            let results: Vec<_> = slice
+                .bl_search_stream_by::<8, _, _>(keys, |p, k| p.cmp(&k.0))
+                .collect();
+
+            assert_eq!(results.len(), count as usize);
+            for (i, (key, result)) in results.into_iter().enumerate() {
+                assert_eq!(key, Key(i as u32 * 3));
+                assert_eq!(result, slice.bl_binary_search(&key.0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_scalar() {
+        let slice: Vec<u32> = (0..500).map(|i| i / 3 * 2).collect();
+        let keys: Vec<u32> = (0..400).map(|i| i * 7 % 350).collect();
+        let expected: Vec<_> = keys.iter().map(|k| slice.bl_binary_search(k)).collect();
+
+        let results: Vec<_> = slice.bl_search_stream(&keys).map(|(_, r)| r).collect();
+        assert_eq!(results, expected);
+
+        let results: Vec<_> = slice.bl_search_stream(keys.clone()).collect();
+        assert!(results.iter().map(|(k, _)| k).eq(&keys));
+        assert!(results.iter().map(|(_, r)| r).eq(&expected));
+
+        let one_at_a_time: Vec<_> = slice
+            .bl_search_stream_by::<1, _, _>(&keys, |p, k| p.cmp(k))
+            .map(|(_, r)| r)
+            .collect();
+        assert_eq!(one_at_a_time, expected);
+    }
+
+    #[test]
+    fn test_infinite_keys() {
+        let slice: Vec<u32> = (0..100).map(|i| i * 2).collect();
+        let pulled = Cell::new(0);
+        let keys = (0..).inspect(|_| pulled.set(pulled.get() + 1));
+
+        let results: Vec<_> = slice.bl_search_stream(keys).take(10).collect();
+        assert_eq!(results.len(), 10);
+        assert_eq!(results[4], (4, Ok(2)));
+        assert_eq!(results[5], (5, Err(3)));
+
+        // Only the windows needed for the first ten results were pulled.
+        assert_eq!(pulled.get(), 16);
+    }
+
+    #[test]
+    fn test_fused() {
+        /// An iterator that yields `None` once, then resumes.
+        struct Flaky(u32);
+
+        impl Iterator for Flaky {
+            type Item = u32;
+
+            fn next(&mut self) -> Option<u32> {
+                self.0 += 1;
+                (self.0 != 3).then_some(self.0)
+            }
+        }
+
+        let slice = [1, 2, 3, 4, 5];
+        let mut stream = slice.bl_search_stream(Flaky(0));
+        assert_eq!(stream.next(), Some((1, Ok(0))));
+        assert_eq!(stream.next(), Some((2, Ok(1))));
+        assert_eq!(stream.next(), None);
+        assert_eq!(stream.next(), None);
+    }
+}
@@ -0,0 +1,267 @@
+//! Static lookup tables whose entries are checked to be sorted at compile time.
+//!
+//! [`shar_table!`](crate::shar_table) declares a `static` [`StaticTable`] from literal entries,
+//! and fails to compile if they aren't sorted by key, so that a row inserted in the wrong place
+//! is caught when it is written rather than as a wrong lookup at runtime. Lookups use the
+//! branchless search.
+//!
+//! ```
+//! use shar_search::shar_table;
+//!
+//! #[derive(Clone, Copy, Debug, PartialEq)]
+//! enum Token {
+//!     Else,
+//!     Fn,
+//!     If,
+//!     Let,
+//! }
+//!
+//! shar_table! {
+//!     /// Keywords by spelling.
+//!     pub static KEYWORDS: [(&str, Token)] = [
+//!         ("else", Token::Else),
+//!         ("fn", Token::Fn),
+//!         ("if", Token::If),
+//!         ("let", Token::Let),
+//!     ];
+//! }
+//!
+//! shar_table! {
+//!     static STATUS: [(u32, &str)] = [(200, "OK"), (404, "Not Found"), (500, "Server Error")];
+//! }
+//!
+//! assert_eq!(KEYWORDS.lookup("let"), Some(&Token::Let));
+//! assert_eq!(KEYWORDS.lookup("loop"), None);
+//! assert_eq!(STATUS.lookup(&404), Some(&"Not Found"));
+//! ```
+//!
+//! Keys can be `&str`, `u8`, `u32`, `i32`, `u64` or `i64`, the types that
+//! [`const_search`](crate::const_search) can compare in a `const` context. Entries out of order
+//! fail to compile:
+//!
+//! ```compile_fail
+//! shar_search::shar_table! {
+//!     static STATUS: [(u32, &str)] = [(404, "Not Found"), (200, "OK")];
+//! }
+//! ```
+
+use core::borrow::Borrow;
+
+use crate::SharBinarySearch;
+
+/// A lookup table of entries sorted by key, borrowed for `'static`. Declare one with
+/// [`shar_table!`](crate::shar_table), which checks the order at compile time.
+#[derive(Debug)]
+pub struct StaticTable<K: 'static, V: 'static> {
+    entries: &'static [(K, V)],
+}
+
+impl<K, V> StaticTable<K, V> {
+    /// Creates a table of `entries`. Note it is assumed that they are sorted by key, which
+    /// [`shar_table!`](crate::shar_table) checks.
+    pub const fn from_sorted(entries: &'static [(K, V)]) -> Self {
+        Self { entries }
+    }
+
+    /// Returns the entries, sorted by key.
+    pub const fn entries(&self) -> &'static [(K, V)] {
+        self.entries
+    }
+
+    /// Returns the number of entries.
+    pub const fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether there are no entries.
+    pub const fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the entry for `key`, or `None` if there is none. If several entries have the
+    /// key, the first is returned.
+    pub fn lookup_entry<Q>(&self, key: &Q) -> Option<&'static (K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let entries = self.entries;
+        let index = entries
+            .bl_binary_search_by(|(k, _)| k.borrow().cmp(key))
+            .ok()?;
+        Some(&entries[index])
+    }
+
+    /// Returns the value for `key`, or `None` if there is none. If several entries have the
+    /// key, the first one's value is returned.
+    pub fn lookup<Q>(&self, key: &Q) -> Option<&'static V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.lookup_entry(key).map(|(_, value)| value)
+    }
+}
+
+// Not derived, which would require `K: Clone` and `V: Clone`.
+impl<K, V> Clone for StaticTable<K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V> Copy for StaticTable<K, V> {}
+
+/// Declares a `static` [`StaticTable`](crate::table::StaticTable) of literal entries, which
+/// fails to compile unless they are sorted by key. See the [`table`](crate::table) module.
+///
+/// The syntax is that of a `static` of a slice of pairs, whose key type is `&str`, `u8`, `u32`,
+/// `i32`, `u64` or `i64`:
+///
+/// ```
+/// shar_search::shar_table! {
+///     pub static PRIMES: [(u8, &str)] = [(2, "two"), (3, "three"), (5, "five")];
+/// }
+///
+/// assert_eq!(PRIMES.lookup(&3), Some(&"three"));
+/// assert_eq!(PRIMES.len(), 3);
+/// ```
+///
+/// Other key types are rejected, as they can't be compared in a `const` context:
+///
+/// ```compile_fail
+/// shar_search::shar_table! {
+///     static NAMES: [(char, &str)] = [('a', "a"), ('b', "b")];
+/// }
+/// ```
+///
+/// As are entries out of order, including by a single byte of a string key:
+///
+/// ```compile_fail
+/// shar_search::shar_table! {
+///     static WORDS: [(&str, u8)] = [("ab", 1), ("aa", 2)];
+/// }
+/// ```
+#[macro_export]
+macro_rules! shar_table {
+    (
+        $(#[$attr:meta])*
+        $vis:vis static $name:ident: [(& $($lifetime:lifetime)? str, $value:ty)] =
+            [$(($key:expr, $entry:expr)),* $(,)?];
+    ) => {
+        $crate::shar_table!(
+            @emit $(#[$attr])* $vis $name, str, &'static str, $value, [$(($key, $entry)),*]
+        );
+    };
+    (
+        $(#[$attr:meta])*
+        $vis:vis static $name:ident: [($key_type:ident, $value:ty)] =
+            [$(($key:expr, $entry:expr)),* $(,)?];
+    ) => {
+        $crate::shar_table!(
+            @emit $(#[$attr])* $vis $name, $key_type, $key_type, $value, [$(($key, $entry)),*]
+        );
+    };
+    (@is_sorted str, $entries:expr) => {
+        $crate::const_search::is_sorted_by_first_str($entries)
+    };
+    (@is_sorted u8, $entries:expr) => {
+        $crate::const_search::is_sorted_by_first_u8($entries)
+    };
+    (@is_sorted u32, $entries:expr) => {
+        $crate::const_search::is_sorted_by_first_u32($entries)
+    };
+    (@is_sorted i32, $entries:expr) => {
+        $crate::const_search::is_sorted_by_first_i32($entries)
+    };
+    (@is_sorted u64, $entries:expr) => {
+        $crate::const_search::is_sorted_by_first_u64($entries)
+    };
+    (@is_sorted i64, $entries:expr) => {
+        $crate::const_search::is_sorted_by_first_i64($entries)
+    };
+    (@is_sorted $other:ident, $entries:expr) => {
+        compile_error!(concat!(
+            "keys of type `",
+            stringify!($other),
+            "` can't be compared at compile time; use &str, u8, u32, i32, u64 or i64"
+        ))
+    };
+    (
+        @emit $(#[$attr:meta])* $vis:vis $name:ident, $sort_key:ident, $key_type:ty,
+        $value:ty, [$(($key:expr, $entry:expr)),*]
+    ) => {
+        $(#[$attr])*
+        $vis static $name: $crate::table::StaticTable<$key_type, $value> = {
+            const ENTRIES: &[($key_type, $value)] = &[$(($key, $entry)),*];
+            const _: () = assert!(
+                $crate::shar_table!(@is_sorted $sort_key, ENTRIES),
+                concat!("the entries of `", stringify!($name), "` are not sorted by key"),
+            );
+            $crate::table::StaticTable::from_sorted(ENTRIES)
+        };
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::StaticTable;
+    use crate::reference::{self, assert_matches_reference, inputs};
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Op {
+        Add,
+        Jump,
+        Load,
+        Store,
+    }
+
+    shar_table! {
+        static OPCODES: [(&'static str, Op)] = [
+            ("add", Op::Add),
+            ("jmp", Op::Jump),
+            ("ld", Op::Load),
+            ("st", Op::Store),
+        ];
+    }
+
+    shar_table! {
+        static SIGNED: [(i64, char)] = [(i64::MIN, 'a'), (-1, 'b'), (0, 'c'), (0, 'd'), (9, 'e')];
+    }
+
+    shar_table! {
+        static EMPTY: [(u32, ())] = [];
+    }
+
+    #[test]
+    fn test_lookups() {
+        assert_eq!(OPCODES.lookup("ld"), Some(&Op::Load));
+        assert_eq!(OPCODES.lookup("add"), Some(&Op::Add));
+        assert_eq!(OPCODES.lookup("st"), Some(&Op::Store));
+        assert_eq!(OPCODES.lookup("ad"), None);
+        assert_eq!(OPCODES.lookup("sta"), None);
+        assert_eq!(OPCODES.lookup_entry("jmp"), Some(&("jmp", Op::Jump)));
+
+        assert_eq!(SIGNED.lookup(&i64::MIN), Some(&'a'));
+        assert_eq!(SIGNED.lookup(&0), Some(&'c'));
+        assert_eq!(SIGNED.lookup(&1), None);
+        assert_eq!(SIGNED.len(), 5);
+
+        assert!(EMPTY.is_empty());
+        assert_eq!(EMPTY.lookup(&0), None);
+    }
+
+    #[test]
+    fn test_against_reference() {
+        // Leaked, as tables borrow their entries for `'static`.
+        assert_matches_reference!(
+            for (keys, x) in inputs::slices_with_keys(181) =>
+            {
+                let entries: Vec<(u32, usize)> = keys.iter().copied().zip(0..).collect();
+                let table = StaticTable::from_sorted(Vec::leak(entries));
+                table.lookup(&x).copied()
+            },
+            reference::binary_search(&keys, &x).ok(),
+        );
+    }
+}
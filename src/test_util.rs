@@ -0,0 +1,95 @@
+//! Helpers shared by the unit tests.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+#[cfg(feature = "io")]
+use std::{fs, path::PathBuf};
+
+/// A small, seeded xorshift generator so randomized tests are reproducible without extra
+/// dependencies.
+pub(crate) struct XorShift(u64);
+
+impl XorShift {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    /// Returns a value in `0..bound`.
+    pub(crate) fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+}
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Wraps the system allocator, counting allocations per thread so that tests running in
+/// parallel do not see each other's allocations.
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // The count may be unavailable while a thread is being torn down.
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Runs `f`, returning its result and the number of heap allocations it made on this thread.
+#[cfg(feature = "alloc")]
+pub(crate) fn count_allocations<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let result = f();
+    (result, ALLOCATIONS.with(Cell::get) - before)
+}
+
+/// A file in the temporary directory, removed when dropped.
+#[cfg(feature = "io")]
+pub(crate) struct TempFile(PathBuf);
+
+#[cfg(feature = "io")]
+impl TempFile {
+    /// Writes `bytes` to a file named after `name` and this process, so that tests running
+    /// at the same time don't share files.
+    pub(crate) fn new(name: &str, bytes: &[u8]) -> Self {
+        let file = format!("shar_search-{}-{name}.bin", std::process::id());
+        let path = std::env::temp_dir().join(file);
+        fs::write(&path, bytes).unwrap();
+        Self(path)
+    }
+
+    pub(crate) fn path(&self) -> &PathBuf {
+        &self.0
+    }
+}
+
+#[cfg(feature = "io")]
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        fs::remove_file(&self.0).ok();
+    }
+}
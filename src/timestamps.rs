@@ -0,0 +1,446 @@
+//! Searches of sorted timestamps, enabled by the `chrono` and `time` features.
+//!
+//! [`SharTimeSearch`] answers the lookups time-series code makes over and over: the latest
+//! entry at or before an instant, the earliest entry after one, and every entry within a window.
+//! Elements and keys are compared only by the instant they denote, so entries recorded with
+//! different UTC offsets (or time zones) sort and match correctly, and the key can be of a
+//! different type than the elements. The slices must be sorted by instant, as by `sort()`.
+//!
+//! The timestamp types are chrono's [`DateTime`](chrono::DateTime) in any time zone with the
+//! `chrono` feature, and time's [`OffsetDateTime`](time::OffsetDateTime) and
+//! [`UtcDateTime`](time::UtcDateTime) with the `time` feature.
+//!
+//! ```
+//! # #[cfg(feature = "chrono")]
+//! # {
+//! use chrono::{DateTime, Duration, TimeZone, Utc};
+//! use shar_search::timestamps::SharTimeSearch;
+//!
+//! struct Trade {
+//!     at: DateTime<Utc>,
+//!     volume: u64,
+//! }
+//!
+//! let open = Utc.with_ymd_and_hms(2024, 3, 1, 9, 30, 0).unwrap();
+//! let trades: Vec<Trade> = [(0, 5), (40, 2), (75, 8), (130, 1), (190, 4)]
+//!     .into_iter()
+//!     .map(|(minutes, volume)| Trade { at: open + Duration::minutes(minutes), volume })
+//!     .collect();
+//!
+//! // The volume traded in each hour after the open.
+//! let hourly: Vec<u64> = (0..4)
+//!     .map(|hour| {
+//!         let start = open + Duration::hours(hour);
+//!         let window = trades.between_by_key(start..start + Duration::hours(1), |t| t.at);
+//!         window.iter().map(|t| t.volume).sum()
+//!     })
+//!     .collect();
+//! assert_eq!(hourly, [7, 8, 1, 4]);
+//!
+//! // The last trade as of 11:00, and the first one after it.
+//! let eleven = open + Duration::minutes(90);
+//! assert_eq!(trades.latest_at_or_before_by_key(&eleven, |t| t.at).unwrap().volume, 8);
+//! assert_eq!(trades.earliest_after_by_key(&eleven, |t| t.at).unwrap().volume, 1);
+//! # }
+//! ```
+
+use core::ops::{Bound, RangeBounds};
+
+use crate::SharBinarySearch;
+
+mod sealed {
+    pub trait Sealed {
+        /// Returns the instant as whole seconds since the Unix epoch and nanoseconds past them.
+        /// The nanoseconds may exceed a second during a leap second, as chrono represents them,
+        /// so the pair still orders like the instants.
+        fn instant(&self) -> (i64, u32);
+    }
+}
+
+use sealed::Sealed;
+
+/// A type of timestamp that denotes an instant, whatever its time zone or offset.
+///
+/// Implemented for chrono's [`DateTime`](chrono::DateTime) with the `chrono` feature, for time's
+/// [`OffsetDateTime`](time::OffsetDateTime) and [`UtcDateTime`](time::UtcDateTime) with the
+/// `time` feature, and for references to them.
+pub trait Timestamp: Sealed {}
+
+impl<T: Timestamp + ?Sized> Sealed for &T {
+    #[inline]
+    fn instant(&self) -> (i64, u32) {
+        (**self).instant()
+    }
+}
+
+impl<T: Timestamp + ?Sized> Timestamp for &T {}
+
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> Sealed for chrono::DateTime<Tz> {
+    #[inline]
+    fn instant(&self) -> (i64, u32) {
+        (self.timestamp(), self.timestamp_subsec_nanos())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> Timestamp for chrono::DateTime<Tz> {}
+
+#[cfg(feature = "time")]
+impl Sealed for time::OffsetDateTime {
+    #[inline]
+    fn instant(&self) -> (i64, u32) {
+        (self.unix_timestamp(), self.nanosecond())
+    }
+}
+
+#[cfg(feature = "time")]
+impl Timestamp for time::OffsetDateTime {}
+
+#[cfg(feature = "time")]
+impl Sealed for time::UtcDateTime {
+    #[inline]
+    fn instant(&self) -> (i64, u32) {
+        (self.unix_timestamp(), self.nanosecond())
+    }
+}
+
+#[cfg(feature = "time")]
+impl Timestamp for time::UtcDateTime {}
+
+/// Trait for searching slices sorted by instant, either of timestamps or of elements with a
+/// timestamp key. See the [module documentation](self).
+pub trait SharTimeSearch<E> {
+    /// Returns the last element at or before `t`, or `None` if every element is after it. Note
+    /// it is assumed that the slice is sorted.
+    ///
+    /// If several elements are at the same instant, the *last* of them is returned.
+    fn latest_at_or_before<U>(&self, t: &U) -> Option<&E>
+    where
+        E: Timestamp,
+        U: Timestamp;
+
+    /// Returns the last element whose key is at or before `t`, using a key extraction function.
+    /// See [`latest_at_or_before`](SharTimeSearch::latest_at_or_before).
+    fn latest_at_or_before_by_key<U, K, F>(&self, t: &U, f: F) -> Option<&E>
+    where
+        U: Timestamp,
+        K: Timestamp,
+        F: FnMut(&E) -> K;
+
+    /// Returns the first element after `t`, or `None` if every element is at or before it. Note
+    /// it is assumed that the slice is sorted.
+    ///
+    /// If several elements are at the same instant, the *first* of them is returned.
+    fn earliest_after<U>(&self, t: &U) -> Option<&E>
+    where
+        E: Timestamp,
+        U: Timestamp;
+
+    /// Returns the first element whose key is after `t`, using a key extraction function. See
+    /// [`earliest_after`](SharTimeSearch::earliest_after).
+    fn earliest_after_by_key<U, K, F>(&self, t: &U, f: F) -> Option<&E>
+    where
+        U: Timestamp,
+        K: Timestamp,
+        F: FnMut(&E) -> K;
+
+    /// Returns the elements within `range`, such as `start..end` for a half-open window. Note it
+    /// is assumed that the slice is sorted.
+    ///
+    /// Every element at an included bound is returned, and none at an excluded one. If the start
+    /// of the range is after its end, the returned slice is empty.
+    fn between<U, R>(&self, range: R) -> &[E]
+    where
+        E: Timestamp,
+        U: Timestamp,
+        R: RangeBounds<U>;
+
+    /// Returns the elements whose keys are within `range`, using a key extraction function. See
+    /// [`between`](SharTimeSearch::between).
+    fn between_by_key<U, R, K, F>(&self, range: R, f: F) -> &[E]
+    where
+        U: Timestamp,
+        R: RangeBounds<U>,
+        K: Timestamp,
+        F: FnMut(&E) -> K;
+}
+
+/// Returns the index of the first element of `slice` after `t`, where `instant` returns an
+/// element's instant.
+fn first_after<E, F>(slice: &[E], t: &impl Timestamp, mut instant: F) -> usize
+where
+    F: FnMut(&E) -> (i64, u32),
+{
+    let t = t.instant();
+    slice.bl_partition_point(|e| instant(e) <= t)
+}
+
+/// Returns the elements of `slice` within `range`, where `instant` returns an element's instant.
+fn within<E, U, R, F>(slice: &[E], range: R, mut instant: F) -> &[E]
+where
+    U: Timestamp,
+    R: RangeBounds<U>,
+    F: FnMut(&E) -> (i64, u32),
+{
+    let start = match range.start_bound() {
+        Bound::Included(t) => {
+            let t = t.instant();
+            slice.bl_partition_point(|e| instant(e) < t)
+        }
+        Bound::Excluded(t) => first_after(slice, t, &mut instant),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(t) => first_after(slice, t, &mut instant),
+        Bound::Excluded(t) => {
+            let t = t.instant();
+            slice.bl_partition_point(|e| instant(e) < t)
+        }
+        Bound::Unbounded => slice.len(),
+    };
+
+    &slice[start..end.max(start)]
+}
+
+impl<E> SharTimeSearch<E> for [E] {
+    #[inline]
+    fn latest_at_or_before<U>(&self, t: &U) -> Option<&E>
+    where
+        E: Timestamp,
+        U: Timestamp,
+    {
+        let end = first_after(self, t, E::instant);
+        end.checked_sub(1).map(|index| &self[index])
+    }
+
+    #[inline]
+    fn latest_at_or_before_by_key<U, K, F>(&self, t: &U, mut f: F) -> Option<&E>
+    where
+        U: Timestamp,
+        K: Timestamp,
+        F: FnMut(&E) -> K,
+    {
+        let end = first_after(self, t, |e| f(e).instant());
+        end.checked_sub(1).map(|index| &self[index])
+    }
+
+    #[inline]
+    fn earliest_after<U>(&self, t: &U) -> Option<&E>
+    where
+        E: Timestamp,
+        U: Timestamp,
+    {
+        self.get(first_after(self, t, E::instant))
+    }
+
+    #[inline]
+    fn earliest_after_by_key<U, K, F>(&self, t: &U, mut f: F) -> Option<&E>
+    where
+        U: Timestamp,
+        K: Timestamp,
+        F: FnMut(&E) -> K,
+    {
+        self.get(first_after(self, t, |e| f(e).instant()))
+    }
+
+    #[inline]
+    fn between<U, R>(&self, range: R) -> &[E]
+    where
+        E: Timestamp,
+        U: Timestamp,
+        R: RangeBounds<U>,
+    {
+        within(self, range, E::instant)
+    }
+
+    #[inline]
+    fn between_by_key<U, R, K, F>(&self, range: R, mut f: F) -> &[E]
+    where
+        U: Timestamp,
+        R: RangeBounds<U>,
+        K: Timestamp,
+        F: FnMut(&E) -> K,
+    {
+        within(self, range, |e| f(e).instant())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "chrono")]
+    mod chrono_tests {
+        use chrono::{DateTime, FixedOffset, NaiveDate, TimeZone, Utc};
+
+        use crate::timestamps::SharTimeSearch;
+
+        fn offset(hours: i32) -> FixedOffset {
+            FixedOffset::east_opt(hours * 3600).unwrap()
+        }
+
+        #[test]
+        fn test_dst_fall_back() {
+            // On 2024-11-03, New York clocks went from 01:59 EDT (-04:00) back to 01:00 EST
+            // (-05:00), so 01:45 EDT is before 01:15 EST although its wall clock is later.
+            let (edt, est) = (offset(-4), offset(-5));
+            let at = |tz: FixedOffset, h, m| tz.with_ymd_and_hms(2024, 11, 3, h, m, 0).unwrap();
+            let log = [
+                at(edt, 1, 10),
+                at(edt, 1, 45),
+                at(est, 1, 15),
+                at(est, 1, 40),
+                at(est, 2, 30),
+            ];
+            assert!(log.is_sorted());
+            assert!(at(edt, 1, 45) < at(est, 1, 15));
+
+            // A key in UTC, between the two 01:30s.
+            let key = Utc.with_ymd_and_hms(2024, 11, 3, 6, 0, 0).unwrap();
+            assert_eq!(log.latest_at_or_before(&key), Some(&at(edt, 1, 45)));
+            assert_eq!(log.earliest_after(&key), Some(&at(est, 1, 15)));
+            assert_eq!(log.between(at(edt, 1, 30)..at(est, 1, 30)), &log[1..3]);
+            assert_eq!(log.between(at(est, 1, 30)..), &log[3..]);
+
+            // The same instant in another offset matches exactly.
+            let same = at(est, 1, 15).with_timezone(&Utc);
+            assert_eq!(log.latest_at_or_before(&same), Some(&log[2]));
+            assert_eq!(log.earliest_after(&same), Some(&log[3]));
+            assert_eq!(log.between(same..=same), &log[2..3]);
+        }
+
+        #[test]
+        fn test_dst_spring_forward() {
+            // On 2024-03-31, Berlin clocks went from 02:00 CET (+01:00) to 03:00 CEST (+02:00).
+            let (cet, cest) = (offset(1), offset(2));
+            let before = cet.with_ymd_and_hms(2024, 3, 31, 1, 59, 0).unwrap();
+            let after = cest.with_ymd_and_hms(2024, 3, 31, 3, 1, 0).unwrap();
+            let log = [before, after];
+
+            // 02:30 CET never showed on Berlin clocks: it is 03:30 CEST, after both entries,
+            // although its wall clock is earlier than the second one's.
+            let gap = cet.with_ymd_and_hms(2024, 3, 31, 2, 30, 0).unwrap();
+            assert_eq!(log.latest_at_or_before(&gap), Some(&after));
+            assert_eq!(log.earliest_after(&gap), None);
+            assert_eq!(log.between(before..gap), &log[..]);
+            assert_eq!((after - before).num_minutes(), 2);
+        }
+
+        #[test]
+        fn test_identical_timestamps() {
+            let t = |s| DateTime::from_timestamp(s, 0).unwrap();
+            let events = [
+                (t(10), 'a'),
+                (t(20), 'b'),
+                (t(20), 'c'),
+                (t(20), 'd'),
+                (t(30), 'e'),
+            ];
+            let latest = |s| {
+                events
+                    .latest_at_or_before_by_key(&t(s), |e| e.0)
+                    .map(|e| e.1)
+            };
+            let earliest = |s| events.earliest_after_by_key(&t(s), |e| e.0).map(|e| e.1);
+
+            assert_eq!(latest(20), Some('d'));
+            assert_eq!(latest(25), Some('d'));
+            assert_eq!(latest(9), None);
+            assert_eq!(earliest(19), Some('b'));
+            assert_eq!(earliest(20), Some('e'));
+            assert_eq!(earliest(30), None);
+            assert_eq!(events.between_by_key(t(20)..t(30), |e| e.0), &events[1..4]);
+            assert_eq!(events.between_by_key(t(10)..=t(20), |e| e.0), &events[..4]);
+            assert_eq!(events.between_by_key(..t(20), |e| e.0), &events[..1]);
+            assert!(events.between_by_key(t(30)..t(10), |e| e.0).is_empty());
+        }
+
+        #[test]
+        fn test_empty() {
+            let empty: [DateTime<Utc>; 0] = [];
+            let t = DateTime::from_timestamp(0, 0).unwrap();
+            assert_eq!(empty.latest_at_or_before(&t), None);
+            assert_eq!(empty.earliest_after(&t), None);
+            assert!(empty.between(..t).is_empty());
+            assert!(empty.between::<DateTime<Utc>, _>(..).is_empty());
+        }
+
+        #[test]
+        fn test_leap_second() {
+            // chrono represents a leap second with nanoseconds past one second.
+            let date = NaiveDate::from_ymd_opt(2016, 12, 31).unwrap();
+            let at = |h, m, s, nanos| date.and_hms_nano_opt(h, m, s, nanos).unwrap().and_utc();
+            let log = [
+                at(23, 59, 59, 0),
+                at(23, 59, 59, 500_000_000),
+                at(23, 59, 59, 1_500_000_000),
+                DateTime::from_timestamp(1_483_228_800, 0).unwrap(),
+            ];
+            assert!(log.is_sorted());
+            assert_eq!(log.latest_at_or_before(&log[2]), Some(&log[2]));
+            assert_eq!(log.earliest_after(&log[2]), Some(&log[3]));
+            assert_eq!(log.between(log[1]..log[3]), &log[1..3]);
+        }
+    }
+
+    #[cfg(feature = "time")]
+    mod time_tests {
+        use time::{OffsetDateTime, UtcDateTime, UtcOffset};
+
+        use crate::timestamps::SharTimeSearch;
+
+        fn at(unix: i64, offset_hours: i8) -> OffsetDateTime {
+            let offset = UtcOffset::from_hms(offset_hours, 0, 0).unwrap();
+            OffsetDateTime::from_unix_timestamp(unix)
+                .unwrap()
+                .to_offset(offset)
+        }
+
+        #[test]
+        fn test_offsets() {
+            // 2024-11-03T05:45Z and 06:15Z: 01:45 EDT, then 01:15 EST.
+            let (t0, t1) = (1_730_612_700, 1_730_614_500);
+            let log = [at(t0 - 3600, -4), at(t0, -4), at(t1, -5), at(t1 + 60, 9)];
+            assert!(log.is_sorted());
+
+            let key = UtcDateTime::from_unix_timestamp(t0 + 60).unwrap();
+            assert_eq!(log.latest_at_or_before(&key), Some(&log[1]));
+            assert_eq!(log.earliest_after(&key), Some(&log[2]));
+            assert_eq!(log.between(at(t0, 0)..at(t1, 3)), &log[1..2]);
+            assert_eq!(log.between(at(t0, 0)..=at(t1, 3)), &log[1..3]);
+
+            let utc: Vec<UtcDateTime> = log.iter().map(|t| t.to_utc()).collect();
+            assert_eq!(utc.earliest_after(&log[2]), Some(&utc[3]));
+
+            let empty: [OffsetDateTime; 0] = [];
+            assert_eq!(empty.latest_at_or_before(&key), None);
+            assert!(empty.between(key..).is_empty());
+        }
+
+        #[test]
+        fn test_sub_second() {
+            let base = at(1_700_000_000, 0);
+            let nanos = |n| base.replace_nanosecond(n).unwrap();
+            let log = [
+                nanos(0),
+                nanos(1),
+                nanos(1),
+                nanos(999_999_999),
+                at(1_700_000_001, 2),
+            ];
+            assert_eq!(log.latest_at_or_before(&nanos(1)), Some(&log[2]));
+            assert_eq!(log.earliest_after(&nanos(0)), Some(&log[1]));
+            assert_eq!(log.between(nanos(1)..nanos(999_999_999)), &log[1..3]);
+        }
+    }
+
+    #[cfg(all(feature = "chrono", feature = "time"))]
+    #[test]
+    fn test_mixed_libraries() {
+        use super::SharTimeSearch;
+
+        let chrono = [0, 60, 120].map(|s| chrono::DateTime::from_timestamp(s, 0).unwrap());
+        let key = time::OffsetDateTime::from_unix_timestamp(60).unwrap();
+        assert_eq!(chrono.latest_at_or_before(&key), Some(&chrono[1]));
+        assert_eq!(chrono.earliest_after(&key), Some(&chrono[2]));
+    }
+}
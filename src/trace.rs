@@ -0,0 +1,247 @@
+//! Recording the probes of a search, for teaching the algorithm or debugging a search that is
+//! slow or wrong on unusual data. Enabled by the `trace` feature; without it, nothing here is
+//! compiled and the searches are unchanged.
+//!
+//! A traced search runs exactly the same probes as
+//! [`bl_binary_search_by`](crate::SharBinarySearch::bl_binary_search_by), in the same order:
+//!
+//! 1. If the slice's length `n` is not a power of two, the first probe is at the largest power
+//!    of two below it, `bit_floor(n)`. If that element is less than the key, the search window
+//!    flips to the last `next_power_of_two(n - bit_floor(n) - 1)` elements.
+//! 2. The window is halved until one element is left, with one probe per halving.
+//! 3. The element left, and if it is less than the key, the one after it, are probed to finish.
+//!    The element left may already have been probed by the last halving, as in the example
+//!    below.
+//!
+//! A search of `n` elements therefore probes at most `⌊log₂ n⌋ + 3` of them.
+//!
+//! ```
+//! use shar_search::trace::SharTracedSearch;
+//!
+//! let slice: Vec<u32> = (0..100).map(|i| i * 2).collect();
+//! let (result, trace) = slice.bl_binary_search_traced(&75);
+//! assert_eq!(result, Err(38));
+//! assert!(trace.probes().len() <= 6 + 3);
+//!
+//! println!("{trace}");
+//! ```
+//!
+//! prints:
+//!
+//! ```text
+//! 100 elements, initial step 64
+//! 1. [........................................*.......................] 64 Greater
+//! 2. [....................*...........................................] 32 Less
+//! 3. [..............................*.................................] 48 Greater
+//! 4. [.........................*......................................] 40 Greater
+//! 5. [.......................*........................................] 36 Less
+//! 6. [........................*.......................................] 38 Greater
+//! 7. [.......................*........................................] 37 Less
+//! 8. [.......................*........................................] 37 Less
+//! 9. [........................*.......................................] 38 Greater
+//! Err(38)
+//! ```
+
+use alloc::{string::ToString, vec::Vec};
+use core::{cmp::Ordering, fmt};
+
+use crate::{bit_floor, search_indices};
+
+/// The width, in characters, of the bar showing where each probe is in the slice.
+const BAR_WIDTH: usize = 64;
+
+/// The probes of one search, returned by the methods of [`SharTracedSearch`]. Its
+/// [`Display`](fmt::Display) implementation draws each probe's position in the slice.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchTrace {
+    len: usize,
+    probes: Vec<(usize, Ordering)>,
+    result: Result<usize, usize>,
+}
+
+impl SearchTrace {
+    /// Returns the length of the searched slice.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the searched slice was empty, in which case nothing was probed.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the index of each probe, with the ordering of that element relative to the key,
+    /// in the order they were made.
+    pub fn probes(&self) -> &[(usize, Ordering)] {
+        &self.probes
+    }
+
+    /// Returns the initial step of the search, the largest power of two not greater than the
+    /// slice's length, or zero for an empty slice.
+    pub fn initial_step(&self) -> usize {
+        if self.len == 0 {
+            0
+        } else {
+            bit_floor(self.len)
+        }
+    }
+
+    /// Returns whether the first probe found a smaller element, so the search window flipped to
+    /// the end of the slice.
+    pub fn flipped(&self) -> bool {
+        let step = self.initial_step();
+        step != self.len && self.probes.first() == Some(&(step, Ordering::Less))
+    }
+
+    /// Returns the result of the search.
+    pub fn result(&self) -> Result<usize, usize> {
+        self.result
+    }
+}
+
+impl fmt::Display for SearchTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} elements, initial step {}",
+            self.len,
+            self.initial_step()
+        )?;
+        if self.flipped() {
+            write!(f, ", window flipped to the end")?;
+        }
+        writeln!(f)?;
+
+        let number_width = self.probes.len().to_string().len();
+        let index_width = self.len.saturating_sub(1).to_string().len();
+        for (number, &(index, ordering)) in self.probes.iter().enumerate() {
+            write!(f, "{:>number_width$}. [", number + 1)?;
+            // The column of the bar covering the index.
+            let marker = index * BAR_WIDTH.min(self.len) / self.len;
+            for column in 0..BAR_WIDTH.min(self.len) {
+                f.write_str(if column == marker { "*" } else { "." })?;
+            }
+            writeln!(f, "] {index:>index_width$} {ordering:?}")?;
+        }
+
+        write!(f, "{:?}", self.result)
+    }
+}
+
+/// Trait for binary searches that record their probes.
+pub trait SharTracedSearch<T> {
+    /// Binary searches this slice with a comparator function, like
+    /// [`bl_binary_search_by`](crate::SharBinarySearch::bl_binary_search_by), and returns the
+    /// probes it made along with the result.
+    fn bl_binary_search_traced_by<'a, F>(&'a self, f: F) -> (Result<usize, usize>, SearchTrace)
+    where
+        F: FnMut(&'a T) -> Ordering,
+        T: 'a;
+
+    /// Binary searches this slice for a given element, like
+    /// [`bl_binary_search`](crate::SharBinarySearch::bl_binary_search), and returns the probes
+    /// it made along with the result.
+    fn bl_binary_search_traced(&self, x: &T) -> (Result<usize, usize>, SearchTrace)
+    where
+        T: Ord,
+    {
+        self.bl_binary_search_traced_by(|p| p.cmp(x))
+    }
+}
+
+impl<T> SharTracedSearch<T> for [T] {
+    fn bl_binary_search_traced_by<'a, F>(&'a self, mut f: F) -> (Result<usize, usize>, SearchTrace)
+    where
+        F: FnMut(&'a T) -> Ordering,
+        T: 'a,
+    {
+        let mut probes = Vec::new();
+        let result = search_indices(self.len(), |i| {
+            let ordering = f(&self[i]);
+            probes.push((i, ordering));
+            ordering
+        });
+
+        let trace = SearchTrace {
+            len: self.len(),
+            probes,
+            result,
+        };
+        (result, trace)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::SharBinarySearch;
+
+    #[test]
+    fn test_probe_bound() {
+        for len in 1..300_usize {
+            let slice: Vec<usize> = (0..len).map(|i| i * 2).collect();
+            let bound = len.ilog2() as usize + 3;
+
+            for key in 0..len * 2 + 2 {
+                let (result, trace) = slice.bl_binary_search_traced(&key);
+                assert_eq!(result, slice.bl_binary_search(&key));
+                assert_eq!(trace.result(), result);
+                assert!(trace.probes().len() <= bound, "len {len}, key {key}");
+
+                // Each probe's ordering is that of the element at its index.
+                for &(index, ordering) in trace.probes() {
+                    assert_eq!(slice[index].cmp(&key), ordering);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_flip() {
+        let slice: Vec<u32> = (0..100).collect();
+
+        let (_, trace) = slice.bl_binary_search_traced(&90);
+        assert_eq!(trace.initial_step(), 64);
+        assert_eq!(trace.probes()[0], (64, Ordering::Less));
+        assert!(trace.flipped());
+        assert!(trace.to_string().contains("window flipped"));
+
+        let (_, trace) = slice.bl_binary_search_traced(&10);
+        assert!(!trace.flipped());
+
+        // A power of two has no initial probe to flip on.
+        let (_, trace) = slice[..64].bl_binary_search_traced(&63);
+        assert_eq!(trace.probes()[0].0, 32);
+        assert!(!trace.flipped());
+    }
+
+    #[test]
+    fn test_display() {
+        let slice: Vec<u32> = (0..100).map(|i| i * 2).collect();
+        let (_, trace) = slice.bl_binary_search_traced(&75);
+        let expected = "\
+100 elements, initial step 64
+1. [........................................*.......................] 64 Greater
+2. [....................*...........................................] 32 Less
+3. [..............................*.................................] 48 Greater
+4. [.........................*......................................] 40 Greater
+5. [.......................*........................................] 36 Less
+6. [........................*.......................................] 38 Greater
+7. [.......................*........................................] 37 Less
+8. [.......................*........................................] 37 Less
+9. [........................*.......................................] 38 Greater
+Err(38)";
+        assert_eq!(trace.to_string(), expected);
+
+        let (_, trace) = [1, 2, 3].bl_binary_search_traced(&3);
+        assert_eq!(
+            trace.to_string(),
+            "3 elements, initial step 2\n1. [..*] 2 Equal\n2. [.*.] 1 Less\n3. [.*.] 1 Less\n4. [..*] 2 Equal\nOk(2)"
+        );
+
+        let (result, trace) = <[u8]>::bl_binary_search_traced(&[], &1);
+        assert_eq!(result, Err(0));
+        assert!(trace.is_empty());
+        assert_eq!(trace.to_string(), "0 elements, initial step 0\nErr(0)");
+    }
+}
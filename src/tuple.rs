@@ -0,0 +1,150 @@
+//! Searching slices of tuples, sorted lexicographically, by a prefix of their fields.
+
+use core::ops::Range;
+
+use crate::SharBinarySearch;
+
+/// A tuple whose leading fields can be searched by. Implemented for tuples of two to four
+/// fields.
+pub trait TupleKey {
+    /// The type of the first field.
+    type First;
+    /// The type of the second field.
+    type Second;
+
+    /// Returns the first field.
+    fn first(&self) -> &Self::First;
+
+    /// Returns the second field.
+    fn second(&self) -> &Self::Second;
+}
+
+macro_rules! impl_tuple_key {
+    ($($rest:ident),*) => {
+        impl<A, B, $($rest),*> TupleKey for (A, B, $($rest),*) {
+            type First = A;
+            type Second = B;
+
+            fn first(&self) -> &A {
+                &self.0
+            }
+
+            fn second(&self) -> &B {
+                &self.1
+            }
+        }
+    };
+}
+
+impl_tuple_key!();
+impl_tuple_key!(C);
+impl_tuple_key!(C, D);
+
+/// Trait for finding the ranges of tuples that share leading fields, in a slice of tuples
+/// sorted lexicographically, without building a full tuple with sentinel values for the
+/// trailing fields.
+pub trait SharTupleSearch<T: TupleKey> {
+    /// Returns the range of indices of all tuples whose first field equals `first`. The range
+    /// is empty (and starts at the insertion point) if there are none. Note it is assumed that
+    /// the slice is sorted.
+    ///
+    /// ```
+    /// use shar_search::SharTupleSearch;
+    ///
+    /// // A secondary index of (author, title, row) triples.
+    /// let by_author = [
+    ///     ("austen", "emma", 4),
+    ///     ("austen", "persuasion", 0),
+    ///     ("eliot", "middlemarch", 2),
+    ///     ("shelley", "frankenstein", 1),
+    ///     ("shelley", "the last man", 3),
+    /// ];
+    ///
+    /// let rows: Vec<_> = by_author[by_author.bl_tuple_prefix_range(&"shelley")]
+    ///     .iter()
+    ///     .map(|&(_, _, row)| row)
+    ///     .collect();
+    /// assert_eq!(rows, [1, 3]);
+    ///
+    /// assert_eq!(by_author.bl_tuple_prefix2_range(&"austen", &"emma"), 0..1);
+    /// assert!(by_author.bl_tuple_prefix_range(&"brontë").is_empty());
+    /// ```
+    fn bl_tuple_prefix_range(&self, first: &T::First) -> Range<usize>
+    where
+        T::First: Ord;
+
+    /// Returns the range of indices of all tuples whose first two fields equal `first` and
+    /// `second`. The range is empty (and starts at the insertion point) if there are none.
+    /// Note it is assumed that the slice is sorted.
+    fn bl_tuple_prefix2_range(&self, first: &T::First, second: &T::Second) -> Range<usize>
+    where
+        T::First: Ord,
+        T::Second: Ord;
+}
+
+impl<T: TupleKey> SharTupleSearch<T> for [T] {
+    fn bl_tuple_prefix_range(&self, first: &T::First) -> Range<usize>
+    where
+        T::First: Ord,
+    {
+        self.bl_equal_range_by(|t| t.first().cmp(first))
+    }
+
+    fn bl_tuple_prefix2_range(&self, first: &T::First, second: &T::Second) -> Range<usize>
+    where
+        T::First: Ord,
+        T::Second: Ord,
+    {
+        self.bl_equal_range_by(|t| t.first().cmp(first).then_with(|| t.second().cmp(second)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SharTupleSearch;
+
+    #[test]
+    fn test_pairs() {
+        let pairs = [(1, 'a'), (1, 'c'), (3, 'a'), (3, 'b'), (3, 'b'), (7, 'z')];
+
+        assert_eq!(pairs.bl_tuple_prefix_range(&1), 0..2);
+        assert_eq!(pairs.bl_tuple_prefix_range(&3), 2..5);
+        assert_eq!(pairs.bl_tuple_prefix_range(&7), 5..6);
+
+        // Absent prefixes, before, between and after the tuples.
+        assert_eq!(pairs.bl_tuple_prefix_range(&0), 0..0);
+        assert_eq!(pairs.bl_tuple_prefix_range(&2), 2..2);
+        assert_eq!(pairs.bl_tuple_prefix_range(&8), 6..6);
+
+        assert_eq!(pairs.bl_tuple_prefix2_range(&3, &'b'), 3..5);
+        assert_eq!(pairs.bl_tuple_prefix2_range(&1, &'b'), 1..1);
+
+        let empty: [(u32, u32); 0] = [];
+        assert_eq!(empty.bl_tuple_prefix_range(&1), 0..0);
+    }
+
+    #[test]
+    fn test_wider_tuples() {
+        let triples = [
+            (0, 0, "x"),
+            (0, 1, "y"),
+            (2, 0, "w"),
+            (2, 0, "z"),
+            (2, 5, "v"),
+        ];
+        assert_eq!(triples.bl_tuple_prefix_range(&0), 0..2);
+        assert_eq!(triples.bl_tuple_prefix_range(&2), 2..5);
+        assert_eq!(triples.bl_tuple_prefix2_range(&2, &0), 2..4);
+        assert_eq!(triples.bl_tuple_prefix2_range(&2, &9), 5..5);
+
+        let quads: Vec<(u8, u8, u8, u8)> = (0..=255).map(|i| (i / 64, i / 16, i / 4, i)).collect();
+        for k1 in 0..4 {
+            let range = quads.bl_tuple_prefix_range(&k1);
+            assert_eq!(range, usize::from(k1) * 64..usize::from(k1) * 64 + 64);
+            for k2 in k1 * 4..k1 * 4 + 4 {
+                let start = usize::from(k2) * 16;
+                assert_eq!(quads.bl_tuple_prefix2_range(&k1, &k2), start..start + 16);
+            }
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! Partition points of monotone predicates over all of `u64`, for searches with no known upper
+//! bound.
+//!
+//! [`partition_point_unbounded`] gallops through `1, 2, 4, …` until the predicate is `false`,
+//! then descends within the last doubling like the branchless search. Finding a partition point
+//! `p` takes about `2 log2(p)` evaluations of the predicate, however large the domain, so this
+//! suits predicates that are expensive to evaluate, such as asking a server whether it accepts
+//! a sequence number.
+//!
+//! ```
+//! use shar_search::unbounded::partition_point_unbounded;
+//!
+//! // The length of a collection that can only be asked whether an index exists.
+//! let hidden = [0_u8; 1234];
+//! let len = partition_point_unbounded(|i| (i as usize) < hidden.len());
+//! assert_eq!(len, 1234);
+//! ```
+
+use core::convert::Infallible;
+
+/// Returns the first `x` for which `pred` is `false`. Note it is assumed that `pred` is
+/// monotone: `true` up to some point, and `false` from there on. Returns 0 if `pred(0)` is
+/// `false`.
+///
+/// # Panics
+///
+/// Panics if `pred` is `true` at every `u64`, including `u64::MAX`. See
+/// [`checked_partition_point_unbounded`] to handle that case.
+pub fn partition_point_unbounded<P>(pred: P) -> u64
+where
+    P: FnMut(u64) -> bool,
+{
+    checked_partition_point_unbounded(pred).expect("the predicate is true at every u64")
+}
+
+/// Returns the first `x` for which `pred` is `false`, or `None` if it is `true` at every `u64`,
+/// including `u64::MAX`. See [`partition_point_unbounded`].
+///
+/// ```
+/// use shar_search::unbounded::checked_partition_point_unbounded;
+///
+/// assert_eq!(checked_partition_point_unbounded(|x| x < u64::MAX), Some(u64::MAX));
+/// assert_eq!(checked_partition_point_unbounded(|_| true), None);
+/// ```
+pub fn checked_partition_point_unbounded<P>(mut pred: P) -> Option<u64>
+where
+    P: FnMut(u64) -> bool,
+{
+    match try_partition_point_unbounded(|x| Ok::<_, Infallible>(pred(x))) {
+        Ok(point) => point,
+    }
+}
+
+/// Returns the first `x` for which the fallible predicate `pred` is `Ok(false)`, or `Ok(None)`
+/// if it is `Ok(true)` at every `u64`. The search stops at the first error, which is returned.
+/// See [`partition_point_unbounded`].
+///
+/// # Errors
+///
+/// Returns the first error `pred` returns.
+///
+/// ```
+/// use shar_search::unbounded::try_partition_point_unbounded;
+///
+/// let accepts = |seq: u64| if seq < 1 << 40 { Ok(seq < 500) } else { Err("timed out") };
+/// assert_eq!(try_partition_point_unbounded(accepts), Ok(Some(500)));
+///
+/// let slow = |seq: u64| if seq < 64 { Ok(true) } else { Err("timed out") };
+/// assert_eq!(try_partition_point_unbounded(slow), Err("timed out"));
+/// ```
+pub fn try_partition_point_unbounded<P, E>(mut pred: P) -> Result<Option<u64>, E>
+where
+    P: FnMut(u64) -> Result<bool, E>,
+{
+    if !pred(0)? {
+        return Ok(Some(0));
+    }
+
+    // `pred(lo)` is true, and the partition point is at most `lo + width`, since `pred` is
+    // false there or it is past `u64::MAX`.
+    let mut lo = 0_u64;
+    let mut width = 1_u64;
+    loop {
+        match lo.checked_add(width) {
+            Some(probe) if pred(probe)? => {
+                lo = probe;
+                width = lo;
+            }
+            _ => break,
+        }
+    }
+
+    // `width` is a power of two, so this halves it down to 1. The probes stay below
+    // `lo + width`, which is at most `2^64`.
+    while width > 1 {
+        width /= 2;
+        let mid = lo + width;
+        lo = if pred(mid)? { mid } else { lo };
+    }
+
+    // Overflows only if `pred(u64::MAX)` is true.
+    Ok(lo.checked_add(1))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        checked_partition_point_unbounded, partition_point_unbounded, try_partition_point_unbounded,
+    };
+
+    /// Returns the partition point found for a flip at `point`, checking that the search
+    /// evaluates the predicate only at distinct points, and few of them.
+    fn search(point: u64) -> Option<u64> {
+        let mut probes = Vec::new();
+        let found = checked_partition_point_unbounded(|x| {
+            probes.push(x);
+            x < point
+        });
+
+        let count = probes.len();
+        probes.sort_unstable();
+        probes.dedup();
+        assert_eq!(probes.len(), count, "repeated probes for {point}");
+        assert!(count <= 2 * 64, "{count} probes for {point}");
+        found
+    }
+
+    #[test]
+    fn test_flip_points() {
+        assert_eq!(search(0), Some(0));
+        assert_eq!(search(1), Some(1));
+        assert_eq!(search(2), Some(2));
+        assert_eq!(search(3), Some(3));
+        for shift in 0..64 {
+            let power = 1_u64 << shift;
+            for point in [power - 1, power, power + 1] {
+                assert_eq!(search(point), Some(point));
+            }
+        }
+        for point in [u64::MAX - 2, u64::MAX - 1, u64::MAX] {
+            assert_eq!(search(point), Some(point));
+        }
+        assert_eq!(
+            partition_point_unbounded(|x| x < 1_000_000_007),
+            1_000_000_007
+        );
+    }
+
+    #[test]
+    fn test_never_false() {
+        assert_eq!(checked_partition_point_unbounded(|_| true), None);
+        assert_eq!(
+            try_partition_point_unbounded(|_| Ok::<_, ()>(true)),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "the predicate is true at every u64")]
+    fn test_never_false_panics() {
+        partition_point_unbounded(|_| true);
+    }
+
+    #[test]
+    fn test_false_at_zero() {
+        let mut probes = 0;
+        let point = partition_point_unbounded(|_| {
+            probes += 1;
+            false
+        });
+        assert_eq!((point, probes), (0, 1));
+    }
+
+    #[test]
+    fn test_errors() {
+        // Errors while galloping, and while descending.
+        let gallop = |x: u64| if x < 100 { Ok(true) } else { Err(x) };
+        assert_eq!(try_partition_point_unbounded(gallop), Err(128));
+        let descend = |x: u64| if x == 96 { Err(x) } else { Ok(x < 100) };
+        assert_eq!(try_partition_point_unbounded(descend), Err(96));
+        assert_eq!(
+            try_partition_point_unbounded(|_| Err::<bool, _>(())),
+            Err(())
+        );
+    }
+
+    #[test]
+    fn test_every_small_point() {
+        for point in 0..=1100 {
+            assert_eq!(search(point), Some(point));
+        }
+    }
+}
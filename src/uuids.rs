@@ -0,0 +1,300 @@
+//! Time-range queries over sorted version 7 UUIDs, enabled by the `uuid` feature.
+//!
+//! A version 7 UUID starts with the Unix time in milliseconds it was created at, as a 48-bit
+//! big-endian number, so sorting UUIDv7s sorts them by creation time, and a sorted slice of them
+//! doubles as a time index. The bits after the timestamp are the version (`0111`), 12 bits that
+//! are random or order IDs created in the same millisecond, the variant (`10`), and 62 more bits.
+//!
+//! [`uuid_range_for_millis`] returns the smallest and largest UUIDv7s that can be created over a
+//! span of milliseconds, and [`search_created_between`] uses them to find the IDs created within
+//! a range of times with two binary searches.
+//!
+//! ```
+//! use shar_search::uuids::search_created_between;
+//! use uuid::{Builder, Uuid};
+//!
+//! let at = |millis: u64, tiebreak: u8| {
+//!     Builder::from_unix_timestamp_millis(millis, &[tiebreak; 10]).into_uuid()
+//! };
+//! let ids: Vec<Uuid> = vec![at(1_000, 9), at(1_500, 0), at(1_500, 255), at(2_000, 3)];
+//! assert!(ids.is_sorted());
+//!
+//! assert_eq!(search_created_between(&ids, 1_500..2_000), &ids[1..3]);
+//! assert_eq!(search_created_between(&ids, 1_001..=2_000), &ids[1..]);
+//! assert!(search_created_between(&ids, 2_001..).is_empty());
+//! ```
+//!
+//! # Other versions
+//!
+//! The searches compare whole UUIDs, so a UUID of another version in the slice is returned when
+//! it sorts between the bounds, whatever time its first 48 bits seem to give. Use
+//! [`first_non_v7`] to check that a slice only holds UUIDv7s.
+
+use core::ops::{Bound, RangeBounds};
+
+use uuid::{Uuid, Variant};
+
+use crate::SharBinarySearch;
+
+/// One past the largest timestamp a UUIDv7 can hold, in milliseconds.
+const MILLIS_END: u64 = 1 << 48;
+
+/// The version and variant bits of a UUIDv7.
+const V7_BITS: u128 = (0x7 << 76) | (0b10 << 62);
+
+/// The bits after the timestamp that aren't the version or the variant.
+const RANDOM_BITS: u128 = (0xfff << 64) | ((1 << 62) - 1);
+
+/// Returns the smallest UUIDv7 created at `millis`, or `None` if it doesn't fit in 48 bits.
+fn min_v7(millis: u64) -> Option<u128> {
+    (millis < MILLIS_END).then_some(((millis as u128) << 80) | V7_BITS)
+}
+
+/// Returns the largest UUIDv7 created at `millis`, or `None` if it doesn't fit in 48 bits.
+fn max_v7(millis: u64) -> Option<u128> {
+    min_v7(millis).map(|min| min | RANDOM_BITS)
+}
+
+/// Returns the smallest UUIDv7 that can be created at `start_ms` and the largest that can be
+/// created at `end_ms`, both in milliseconds since the Unix epoch. The UUIDv7s created from
+/// `start_ms` through `end_ms`, inclusive, are exactly those from the first through the second.
+///
+/// ```
+/// use shar_search::uuids::uuid_range_for_millis;
+///
+/// let (min, max) = uuid_range_for_millis(0x0123_4567_89ab, 0x0123_4567_89ab);
+/// assert_eq!(min.to_string(), "01234567-89ab-7000-8000-000000000000");
+/// assert_eq!(max.to_string(), "01234567-89ab-7fff-bfff-ffffffffffff");
+/// ```
+///
+/// # Panics
+///
+/// Panics if either timestamp is `2^48` or more, which a UUIDv7 can't hold.
+pub fn uuid_range_for_millis(start_ms: u64, end_ms: u64) -> (Uuid, Uuid) {
+    match (min_v7(start_ms), max_v7(end_ms)) {
+        (Some(min), Some(max)) => (Uuid::from_u128(min), Uuid::from_u128(max)),
+        _ => panic!("timestamps of UUIDv7s must be less than 2^48 milliseconds"),
+    }
+}
+
+/// Returns the UUIDs of `ids` that were created within `range`, in milliseconds since the Unix
+/// epoch. Note it is assumed that `ids` is sorted.
+///
+/// Every UUIDv7 created at an included bound is returned, whatever its other bits, and none
+/// created at an excluded one. Times past what a UUIDv7 can hold are after every UUIDv7. See the
+/// [module documentation](self#other-versions) for UUIDs of other versions.
+pub fn search_created_between<R>(ids: &[Uuid], range: R) -> &[Uuid]
+where
+    R: RangeBounds<u64>,
+{
+    // The index of the first ID at least `min`, or the end if there is no such UUIDv7.
+    let first_at_least = |min: Option<u128>| {
+        min.map_or(ids.len(), |min| {
+            ids.bl_partition_point(|id| id.as_u128() < min)
+        })
+    };
+    let first_after = |max: Option<u128>| {
+        max.map_or(ids.len(), |max| {
+            ids.bl_partition_point(|id| id.as_u128() <= max)
+        })
+    };
+
+    let start = match range.start_bound() {
+        Bound::Included(&start) => first_at_least(min_v7(start)),
+        Bound::Excluded(&start) => first_after(max_v7(start)),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => first_after(max_v7(end)),
+        Bound::Excluded(&end) => first_at_least(min_v7(end)),
+        Bound::Unbounded => ids.len(),
+    };
+
+    &ids[start..end.max(start)]
+}
+
+/// Returns the time `id` was created at in milliseconds since the Unix epoch, or `None` if it
+/// isn't a UUIDv7.
+///
+/// ```
+/// use shar_search::uuids::created_millis;
+/// use uuid::{Builder, Uuid};
+///
+/// let id = Builder::from_unix_timestamp_millis(1_700_000_000_000, &[7; 10]).into_uuid();
+/// assert_eq!(created_millis(&id), Some(1_700_000_000_000));
+/// assert_eq!(created_millis(&Uuid::nil()), None);
+/// ```
+pub fn created_millis(id: &Uuid) -> Option<u64> {
+    is_v7(id).then_some((id.as_u128() >> 80) as u64)
+}
+
+fn is_v7(id: &Uuid) -> bool {
+    id.get_version_num() == 7 && id.get_variant() == Variant::RFC4122
+}
+
+/// Returns the index of the first UUID of `ids` that isn't a UUIDv7 (of the RFC 9562 variant),
+/// or `None` if they all are.
+pub fn first_non_v7(ids: &[Uuid]) -> Option<usize> {
+    ids.iter().position(|id| !is_v7(id))
+}
+
+#[cfg(test)]
+mod test {
+    use core::ops::Bound;
+
+    use uuid::{Builder, Uuid};
+
+    use super::{created_millis, first_non_v7, search_created_between, uuid_range_for_millis};
+    use crate::test_util::XorShift;
+
+    /// Returns a UUIDv7 created at `millis`, with `rand_a` in the 12 bits after the version and
+    /// `rand_b` in the 62 bits after the variant.
+    fn v7(millis: u64, rand_a: u16, rand_b: u64) -> Uuid {
+        let bits = ((millis as u128) << 80)
+            | (0x7 << 76)
+            | ((rand_a as u128 & 0xfff) << 64)
+            | (0b10 << 62)
+            | (rand_b as u128 & ((1 << 62) - 1));
+        Uuid::from_u128(bits)
+    }
+
+    #[test]
+    fn test_range_for_millis() {
+        let (min, max) = uuid_range_for_millis(1_000, 2_000);
+        assert_eq!(min, v7(1_000, 0, 0));
+        assert_eq!(max, v7(2_000, 0xfff, u64::MAX));
+        assert_eq!(created_millis(&min), Some(1_000));
+        assert_eq!(created_millis(&max), Some(2_000));
+        assert_eq!(first_non_v7(&[min, max]), None);
+
+        let last = (1 << 48) - 1;
+        let (min, max) = uuid_range_for_millis(0, last);
+        assert_eq!(created_millis(&min), Some(0));
+        assert_eq!(created_millis(&max), Some(last));
+
+        // The builder in `uuid` agrees on where a millisecond starts and ends.
+        let built = Builder::from_unix_timestamp_millis(1_000, &[0; 10]).into_uuid();
+        assert_eq!(built, uuid_range_for_millis(1_000, 1_000).0);
+        let built = Builder::from_unix_timestamp_millis(1_000, &[0xff; 10]).into_uuid();
+        assert_eq!(built, uuid_range_for_millis(1_000, 1_000).1);
+    }
+
+    #[test]
+    #[should_panic(expected = "timestamps of UUIDv7s must be less than 2^48 milliseconds")]
+    fn test_range_for_millis_too_late() {
+        uuid_range_for_millis(0, 1 << 48);
+    }
+
+    #[test]
+    fn test_boundaries_and_sub_millisecond_bits() {
+        // The extremes of the bits after the timestamp, at adjacent milliseconds.
+        let ids = [
+            v7(999, 0xfff, u64::MAX),
+            v7(1_000, 0, 0),
+            v7(1_000, 0, u64::MAX),
+            v7(1_000, 0x800, 5),
+            v7(1_000, 0xfff, u64::MAX),
+            v7(1_001, 0, 0),
+            v7(1_001, 0xfff, 0),
+        ];
+        assert!(ids.is_sorted());
+
+        assert_eq!(search_created_between(&ids, 1_000..1_001), &ids[1..5]);
+        assert_eq!(search_created_between(&ids, 1_000..=1_000), &ids[1..5]);
+        assert_eq!(search_created_between(&ids, 1_000..=1_001), &ids[1..]);
+        assert_eq!(search_created_between(&ids, ..1_000), &ids[..1]);
+        assert_eq!(search_created_between(&ids, ..=1_000), &ids[..5]);
+        assert_eq!(search_created_between(&ids, 1_001..), &ids[5..]);
+        assert_eq!(
+            search_created_between(&ids, (Bound::Excluded(999), Bound::Unbounded)),
+            &ids[1..]
+        );
+        assert!(search_created_between(&ids, 1_000..1_000).is_empty());
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 1_001..1_000;
+        assert!(search_created_between(&ids, reversed).is_empty());
+        assert_eq!(search_created_between(&ids, ..), &ids[..]);
+
+        let empty: [Uuid; 0] = [];
+        assert!(search_created_between(&empty, 0..u64::MAX).is_empty());
+    }
+
+    #[test]
+    fn test_times_past_48_bits() {
+        let last = (1 << 48) - 1;
+        let ids = [v7(0, 0, 0), v7(last, 0, 0), v7(last, 0xfff, u64::MAX)];
+        assert_eq!(search_created_between(&ids, last..), &ids[1..]);
+        assert_eq!(search_created_between(&ids, last..=u64::MAX), &ids[1..]);
+        assert!(search_created_between(&ids, 1 << 48..).is_empty());
+        assert!(
+            search_created_between(&ids, (Bound::Excluded(u64::MAX), Bound::Unbounded)).is_empty()
+        );
+        assert_eq!(search_created_between(&ids, ..u64::MAX), &ids[..]);
+    }
+
+    #[test]
+    fn test_other_versions() {
+        // A nil UUID and a v4 UUID sort by their bits, like any other.
+        let v4 = Uuid::from_u128((1_000 << 80) | (0x4 << 76) | (0b10 << 62));
+        let v8 = Uuid::from_u128((1_000 << 80) | (0x8 << 76) | (0b10 << 62));
+        let ids = [
+            Uuid::nil(),
+            v7(500, 0, 0),
+            v4,
+            v7(1_000, 1, 1),
+            v8,
+            v7(1_001, 0, 0),
+            Uuid::max(),
+        ];
+        assert!(ids.is_sorted());
+
+        assert_eq!(first_non_v7(&ids), Some(0));
+        assert_eq!(first_non_v7(&ids[1..]), Some(1));
+        assert_eq!(created_millis(&v4), None);
+        assert_eq!(created_millis(&Uuid::max()), None);
+
+        // Version 4 and 8 IDs whose first 48 bits read 1000 sort just outside UUIDv7s created
+        // at that millisecond, so they aren't returned for it.
+        assert_eq!(search_created_between(&ids, 1_000..=1_000), &ids[3..4]);
+        // But they are inside wider ranges, as is the nil UUID in an unbounded one.
+        assert_eq!(search_created_between(&ids, 500..=1_001), &ids[1..6]);
+        assert_eq!(search_created_between(&ids, ..1_000), &ids[..3]);
+    }
+
+    #[test]
+    fn test_against_filtering() {
+        let mut rng = XorShift::new(199);
+        for _ in 0..300 {
+            let len = rng.below(60);
+            let mut ids: Vec<Uuid> = (0..len)
+                .map(|_| {
+                    let millis = 1_000 + rng.below(20);
+                    let rand_a = match rng.below(3) {
+                        0 => 0,
+                        1 => 0xfff,
+                        _ => rng.below(0x1000) as u16,
+                    };
+                    v7(millis, rand_a, rng.below(u64::MAX))
+                })
+                .collect();
+            ids.sort();
+
+            for _ in 0..20 {
+                let (a, b) = (995 + rng.below(30), 995 + rng.below(30));
+                let expected: Vec<Uuid> = ids
+                    .iter()
+                    .filter(|id| (a..b).contains(&created_millis(id).unwrap()))
+                    .copied()
+                    .collect();
+                assert_eq!(search_created_between(&ids, a..b), expected, "{a}..{b}");
+
+                let expected: Vec<Uuid> = ids
+                    .iter()
+                    .filter(|id| (a..=b).contains(&created_millis(id).unwrap()))
+                    .copied()
+                    .collect();
+                assert_eq!(search_created_between(&ids, a..=b), expected, "{a}..={b}");
+            }
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! Checks that slices are sorted, fast enough for validating very large inputs.
+//!
+//! Rather than stopping at the first inversion, which forces comparing one pair at a time,
+//! these checks compare pairs in fixed-size chunks, combining the results of each chunk without
+//! branching, and only stop between chunks. For primitive integers, the compiler turns each
+//! chunk into a few SIMD comparisons. Recent versions of [`slice::is_sorted`] do the same for
+//! `Ord` types, so for those the gain is mainly with older toolchains and custom comparators.
+//!
+//! Floats have no such fast path in the standard library: checking them under their total order
+//! means [`slice::is_sorted_by`] with [`f64::total_cmp`], one pair at a time. Here they are
+//! compared as integer keys derived from their bits, which vectorize like any integers. On
+//! x86-64, 64-bit integer comparisons need SSE4.2, so enable it (e.g. with `-C
+//! target-cpu=native`) to see the difference for `f64`: it is about three times faster then.
+//!
+//! Once a chunk has an inversion, it is scanned pair by pair to find the first one, so every
+//! function here returns exactly what the plain pairwise loop would.
+//!
+//! ```
+//! use shar_search::validate::{first_unsorted_at, is_sorted_f64, is_sorted_fast};
+//!
+//! let values: Vec<u64> = (0..1000).collect();
+//! assert!(is_sorted_fast(&values));
+//! assert_eq!(first_unsorted_at(&[1, 2, 5, 3, 4]), Some(3));
+//! assert!(is_sorted_f64(&[-0.0, 0.0, 1.0, f64::NAN]));
+//! ```
+
+/// The number of pairs compared together, before checking whether any was out of order.
+const CHUNK: usize = 32;
+
+/// Returns the index of the first element that is not ordered correctly relative to its
+/// predecessor, where `in_order(prev, next)` decides whether a pair is correctly ordered.
+pub(crate) fn first_unsorted_by<T, F>(slice: &[T], mut in_order: F) -> Option<usize>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    if slice.len() < 2 {
+        return None;
+    }
+    let (prevs, nexts) = (&slice[..slice.len() - 1], &slice[1..]);
+
+    let mut start = 0;
+    for (prev, next) in prevs.chunks_exact(CHUNK).zip(nexts.chunks_exact(CHUNK)) {
+        // Fixed-length arrays, and `&` rather than `&&`, so the whole chunk is compared without
+        // bounds checks or branches.
+        let (prev, next): (&[T; CHUNK], &[T; CHUNK]) =
+            (prev.try_into().unwrap(), next.try_into().unwrap());
+        let mut sorted = true;
+        for i in 0..CHUNK {
+            sorted &= in_order(&prev[i], &next[i]);
+        }
+        if !sorted {
+            break;
+        }
+        start += CHUNK;
+    }
+
+    // The chunk with the inversion, or the pairs left over after the last full chunk.
+    slice[start..]
+        .windows(2)
+        .position(|pair| !in_order(&pair[0], &pair[1]))
+        .map(|index| start + index + 1)
+}
+
+/// Returns whether `slice` is sorted in non-decreasing order, like [`slice::is_sorted`] but
+/// checked in chunks; see the [module documentation](self).
+pub fn is_sorted_fast<T: Ord>(slice: &[T]) -> bool {
+    first_unsorted_at(slice).is_none()
+}
+
+/// Returns whether `slice` is sorted in strictly increasing order, so also has no duplicates.
+pub fn is_strictly_sorted<T: Ord>(slice: &[T]) -> bool {
+    first_unsorted_by(slice, |a, b| a < b).is_none()
+}
+
+/// Returns whether `slice` is sorted, where `in_order(a, b)` returns whether `a` may come
+/// before `b`, like [`slice::is_sorted_by`].
+pub fn is_sorted_by<T, F>(slice: &[T], in_order: F) -> bool
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    first_unsorted_by(slice, in_order).is_none()
+}
+
+/// Returns the index of the first element that is less than its predecessor, or `None` if
+/// `slice` is sorted in non-decreasing order. This is the index reported by
+/// [`UnsortedError`](crate::UnsortedError).
+pub fn first_unsorted_at<T: Ord>(slice: &[T]) -> Option<usize> {
+    first_unsorted_by(slice, |a, b| a <= b)
+}
+
+macro_rules! validate_float {
+    ($($float:ty => $bits:ty, $unsigned:ty, $key:ident, $is_sorted:ident, $first_unsorted_at:ident;)*) => {$(
+        /// Maps a float to an integer with the same order as its total order: flipping the
+        /// magnitude bits of negative floats makes them compare like two's complement.
+        #[inline]
+        fn $key(x: &$float) -> $bits {
+            let bits = x.to_bits() as $bits;
+            bits ^ (((bits >> (<$bits>::BITS - 1)) as $unsigned) >> 1) as $bits
+        }
+
+        #[doc = concat!("Returns whether a slice of `", stringify!($float), "` is sorted under")]
+        #[doc = concat!("[`", stringify!($float), "::total_cmp`], the order that sorts `-0.0`")]
+        /// before `0.0` and positive NaNs after every number.
+        pub fn $is_sorted(slice: &[$float]) -> bool {
+            $first_unsorted_at(slice).is_none()
+        }
+
+        #[doc = concat!("Returns the index of the first `", stringify!($float), "` that is less")]
+        #[doc = concat!("than its predecessor under [`", stringify!($float), "::total_cmp`], or")]
+        /// `None` if the slice is sorted that way.
+        pub fn $first_unsorted_at(slice: &[$float]) -> Option<usize> {
+            first_unsorted_by(slice, |a, b| $key(a) <= $key(b))
+        }
+    )*};
+}
+
+validate_float! {
+    f32 => i32, u32, total_key_f32, is_sorted_f32, first_unsorted_at_f32;
+    f64 => i64, u64, total_key_f64, is_sorted_f64, first_unsorted_at_f64;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        reference::{self, assert_matches_reference, inputs},
+        test_util::XorShift,
+    };
+
+    #[test]
+    fn test_inversion_positions() {
+        for len in [2, 3, CHUNK, CHUNK + 1, CHUNK + 2, 3 * CHUNK + 7, 1000] {
+            let sorted: Vec<u64> = (0..len as u64).collect();
+            assert!(is_sorted_fast(&sorted));
+            assert!(is_strictly_sorted(&sorted));
+            assert_eq!(first_unsorted_at(&sorted), None);
+
+            // Every position, including the very first and very last pairs.
+            for at in 1..len {
+                let mut slice = sorted.clone();
+                slice[at] = 0;
+                slice[at - 1] = u64::MAX;
+                assert_eq!(first_unsorted_at(&slice), Some(at), "len {len}, at {at}");
+                assert_eq!(
+                    first_unsorted_at(&slice),
+                    reference::first_unsorted_at(&slice)
+                );
+                assert!(!is_sorted_fast(&slice));
+            }
+        }
+
+        let empty: [u8; 0] = [];
+        assert!(is_sorted_fast(&empty) && is_strictly_sorted(&empty) && is_sorted_fast(&[1]));
+    }
+
+    #[test]
+    fn test_against_reference() {
+        let mut rng = XorShift::new(159);
+        // Sorted slices with a few swaps, or none.
+        let slices = inputs::sorted_slices(159).map(move |mut slice| {
+            let len = slice.len() as u64;
+            for _ in 0..rng.below(3) {
+                if len > 0 {
+                    let (i, j) = (rng.below(len), rng.below(len));
+                    slice.swap(i as usize, j as usize);
+                }
+            }
+            slice
+        });
+
+        assert_matches_reference!(
+            for slice in slices =>
+            (
+                first_unsorted_at(&slice),
+                is_sorted_fast(&slice),
+                is_strictly_sorted(&slice),
+                is_sorted_by(&slice, |a, b| a >= b),
+            ),
+            (
+                reference::first_unsorted_at(&slice),
+                slice.is_sorted(),
+                reference::first_unsorted_by(&slice, |a, b| a < b).is_none(),
+                reference::first_unsorted_by(&slice, |a, b| a >= b).is_none(),
+            ),
+        );
+    }
+
+    #[test]
+    fn test_floats_total_order() {
+        let sorted = [
+            -f64::NAN,
+            f64::NEG_INFINITY,
+            -1.5,
+            -0.0,
+            0.0,
+            f64::MIN_POSITIVE,
+            2.0,
+            f64::INFINITY,
+            f64::NAN,
+        ];
+        assert!(sorted.is_sorted_by(|a, b| a.total_cmp(b).is_le()));
+        assert!(is_sorted_f64(&sorted));
+        assert!(is_sorted_f64(&[f64::NAN, f64::NAN]));
+
+        // Under the total order, `0.0` is not less than or equal to `-0.0`, and a NaN is not
+        // less than a number.
+        assert_eq!(first_unsorted_at_f64(&[1.0, 0.0, -0.0]), Some(1));
+        assert_eq!(first_unsorted_at_f64(&[0.0, -0.0]), Some(1));
+        assert_eq!(first_unsorted_at_f64(&[1.0, f64::NAN, 2.0]), Some(2));
+
+        // Each sorted slice on its own, and followed by another, which is unsorted unless one
+        // of them is empty or they happen to line up.
+        let slices = inputs::f32_slice_pairs(1159).flat_map(|(a, b)| {
+            let joined = [a.as_slice(), &b].concat();
+            [a, joined]
+        });
+        assert_matches_reference!(
+            for slice in slices =>
+            first_unsorted_at_f32(&slice),
+            reference::first_unsorted_by(&slice, |a, b| a.total_cmp(b).is_le()),
+        );
+    }
+}
@@ -0,0 +1,362 @@
+//! Sorted insertion into plain vectors.
+
+use alloc::vec::Vec;
+use core::{borrow::Borrow, cmp::Ordering};
+
+use crate::{raw, SharBinarySearch};
+
+/// Extension trait for keeping a plain [`Vec`] sorted as elements are inserted, using the
+/// branchless search to find each position.
+///
+/// Following the crate's leftmost convention, an element is inserted *before* any existing
+/// elements equal to it, at the index [`bl_binary_search`](SharBinarySearch::bl_binary_search)
+/// would return for it. Each insertion shifts the elements after it, so it is `O(n)`; for many
+/// insertions at once, prefer [`SortedVec::insert_many`](crate::SortedVec::insert_many).
+///
+/// ```
+/// use shar_search::VecSortedExt;
+///
+/// let mut scores = vec![10, 30];
+/// assert_eq!(scores.insert_sorted(20), 1);
+/// assert_eq!(scores.insert_sorted(30), 2);
+/// assert_eq!(scores, [10, 20, 30, 30]);
+///
+/// assert_eq!(scores.insert_sorted_unique(20), Err(1));
+/// assert_eq!(scores.insert_sorted_unique(40), Ok(4));
+/// ```
+pub trait VecSortedExt<T> {
+    /// Inserts `item`, keeping the vector sorted, and returns its index. The item is placed
+    /// before any equal elements. Note it is assumed that the vector is sorted.
+    fn insert_sorted(&mut self, item: T) -> usize
+    where
+        T: Ord;
+
+    /// Inserts `item`, keeping the vector sorted by the comparator `compare`, and returns its
+    /// index. See [`insert_sorted`](VecSortedExt::insert_sorted).
+    fn insert_sorted_by<F>(&mut self, item: T, compare: F) -> usize
+    where
+        F: FnMut(&T, &T) -> Ordering;
+
+    /// Inserts `item`, keeping the vector sorted by the key `f` extracts, and returns its index.
+    /// The item is placed before any elements with an equal key. See
+    /// [`insert_sorted`](VecSortedExt::insert_sorted).
+    fn insert_sorted_by_key<K, F>(&mut self, item: T, f: F) -> usize
+    where
+        K: Ord,
+        F: FnMut(&T) -> K;
+
+    /// Inserts `item` if the vector holds no element equal to it, keeping the vector sorted.
+    /// Returns `Ok` with the new element's index, or `Err` with the index of the first existing
+    /// equal element, in which case `item` is dropped. Note it is assumed that the vector is
+    /// sorted.
+    fn insert_sorted_unique(&mut self, item: T) -> Result<usize, usize>
+    where
+        T: Ord;
+
+    /// Returns the value paired with `key` in this vector of key-value pairs, first inserting
+    /// `(key, default())` if there is none, keeping the vector sorted by key. This takes one
+    /// search, and `default` is only called on a miss. Note it is assumed that the vector is
+    /// sorted by key; if several pairs have the key, the first is returned.
+    ///
+    /// This is only callable on a `Vec<(K, V)>`, which the `AsMut` bound expresses.
+    ///
+    /// ```
+    /// use shar_search::VecSortedExt;
+    ///
+    /// let mut counts: Vec<(&str, u32)> = Vec::new();
+    /// for word in "the cat and the hat".split(' ') {
+    ///     *counts.get_or_insert_sorted_with(word, || 0) += 1;
+    /// }
+    /// assert_eq!(counts, [("and", 1), ("cat", 1), ("hat", 1), ("the", 2)]);
+    /// ```
+    fn get_or_insert_sorted_with<'a, K, V, F>(&'a mut self, key: K, default: F) -> &'a mut V
+    where
+        Self: AsMut<Vec<(K, V)>>,
+        K: Ord + 'a,
+        V: 'a,
+        F: FnOnce() -> V;
+
+    /// Returns the first element whose key (as extracted by `f`) equals `key`, first inserting
+    /// `default()` if there is none, keeping the vector sorted by key. This takes one search,
+    /// and `default` is only called on a miss. Note it is assumed that the vector is sorted by
+    /// the key.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if the element returned by `default` does not have key `key`.
+    fn get_or_insert_sorted_by_key_with<K, F, D>(&mut self, key: &K, f: F, default: D) -> &mut T
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+        D: FnOnce() -> T;
+
+    /// Removes every element equal to one of `keys`, returning how many were removed. Note it is
+    /// assumed that both the vector and `keys` are sorted.
+    ///
+    /// All occurrences of each listed key are removed, and listing a key more than once is
+    /// harmless. The vector is compacted in a single pass that gallops over the elements
+    /// between keys, so this takes `O(m log(n / m))` comparisons and `O(n)` moves, rather than
+    /// the `O(m * n)` moves of repeated removals.
+    ///
+    /// ```
+    /// use shar_search::VecSortedExt;
+    ///
+    /// let mut ids = vec![1, 2, 2, 3, 5, 8, 13];
+    /// assert_eq!(ids.remove_sorted_keys(&[2, 4, 8]), 3);
+    /// assert_eq!(ids, [1, 3, 5, 13]);
+    /// ```
+    fn remove_sorted_keys<Q>(&mut self, keys: &[Q]) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord;
+}
+
+impl<T> VecSortedExt<T> for Vec<T> {
+    fn insert_sorted(&mut self, item: T) -> usize
+    where
+        T: Ord,
+    {
+        self.insert_sorted_by(item, T::cmp)
+    }
+
+    fn insert_sorted_by<F>(&mut self, item: T, mut compare: F) -> usize
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let index = self.bl_lower_bound_by(|p| compare(p, &item));
+        self.insert(index, item);
+        index
+    }
+
+    fn insert_sorted_by_key<K, F>(&mut self, item: T, mut f: F) -> usize
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        let key = f(&item);
+        let index = self.bl_lower_bound_by(|p| f(p).cmp(&key));
+        self.insert(index, item);
+        index
+    }
+
+    fn insert_sorted_unique(&mut self, item: T) -> Result<usize, usize>
+    where
+        T: Ord,
+    {
+        match self.bl_binary_search(&item) {
+            Ok(index) => Err(index),
+            Err(index) => {
+                self.insert(index, item);
+                Ok(index)
+            }
+        }
+    }
+
+    fn get_or_insert_sorted_with<'a, K, V, F>(&'a mut self, key: K, default: F) -> &'a mut V
+    where
+        Self: AsMut<Vec<(K, V)>>,
+        K: Ord + 'a,
+        V: 'a,
+        F: FnOnce() -> V,
+    {
+        let pairs: &mut Vec<(K, V)> = self.as_mut();
+        let index = match pairs.bl_binary_search_by(|p| p.0.cmp(&key)) {
+            Ok(index) => index,
+            Err(index) => {
+                pairs.insert(index, (key, default()));
+                index
+            }
+        };
+        &mut pairs[index].1
+    }
+
+    fn get_or_insert_sorted_by_key_with<K, F, D>(&mut self, key: &K, mut f: F, default: D) -> &mut T
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+        D: FnOnce() -> T,
+    {
+        let index = match self.bl_binary_search_by(|p| f(p).cmp(key)) {
+            Ok(index) => index,
+            Err(index) => {
+                let item = default();
+                debug_assert!(
+                    f(&item) == *key,
+                    "the inserted element must have the searched key"
+                );
+                self.insert(index, item);
+                index
+            }
+        };
+        &mut self[index]
+    }
+
+    fn remove_sorted_keys<Q>(&mut self, keys: &[Q]) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord,
+    {
+        raw::remove_sorted_keys_by(self, keys, T::borrow)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::VecSortedExt;
+    use crate::{comparators::reverse, test_util::XorShift};
+
+    #[test]
+    fn test_empty() {
+        let mut v = Vec::new();
+        assert_eq!(v.insert_sorted(5), 0);
+        assert_eq!(v, [5]);
+
+        let mut v = Vec::new();
+        assert_eq!(v.insert_sorted_unique(5), Ok(0));
+        assert_eq!(v.insert_sorted_unique(5), Err(0));
+        assert_eq!(v, [5]);
+
+        let mut v: Vec<(u32, char)> = Vec::new();
+        assert_eq!(v.insert_sorted_by_key((1, 'a'), |p| p.0), 0);
+        let mut v: Vec<u32> = Vec::new();
+        assert_eq!(v.insert_sorted_by(1, reverse(u32::cmp)), 0);
+    }
+
+    #[test]
+    fn test_duplicate_placement() {
+        let mut v = vec![(1, 'a'), (2, 'a'), (2, 'b'), (3, 'a')];
+
+        // New elements go before the existing run of equal keys.
+        assert_eq!(v.insert_sorted_by_key((2, 'c'), |p| p.0), 1);
+        assert_eq!(v.insert_sorted_by_key((2, 'd'), |p| p.0), 1);
+        assert_eq!(v.insert_sorted_by_key((3, 'b'), |p| p.0), 5);
+        assert_eq!(
+            v,
+            [
+                (1, 'a'),
+                (2, 'd'),
+                (2, 'c'),
+                (2, 'a'),
+                (2, 'b'),
+                (3, 'b'),
+                (3, 'a')
+            ]
+        );
+
+        let mut v = vec![5, 5, 5];
+        assert_eq!(v.insert_sorted(5), 0);
+        assert_eq!(v.insert_sorted(6), 4);
+        assert_eq!(v.insert_sorted_unique(5), Err(0));
+        assert_eq!(v, [5, 5, 5, 5, 6]);
+
+        let mut v = vec![9, 7, 7, 3];
+        assert_eq!(v.insert_sorted_by(7, reverse(i32::cmp)), 1);
+        assert_eq!(v.insert_sorted_by(8, reverse(i32::cmp)), 1);
+        assert_eq!(v, [9, 8, 7, 7, 7, 3]);
+    }
+
+    #[test]
+    fn test_get_or_insert() {
+        let mut pairs = vec![(1, 'a'), (3, 'c')];
+
+        // A hit inserts nothing and never calls `default`.
+        *pairs.get_or_insert_sorted_with(3, || unreachable!()) = 'C';
+        assert_eq!(pairs, [(1, 'a'), (3, 'C')]);
+
+        assert_eq!(*pairs.get_or_insert_sorted_with(2, || 'b'), 'b');
+        assert_eq!(*pairs.get_or_insert_sorted_with(0, || 'z'), 'z');
+        assert_eq!(*pairs.get_or_insert_sorted_with(4, || 'd'), 'd');
+        assert_eq!(pairs, [(0, 'z'), (1, 'a'), (2, 'b'), (3, 'C'), (4, 'd')]);
+
+        let mut empty: Vec<(String, Vec<u32>)> = Vec::new();
+        empty
+            .get_or_insert_sorted_with("x".to_string(), Vec::new)
+            .push(1);
+        assert_eq!(empty, [("x".to_string(), vec![1])]);
+    }
+
+    #[test]
+    fn test_get_or_insert_by_key() {
+        #[derive(Debug, PartialEq)]
+        struct Account {
+            id: u32,
+            balance: i64,
+        }
+        let open = |id| move || Account { id, balance: 0 };
+
+        let mut accounts = Vec::new();
+        for (id, amount) in [(7, 10), (3, 5), (7, -4), (9, 1), (3, 2)] {
+            accounts
+                .get_or_insert_sorted_by_key_with(&id, |a: &Account| a.id, open(id))
+                .balance += amount;
+        }
+        assert_eq!(
+            accounts,
+            [
+                Account { id: 3, balance: 7 },
+                Account { id: 7, balance: 6 },
+                Account { id: 9, balance: 1 },
+            ]
+        );
+
+        let hit = accounts.get_or_insert_sorted_by_key_with(&9, |a| a.id, || unreachable!());
+        assert_eq!(hit.balance, 1);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "the inserted element must have the searched key")]
+    fn test_get_or_insert_by_key_wrong_key() {
+        let mut v = vec![(1, 'a')];
+        v.get_or_insert_sorted_by_key_with(&2, |p| p.0, || (5, 'e'));
+    }
+
+    #[test]
+    fn test_random_upserts() {
+        let mut rng = XorShift::new(97);
+        let mut pairs: Vec<(u32, u32)> = Vec::new();
+        let mut counts = std::collections::BTreeMap::new();
+
+        for _ in 0..2000 {
+            let key = rng.below(200) as u32;
+            *pairs.get_or_insert_sorted_with(key, || 0) += 1;
+            *counts.entry(key).or_insert(0) += 1;
+        }
+        assert!(pairs.into_iter().eq(counts));
+    }
+
+    #[test]
+    fn test_random_sequences_stay_sorted() {
+        let mut rng = XorShift::new(89);
+
+        for _ in 0..50 {
+            let mut all = Vec::new();
+            let mut unique = Vec::new();
+            let mut keyed: Vec<(u32, usize)> = Vec::new();
+
+            for i in 0..rng.below(100) as usize {
+                let x = rng.below(30) as u32;
+
+                let index = all.insert_sorted(x);
+                assert_eq!(all[index], x);
+                assert!(index == 0 || all[index - 1] < x);
+
+                let before = unique.contains(&x);
+                match unique.insert_sorted_unique(x) {
+                    Ok(index) => assert!(!before && unique[index] == x),
+                    Err(index) => assert!(before && unique[index] == x),
+                }
+
+                let index = keyed.insert_sorted_by_key((x, i), |p| p.0);
+                assert_eq!(keyed[index], (x, i));
+
+                assert!(all.is_sorted());
+                assert!(unique.windows(2).all(|w| w[0] < w[1]));
+                assert!(keyed.is_sorted_by_key(|p| p.0));
+            }
+
+            // Within each run of equal keys, later insertions come first.
+            assert!(keyed.windows(2).all(|w| w[0].0 < w[1].0 || w[0].1 > w[1].1));
+        }
+    }
+}
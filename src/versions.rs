@@ -0,0 +1,383 @@
+//! Finding the versions that satisfy a [`VersionReq`] in a sorted slice of [`Version`]s, enabled
+//! by the `semver` feature.
+//!
+//! The slices must be sorted by [`Version`]'s own order, as by `sort()`. Build metadata never
+//! affects whether a version satisfies a requirement, so the versions can carry any.
+//!
+//! # Bounds and pre-releases
+//!
+//! Every comparator of a requirement matches the versions between two bounds, ignoring build
+//! metadata, which are found with two binary searches. This covers every operator semver 1 has:
+//! `^`, `~`, `=`, `>`, `>=`, `<`, `<=` and wildcards, with or without a pre-release. The slice
+//! between the tightest bounds of all comparators holds every satisfying version, and every
+//! release (a version without a pre-release) in it satisfies the requirement.
+//!
+//! A pre-release between the bounds may not satisfy it: `1.5.0-alpha` lies between `1.4.0` and
+//! `1.5.0`, but a pre-release only satisfies a requirement if one of its comparators names the
+//! same `major.minor.patch` with a pre-release of its own. So `^1.4` doesn't match
+//! `1.5.0-alpha`, while `>=1.5.0-alpha, <2` does. [`max_satisfying`] and [`range_satisfying`]
+//! check such pre-releases one by one from the ends of that slice inward. If the slice holds no
+//! pre-releases between the bounds, this takes no more than the binary searches and one check at
+//! each end.
+//!
+//! Requirements with operators this module doesn't know, which a later semver could add, are
+//! answered by checking every version.
+//!
+//! ```
+//! use semver::{Version, VersionReq};
+//! use shar_search::versions::{max_satisfying, range_satisfying};
+//!
+//! let versions: Vec<Version> = ["1.3.9", "1.4.0", "1.4.2", "1.5.0-alpha", "1.5.0", "2.0.0"]
+//!     .iter()
+//!     .map(|v| Version::parse(v).unwrap())
+//!     .collect();
+//!
+//! let req = VersionReq::parse("^1.4").unwrap();
+//! assert_eq!(max_satisfying(&versions, &req), Some(&versions[4]));
+//!
+//! let req = VersionReq::parse("~1.4").unwrap();
+//! assert_eq!(range_satisfying(&versions, &req), &versions[1..3]);
+//! ```
+
+use core::ops::Range;
+
+use semver::{Comparator, Op, Prerelease, Version, VersionReq};
+
+use crate::SharBinarySearch;
+
+/// A version without its build metadata, which matching ignores.
+type Point<'a> = (u64, u64, u64, &'a Prerelease);
+
+fn point(version: &Version) -> Point<'_> {
+    (version.major, version.minor, version.patch, &version.pre)
+}
+
+/// Returns the range of indices of `versions` between the bounds of `comparator`, or `None` if
+/// its operator is unknown.
+fn comparator_range(versions: &[Version], comparator: &Comparator) -> Option<Range<usize>> {
+    // The lowest pre-release, so `(x, y, z, &lowest)` comes before every version `x.y.z-*`.
+    let lowest = Prerelease::new("0").expect("0 is a valid pre-release");
+    let empty = &Prerelease::EMPTY;
+    let (major, pre) = (comparator.major, &comparator.pre);
+
+    // The first version past a major, minor or patch, or `None` if it would overflow.
+    let next_major = major.checked_add(1).map(|major| (major, 0, 0, &lowest));
+    let next_minor = |minor: u64| match minor.checked_add(1) {
+        Some(minor) => Some((major, minor, 0, &lowest)),
+        None => next_major,
+    };
+
+    let start_of = |bound: Point<'_>| versions.bl_partition_point(|v| point(v) < bound);
+    let end_after = |bound: Point<'_>| versions.bl_partition_point(|v| point(v) <= bound);
+    let before = |bound: Option<Point<'_>>| bound.map_or(versions.len(), start_of);
+    let len = versions.len();
+
+    let range = match (comparator.op, comparator.minor, comparator.patch) {
+        (Op::Exact | Op::Wildcard, None, _) => start_of((major, 0, 0, empty))..before(next_major),
+        (Op::Exact | Op::Wildcard, Some(minor), None) => {
+            start_of((major, minor, 0, empty))..before(next_minor(minor))
+        }
+        (Op::Exact | Op::Wildcard, Some(minor), Some(patch)) => {
+            start_of((major, minor, patch, pre))..end_after((major, minor, patch, pre))
+        }
+        (Op::Greater, None, _) => before(next_major)..len,
+        (Op::Greater, Some(minor), None) => before(next_minor(minor))..len,
+        (Op::Greater, Some(minor), Some(patch)) => end_after((major, minor, patch, pre))..len,
+        (Op::GreaterEq, minor, patch) => {
+            // `>=1.2` is `=1.2 || >1.2`, which doesn't match pre-releases of `1.2.0`.
+            let pre = if patch.is_some() { pre } else { empty };
+            start_of((major, minor.unwrap_or(0), patch.unwrap_or(0), pre))..len
+        }
+        (Op::Less, minor, patch) => {
+            let pre = if patch.is_some() { pre } else { &lowest };
+            0..start_of((major, minor.unwrap_or(0), patch.unwrap_or(0), pre))
+        }
+        (Op::LessEq, None, _) => 0..before(next_major),
+        (Op::LessEq, Some(minor), None) => 0..before(next_minor(minor)),
+        (Op::LessEq, Some(minor), Some(patch)) => 0..end_after((major, minor, patch, pre)),
+        (Op::Tilde, None, _) => start_of((major, 0, 0, empty))..before(next_major),
+        (Op::Tilde, Some(minor), patch) => {
+            let pre = if patch.is_some() { pre } else { empty };
+            start_of((major, minor, patch.unwrap_or(0), pre))..before(next_minor(minor))
+        }
+        (Op::Caret, None, _) => start_of((major, 0, 0, &lowest))..before(next_major),
+        (Op::Caret, Some(minor), None) => {
+            let end = if major > 0 {
+                next_major
+            } else {
+                next_minor(minor)
+            };
+            start_of((major, minor, 0, &lowest))..before(end)
+        }
+        (Op::Caret, Some(minor), Some(patch)) => {
+            let end = match (major, minor) {
+                (0, 0) => match patch.checked_add(1) {
+                    Some(patch) => Some((0, 0, patch, &lowest)),
+                    None => next_minor(0),
+                },
+                (0, _) => next_minor(minor),
+                _ => next_major,
+            };
+            start_of((major, minor, patch, pre))..before(end)
+        }
+        _ => return None,
+    };
+    Some(range)
+}
+
+/// Returns the range of indices of `versions` between the bounds of every comparator of `req`,
+/// or `None` if one has an unknown operator.
+fn candidates(versions: &[Version], req: &VersionReq) -> Option<Range<usize>> {
+    let mut range = 0..versions.len();
+    for comparator in &req.comparators {
+        let bounds = comparator_range(versions, comparator)?;
+        range.start = range.start.max(bounds.start);
+        range.end = range.end.min(bounds.end);
+    }
+    range.end = range.end.max(range.start);
+    Some(range)
+}
+
+/// Returns the highest version of `versions` that satisfies `req`, or `None` if none do. Note it
+/// is assumed that `versions` is sorted.
+///
+/// See the [module documentation](self) for when this checks more than one version.
+pub fn max_satisfying<'a>(versions: &'a [Version], req: &VersionReq) -> Option<&'a Version> {
+    let range = candidates(versions, req).unwrap_or(0..versions.len());
+    versions[range].iter().rev().find(|v| req.matches(v))
+}
+
+/// Returns the part of `versions` from its lowest to its highest version that satisfies `req`,
+/// which is empty if none do. Note it is assumed that `versions` is sorted.
+///
+/// Every satisfying version is in the returned slice, and every release in it satisfies `req`.
+/// A pre-release inside it may not, as described in the [module documentation](self), so if
+/// `versions` has pre-releases, filter the slice with [`VersionReq::matches`] to visit only the
+/// satisfying versions.
+pub fn range_satisfying<'a>(versions: &'a [Version], req: &VersionReq) -> &'a [Version] {
+    let range = candidates(versions, req).unwrap_or(0..versions.len());
+    let candidates = &versions[range.clone()];
+    match candidates.iter().position(|v| req.matches(v)) {
+        Some(first) => {
+            let last = candidates
+                .iter()
+                .rposition(|v| req.matches(v))
+                .unwrap_or(first);
+            &candidates[first..=last]
+        }
+        None => &versions[range.start..range.start],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use semver::{Version, VersionReq};
+
+    use super::{max_satisfying, range_satisfying};
+    use crate::test_util::XorShift;
+
+    fn versions(versions: &[&str]) -> Vec<Version> {
+        let versions: Vec<Version> = versions
+            .iter()
+            .map(|v| Version::parse(v).unwrap())
+            .collect();
+        assert!(versions.is_sorted(), "{versions:?}");
+        versions
+    }
+
+    fn req(req: &str) -> VersionReq {
+        VersionReq::parse(req).unwrap()
+    }
+
+    /// Checks both functions against filtering every version.
+    fn check(versions: &[Version], req: &VersionReq) {
+        let expected = versions.iter().filter(|v| req.matches(v)).max();
+        assert_eq!(max_satisfying(versions, req), expected, "{req}");
+
+        let range = range_satisfying(versions, req);
+        let satisfying: Vec<&Version> = versions.iter().filter(|v| req.matches(v)).collect();
+        let in_range: Vec<&Version> = range.iter().filter(|v| req.matches(v)).collect();
+        assert_eq!(in_range, satisfying, "{req}");
+        assert_eq!(range.first(), satisfying.first().copied(), "{req}");
+        assert_eq!(range.last(), satisfying.last().copied(), "{req}");
+        assert!(
+            range.iter().all(|v| !v.pre.is_empty() || req.matches(v)),
+            "{req}"
+        );
+    }
+
+    #[test]
+    fn test_common_requirements() {
+        let versions = versions(&[
+            "0.1.0", "0.1.4", "0.2.0", "0.2.7", "1.0.0", "1.3.9", "1.4.0", "1.4.2", "1.5.0",
+            "1.12.1", "2.0.0", "2.1.0", "3.0.0",
+        ]);
+        let max = |r: &str| max_satisfying(&versions, &req(r)).map(ToString::to_string);
+        let range = |r: &str| range_satisfying(&versions, &req(r)).len();
+
+        assert_eq!(max("^1.4").as_deref(), Some("1.12.1"));
+        assert_eq!(max("~1.4").as_deref(), Some("1.4.2"));
+        assert_eq!(max("^0.1").as_deref(), Some("0.1.4"));
+        assert_eq!(max("^0.2.1").as_deref(), Some("0.2.7"));
+        assert_eq!(max("=1.4").as_deref(), Some("1.4.2"));
+        assert_eq!(max("1.*").as_deref(), Some("1.12.1"));
+        assert_eq!(max("<2").as_deref(), Some("1.12.1"));
+        assert_eq!(max("<=2.0").as_deref(), Some("2.0.0"));
+        assert_eq!(max(">1.12.1, <3").as_deref(), Some("2.1.0"));
+        assert_eq!(max("*").as_deref(), Some("3.0.0"));
+        assert_eq!(max("^4"), None);
+        assert_eq!(max("^0.0.1"), None);
+
+        assert_eq!(range("^1.4"), 4);
+        assert_eq!(range(">=0.2, <1.4.1"), 5);
+        assert_eq!(range("=1.4.2"), 1);
+        assert_eq!(range(">3"), 0);
+        assert_eq!(
+            range_satisfying(&versions, &req("^1.6")).as_ptr(),
+            versions[9..].as_ptr()
+        );
+
+        for r in [
+            "^1.4",
+            "~1.4",
+            "^0.1",
+            "=1.4",
+            ">=1, <2.1",
+            "<0.1.0",
+            ">0.2.7",
+            "~0",
+        ] {
+            check(&versions, &req(r));
+        }
+        check(&[], &req("^1"));
+    }
+
+    #[test]
+    fn test_pre_releases() {
+        let versions = versions(&[
+            "1.4.0",
+            "1.4.1",
+            "1.5.0-alpha",
+            "1.5.0-alpha.2",
+            "1.5.0-beta",
+            "1.5.0",
+            "1.5.1-rc.1",
+            "1.5.1",
+            "2.0.0-alpha",
+            "2.0.0",
+        ]);
+        let max = |r: &str| max_satisfying(&versions, &req(r)).map(ToString::to_string);
+
+        // Pre-releases only satisfy requirements that name their `major.minor.patch`.
+        assert_eq!(max("^1.4").as_deref(), Some("1.5.1"));
+        assert_eq!(max("<1.5.0").as_deref(), Some("1.4.1"));
+        assert_eq!(max("<=1.5.0-beta").as_deref(), Some("1.5.0-beta"));
+        assert_eq!(
+            max(">=1.5.0-alpha.2, <1.5.0").as_deref(),
+            Some("1.5.0-beta")
+        );
+        assert_eq!(max("~1.5.0-alpha").as_deref(), Some("1.5.1"));
+        assert_eq!(max("=1.5.1-rc.1").as_deref(), Some("1.5.1-rc.1"));
+        assert_eq!(max("^2.0.0-alpha").as_deref(), Some("2.0.0"));
+        assert_eq!(max(">=2.0.0-alpha, <2.0.0").as_deref(), Some("2.0.0-alpha"));
+        assert_eq!(max("<2.0.0-beta").as_deref(), Some("2.0.0-alpha"));
+
+        // The release candidate is inside the range, but doesn't satisfy it.
+        let range = range_satisfying(&versions, &req(">=1.5.0-alpha, <1.6"));
+        assert_eq!(range, &versions[2..8]);
+        assert!(!req(">=1.5.0-alpha, <1.6").matches(&versions[6]));
+        assert_eq!(range_satisfying(&versions, &req("^1.5")), &versions[5..8]);
+        assert!(range_satisfying(&versions, &req("=1.5.0-gamma")).is_empty());
+
+        for r in [
+            "^1.4",
+            "^1.5.0-alpha",
+            ">1.5.0-alpha, <=1.5.1-rc.1",
+            "<1.5.1-rc.2",
+            "2.0.0-alpha",
+        ] {
+            check(&versions, &req(r));
+        }
+    }
+
+    #[test]
+    fn test_build_metadata() {
+        let versions = versions(&[
+            "1.0.0",
+            "1.0.0+build.1",
+            "1.0.0+build.2",
+            "1.0.1-rc+ci",
+            "1.1.0+x",
+        ]);
+
+        // Build metadata is ignored by matching, including at the bounds.
+        assert_eq!(range_satisfying(&versions, &req("=1.0.0")), &versions[..3]);
+        assert_eq!(range_satisfying(&versions, &req("<=1.0.0")), &versions[..3]);
+        assert_eq!(range_satisfying(&versions, &req(">1.0.0")), &versions[4..]);
+        assert_eq!(max_satisfying(&versions, &req("~1.0")), Some(&versions[2]));
+        assert_eq!(
+            max_satisfying(&versions, &req("=1.0.1-rc")),
+            Some(&versions[3])
+        );
+        assert_eq!(max_satisfying(&versions, &req("^1")), Some(&versions[4]));
+
+        for r in ["=1.0.0", "<=1.0.0", ">1.0.0", "^1.0.1-rc", "<1.1.0"] {
+            check(&versions, &req(r));
+        }
+    }
+
+    #[test]
+    fn test_overflow() {
+        let max = u64::MAX;
+        let versions = versions(&["0.0.1", &format!("{max}.{max}.{max}")]);
+        for r in [
+            format!("^{max}"),
+            format!("~{max}.{max}"),
+            format!(">{max}"),
+            format!(">{max}.{max}"),
+            format!("<={max}.{max}"),
+            format!("^0.0.{max}"),
+        ] {
+            check(&versions, &req(&r));
+        }
+    }
+
+    #[test]
+    fn test_against_filtering() {
+        let mut rng = XorShift::new(197);
+        let pres = ["", "-0", "-alpha", "-alpha.1", "-beta", "-rc.1"];
+        let ops = ["", "^", "~", "=", ">", ">=", "<", "<="];
+        let part = |rng: &mut XorShift| rng.below(3);
+
+        for _ in 0..200 {
+            let len = rng.below(40);
+            let mut versions: Vec<Version> = (0..len)
+                .map(|_| {
+                    let (major, minor, patch) = (part(&mut rng), part(&mut rng), part(&mut rng));
+                    let pre = pres[rng.below(pres.len() as u64) as usize];
+                    let build = if rng.below(4) == 0 { "+b" } else { "" };
+                    Version::parse(&format!("{major}.{minor}.{patch}{pre}{build}")).unwrap()
+                })
+                .collect();
+            versions.sort();
+
+            for _ in 0..20 {
+                let comparators: Vec<String> = (0..1 + rng.below(2))
+                    .map(|_| {
+                        let op = ops[rng.below(ops.len() as u64) as usize];
+                        match rng.below(4) {
+                            0 => format!("{op}{}", part(&mut rng)),
+                            1 => format!("{op}{}.{}", part(&mut rng), part(&mut rng)),
+                            _ => {
+                                let pre = pres[rng.below(pres.len() as u64) as usize];
+                                let (major, minor) = (part(&mut rng), part(&mut rng));
+                                format!("{op}{major}.{minor}.{}{pre}", part(&mut rng))
+                            }
+                        }
+                    })
+                    .collect();
+                check(&versions, &req(&comparators.join(", ")));
+            }
+        }
+    }
+}
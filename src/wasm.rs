@@ -0,0 +1,101 @@
+//! [`wasm_bindgen`] exports for searching sorted typed arrays from JavaScript, enabled by the
+//! `wasm` feature.
+//!
+//! Each search takes the sorted data as a typed array (`Float64Array`, `Int32Array` or
+//! `BigUint64Array`) and returns a single number encoding the result the way Java's
+//! `Arrays.binarySearch` does: the index of the first match if there is one, and otherwise
+//! `-(insertion point) - 1`, which is always negative. The `_batch` variants take a typed array
+//! of keys and return an `Int32Array` of such results, so that searching many keys crosses the
+//! JavaScript boundary once rather than once per key.
+//!
+//! ```js
+//! import { search_f64, search_batch_f64 } from "shar_search";
+//!
+//! const data = new Float64Array([0.5, 1.5, 1.5, 4.0]);
+//! search_f64(data, 1.5); // 1
+//! search_f64(data, 2.0); // -4, as 2.0 would be inserted at 3
+//! search_batch_f64(data, new Float64Array([4.0, 0.0])); // Int32Array [3, -1]
+//! ```
+//!
+//! # Floats
+//!
+//! Floats are compared with [`f64::total_cmp`], so `-0.0` sorts before `0.0`, and NaNs are
+//! ordered rather than never matching: negative NaNs come before every other value and positive
+//! NaNs (including JavaScript's `NaN`) after. This is the order `Float64Array.prototype.sort`
+//! produces, so data sorted with it can be passed in directly, and searching for `NaN` finds the
+//! first `NaN` at its end.
+
+use alloc::vec::Vec;
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{batch::DEFAULT_INTERLEAVE, SharBatchSearch, SharBinarySearch};
+
+/// Encodes a search result as a single number: the index if found, and `-(index) - 1` if not.
+///
+/// WebAssembly's 32-bit address space means that arrays of elements of at least 4 bytes, the
+/// only ones exported, always have fewer than `i32::MAX` elements, so both cases fit.
+fn encode(result: Result<usize, usize>) -> i32 {
+    let (index, found) = match result {
+        Ok(index) => (index, true),
+        Err(index) => (index, false),
+    };
+    let index = i32::try_from(index).expect("arrays have fewer than `i32::MAX` elements");
+
+    if found {
+        index
+    } else {
+        -index - 1
+    }
+}
+
+/// Searches sorted `data` for `key`, comparing with [`f64::total_cmp`]. See the
+/// [module documentation](self) for how the result is encoded.
+#[wasm_bindgen]
+pub fn search_f64(data: &[f64], key: f64) -> i32 {
+    encode(data.bl_binary_search_by(|p| p.total_cmp(&key)))
+}
+
+/// Searches sorted `data` for each of `keys`, comparing with [`f64::total_cmp`]. See the
+/// [module documentation](self) for how the results are encoded.
+#[wasm_bindgen]
+pub fn search_batch_f64(data: &[f64], keys: &[f64]) -> Vec<i32> {
+    data.bl_binary_search_batch_by::<DEFAULT_INTERLEAVE, _, _>(keys, f64::total_cmp)
+        .into_iter()
+        .map(encode)
+        .collect()
+}
+
+/// Searches sorted `data` for `key`. See the [module documentation](self) for how the result is
+/// encoded.
+#[wasm_bindgen]
+pub fn search_i32(data: &[i32], key: i32) -> i32 {
+    encode(data.bl_binary_search(&key))
+}
+
+/// Searches sorted `data` for each of `keys`. See the [module documentation](self) for how the
+/// results are encoded.
+#[wasm_bindgen]
+pub fn search_batch_i32(data: &[i32], keys: &[i32]) -> Vec<i32> {
+    data.bl_binary_search_batch(keys)
+        .into_iter()
+        .map(encode)
+        .collect()
+}
+
+/// Searches sorted `data` for `key`, which is a `BigInt` on the JavaScript side. See the
+/// [module documentation](self) for how the result is encoded.
+#[wasm_bindgen]
+pub fn search_u64(data: &[u64], key: u64) -> i32 {
+    encode(data.bl_binary_search(&key))
+}
+
+/// Searches sorted `data` for each of `keys`. See the [module documentation](self) for how the
+/// results are encoded.
+#[wasm_bindgen]
+pub fn search_batch_u64(data: &[u64], keys: &[u64]) -> Vec<i32> {
+    data.bl_binary_search_batch(keys)
+        .into_iter()
+        .map(encode)
+        .collect()
+}
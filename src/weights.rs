@@ -0,0 +1,292 @@
+//! Weighted random selection over prefix sums of weights.
+//!
+//! Bucket `i` of prefix sums `prefix` covers the draws in `prefix[i - 1]..prefix[i]` (with
+//! `prefix[-1]` taken as zero), so choosing a bucket with probability proportional to its weight
+//! is drawing `u` uniformly from `0..total` and finding the first prefix sum greater than `u`.
+//! Zero-weight buckets cover an empty range, and so are never chosen, even when a draw lands on
+//! their boundary.
+//!
+//! Drawing the random numbers is left to the caller, so that any source of randomness can be
+//! used.
+//!
+//! ```
+//! # #[cfg(feature = "alloc")]
+//! # {
+//! use shar_search::weights::{build_prefix_sums, sample_index};
+//!
+//! let prefix = build_prefix_sums(&[2, 0, 5, 1]).unwrap();
+//! assert_eq!(prefix, [2, 2, 7, 8]);
+//!
+//! // Draws of 0 and 1 choose bucket 0, 2 to 6 choose bucket 2, and 7 chooses bucket 3.
+//! assert_eq!(sample_index(&prefix, 1), 0);
+//! assert_eq!(sample_index(&prefix, 2), 2);
+//! assert_eq!(sample_index(&prefix, 7), 3);
+//! # }
+//! ```
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use core::cmp::Ordering;
+use core::{error::Error, fmt};
+
+#[cfg(feature = "alloc")]
+use crate::batch::{SharBatchSearch, DEFAULT_INTERLEAVE};
+use crate::SharBinarySearch;
+
+/// The error returned when weights can't be turned into prefix sums to sample from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WeightError {
+    /// The sum of the weights up to and including this index overflows.
+    Overflow(usize),
+    /// The weights sum to zero, including when there are none, so nothing can be drawn.
+    ZeroTotal,
+}
+
+impl fmt::Display for WeightError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WeightError::Overflow(index) => {
+                write!(f, "the sum of the weights overflows at index {index}")
+            }
+            WeightError::ZeroTotal => write!(f, "the weights sum to zero"),
+        }
+    }
+}
+
+impl Error for WeightError {}
+
+/// Returns the running totals of `weights`, for sampling with [`sample_index`].
+///
+/// # Errors
+///
+/// Returns an error if the total overflows a `u64`, or if it is zero.
+#[cfg(feature = "alloc")]
+pub fn build_prefix_sums(weights: &[u64]) -> Result<Vec<u64>, WeightError> {
+    let mut total = 0_u64;
+    let prefix = weights
+        .iter()
+        .enumerate()
+        .map(|(i, &w)| {
+            total = total.checked_add(w).ok_or(WeightError::Overflow(i))?;
+            Ok(total)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if total == 0 {
+        return Err(WeightError::ZeroTotal);
+    }
+
+    Ok(prefix)
+}
+
+/// Returns the first bucket whose prefix sum is greater than `u`, checking that there is one.
+#[inline]
+fn sample<T: PartialOrd + fmt::Debug>(prefix_sums: &[T], u: T) -> usize {
+    // Checked up front, rather than by the search's result, so that a NaN draw is caught too.
+    assert!(
+        prefix_sums.last().is_some_and(|total| u < *total),
+        "draw {u:?} is not less than the total weight of {:?}",
+        prefix_sums.last()
+    );
+
+    prefix_sums.bl_partition_point(|p| *p <= u)
+}
+
+/// Returns the bucket of `prefix_sums` whose range of draws contains `u`. Note it is assumed
+/// that `prefix_sums` is sorted, as prefix sums of non-negative weights are.
+///
+/// `u` should be drawn uniformly from `0..total`, where `total` is the last prefix sum. A
+/// zero-weight bucket is never returned.
+///
+/// # Panics
+///
+/// Panics if `u` is not less than the total.
+#[inline]
+pub fn sample_index(prefix_sums: &[u64], u: u64) -> usize {
+    sample(prefix_sums, u)
+}
+
+/// Returns the bucket of `prefix_sums` whose range of draws contains `u`, for totals past
+/// `u64::MAX`. See [`sample_index`].
+///
+/// # Panics
+///
+/// Panics if `u` is not less than the total.
+#[inline]
+pub fn sample_index_u128(prefix_sums: &[u128], u: u128) -> usize {
+    sample(prefix_sums, u)
+}
+
+/// Returns the bucket of `prefix_sums` whose range of draws contains `u`, for fractional
+/// weights. See [`sample_index`].
+///
+/// `u` should be drawn uniformly from `[0, total)`. Weights should be non-negative and finite,
+/// and if the prefix sums were computed in floating point, rounding can leave a tiny weight
+/// with the same prefix sum as the bucket before it, in which case it is never returned.
+///
+/// # Panics
+///
+/// Panics if `u` is not less than the total, or is NaN.
+#[inline]
+pub fn sample_index_f64(prefix_sums: &[f64], u: f64) -> usize {
+    sample(prefix_sums, u)
+}
+
+/// Returns the bucket of `prefix_sums` for each of `draws`, in the same order. See
+/// [`sample_index`].
+///
+/// If `draws` is sorted, the searches gallop forward from one to the next, which is faster for
+/// many draws. Otherwise, they are interleaved.
+///
+/// # Panics
+///
+/// Panics if any draw is not less than the total.
+#[cfg(feature = "alloc")]
+pub fn sample_many(prefix_sums: &[u64], draws: &[u64]) -> Vec<usize> {
+    // Never `Equal`, so every result is the `Err` of the first prefix sum greater than the draw.
+    let compare = |p: &u64, u: &u64| {
+        if p <= u {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    };
+
+    let results = if draws.is_sorted() {
+        prefix_sums.bl_binary_search_sorted_keys_by(draws, compare)
+    } else {
+        prefix_sums.bl_binary_search_batch_by::<DEFAULT_INTERLEAVE, _, _>(draws, compare)
+    };
+
+    results
+        .into_iter()
+        .zip(draws)
+        .map(|(result, u)| {
+            let index = result.unwrap_or_else(|index| index);
+            assert!(
+                index < prefix_sums.len(),
+                "draw {u} is not less than the total weight of {:?}",
+                prefix_sums.last()
+            );
+            index
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod test {
+    use super::*;
+    use crate::test_util::XorShift;
+
+    #[test]
+    fn test_build_errors() {
+        assert_eq!(build_prefix_sums(&[]), Err(WeightError::ZeroTotal));
+        assert_eq!(build_prefix_sums(&[0, 0, 0]), Err(WeightError::ZeroTotal));
+        assert_eq!(
+            build_prefix_sums(&[1, u64::MAX - 1, 0, 1]),
+            Err(WeightError::Overflow(3))
+        );
+        assert_eq!(build_prefix_sums(&[1, u64::MAX - 1]), Ok(vec![1, u64::MAX]));
+    }
+
+    #[test]
+    fn test_single_bucket() {
+        let prefix = build_prefix_sums(&[5]).unwrap();
+        for u in 0..5 {
+            assert_eq!(sample_index(&prefix, u), 0);
+        }
+        assert_eq!(sample_many(&prefix, &[4, 0, 3]), [0, 0, 0]);
+
+        // Zero-weight buckets on either side are skipped.
+        let prefix = build_prefix_sums(&[0, 0, 5, 0]).unwrap();
+        for u in 0..5 {
+            assert_eq!(sample_index(&prefix, u), 2);
+        }
+    }
+
+    #[test]
+    fn test_boundary_draws() {
+        let weights = [3, 0, 0, 4, 1, 0, 2];
+        let prefix = build_prefix_sums(&weights).unwrap();
+
+        // A draw equal to a prefix sum belongs to the next bucket with any weight.
+        for (u, expected) in [(0, 0), (2, 0), (3, 3), (6, 3), (7, 4), (8, 6), (9, 6)] {
+            assert_eq!(sample_index(&prefix, u), expected, "draw {u}");
+            assert_eq!(
+                sample_index_u128(&[3, 3, 3, 7, 8, 8, 10], u.into()),
+                expected
+            );
+            assert_eq!(
+                sample_index_f64(&[3.0, 3.0, 3.0, 7.0, 8.0, 8.0, 10.0], u as f64),
+                expected
+            );
+        }
+        assert_eq!(sample_index_f64(&[0.5, 0.5, 1.0], 0.5), 2);
+        assert_eq!(sample_index_f64(&[0.5, 0.5, 1.0], 0.4999), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_draw_out_of_range() {
+        sample_index(&[3, 3, 10], 10);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_draw_nan() {
+        sample_index_f64(&[1.0, 2.0], f64::NAN);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_many_out_of_range() {
+        sample_many(&[3, 3, 10], &[0, 10, 2]);
+    }
+
+    #[test]
+    fn test_many_sorted_and_unsorted() {
+        let mut rng = XorShift::new(150);
+        let weights: Vec<u64> = (0..100).map(|_| rng.below(4)).collect();
+        let prefix = build_prefix_sums(&weights).unwrap();
+        let total = *prefix.last().unwrap();
+
+        let mut draws: Vec<u64> = (0..500).map(|_| rng.below(total)).collect();
+        let expected: Vec<usize> = draws.iter().map(|&u| sample_index(&prefix, u)).collect();
+        assert_eq!(sample_many(&prefix, &draws), expected);
+
+        draws.sort_unstable();
+        let expected: Vec<usize> = draws.iter().map(|&u| sample_index(&prefix, u)).collect();
+        assert_eq!(sample_many(&prefix, &draws), expected);
+        assert!(expected.iter().all(|&i| weights[i] > 0));
+    }
+
+    #[test]
+    fn test_distribution() {
+        let weights = [1, 0, 3, 6, 0, 10];
+        let prefix = build_prefix_sums(&weights).unwrap();
+        let total = *prefix.last().unwrap();
+
+        let mut rng = XorShift::new(1150);
+        let n = 200_000;
+        let mut counts = [0_u64; 6];
+        for _ in 0..n {
+            counts[sample_index(&prefix, rng.below(total))] += 1;
+        }
+
+        assert_eq!(counts[1], 0);
+        assert_eq!(counts[4], 0);
+
+        // With 3 degrees of freedom, a statistic over 16.27 has a probability of 0.1%.
+        let chi_squared: f64 = weights
+            .iter()
+            .zip(counts)
+            .filter(|(&w, _)| w > 0)
+            .map(|(&w, count)| {
+                let expected = (n * w) as f64 / total as f64;
+                (count as f64 - expected).powi(2) / expected
+            })
+            .sum();
+        assert!(chi_squared < 16.27, "chi-squared of {chi_squared}");
+    }
+}
@@ -0,0 +1,328 @@
+//! Pruning blocks of columnar data by their zone maps: the minimum and maximum value of each
+//! block, kept so that a query can skip blocks without reading them.
+//!
+//! A block is a *candidate* for a query range if the range overlaps the interval from the
+//! block's minimum to its maximum (inclusive), so the block may hold values the query wants.
+//! Other blocks can be skipped. Only comparisons are used, so an integer range with no integers
+//! in it, such as `(Excluded(5), Excluded(6))`, still overlaps a block from 3 to 8.
+//!
+//! When the data is sorted across blocks, as for a sorted column split into pages, both the
+//! minimums and the maximums are non-decreasing, and the candidates are a contiguous run of
+//! blocks that [`candidate_blocks`] finds with two branchless searches. When only each block's
+//! summary holds, as for a column written in arrival order, [`candidate_blocks_unsorted`]
+//! checks every block.
+//!
+//! ```
+//! # #[cfg(feature = "alloc")]
+//! # {
+//! use shar_search::compressed::{encode, BLOCK_LEN};
+//! use shar_search::zonemap::candidate_blocks;
+//!
+//! // A sorted column compressed in blocks, whose first values are the block minimums.
+//! let values: Vec<u64> = (0..1000).map(|i| i * i).collect();
+//! let encoded = encode(&values);
+//! let index = encoded.index();
+//! let maxs: Vec<u64> = (0..encoded.firsts.len())
+//!     .map(|block| index.get(((block + 1) * BLOCK_LEN).min(index.len()) - 1))
+//!     .collect();
+//!
+//! // Only decode the blocks that can hold values in the query.
+//! let query = 100_000..=200_000;
+//! let blocks = candidate_blocks(&encoded.firsts, &maxs, query.clone()).unwrap();
+//! assert_eq!(blocks, 2..4);
+//!
+//! let start = blocks.start * BLOCK_LEN;
+//! let end = (blocks.end * BLOCK_LEN).min(index.len());
+//! let hits = index.iter_from(start).take(end - start).filter(|v| query.contains(v));
+//! assert_eq!(hits.count(), 131);
+//! # }
+//! ```
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::{
+    error::Error,
+    fmt,
+    ops::{Bound, Range, RangeBounds},
+};
+
+use crate::SharBinarySearch;
+
+/// The error returned when there aren't as many block maximums as minimums.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SummaryLengthMismatch {
+    mins: usize,
+    maxs: usize,
+}
+
+impl SummaryLengthMismatch {
+    /// Returns the number of block minimums.
+    pub fn mins(&self) -> usize {
+        self.mins
+    }
+
+    /// Returns the number of block maximums.
+    pub fn maxs(&self) -> usize {
+        self.maxs
+    }
+}
+
+impl fmt::Display for SummaryLengthMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} block minimums were given with {} maximums",
+            self.mins, self.maxs
+        )
+    }
+}
+
+impl Error for SummaryLengthMismatch {}
+
+fn check_lengths<K>(mins: &[K], maxs: &[K]) -> Result<(), SummaryLengthMismatch> {
+    if mins.len() == maxs.len() {
+        Ok(())
+    } else {
+        Err(SummaryLengthMismatch {
+            mins: mins.len(),
+            maxs: maxs.len(),
+        })
+    }
+}
+
+/// Returns whether no value can be within `query`, because its start is after its end, or they
+/// are the same value and either is excluded.
+fn is_empty_query<K: Ord, R: RangeBounds<K>>(query: &R) -> bool {
+    match (query.start_bound(), query.end_bound()) {
+        (Bound::Included(start), Bound::Included(end)) => start > end,
+        (Bound::Included(start), Bound::Excluded(end))
+        | (Bound::Excluded(start), Bound::Included(end))
+        | (Bound::Excluded(start), Bound::Excluded(end)) => start >= end,
+        _ => false,
+    }
+}
+
+/// Returns whether a block whose maximum is `max` reaches the start of `query`.
+fn reaches_start<K: Ord, R: RangeBounds<K>>(max: &K, query: &R) -> bool {
+    match query.start_bound() {
+        Bound::Included(start) => max >= start,
+        Bound::Excluded(start) => max > start,
+        Bound::Unbounded => true,
+    }
+}
+
+/// Returns whether a block whose minimum is `min` starts before the end of `query`.
+fn reaches_end<K: Ord, R: RangeBounds<K>>(min: &K, query: &R) -> bool {
+    match query.end_bound() {
+        Bound::Included(end) => min <= end,
+        Bound::Excluded(end) => min < end,
+        Bound::Unbounded => true,
+    }
+}
+
+/// Returns the range of blocks that are candidates for `query`, for blocks whose minimums are
+/// `mins` and maximums are `maxs`. Note it is assumed that both are non-decreasing, as when the
+/// data is sorted across blocks, and that each block's minimum is at most its maximum.
+///
+/// Every block in the returned range is a candidate, and no other is. The range is empty (and
+/// starts at the first block that reaches the start of the query) if there are none.
+///
+/// ```
+/// use shar_search::zonemap::candidate_blocks;
+///
+/// let mins = [0, 10, 10, 25, 40];
+/// let maxs = [9, 10, 20, 39, 50];
+/// assert_eq!(candidate_blocks(&mins, &maxs, 10..=10), Ok(1..3));
+/// assert_eq!(candidate_blocks(&mins, &maxs, 21..25), Ok(3..3));
+/// assert_eq!(candidate_blocks(&mins, &maxs, 45..), Ok(4..5));
+/// assert!(candidate_blocks(&mins, &maxs[1..], ..).is_err());
+/// ```
+///
+/// # Errors
+///
+/// Returns [`SummaryLengthMismatch`] if `mins` and `maxs` have different lengths.
+pub fn candidate_blocks<K, R>(
+    mins: &[K],
+    maxs: &[K],
+    query: R,
+) -> Result<Range<usize>, SummaryLengthMismatch>
+where
+    K: Ord,
+    R: RangeBounds<K>,
+{
+    check_lengths(mins, maxs)?;
+
+    // Blocks before `start` end before the query, and blocks from `end` on start after it.
+    let start = maxs.bl_partition_point(|max| !reaches_start(max, &query));
+    if is_empty_query(&query) {
+        return Ok(start..start);
+    }
+    let end = mins.bl_partition_point(|min| reaches_end(min, &query));
+
+    Ok(start..end.max(start))
+}
+
+/// Returns the indices of the blocks that are candidates for `query`, in increasing order, for
+/// blocks whose minimums are `mins` and maximums are `maxs`. Unlike [`candidate_blocks`], this
+/// checks every block, so the blocks can be in any order.
+///
+/// ```
+/// use shar_search::zonemap::candidate_blocks_unsorted;
+///
+/// // Blocks of late-arriving events overlap earlier ones.
+/// let mins = [100, 150, 90, 300];
+/// let maxs = [199, 260, 120, 400];
+/// assert_eq!(candidate_blocks_unsorted(&mins, &maxs, 110..160), Ok(vec![0, 1, 2]));
+/// assert_eq!(candidate_blocks_unsorted(&mins, &maxs, 261..300), Ok(vec![]));
+/// ```
+///
+/// # Errors
+///
+/// Returns [`SummaryLengthMismatch`] if `mins` and `maxs` have different lengths.
+#[cfg(feature = "alloc")]
+pub fn candidate_blocks_unsorted<K, R>(
+    mins: &[K],
+    maxs: &[K],
+    query: R,
+) -> Result<Vec<usize>, SummaryLengthMismatch>
+where
+    K: Ord,
+    R: RangeBounds<K>,
+{
+    check_lengths(mins, maxs)?;
+    if is_empty_query(&query) {
+        return Ok(Vec::new());
+    }
+
+    Ok(mins
+        .iter()
+        .zip(maxs)
+        .enumerate()
+        .filter(|&(_, (min, max))| reaches_end(min, &query) && reaches_start(max, &query))
+        .map(|(block, _)| block)
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use core::ops::Bound;
+
+    use super::{candidate_blocks, SummaryLengthMismatch};
+    use crate::reference::{self, assert_matches_reference, inputs};
+
+    #[test]
+    fn test_constant_blocks_and_point_queries() {
+        let mins = [1, 5, 5, 5, 8, 12];
+        let maxs = [4, 5, 5, 8, 12, 12];
+
+        assert_eq!(candidate_blocks(&mins, &maxs, 5..=5), Ok(1..4));
+        assert_eq!(candidate_blocks(&mins, &maxs, 12..=12), Ok(4..6));
+        assert_eq!(candidate_blocks(&mins, &maxs, 8..=8), Ok(3..5));
+        assert_eq!(candidate_blocks(&mins, &maxs, 0..=0), Ok(0..0));
+        assert_eq!(candidate_blocks(&mins, &maxs, 13..=13), Ok(6..6));
+
+        // Excluded bounds at a constant block's value leave it out.
+        assert_eq!(candidate_blocks(&mins, &maxs, 4..5), Ok(0..1));
+        assert_eq!(
+            candidate_blocks(&mins, &maxs, (Bound::Excluded(5), Bound::Included(7))),
+            Ok(3..4)
+        );
+
+        // Empty queries have no candidates, even inside a block.
+        assert_eq!(candidate_blocks(&mins, &maxs, 6..6), Ok(3..3));
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 7..=6;
+        assert_eq!(candidate_blocks(&mins, &maxs, reversed), Ok(3..3));
+    }
+
+    #[test]
+    fn test_unbounded() {
+        let mins = [0, 10, 20];
+        let maxs = [9, 19, 29];
+        assert_eq!(candidate_blocks(&mins, &maxs, ..), Ok(0..3));
+        assert_eq!(candidate_blocks(&mins, &maxs, ..10), Ok(0..1));
+        assert_eq!(candidate_blocks(&mins, &maxs, ..=10), Ok(0..2));
+        assert_eq!(candidate_blocks(&mins, &maxs, 19..), Ok(1..3));
+        assert_eq!(candidate_blocks::<u64, _>(&[], &[], ..), Ok(0..0));
+    }
+
+    #[test]
+    fn test_mismatched_lengths() {
+        let err = candidate_blocks(&[1, 2, 3], &[4, 5], ..).unwrap_err();
+        assert_eq!(err, SummaryLengthMismatch { mins: 3, maxs: 2 });
+        assert_eq!((err.mins(), err.maxs()), (3, 2));
+        assert_eq!(
+            err.to_string(),
+            "3 block minimums were given with 2 maximums"
+        );
+
+        #[cfg(feature = "alloc")]
+        assert_eq!(
+            super::candidate_blocks_unsorted(&[1], &[], 0..1),
+            Err(SummaryLengthMismatch { mins: 1, maxs: 0 })
+        );
+    }
+
+    /// A query range, in the form with every kind of bound at either end.
+    type Query = (Bound<u32>, Bound<u32>);
+
+    /// Returns each of `blocks` with queries of every kind of bound, starting at each value
+    /// from below the first block to past the last, and ending there or further on.
+    fn with_queries(
+        blocks: impl Iterator<Item = (Vec<u32>, Vec<u32>)>,
+    ) -> impl Iterator<Item = (Vec<u32>, Vec<u32>, Query)> {
+        use Bound::{Excluded, Included, Unbounded};
+
+        let bounds = |x: u32| [Included(x), Excluded(x), Unbounded];
+        blocks.flat_map(move |(mins, maxs)| {
+            let last = maxs.iter().max().copied().unwrap_or(0);
+            // Long summaries have too many values to start at them all.
+            let step = (last as usize / 8).max(1);
+            (0..=last + 1).step_by(step).flat_map(move |x| {
+                let (mins, maxs) = (mins.clone(), maxs.clone());
+                bounds(x).into_iter().flat_map(move |start| {
+                    let (mins, maxs) = (mins.clone(), maxs.clone());
+                    [
+                        Included(x),
+                        Excluded(x),
+                        Included(x + 3),
+                        Excluded(x + 3),
+                        Unbounded,
+                    ]
+                    .map(move |end| (mins.clone(), maxs.clone(), (start, end)))
+                })
+            })
+        })
+    }
+
+    #[test]
+    fn test_against_reference() {
+        assert_matches_reference!(
+            for (mins, maxs, query) in with_queries(inputs::blocks(200)) =>
+            candidate_blocks(&mins, &maxs, query).unwrap().collect::<Vec<_>>(),
+            reference::candidate_blocks(&mins, &maxs, &query),
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_unsorted_against_reference() {
+        use super::candidate_blocks_unsorted;
+
+        // Each block stretched over the next two, so that they overlap, in reverse order.
+        let blocks = inputs::blocks(2000).map(|(mut mins, mut maxs)| {
+            for i in 0..maxs.len() {
+                maxs[i] = maxs[(i + 2).min(maxs.len() - 1)];
+            }
+            mins.reverse();
+            maxs.reverse();
+            (mins, maxs)
+        });
+
+        assert_matches_reference!(
+            for (mins, maxs, query) in with_queries(blocks) =>
+            candidate_blocks_unsorted(&mins, &maxs, query),
+            Ok(reference::candidate_blocks(&mins, &maxs, &query)),
+        );
+    }
+}
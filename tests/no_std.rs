@@ -0,0 +1,91 @@
+//! Exercises the slice searches from a `no_std` crate that uses only `core`, so that building
+//! this test without default features proves they need nothing more.
+
+#![no_std]
+
+use core::cmp::Ordering;
+
+use shar_search::{
+    comparators::{against, natural_ascii},
+    join::semi_join,
+    merge::merge_iter,
+    multi::search_in_each_into,
+    SearchCursor, Searcher, SharBatchSearch, SharBinarySearch,
+};
+
+#[test]
+fn test_search() {
+    let slice = [1, 3, 3, 5, 8];
+
+    assert_eq!(slice.bl_binary_search(&3), Ok(1));
+    assert_eq!(slice.bl_binary_search(&4), Err(3));
+    assert_eq!(slice.bl_binary_search_by(|p| p.cmp(&8)), Ok(4));
+    assert_eq!(slice.bl_binary_search_by_key(&10, |p| p * 2), Ok(3));
+    assert_eq!(slice.bl_partition_point(|&p| p < 5), 3);
+    assert_eq!(slice.bl_equal_range(&3), 1..3);
+
+    let missed = slice.bl_search_or_neighbors(&4).err().unwrap();
+    assert_eq!((missed.before, missed.after), (Some(&3), Some(&5)));
+}
+
+#[test]
+fn test_runs_and_rotation() {
+    let slice = [1, 1, 2, 4, 4, 4];
+    let mut runs = slice.bl_runs();
+    assert_eq!(runs.next(), Some((0, &[1, 1][..])));
+    assert_eq!(runs.next_back(), Some((3, &[4, 4, 4][..])));
+
+    let rotated = [7, 9, 12, 1, 3, 5];
+    assert_eq!(rotated.bl_rotation_point(), 3);
+    assert_eq!(rotated.bl_binary_search_rotated(&9), Ok(1));
+}
+
+#[test]
+fn test_cursor_and_searcher() {
+    let slice = [10, 20, 20, 30, 40];
+    let mut cursor = SearchCursor::new(&slice);
+    assert_eq!(cursor.seek(&20), Ok(1));
+    assert_eq!(cursor.seek(&35), Err(4));
+
+    const LAST: Searcher<shar_search::searcher::Ascending, shar_search::searcher::Rightmost> =
+        Searcher::new().rightmost();
+    assert_eq!(LAST.search(&slice, &20), Ok(2));
+
+    let names = ["file1", "file2", "file10"];
+    assert_eq!(
+        names.bl_binary_search_by(against(&"file10", natural_ascii())),
+        Ok(2)
+    );
+}
+
+#[test]
+fn test_buffered_batches() {
+    let slice = [10, 20, 30];
+    let mut out = [Ok(0); 3];
+    slice
+        .bl_binary_search_batch_into(&[30, 5, 20], &mut out)
+        .unwrap();
+    assert_eq!(out, [Ok(2), Err(0), Ok(1)]);
+
+    let other = [5, 30];
+    let mut out = [Ok(0); 2];
+    search_in_each_into(&[&slice[..], &other[..]], &30, &mut out).unwrap();
+    assert_eq!(out, [Ok(2), Ok(1)]);
+}
+
+#[test]
+fn test_iterators() {
+    let a = [1, 4, 9];
+    let b = [2, 4, 10];
+
+    let mut merged = [0; 6];
+    for (slot, x) in merged.iter_mut().zip(merge_iter(&a, &b)) {
+        *slot = *x;
+    }
+    assert_eq!(merged, [1, 2, 4, 4, 9, 10]);
+
+    let mut common = semi_join(&a, &b);
+    assert_eq!(common.next(), Some(&4));
+    assert_eq!(common.next(), None);
+    assert_eq!(a[0].cmp(&b[0]), Ordering::Less);
+}
@@ -0,0 +1,61 @@
+//! Tests for the `wasm` exports. Under `wasm-pack test --node --features wasm` they run in
+//! WebAssembly through the generated bindings; elsewhere they run as ordinary tests.
+
+#![cfg(feature = "wasm")]
+
+use shar_search::wasm::*;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test as test;
+
+#[test]
+fn test_encoding() {
+    let data = [10, 20, 20, 30];
+
+    assert_eq!(search_i32(&data, 10), 0);
+    assert_eq!(search_i32(&data, 20), 1);
+    assert_eq!(search_i32(&data, 30), 3);
+    assert_eq!(search_i32(&data, 5), -1);
+    assert_eq!(search_i32(&data, 25), -4);
+    assert_eq!(search_i32(&data, 35), -5);
+    assert_eq!(search_i32(&[], 1), -1);
+}
+
+#[test]
+fn test_total_order() {
+    let nan = f64::NAN;
+    let data = [
+        -nan,
+        f64::NEG_INFINITY,
+        -0.0,
+        0.0,
+        1.5,
+        f64::INFINITY,
+        nan,
+        nan,
+    ];
+
+    assert_eq!(search_f64(&data, -0.0), 2);
+    assert_eq!(search_f64(&data, 0.0), 3);
+    assert_eq!(search_f64(&data, nan), 6);
+    assert_eq!(search_f64(&data, -nan), 0);
+    assert_eq!(search_f64(&data, 1.0), -5);
+    assert_eq!(search_f64(&data[1..6], nan), -6);
+}
+
+#[test]
+fn test_batches_match_single_searches() {
+    let floats = [0.5, 1.5, 1.5, 4.0, 9.25];
+    let float_keys = [4.0, 0.0, 1.5, 10.0, 2.0, f64::NAN];
+    let expected: Vec<i32> = float_keys.iter().map(|&k| search_f64(&floats, k)).collect();
+    assert_eq!(search_batch_f64(&floats, &float_keys), expected);
+
+    let ints: Vec<i32> = (-20..20).map(|x| x * 3).collect();
+    let int_keys: Vec<i32> = (-70..70).collect();
+    let expected: Vec<i32> = int_keys.iter().map(|&k| search_i32(&ints, k)).collect();
+    assert_eq!(search_batch_i32(&ints, &int_keys), expected);
+
+    let bigs = [1, 1 << 40, u64::MAX - 1, u64::MAX];
+    let big_keys = [0, 1, 1 << 40, u64::MAX, u64::MAX - 2];
+    assert_eq!(search_batch_u64(&bigs, &big_keys), [-1, 0, 1, 3, -3]);
+    assert!(search_batch_u64(&bigs, &[]).is_empty());
+}